@@ -1,6 +1,17 @@
 //! Command line argument parsing
 
-use clap::{Parser, ValueEnum};
+use crate::cli::Profile;
+use crate::core::SleepInterval;
+use crate::download::{ExternalDownloader, RateLimitGrace};
+use crate::error::RytError;
+use crate::platform::{FormatFallback, TrendingCategory, DEFAULT_TRAFFIC_BODY_LIMIT};
+use crate::utils::{
+    expand_path, parse_duration, parse_si_rate, parse_si_size, LinkKind, ThumbnailFormat,
+};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::ffi::OsString;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -8,10 +19,32 @@ use std::time::Duration;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// YouTube video or playlist URL
+    #[arg(default_value = "", hide_default_value = true)]
     pub url: String,
 
-    /// Format selector (e.g., 'itag=22', 'best', 'height<=480')
+    /// File containing URLs to process (one per line, '#' starts a comment; '-' reads stdin)
+    #[arg(short = 'a', long, value_name = "FILE")]
+    pub batch_file: Option<PathBuf>,
+
+    /// Apply a preset bundle of options; explicit flags override it (e.g., --write-thumbnail=false)
+    #[arg(long, value_enum, value_name = "PROFILE")]
+    pub profile: Option<Profile>,
+
+    /// Load download options from a TOML or JSON file; options given on the command line override it
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Ids of the options given on the command line, filled by
+    /// [`Args::parse_with_explicit`]
+    #[arg(skip)]
+    explicit: Vec<String>,
+
+    /// Format selector (e.g., 'itag=22', 'itag:299', '299', 'best', 'height<=480',
+    /// 'best[height=720][fps>=60]', 'bestvideo[height<=1080][ext=mp4]+bestaudio[ext=m4a]')
     #[arg(short, long, value_name = "FORMAT")]
     pub format: Option<String>,
 
@@ -19,25 +52,217 @@ pub struct Args {
     #[arg(short, long, value_name = "EXT")]
     pub ext: Option<String>,
 
-    /// Output path (file or directory)
+    /// What to download when the format selector matches nothing
+    #[arg(
+        long,
+        value_enum,
+        default_value = "closest-lower",
+        value_name = "POLICY"
+    )]
+    pub format_fallback: FormatFallback,
+
+    /// Prefer WebM (VP9/Opus) formats over MP4 when both are available
+    #[arg(long)]
+    pub prefer_webm: bool,
+
+    /// Prefer 60 fps formats when selecting the best quality
+    #[arg(long = "prefer-60fps")]
+    pub prefer_60fps: bool,
+
+    /// Skip formats larger than this (e.g., 500m, 1.5G)
+    #[arg(long, value_name = "SIZE", value_parser = size_arg)]
+    pub max_filesize: Option<u64>,
+
+    /// Skip formats smaller than this (e.g., 10m)
+    #[arg(long, value_name = "SIZE", value_parser = size_arg)]
+    pub min_filesize: Option<u64>,
+
+    /// Also skip formats of unknown size when a file size limit is set
+    #[arg(long)]
+    pub reject_unknown_size: bool,
+
+    /// Fail when the selected format is outside the file size limits instead of picking a smaller one
+    #[arg(long)]
+    pub strict_format: bool,
+
+    /// Output path (file, directory or template such as '%(title).50s [%(id)s].%(ext)s')
     #[arg(short, long, value_name = "PATH")]
     pub output: Option<PathBuf>,
 
+    /// Write the video thumbnail next to the downloaded file
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    pub write_thumbnail: Option<bool>,
+
+    /// Write the video description to a .description file next to the downloaded file
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    pub write_description: Option<bool>,
+
+    /// Write the video metadata to a yt-dlp style .info.json file next to the downloaded file
+    #[arg(
+        long = "write-info-json",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    pub write_infojson: Option<bool>,
+
+    /// Also write the playlist metadata to <playlist_id>.info.json when downloading a playlist
+    #[arg(long)]
+    pub write_playlist_metafiles: bool,
+
+    /// Write the video captions to a .<lang>.vtt file next to the downloaded file
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    pub write_subs: Option<bool>,
+
+    /// Write the SHA-256 of the downloaded file to a .sha256 file next to it
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    pub write_checksum: Option<bool>,
+
+    /// Language code of the captions written with --write-subs (default: en)
+    #[arg(long, value_name = "LANG")]
+    pub sub_lang: Option<String>,
+
+    /// Write a shortcut to the video page in this platform's format next to the downloaded file
+    #[arg(long)]
+    pub write_link: bool,
+
+    /// Write a Windows .url shortcut to the video page next to the downloaded file
+    #[arg(long)]
+    pub write_url_link: bool,
+
+    /// Write a macOS .webloc shortcut to the video page next to the downloaded file
+    #[arg(long)]
+    pub write_webloc_link: bool,
+
+    /// Write a freedesktop .desktop shortcut to the video page next to the downloaded file
+    #[arg(long)]
+    pub write_desktop_link: bool,
+
+    /// Fetch the category and comment count with an extra request per video
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    pub fetch_extra_metadata: Option<bool>,
+
+    /// Convert written thumbnails to this format (no ffmpeg needed)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub convert_thumbnails: Option<ThumbnailFormat>,
+
+    /// Directory for partial and intermediate files
+    #[arg(long, value_name = "DIR")]
+    pub temp_dir: Option<PathBuf>,
+
+    /// Remove leftover temp files older than AGE from --temp-dir on startup
+    #[arg(
+        long,
+        value_name = "AGE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "24h",
+        value_parser = duration_arg
+    )]
+    pub cleanup_temp: Option<Duration>,
+
+    /// Keep partial files of failed downloads as .part files for inspection
+    #[arg(long)]
+    pub keep_fragments: bool,
+
+    /// Fail instead of creating missing directories of the output path
+    #[arg(long)]
+    pub no_create_dirs: bool,
+
     /// Disable progress output
     #[arg(long)]
     pub no_progress: bool,
 
-    /// HTTP timeout (e.g., 30s, 1m)
-    #[arg(long, value_name = "DURATION", default_value = "30s")]
-    pub timeout: humantime::Duration,
+    /// Total timeout for API requests (e.g., 30s, 1m); media downloads have none
+    #[arg(
+        long = "api-timeout",
+        alias = "timeout",
+        value_name = "DURATION",
+        default_value = "30s",
+        value_parser = duration_arg
+    )]
+    pub timeout: Duration,
+
+    /// Connect timeout and idle timeout between received data (e.g., 10s)
+    #[arg(long, value_name = "DURATION", value_parser = duration_arg)]
+    pub socket_timeout: Option<Duration>,
 
     /// HTTP retries for transient errors
     #[arg(long, default_value = "3")]
     pub retries: u32,
 
-    /// Download rate limit (e.g., 2MiB/s, 500KiB/s)
-    #[arg(long, value_name = "RATE")]
-    pub rate_limit: Option<String>,
+    /// Retries per media fragment (chunk or segment)
+    #[arg(long, default_value = "10")]
+    pub fragment_retries: u32,
+
+    /// Segments of a live (HLS) stream to download at once
+    #[arg(
+        short = 'N',
+        long,
+        value_name = "N",
+        default_value = "1",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub concurrent_fragments: u32,
+
+    /// Write buffer size for streamed downloads (e.g., 256K, 1M)
+    #[arg(long, value_name = "SIZE", value_parser = buffer_size_arg)]
+    pub buffer_size: Option<usize>,
+
+    /// Download rate limit (e.g., 2MiB/s, 500KiB/s, 5M)
+    #[arg(long, value_name = "RATE", value_parser = rate_arg)]
+    pub rate_limit: Option<u64>,
+
+    /// Only apply the rate limit after this much data or time per download (e.g., 20M, 30s)
+    #[arg(long, value_name = "SIZE|DURATION", value_parser = rate_limit_after_arg)]
+    pub rate_limit_after: Option<RateLimitGrace>,
+
+    /// Share one rate limit across all downloads instead of one per download
+    #[arg(long)]
+    pub global_rate_limit: bool,
+
+    /// Re-resolve the media URL with another client when the download stays below this rate (e.g., 100K)
+    #[arg(long, value_name = "RATE", value_parser = rate_arg)]
+    pub throttled_rate: Option<u64>,
+
+    /// Download media with an external program such as aria2c, curl or wget ('native' for the built-in downloader)
+    #[arg(long, value_name = "NAME")]
+    pub downloader: Option<String>,
+
+    /// Extra arguments for the external downloader, split on whitespace (e.g., "-x16 -s16")
+    #[arg(long, value_name = "ARGS", allow_hyphen_values = true)]
+    pub downloader_args: Option<String>,
 
     /// Treat input as playlist URL or ID
     #[arg(long)]
@@ -47,9 +272,61 @@ pub struct Args {
     #[arg(long, default_value = "0")]
     pub limit: usize,
 
-    /// Parallelism for playlist downloads
-    #[arg(long, default_value = "1")]
-    pub concurrency: usize,
+    /// Parallelism for playlist downloads [default: 1]
+    #[arg(long, value_name = "N")]
+    pub concurrency: Option<usize>,
+
+    /// Process playlist items in reverse order
+    #[arg(long)]
+    pub playlist_reverse: bool,
+
+    /// Playlist items to process, e.g. "1,3,5-8,10-" (negative positions count from the end)
+    #[arg(long, value_name = "SPEC")]
+    pub playlist_items: Option<String>,
+
+    /// Wait a random time between playlist/batch items (seconds or duration, e.g. 5, 10-30, 1m-2m)
+    #[arg(long, value_name = "MIN[-MAX]", value_parser = sleep_interval_arg)]
+    pub sleep_interval: Option<SleepInterval>,
+
+    /// Stop after N successful downloads
+    #[arg(long, value_name = "N")]
+    pub max_downloads: Option<usize>,
+
+    /// Stop the whole run at the first archived or already existing item
+    #[arg(long)]
+    pub break_on_existing: bool,
+
+    /// Stop a playlist or batch run at the first failed video and exit with an error
+    #[arg(long)]
+    pub playlist_abort_on_error: bool,
+
+    /// Only download videos matching FILTER (e.g. "duration < 600 & view_count > 1000")
+    #[arg(long, value_name = "FILTER")]
+    pub match_filter: Option<String>,
+
+    /// Record downloaded video IDs in FILE and skip videos already in it
+    #[arg(long, value_name = "FILE")]
+    pub download_archive: Option<PathBuf>,
+
+    /// Download archive line format
+    #[arg(long, value_enum, default_value = "plain")]
+    pub download_archive_format: ArchiveFormat,
+
+    /// Re-download archived videos recorded below this height
+    #[arg(long, value_name = "HEIGHT")]
+    pub redownload_if_below: Option<u32>,
+
+    /// Save playlist/batch progress to FILE and resume from it on the next run
+    #[arg(long, value_name = "FILE")]
+    pub session_file: Option<PathBuf>,
+
+    /// Retry items that failed in an earlier run of the session
+    #[arg(long)]
+    pub retry_failed: bool,
+
+    /// Record downloaded playlist items with their titles in FILE and skip them on the next run
+    #[arg(long, value_name = "FILE")]
+    pub playlist_archive: Option<PathBuf>,
 
     /// Botguard mode
     #[arg(long, value_enum, default_value = "off")]
@@ -68,8 +345,13 @@ pub struct Args {
     pub botguard_cache_dir: Option<PathBuf>,
 
     /// Default Botguard token TTL if solver doesn't set
-    #[arg(long, value_name = "DURATION", default_value = "30m")]
-    pub botguard_ttl: humantime::Duration,
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "30m",
+        value_parser = duration_arg
+    )]
+    pub botguard_ttl: Duration,
 
     /// Path to JS script implementing bgAttest(input)
     #[arg(long, value_name = "PATH")]
@@ -87,6 +369,54 @@ pub struct Args {
     #[arg(short = 'g', long)]
     pub print_url: bool,
 
+    /// Print video title and exit (no download)
+    #[arg(long)]
+    pub get_title: bool,
+
+    /// Print video ID and exit (no download)
+    #[arg(long)]
+    pub get_id: bool,
+
+    /// Print thumbnail URL and exit (no download)
+    #[arg(long)]
+    pub get_thumbnail: bool,
+
+    /// Print video description and exit (no download)
+    #[arg(long)]
+    pub get_description: bool,
+
+    /// Print video duration in seconds and exit (no download)
+    #[arg(long)]
+    pub get_duration: bool,
+
+    /// Print uploader name and exit (no download)
+    #[arg(long)]
+    pub get_uploader: bool,
+
+    /// Print upload date and exit (no download)
+    #[arg(long)]
+    pub get_upload_date: bool,
+
+    /// Print video metadata as JSON and exit (no download)
+    #[arg(short = 'j', long)]
+    pub dump_json: bool,
+
+    /// Print a line per video from TEMPLATE, e.g. '%(id)s\t%(title)s', and exit (no download unless --no-simulate is given; repeatable; implies --quiet)
+    #[arg(long, value_name = "TEMPLATE")]
+    pub print: Vec<String>,
+
+    /// Print what would be downloaded without downloading anything
+    #[arg(short = 's', long, visible_alias = "dry-run")]
+    pub simulate: bool,
+
+    /// Resolve videos without downloading them
+    #[arg(long)]
+    pub skip_download: bool,
+
+    /// With --print, also download each video and print its lines once it has downloaded
+    #[arg(long, requires = "print", conflicts_with_all = ["simulate", "skip_download"])]
+    pub no_simulate: bool,
+
     /// Override User-Agent header
     #[arg(long, value_name = "USER_AGENT")]
     pub user_agent: Option<String>,
@@ -95,15 +425,137 @@ pub struct Args {
     #[arg(long, value_name = "URL")]
     pub proxy: Option<String>,
 
+    /// Send the YouTube cookies of a browser: brave, chrome, chromium, edge, firefox or safari, optionally with a profile (e.g., "chrome:Profile 1")
+    #[arg(long, value_name = "BROWSER[:PROFILE]")]
+    pub cookies_from_browser: Option<String>,
+
+    /// Country to request content for (two-letter code, e.g. US); geo-blocked videos are retried once from US
+    #[arg(long, value_name = "CODE", value_parser = country_arg)]
+    pub geo_bypass_country: Option<String>,
+
+    /// Language to request content in (e.g. en, de)
+    #[arg(long, value_name = "LANG")]
+    pub language: Option<String>,
+
+    /// Connect over IPv4 only
+    #[arg(long, conflicts_with = "force_ipv6")]
+    pub force_ipv4: bool,
+
+    /// Connect over IPv6 only
+    #[arg(long)]
+    pub force_ipv6: bool,
+
+    /// Connect to media servers over IPv4 only (API requests are unaffected)
+    #[arg(long)]
+    pub force_ipv4_for_media: bool,
+
+    /// Connect to HOST at ADDR instead of resolving it, like curl (repeatable; PORT must be 443; ADDR may list several addresses separated by commas)
+    #[arg(long, value_name = "HOST:PORT:ADDR", value_parser = parse_resolve)]
+    pub resolve: Vec<DnsOverride>,
+
+    /// Log every HTTP request and response, with cookies and keys redacted
+    #[arg(long)]
+    pub print_traffic: bool,
+
+    /// Maximum number of request body bytes shown by --print-traffic
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_TRAFFIC_BODY_LIMIT)]
+    pub traffic_body_limit: usize,
+
+    /// Serve Prometheus metrics on this address (requires the `metrics` feature)
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_listen: Option<std::net::SocketAddr>,
+
+    /// Print cipher cache hit/miss statistics at the end of the run
+    #[arg(long)]
+    pub print_cache_stats: bool,
+
+    /// Log full media URLs, including signatures and IP addresses, instead of redacting them
+    #[arg(long)]
+    pub no_redact_urls: bool,
+
     /// Verbose output
     #[arg(short, long)]
     pub verbose: bool,
 
-    /// Quiet output (only errors)
+    /// Quiet output: only errors, on stderr (--dump-json and --print output is kept)
     #[arg(short, long)]
     pub quiet: bool,
 }
 
+/// Subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// List trending videos
+    Trending {
+        /// Region code (e.g., 'US', 'DE')
+        #[arg(long, default_value = "US")]
+        region: String,
+
+        /// Trending category
+        #[arg(long, value_enum, default_value = "now")]
+        category: TrendingCategory,
+
+        /// Maximum number of videos to list (0 = all)
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// List the videos queued to play after a video, one URL per line
+    WatchNext {
+        /// YouTube video URL
+        url: String,
+
+        /// Maximum number of videos to list (0 = all)
+        #[arg(long, default_value = "5")]
+        count: usize,
+    },
+
+    /// List the playlists of a channel, one URL per line
+    ChannelPlaylists {
+        /// YouTube channel URL (e.g., https://www.youtube.com/channel/UC...)
+        url: String,
+
+        /// Maximum number of playlists to list (0 = all)
+        #[arg(long, default_value = "0")]
+        limit: usize,
+    },
+
+    /// Update ryt to the latest GitHub release
+    Update {
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+/// Video metadata field printed by the --get-* flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoField {
+    /// Video title
+    Title,
+    /// Video ID
+    Id,
+    /// Thumbnail URL
+    Thumbnail,
+    /// Full description
+    Description,
+    /// Duration in seconds
+    Duration,
+    /// Uploader name
+    Uploader,
+    /// Upload date
+    UploadDate,
+}
+
+/// Download archive line format
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+pub enum ArchiveFormat {
+    /// `youtube <id>`
+    Plain,
+    /// `youtube <id> <timestamp> <itag> <height>`
+    Extended,
+}
+
 /// Botguard mode
 #[derive(Debug, Clone, ValueEnum, PartialEq)]
 pub enum BotguardMode {
@@ -125,31 +577,188 @@ pub enum BotguardCacheMode {
 }
 
 impl Args {
-    /// Get HTTP timeout as Duration
+    /// Parse the process arguments, recording which options were given on
+    /// the command line; exits on errors like [`Parser::parse`]
+    pub fn parse_with_explicit() -> Self {
+        Self::try_parse_with_explicit_from(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Parse arguments, recording which options were given on the command
+    /// line rather than left at their defaults
+    pub fn try_parse_with_explicit_from<I, T>(itr: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = Self::command().try_get_matches_from(itr)?;
+        let mut args = Self::from_arg_matches(&matches)?;
+        args.explicit = matches
+            .ids()
+            .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+            .map(|id| id.to_string())
+            .collect();
+        Ok(args)
+    }
+
+    /// Check if an option, named by its field, was given on the command
+    /// line. Always false for arguments not parsed with
+    /// [`Args::try_parse_with_explicit_from`].
+    pub fn is_explicit(&self, id: &str) -> bool {
+        self.explicit.iter().any(|explicit| explicit == id)
+    }
+
+    /// Check if an option with a default should be applied over the config
+    /// file: always without `--config`, otherwise only when given explicitly
+    pub fn overrides_config(&self, id: &str) -> bool {
+        self.config.is_none() || self.is_explicit(id)
+    }
+
+    /// Get API request timeout as Duration
     pub fn timeout_duration(&self) -> Duration {
-        self.timeout.into()
+        self.timeout
+    }
+
+    /// Get socket timeout as Duration
+    pub fn socket_timeout_duration(&self) -> Option<Duration> {
+        self.socket_timeout
     }
 
     /// Get Botguard TTL as Duration
     pub fn botguard_ttl_duration(&self) -> Duration {
-        self.botguard_ttl.into()
+        self.botguard_ttl
+    }
+
+    /// Get max age of temp files to clean up on startup
+    pub fn cleanup_temp_age(&self) -> Option<Duration> {
+        self.cleanup_temp
     }
 
-    /// Parse rate limit string to bytes per second
+    /// Get rate limit in bytes per second
     pub fn parse_rate_limit(&self) -> Option<u64> {
         self.rate_limit
-            .as_ref()
-            .and_then(|rate| parse_rate_limit(rate))
+    }
+
+    /// Get the kinds of shortcut files to write
+    pub fn link_kinds(&self) -> Vec<LinkKind> {
+        let mut kinds = Vec::new();
+        if self.write_link {
+            kinds.push(LinkKind::platform_default());
+        }
+        for (enabled, kind) in [
+            (self.write_url_link, LinkKind::Url),
+            (self.write_webloc_link, LinkKind::Webloc),
+            (self.write_desktop_link, LinkKind::Desktop),
+        ] {
+            if enabled && !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        kinds
+    }
+
+    /// Get maximum file size in bytes
+    pub fn parse_max_filesize(&self) -> Option<u64> {
+        self.max_filesize
+    }
+
+    /// Get minimum file size in bytes
+    pub fn parse_min_filesize(&self) -> Option<u64> {
+        self.min_filesize
+    }
+
+    /// Get write buffer size in bytes
+    pub fn parse_buffer_size(&self) -> Option<usize> {
+        self.buffer_size
+    }
+
+    /// Get geo bypass country as an uppercase two-letter code
+    pub fn parse_geo_bypass_country(&self) -> Option<String> {
+        self.geo_bypass_country.clone()
+    }
+
+    /// Get sleep interval between playlist/batch items
+    pub fn parse_sleep_interval(&self) -> Option<SleepInterval> {
+        self.sleep_interval
+    }
+
+    /// Get rate limit grace threshold
+    pub fn parse_rate_limit_after(&self) -> Option<RateLimitGrace> {
+        self.rate_limit_after
+    }
+
+    /// Get throttle detection threshold in bytes per second
+    pub fn parse_throttled_rate(&self) -> Option<u64> {
+        self.throttled_rate
+    }
+
+    /// Get the external downloader to use, if any
+    pub fn external_downloader(&self) -> Option<ExternalDownloader> {
+        let program = self
+            .downloader
+            .as_deref()
+            .filter(|name| *name != "native")?;
+        let args = self.downloader_args.as_deref().unwrap_or_default();
+        Some(ExternalDownloader::new(program).with_args(args.split_whitespace()))
     }
 
     /// Check if this is a playlist operation
     pub fn is_playlist(&self) -> bool {
-        self.playlist || crate::utils::url::is_playlist_url(&self.url)
+        self.is_playlist_target(&self.url)
+    }
+
+    /// Check if the given URL should be treated as a playlist
+    pub fn is_playlist_target(&self, url: &str) -> bool {
+        self.playlist || crate::utils::url::is_playlist_url(url)
+    }
+
+    /// Get URLs to process: the positional URL followed by batch file
+    /// entries, with bare video IDs turned into watch URLs
+    pub fn urls(&self) -> std::io::Result<Vec<String>> {
+        let mut urls = Vec::new();
+        if !self.url.is_empty() {
+            urls.push(self.url.clone());
+        }
+
+        if let Some(batch_file) = &self.batch_file {
+            let content = if batch_file.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin())?
+            } else {
+                std::fs::read_to_string(expand_path(batch_file))?
+            };
+            urls.extend(parse_batch_file(&content));
+        }
+
+        Ok(urls
+            .iter()
+            .map(|url| crate::utils::url::normalize_video_input(url))
+            .collect())
+    }
+
+    /// Get metadata fields requested with the --get-* flags, in output order
+    pub fn info_fields(&self) -> Vec<InfoField> {
+        [
+            (self.get_title, InfoField::Title),
+            (self.get_id, InfoField::Id),
+            (self.get_thumbnail, InfoField::Thumbnail),
+            (self.get_description, InfoField::Description),
+            (self.get_duration, InfoField::Duration),
+            (self.get_uploader, InfoField::Uploader),
+            (self.get_upload_date, InfoField::UploadDate),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, field)| enabled.then_some(field))
+        .collect()
+    }
+
+    /// Get parallelism for playlist downloads
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.unwrap_or(1)
     }
 
     /// Get output verbosity level
     pub fn verbosity_level(&self) -> VerbosityLevel {
-        if self.quiet {
+        // Progress and status messages would mix with the --print lines on stdout
+        if self.quiet || !self.print.is_empty() {
             VerbosityLevel::Quiet
         } else if self.verbose {
             VerbosityLevel::Verbose
@@ -159,69 +768,203 @@ impl Args {
     }
 }
 
+/// Fixed addresses for a host, given with --resolve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsOverride {
+    /// Host name to override
+    pub host: String,
+    /// Addresses to connect to instead
+    pub addrs: Vec<SocketAddr>,
+}
+
 /// Output verbosity level
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(i8)]
 pub enum VerbosityLevel {
     /// Quiet (only errors)
-    Quiet,
+    Quiet = -1,
     /// Normal
-    Normal,
+    Normal = 0,
     /// Verbose (debug info)
-    Verbose,
+    Verbose = 1,
+}
+
+impl VerbosityLevel {
+    /// Get the level as a number; negative values are quiet
+    pub fn value(self) -> i8 {
+        self as i8
+    }
+}
+
+/// Parse batch file content into URLs, skipping blank lines and comments
+pub fn parse_batch_file(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';'))
+        .map(str::to_string)
+        .collect()
 }
 
 /// Parse rate limit string to bytes per second
+///
+/// See [`parse_si_rate`] for the accepted units.
 pub fn parse_rate_limit(rate: &str) -> Option<u64> {
-    let rate = rate.trim().to_uppercase();
-    if rate.is_empty() {
-        return None;
+    parse_si_rate(rate).ok()
+}
+
+/// Parse a rate limit grace threshold: a size (e.g., 20M) or a duration (e.g., 30s)
+///
+/// Sizes take precedence, so a bare `m` suffix means mebibytes, not minutes.
+pub fn parse_rate_limit_after(value: &str) -> Option<RateLimitGrace> {
+    if let Some(bytes) = parse_rate_limit(value) {
+        return Some(RateLimitGrace::Bytes(bytes));
     }
 
-    // Remove /s suffix if present
-    let rate = rate.trim_end_matches("/S");
+    parse_duration(value)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+        .map(RateLimitGrace::Duration)
+}
 
-    // Find the number and unit
-    let mut number_end = 0;
-    for (i, c) in rate.char_indices() {
-        if c.is_ascii_digit() || c == '.' {
-            number_end = i + 1;
-        } else {
-            break;
-        }
+/// Parse a duration flag such as `30s` or `1h30m`
+fn duration_arg(value: &str) -> Result<Duration, String> {
+    parse_duration(value).map_err(arg_error)
+}
+
+/// Parse a size flag such as `500M` or `1.5GB`
+fn size_arg(value: &str) -> Result<u64, String> {
+    parse_si_size(value).map_err(arg_error)
+}
+
+/// Parse a rate flag such as `2MiB/s` or `500K`
+fn rate_arg(value: &str) -> Result<u64, String> {
+    parse_si_rate(value).map_err(arg_error)
+}
+
+/// Parse a write buffer size flag, which must be above zero
+fn buffer_size_arg(value: &str) -> Result<usize, String> {
+    let bytes = parse_si_size(value).map_err(arg_error)?;
+    match usize::try_from(bytes) {
+        Ok(0) => Err(format!("invalid size '{}': must be above zero", value)),
+        Ok(bytes) => Ok(bytes),
+        Err(_) => Err(format!("invalid size '{}': too large", value)),
     }
+}
 
-    if number_end == 0 {
-        return None;
+/// Parse a `--rate-limit-after` flag
+fn rate_limit_after_arg(value: &str) -> Result<RateLimitGrace, String> {
+    parse_rate_limit_after(value).ok_or_else(|| {
+        format!(
+            "invalid threshold '{}': expected a size (e.g., 20M) or a duration (e.g., 30s)",
+            value
+        )
+    })
+}
+
+/// Parse a `--geo-bypass-country` flag into an uppercase two-letter code
+fn country_arg(value: &str) -> Result<String, String> {
+    let code = value.trim();
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "invalid country '{}': expected a two-letter country code (e.g., US)",
+            value
+        ));
     }
+    Ok(code.to_uppercase())
+}
 
-    let number_str = &rate[..number_end];
-    let unit = &rate[number_end..].trim();
+/// Parse a `--sleep-interval` flag
+fn sleep_interval_arg(value: &str) -> Result<SleepInterval, String> {
+    SleepInterval::parse(value).ok_or_else(|| {
+        format!(
+            "invalid interval '{}': expected MIN[-MAX] with MIN at most MAX (e.g., 5, 10-30, 1m-2m)",
+            value
+        )
+    })
+}
 
-    let number: f64 = number_str.parse().ok()?;
-    if number <= 0.0 {
-        return None;
+/// Message for a flag value rejected by a `utils::parse` function
+fn arg_error(e: RytError) -> String {
+    match e {
+        RytError::InvalidInput(message) => message,
+        e => e.to_string(),
     }
+}
 
-    let multiplier = match *unit {
-        "B" | "" => 1,
-        "KB" => 1000,
-        "KIB" => 1024,
-        "MB" => 1000 * 1000,
-        "MIB" => 1024 * 1024,
-        "GB" => 1000 * 1000 * 1000,
-        "GIB" => 1024 * 1024 * 1024,
-        "TB" => 1000_u64.pow(4),
-        "TIB" => 1024_u64.pow(4),
-        _ => return None,
+/// Port of every request ryt makes
+const HTTPS_PORT: u16 = 443;
+
+/// Parse a `HOST:PORT:ADDR[,ADDR...]` DNS override; IPv6 addresses may be bracketed
+///
+/// Overrides apply to every connection to the host whatever its port, so
+/// only 443, the port all requests go to, is accepted.
+pub fn parse_resolve(value: &str) -> Result<DnsOverride, String> {
+    let mut parts = value.splitn(3, ':');
+    let (Some(host), Some(port), Some(addrs)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("expected HOST:PORT:ADDR, got '{}'", value));
     };
-
-    Some((number * multiplier as f64) as u64)
+    if host.is_empty() {
+        return Err(format!("missing host in '{}'", value));
+    }
+    let port: u16 = port
+        .parse()
+        .map_err(|_| format!("invalid port '{}' in '{}'", port, value))?;
+    if port != HTTPS_PORT {
+        return Err(format!(
+            "unsupported port {} in '{}', only {} is used",
+            port, value, HTTPS_PORT
+        ));
+    }
+    let addrs = addrs
+        .split(',')
+        .map(|addr| {
+            addr.trim()
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .map(|ip| SocketAddr::new(ip, port))
+                .map_err(|_| format!("invalid address '{}' in '{}'", addr, value))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(DnsOverride {
+        host: host.to_string(),
+        addrs,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_resolve() {
+        let entry = parse_resolve("example.com:443:127.0.0.1").unwrap();
+        assert_eq!(entry.host, "example.com");
+        assert_eq!(entry.addrs, vec!["127.0.0.1:443".parse().unwrap()]);
+
+        let entry = parse_resolve("example.com:443:[::1],10.0.0.2").unwrap();
+        assert_eq!(
+            entry.addrs,
+            vec![
+                "[::1]:443".parse::<SocketAddr>().unwrap(),
+                "10.0.0.2:443".parse().unwrap()
+            ]
+        );
+
+        for value in [
+            "example.com",
+            "example.com:443",
+            ":443:127.0.0.1",
+            "example.com:https:127.0.0.1",
+            "example.com:80:127.0.0.1",
+            "example.com:8443:127.0.0.1",
+            "example.com:443:localhost",
+        ] {
+            assert!(parse_resolve(value).is_err(), "{}", value);
+        }
+    }
+
     #[test]
     fn test_parse_rate_limit() {
         assert_eq!(parse_rate_limit("1MB/s"), Some(1000 * 1000));
@@ -260,6 +1003,14 @@ mod tests {
             ..Default::default()
         };
         assert_eq!(args.verbosity_level(), VerbosityLevel::Verbose);
+
+        let args = Args {
+            url: "https://example.com".to_string(),
+            print: vec!["%(id)s".to_string()],
+            verbose: true,
+            ..Default::default()
+        };
+        assert_eq!(args.verbosity_level(), VerbosityLevel::Quiet);
     }
 
     #[test]
@@ -282,16 +1033,64 @@ mod tests {
     #[test]
     fn test_args_timeout_duration() {
         let args = Args {
-            timeout: humantime::Duration::from(Duration::from_secs(60)),
+            timeout: Duration::from_secs(60),
             ..Default::default()
         };
         assert_eq!(args.timeout_duration(), Duration::from_secs(60));
     }
 
+    #[test]
+    fn test_args_split_timeouts() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--api-timeout",
+            "1m",
+            "--socket-timeout",
+            "15s",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.timeout_duration(), Duration::from_secs(60));
+        assert_eq!(
+            args.socket_timeout_duration(),
+            Some(Duration::from_secs(15))
+        );
+
+        // --timeout is kept as an alias of --api-timeout
+        let args =
+            Args::try_parse_from(["ryt", "--timeout", "45s", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.timeout_duration(), Duration::from_secs(45));
+        assert_eq!(args.socket_timeout_duration(), None);
+    }
+
+    #[test]
+    fn test_args_duration_values() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--api-timeout",
+            "90",
+            "--botguard-ttl",
+            "1h30m",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.timeout_duration(), Duration::from_secs(90));
+        assert_eq!(args.botguard_ttl_duration(), Duration::from_secs(5400));
+
+        let err = Args::try_parse_from(["ryt", "--api-timeout", "1h30", "https://youtu.be/xxx"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("missing unit after '30'"), "{}", err);
+        assert!(
+            Args::try_parse_from(["ryt", "--socket-timeout", "-5s", "https://youtu.be/xxx"])
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_args_botguard_ttl_duration() {
         let args = Args {
-            botguard_ttl: humantime::Duration::from(Duration::from_secs(3600)),
+            botguard_ttl: Duration::from_secs(3600),
             ..Default::default()
         };
         assert_eq!(args.botguard_ttl_duration(), Duration::from_secs(3600));
@@ -299,10 +1098,8 @@ mod tests {
 
     #[test]
     fn test_args_parse_rate_limit() {
-        let args = Args {
-            rate_limit: Some("1MB/s".to_string()),
-            ..Default::default()
-        };
+        let args =
+            Args::try_parse_from(["ryt", "--rate-limit", "1MB/s", "https://youtu.be/xxx"]).unwrap();
         assert_eq!(args.parse_rate_limit(), Some(1000 * 1000));
 
         let args = Args {
@@ -341,6 +1138,856 @@ mod tests {
         assert_eq!(parse_rate_limit("-1MB"), None);
         assert_eq!(parse_rate_limit("invalid"), None);
         assert_eq!(parse_rate_limit("1XB"), None);
+
+        // yt-dlp style single-letter units are binary
+        assert_eq!(parse_rate_limit("500K"), Some(500 * 1024));
+        assert_eq!(parse_rate_limit("5M"), Some(5 * 1024 * 1024));
+        assert_eq!(parse_rate_limit("1G"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_rate_limit("1T"), Some(1024_u64.pow(4)));
+    }
+
+    #[test]
+    fn test_args_print_cache_stats() {
+        let args =
+            Args::try_parse_from(["ryt", "--print-cache-stats", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.print_cache_stats);
+    }
+
+    #[test]
+    fn test_args_metrics_listen() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--metrics-listen",
+            "127.0.0.1:9184",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.metrics_listen, Some("127.0.0.1:9184".parse().unwrap()));
+
+        assert!(
+            Args::try_parse_from(["ryt", "--metrics-listen", "nope", "https://youtu.be/xxx"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_args_dump_json_flag() {
+        let args = Args::try_parse_from(["ryt", "-j", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.dump_json);
+
+        let args = Args::try_parse_from(["ryt", "--dump-json", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.dump_json);
+    }
+
+    #[test]
+    fn test_args_download_archive() {
+        let args = Args::try_parse_from(["ryt", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.download_archive, None);
+        assert_eq!(args.download_archive_format, ArchiveFormat::Plain);
+        assert_eq!(args.redownload_if_below, None);
+
+        let args = Args::try_parse_from([
+            "ryt",
+            "--download-archive",
+            "archive.txt",
+            "--download-archive-format",
+            "extended",
+            "--redownload-if-below",
+            "720",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.download_archive, Some(PathBuf::from("archive.txt")));
+        assert_eq!(args.download_archive_format, ArchiveFormat::Extended);
+        assert_eq!(args.redownload_if_below, Some(720));
+    }
+
+    #[test]
+    fn test_args_format_fallback() {
+        let args = Args::try_parse_from(["ryt", "-f", "1080p", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.format_fallback, FormatFallback::ClosestLower);
+
+        let args =
+            Args::try_parse_from(["ryt", "--format-fallback", "error", "https://youtu.be/xxx"])
+                .unwrap();
+        assert_eq!(args.format_fallback, FormatFallback::Error);
+        assert!(Args::try_parse_from([
+            "ryt",
+            "--format-fallback",
+            "lower",
+            "https://youtu.be/xxx"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_args_filesize_limits() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--max-filesize",
+            "500m",
+            "--min-filesize",
+            "1.5K",
+            "--reject-unknown-size",
+            "--strict-format",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.parse_max_filesize(), Some(500 * 1024 * 1024));
+        assert_eq!(args.parse_min_filesize(), Some(1536));
+        assert!(args.reject_unknown_size);
+        assert!(args.strict_format);
+
+        let args = Args::try_parse_from(["ryt", "--max-filesize", "500MB", "https://youtu.be/xxx"])
+            .unwrap();
+        assert_eq!(args.parse_max_filesize(), Some(500 * 1000 * 1000));
+
+        let err = Args::try_parse_from(["ryt", "--max-filesize", "huge", "https://youtu.be/xxx"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid size 'huge'"), "{}", err);
+        assert!(
+            Args::try_parse_from(["ryt", "--min-filesize", "10x", "https://youtu.be/xxx"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_args_prefer_60fps() {
+        let args = Args::try_parse_from(["ryt", "--prefer-60fps", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.prefer_60fps);
+    }
+
+    #[test]
+    fn test_args_prefer_webm() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--prefer-webm",
+            "-f",
+            "bestvideo[ext=webm]+bestaudio[ext=webm]",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert!(args.prefer_webm);
+        assert_eq!(
+            args.format.as_deref(),
+            Some("bestvideo[ext=webm]+bestaudio[ext=webm]")
+        );
+    }
+
+    #[test]
+    fn test_args_thumbnails() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--write-thumbnail",
+            "--convert-thumbnails",
+            "jpg",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.write_thumbnail, Some(true));
+        assert_eq!(args.convert_thumbnails, Some(ThumbnailFormat::Jpg));
+
+        let args = Args::try_parse_from([
+            "ryt",
+            "--convert-thumbnails",
+            "jpeg",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.convert_thumbnails, Some(ThumbnailFormat::Jpg));
+
+        let result =
+            Args::try_parse_from(["ryt", "--convert-thumbnails", "gif", "https://youtu.be/xxx"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_args_link_kinds() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--write-url-link",
+            "--write-desktop-link",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.link_kinds(), vec![LinkKind::Url, LinkKind::Desktop]);
+
+        let args = Args::try_parse_from([
+            "ryt",
+            "--write-link",
+            "--write-url-link",
+            "--write-webloc-link",
+            "--write-desktop-link",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        let kinds = args.link_kinds();
+        assert_eq!(kinds[0], LinkKind::platform_default());
+        assert_eq!(kinds.len(), 3);
+    }
+
+    #[test]
+    fn test_args_write_info_json() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--write-info-json",
+            "--write-playlist-metafiles",
+            "https://www.youtube.com/playlist?list=PLxxx",
+        ])
+        .unwrap();
+        assert_eq!(args.write_infojson, Some(true));
+        assert!(args.write_playlist_metafiles);
+    }
+
+    #[test]
+    fn test_args_write_subs() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--write-subs",
+            "--sub-lang",
+            "de",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.write_subs, Some(true));
+        assert_eq!(args.sub_lang.as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn test_args_write_description() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--write-description",
+            "-o",
+            "%(title).50s [%(id)s].%(ext)s",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.write_description, Some(true));
+        assert_eq!(
+            args.output,
+            Some(PathBuf::from("%(title).50s [%(id)s].%(ext)s"))
+        );
+    }
+
+    #[test]
+    fn test_args_force_ip_family() {
+        let args = Args::try_parse_from(["ryt", "--force-ipv4", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.force_ipv4);
+        assert!(!args.force_ipv6);
+
+        let args = Args::try_parse_from(["ryt", "--force-ipv6", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.force_ipv6);
+
+        let result = Args::try_parse_from([
+            "ryt",
+            "--force-ipv4",
+            "--force-ipv6",
+            "https://youtu.be/xxx",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_args_force_ipv4_for_media() {
+        let args = Args::try_parse_from(["ryt", "--force-ipv4-for-media", "https://youtu.be/xxx"])
+            .unwrap();
+        assert!(args.force_ipv4_for_media);
+    }
+
+    #[test]
+    fn test_args_resolve() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--resolve",
+            "rr1.googlevideo.com:443:127.0.0.1",
+            "--resolve",
+            "www.youtube.com:443:[::1]",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.resolve.len(), 2);
+        assert_eq!(args.resolve[0].host, "rr1.googlevideo.com");
+        assert_eq!(args.resolve[1].addrs, vec!["[::1]:443".parse().unwrap()]);
+
+        let result = Args::try_parse_from(["ryt", "--resolve", "www.youtube.com", "x"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_args_print_traffic() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--print-traffic",
+            "--traffic-body-limit",
+            "256",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert!(args.print_traffic);
+        assert_eq!(args.traffic_body_limit, 256);
+
+        let result = Args::try_parse_from(["ryt", "--traffic-body-limit", "-1", "x"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_args_session_file() {
+        let args = Args::try_parse_from(["ryt", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.session_file, None);
+        assert!(!args.retry_failed);
+        assert_eq!(args.playlist_archive, None);
+
+        let args = Args::try_parse_from([
+            "ryt",
+            "--session-file",
+            "sync.json",
+            "--retry-failed",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.session_file, Some(PathBuf::from("sync.json")));
+        assert!(args.retry_failed);
+
+        let args = Args::try_parse_from([
+            "ryt",
+            "--playlist-archive",
+            "progress.txt",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.playlist_archive, Some(PathBuf::from("progress.txt")));
+    }
+
+    #[test]
+    fn test_args_playlist_controls() {
+        let args = Args::try_parse_from(["ryt", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.parse_sleep_interval(), None);
+        assert_eq!(args.max_downloads, None);
+        assert!(!args.break_on_existing);
+        assert!(!args.playlist_abort_on_error);
+        assert!(!args.playlist_reverse);
+        assert_eq!(args.playlist_items, None);
+
+        let args = Args::try_parse_from([
+            "ryt",
+            "--sleep-interval",
+            "5-30",
+            "--max-downloads",
+            "20",
+            "--break-on-existing",
+            "--playlist-abort-on-error",
+            "--playlist-reverse",
+            "--playlist-items",
+            "1,3,5-8,-1",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.parse_sleep_interval(),
+            Some(SleepInterval::new(
+                Duration::from_secs(5),
+                Duration::from_secs(30)
+            ))
+        );
+        assert_eq!(args.max_downloads, Some(20));
+        assert!(args.break_on_existing);
+        assert!(args.playlist_abort_on_error);
+        assert!(args.playlist_reverse);
+        assert_eq!(args.playlist_items.as_deref(), Some("1,3,5-8,-1"));
+
+        for invalid in ["soon", "30-5", ""] {
+            let result =
+                Args::try_parse_from(["ryt", "--sleep-interval", invalid, "https://youtu.be/xxx"]);
+            assert!(result.is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_args_locale() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--geo-bypass-country",
+            "de",
+            "--language",
+            "de",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.parse_geo_bypass_country(), Some("DE".to_string()));
+        assert_eq!(args.language, Some("de".to_string()));
+
+        for invalid in ["USA", "1A", ""] {
+            let result = Args::try_parse_from([
+                "ryt",
+                "--geo-bypass-country",
+                invalid,
+                "https://youtu.be/xxx",
+            ]);
+            assert!(result.is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_args_match_filter() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--match-filter",
+            "duration < 600 & title ~= '(?i)live'",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.match_filter.as_deref(),
+            Some("duration < 600 & title ~= '(?i)live'")
+        );
+    }
+
+    #[test]
+    fn test_args_buffer_size() {
+        let args =
+            Args::try_parse_from(["ryt", "--buffer-size", "1M", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.parse_buffer_size(), Some(1024 * 1024));
+
+        let args = Args::try_parse_from(["ryt", "--buffer-size", "64KiB", "https://youtu.be/xxx"])
+            .unwrap();
+        assert_eq!(args.parse_buffer_size(), Some(64 * 1024));
+
+        for invalid in ["lots", "0"] {
+            assert!(
+                Args::try_parse_from(["ryt", "--buffer-size", invalid, "https://youtu.be/xxx"])
+                    .is_err(),
+                "{}",
+                invalid
+            );
+        }
+        assert_eq!(Args::default().parse_buffer_size(), None);
+    }
+
+    #[test]
+    fn test_args_trending_command() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "trending",
+            "--region",
+            "DE",
+            "--category",
+            "gaming",
+            "--limit",
+            "5",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::Trending {
+                region: "DE".to_string(),
+                category: TrendingCategory::Gaming,
+                limit: 5,
+            })
+        );
+        assert_eq!(args.url, "");
+
+        let args = Args::try_parse_from(["ryt", "-j", "trending"]).unwrap();
+        assert!(args.dump_json);
+        assert_eq!(
+            args.command,
+            Some(Command::Trending {
+                region: "US".to_string(),
+                category: TrendingCategory::Now,
+                limit: 20,
+            })
+        );
+
+        assert!(Args::try_parse_from(["ryt", "trending", "--category", "news"]).is_err());
+
+        let args = Args::try_parse_from(["ryt", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.command, None);
+        assert_eq!(args.url, "https://youtu.be/xxx");
+    }
+
+    #[test]
+    fn test_args_channel_playlists_command() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "channel-playlists",
+            "https://www.youtube.com/channel/UCxxx",
+            "--limit",
+            "10",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::ChannelPlaylists {
+                url: "https://www.youtube.com/channel/UCxxx".to_string(),
+                limit: 10,
+            })
+        );
+
+        let args = Args::try_parse_from(["ryt", "channel-playlists", "UCxxx"]).unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::ChannelPlaylists {
+                url: "UCxxx".to_string(),
+                limit: 0,
+            })
+        );
+        assert!(Args::try_parse_from(["ryt", "channel-playlists"]).is_err());
+    }
+
+    #[test]
+    fn test_args_watch_next_command() {
+        let args =
+            Args::try_parse_from(["ryt", "watch-next", "https://youtu.be/xxx", "--count", "3"])
+                .unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::WatchNext {
+                url: "https://youtu.be/xxx".to_string(),
+                count: 3,
+            })
+        );
+
+        let args = Args::try_parse_from(["ryt", "watch-next", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(
+            args.command,
+            Some(Command::WatchNext {
+                url: "https://youtu.be/xxx".to_string(),
+                count: 5,
+            })
+        );
+
+        assert!(Args::try_parse_from(["ryt", "watch-next"]).is_err());
+    }
+
+    #[test]
+    fn test_args_update_command() {
+        let args = Args::try_parse_from(["ryt", "update", "--check"]).unwrap();
+        assert_eq!(args.command, Some(Command::Update { check: true }));
+
+        let args = Args::try_parse_from(["ryt", "update"]).unwrap();
+        assert_eq!(args.command, Some(Command::Update { check: false }));
+    }
+
+    #[test]
+    fn test_args_batch_file_stdin() {
+        let args = Args::try_parse_from(["ryt", "--batch-file", "-"]).unwrap();
+        assert_eq!(args.batch_file, Some(PathBuf::from("-")));
+    }
+
+    #[test]
+    fn test_args_print_templates() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--print",
+            r"%(id)s\t%(title)s",
+            "--print",
+            "%(webpage_url)s",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.print, [r"%(id)s\t%(title)s", "%(webpage_url)s"]);
+        assert!(!args.skip_download);
+        assert!(!args.no_simulate);
+
+        let args = Args::try_parse_from([
+            "ryt",
+            "--print",
+            "%(id)s",
+            "--skip-download",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert!(args.skip_download);
+
+        // Downloading after printing is opt-in and needs --print
+        let args = Args::try_parse_from([
+            "ryt",
+            "--print",
+            "%(id)s",
+            "--no-simulate",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert!(args.no_simulate);
+        for extra in [None, Some("--simulate"), Some("--skip-download")] {
+            let mut argv = vec!["ryt", "--no-simulate"];
+            if let Some(extra) = extra {
+                argv.extend(["--print", "%(id)s", extra]);
+            }
+            argv.push("https://youtu.be/xxx");
+            assert!(Args::try_parse_from(argv).is_err(), "{:?}", extra);
+        }
+    }
+
+    #[test]
+    fn test_args_simulate_flag() {
+        let args = Args::try_parse_from(["ryt", "--simulate", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.simulate);
+
+        let args = Args::try_parse_from(["ryt", "--dry-run", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.simulate);
+
+        let args = Args::try_parse_from(["ryt", "-s", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.simulate);
+    }
+
+    #[test]
+    fn test_parse_rate_limit_after() {
+        assert_eq!(
+            parse_rate_limit_after("20M"),
+            Some(RateLimitGrace::Bytes(20 * 1024 * 1024))
+        );
+        assert_eq!(
+            parse_rate_limit_after("500KB"),
+            Some(RateLimitGrace::Bytes(500 * 1000))
+        );
+        assert_eq!(
+            parse_rate_limit_after("30s"),
+            Some(RateLimitGrace::Duration(Duration::from_secs(30)))
+        );
+        assert_eq!(
+            parse_rate_limit_after("2min"),
+            Some(RateLimitGrace::Duration(Duration::from_secs(120)))
+        );
+        assert_eq!(parse_rate_limit_after("0s"), None);
+        assert_eq!(parse_rate_limit_after("invalid"), None);
+        assert_eq!(parse_rate_limit_after(""), None);
+    }
+
+    #[test]
+    fn test_args_rate_limit_after() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--rate-limit",
+            "5M",
+            "--rate-limit-after",
+            "20M",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.parse_rate_limit(), Some(5 * 1024 * 1024));
+        assert_eq!(
+            args.parse_rate_limit_after(),
+            Some(RateLimitGrace::Bytes(20 * 1024 * 1024))
+        );
+        assert!(!args.global_rate_limit);
+
+        let args = Args::try_parse_from([
+            "ryt",
+            "--rate-limit",
+            "5M",
+            "--rate-limit-after",
+            "30s",
+            "--global-rate-limit",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.parse_rate_limit_after(),
+            Some(RateLimitGrace::Duration(Duration::from_secs(30)))
+        );
+        assert!(args.global_rate_limit);
+
+        let err = Args::try_parse_from([
+            "ryt",
+            "--rate-limit",
+            "5M",
+            "--rate-limit-after",
+            "soon",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("invalid threshold 'soon'"), "{}", err);
+    }
+
+    #[test]
+    fn test_args_throttled_rate() {
+        let args =
+            Args::try_parse_from(["ryt", "--throttled-rate", "100K", "https://youtu.be/xxx"])
+                .unwrap();
+        assert_eq!(args.parse_throttled_rate(), Some(100 * 1024));
+
+        let err = Args::try_parse_from(["ryt", "--throttled-rate", "slow", "https://youtu.be/xxx"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid rate 'slow'"), "{}", err);
+        assert!(
+            Args::try_parse_from(["ryt", "--rate-limit", "0", "https://youtu.be/xxx"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_args_external_downloader() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--downloader",
+            "aria2c",
+            "--downloader-args",
+            "-x16 -s16",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.external_downloader(),
+            Some(ExternalDownloader::new("aria2c").with_args(["-x16", "-s16"]))
+        );
+
+        let args = Args::try_parse_from(["ryt", "--downloader", "native", "https://youtu.be/xxx"])
+            .unwrap();
+        assert_eq!(args.external_downloader(), None);
+    }
+
+    #[test]
+    fn test_args_cleanup_temp() {
+        let args = Args::try_parse_from(["ryt", "--cleanup-temp", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(
+            args.cleanup_temp_age(),
+            Some(Duration::from_secs(24 * 3600))
+        );
+
+        let args =
+            Args::try_parse_from(["ryt", "--cleanup-temp=2h", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.cleanup_temp_age(), Some(Duration::from_secs(2 * 3600)));
+
+        let args = Args::try_parse_from(["ryt", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.cleanup_temp_age(), None);
+    }
+
+    #[test]
+    fn test_args_fetch_extra_metadata() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--fetch-extra-metadata",
+            "-j",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.fetch_extra_metadata, Some(true));
+    }
+
+    #[test]
+    fn test_args_keep_fragments() {
+        let args =
+            Args::try_parse_from(["ryt", "--keep-fragments", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.keep_fragments);
+    }
+
+    #[test]
+    fn test_args_cookies_from_browser() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--cookies-from-browser",
+            "chrome:Profile 1",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.cookies_from_browser.as_deref(),
+            Some("chrome:Profile 1")
+        );
+    }
+
+    #[test]
+    fn test_args_no_create_dirs() {
+        let args =
+            Args::try_parse_from(["ryt", "--no-create-dirs", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.no_create_dirs);
+    }
+
+    #[test]
+    fn test_args_info_fields() {
+        let args = Args::try_parse_from([
+            "ryt",
+            "--get-duration",
+            "--get-title",
+            "--get-id",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        // Output order is fixed regardless of flag order
+        assert_eq!(
+            args.info_fields(),
+            vec![InfoField::Title, InfoField::Id, InfoField::Duration]
+        );
+
+        let args = Args {
+            get_thumbnail: true,
+            get_description: true,
+            get_uploader: true,
+            get_upload_date: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            args.info_fields(),
+            vec![
+                InfoField::Thumbnail,
+                InfoField::Description,
+                InfoField::Uploader,
+                InfoField::UploadDate
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_concurrent_fragments() {
+        let args = Args::try_parse_from(["ryt", "-N", "4", "https://youtu.be/xxx"]).unwrap();
+        assert_eq!(args.concurrent_fragments, 4);
+        let args =
+            Args::try_parse_from(["ryt", "--concurrent-fragments", "8", "https://youtu.be/xxx"])
+                .unwrap();
+        assert_eq!(args.concurrent_fragments, 8);
+        assert!(Args::try_parse_from(["ryt", "-N", "0", "https://youtu.be/xxx"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_batch_file() {
+        let content = "https://youtu.be/a\n\n  # comment\n; also comment\n  https://youtu.be/b  \n";
+        assert_eq!(
+            parse_batch_file(content),
+            vec!["https://youtu.be/a", "https://youtu.be/b"]
+        );
+        assert!(parse_batch_file("").is_empty());
+    }
+
+    #[test]
+    fn test_args_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let batch_file = dir.path().join("urls.txt");
+        std::fs::write(&batch_file, "https://youtu.be/b\nhttps://youtu.be/c\n").unwrap();
+
+        let args = Args {
+            url: "https://youtu.be/a".to_string(),
+            batch_file: Some(batch_file),
+            ..Default::default()
+        };
+        assert_eq!(
+            args.urls().unwrap(),
+            vec![
+                "https://youtu.be/a",
+                "https://youtu.be/b",
+                "https://youtu.be/c"
+            ]
+        );
+
+        // Bare video IDs become watch URLs
+        let args = Args {
+            url: "dQw4w9WgXcQ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            args.urls().unwrap(),
+            vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]
+        );
+
+        let args = Args {
+            batch_file: Some(dir.path().join("missing.txt")),
+            ..Default::default()
+        };
+        assert!(args.urls().is_err());
+
+        let args = Args::try_parse_from(["ryt"]).unwrap();
+        assert!(args.urls().unwrap().is_empty());
     }
 
     #[test]
@@ -365,19 +2012,70 @@ mod tests {
         assert_eq!(VerbosityLevel::Verbose, VerbosityLevel::Verbose);
     }
 
+    #[test]
+    fn test_verbosity_level_value() {
+        assert_eq!(VerbosityLevel::Quiet.value(), -1);
+        assert_eq!(VerbosityLevel::Normal.value(), 0);
+        assert_eq!(VerbosityLevel::Verbose.value(), 1);
+        assert!(VerbosityLevel::Quiet < VerbosityLevel::Normal);
+
+        let args = Args::try_parse_from(["ryt", "-q", "https://youtu.be/xxx"]).unwrap();
+        assert!(args.verbosity_level().value() < 0);
+    }
+
     #[test]
     fn test_args_default_values() {
         let args = Args::default();
+        assert_eq!(args.command, None);
         assert_eq!(args.url, "");
         assert_eq!(args.format, None);
         assert_eq!(args.ext, None);
+        assert_eq!(args.format_fallback, FormatFallback::ClosestLower);
+        assert!(!args.prefer_webm);
+        assert!(!args.prefer_60fps);
+        assert_eq!(args.max_filesize, None);
+        assert_eq!(args.min_filesize, None);
+        assert!(!args.reject_unknown_size);
+        assert!(!args.strict_format);
         assert_eq!(args.output, None);
+        assert_eq!(args.write_thumbnail, None);
+        assert_eq!(args.write_description, None);
+        assert_eq!(args.write_infojson, None);
+        assert!(!args.write_playlist_metafiles);
+        assert_eq!(args.write_subs, None);
+        assert_eq!(args.write_checksum, None);
+        assert_eq!(args.sub_lang, None);
+        assert!(args.link_kinds().is_empty());
+        assert_eq!(args.fetch_extra_metadata, None);
+        assert_eq!(args.convert_thumbnails, None);
+        assert_eq!(args.temp_dir, None);
+        assert!(args.cleanup_temp.is_none());
+        assert!(!args.keep_fragments);
+        assert!(!args.no_create_dirs);
         assert!(!args.no_progress);
         assert_eq!(args.retries, 3);
+        assert_eq!(args.fragment_retries, 10);
+        assert_eq!(args.concurrent_fragments, 1);
         assert_eq!(args.rate_limit, None);
+        assert_eq!(args.rate_limit_after, None);
+        assert!(!args.global_rate_limit);
+        assert_eq!(args.throttled_rate, None);
+        assert_eq!(args.downloader, None);
+        assert_eq!(args.downloader_args, None);
+        assert_eq!(args.external_downloader(), None);
         assert!(!args.playlist);
         assert_eq!(args.limit, 0);
-        assert_eq!(args.concurrency, 1);
+        assert_eq!(args.profile, None);
+        assert_eq!(args.config, None);
+        assert_eq!(args.concurrency, None);
+        assert_eq!(args.concurrency(), 1);
+        assert!(!args.playlist_reverse);
+        assert_eq!(args.playlist_items, None);
+        assert_eq!(args.sleep_interval, None);
+        assert_eq!(args.max_downloads, None);
+        assert!(!args.break_on_existing);
+        assert!(!args.playlist_abort_on_error);
+        assert_eq!(args.match_filter, None);
         assert_eq!(args.botguard, BotguardMode::Off);
         assert!(!args.debug_botguard);
         assert_eq!(args.botguard_cache, BotguardCacheMode::Mem);
@@ -386,8 +2084,26 @@ mod tests {
         assert_eq!(args.client_name, None);
         assert_eq!(args.client_version, None);
         assert!(!args.print_url);
+        assert!(args.info_fields().is_empty());
+        assert!(!args.dump_json);
+        assert!(args.print.is_empty());
+        assert!(!args.simulate);
+        assert!(!args.skip_download);
+        assert!(!args.no_simulate);
         assert_eq!(args.user_agent, None);
         assert_eq!(args.proxy, None);
+        assert_eq!(args.cookies_from_browser, None);
+        assert_eq!(args.geo_bypass_country, None);
+        assert_eq!(args.language, None);
+        assert!(!args.force_ipv4);
+        assert!(!args.force_ipv6);
+        assert!(!args.force_ipv4_for_media);
+        assert!(args.resolve.is_empty());
+        assert!(!args.print_traffic);
+        assert_eq!(args.traffic_body_limit, 1024);
+        assert_eq!(args.metrics_listen, None);
+        assert!(!args.print_cache_stats);
+        assert!(!args.no_redact_urls);
         assert!(!args.verbose);
         assert!(!args.quiet);
     }
@@ -401,10 +2117,10 @@ mod tests {
             output: Some(PathBuf::from("/tmp")),
             no_progress: true,
             retries: 5,
-            rate_limit: Some("2MB/s".to_string()),
+            rate_limit: Some(2_000_000),
             playlist: true,
             limit: 10,
-            concurrency: 3,
+            concurrency: Some(3),
             botguard: BotguardMode::Auto,
             debug_botguard: true,
             botguard_cache: BotguardCacheMode::File,
@@ -426,10 +2142,10 @@ mod tests {
         assert_eq!(args.output, Some(PathBuf::from("/tmp")));
         assert!(args.no_progress);
         assert_eq!(args.retries, 5);
-        assert_eq!(args.rate_limit, Some("2MB/s".to_string()));
+        assert_eq!(args.rate_limit, Some(2_000_000));
         assert!(args.playlist);
         assert_eq!(args.limit, 10);
-        assert_eq!(args.concurrency, 3);
+        assert_eq!(args.concurrency(), 3);
         assert_eq!(args.botguard, BotguardMode::Auto);
         assert!(args.debug_botguard);
         assert_eq!(args.botguard_cache, BotguardCacheMode::File);
@@ -443,34 +2159,140 @@ mod tests {
         assert!(args.verbose);
         assert!(!args.quiet);
     }
+
+    #[test]
+    fn test_explicit_options_override_config() {
+        let args = Args::try_parse_with_explicit_from([
+            "ryt",
+            "--config",
+            "ryt.toml",
+            "--retries",
+            "3",
+            "--format-fallback",
+            "best",
+            "https://youtu.be/xxx",
+        ])
+        .unwrap();
+        assert_eq!(args.config, Some(PathBuf::from("ryt.toml")));
+        // Given explicitly, even when equal to the default
+        assert!(args.is_explicit("retries"));
+        assert!(args.overrides_config("retries"));
+        assert!(args.overrides_config("format_fallback"));
+        // Left at their defaults, so the config file wins
+        assert!(!args.is_explicit("fragment_retries"));
+        assert!(!args.overrides_config("fragment_retries"));
+        assert!(!args.overrides_config("timeout"));
+
+        // Without a config file every option applies
+        let args = Args::try_parse_with_explicit_from(["ryt", "https://youtu.be/xxx"]).unwrap();
+        assert!(!args.is_explicit("retries"));
+        assert!(args.overrides_config("retries"));
+
+        assert!(Args::try_parse_with_explicit_from(["ryt", "--retries", "many"]).is_err());
+    }
 }
 
 // Implement Default for Args to make tests work
 impl Default for Args {
     fn default() -> Self {
         Self {
+            command: None,
             url: String::new(),
+            batch_file: None,
+            profile: None,
+            config: None,
+            explicit: Vec::new(),
             format: None,
             ext: None,
+            format_fallback: FormatFallback::ClosestLower,
+            prefer_webm: false,
+            prefer_60fps: false,
+            max_filesize: None,
+            min_filesize: None,
+            reject_unknown_size: false,
+            strict_format: false,
             output: None,
+            write_thumbnail: None,
+            write_description: None,
+            write_infojson: None,
+            write_playlist_metafiles: false,
+            write_subs: None,
+            write_checksum: None,
+            sub_lang: None,
+            write_link: false,
+            write_url_link: false,
+            write_webloc_link: false,
+            write_desktop_link: false,
+            fetch_extra_metadata: None,
+            convert_thumbnails: None,
+            temp_dir: None,
+            cleanup_temp: None,
+            keep_fragments: false,
+            no_create_dirs: false,
             no_progress: false,
-            timeout: humantime::Duration::from(Duration::from_secs(30)),
+            timeout: Duration::from_secs(30),
+            socket_timeout: None,
             retries: 3,
+            fragment_retries: 10,
+            concurrent_fragments: 1,
+            buffer_size: None,
             rate_limit: None,
+            rate_limit_after: None,
+            global_rate_limit: false,
+            throttled_rate: None,
+            downloader: None,
+            downloader_args: None,
             playlist: false,
             limit: 0,
-            concurrency: 1,
+            concurrency: None,
+            playlist_reverse: false,
+            playlist_items: None,
+            sleep_interval: None,
+            max_downloads: None,
+            break_on_existing: false,
+            playlist_abort_on_error: false,
+            match_filter: None,
+            download_archive: None,
+            download_archive_format: ArchiveFormat::Plain,
+            redownload_if_below: None,
+            session_file: None,
+            playlist_archive: None,
+            retry_failed: false,
             botguard: BotguardMode::Off,
             debug_botguard: false,
             botguard_cache: BotguardCacheMode::Mem,
             botguard_cache_dir: None,
-            botguard_ttl: humantime::Duration::from(Duration::from_secs(1800)),
+            botguard_ttl: Duration::from_secs(1800),
             botguard_script: None,
             client_name: None,
             client_version: None,
             print_url: false,
+            get_title: false,
+            get_id: false,
+            get_thumbnail: false,
+            get_description: false,
+            get_duration: false,
+            get_uploader: false,
+            get_upload_date: false,
+            dump_json: false,
+            print: Vec::new(),
+            simulate: false,
+            skip_download: false,
+            no_simulate: false,
             user_agent: None,
             proxy: None,
+            cookies_from_browser: None,
+            geo_bypass_country: None,
+            language: None,
+            force_ipv4: false,
+            force_ipv6: false,
+            force_ipv4_for_media: false,
+            resolve: Vec::new(),
+            print_traffic: false,
+            traffic_body_limit: DEFAULT_TRAFFIC_BODY_LIMIT,
+            metrics_listen: None,
+            print_cache_stats: false,
+            no_redact_urls: false,
             verbose: false,
             quiet: false,
         }
@@ -2,6 +2,10 @@
 
 pub mod args;
 pub mod output;
+pub mod profile;
+pub mod update;
 
 pub use args::*;
 pub use output::*;
+pub use profile::*;
+pub use update::*;
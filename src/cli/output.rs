@@ -1,15 +1,44 @@
 //! Output formatting and progress display
 
-use crate::cli::args::VerbosityLevel;
-use crate::core::progress::Progress;
+use crate::cli::args::{InfoField, VerbosityLevel};
+use crate::core::playlist::PlaylistDownloadResult;
+use crate::core::progress::{format_duration, Progress};
+use crate::core::video_info::{Format, VideoInfo};
+use crate::error::RytError;
+use crate::platform::formats::Itag;
+use crate::utils::cache::CacheStats;
+use crate::utils::filename::{expand_template, template_fields};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::sync::Arc;
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+/// Smoothing factor for the speed used to estimate the ETA
+const ETA_SPEED_ALPHA: f64 = 0.5;
+
+/// Terminal width assumed when it cannot be detected
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/// Narrowest bar drawn on the progress line
+const MIN_BAR_WIDTH: usize = 10;
+
+/// Percentage step between lines when progress cannot be redrawn in place
+const FALLBACK_PERCENT_STEP: u64 = 5;
+
+/// State of the progress line drawn without a progress bar
+#[derive(Debug, Default)]
+struct ProgressLine {
+    /// A line was drawn and not yet finished with a newline
+    active: bool,
+    /// Last percentage printed in line-by-line mode
+    last_percent: Option<u64>,
+}
+
 /// Output formatter for ryt
 pub struct OutputFormatter {
     verbosity: VerbosityLevel,
     progress_bar: Option<ProgressBar>,
+    progress_line: Mutex<ProgressLine>,
 }
 
 impl OutputFormatter {
@@ -18,6 +47,7 @@ impl OutputFormatter {
         Self {
             verbosity,
             progress_bar: None,
+            progress_line: Mutex::new(ProgressLine::default()),
         }
     }
 
@@ -28,7 +58,7 @@ impl OutputFormatter {
         }
 
         let style = ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta}) {msg}")
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} {msg}")
             .unwrap()
             .progress_chars("#>-");
 
@@ -41,21 +71,80 @@ impl OutputFormatter {
     }
 
     /// Update progress bar
+    ///
+    /// Without a bar from [`OutputFormatter::create_progress_bar`], progress
+    /// is drawn on stderr as `[====>    ] 45% @ 4.2 MB/s ETA 32s`, redrawn in
+    /// place and sized to the terminal. When stderr is not a terminal or
+    /// `TERM` is unset, a `Downloading... 45% (4.2 MB / 9.3 MB)` line is
+    /// printed every few percent instead.
     pub fn update_progress(&self, progress: &Progress) {
         if let Some(progress_bar) = &self.progress_bar {
+            if progress.phase.is_postprocess() {
+                update_postprocess_progress(progress_bar, progress);
+                return;
+            }
+
             progress_bar.set_position(progress.downloaded_size);
             progress_bar.set_length(progress.total_size);
 
-            if let Some(speed) = progress.speed {
-                progress_bar.set_message(format!("{}/s", format_bytes(speed as u64)));
+            if progress.speed.is_some() {
+                progress_bar.set_message(progress_message(progress));
             }
+        } else if self.verbosity != VerbosityLevel::Quiet {
+            self.draw_progress_line(progress);
         }
     }
 
+    /// Draw progress on a single line of stderr
+    fn draw_progress_line(&self, progress: &Progress) {
+        let mut line = self.progress_line.lock().unwrap();
+        let done = progress.total_size > 0 && progress.downloaded_size >= progress.total_size;
+        let mut stderr = std::io::stderr().lock();
+
+        if is_interactive() {
+            let width = terminal_width();
+            let _ = write!(
+                stderr,
+                "\r{:<width$}",
+                progress_line(progress, width),
+                width = width.saturating_sub(1)
+            );
+            line.active = true;
+        } else {
+            let percent = progress.percent as u64;
+            let due = match line.last_percent {
+                Some(last) => percent >= last + FALLBACK_PERCENT_STEP || (done && percent > last),
+                None => true,
+            };
+            if due {
+                let _ = writeln!(stderr, "{}", fallback_progress_line(progress));
+                line.last_percent = Some(percent);
+            }
+        }
+
+        if done {
+            if line.active {
+                let _ = writeln!(stderr);
+            }
+            if !progress.phase.is_postprocess() {
+                let _ = writeln!(stderr, "✓ Done");
+            }
+            *line = ProgressLine::default();
+        }
+        let _ = stderr.flush();
+    }
+
     /// Finish progress bar
     pub fn finish_progress(&self, message: &str) {
         if let Some(progress_bar) = &self.progress_bar {
             progress_bar.finish_with_message(message.to_string());
+        } else {
+            // End a progress line left open by an interrupted download
+            let mut line = self.progress_line.lock().unwrap();
+            if line.active {
+                eprintln!();
+            }
+            *line = ProgressLine::default();
         }
     }
 
@@ -93,25 +182,22 @@ impl OutputFormatter {
     }
 
     /// Print video information
-    pub fn print_video_info(&self, title: &str, author: &str, duration: u32, formats: usize) {
+    pub fn print_video_info(&self, video_info: &VideoInfo) {
         if self.verbosity == VerbosityLevel::Quiet {
             return;
         }
 
-        println!("📹 {}", title);
-        println!("👤 {}", author);
-        println!(
-            "⏱️  {}",
-            format_duration(Duration::from_secs(duration as u64))
-        );
-        println!("📊 {} formats available", formats);
+        println!("📹 {}", video_info.title);
+        println!("👤 {}", video_info.author);
+        println!("⏱️  {}", video_info.duration_string());
+        println!("📊 {} formats available", video_info.formats.len());
         println!();
     }
 
     /// Print format information
     pub fn print_format_info(
         &self,
-        itag: u32,
+        itag: Itag,
         quality: &str,
         mime_type: &str,
         bitrate: u32,
@@ -125,7 +211,7 @@ impl OutputFormatter {
             .map(|s| format!(" ({})", format_bytes(s)))
             .unwrap_or_default();
         println!(
-            "  📋 itag={} | {} | {} | {} kbps{}",
+            "  📋 {} | {} | {} | {} kbps{}",
             itag,
             quality,
             mime_type,
@@ -146,14 +232,22 @@ impl OutputFormatter {
         println!();
     }
 
-    /// Print download complete message
-    pub fn print_download_complete(&self, output_path: &str, duration: Duration) {
+    /// Print download complete message, with the downloaded format if known
+    pub fn print_download_complete(
+        &self,
+        output_path: &str,
+        duration: Duration,
+        format: Option<&Format>,
+    ) {
         if self.verbosity == VerbosityLevel::Quiet {
             return;
         }
 
         println!();
         println!("✅ Download completed!");
+        if let Some(format) = format {
+            println!("🎞️  {}", format_summary(format));
+        }
         println!("💾 Saved to: {}", output_path);
         println!("⏱️  Time: {}", format_duration(duration));
     }
@@ -182,6 +276,120 @@ impl OutputFormatter {
         println!("📥 [{}/{}] {}", index + 1, total, title);
     }
 
+    /// Print the outcome of a playlist or batch run
+    ///
+    /// A run where every attempted item failed is reported as an error, so
+    /// it shows in quiet mode too.
+    pub fn print_playlist_summary(&self, result: &PlaylistDownloadResult) {
+        let summary = playlist_summary(result);
+        if result.failed.is_empty() {
+            self.success(&summary);
+        } else if result.all_failed() {
+            self.error(&summary);
+        } else {
+            self.warning(&summary);
+        }
+    }
+
+    /// Print what a simulated download would do
+    pub fn print_dry_run(
+        &self,
+        title: &str,
+        quality: &str,
+        ext: &str,
+        size: Option<u64>,
+        output_path: &str,
+    ) {
+        let _ = self.write_dry_run(
+            &mut std::io::stdout(),
+            title,
+            quality,
+            ext,
+            size,
+            output_path,
+        );
+    }
+
+    /// Print simulated download error
+    pub fn print_dry_run_error(&self, target: &str, error: &str) {
+        let _ = self.write_dry_run_error(&mut std::io::stderr(), target, error);
+    }
+
+    /// Print simulated download summary
+    pub fn print_dry_run_summary(&self, planned: usize, failed: usize, total_size: u64) {
+        let _ = self.write_dry_run_summary(&mut std::io::stdout(), planned, failed, total_size);
+    }
+
+    /// Write the line printed by [`print_dry_run`](Self::print_dry_run) to `out`
+    fn write_dry_run(
+        &self,
+        out: &mut impl Write,
+        title: &str,
+        quality: &str,
+        ext: &str,
+        size: Option<u64>,
+        output_path: &str,
+    ) -> std::io::Result<()> {
+        if self.verbosity == VerbosityLevel::Quiet {
+            return Ok(());
+        }
+
+        let size_str = size
+            .map(format_bytes)
+            .unwrap_or_else(|| "unknown size".to_string());
+        writeln!(
+            out,
+            "[dryrun] Would download: {} ({}, {}, {}) → {}",
+            title, quality, ext, size_str, output_path
+        )
+    }
+
+    /// Write the line printed by [`print_dry_run_error`](Self::print_dry_run_error) to `out`
+    fn write_dry_run_error(
+        &self,
+        out: &mut impl Write,
+        target: &str,
+        error: &str,
+    ) -> std::io::Result<()> {
+        writeln!(out, "[dryrun][error] {}: {}", target, error)
+    }
+
+    /// Write the lines printed by [`print_dry_run_summary`](Self::print_dry_run_summary) to `out`
+    fn write_dry_run_summary(
+        &self,
+        out: &mut impl Write,
+        planned: usize,
+        failed: usize,
+        total_size: u64,
+    ) -> std::io::Result<()> {
+        if self.verbosity == VerbosityLevel::Quiet {
+            return Ok(());
+        }
+
+        writeln!(out)?;
+        writeln!(
+            out,
+            "[dryrun] {} video(s) would be downloaded, ~{} total",
+            planned,
+            format_bytes(total_size)
+        )?;
+        if failed > 0 {
+            writeln!(out, "[dryrun] {} video(s) failed metadata fetch", failed)?;
+        }
+        Ok(())
+    }
+
+    /// Print cache hit/miss statistics to stderr
+    pub fn print_cache_stats(&self, stats: &CacheStats) {
+        eprintln!(
+            "📦 Cache: player.js {} hits / {} misses, signatures {} hits / {} misses",
+            stats.player_js_hits,
+            stats.player_js_misses,
+            stats.signature_hits,
+            stats.signature_misses
+        );
+    }
+
     /// Print help text
     pub fn print_help(&self) {
         println!("RYT - Rust Video Downloader");
@@ -204,6 +412,110 @@ impl OutputFormatter {
     }
 }
 
+/// Format a single metadata field for --get-* output
+///
+/// Missing values are printed as `NA` so each field keeps one line.
+pub fn format_info_field(field: InfoField, video_info: &VideoInfo) -> String {
+    let value = match field {
+        InfoField::Title => Some(video_info.title.clone()),
+        InfoField::Id => Some(video_info.id.clone()),
+        InfoField::Thumbnail => video_info.thumbnail.clone(),
+        InfoField::Description => Some(video_info.description.clone()),
+        InfoField::Duration => Some(video_info.duration.to_string()),
+        InfoField::Uploader => Some(video_info.author.clone()),
+        InfoField::UploadDate => video_info.upload_date.clone(),
+    };
+    value.unwrap_or_else(|| "NA".to_string())
+}
+
+/// Fields `--print` templates know besides [`VideoInfo::TEMPLATE_FIELDS`],
+/// taken from the selected format
+pub const PRINT_FIELDS: &[&str] = &[
+    "ext",
+    "format_id",
+    "format_note",
+    "filesize_approx",
+    "webpage_url",
+];
+
+/// Template printed as one line per video with --print, e.g. `%(id)s\t%(title)s`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintTemplate {
+    template: String,
+}
+
+impl PrintTemplate {
+    /// Parse a template, resolving `\t`, `\n` and `\\` escapes.
+    ///
+    /// Unknown fields are rejected here, so a typo is reported before any
+    /// video is fetched.
+    pub fn parse(template: &str) -> Result<Self, RytError> {
+        let template = unescape(template);
+        if let Some(name) = template_fields(&template)
+            .into_iter()
+            .find(|name| !VideoInfo::TEMPLATE_FIELDS.contains(name) && !PRINT_FIELDS.contains(name))
+        {
+            return Err(RytError::InvalidTemplate(format!(
+                "unknown field '{}' (supported: {}, {})",
+                name,
+                VideoInfo::TEMPLATE_FIELDS.join(", "),
+                PRINT_FIELDS.join(", ")
+            )));
+        }
+        Ok(Self { template })
+    }
+
+    /// Render the template for a video and its selected format
+    pub fn render(&self, video_info: &VideoInfo, format: &Format) -> String {
+        expand_template(&self.template, |name| match name {
+            "ext" => Some(format.extension().to_string()),
+            "format_id" => Some(format.itag.get().to_string()),
+            "format_note" => Some(format.quality_label()).filter(|label| !label.is_empty()),
+            "filesize_approx" => format
+                .approx_filesize(video_info.duration)
+                .map(|size| size.to_string()),
+            "webpage_url" => Some(format!("https://www.youtube.com/watch?v={}", video_info.id)),
+            name => video_info.template_field(name),
+        })
+    }
+}
+
+/// Resolve the `\t`, `\n` and `\\` escapes a shell passes through literally
+fn unescape(template: &str) -> String {
+    let mut unescaped = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('\\') => unescaped.push('\\'),
+            // Other escapes are kept as they are
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Render every --print template for a video, one line each
+pub fn render_print_lines(
+    templates: &[PrintTemplate],
+    video_info: &VideoInfo,
+    format: &Format,
+) -> Vec<String> {
+    templates
+        .iter()
+        .map(|template| template.render(video_info, format))
+        .collect()
+}
+
 /// Create a progress callback for the downloader
 pub fn create_progress_callback(
     formatter: Arc<OutputFormatter>,
@@ -213,6 +525,149 @@ pub fn create_progress_callback(
     }
 }
 
+/// Format speed and ETA for the download bar
+///
+/// The speed is smoothed with the progress's configured factor for a steady
+/// display, while the ETA uses a more reactive average so it follows real
+/// changes in throughput.
+fn progress_message(progress: &Progress) -> String {
+    let speed = format_bytes(progress.smoothed_speed_bps());
+    match progress.ema_eta(ETA_SPEED_ALPHA) {
+        Some(eta) => format!("{}/s, ETA {}", speed, format_duration(eta)),
+        None => format!("{}/s", speed),
+    }
+}
+
+/// Whether progress can be redrawn in place on stderr
+fn is_interactive() -> bool {
+    std::env::var_os("TERM").is_some() && std::io::stderr().is_terminal()
+}
+
+/// Width of the terminal in columns
+fn terminal_width() -> usize {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(width), _)| usize::from(width))
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+/// Render a progress line such as `[====>    ] 45% @ 4.2 MB/s ETA 32s`
+///
+/// The bar takes whatever the terminal width leaves after the percentage,
+/// speed and ETA, but never less than [`MIN_BAR_WIDTH`] columns.
+fn progress_line(progress: &Progress, width: usize) -> String {
+    let percent = progress.percent.clamp(0.0, 100.0);
+    let speed = progress.smoothed_speed_bps();
+    let mut status = format!("{:.0}%", percent);
+    if progress.phase.is_postprocess() {
+        status.push(' ');
+        status.push_str(progress.phase.label());
+    } else if speed > 0 {
+        status.push_str(&format!(" @ {}/s", format_bytes(speed)));
+        if let Some(eta) = progress.ema_eta(ETA_SPEED_ALPHA) {
+            status.push_str(&format!(" ETA {}", format_duration(eta)));
+        }
+    }
+
+    // Brackets, the space before the status and the last column stay free
+    let bar_width = width
+        .saturating_sub(status.chars().count() + 4)
+        .max(MIN_BAR_WIDTH);
+    let filled = ((percent / 100.0) * bar_width as f64) as usize;
+    let bar = if filled >= bar_width {
+        "=".repeat(bar_width)
+    } else if filled == 0 {
+        " ".repeat(bar_width)
+    } else {
+        format!(
+            "{}>{}",
+            "=".repeat(filled - 1),
+            " ".repeat(bar_width - filled)
+        )
+    };
+    format!("[{}] {}", bar, status)
+}
+
+/// Render a plain progress line for logs, e.g. `Downloading... 45% (4.2 MB / 9.3 MB)`
+fn fallback_progress_line(progress: &Progress) -> String {
+    let percent = progress.percent.clamp(0.0, 100.0);
+    if progress.phase.is_postprocess() {
+        return format!("{} {:.0}%", progress.phase.label(), percent);
+    }
+    if progress.total_size == 0 {
+        return format!("Downloading... {}", format_bytes(progress.downloaded_size));
+    }
+    format!(
+        "Downloading... {:.0}% ({} / {})",
+        percent,
+        format_bytes(progress.downloaded_size),
+        format_bytes(progress.total_size)
+    )
+}
+
+/// Switch the bar to a postprocessing display and update it
+///
+/// Shows a percentage bar when the media duration is known and an
+/// indeterminate spinner otherwise.
+fn update_postprocess_progress(progress_bar: &ProgressBar, progress: &Progress) {
+    let label = progress.phase.label();
+    if progress_bar.message() != label {
+        if progress.total_size > 0 {
+            let style = ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {percent}% {msg}",
+                )
+                .unwrap()
+                .progress_chars("#>-");
+            progress_bar.set_style(style);
+        } else {
+            let style = ProgressStyle::default_spinner()
+                .template("{spinner:.green} [{elapsed_precise}] {msg}")
+                .unwrap();
+            progress_bar.set_style(style);
+            progress_bar.enable_steady_tick(Duration::from_millis(100));
+        }
+        progress_bar.set_message(label);
+    }
+
+    progress_bar.set_length(progress.total_size);
+    progress_bar.set_position(progress.downloaded_size);
+}
+
+/// Summarize a downloaded format, e.g. `Downloaded 720p mp4 (avc1+aac, 45.0 MB)`
+fn format_summary(format: &Format) -> String {
+    let codecs: Vec<&str> = [format.video_codec_name(), format.audio_codec_name()]
+        .into_iter()
+        .flatten()
+        .map(short_codec_name)
+        .collect();
+    let details: Vec<String> = [
+        (!codecs.is_empty()).then(|| codecs.join("+")),
+        format.size.map(format_bytes),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let label = format.quality_label();
+    let mut summary = if label.is_empty() {
+        format!("Downloaded {}", format.extension())
+    } else {
+        format!("Downloaded {} {}", label, format.extension())
+    };
+    if !details.is_empty() {
+        summary.push_str(&format!(" ({})", details.join(", ")));
+    }
+    summary
+}
+
+/// Shorten a codec string such as `avc1.64001F` to its family name
+fn short_codec_name(codec: &str) -> &str {
+    match codec.split('.').next().unwrap_or(codec) {
+        "mp4a" => "aac",
+        name => name,
+    }
+}
+
 /// Format bytes as human-readable string
 fn format_bytes(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -235,34 +690,134 @@ fn format_bytes(bytes: u64) -> String {
     }
 }
 
-/// Format duration as human-readable string
-fn format_duration(duration: Duration) -> String {
-    let total_seconds = duration.as_secs();
-
-    if total_seconds < 60 {
-        format!("{}s", total_seconds)
-    } else if total_seconds < 3600 {
-        let minutes = total_seconds / 60;
-        let seconds = total_seconds % 60;
-        if seconds == 0 {
-            format!("{}m", minutes)
-        } else {
-            format!("{}m {}s", minutes, seconds)
-        }
-    } else {
-        let hours = total_seconds / 3600;
-        let minutes = (total_seconds % 3600) / 60;
-        if minutes == 0 {
-            format!("{}h", hours)
-        } else {
-            format!("{}h {}m", hours, minutes)
-        }
+/// Summarise a playlist run, e.g. `Downloaded 18/20 videos. Failed: "Title" (AgeRestricted)`
+fn playlist_summary(result: &PlaylistDownloadResult) -> String {
+    let mut summary = format!(
+        "Downloaded {}/{} videos",
+        result.succeeded.len(),
+        result.attempted()
+    );
+    let mut skipped = Vec::new();
+    if result.archived > 0 {
+        skipped.push(format!("{} already in the archive", result.archived));
+    }
+    if result.duplicates > 0 {
+        skipped.push(format!(
+            "{} duplicate{}",
+            result.duplicates,
+            if result.duplicates == 1 { "" } else { "s" }
+        ));
+    }
+    if result.members_only > 0 {
+        skipped.push(format!(
+            "{} members-only video{}",
+            result.members_only,
+            if result.members_only == 1 { "" } else { "s" }
+        ));
+    }
+    if result.payment_required > 0 {
+        skipped.push(format!("{} requiring payment", result.payment_required));
     }
+    if !skipped.is_empty() {
+        summary.push_str(&format!(" (skipped {})", skipped.join(", ")));
+    }
+    if !result.failed.is_empty() {
+        let failures: Vec<String> = result
+            .failed
+            .iter()
+            .map(|failure| {
+                format!(
+                    "\"{}\" ({})",
+                    failure.item.title,
+                    error_kind(&failure.error)
+                )
+            })
+            .collect();
+        summary.push_str(&format!(". Failed: {}", failures.join(", ")));
+    }
+    summary
+}
+
+/// Get the variant name of an error, e.g. `TimeoutError`
+fn error_kind(error: &RytError) -> String {
+    format!("{:?}", error)
+        .chars()
+        .take_while(|c| c.is_alphanumeric())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::playlist::PlaylistDownloadError;
+    use crate::core::video_info::PlaylistItem;
+
+    fn failed_item(title: &str, error: RytError) -> PlaylistDownloadError {
+        PlaylistDownloadError {
+            item: PlaylistItem::new("id".to_string(), title.to_string(), 1),
+            error,
+        }
+    }
+
+    #[test]
+    fn test_playlist_summary() {
+        let mut result = PlaylistDownloadResult::default();
+        for _ in 0..3 {
+            result
+                .succeeded
+                .push(VideoInfo::new("id".to_string(), "Title".to_string()));
+        }
+        assert_eq!(playlist_summary(&result), "Downloaded 3/3 videos");
+
+        result.duplicates = 1;
+        assert_eq!(
+            playlist_summary(&result),
+            "Downloaded 3/3 videos (skipped 1 duplicate)"
+        );
+        result.archived = 2;
+        result.duplicates = 3;
+        assert_eq!(
+            playlist_summary(&result),
+            "Downloaded 3/3 videos (skipped 2 already in the archive, 3 duplicates)"
+        );
+        result.archived = 0;
+        result.duplicates = 0;
+
+        result.members_only = 3;
+        assert_eq!(
+            playlist_summary(&result),
+            "Downloaded 3/3 videos (skipped 3 members-only videos)"
+        );
+        result.members_only = 1;
+        result.payment_required = 2;
+        assert_eq!(
+            playlist_summary(&result),
+            "Downloaded 3/3 videos (skipped 1 members-only video, 2 requiring payment)"
+        );
+        result.members_only = 0;
+        result.payment_required = 0;
+
+        result
+            .failed
+            .push(failed_item("Video Title", RytError::AgeRestricted));
+        result.failed.push(failed_item(
+            "Video 2",
+            RytError::TimeoutError("read".to_string()),
+        ));
+        assert_eq!(
+            playlist_summary(&result),
+            "Downloaded 3/5 videos. Failed: \"Video Title\" (AgeRestricted), \"Video 2\" (TimeoutError)"
+        );
+    }
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(error_kind(&RytError::VideoUnavailable), "VideoUnavailable");
+        assert_eq!(
+            error_kind(&RytError::InvalidInput("x".to_string())),
+            "InvalidInput"
+        );
+    }
 
     #[test]
     fn test_output_formatter_creation() {
@@ -303,6 +858,159 @@ mod tests {
         formatter.error("test");
     }
 
+    #[test]
+    fn test_format_info_field() {
+        let mut info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Test Video".to_string());
+        info.author = "Test Author".to_string();
+        info.duration = 212;
+        info.description = "Line one\nLine two".to_string();
+
+        assert_eq!(format_info_field(InfoField::Title, &info), "Test Video");
+        assert_eq!(format_info_field(InfoField::Id, &info), "dQw4w9WgXcQ");
+        assert_eq!(format_info_field(InfoField::Duration, &info), "212");
+        assert_eq!(format_info_field(InfoField::Uploader, &info), "Test Author");
+        assert_eq!(
+            format_info_field(InfoField::Description, &info),
+            "Line one\nLine two"
+        );
+        assert_eq!(format_info_field(InfoField::Thumbnail, &info), "NA");
+        assert_eq!(format_info_field(InfoField::UploadDate, &info), "NA");
+
+        info.thumbnail = Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/hq.jpg".to_string());
+        info.upload_date = Some("20091025".to_string());
+        assert_eq!(
+            format_info_field(InfoField::Thumbnail, &info),
+            "https://i.ytimg.com/vi/dQw4w9WgXcQ/hq.jpg"
+        );
+        assert_eq!(format_info_field(InfoField::UploadDate, &info), "20091025");
+    }
+
+    fn print_test_video(id: &str, title: &str) -> (VideoInfo, Format) {
+        let mut info = VideoInfo::new(id.to_string(), title.to_string());
+        info.duration = 10;
+        let mut format = Format::new(
+            22,
+            String::new(),
+            "720p".to_string(),
+            "video/mp4".to_string(),
+        );
+        format.bitrate = 800_000;
+        (info, format)
+    }
+
+    #[test]
+    fn test_print_template_escapes() {
+        let (info, format) = print_test_video("dQw4w9WgXcQ", "Title");
+        let template = PrintTemplate::parse(r"%(id)s\t%(title)s\t%(duration)s").unwrap();
+        assert_eq!(template.render(&info, &format), "dQw4w9WgXcQ\tTitle\t10");
+
+        let template = PrintTemplate::parse(r"%(id)s\n\\t \x 100%%\").unwrap();
+        assert_eq!(
+            template.render(&info, &format),
+            "dQw4w9WgXcQ\n\\t \\x 100%\\"
+        );
+    }
+
+    #[test]
+    fn test_print_template_fields() {
+        let (info, mut format) = print_test_video("dQw4w9WgXcQ", "AC/DC: Live");
+        let template = PrintTemplate::parse(
+            "%(format_id)s %(ext)s %(filesize_approx)s %(webpage_url)s %(title)s %(view_count)s",
+        )
+        .unwrap();
+        // Values are printed as is, unlike in filenames
+        assert_eq!(
+            template.render(&info, &format),
+            "22 mp4 1000000 https://www.youtube.com/watch?v=dQw4w9WgXcQ AC/DC: Live NA"
+        );
+
+        format.size = Some(1234);
+        let template = PrintTemplate::parse("%(filesize_approx)s").unwrap();
+        assert_eq!(template.render(&info, &format), "1234");
+
+        let template = PrintTemplate::parse("%(format_note)s").unwrap();
+        assert_eq!(template.render(&info, &format), "720p");
+        format.quality = String::new();
+        format.height = Some(1080);
+        format.fps = Some(60);
+        assert_eq!(template.render(&info, &format), "1080p60");
+    }
+
+    #[test]
+    fn test_print_template_unknown_field() {
+        let err = PrintTemplate::parse(r"%(id)s\t%(titel)s").unwrap_err();
+        assert!(matches!(err, RytError::InvalidTemplate(_)));
+        assert!(err.to_string().contains("unknown field 'titel'"));
+        // Text that isn't a field is left alone
+        assert!(PrintTemplate::parse("%(id) and %d").is_ok());
+    }
+
+    #[test]
+    fn test_render_print_lines_order() {
+        let templates = [
+            PrintTemplate::parse("%(id)s").unwrap(),
+            PrintTemplate::parse("%(title)s").unwrap(),
+        ];
+        // Playlist items in order, each printing every template in order
+        let lines: Vec<String> = [("a", "First"), ("b", "Second"), ("c", "Third")]
+            .iter()
+            .flat_map(|(id, title)| {
+                let (info, format) = print_test_video(id, title);
+                render_print_lines(&templates, &info, &format)
+            })
+            .collect();
+        assert_eq!(lines, ["a", "First", "b", "Second", "c", "Third"]);
+    }
+
+    #[test]
+    fn test_print_dry_run() {
+        let capture = |formatter: &OutputFormatter| {
+            let mut out = Vec::new();
+            formatter
+                .write_dry_run(&mut out, "Title", "720p", "mp4", Some(1024), "./Title.mp4")
+                .unwrap();
+            formatter
+                .write_dry_run(&mut out, "Title", "720p", "mp4", None, "./Title.mp4")
+                .unwrap();
+            formatter
+                .write_dry_run_summary(&mut out, 2, 1, 2048)
+                .unwrap();
+            formatter.write_dry_run_summary(&mut out, 1, 0, 0).unwrap();
+            let mut err = Vec::new();
+            formatter
+                .write_dry_run_error(&mut err, "https://youtu.be/xxx", "Video unavailable")
+                .unwrap();
+            (
+                String::from_utf8(out).unwrap(),
+                String::from_utf8(err).unwrap(),
+            )
+        };
+
+        let (out, err) = capture(&OutputFormatter::new(VerbosityLevel::Normal));
+        assert_eq!(
+            out,
+            "[dryrun] Would download: Title (720p, mp4, 1.0 KB) → ./Title.mp4\n\
+             [dryrun] Would download: Title (720p, mp4, unknown size) → ./Title.mp4\n\
+             \n\
+             [dryrun] 2 video(s) would be downloaded, ~2.0 KB total\n\
+             [dryrun] 1 video(s) failed metadata fetch\n\
+             \n\
+             [dryrun] 1 video(s) would be downloaded, ~0 B total\n"
+        );
+        assert_eq!(
+            err,
+            "[dryrun][error] https://youtu.be/xxx: Video unavailable\n"
+        );
+
+        // Quiet mode keeps only the errors
+        let (out, err) = capture(&OutputFormatter::new(VerbosityLevel::Quiet));
+        assert!(out.is_empty());
+        assert_eq!(
+            err,
+            "[dryrun][error] https://youtu.be/xxx: Video unavailable\n"
+        );
+    }
+
     #[test]
     fn test_output_formatter_verbosity_levels() {
         // Test Normal verbosity
@@ -333,6 +1041,26 @@ mod tests {
         assert!(formatter.progress_bar.is_some());
     }
 
+    #[test]
+    fn test_update_progress_postprocess_phase() {
+        use crate::core::progress::ProgressPhase;
+
+        let mut formatter = OutputFormatter::new(VerbosityLevel::Normal);
+        let progress_bar = formatter.create_progress_bar(1000).unwrap();
+
+        let mut progress = Progress::with_phase(10_000, ProgressPhase::Merge);
+        progress.update(2_500);
+        formatter.update_progress(&progress);
+        assert_eq!(progress_bar.message(), "Merging...");
+        assert_eq!(progress_bar.position(), 2_500);
+        assert_eq!(progress_bar.length(), Some(10_000));
+
+        // Unknown duration falls back to a spinner
+        let progress = Progress::with_phase(0, ProgressPhase::Remux);
+        formatter.update_progress(&progress);
+        assert_eq!(progress_bar.message(), "Remuxing...");
+    }
+
     #[test]
     fn test_create_progress_bar_verbose_mode() {
         let mut formatter = OutputFormatter::new(VerbosityLevel::Verbose);
@@ -382,36 +1110,43 @@ mod tests {
     #[test]
     fn test_print_video_info_quiet_mode() {
         let formatter = OutputFormatter::new(VerbosityLevel::Quiet);
+        let info = VideoInfo::new("id".to_string(), "Test Video".to_string());
         // Should not panic or print anything
-        formatter.print_video_info("Test Video", "Test Author", 120, 5);
+        formatter.print_video_info(&info);
     }
 
     #[test]
     fn test_print_video_info_normal_mode() {
         let formatter = OutputFormatter::new(VerbosityLevel::Normal);
-        // Should not panic
-        formatter.print_video_info("Test Video", "Test Author", 120, 5);
+        let mut info = VideoInfo::new("id".to_string(), "Test Video".to_string());
+        // Should not panic, with known, unknown and live lengths
+        info.duration = 120;
+        formatter.print_video_info(&info);
+        info.duration = 0;
+        formatter.print_video_info(&info);
+        info.is_live = true;
+        formatter.print_video_info(&info);
     }
 
     #[test]
     fn test_print_format_info_quiet_mode() {
         let formatter = OutputFormatter::new(VerbosityLevel::Quiet);
         // Should not panic or print anything
-        formatter.print_format_info(22, "720p", "video/mp4", 1000000, Some(50000000));
+        formatter.print_format_info(Itag(22), "720p", "video/mp4", 1000000, Some(50000000));
     }
 
     #[test]
     fn test_print_format_info_normal_mode() {
         let formatter = OutputFormatter::new(VerbosityLevel::Normal);
         // Should not panic
-        formatter.print_format_info(22, "720p", "video/mp4", 1000000, Some(50000000));
+        formatter.print_format_info(Itag(22), "720p", "video/mp4", 1000000, Some(50000000));
     }
 
     #[test]
     fn test_print_format_info_without_size() {
         let formatter = OutputFormatter::new(VerbosityLevel::Normal);
         // Should not panic
-        formatter.print_format_info(22, "720p", "video/mp4", 1000000, None);
+        formatter.print_format_info(Itag(22), "720p", "video/mp4", 1000000, None);
     }
 
     #[test]
@@ -432,14 +1167,50 @@ mod tests {
     fn test_print_download_complete_quiet_mode() {
         let formatter = OutputFormatter::new(VerbosityLevel::Quiet);
         // Should not panic or print anything
-        formatter.print_download_complete("/tmp/video.mp4", Duration::from_secs(30));
+        formatter.print_download_complete("/tmp/video.mp4", Duration::from_secs(30), None);
     }
 
     #[test]
     fn test_print_download_complete_normal_mode() {
         let formatter = OutputFormatter::new(VerbosityLevel::Normal);
         // Should not panic
-        formatter.print_download_complete("/tmp/video.mp4", Duration::from_secs(30));
+        formatter.print_download_complete("/tmp/video.mp4", Duration::from_secs(30), None);
+        let format = Format::new(
+            22,
+            String::new(),
+            "720p".to_string(),
+            "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"".to_string(),
+        );
+        formatter.print_download_complete("/tmp/video.mp4", Duration::from_secs(30), Some(&format));
+    }
+
+    #[test]
+    fn test_format_summary() {
+        let mut format = Format::new(
+            22,
+            String::new(),
+            "720p".to_string(),
+            "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"".to_string(),
+        );
+        format.size = Some(45 * 1024 * 1024);
+        assert_eq!(
+            format_summary(&format),
+            "Downloaded 720p mp4 (avc1+aac, 45.0 MB)"
+        );
+
+        let mut format = Format::new(
+            251,
+            String::new(),
+            String::new(),
+            "audio/webm; codecs=\"opus\"".to_string(),
+        );
+        assert_eq!(format_summary(&format), "Downloaded audio only webm (opus)");
+        format.mime_type = "audio/webm".to_string();
+        assert_eq!(format_summary(&format), "Downloaded audio only webm");
+
+        // Formats without a label skip it
+        let format = Format::new(0, String::new(), String::new(), "video/mp4".to_string());
+        assert_eq!(format_summary(&format), "Downloaded mp4");
     }
 
     #[test]
@@ -512,6 +1283,92 @@ mod tests {
         formatter.update_progress(&progress);
     }
 
+    #[test]
+    fn test_progress_message() {
+        let mut progress = Progress::new(10_000);
+        progress.push_speed_sample(1000.0);
+        assert_eq!(progress_message(&progress), "1000 B/s, ETA 10s");
+
+        progress.push_speed_sample(2000.0);
+        // Display speed 0.2-weighted, ETA from the 0.5-weighted 1500 B/s
+        assert_eq!(progress_message(&progress), "1.2 KB/s, ETA 6s");
+
+        let mut progress = Progress::new(0);
+        progress.push_speed_sample(1024.0);
+        assert_eq!(progress_message(&progress), "1.0 KB/s");
+
+        // The displayed speed follows the configured smoothing
+        let mut progress = Progress::new(0).with_speed_smoothing_alpha(1.0);
+        progress.push_speed_sample(1000.0);
+        progress.push_speed_sample(2000.0);
+        assert_eq!(progress_message(&progress), "2.0 KB/s");
+    }
+
+    #[test]
+    fn test_progress_line() {
+        let mut progress = Progress::new(10_000);
+        progress.downloaded_size = 4_500;
+        progress.percent = 45.0;
+        progress.push_speed_sample(1000.0);
+        let line = progress_line(&progress, 60);
+        assert_eq!(
+            line,
+            format!(
+                "[{}>{}] 45% @ 1000 B/s ETA 5s",
+                "=".repeat(14),
+                " ".repeat(20)
+            )
+        );
+        assert_eq!(line.chars().count(), 59);
+
+        // The bar follows the terminal width but keeps a minimum size
+        assert_eq!(progress_line(&progress, 120).chars().count(), 119);
+        let narrow = progress_line(&progress, 20);
+        assert!(narrow.starts_with(&format!("[{}>{}]", "=".repeat(3), " ".repeat(6))));
+
+        // Speed is left out until it is known
+        let mut progress = Progress::new(100);
+        assert_eq!(
+            progress_line(&progress, 20),
+            format!("[{}] 0%", " ".repeat(14))
+        );
+        progress.downloaded_size = 100;
+        progress.percent = 100.0;
+        assert_eq!(
+            progress_line(&progress, 20),
+            format!("[{}] 100%", "=".repeat(12))
+        );
+    }
+
+    #[test]
+    fn test_fallback_progress_line() {
+        let mut progress = Progress::new(9_751_756);
+        progress.update(4_404_019);
+        assert_eq!(
+            fallback_progress_line(&progress),
+            "Downloading... 45% (4.2 MB / 9.3 MB)"
+        );
+
+        let mut progress = Progress::new(0);
+        progress.update(2048);
+        assert_eq!(fallback_progress_line(&progress), "Downloading... 2.0 KB");
+    }
+
+    #[test]
+    fn test_update_progress_without_bar() {
+        let formatter = OutputFormatter::new(VerbosityLevel::Normal);
+        let mut progress = Progress::new(1000);
+        progress.update(500);
+        formatter.update_progress(&progress);
+
+        // Completing the download resets the line for the next one
+        progress.update(1000);
+        formatter.update_progress(&progress);
+        let line = formatter.progress_line.lock().unwrap();
+        assert!(!line.active);
+        assert!(line.last_percent.is_none());
+    }
+
     #[test]
     fn test_update_progress_without_speed() {
         let mut formatter = OutputFormatter::new(VerbosityLevel::Normal);
@@ -0,0 +1,168 @@
+//! Preset option bundles selected with `--profile`
+
+use crate::cli::Args;
+use clap::ValueEnum;
+use std::path::PathBuf;
+
+/// Download archive used by the archive profile when none is given
+pub const PROFILE_ARCHIVE_FILE: &str = "archive.txt";
+
+/// Preset bundle of options for a common use case
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum Profile {
+    /// Best quality with merged audio, thumbnail, description, info JSON, captions, checksum and extra metadata, recorded in archive.txt
+    Archive,
+    /// Best M4A audio with a thumbnail file, named '%(uploader)s - %(title)s'; approximates audio extraction by picking an M4A stream, and embeds neither the thumbnail nor metadata
+    Music,
+    /// Muxed formats up to 720p with AAC audio, no extras
+    Quick,
+}
+
+impl Profile {
+    /// Apply the profile to parsed arguments.
+    ///
+    /// Only options the command line left unset are changed, so explicit
+    /// flags win, including switches turned off with e.g.
+    /// `--write-thumbnail=false`.
+    pub fn apply(self, args: &mut Args) {
+        match self {
+            Profile::Archive => {
+                args.format
+                    .get_or_insert_with(|| "bestvideo+bestaudio".to_string());
+                args.download_archive
+                    .get_or_insert_with(|| PathBuf::from(PROFILE_ARCHIVE_FILE));
+                for switch in [
+                    &mut args.write_thumbnail,
+                    &mut args.write_description,
+                    &mut args.write_infojson,
+                    &mut args.write_subs,
+                    &mut args.write_checksum,
+                    &mut args.fetch_extra_metadata,
+                ] {
+                    switch.get_or_insert(true);
+                }
+            }
+            Profile::Music => {
+                args.format
+                    .get_or_insert_with(|| "bestaudio[ext=m4a]".to_string());
+                args.output
+                    .get_or_insert_with(|| PathBuf::from("%(uploader)s - %(title)s.%(ext)s"));
+                args.write_thumbnail.get_or_insert(true);
+            }
+            Profile::Quick => {
+                args.format
+                    .get_or_insert_with(|| "best[height<=720][acodec=mp4a]".to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn parse(argv: &[&str]) -> Args {
+        let mut args = Args::try_parse_from(argv).unwrap();
+        if let Some(profile) = args.profile {
+            profile.apply(&mut args);
+        }
+        args
+    }
+
+    #[test]
+    fn test_archive_profile() {
+        let args = parse(&["ryt", "--profile", "archive", "https://youtu.be/xxx"]);
+        assert_eq!(args.profile, Some(Profile::Archive));
+        assert_eq!(args.format.as_deref(), Some("bestvideo+bestaudio"));
+        assert_eq!(args.download_archive, Some(PathBuf::from("archive.txt")));
+        assert_eq!(args.write_thumbnail, Some(true));
+        assert_eq!(args.write_description, Some(true));
+        assert_eq!(args.write_infojson, Some(true));
+        assert_eq!(args.write_subs, Some(true));
+        assert_eq!(args.write_checksum, Some(true));
+        assert_eq!(args.fetch_extra_metadata, Some(true));
+    }
+
+    #[test]
+    fn test_music_profile() {
+        let args = parse(&["ryt", "--profile", "music", "https://youtu.be/xxx"]);
+        assert_eq!(args.format.as_deref(), Some("bestaudio[ext=m4a]"));
+        assert_eq!(
+            args.output,
+            Some(PathBuf::from("%(uploader)s - %(title)s.%(ext)s"))
+        );
+        assert_eq!(args.write_thumbnail, Some(true));
+        assert_eq!(args.write_description, None);
+        assert_eq!(args.download_archive, None);
+    }
+
+    #[test]
+    fn test_quick_profile() {
+        let args = parse(&["ryt", "--profile", "quick", "https://youtu.be/xxx"]);
+        assert_eq!(
+            args.format.as_deref(),
+            Some("best[height<=720][acodec=mp4a]")
+        );
+        assert_eq!(args.write_thumbnail, None);
+        assert!(crate::platform::parse_format_selector(args.format.as_deref().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_explicit_flags_win() {
+        let args = parse(&[
+            "ryt",
+            "--profile",
+            "archive",
+            "--format",
+            "best[height<=1080]",
+            "--download-archive",
+            "seen.txt",
+            "https://youtu.be/xxx",
+        ]);
+        assert_eq!(args.format.as_deref(), Some("best[height<=1080]"));
+        assert_eq!(args.download_archive, Some(PathBuf::from("seen.txt")));
+        assert_eq!(args.write_thumbnail, Some(true));
+
+        // Switches the profile turns on can be turned off again
+        let args = parse(&[
+            "ryt",
+            "--profile",
+            "archive",
+            "--write-thumbnail=false",
+            "--write-subs=false",
+            "--fetch-extra-metadata=false",
+            "https://youtu.be/xxx",
+        ]);
+        assert_eq!(args.write_thumbnail, Some(false));
+        assert_eq!(args.write_subs, Some(false));
+        assert_eq!(args.fetch_extra_metadata, Some(false));
+        assert_eq!(args.write_infojson, Some(true));
+        assert_eq!(args.write_checksum, Some(true));
+
+        let args = parse(&[
+            "ryt",
+            "--profile",
+            "music",
+            "--write-thumbnail=false",
+            "https://youtu.be/xxx",
+        ]);
+        assert_eq!(args.write_thumbnail, Some(false));
+
+        let args = parse(&[
+            "ryt",
+            "--profile",
+            "quick",
+            "-f",
+            "best",
+            "-o",
+            "out",
+            "https://youtu.be/xxx",
+        ]);
+        assert_eq!(args.format.as_deref(), Some("best"));
+        assert_eq!(args.output, Some(PathBuf::from("out")));
+
+        let result = Args::try_parse_from(["ryt", "--profile", "everything", "x"]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,473 @@
+//! Self-update from GitHub releases
+//!
+//! `ryt update` looks up the latest release of [`UPDATE_REPOSITORY`], and when
+//! it is newer than the running binary, downloads the asset built for this
+//! platform (e.g. `ryt-linux-x86_64`), checks it against the release's
+//! `checksums.txt` and moves it over the current executable.
+
+use crate::error::RytError;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::io::Write;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Repository whose releases `ryt update` installs
+pub const UPDATE_REPOSITORY: &str = "ytget/ryt";
+
+/// GitHub REST API base URL
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Asset listing the SHA-256 of every binary of a release
+const CHECKSUMS_ASSET: &str = "checksums.txt";
+
+/// Published release
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    /// Git tag, e.g. `v0.3.0`
+    pub tag_name: String,
+    /// Release notes
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Downloadable files
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    /// Get the version without the leading `v` of the tag
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// Get the release notes, if any
+    pub fn changelog(&self) -> Option<&str> {
+        self.body
+            .as_deref()
+            .map(str::trim)
+            .filter(|body| !body.is_empty())
+    }
+
+    /// Find an asset by file name
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// File attached to a release
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    /// File name
+    pub name: String,
+    /// Direct download URL
+    pub browser_download_url: String,
+}
+
+/// Client for the GitHub releases of ryt
+pub struct Updater {
+    client: reqwest::Client,
+    api_url: String,
+    token: Option<String>,
+    current_version: String,
+}
+
+impl Updater {
+    /// Create an updater for the running version
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_url: GITHUB_API_URL.to_string(),
+            token: None,
+            current_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Set GitHub token used to avoid the anonymous rate limit
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token.filter(|token| !token.is_empty());
+        self
+    }
+
+    /// Set version the latest release is compared against
+    pub fn with_current_version(mut self, version: &str) -> Self {
+        self.current_version = version.trim_start_matches('v').to_string();
+        self
+    }
+
+    /// Set API base URL (for testing)
+    #[cfg(test)]
+    pub(crate) fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Get the version the updater compares against
+    pub fn current_version(&self) -> &str {
+        &self.current_version
+    }
+
+    /// Fetch the latest release
+    pub async fn latest_release(&self) -> Result<Release, RytError> {
+        let url = format!(
+            "{}/repos/{}/releases/latest",
+            self.api_url, UPDATE_REPOSITORY
+        );
+        debug!("Fetching latest release from {}", url);
+        let response = self
+            .request(&url)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await?;
+        let response = check_status(response, &url, self.token.is_none())?;
+        Ok(response.json().await?)
+    }
+
+    /// Check whether a release is newer than the running version
+    pub fn is_update(&self, release: &Release) -> bool {
+        compare_versions(release.version(), &self.current_version) == Ordering::Greater
+    }
+
+    /// Download the binary for this platform from `release`, verify it
+    /// against the release checksums and move it to `target`
+    pub async fn install(&self, release: &Release, target: &Path) -> Result<(), RytError> {
+        let asset_name = platform_asset_name().ok_or_else(|| {
+            RytError::UpdateFailed(format!(
+                "no release binary is built for {}-{}",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ))
+        })?;
+        self.install_asset(release, &asset_name, target).await
+    }
+
+    /// Install a named asset of `release` to `target`
+    async fn install_asset(
+        &self,
+        release: &Release,
+        asset_name: &str,
+        target: &Path,
+    ) -> Result<(), RytError> {
+        let asset = release.asset(asset_name).ok_or_else(|| {
+            RytError::UpdateFailed(format!(
+                "release {} has no {} binary",
+                release.tag_name, asset_name
+            ))
+        })?;
+        let checksums = release.asset(CHECKSUMS_ASSET).ok_or_else(|| {
+            RytError::UpdateFailed(format!(
+                "release {} has no {}",
+                release.tag_name, CHECKSUMS_ASSET
+            ))
+        })?;
+
+        let checksums = String::from_utf8_lossy(&self.download(checksums).await?).into_owned();
+        let expected = find_checksum(&checksums, asset_name).ok_or_else(|| {
+            RytError::UpdateFailed(format!(
+                "{} has no entry for {}",
+                CHECKSUMS_ASSET, asset_name
+            ))
+        })?;
+
+        info!("Downloading {}", asset.browser_download_url);
+        let binary = self.download(asset).await?;
+        let actual = sha256_hex(&binary);
+        if !actual.eq_ignore_ascii_case(&expected) {
+            return Err(RytError::UpdateFailed(format!(
+                "checksum mismatch for {}: expected {}, got {}",
+                asset_name, expected, actual
+            )));
+        }
+
+        replace_executable(&binary, target)
+    }
+
+    /// Download a release asset
+    async fn download(&self, asset: &ReleaseAsset) -> Result<Vec<u8>, RytError> {
+        let response = self
+            .request(&asset.browser_download_url)
+            .header("Accept", "application/octet-stream")
+            .send()
+            .await?;
+        let response = check_status(response, &asset.browser_download_url, self.token.is_none())?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Start a GET request with the user agent and token GitHub expects
+    fn request(&self, url: &str) -> reqwest::RequestBuilder {
+        let mut request = self
+            .client
+            .get(url)
+            .header("User-Agent", format!("ryt/{}", self.current_version));
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        request
+    }
+}
+
+impl Default for Updater {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Turn an unsuccessful response into an error, hinting at `GITHUB_TOKEN`
+/// when an anonymous request was rate limited
+fn check_status(
+    response: reqwest::Response,
+    url: &str,
+    anonymous: bool,
+) -> Result<reqwest::Response, RytError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let hint = if anonymous
+        && (status == reqwest::StatusCode::FORBIDDEN
+            || status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+    {
+        " (set GITHUB_TOKEN to raise the GitHub API rate limit)"
+    } else {
+        ""
+    };
+    Err(RytError::UpdateFailed(format!(
+        "GitHub returned {} for {}{}",
+        status, url, hint
+    )))
+}
+
+/// Name of the release binary built for this platform, e.g. `ryt-macos-aarch64`
+pub fn platform_asset_name() -> Option<String> {
+    let os = match std::env::consts::OS {
+        os @ ("linux" | "macos") => os,
+        _ => return None,
+    };
+    let arch = match std::env::consts::ARCH {
+        arch @ ("x86_64" | "aarch64") => arch,
+        _ => return None,
+    };
+    Some(format!("ryt-{}-{}", os, arch))
+}
+
+/// Compare dotted versions numerically, e.g. `0.10.0` > `0.9.1`.
+///
+/// Pre-release and build suffixes (`-rc.1`, `+abc`) are ignored, and
+/// missing components count as zero.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    let (a, b) = (parse(a), parse(b));
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let left = a.get(i).copied().unwrap_or(0);
+            let right = b.get(i).copied().unwrap_or(0);
+            left.cmp(&right)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Find the checksum of `name` in `sha256sum` output (`<hex>  <name>`, or
+/// `<hex> *<name>` for binary mode)
+fn find_checksum(checksums: &str, name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (hash, file) = line.trim().split_once(char::is_whitespace)?;
+        let file = file.trim_start();
+        let file = file.strip_prefix('*').unwrap_or(file);
+        (file == name).then(|| hash.to_string())
+    })
+}
+
+/// Lowercase hex SHA-256 of `data`
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Write the new binary next to `target` and rename it over `target`, so
+/// the executable is never left half-written
+fn replace_executable(binary: &[u8], target: &Path) -> Result<(), RytError> {
+    let dir = target.parent().unwrap_or_else(|| Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(dir)
+        .map_err(|e| RytError::io(e, "create temporary file in", dir))?;
+    file.write_all(binary)
+        .and_then(|_| file.flush())
+        .map_err(|e| RytError::io(e, "write", file.path()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(file.path(), std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| RytError::io(e, "set permissions of", file.path()))?;
+    }
+
+    file.persist(target)
+        .map_err(|e| RytError::io(e.error, "replace", target))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(server_url: &str) -> Release {
+        serde_json::from_value(serde_json::json!({
+            "tag_name": "v9.9.9",
+            "body": "## Changes\n- Faster downloads\n",
+            "assets": [
+                { "name": "ryt-test", "browser_download_url": format!("{}/ryt-test", server_url) },
+                { "name": "checksums.txt", "browser_download_url": format!("{}/checksums.txt", server_url) }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.2.0", "0.1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("0.10.0", "0.9.1"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0-rc.1", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("0.1.0", "0.1.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_find_checksum() {
+        let checksums = "abc123  ryt-linux-x86_64\ndef456 *ryt-macos-aarch64\n";
+        assert_eq!(
+            find_checksum(checksums, "ryt-linux-x86_64").as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(
+            find_checksum(checksums, "ryt-macos-aarch64").as_deref(),
+            Some("def456")
+        );
+        assert_eq!(find_checksum(checksums, "ryt-macos-x86_64"), None);
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_platform_asset_name() {
+        if let Some(name) = platform_asset_name() {
+            assert!(name.starts_with("ryt-"));
+            assert!(name.ends_with(std::env::consts::ARCH));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_latest_release() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/repos/ytget/ryt/releases/latest")
+            .match_header("authorization", "Bearer secret")
+            .match_header("user-agent", "ryt/0.1.0")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"tag_name": "v0.2.0", "body": " - Fixes \n", "assets": []}"#)
+            .create_async()
+            .await;
+
+        let updater = Updater::new()
+            .with_api_url(&server.url())
+            .with_current_version("0.1.0")
+            .with_token(Some("secret".to_string()));
+        let release = updater.latest_release().await.unwrap();
+        assert_eq!(release.version(), "0.2.0");
+        assert_eq!(release.changelog(), Some("- Fixes"));
+        assert!(updater.is_update(&release));
+        assert!(!updater.with_current_version("0.2.0").is_update(&release));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_latest_release_rate_limited() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/repos/ytget/ryt/releases/latest")
+            .with_status(403)
+            .create_async()
+            .await;
+
+        let err = Updater::new()
+            .with_api_url(&server.url())
+            .latest_release()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RytError::UpdateFailed(_)));
+        assert!(err.to_string().contains("GITHUB_TOKEN"));
+    }
+
+    #[tokio::test]
+    async fn test_install_asset_verifies_checksum() {
+        let mut server = mockito::Server::new_async().await;
+        let binary = b"#!/bin/sh\necho new\n";
+        server
+            .mock("GET", "/ryt-test")
+            .with_body(binary)
+            .create_async()
+            .await;
+        let checksums = server
+            .mock("GET", "/checksums.txt")
+            .with_body(format!("{}  ryt-test\n", sha256_hex(binary)))
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("ryt");
+        std::fs::write(&target, "old").unwrap();
+
+        let updater = Updater::new();
+        let release = release(&server.url());
+        updater
+            .install_asset(&release, "ryt-test", &target)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), binary);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+
+        // A mismatching checksum leaves the installed binary alone
+        checksums.remove_async().await;
+        server
+            .mock("GET", "/checksums.txt")
+            .with_body(format!("{}  ryt-test\n", sha256_hex(b"other")))
+            .create_async()
+            .await;
+        std::fs::write(&target, "old").unwrap();
+        let err = updater
+            .install_asset(&release, "ryt-test", &target)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+        assert_eq!(std::fs::read(&target).unwrap(), b"old");
+
+        // Missing assets are reported by name
+        let err = updater
+            .install_asset(&release, "ryt-missing", &target)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no ryt-missing binary"));
+    }
+}
@@ -1,61 +1,275 @@
 //! Main downloader implementation
 
+use crate::core::progress::format_bytes;
 use crate::core::video_info::Format;
-use crate::core::{FormatSelector, Progress, QualitySelector, VideoInfo};
-use crate::download::ChunkedDownloader;
+use crate::core::{
+    check_client, check_country, check_output_path, check_rate_limit, check_timeout,
+    check_write_buffer, mux_args, mux_container, run_ffmpeg, FormatSelector, ItemOutcome,
+    MatchFilter, PlaylistDownloadError, PlaylistDownloadResult, PlaylistItem, PlaylistItemsSpec,
+    PlaylistMetadata, PlaylistOptions, Progress, ProgressPhase, QualitySelector, SearchResult,
+    SleepInterval, Status, VideoInfo, VisitorData,
+};
+use crate::download::{
+    ChunkedDownloader, DownloaderConfig, ItemStatus, RateLimitGrace, Session, SessionItem,
+    TransferBackend, TransferRequest, DEFAULT_READ_TIMEOUT, DEFAULT_WRITE_BUFFER_SIZE,
+};
 use crate::error::RytError;
-use crate::platform::{InnerTubeClient, PlayerResponse};
-use crate::utils::{extract_video_id, to_safe_filename};
-use std::path::PathBuf;
+use crate::platform::cipher::{Cipher, DEFAULT_CIPHER_TIMEOUT};
+use crate::platform::{
+    best_60fps_format, best_audio_stream, best_format, best_video_stream, filter_formats,
+    parse_format_selector, select_caption_track, select_dolby_atmos, select_with_fallback,
+    AcceptEncoding, CaptionFormat, FormatFallback, FormatSelection, Http2Mode, HttpClientConfig,
+    InnerTubeClient, IpFamily, Itag, PlayerResponse, TrafficLog, TrendingCategory, VideoClient,
+    DEFAULT_API_TIMEOUT, DEFAULT_CONNECT_TIMEOUT,
+};
+use crate::utils::{
+    base_mime_type, convert_thumbnail, duration_serde, ensure_output_directory, expand_path,
+    ext_from_mime, extract_channel_id, extract_video_id, has_trailing_separator, loggable_secret,
+    loggable_url, media_host_candidates, metrics, parse_length_seconds, render_template,
+    to_safe_filename, unique_path, write_atomic, ArchiveEntry, ArchiveFormat, CacheStats,
+    DownloadArchive, LinkKind, PlaylistArchive, ThumbnailFormat,
+};
+use reqwest::cookie::Jar;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+/// Chunk size for live stream VODs, whose URLs reject large or open-ended ranges
+const LIVE_VOD_CHUNK_SIZE: u64 = 512 * 1024;
+
+/// Maximum number of mid-download media URL refreshes
+const MAX_URL_REFRESHES: u32 = 5;
+
+/// Default number of other formats tried after a 403 before switching clients
+pub const DEFAULT_FORMAT_RETRIES: u32 = 3;
+
+/// Default language of captions written with `write_subs`
+pub const DEFAULT_SUB_LANG: &str = "en";
+
+/// InnerTube clients tried in order until one returns a playable response
+pub const DEFAULT_CLIENT_FALLBACK_CHAIN: [(&str, &str); 4] = [
+    ("ANDROID", "20.10.38"),
+    ("IOS", "19.29.1"),
+    ("TVHTML5", "7.20250101"),
+    ("WEB", "2.20251002"),
+];
+
 /// Main downloader configuration
-#[derive(Debug, Clone)]
+///
+/// Serializes with the field names below; see
+/// [`DownloadOptions::from_file`] for loading a config file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct DownloadOptions {
-    /// Format selector
+    /// Format selector; a config file may give it as a selector string
+    #[serde(
+        alias = "format",
+        deserialize_with = "crate::core::options::deserialize_format_selector"
+    )]
     pub format_selector: Option<FormatSelector>,
+    /// What to select when the format selector matches nothing
+    pub format_fallback: FormatFallback,
+    /// Other formats of the same player response to try when the selected
+    /// one is refused with a 403, before switching clients
+    pub format_retries: u32,
     /// Desired file extension
     pub desired_ext: Option<String>,
     /// Output path (file or directory)
+    #[serde(alias = "output")]
     pub output_path: Option<PathBuf>,
     /// Rate limit in bytes per second
     pub rate_limit_bps: Option<u64>,
+    /// Only start rate limiting a download after this threshold
+    pub rate_limit_after: Option<RateLimitGrace>,
+    /// Share one rate limit across all downloads instead of one per download
+    pub global_rate_limit: bool,
+    /// Re-resolve the media URL when throughput stays below this many bytes
+    /// per second for a while
+    pub throttled_rate: Option<u64>,
     /// InnerTube client name
     pub client_name: String,
     /// InnerTube client version
     pub client_version: String,
-    /// HTTP timeout
+    /// Clients tried after the configured one when a client can't play a video
+    pub client_fallback_chain: Vec<(String, String)>,
+    /// Total timeout for API requests (media downloads have none)
+    #[serde(with = "duration_serde")]
     pub timeout: Duration,
+    /// Connection establishment timeout
+    #[serde(with = "duration_serde")]
+    pub connect_timeout: Duration,
+    /// Idle timeout between chunks of a media body
+    #[serde(with = "duration_serde::option")]
+    pub read_timeout: Option<Duration>,
+    /// Limit on how long a player.js execution may run while deciphering
+    #[serde(with = "duration_serde")]
+    pub cipher_timeout: Duration,
+    /// Address family for API connections
+    pub ip_family: IpFamily,
+    /// Address family for media connections; with `Any`, ranged chunks try
+    /// IPv6 first and fall back to IPv4
+    pub media_ip_family: IpFamily,
+    /// HTTP/2 use for API connections; media connections are always HTTP/1.1
+    pub api_http2: Http2Mode,
+    /// Compression advertised by API requests; media requests never ask for any
+    pub api_accept_encoding: AcceptEncoding,
+    /// Fixed addresses for hosts, used by both API and media connections
+    pub dns_overrides: HashMap<String, Vec<SocketAddr>>,
+    /// Log every API and media request and response (`--print-traffic`)
+    pub traffic_log: Option<TrafficLog>,
     /// Maximum retries
     pub max_retries: u32,
+    /// Maximum retries per media fragment
+    pub fragment_retries: u32,
+    /// Segments of a segmented (HLS) download fetched at once
+    pub concurrent_fragments: usize,
+    /// Directory for partial and intermediate files
+    pub temp_dir: Option<PathBuf>,
+    /// Keep partial files of failed downloads for inspection
+    pub keep_temp_on_error: bool,
+    /// Create missing parent directories of the output path
+    pub create_dirs: bool,
+    /// Add a ` (1)`, ` (2)`, … suffix instead of replacing an existing file
+    pub unique_filenames: bool,
+    /// Write buffer size in bytes for streamed downloads
+    pub write_buffer_size: usize,
+    /// Download archive file recording downloaded videos
+    pub download_archive: Option<PathBuf>,
+    /// Line format used when recording to the download archive
+    pub archive_format: ArchiveFormat,
+    /// Re-download archived videos recorded below this height
+    pub redownload_below: Option<u32>,
+    /// Session file for resuming interrupted playlist and batch jobs
+    pub session_file: Option<PathBuf>,
+    /// Retry items that failed in an earlier run of the session
+    pub retry_failed: bool,
+    /// Playlist progress file; downloaded items are recorded with their
+    /// titles and skipped on the next run
+    pub playlist_archive: Option<PathBuf>,
+    /// Prefer WebM (VP9/Opus) formats over MP4 when both are available
+    pub prefer_webm: bool,
+    /// Select the best format at 48 fps or more when asked for the best one
+    pub prefer_60fps: bool,
+    /// Skip formats larger than this many bytes
+    pub max_filesize: Option<u64>,
+    /// Skip formats smaller than this many bytes
+    pub min_filesize: Option<u64>,
+    /// Skip formats of unknown size when a size limit is set
+    pub reject_unknown_size: bool,
+    /// Fail when the selected format is outside the file size limits
+    /// instead of falling through to a smaller one
+    pub strict_format: bool,
+    /// Write the video thumbnail next to the downloaded file
+    pub write_thumbnail: bool,
+    /// Convert written thumbnails to this format
+    pub thumbnail_format: Option<ThumbnailFormat>,
+    /// Write the video description to a `.description` file
+    pub write_description: bool,
+    /// Write the video metadata to a yt-dlp style `.info.json` file
+    pub write_infojson: bool,
+    /// Write the playlist metadata to `<playlist_id>.info.json` when
+    /// downloading a playlist
+    pub write_playlist_metafiles: bool,
+    /// Write the captions in `sub_lang` to a `.<lang>.vtt` file
+    pub write_subs: bool,
+    /// Write the SHA-256 of the downloaded file to a `.sha256` file
+    pub write_checksum: bool,
+    /// Language code of the captions to write
+    pub sub_lang: String,
+    /// Write shortcut files of these kinds pointing to the video page
+    pub write_links: Vec<LinkKind>,
+    /// Fetch the category and comment count with an extra `next` request
+    pub fetch_extra_metadata: bool,
+    /// Only download videos passing this filter
+    pub match_filter: Option<MatchFilter>,
+    /// Country sent with API requests (`gl`), e.g. `US`
+    pub geo_bypass_country: Option<String>,
+    /// Host language sent with API requests (`hl`), e.g. `en`
+    pub language: Option<String>,
 }
 
 impl Default for DownloadOptions {
     fn default() -> Self {
         Self {
             format_selector: None,
+            format_fallback: FormatFallback::Error,
+            format_retries: DEFAULT_FORMAT_RETRIES,
             desired_ext: None,
             output_path: None,
             rate_limit_bps: None,
+            rate_limit_after: None,
+            global_rate_limit: false,
+            throttled_rate: None,
             client_name: "ANDROID".to_string(), // ANDROID gives direct URLs without cipher complexity
             client_version: "20.10.38".to_string(),
-            timeout: Duration::from_secs(30),
+            client_fallback_chain: DEFAULT_CLIENT_FALLBACK_CHAIN
+                .iter()
+                .map(|(name, version)| (name.to_string(), version.to_string()))
+                .collect(),
+            timeout: DEFAULT_API_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            cipher_timeout: DEFAULT_CIPHER_TIMEOUT,
+            ip_family: IpFamily::Any,
+            media_ip_family: IpFamily::Any,
+            api_http2: Http2Mode::Auto,
+            api_accept_encoding: AcceptEncoding::default(),
+            dns_overrides: HashMap::new(),
+            traffic_log: None,
             max_retries: 3,
+            fragment_retries: 10,
+            concurrent_fragments: 1,
+            temp_dir: None,
+            keep_temp_on_error: false,
+            create_dirs: true,
+            unique_filenames: false,
+            write_buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
+            download_archive: None,
+            archive_format: ArchiveFormat::Plain,
+            redownload_below: None,
+            session_file: None,
+            retry_failed: false,
+            playlist_archive: None,
+            prefer_webm: false,
+            prefer_60fps: false,
+            max_filesize: None,
+            min_filesize: None,
+            reject_unknown_size: false,
+            strict_format: false,
+            write_thumbnail: false,
+            thumbnail_format: None,
+            write_description: false,
+            write_infojson: false,
+            write_playlist_metafiles: false,
+            write_subs: false,
+            write_checksum: false,
+            sub_lang: DEFAULT_SUB_LANG.to_string(),
+            write_links: Vec::new(),
+            fetch_extra_metadata: false,
+            match_filter: None,
+            geo_bypass_country: None,
+            language: None,
         }
     }
 }
 
 /// Botguard configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct BotguardConfig {
     /// Botguard mode
     pub mode: crate::platform::botguard::BotguardMode,
     /// Debug mode
     pub debug: bool,
     /// Token TTL
+    #[serde(with = "duration_serde")]
     pub ttl: Duration,
 }
 
@@ -69,12 +283,172 @@ impl Default for BotguardConfig {
     }
 }
 
+/// File size limits applied during format selection
+#[derive(Debug, Clone, Copy)]
+struct SizeLimits {
+    min: Option<u64>,
+    max: Option<u64>,
+    reject_unknown: bool,
+    /// Video duration in seconds, for estimating unknown sizes
+    duration: u32,
+}
+
+impl SizeLimits {
+    /// Get the limits configured in `options`
+    fn new(options: &DownloadOptions, duration: u32) -> Self {
+        Self {
+            min: options.min_filesize,
+            max: options.max_filesize,
+            reject_unknown: options.reject_unknown_size,
+            duration,
+        }
+    }
+
+    /// Get limits letting every format through
+    fn none(duration: u32) -> Self {
+        Self {
+            min: None,
+            max: None,
+            reject_unknown: false,
+            duration,
+        }
+    }
+
+    /// Check if no size limit is set
+    fn is_unlimited(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// Check a format's size, or its estimated size, against the limits
+    fn allows(&self, format: &Format) -> bool {
+        match format.approx_filesize(self.duration) {
+            Some(size) => {
+                self.min.map_or(true, |min| size >= min) && self.max.map_or(true, |max| size <= max)
+            }
+            None => !(self.reject_unknown && !self.is_unlimited()),
+        }
+    }
+
+    /// Describe a format's size for logging, marking estimates with `~`
+    fn describe(&self, format: &Format) -> String {
+        match (format.size, format.approx_filesize(self.duration)) {
+            (Some(size), _) => format_bytes(size),
+            (None, Some(estimate)) => format!("~{}", format_bytes(estimate)),
+            (None, None) => "unknown size".to_string(),
+        }
+    }
+
+    /// Get the error for a selection that found nothing within the limits,
+    /// reporting `smallest` when it is over the maximum
+    fn rejection(&self, smallest: Option<u64>) -> RytError {
+        match (self.max, smallest) {
+            (Some(limit), Some(actual)) if actual > limit => {
+                RytError::FileSizeExceeds { limit, actual }
+            }
+            _ => RytError::NoFormatFound,
+        }
+    }
+}
+
+/// Download that would be performed for a video, without fetching media
+#[derive(Debug, Clone)]
+pub struct DownloadPlan {
+    /// Video metadata
+    pub video_info: VideoInfo,
+    /// Selected format
+    pub format: Format,
+    /// Resolved media URL
+    pub url: String,
+    /// Output file path
+    pub output_path: PathBuf,
+}
+
+/// Video resolved from a player response
+#[derive(Debug, Clone)]
+struct Resolution {
+    /// Media URL of the selected format
+    url: String,
+    /// Video metadata
+    video_info: VideoInfo,
+    /// Selected format
+    format: Format,
+    /// Formats of the same response to fall back on when the selected one
+    /// is refused, best first
+    candidates: Vec<Format>,
+}
+
+/// Cancellation and in-flight tracking shared by a downloader, its clones
+/// and its forks
+struct Lifecycle {
+    cancel: CancellationToken,
+    /// Number of operations in flight
+    in_flight: watch::Sender<usize>,
+    /// Partial files of transfers interrupted by cancellation
+    partial_files: std::sync::Mutex<Vec<PathBuf>>,
+}
+
+impl Lifecycle {
+    fn new() -> Self {
+        Self {
+            cancel: CancellationToken::new(),
+            in_flight: watch::channel(0).0,
+            partial_files: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Drop for Lifecycle {
+    /// Cancel whatever still holds the token once the last downloader is gone
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+/// Marks an operation as in flight until dropped
+struct OperationGuard {
+    lifecycle: Arc<Lifecycle>,
+}
+
+impl OperationGuard {
+    /// Start an operation, failing if the downloader was shut down
+    fn begin(lifecycle: &Arc<Lifecycle>) -> Result<Self, RytError> {
+        if lifecycle.cancel.is_cancelled() {
+            return Err(RytError::Cancelled);
+        }
+        lifecycle.in_flight.send_modify(|count| *count += 1);
+        Ok(Self {
+            lifecycle: Arc::clone(lifecycle),
+        })
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        self.lifecycle.in_flight.send_modify(|count| *count -= 1);
+    }
+}
+
 /// Main downloader struct
+///
+/// Downloading, resolving and metadata methods take `&self`, so one
+/// downloader can serve several tasks at once. Clones are cheap and share
+/// the clients, caches and callbacks (unlike [`Downloader::fork`], which
+/// drops the progress callback); configure the downloader before cloning it,
+/// since builders only affect the value they are called on.
+#[derive(Clone)]
 pub struct Downloader {
     options: DownloadOptions,
     botguard: BotguardConfig,
+    playlist: PlaylistOptions,
     inner_tube: Arc<Mutex<InnerTubeClient>>,
     downloader: Arc<Mutex<ChunkedDownloader>>,
+    transfer_backend: Option<Arc<dyn TransferBackend>>,
+    cookie_jar: Option<Arc<Jar>>,
+    cipher: Arc<Cipher>,
+    progress_callback: Option<Arc<dyn Fn(Progress) + Send + Sync>>,
+    status_callback: Option<Arc<dyn Fn(Status) + Send + Sync>>,
+    plan_callback: Option<Arc<dyn Fn(&DownloadPlan) + Send + Sync>>,
+    lifecycle: Arc<Lifecycle>,
 }
 
 impl Downloader {
@@ -83,44 +457,404 @@ impl Downloader {
         Self {
             options: DownloadOptions::default(),
             botguard: BotguardConfig::default(),
+            playlist: PlaylistOptions::default(),
             inner_tube: Arc::new(Mutex::new(InnerTubeClient::new())),
             downloader: Arc::new(Mutex::new(ChunkedDownloader::new())),
+            transfer_backend: None,
+            cookie_jar: None,
+            cipher: Arc::new(Cipher::new()),
+            progress_callback: None,
+            status_callback: None,
+            plan_callback: None,
+            lifecycle: Arc::new(Lifecycle::new()),
+        }
+    }
+
+    /// Create a downloader from options, after validating them
+    pub fn from_options(options: DownloadOptions) -> Result<Self, RytError> {
+        options.validate()?;
+        let mut downloader = Self::new();
+        downloader.options = options;
+        downloader.inner_tube = Arc::new(Mutex::new(downloader.build_inner_tube()));
+        downloader.downloader = Arc::new(Mutex::new(downloader.build_chunked_downloader()));
+        downloader.cipher = Arc::new(downloader.build_cipher());
+        Ok(downloader)
+    }
+
+    /// Create a downloader from a TOML or JSON config file; see
+    /// [`DownloadOptions::from_file`]. Builders applied afterwards override
+    /// the file's values.
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self, RytError> {
+        Self::from_options(DownloadOptions::from_file(path)?)
+    }
+
+    /// Create a downloader sharing this one's clients and caches.
+    ///
+    /// Forks share the InnerTube client, the media downloader and the cipher
+    /// caches, so client health and cached player data carry over between
+    /// them. Options and the Botguard configuration are copied at the fork
+    /// point and are not affected by later changes to the parent. The status
+    /// and plan callbacks are inherited, but the progress callback is not; set one per
+    /// fork with [`Downloader::with_progress`]. Builders that rebuild clients (timeouts,
+    /// rate limits, address family) detach the fork from the shared clients.
+    pub fn fork(&self) -> Self {
+        Self {
+            options: self.options.clone(),
+            botguard: self.botguard.clone(),
+            playlist: self.playlist.clone(),
+            inner_tube: Arc::clone(&self.inner_tube),
+            downloader: Arc::clone(&self.downloader),
+            transfer_backend: self.transfer_backend.clone(),
+            cookie_jar: self.cookie_jar.clone(),
+            cipher: Arc::clone(&self.cipher),
+            progress_callback: None,
+            status_callback: self.status_callback.clone(),
+            plan_callback: self.plan_callback.clone(),
+            lifecycle: Arc::clone(&self.lifecycle),
+        }
+    }
+
+    /// Get the token cancelled by [`Downloader::shutdown`], e.g. to cancel
+    /// downloads from a Ctrl-C handler. It is also cancelled once the
+    /// downloader and all its clones and forks are dropped.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.lifecycle.cancel.clone()
+    }
+
+    /// Shut the downloader down, returning the partial files of interrupted
+    /// downloads.
+    ///
+    /// Shutdown applies to this downloader and all its clones and forks:
+    /// running downloads are cancelled and new ones fail with
+    /// [`RytError::Cancelled`]. Cancellation only interrupts media
+    /// transfers, so once this returns every operation has unwound: partial
+    /// files are closed, and archive entries and session state of finished
+    /// downloads are on disk (they are written before an operation ends).
+    /// Caches live in memory and the cipher runs JavaScript on the calling
+    /// task, joining its timeout thread when the script ends, so there is no
+    /// cache or worker thread left to flush or join.
+    ///
+    /// Dropping the last downloader only cancels, without waiting.
+    pub async fn shutdown(self) -> Result<Vec<PathBuf>, RytError> {
+        self.lifecycle.cancel.cancel();
+        let mut in_flight = self.lifecycle.in_flight.subscribe();
+        in_flight
+            .wait_for(|count| *count == 0)
+            .await
+            .map_err(|e| RytError::Generic(format!("Shutdown failed: {}", e)))?;
+
+        let partial_files: Vec<PathBuf> = std::mem::take(
+            &mut *self
+                .lifecycle
+                .partial_files
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        )
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect();
+        for path in &partial_files {
+            warn!(
+                "Download interrupted, partial file left at {}",
+                path.display()
+            );
+        }
+        Ok(partial_files)
+    }
+
+    /// Run a media transfer to `output_path`, stopping it on shutdown
+    async fn cancellable_transfer(
+        &self,
+        transfer: impl std::future::Future<Output = Result<(), RytError>>,
+        url: &str,
+        output_path: &Path,
+    ) -> Result<(), RytError> {
+        tokio::select! {
+            result = transfer => result,
+            _ = self.lifecycle.cancel.cancelled() => {
+                let partial = crate::utils::temp::partial_file_path(
+                    self.options.temp_dir.as_deref(),
+                    output_path,
+                    url,
+                );
+                self.lifecycle
+                    .partial_files
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(partial);
+                Err(RytError::Cancelled)
+            }
+        }
+    }
+
+    /// Transfer media with a configured backend, passing it the headers the
+    /// built-in downloader would send and the progress callback
+    async fn backend_transfer(
+        &self,
+        backend: &dyn TransferBackend,
+        downloader: &ChunkedDownloader,
+        url: &str,
+        output_path: &Path,
+    ) -> Result<(), RytError> {
+        let partial = (!backend.manages_partials()).then(|| {
+            crate::utils::temp::partial_file_path(
+                self.options.temp_dir.as_deref(),
+                output_path,
+                url,
+            )
+        });
+        let request = TransferRequest {
+            url,
+            output_path: partial.as_deref().unwrap_or(output_path),
+            headers: downloader.media_headers(url),
+            progress: self.progress_callback.clone(),
+        };
+        backend.transfer(&request).await?;
+        if let Some(partial) = &partial {
+            crate::utils::temp::move_file(partial, output_path)
+                .await
+                .map_err(|e| RytError::io(e, "move", partial))?;
+        }
+        Ok(())
+    }
+
+    /// Run a media transfer, retrying it on the alternative CDN hosts of
+    /// `url` when the primary host keeps refusing it or times out.
+    ///
+    /// Errors another host won't fix, such as an expired URL, are returned
+    /// right away. Once every host has failed, the last error is returned so
+    /// the caller can re-resolve the URL.
+    async fn transfer_with_host_fallback<F, Fut>(
+        &self,
+        url: &str,
+        output_path: &Path,
+        mut transfer: F,
+    ) -> Result<(), RytError>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: std::future::Future<Output = Result<(), RytError>>,
+    {
+        let mut candidates = media_host_candidates(url).into_iter();
+        let mut current = url.to_string();
+        loop {
+            let result = self
+                .cancellable_transfer(transfer(current.clone()), &current, output_path)
+                .await;
+            match result {
+                Ok(()) => {
+                    info!("Media served by {}", media_host(&current));
+                    return Ok(());
+                }
+                Err(e) if is_host_specific(&e) => {
+                    let Some(next) = candidates.next() else {
+                        return Err(e);
+                    };
+                    metrics::retry("cdn_host");
+                    warn!(
+                        "{} from {}, trying {}",
+                        e,
+                        media_host(&current),
+                        media_host(&next)
+                    );
+                    current = next;
+                }
+                Err(e) => return Err(e),
+            }
         }
     }
 
-    /// Set format selector
+    /// Set format selector, e.g. `best` or `bestvideo[height<=1080]+bestaudio[ext=m4a]`.
+    ///
+    /// `ext` applies unless the selector has its own `[ext=...]` filter. An
+    /// invalid selector is ignored with a warning.
+    #[deprecated(note = "use `try_with_format`, which rejects invalid selectors")]
     pub fn with_format(mut self, selector: &str, ext: &str) -> Self {
-        if let Ok(quality) = QualitySelector::from_str(selector) {
-            self.options.format_selector = Some(FormatSelector::new(quality).with_extension(ext));
+        match parse_format_selector(selector) {
+            Ok(mut selector) => {
+                if selector.extension.is_none() && !ext.is_empty() {
+                    selector = selector.with_extension(ext);
+                }
+                self.options.format_selector = Some(selector);
+            }
+            Err(e) => warn!("Ignoring format selector: {}", e),
         }
         self
     }
 
-    /// Set output path
+    /// Set format selector, failing on an invalid one
+    #[allow(deprecated)]
+    pub fn try_with_format(self, selector: &str, ext: &str) -> Result<Self, RytError> {
+        parse_format_selector(selector)?;
+        Ok(self.with_format(selector, ext))
+    }
+
+    /// Set what to select when the format selector matches nothing
+    pub fn with_format_fallback(mut self, policy: FormatFallback) -> Self {
+        self.options.format_fallback = policy;
+        self
+    }
+
+    /// Set how many other formats to try after a 403 before switching clients
+    pub fn with_format_retries(mut self, retries: u32) -> Self {
+        self.options.format_retries = retries;
+        self
+    }
+
+    /// Set whether WebM formats are preferred over MP4
+    pub fn with_prefer_webm(mut self, prefer_webm: bool) -> Self {
+        self.options.prefer_webm = prefer_webm;
+        self
+    }
+
+    /// Set whether high frame rate formats are preferred for the best quality
+    pub fn with_prefer_60fps(mut self, prefer_60fps: bool) -> Self {
+        self.options.prefer_60fps = prefer_60fps;
+        self
+    }
+
+    /// Skip formats larger than `bytes`
+    pub fn with_max_filesize(mut self, bytes: u64) -> Self {
+        self.options.max_filesize = Some(bytes);
+        self
+    }
+
+    /// Skip formats smaller than `bytes`
+    pub fn with_min_filesize(mut self, bytes: u64) -> Self {
+        self.options.min_filesize = Some(bytes);
+        self
+    }
+
+    /// Set whether formats of unknown size are skipped when a size limit is set
+    pub fn with_reject_unknown_size(mut self, reject: bool) -> Self {
+        self.options.reject_unknown_size = reject;
+        self
+    }
+
+    /// Set whether a selected format outside the size limits is an error
+    /// instead of a reason to pick a smaller one
+    pub fn with_strict_format(mut self, strict: bool) -> Self {
+        self.options.strict_format = strict;
+        self
+    }
+
+    /// Set output path, expanding `~` and environment variables
+    #[deprecated(note = "use `try_with_output_path`, which rejects empty paths")]
     pub fn with_output_path(mut self, path: impl Into<PathBuf>) -> Self {
-        self.options.output_path = Some(path.into());
+        self.options.output_path = Some(expand_path(&path.into()));
         self
     }
 
+    /// Set output path, failing on an empty one
+    #[allow(deprecated)]
+    pub fn try_with_output_path(self, path: impl Into<PathBuf>) -> Result<Self, RytError> {
+        let path = path.into();
+        check_output_path(&path)?;
+        Ok(self.with_output_path(path))
+    }
+
     /// Set progress callback
-    pub fn with_progress(self, _callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
-        // TODO: Implement progress callback in ChunkedDownloader
+    pub fn with_progress(mut self, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.progress_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Hand media transfers to another backend, such as an
+    /// [`ExternalDownloader`](crate::download::ExternalDownloader), instead
+    /// of the built-in chunked downloader
+    pub fn with_transfer_backend(mut self, backend: impl TransferBackend + 'static) -> Self {
+        self.transfer_backend = Some(Arc::new(backend));
         self
     }
 
+    /// Set status callback, called as a download moves between stages
+    pub fn with_status_callback(
+        mut self,
+        callback: impl Fn(Status) + Send + Sync + 'static,
+    ) -> Self {
+        self.status_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Set a callback called with the plan of each downloaded video once its
+    /// download has finished, with the format that was actually written
+    pub fn with_plan_callback(
+        mut self,
+        callback: impl Fn(&DownloadPlan) + Send + Sync + 'static,
+    ) -> Self {
+        self.plan_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Report a status change to the status callback, if any
+    fn emit_status(&self, status: Status) {
+        if let Some(callback) = &self.status_callback {
+            callback(status);
+        }
+    }
+
     /// Set rate limit
+    #[deprecated(note = "use `try_with_rate_limit`, which rejects limits of 1 KB/s or less")]
     pub fn with_rate_limit(mut self, bytes_per_second: u64) -> Self {
         self.options.rate_limit_bps = Some(bytes_per_second);
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
+
+    /// Set rate limit, failing on limits of 1 KB/s or less
+    #[allow(deprecated)]
+    pub fn try_with_rate_limit(self, bytes_per_second: u64) -> Result<Self, RytError> {
+        check_rate_limit(bytes_per_second)?;
+        Ok(self.with_rate_limit(bytes_per_second))
+    }
+
+    /// Set threshold (bytes or time) after which rate limiting kicks in
+    pub fn with_rate_limit_after(mut self, grace: RateLimitGrace) -> Self {
+        self.options.rate_limit_after = Some(grace);
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
+
+    /// Get the rate limit in bytes per second, if any
+    pub fn rate_limit(&self) -> Option<u64> {
+        self.options.rate_limit_bps
+    }
+
+    /// Share one rate limit across all downloads
+    pub fn with_global_rate_limit(mut self, global: bool) -> Self {
+        self.options.global_rate_limit = global;
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
+
+    /// Re-resolve the media URL with another client when throughput stays
+    /// below `bytes_per_second`, resuming from the bytes already written
+    pub fn with_throttled_rate(mut self, bytes_per_second: u64) -> Self {
+        self.options.throttled_rate = Some(bytes_per_second);
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
         self
     }
 
     /// Set InnerTube client
+    #[deprecated(note = "use `try_with_innertube_client`, which rejects empty names")]
     pub fn with_innertube_client(mut self, name: &str, version: &str) -> Self {
         self.options.client_name = name.to_string();
         self.options.client_version = version.to_string();
         self
     }
 
+    /// Set InnerTube client, failing on an empty name or version
+    #[allow(deprecated)]
+    pub fn try_with_innertube_client(self, name: &str, version: &str) -> Result<Self, RytError> {
+        check_client(name, version)?;
+        Ok(self.with_innertube_client(name, version))
+    }
+
+    /// Set clients tried after the configured one, as `(name, version)` pairs
+    pub fn with_client_fallback_chain(mut self, chain: Vec<(String, String)>) -> Self {
+        self.options.client_fallback_chain = chain;
+        self
+    }
+
     /// Set Botguard mode
     pub fn with_botguard(mut self, mode: crate::platform::botguard::BotguardMode) -> Self {
         self.botguard.mode = mode;
@@ -139,416 +873,2028 @@ impl Downloader {
         self
     }
 
-    /// Set HTTP timeout
-    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+    /// Set API request timeout (same as `with_request_timeout`)
+    #[deprecated(note = "use `try_with_request_timeout`, which rejects a zero timeout")]
+    #[allow(deprecated)]
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_request_timeout(timeout)
+    }
+
+    /// Set total timeout for API/JSON requests; media downloads are not limited
+    #[deprecated(note = "use `try_with_request_timeout`, which rejects a zero timeout")]
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
         self.options.timeout = timeout;
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
         self
     }
 
-    /// Set maximum retries
-    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
-        self.options.max_retries = max_retries;
+    /// Set total timeout for API/JSON requests, failing on a zero timeout
+    #[allow(deprecated)]
+    pub fn try_with_request_timeout(self, timeout: Duration) -> Result<Self, RytError> {
+        check_timeout("timeout", timeout)?;
+        Ok(self.with_request_timeout(timeout))
+    }
+
+    /// Set connection establishment timeout for API and media requests
+    #[deprecated(note = "use `try_with_connect_timeout`, which rejects a zero timeout")]
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.options.connect_timeout = timeout;
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
         self
     }
 
-    /// Resolve video URL and get metadata without downloading
-    pub async fn resolve_url(&mut self, video_url: &str) -> Result<(String, VideoInfo), RytError> {
-        // Extract video ID
-        let video_id = extract_video_id(video_url)?;
-        info!("Resolving URL for video ID: {}", video_id);
+    /// Set connection establishment timeout, failing on a zero timeout
+    #[allow(deprecated)]
+    pub fn try_with_connect_timeout(self, timeout: Duration) -> Result<Self, RytError> {
+        check_timeout("connect timeout", timeout)?;
+        Ok(self.with_connect_timeout(timeout))
+    }
 
-        // Try to get player response with retry logic for age restrictions
-        let mut last_error = None;
-        let max_retries = 3;
+    /// Set idle timeout between chunks of a media body.
+    ///
+    /// A stalled download fails with a retryable error and resumes.
+    #[deprecated(note = "use `try_with_read_timeout`, which rejects a zero timeout")]
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.options.read_timeout = Some(timeout);
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
 
-        for attempt in 0..=max_retries {
-            let mut inner_tube = self.inner_tube.lock().await;
+    /// Set idle timeout between chunks of a media body, failing on a zero timeout
+    #[allow(deprecated)]
+    pub fn try_with_read_timeout(self, timeout: Duration) -> Result<Self, RytError> {
+        check_timeout("read timeout", timeout)?;
+        Ok(self.with_read_timeout(timeout))
+    }
 
-            match inner_tube.get_player_response(&video_id).await {
-                Ok(player_response) => {
-                    // Success, continue with processing
-                    drop(inner_tube); // Release lock early
-                    let (final_url, video_info) = self
-                        .process_player_response(player_response, &video_id)
-                        .await?;
+    /// Set how long a player.js execution may run while deciphering
+    #[deprecated(note = "use `try_with_cipher_timeout`, which rejects a zero timeout")]
+    pub fn with_cipher_timeout(mut self, timeout: Duration) -> Self {
+        self.options.cipher_timeout = timeout;
+        self.cipher = Arc::new(self.build_cipher());
+        self
+    }
 
-                    // Professional: WEB fallback causes c=WEB in URL, breaking ANDROID client context
-                    // ANDROID already returns valid itag=18 URLs without 'n' parameter - this is OK
-                    // Do NOT switch to WEB just for 'n' parameter - it breaks client consistency
-                    // YouTube CDN validates that URL's c= parameter matches the client that obtained it
+    /// Set how long a player.js execution may run, failing on a zero timeout
+    #[allow(deprecated)]
+    pub fn try_with_cipher_timeout(self, timeout: Duration) -> Result<Self, RytError> {
+        check_timeout("cipher timeout", timeout)?;
+        Ok(self.with_cipher_timeout(timeout))
+    }
 
-                    return Ok((final_url, video_info));
-                }
-                Err(RytError::AgeRestricted) => {
-                    warn!(
-                        "Age restriction detected on attempt {}, switching client",
-                        attempt + 1
-                    );
-                    // Switch client for age restriction
-                    inner_tube.switch_client_for_error(&RytError::AgeRestricted);
-                    last_error = Some(RytError::AgeRestricted);
-
-                    // Wait before retry
-                    if attempt < max_retries {
-                        drop(inner_tube); // Release lock before sleep
-                        tokio::time::sleep(Duration::from_millis(500 * (attempt + 1) as u64)).await;
-                    }
-                }
-                Err(e) => {
-                    // Non-retryable error or other error
-                    return Err(e);
-                }
-            }
-        }
+    /// Set country sent with API requests, e.g. `US`
+    #[deprecated(note = "use `try_with_geo_bypass_country`, which rejects invalid codes")]
+    pub fn with_geo_bypass_country(mut self, country: &str) -> Self {
+        self.options.geo_bypass_country = Some(country.to_uppercase());
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self
+    }
 
-        // If we get here, all retries failed
-        Err(last_error.unwrap_or(RytError::AgeRestricted))
+    /// Set country sent with API requests, failing on an invalid country code
+    #[allow(deprecated)]
+    pub fn try_with_geo_bypass_country(self, country: &str) -> Result<Self, RytError> {
+        check_country(country)?;
+        Ok(self.with_geo_bypass_country(country))
     }
 
-    /// Process player response and extract video info
-    async fn process_player_response(
-        &mut self,
-        player_response: PlayerResponse,
-        video_id: &str,
-    ) -> Result<(String, VideoInfo), RytError> {
-        // Parse formats
-        let formats = player_response.parse_formats()?;
-        debug!("Found {} formats for video {}", formats.len(), video_id);
+    /// Set host language sent with API requests, e.g. `en`
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.options.language = Some(language.to_string());
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self
+    }
 
-        // Debug: print all formats
-        // println!("📋 Available formats ({}):", formats.len());
-        // for (i, format) in formats.iter().enumerate() {
-        //     println!("  {}: itag={}, quality={}, url_len={}, needs_deciphering={}",
-        //         i, format.itag, format.quality, format.url.len(), format.needs_deciphering());
-        //     if let Some(sig_cipher) = &format.signature_cipher {
-        //         println!("    signature_cipher: {}", sig_cipher);
-        //     }
-        // }
+    /// Set address family for both API requests and media downloads
+    pub fn with_ip_family(mut self, ip_family: IpFamily) -> Self {
+        self.options.ip_family = ip_family;
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self.with_media_ip_family(ip_family)
+    }
 
-        // Check if we got muxed formats (itag 18, 22, etc.) - these are stable and don't get 403
-        let all_itags: Vec<u32> = formats.iter().map(|f| f.itag).collect();
-        debug!("All itags from ANDROID: {:?}", all_itags);
-        let has_muxed = formats.iter().any(|f| matches!(f.itag, 18 | 22 | 43 | 36));
-        debug!("has_muxed={}, will try IOS={}", has_muxed, !has_muxed);
-
-        // If only adaptive formats (itag 299+), try to get muxed from IOS client
-        let formats = if !has_muxed {
-            debug!("No muxed formats found (only adaptive), trying IOS client for itag 18/22");
-            // IOS client often returns muxed formats that ANDROID doesn't provide
-            let mut ios_inner_tube = InnerTubeClient::new().with_client("IOS", "19.29.1");
-
-            match ios_inner_tube.get_player_response(video_id).await {
-                Ok(ios_response) => match ios_response.parse_formats() {
-                    Ok(ios_formats) if !ios_formats.is_empty() => {
-                        let has_ios_muxed = ios_formats.iter().any(|f| matches!(f.itag, 18 | 22));
-                        if has_ios_muxed {
-                            debug!(
-                                "✅ IOS client returned {} formats with muxed (itag 18/22)",
-                                ios_formats.len()
-                            );
-                            ios_formats
-                        } else {
-                            debug!("IOS also has no muxed formats, using original ANDROID");
-                            formats
-                        }
-                    }
-                    _ => {
-                        debug!("IOS response parse failed or empty, using ANDROID");
-                        formats
-                    }
-                },
-                Err(e) => {
-                    warn!("IOS client request failed: {}, using ANDROID formats", e);
-                    formats
-                }
-            }
-        } else {
-            formats
-        };
+    /// Set address family for media downloads only
+    pub fn with_media_ip_family(mut self, ip_family: IpFamily) -> Self {
+        self.options.media_ip_family = ip_family;
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
 
-        // Strongly prefer muxed formats (itag 18/22) to avoid 403
-        let selected_format = formats
-            .iter()
-            .filter(|f| matches!(f.itag, 18 | 22))
-            .max_by_key(|f| f.height.unwrap_or(0))
-            .or_else(|| {
-                formats
-                    .iter()
-                    .filter(|f| matches!(f.itag, 43 | 36))
-                    .max_by_key(|f| f.height.unwrap_or(0))
-            })
-            .or_else(|| self.select_format(&formats).ok())
-            .ok_or_else(|| RytError::NoFormatFound)?;
-        debug!(
-            "Selected format: itag={}, quality={}, size={} (muxed={})",
-            selected_format.itag,
-            selected_format.quality,
-            selected_format.size.unwrap_or(0),
-            matches!(selected_format.itag, 18 | 22 | 43 | 36)
-        );
+    /// Set HTTP/2 use for API requests; media downloads stay on HTTP/1.1
+    pub fn with_api_http_version(mut self, http2: Http2Mode) -> Self {
+        self.options.api_http2 = http2;
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self
+    }
 
-        // Resolve final URL with signature deciphering
-        let mut final_url = if selected_format.needs_deciphering() {
-            debug!("Format requires deciphering, resolving cipher...");
-            let video_url = format!("https://www.youtube.com/watch?v={}", video_id);
-            self.resolve_format_url_with_cipher(selected_format, &video_url)
-                .await?
-        } else {
-            debug!("Format does not require deciphering");
-            selected_format.url.clone()
-        };
+    /// Set the compression API requests advertise; media downloads never
+    /// ask for compression
+    pub fn with_api_accept_encoding(mut self, accept_encoding: AcceptEncoding) -> Self {
+        self.options.api_accept_encoding = accept_encoding;
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self
+    }
 
-        // Normalize final_url for direct URL path as well (ratebypass, alr, n, drop rqh)
-        if let Ok(mut parsed) = url::Url::parse(&final_url) {
-            // If n present, try to decode and rewrite query pairs safely
-            if let Some(n_val) = parsed
-                .query_pairs()
-                .find(|(k, _)| k == "n")
-                .map(|(_, v)| v.to_string())
-            {
-                let cipher = crate::platform::cipher::Cipher::new();
-                if let Ok(n_out) = cipher
-                    .decipher_n_parameter(
-                        &n_val,
-                        &format!("https://www.youtube.com/watch?v={}", video_id),
-                    )
-                    .await
-                {
-                    // Collect pairs first (immutable borrow), then rebuild (mutable borrow)
-                    let pairs: Vec<(String, String)> = parsed
-                        .query_pairs()
-                        .map(|(k, v)| (k.into_owned(), v.into_owned()))
-                        .collect();
-                    let mut new_pairs: Vec<(String, String)> = Vec::with_capacity(pairs.len() + 1);
-                    let mut replaced = false;
-                    for (k, v) in pairs {
-                        if k == "n" {
-                            new_pairs.push((k, n_out.clone()));
-                            replaced = true;
-                        } else {
-                            new_pairs.push((k, v));
-                        }
-                    }
-                    if !replaced {
-                        new_pairs.push(("n".to_string(), n_out));
-                    }
-                    {
-                        let mut qp = parsed.query_pairs_mut();
-                        qp.clear()
-                            .extend_pairs(new_pairs.iter().map(|(k, v)| (k.as_str(), v.as_str())));
-                    }
-                }
-            }
-            // Add missing critical params (DO NOT remove rqh!)
-            let sparams_val = parsed
-                .query_pairs()
-                .find(|(k, _)| k == "sparams")
-                .map(|(_, v)| v.to_string());
-            let has_rqh = parsed.query_pairs().any(|(k, _)| k == "rqh");
-            let sparams_has_rqh = sparams_val.as_ref().map_or(false, |s| s.contains("rqh"));
-            let has_ratebypass = parsed.query_pairs().any(|(k, _)| k == "ratebypass");
-            let has_alr = parsed.query_pairs().any(|(k, _)| k == "alr");
+    /// Send cookies from a jar with API requests, e.g. from
+    /// [`extract_from_browser`](crate::utils::extract_from_browser)
+    pub fn with_cookie_jar(mut self, jar: Arc<Jar>) -> Self {
+        self.cookie_jar = Some(jar);
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self
+    }
 
-            debug!(
-                "Direct URL norm: has_rqh={}, sparams_has_rqh={}, adding={}",
-                has_rqh,
-                sparams_has_rqh,
-                sparams_has_rqh && !has_rqh
-            );
+    /// Connect to `host` at `addrs` instead of resolving it, for API and media requests
+    pub fn with_dns_override(mut self, host: &str, addrs: Vec<SocketAddr>) -> Self {
+        self.options.dns_overrides.insert(host.to_string(), addrs);
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
 
-            {
-                let mut qp = parsed.query_pairs_mut();
-                if !has_ratebypass {
-                    qp.append_pair("ratebypass", "yes");
-                }
-                if !has_alr {
-                    qp.append_pair("alr", "yes");
-                }
-                // CRITICAL FIX: Add rqh=1 if sparams lists it (required for itag=18)
-                if sparams_has_rqh && !has_rqh {
-                    qp.append_pair("rqh", "1");
-                    debug!(
-                        "✅ CRITICAL: Added rqh=1 to direct URL (itag={})",
-                        selected_format.itag
-                    );
-                }
-            }
-            let s: String = parsed.into();
-            final_url = s;
+    /// Log every request and response, showing up to `body_limit` bytes of request bodies
+    pub fn with_print_traffic(mut self, body_limit: usize) -> Self {
+        self.options.traffic_log = Some(TrafficLog::new(body_limit));
+        self.inner_tube = Arc::new(Mutex::new(self.build_inner_tube()));
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
+
+    /// Set maximum retries
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.options.max_retries = max_retries;
+        self
+    }
+
+    /// Set maximum retries per media fragment
+    pub fn with_fragment_retries(mut self, fragment_retries: u32) -> Self {
+        self.options.fragment_retries = fragment_retries;
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
+
+    /// Set how many segments of a segmented (HLS) download are fetched at
+    /// once, across all downloads of this downloader
+    pub fn with_concurrent_fragments(mut self, concurrent_fragments: usize) -> Self {
+        self.options.concurrent_fragments = concurrent_fragments;
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
+
+    /// Set write buffer size in bytes for streamed downloads
+    #[deprecated(note = "use `try_with_write_buffer`, which rejects a zero size")]
+    pub fn with_write_buffer(mut self, size: usize) -> Self {
+        self.options.write_buffer_size = size;
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
+
+    /// Set write buffer size in bytes, failing on a zero size
+    #[allow(deprecated)]
+    pub fn try_with_write_buffer(self, size: usize) -> Result<Self, RytError> {
+        check_write_buffer(size)?;
+        Ok(self.with_write_buffer(size))
+    }
+
+    /// Set download archive file; archived videos are skipped
+    pub fn with_download_archive(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.download_archive = Some(path.into());
+        self
+    }
+
+    /// Set download archive line format
+    pub fn with_archive_format(mut self, format: ArchiveFormat) -> Self {
+        self.options.archive_format = format;
+        self
+    }
+
+    /// Re-download archived videos recorded below this height
+    pub fn with_redownload_below(mut self, height: u32) -> Self {
+        self.options.redownload_below = Some(height);
+        self
+    }
+
+    /// Set session file for resumable playlist and batch jobs
+    pub fn with_session_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.session_file = Some(path.into());
+        self
+    }
+
+    /// Set whether failed session items are retried
+    pub fn with_retry_failed(mut self, retry_failed: bool) -> Self {
+        self.options.retry_failed = retry_failed;
+        self
+    }
+
+    /// Set playlist progress file; items downloaded in earlier runs are skipped
+    pub fn with_playlist_archive(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.playlist_archive = Some(path.into());
+        self
+    }
+
+    /// Set playlist and batch run controls
+    pub fn with_playlist_options(mut self, playlist: PlaylistOptions) -> Self {
+        self.playlist = playlist;
+        self
+    }
+
+    /// Set random wait between playlist and batch items
+    pub fn with_sleep_interval(mut self, interval: SleepInterval) -> Self {
+        self.playlist.sleep_interval = Some(interval);
+        self
+    }
+
+    /// Set number of successful downloads after which a run stops
+    pub fn with_max_downloads(mut self, max_downloads: usize) -> Self {
+        self.playlist.max_downloads = Some(max_downloads);
+        self
+    }
+
+    /// Set whether a run stops at the first archived or existing item
+    pub fn with_break_on_existing(mut self, break_on_existing: bool) -> Self {
+        self.playlist.break_on_existing = break_on_existing;
+        self
+    }
+
+    /// Set whether a run stops at the first failed item
+    pub fn with_playlist_abort_on_error(mut self, abort_on_error: bool) -> Self {
+        self.playlist.abort_on_error = abort_on_error;
+        self
+    }
+
+    /// Set whether playlist items are processed in reverse order
+    pub fn with_playlist_reverse(mut self, reverse: bool) -> Self {
+        self.playlist.reverse = reverse;
+        self
+    }
+
+    /// Set which playlist items are processed, e.g. `1,3,5-8,10-`
+    pub fn with_playlist_items(mut self, spec: PlaylistItemsSpec) -> Self {
+        self.playlist.items = Some(spec);
+        self
+    }
+
+    /// Get playlist and batch run controls
+    pub fn playlist_options(&self) -> &PlaylistOptions {
+        &self.playlist
+    }
+
+    /// Set whether the video thumbnail is written next to the downloaded file
+    pub fn with_write_thumbnail(mut self, write_thumbnail: bool) -> Self {
+        self.options.write_thumbnail = write_thumbnail;
+        self
+    }
+
+    /// Set format written thumbnails are converted to
+    pub fn with_thumbnail_format(mut self, format: ThumbnailFormat) -> Self {
+        self.options.thumbnail_format = Some(format);
+        self
+    }
+
+    /// Set whether the video description is written to a `.description` file
+    pub fn with_write_description(mut self, write_description: bool) -> Self {
+        self.options.write_description = write_description;
+        self
+    }
+
+    /// Set whether the video metadata is written to a `.info.json` file
+    pub fn with_write_infojson(mut self, write_infojson: bool) -> Self {
+        self.options.write_infojson = write_infojson;
+        self
+    }
+
+    /// Set whether playlist downloads also write the playlist metadata to
+    /// `<playlist_id>.info.json`
+    pub fn with_write_playlist_metafiles(mut self, write_playlist_metafiles: bool) -> Self {
+        self.options.write_playlist_metafiles = write_playlist_metafiles;
+        self
+    }
+
+    /// Set whether captions are written to a `.<lang>.vtt` file
+    pub fn with_write_subs(mut self, write_subs: bool) -> Self {
+        self.options.write_subs = write_subs;
+        self
+    }
+
+    /// Set whether the SHA-256 of the downloaded file is written to a
+    /// `.sha256` file
+    pub fn with_write_checksum(mut self, write_checksum: bool) -> Self {
+        self.options.write_checksum = write_checksum;
+        self
+    }
+
+    /// Set the language code of the captions to write
+    pub fn with_sub_lang(mut self, sub_lang: &str) -> Self {
+        self.options.sub_lang = sub_lang.to_string();
+        self
+    }
+
+    /// Also write a shortcut file of `kind` pointing to the video page
+    pub fn with_write_link(mut self, kind: LinkKind) -> Self {
+        if !self.options.write_links.contains(&kind) {
+            self.options.write_links.push(kind);
         }
+        self
+    }
 
-        // println!("🔗 Selected format: itag={}, quality={}, url={}", selected_format.itag, selected_format.quality, final_url);
-        // if let Some(sig_cipher) = &selected_format.signature_cipher {
-        //     println!("🔐 Signature cipher: {}", sig_cipher);
-        // }
+    /// Set whether the category and comment count are fetched with an extra request
+    pub fn with_fetch_extra_metadata(mut self, fetch: bool) -> Self {
+        self.options.fetch_extra_metadata = fetch;
+        self
+    }
 
-        // Create video info
-        let video_info = VideoInfo {
-            id: video_id.to_string(),
-            title: player_response
-                .video_details
-                .as_ref()
-                .map(|v| v.title.clone())
-                .unwrap_or_default(),
-            author: player_response
-                .video_details
-                .as_ref()
-                .map(|v| v.author.clone())
-                .unwrap_or_default(),
-            duration: player_response
-                .video_details
-                .as_ref()
-                .and_then(|v| v.length_seconds.parse().ok())
-                .unwrap_or(0),
-            description: player_response
-                .video_details
-                .as_ref()
-                .map(|v| v.short_description.clone())
-                .unwrap_or_default(),
-            formats,
-            thumbnail: player_response
-                .video_details
-                .as_ref()
-                .and_then(|v| v.thumbnail.thumbnails.first())
-                .map(|t| t.url.clone()),
-            upload_date: None,
-            view_count: None,
-            like_count: None,
-            tags: Vec::new(),
-            category: None,
-        };
+    /// Set filter videos must pass to be downloaded
+    pub fn with_match_filter(mut self, filter: MatchFilter) -> Self {
+        self.options.match_filter = Some(filter);
+        self
+    }
 
-        Ok((final_url, video_info))
+    /// Set directory for partial and intermediate files, expanding `~` and
+    /// environment variables
+    pub fn with_temp_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.temp_dir = Some(expand_path(&path.into()));
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
     }
 
-    /// Download video to file
-    pub async fn download(&mut self, video_url: &str) -> Result<VideoInfo, RytError> {
-        // Resolve URL and get metadata (first attempt)
-        let (mut final_url, mut video_info) = self.resolve_url(video_url).await?;
-        info!("Starting download for: {}", video_info.title);
+    /// Keep partial files of failed downloads (renamed to `.part`) instead of removing them
+    pub fn with_keep_temp(mut self, keep: bool) -> Self {
+        self.options.keep_temp_on_error = keep;
+        self.downloader = Arc::new(Mutex::new(self.build_chunked_downloader()));
+        self
+    }
 
-        // Determine output path
-        let output_path = self.determine_output_path(&video_info)?;
-        debug!("Output path: {:?}", output_path);
+    /// Set whether missing parent directories of the output path are created
+    pub fn with_create_dirs(mut self, create_dirs: bool) -> Self {
+        self.options.create_dirs = create_dirs;
+        self
+    }
 
-        // Try download with limited retries; on 403/RateLimited regenerate URL and retry
-        let max_attempts = 2u32;
-        for attempt in 1..=max_attempts {
-            let downloader = self.downloader.lock().await;
-            let result = downloader.download(&final_url, &output_path).await;
-            drop(downloader);
+    /// Set whether an existing file at the output path is kept and the
+    /// download named with the lowest free ` (n)` suffix instead
+    pub fn with_unique_filenames(mut self, unique_filenames: bool) -> Self {
+        self.options.unique_filenames = unique_filenames;
+        self
+    }
 
-            match result {
-                Ok(()) => {
-                    info!("Download completed successfully");
-                    // Update video info with output path
-                    video_info.title = output_path
-                        .file_stem()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("video")
-                        .to_string();
-                    return Ok(video_info);
-                }
-                Err(RytError::RateLimited) if attempt < max_attempts => {
-                    warn!("Rate limited/403 during media download (attempt {}/{}). Regenerating URL and retrying...", attempt, max_attempts);
-                    // Switch client strategy for error and regenerate URL
-                    {
-                        let mut inner = self.inner_tube.lock().await;
-                        inner.switch_client_for_error(&RytError::RateLimited);
-                    }
-                    // Resolve again to get fresh final_url
-                    let (new_url, _vi) = self.resolve_url(video_url).await?;
-                    final_url = new_url;
-                    continue;
-                }
-                Err(e) => return Err(e),
+    /// Clear player.js, signature and n-parameter caches
+    pub fn clear_caches(&self) {
+        self.cipher.clear_caches();
+    }
+
+    /// Get cache entry counts and hit/miss counters
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cipher.cache_stats()
+    }
+
+    /// Build the InnerTube client from current options
+    fn build_inner_tube(&self) -> InnerTubeClient {
+        InnerTubeClient::new()
+            .with_http_config(self.api_http_config())
+            .with_locale(
+                self.options.language.as_deref().unwrap_or_default(),
+                self.options
+                    .geo_bypass_country
+                    .as_deref()
+                    .unwrap_or_default(),
+            )
+    }
+
+    /// HTTP configuration for API and other small requests
+    fn api_http_config(&self) -> HttpClientConfig {
+        HttpClientConfig {
+            timeout: Some(self.options.timeout),
+            connect_timeout: self.options.connect_timeout,
+            ip_family: self.options.ip_family,
+            dns_overrides: self.options.dns_overrides.clone(),
+            traffic_log: self.options.traffic_log,
+            http2: self.options.api_http2,
+            accept_encoding: self.options.api_accept_encoding,
+            cookie_jar: self.cookie_jar.clone(),
+            ..HttpClientConfig::default()
+        }
+    }
+
+    /// Fork the shared media downloader with this downloader's progress callback.
+    ///
+    /// The lock is only held while forking, so forks download concurrently.
+    async fn media_downloader(&self) -> ChunkedDownloader {
+        let mut downloader = self.downloader.lock().await.fork();
+        if let Some(visitor_data) = self.inner_tube.lock().await.visitor_data() {
+            downloader = downloader.with_visitor_data(visitor_data);
+        }
+        match &self.progress_callback {
+            Some(callback) => {
+                let callback = Arc::clone(callback);
+                downloader.with_progress_callback(move |progress| callback(progress))
             }
+            None => downloader,
         }
+    }
 
-        // If we got here, all attempts failed
-        Err(RytError::Generic(
-            "Download failed after retries".to_string(),
-        ))
+    /// Build the media downloader from current options
+    /// Build a cipher with the configured execution timeout
+    fn build_cipher(&self) -> Cipher {
+        Cipher::new().with_cipher_timeout(self.options.cipher_timeout)
     }
 
-    /// Download playlist
-    pub async fn download_playlist(
-        &mut self,
+    fn build_chunked_downloader(&self) -> ChunkedDownloader {
+        let mut downloader = ChunkedDownloader::with_config(DownloaderConfig {
+            fragment_retries: self.options.fragment_retries,
+            concurrent_fragments: self.options.concurrent_fragments,
+            write_buffer_size: self.options.write_buffer_size,
+            global_rate_limit: self.options.global_rate_limit,
+            connect_timeout: self.options.connect_timeout,
+            read_timeout: self.options.read_timeout,
+            ip_family: self.options.media_ip_family,
+            keep_temp_on_error: self.options.keep_temp_on_error,
+            dns_overrides: self.options.dns_overrides.clone(),
+            traffic_log: self.options.traffic_log,
+            ..DownloaderConfig::default()
+        });
+        if let Some(bytes_per_second) = self.options.rate_limit_bps {
+            downloader = downloader.with_rate_limit(bytes_per_second);
+        }
+        if let Some(grace) = self.options.rate_limit_after {
+            downloader = downloader.with_rate_limit_after(grace);
+        }
+        if let Some(bytes_per_second) = self.options.throttled_rate {
+            downloader = downloader.with_throttled_rate(bytes_per_second);
+        }
+        if let Some(temp_dir) = &self.options.temp_dir {
+            downloader = downloader.with_temp_dir(temp_dir);
+        }
+        downloader
+    }
+
+    /// Resolve video URL and get metadata without downloading
+    pub async fn resolve_url(&self, video_url: &str) -> Result<(String, VideoInfo), RytError> {
+        let resolution = self.resolve(video_url).await?;
+        Ok((resolution.url, resolution.video_info))
+    }
+
+    /// Fetch metadata and plan the download without fetching any media
+    pub async fn fetch_info(&self, video_url: &str) -> Result<DownloadPlan, RytError> {
+        let resolution = self.resolve(video_url).await?;
+        let output_path = self.claim_output_path(&resolution.video_info)?;
+
+        Ok(DownloadPlan {
+            video_info: resolution.video_info,
+            format: resolution.format,
+            url: resolution.url,
+            output_path,
+        })
+    }
+
+    /// Get playlist items without downloading them, in processing order
+    pub async fn get_playlist_items(
+        &self,
         playlist_url: &str,
         limit: Option<usize>,
-    ) -> Result<Vec<VideoInfo>, RytError> {
-        // Extract playlist ID
+    ) -> Result<Vec<PlaylistItem>, RytError> {
         let playlist_id = crate::utils::url::extract_playlist_id(playlist_url)?;
-
-        // Get playlist items
         let items = {
             let mut inner_tube = self.inner_tube.lock().await;
-            inner_tube.get_playlist_items(&playlist_id, limit).await?
+            inner_tube
+                .get_playlist_items(&playlist_id, self.playlist.fetch_limit(limit))
+                .await?
         };
+        Ok(self.playlist.apply(items, limit))
+    }
 
-        // Download each video
-        let mut results = Vec::new();
-        for item in items {
-            let video_url = format!("https://www.youtube.com/watch?v={}", item.video_id);
-            match self.download(&video_url).await {
-                Ok(info) => results.push(info),
-                Err(e) => {
-                    eprintln!("Failed to download {}: {}", item.title, e);
-                    continue;
-                }
-            }
+    /// Get trending videos for a region without downloading them
+    pub async fn get_trending(
+        &self,
+        region: &str,
+        category: TrendingCategory,
+        limit: Option<usize>,
+    ) -> Result<Vec<SearchResult>, RytError> {
+        let mut inner_tube = self.inner_tube.lock().await;
+        let mut results = inner_tube.get_trending(region, category).await?;
+        if let Some(limit) = limit {
+            results.truncate(limit);
         }
-
         Ok(results)
     }
 
-    /// Select format based on selector
-    fn select_format<'a>(&self, formats: &'a [Format]) -> Result<&'a Format, RytError> {
-        let default_selector = FormatSelector::new(QualitySelector::Best);
-        let selector = self
-            .options
-            .format_selector
-            .as_ref()
-            .unwrap_or(&default_selector);
+    /// Get a playlist's title, video count and thumbnail
+    pub async fn get_playlist_metadata(
+        &self,
+        playlist_url: &str,
+    ) -> Result<PlaylistMetadata, RytError> {
+        let playlist_id = crate::utils::url::extract_playlist_id(playlist_url)?;
+        let mut inner_tube = self.inner_tube.lock().await;
+        inner_tube.get_playlist_metadata(&playlist_id).await
+    }
+
+    /// Get a video's captions in a language and format
+    pub async fn get_video_captions(
+        &self,
+        video_url: &str,
+        lang: &str,
+        format: CaptionFormat,
+    ) -> Result<String, RytError> {
+        let video_id = extract_video_id(video_url)?;
+        let mut inner_tube = self.inner_tube.lock().await;
+        inner_tube
+            .get_video_captions_as(&video_id, lang, format)
+            .await
+    }
 
-        let mut candidates: Vec<&Format> = formats.iter().collect();
+    /// Get the playlists listed on a channel
+    pub async fn get_channel_playlists(
+        &self,
+        channel_url: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<PlaylistMetadata>, RytError> {
+        let channel_id = extract_channel_id(channel_url)?;
+        let mut inner_tube = self.inner_tube.lock().await;
+        inner_tube.get_channel_playlists(&channel_id, limit).await
+    }
 
-        // Filter by extension
-        if let Some(ext) = &selector.extension {
-            candidates.retain(|f| f.mime_type.contains(ext));
+    /// Get the "Up Next" queue for a video, autoplay entries first
+    pub async fn get_watch_next(
+        &self,
+        video_url: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<SearchResult>, RytError> {
+        let video_id = extract_video_id(video_url)?;
+        let mut inner_tube = self.inner_tube.lock().await;
+        let mut results = inner_tube.get_watch_next(&video_id).await?;
+        if let Some(limit) = limit {
+            results.truncate(limit);
         }
+        Ok(results)
+    }
 
-        // Filter by height constraints
-        if let Some(height_limit) = selector.height_limit {
-            candidates.retain(|f| {
-                let height = f.height.unwrap_or(0);
-                height <= height_limit
-            });
+    /// Get videos related to a video, as listed next to it on the watch page
+    pub async fn get_related(
+        &self,
+        video_url: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<PlaylistItem>, RytError> {
+        let video_id = extract_video_id(video_url)?;
+        let response = self
+            .inner_tube
+            .lock()
+            .await
+            .get_next_response(&video_id)
+            .await?;
+        let mut related = response.related_videos();
+        if let Some(limit) = limit {
+            related.truncate(limit);
         }
+        Ok(related)
+    }
 
-        if let Some(height_min) = selector.height_min {
-            candidates.retain(|f| {
-                let height = f.height.unwrap_or(0);
-                height >= height_min
-            });
+    /// Fill in the category, comment and like count from the `next`
+    /// endpoint when enabled or the match filter needs the like count.
+    /// Failures only cost the extra fields.
+    async fn enrich_video_info(&self, video_info: &mut VideoInfo) {
+        let filter_needs_likes = self
+            .options
+            .match_filter
+            .as_ref()
+            .is_some_and(MatchFilter::uses_like_count);
+        if !self.options.fetch_extra_metadata && !filter_needs_likes {
+            return;
         }
 
-        // Select by quality
-        match &selector.quality {
-            QualitySelector::Best => {
-                candidates.sort_by(|a, b| b.bitrate.cmp(&a.bitrate));
-                candidates.first().copied()
+        let response = self
+            .inner_tube
+            .lock()
+            .await
+            .get_next_response(&video_info.id)
+            .await;
+        match response {
+            Ok(response) => {
+                video_info.category = video_info.category.take().or_else(|| response.category());
+                video_info.comment_count = response.comment_count();
+                video_info.like_count = response.like_count();
             }
-            QualitySelector::Worst => {
-                candidates.sort_by(|a, b| a.bitrate.cmp(&b.bitrate));
-                candidates.first().copied()
+            Err(e) => warn!(
+                "Could not fetch extra metadata for {}: {}",
+                video_info.id, e
+            ),
+        }
+    }
+
+    /// Resolve video URL, returning the final URL, metadata, selected format
+    /// and fallback candidates
+    async fn resolve(&self, video_url: &str) -> Result<Resolution, RytError> {
+        let mut resolution = self.resolve_player(video_url).await?;
+        self.enrich_video_info(&mut resolution.video_info).await;
+        Ok(resolution)
+    }
+
+    /// Resolve video URL from the player response alone
+    async fn resolve_player(&self, video_url: &str) -> Result<Resolution, RytError> {
+        // Extract video ID
+        let video_id = extract_video_id(video_url)?;
+        info!("Resolving URL for video ID: {}", video_id);
+        self.emit_status(Status::Resolving);
+
+        // Media URLs expire, so each resolution starts from fresh responses
+        let inner_tube = Arc::clone(&self.inner_tube);
+        let requests_before = {
+            let mut inner_tube = inner_tube.lock().await;
+            inner_tube.clear_player_cache();
+            inner_tube.player_request_count()
+        };
+
+        // Try each client in turn through the shared InnerTube client, so
+        // all of them use the same HTTP settings and visitor ID. Media URLs
+        // are tied to the client that obtained them (c= parameter), so the
+        // whole response comes from the first client that can play the video.
+        let chain = self.client_chain();
+        let id = &video_id;
+        let result = first_working_client(&chain, id, |name, version| {
+            let inner_tube = Arc::clone(&inner_tube);
+            let (name, version) = (name.to_string(), version.to_string());
+            async move {
+                inner_tube
+                    .lock()
+                    .await
+                    .get_player_response_as(id, (&name, &version))
+                    .await
+                    .and_then(|response| match response.parse_formats() {
+                        Ok(formats) if !formats.is_empty() => Ok(response),
+                        Ok(_) => Err(RytError::NoFormatFound),
+                        Err(e) => Err(e),
+                    })
             }
-            QualitySelector::Itag(target_itag) => {
-                candidates.iter().find(|f| f.itag == *target_itag).copied()
+        })
+        .await;
+        debug!(
+            "{} player request(s) for {}",
+            inner_tube.lock().await.player_request_count() - requests_before,
+            video_id
+        );
+        let last_error = match result {
+            Ok(player_response) => {
+                return self
+                    .process_player_response(player_response, &video_id)
+                    .await;
             }
-            QualitySelector::Height(target_height) => candidates
-                .iter()
-                .filter(|f| f.height.unwrap_or(0) == *target_height)
+            Err(e) => e,
+        };
+
+        // Last resort for age restrictions: the embed page sometimes still
+        // carries a playable player response
+        if matches!(last_error, RytError::AgeRestricted) {
+            let inner_tube = self.inner_tube.lock().await;
+            match inner_tube.get_embed_player_response(&video_id).await {
+                Ok(player_response) => {
+                    drop(inner_tube);
+                    info!("Using player response from the embed page");
+                    return self
+                        .process_player_response(player_response, &video_id)
+                        .await;
+                }
+                Err(e) => warn!("Embed page fallback failed: {}", e),
+            }
+        }
+
+        // If we get here, every client failed
+        Err(last_error)
+    }
+
+    /// Get the clients to resolve with: the configured client, then the
+    /// fallback chain without it
+    fn client_chain(&self) -> Vec<(String, String)> {
+        let primary = (
+            self.options.client_name.clone(),
+            self.options.client_version.clone(),
+        );
+        let fallbacks = self
+            .options
+            .client_fallback_chain
+            .iter()
+            .filter(|(name, _)| *name != primary.0)
+            .cloned();
+        std::iter::once(primary).chain(fallbacks).collect()
+    }
+
+    /// Process player response and extract video info
+    async fn process_player_response(
+        &self,
+        player_response: PlayerResponse,
+        video_id: &str,
+    ) -> Result<Resolution, RytError> {
+        // Fail early on responses without any video details
+        let details = player_response.details(video_id)?;
+
+        // Parse formats
+        let formats = player_response.parse_formats()?;
+        debug!("Found {} formats for video {}", formats.len(), video_id);
+
+        // Debug: print all formats
+        // println!("📋 Available formats ({}):", formats.len());
+        // for (i, format) in formats.iter().enumerate() {
+        //     println!("  {}: itag={}, quality={}, url_len={}, needs_deciphering={}",
+        //         i, format.itag, format.quality, format.url.len(), format.needs_deciphering());
+        //     if let Some(sig_cipher) = &format.signature_cipher {
+        //         println!("    signature_cipher: {}", sig_cipher);
+        //     }
+        // }
+
+        let all_itags: Vec<u32> = formats.iter().map(|f| f.itag.get()).collect();
+        debug!("All itags: {:?}", all_itags);
+
+        let duration = parse_length_seconds(&details.length_seconds);
+        let selected_format = self.select_preferred_format(&formats, duration)?;
+        debug!(
+            "Selected format: itag {}, quality={}, size={} (muxed={})",
+            selected_format.itag,
+            selected_format.quality_label(),
+            selected_format.size.unwrap_or(0),
+            matches!(selected_format.itag, Itag(18 | 22 | 43 | 36))
+        );
+
+        // Resolve final URL with signature and n-parameter deciphering
+        let video_url = format!("https://www.youtube.com/watch?v={}", video_id);
+        let cipher = self.cipher.clone();
+        let final_url = self
+            .resolve_media_url(&cipher, selected_format, &video_url)
+            .await?;
+
+        debug!(
+            "Selected format: itag={}, quality={}, url={}",
+            selected_format.itag,
+            selected_format.quality_label(),
+            loggable_url(&final_url)
+        );
+
+        let selected_format = selected_format.clone();
+        let candidates = self.rank_format_candidates(&formats, &selected_format, duration);
+        debug!(
+            "Fallback formats: {:?}",
+            candidates.iter().map(|f| f.itag.get()).collect::<Vec<_>>()
+        );
+        let live_status = player_response.live_status();
+
+        // Create video info
+        let video_info = VideoInfo {
+            id: video_id.to_string(),
+            title: details.title,
+            author: details.author,
+            duration,
+            description: details.short_description,
+            formats,
+            thumbnail: details.thumbnail.thumbnails.first().map(|t| t.url.clone()),
+            upload_date: None,
+            view_count: details.view_count.as_deref().and_then(|v| v.parse().ok()),
+            like_count: None,
+            comment_count: None,
+            tags: Vec::new(),
+            category: None,
+            is_live_content: details.is_live_content,
+            is_live: live_status.is_live,
+            was_live: live_status.was_live,
+            is_upcoming: live_status.is_upcoming,
+            selected_format: Some(selected_format.clone()),
+            visitor_data: player_response.visitor_data().map(VisitorData::new),
+            caption_tracks: player_response.caption_tracks().to_vec(),
+        };
+
+        Ok(Resolution {
+            url: final_url,
+            video_info,
+            format: selected_format,
+            candidates,
+        })
+    }
+
+    /// Install a global Prometheus metrics recorder
+    ///
+    /// Serve the returned handle with `utils::metrics::serve_metrics`.
+    #[cfg(feature = "metrics")]
+    pub fn install_metrics_recorder(
+    ) -> Result<metrics_exporter_prometheus::PrometheusHandle, RytError> {
+        metrics::install_recorder()
+    }
+
+    /// Download video to file
+    pub async fn download(&self, video_url: &str) -> Result<VideoInfo, RytError> {
+        let _operation = OperationGuard::begin(&self.lifecycle)?;
+        let started = Instant::now();
+        metrics::download_started();
+        let result = self.download_video(video_url).await;
+        metrics::download_finished(result.is_ok(), started.elapsed());
+        result
+    }
+
+    /// Download video to file, refreshing the media URL as needed
+    async fn download_video(&self, video_url: &str) -> Result<VideoInfo, RytError> {
+        // Skip videos already recorded in the download archive
+        let video_id = extract_video_id(video_url)?;
+        let archive = self.check_archive(&video_id)?;
+
+        // Resolve URL and get metadata (first attempt)
+        let Resolution {
+            url: mut final_url,
+            mut video_info,
+            mut format,
+            candidates,
+        } = self.resolve(video_url).await?;
+        let mut candidates = candidates.into_iter();
+        if let Some(filter) = &self.options.match_filter {
+            if !filter.matches(&video_info) {
+                info!(
+                    "Skipping {}: does not pass the match filter",
+                    video_info.title
+                );
+                return Err(RytError::FilteredOut(format!(
+                    "{} does not pass '{}'",
+                    video_info.title, filter
+                )));
+            }
+        }
+        info!("Starting download for: {}", video_info.title);
+
+        // Determine output path
+        let output_path = self.claim_output_path(&video_info)?;
+        debug!("Output path: {:?}", output_path);
+        self.prepare_output(&output_path, format.size).await?;
+
+        if video_info.is_upcoming {
+            warn!("Video is an upcoming live stream or premiere, it may not be downloadable yet");
+        } else if video_info.is_live {
+            warn!("Video is currently live, only the part streamed so far will be downloaded");
+        }
+
+        // Streams that are live now are downloaded from their HLS playlist,
+        // whose variants carry audio as well
+        let hls_playlist = if video_info.is_live {
+            self.hls_manifest_url(&video_id).await
+        } else {
+            None
+        };
+        if let Some(playlist_url) = &hls_playlist {
+            info!("Live stream detected, downloading HLS segments");
+            final_url = playlist_url.clone();
+        } else if video_info.is_live_content {
+            // Live stream VODs reject plain streaming GETs, so always use small sequential ranges
+            info!("Live content VOD detected, using ranged download");
+        }
+        let max_height = self
+            .options
+            .format_selector
+            .as_ref()
+            .and_then(|selector| selector.height_limit);
+
+        // With a separate audio stream, the video goes to a part file first
+        let merge_audio = if hls_playlist.is_some() {
+            None
+        } else {
+            self.merge_audio_selector()
+        };
+        let mut download_path = if merge_audio.is_some() {
+            stream_part_path(&output_path, &format)
+        } else {
+            output_path.clone()
+        };
+
+        // Try download with limited retries. On 403/RateLimited, first try
+        // other formats of the same response, then regenerate the URL with
+        // another client and retry
+        let max_attempts = 2u32;
+        let mut attempt = 1u32;
+        let mut url_refreshes = 0u32;
+        let mut attempted_formats: Vec<Itag> = Vec::new();
+        // Set once a throttled download left a partial file to resume
+        let mut resume = false;
+        self.emit_status(Status::Downloading);
+        loop {
+            let downloader = self.media_downloader().await;
+            let is_hls = hls_playlist.is_some();
+            let is_live_content = video_info.is_live_content;
+            let backend = self.transfer_backend.as_deref();
+            let transfer = |url: String| {
+                let (downloader, download_path) = (&downloader, &download_path);
+                async move {
+                    if is_hls {
+                        downloader
+                            .download_hls(&url, download_path, max_height)
+                            .await
+                    } else if let Some(backend) = backend {
+                        self.backend_transfer(backend, downloader, &url, download_path)
+                            .await
+                    } else if is_live_content {
+                        downloader
+                            .download_ranged(&url, download_path, LIVE_VOD_CHUNK_SIZE)
+                            .await
+                    } else if resume {
+                        downloader.download_with_resume(&url, download_path).await
+                    } else {
+                        downloader.download(&url, download_path).await
+                    }
+                }
+            };
+            let result = self
+                .transfer_with_host_fallback(&final_url, &download_path, transfer)
+                .await;
+
+            match result {
+                Ok(()) => {
+                    let output_path = match &merge_audio {
+                        Some(selector) => {
+                            self.merge_audio(
+                                video_url,
+                                &video_info,
+                                &format,
+                                &download_path,
+                                &output_path,
+                                selector,
+                            )
+                            .await?
+                        }
+                        None => output_path.clone(),
+                    };
+                    info!("Download completed successfully");
+                    if self.options.write_thumbnail {
+                        match self.download_thumbnail(&video_info, &output_path).await {
+                            Ok(path) => info!("Thumbnail written to {:?}", path),
+                            Err(e) => warn!("Failed to write thumbnail: {}", e),
+                        }
+                    }
+                    if self.options.write_description {
+                        match write_description(&video_info, &output_path).await {
+                            Ok(path) => info!("Description written to {:?}", path),
+                            Err(e) => warn!("Failed to write description: {}", e),
+                        }
+                    }
+                    if self.options.write_infojson {
+                        match write_info_json(&video_info, &output_path).await {
+                            Ok(path) => info!("Metadata written to {:?}", path),
+                            Err(e) => warn!("Failed to write metadata: {}", e),
+                        }
+                    }
+                    if self.options.write_subs {
+                        match self.write_subtitles(&video_info, &output_path).await {
+                            Ok(path) => info!("Captions written to {:?}", path),
+                            Err(e) => warn!("Failed to write captions: {}", e),
+                        }
+                    }
+                    if self.options.write_checksum {
+                        match write_checksum(&output_path).await {
+                            Ok(path) => info!("Checksum written to {:?}", path),
+                            Err(e) => warn!("Failed to write checksum: {}", e),
+                        }
+                    }
+                    for kind in &self.options.write_links {
+                        match write_link(&video_info, &output_path, *kind).await {
+                            Ok(path) => info!("Link written to {:?}", path),
+                            Err(e) => warn!("Failed to write link: {}", e),
+                        }
+                    }
+                    if let Some(callback) = &self.plan_callback {
+                        callback(&DownloadPlan {
+                            video_info: video_info.clone(),
+                            format: format.clone(),
+                            url: final_url.clone(),
+                            output_path: output_path.clone(),
+                        });
+                    }
+                    if let Some(mut archive) = archive {
+                        let entry = ArchiveEntry::new(video_id)
+                            .with_downloaded_at(chrono::Utc::now())
+                            .with_format(format.itag.into(), format.height);
+                        if let Err(e) = archive.record(entry) {
+                            warn!(
+                                "Failed to record download in archive {}: {}",
+                                archive.path().display(),
+                                e
+                            );
+                        }
+                    }
+                    // Update video info with output path
+                    video_info.title = output_path
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("video")
+                        .to_string();
+                    return Ok(video_info);
+                }
+                Err(RytError::NeedsUrlRefresh(offset)) if url_refreshes < MAX_URL_REFRESHES => {
+                    url_refreshes += 1;
+                    metrics::retry("url_refresh");
+                    warn!(
+                        "Media URL expired at byte {} (refresh {}/{}). Regenerating URL and resuming...",
+                        offset, url_refreshes, MAX_URL_REFRESHES
+                    );
+                    // Partial data is kept, so the next attempt resumes from the same offset
+                    final_url = self.resolve_player(video_url).await?.url;
+                    continue;
+                }
+                Err(RytError::Throttled(offset)) if url_refreshes < MAX_URL_REFRESHES => {
+                    url_refreshes += 1;
+                    metrics::retry("throttled");
+                    warn!(
+                        "Download throttled at byte {} (refresh {}/{}). Switching client and resuming...",
+                        offset, url_refreshes, MAX_URL_REFRESHES
+                    );
+                    final_url = self
+                        .refresh_throttled_url(video_url, &video_info.id, format.itag)
+                        .await?;
+                    resume = true;
+                    continue;
+                }
+                Err(RytError::RateLimited) => {
+                    // Sometimes only one itag is refused, so try the next
+                    // best format before asking for a new player response
+                    let next = if attempted_formats.len() < self.options.format_retries as usize {
+                        attempted_formats.push(format.itag);
+                        candidates.find(|f| !attempted_formats.contains(&f.itag))
+                    } else {
+                        None
+                    };
+                    if let Some(next) = next {
+                        metrics::retry("format_switch");
+                        warn!(
+                            "403 for itag {}, trying itag {} from the same response ({}/{})",
+                            format.itag,
+                            next.itag,
+                            attempted_formats.len(),
+                            self.options.format_retries
+                        );
+                        // The refused format's partial data can't be resumed by another one
+                        let partial = crate::utils::temp::partial_file_path(
+                            self.options.temp_dir.as_deref(),
+                            &download_path,
+                            &final_url,
+                        );
+                        let _ = tokio::fs::remove_file(&partial).await;
+
+                        let cipher = self.cipher.clone();
+                        final_url = self.resolve_media_url(&cipher, &next, video_url).await?;
+                        if merge_audio.is_some() {
+                            download_path = stream_part_path(&output_path, &next);
+                        }
+                        video_info.selected_format = Some(next.clone());
+                        format = next;
+                        continue;
+                    }
+
+                    if attempt >= max_attempts {
+                        return Err(RytError::RateLimited);
+                    }
+                    warn!("Rate limited/403 during media download (attempt {}/{}). Regenerating URL and retrying...", attempt, max_attempts);
+                    attempt += 1;
+                    metrics::retry("rate_limited");
+                    if !attempted_formats.is_empty() {
+                        info!(
+                            "Formats refused before switching clients: {}",
+                            attempted_formats
+                                .iter()
+                                .map(|itag| itag.get().to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                    }
+                    // Switch client strategy for error and regenerate URL
+                    {
+                        let mut inner = self.inner_tube.lock().await;
+                        inner.switch_client_for_error(&RytError::RateLimited);
+                    }
+                    // Resolve again to get a fresh final_url, for whichever
+                    // format the new response selects
+                    let resolution = self.resolve_player(video_url).await?;
+                    final_url = resolution.url;
+                    if merge_audio.is_some() {
+                        download_path = stream_part_path(&output_path, &resolution.format);
+                    }
+                    video_info.formats = resolution.video_info.formats;
+                    video_info.selected_format = Some(resolution.format.clone());
+                    format = resolution.format;
+                    candidates = resolution.candidates.into_iter();
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Download a specific format, e.g. one picked from the formats of
+    /// [`Downloader::fetch_info`].
+    ///
+    /// `output` overrides the configured output path. Without an explicit
+    /// output file, the filename is generated from the video title with the
+    /// format's own extension. An expired media URL is re-resolved for the
+    /// same itag. Returns the path the format was written to.
+    pub async fn download_format(
+        &self,
+        video_url: &str,
+        format: &Format,
+        output: Option<&Path>,
+    ) -> Result<PathBuf, RytError> {
+        let _operation = OperationGuard::begin(&self.lifecycle)?;
+        let started = Instant::now();
+        metrics::download_started();
+        let result = self.download_format_to(video_url, format, output).await;
+        metrics::download_finished(result.is_ok(), started.elapsed());
+        result
+    }
+
+    /// Get the HLS playlist of a live video from its cached player response
+    async fn hls_manifest_url(&self, video_id: &str) -> Option<String> {
+        self.inner_tube
+            .lock()
+            .await
+            .cached_player_response(video_id)
+            .and_then(PlayerResponse::hls_manifest_url)
+            .map(str::to_string)
+    }
+
+    /// Download a specific format, refreshing its media URL as needed
+    async fn download_format_to(
+        &self,
+        video_url: &str,
+        format: &Format,
+        output: Option<&Path>,
+    ) -> Result<PathBuf, RytError> {
+        let video_id = extract_video_id(video_url)?;
+        let target = output
+            .map(Path::to_path_buf)
+            .or_else(|| self.options.output_path.clone());
+        let output_path = match target.as_deref() {
+            Some(path) if !path.is_dir() && !has_trailing_separator(path) => path.to_path_buf(),
+            dir => {
+                // The filename needs the title, which the format doesn't
+                // carry; the player response is usually cached from resolving
+                let mut inner_tube = self.inner_tube.lock().await;
+                let player_response = match inner_tube.cached_player_response(&video_id).cloned() {
+                    Some(response) => response,
+                    None => inner_tube.get_player_response(&video_id).await?,
+                };
+                drop(inner_tube);
+                let details = player_response.details(&video_id)?;
+                let video_info = VideoInfo::new(video_id.clone(), details.title);
+                output_path_for(dir, &video_info, format.extension())
+            }
+        };
+        info!("Downloading itag {} to {:?}", format.itag, output_path);
+        self.prepare_output(&output_path, format.size).await?;
+
+        let cipher = self.cipher.clone();
+        let mut url = self.resolve_media_url(&cipher, format, video_url).await?;
+        let mut url_refreshes = 0u32;
+        let mut resume = false;
+        loop {
+            let downloader = self.media_downloader().await;
+            let backend = self.transfer_backend.as_deref();
+            let transfer = |url: String| {
+                let (downloader, output_path) = (&downloader, &output_path);
+                async move {
+                    if let Some(backend) = backend {
+                        self.backend_transfer(backend, downloader, &url, output_path)
+                            .await
+                    } else if resume {
+                        downloader.download_with_resume(&url, output_path).await
+                    } else {
+                        downloader.download(&url, output_path).await
+                    }
+                }
+            };
+            match self
+                .transfer_with_host_fallback(&url, &output_path, transfer)
+                .await
+            {
+                Ok(()) => return Ok(output_path),
+                Err(RytError::Throttled(offset)) if url_refreshes < MAX_URL_REFRESHES => {
+                    url_refreshes += 1;
+                    metrics::retry("throttled");
+                    warn!(
+                        "Download of itag {} throttled at byte {} (refresh {}/{}). Switching client and resuming...",
+                        format.itag, offset, url_refreshes, MAX_URL_REFRESHES
+                    );
+                    url = self
+                        .refresh_throttled_url(video_url, &video_id, format.itag)
+                        .await?;
+                    resume = true;
+                }
+                Err(e @ (RytError::NeedsUrlRefresh(_) | RytError::RateLimited))
+                    if url_refreshes < MAX_URL_REFRESHES =>
+                {
+                    url_refreshes += 1;
+                    metrics::retry("url_refresh");
+                    warn!(
+                        "{} (refresh {}/{}). Regenerating URL for itag {}...",
+                        e, url_refreshes, MAX_URL_REFRESHES, format.itag
+                    );
+                    url = self
+                        .refresh_format_url(video_url, &video_id, format.itag)
+                        .await?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Write the video's captions in `sub_lang` as WebVTT next to
+    /// `output_path`, returning the path written
+    async fn write_subtitles(
+        &self,
+        video_info: &VideoInfo,
+        output_path: &Path,
+    ) -> Result<PathBuf, RytError> {
+        let lang = &self.options.sub_lang;
+        let track = select_caption_track(&video_info.caption_tracks, lang)
+            .ok_or_else(|| RytError::NoCaptions(lang.clone()))?;
+        let vtt = self
+            .inner_tube
+            .lock()
+            .await
+            .get_caption_track(track, CaptionFormat::Vtt)
+            .await?;
+        let path = output_path.with_extension(format!("{}.vtt", lang));
+        write_atomic(&path, vtt)
+            .await
+            .map_err(|e| RytError::io(e, "write", &path))?;
+        Ok(path)
+    }
+
+    /// Download the thumbnail of a video next to `output_path`.
+    ///
+    /// The image keeps the extension it is served with, unless a format was
+    /// set with [`Downloader::with_thumbnail_format`] and it is converted.
+    /// Returns the path the thumbnail was written to.
+    pub async fn download_thumbnail(
+        &self,
+        video_info: &VideoInfo,
+        output_path: &Path,
+    ) -> Result<PathBuf, RytError> {
+        let url = video_info.thumbnail.as_deref().ok_or_else(|| {
+            RytError::Generic(format!("Video {} has no thumbnail", video_info.id))
+        })?;
+
+        let client = VideoClient::with_config(self.api_http_config());
+        let response = client
+            .send(client.create_simple_media_request(reqwest::Method::GET, url))
+            .await?
+            .error_for_status()?;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let path = output_path.with_extension(thumbnail_extension(url, content_type.as_deref()));
+        tokio::fs::write(&path, response.bytes().await?)
+            .await
+            .map_err(|e| RytError::io(e, "write", &path))?;
+
+        match self.options.thumbnail_format {
+            // Decoding and encoding images is CPU-bound, so keep it off the runtime
+            Some(format) => tokio::task::spawn_blocking(move || convert_thumbnail(&path, format))
+                .await
+                .map_err(|e| RytError::Generic(format!("Thumbnail conversion failed: {}", e)))?,
+            None => Ok(path),
+        }
+    }
+
+    /// Download the audio stream picked by `selector` and mux it with the
+    /// already downloaded video into the final file.
+    ///
+    /// The result is WebM when both streams are WebM and MP4 otherwise. Part
+    /// files are removed after a successful merge.
+    async fn merge_audio(
+        &self,
+        video_url: &str,
+        video_info: &VideoInfo,
+        video_format: &Format,
+        video_path: &Path,
+        output_path: &Path,
+        selector: &FormatSelector,
+    ) -> Result<PathBuf, RytError> {
+        let audio = crate::platform::select_format(&video_info.formats, selector)?;
+        let audio_path = stream_part_path(output_path, audio);
+        info!("Downloading audio itag {} for merging", audio.itag);
+        self.download_format_to(video_url, audio, Some(audio_path.as_path()))
+            .await?;
+
+        let merged_path = output_path.with_extension(mux_container(video_format, audio));
+        run_ffmpeg(
+            &mux_args(video_path, &audio_path, audio, &merged_path),
+            ProgressPhase::Merge,
+            Some(Duration::from_secs(video_info.duration.into())),
+            self.progress_callback.clone(),
+        )
+        .await?;
+
+        for part in [video_path, audio_path.as_path()] {
+            if let Err(e) = std::fs::remove_file(part) {
+                warn!("Failed to remove part file {}: {}", part.display(), e);
+            }
+        }
+        Ok(merged_path)
+    }
+
+    /// Resolve a fresh media URL for `itag` from a new player response
+    async fn refresh_format_url(
+        &self,
+        video_url: &str,
+        video_id: &str,
+        itag: Itag,
+    ) -> Result<String, RytError> {
+        let player_response = {
+            // A cached or settled response would carry the same stale URL
+            let mut inner = self.inner_tube.lock().await;
+            inner.clear_player_cache();
+            inner.get_player_response(video_id).await?
+        };
+        let formats = player_response.parse_formats()?;
+        let format = formats
+            .iter()
+            .find(|f| f.itag == itag)
+            .ok_or(RytError::NoFormatFound)?;
+
+        let cipher = self.cipher.clone();
+        self.resolve_media_url(&cipher, format, video_url).await
+    }
+
+    /// Resolve a fresh media URL for `itag` after a throttled download.
+    ///
+    /// Throttling usually means the URL's n-parameter wasn't deciphered or
+    /// the client is slowed down, so the client is switched and the player
+    /// response fetched and deciphered again.
+    async fn refresh_throttled_url(
+        &self,
+        video_url: &str,
+        video_id: &str,
+        itag: Itag,
+    ) -> Result<String, RytError> {
+        {
+            let mut inner = self.inner_tube.lock().await;
+            inner.switch_client_for_error(&RytError::Throttled(0));
+        }
+        self.refresh_format_url(video_url, video_id, itag).await
+    }
+
+    /// Create the output directory if configured and warn when the disk
+    /// can't hold `expected_size` bytes
+    async fn prepare_output(
+        &self,
+        output_path: &Path,
+        expected_size: Option<u64>,
+    ) -> Result<(), RytError> {
+        let parent = output_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty());
+        if let Some(parent) = parent.filter(|_| self.options.create_dirs) {
+            ensure_output_directory(parent).await?;
+        }
+        if let Some(expected_size) = expected_size {
+            let dir = parent.unwrap_or(Path::new("."));
+            // statvfs can stall on network mounts, so keep it off the runtime
+            let query_dir = dir.to_path_buf();
+            let available =
+                tokio::task::spawn_blocking(move || fs2::available_space(&query_dir)).await;
+            if let Ok(Ok(available)) = available {
+                if available < expected_size {
+                    warn!(
+                        "Only {} free in {} but the download needs about {}",
+                        format_bytes(available),
+                        dir.display(),
+                        format_bytes(expected_size)
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the download archive, failing with `AlreadyArchived` if the video is done
+    fn check_archive(&self, video_id: &str) -> Result<Option<DownloadArchive>, RytError> {
+        let Some(path) = &self.options.download_archive else {
+            return Ok(None);
+        };
+
+        let archive = DownloadArchive::load(path, self.options.archive_format)
+            .map_err(|e| RytError::io(e, "read archive", path))?;
+        if archive.is_done(video_id, self.options.redownload_below) {
+            info!("{} has already been recorded in the archive", video_id);
+            return Err(RytError::AlreadyArchived(video_id.to_string()));
+        }
+        if archive.get(video_id).is_some() {
+            info!(
+                "{} is archived below the requested height, downloading again",
+                video_id
+            );
+        }
+
+        Ok(Some(archive))
+    }
+
+    /// Download playlist
+    ///
+    /// With a session file configured, the playlist is resolved only once
+    /// and a later run resumes with the items not yet done. Otherwise, with
+    /// a playlist archive configured, items recorded in it by earlier runs
+    /// are skipped and each downloaded item is added to it. The run honours
+    /// the [`PlaylistOptions`] stop conditions and sleep interval. Failed
+    /// items are collected in the result rather than failing the run. A
+    /// video listed more than once is downloaded once.
+    pub async fn download_playlist(
+        &self,
+        playlist_url: &str,
+        limit: Option<usize>,
+    ) -> Result<PlaylistDownloadResult, RytError> {
+        self.download_playlist_deduped(playlist_url, limit, &mut HashSet::new())
+            .await
+    }
+
+    /// Download a playlist like [`Downloader::download_playlist`], skipping
+    /// the videos in `seen` and adding the ones it handles, so a video in
+    /// several inputs of a run is downloaded once
+    pub async fn download_playlist_deduped(
+        &self,
+        playlist_url: &str,
+        limit: Option<usize>,
+        seen: &mut HashSet<String>,
+    ) -> Result<PlaylistDownloadResult, RytError> {
+        self.playlist.validate()?;
+        if self.options.write_playlist_metafiles {
+            self.write_playlist_info_json(playlist_url).await;
+        }
+        if self.options.session_file.is_some() {
+            let mut session = match self.resume_session(playlist_url)? {
+                Some(session) => session,
+                None => {
+                    let items = self.get_playlist_items(playlist_url, limit).await?;
+                    let items = items
+                        .iter()
+                        .map(|item| self.plan_session_item(&item.video_id, &item.title))
+                        .collect();
+                    self.start_session(playlist_url, items)?
+                }
+            };
+            return self.download_session(&mut session).await;
+        }
+
+        // Get playlist items and download each video
+        let items = self.get_playlist_items(playlist_url, limit).await?;
+        let archive = self.load_playlist_archive()?;
+        Ok(self.download_items(items, archive, seen).await)
+    }
+
+    /// Download a batch of video URLs
+    ///
+    /// Like playlists, batches honour the [`PlaylistOptions`] stop conditions
+    /// and sleep interval. Failed items are collected in the result, titled
+    /// by their URL; lines that are not video URLs fail without stopping the
+    /// rest of the batch. A video given more than once, in any URL form, is
+    /// downloaded once.
+    pub async fn download_batch(
+        &self,
+        urls: &[String],
+    ) -> Result<PlaylistDownloadResult, RytError> {
+        self.playlist.validate()?;
+        let mut items = Vec::new();
+        let mut invalid = Vec::new();
+        for (url, index) in urls.iter().zip(1..) {
+            match extract_video_id(url) {
+                // The title is only known once the video is resolved
+                Ok(video_id) => items.push(PlaylistItem::new(video_id, String::new(), index)),
+                Err(error) => {
+                    warn!("Skipping {}: {}", url, error);
+                    let item = PlaylistItem::new(String::new(), url.clone(), index);
+                    invalid.push(PlaylistDownloadError { item, error });
+                }
+            }
+        }
+
+        let mut result = self.download_items(items, None, &mut HashSet::new()).await;
+        result.failed.extend(invalid);
+        result.failed.sort_by_key(|failure| failure.item.index);
+        Ok(result)
+    }
+
+    /// Write the playlist metadata to `<playlist_id>.info.json` in the output
+    /// directory, warning on failure
+    async fn write_playlist_info_json(&self, playlist_url: &str) {
+        let metadata = match self.get_playlist_metadata(playlist_url).await {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!("Failed to fetch playlist metadata: {}", e);
+                return;
+            }
+        };
+        let path = output_dir_for(self.options.output_path.as_deref())
+            .join(format!("{}.info.json", metadata.playlist_id));
+        match write_json(&path, &metadata.to_yt_dlp_json()).await {
+            Ok(()) => info!("Playlist metadata written to {:?}", path),
+            Err(e) => warn!("Failed to write playlist metadata: {}", e),
+        }
+    }
+
+    /// Load the configured playlist archive, if any
+    fn load_playlist_archive(&self) -> Result<Option<PlaylistArchive>, RytError> {
+        let Some(path) = &self.options.playlist_archive else {
+            return Ok(None);
+        };
+        let archive = PlaylistArchive::load(path)
+            .map_err(|e| RytError::io(e, "read playlist archive", path))?;
+        if !archive.is_empty() {
+            info!(
+                "Resuming from playlist archive {}: {} items already downloaded",
+                path.display(),
+                archive.len()
+            );
+        }
+        Ok(Some(archive))
+    }
+
+    /// Download items in order until a stop condition hits, skipping and
+    /// recording items in the playlist archive if there is one, and skipping
+    /// videos already in `seen`
+    async fn download_items(
+        &self,
+        items: Vec<PlaylistItem>,
+        mut archive: Option<PlaylistArchive>,
+        seen: &mut HashSet<String>,
+    ) -> PlaylistDownloadResult {
+        let mut result = PlaylistDownloadResult::default();
+        let mut last = None;
+        for mut item in items {
+            // Batch items are named by their URL until they are resolved
+            let title_known = !item.title.is_empty();
+            if !title_known {
+                item.title = item.url();
+            }
+            // Items done in an earlier run don't count as existing, so
+            // resuming isn't stopped by --break-on-existing
+            if archive
+                .as_ref()
+                .is_some_and(|archive| archive.contains(&item.video_id))
+            {
+                debug!("Skipping {}: already in the playlist archive", item.title);
+                continue;
+            }
+            if let Some(reason) = self.playlist.stop_reason(last, result.succeeded.len()) {
+                info!("Stopping: {}", reason);
+                break;
+            }
+            if !seen.insert(item.video_id.clone()) {
+                info!("Skipping {}: already handled in this run", item.title);
+                result.duplicates += 1;
+                continue;
+            }
+            // Playlist metadata often decides the filter without a resolve
+            let filtered_out = title_known
+                && self
+                    .options
+                    .match_filter
+                    .as_ref()
+                    .and_then(|filter| filter.matches_item(&item))
+                    == Some(false);
+            if filtered_out {
+                info!("Skipping {}: does not pass the match filter", item.title);
+                last = Some(ItemOutcome::Skipped);
+                continue;
+            }
+            if last.is_some() {
+                self.sleep_between_items().await;
+            }
+
+            let outcome = match self
+                .download_item(
+                    &item.url(),
+                    &item.video_id,
+                    title_known.then_some(&*item.title),
+                )
+                .await
+            {
+                Ok(Some(info)) => {
+                    if let Some(archive) = archive.as_mut() {
+                        if let Err(e) = archive.record(&item.video_id, &info.title) {
+                            warn!(
+                                "Failed to record {} in playlist archive {}: {}",
+                                item.video_id,
+                                archive.path().display(),
+                                e
+                            );
+                        }
+                    }
+                    result.succeeded.push(info);
+                    ItemOutcome::Downloaded
+                }
+                Ok(None) => ItemOutcome::Existing,
+                Err(RytError::AlreadyArchived(_)) => {
+                    info!("Skipping {}: already in the download archive", item.title);
+                    result.archived += 1;
+                    ItemOutcome::Existing
+                }
+                Err(RytError::FilteredOut(_)) => ItemOutcome::Skipped,
+                Err(RytError::MembersOnly) => {
+                    info!("Skipping {}: members-only video", item.title);
+                    result.members_only += 1;
+                    ItemOutcome::Skipped
+                }
+                Err(RytError::PaymentRequired { .. }) => {
+                    info!("Skipping {}: video requires payment", item.title);
+                    result.payment_required += 1;
+                    ItemOutcome::Skipped
+                }
+                Err(RytError::Cancelled) => {
+                    info!("Stopping: cancelled");
+                    break;
+                }
+                Err(error) => {
+                    warn!("Failed to download {}: {}", item.title, error);
+                    result.failed.push(PlaylistDownloadError { item, error });
+                    ItemOutcome::Failed
+                }
+            };
+            last = Some(outcome);
+        }
+
+        result
+    }
+
+    /// Download a playlist or batch item, returning `None` if it is skipped
+    /// because its output file exists.
+    ///
+    /// Without a known `title`, the video is resolved first so its output
+    /// path can be checked.
+    async fn download_item(
+        &self,
+        video_url: &str,
+        video_id: &str,
+        title: Option<&str>,
+    ) -> Result<Option<VideoInfo>, RytError> {
+        if self.playlist.break_on_existing {
+            let video_info = match title {
+                Some(title) => VideoInfo::new(video_id.to_string(), title.to_string()),
+                None => self.resolve_url(video_url).await?.1,
+            };
+            if let Ok(path) = self.determine_output_path(&video_info) {
+                if path.exists() {
+                    info!(
+                        "Skipping {}: {} already exists",
+                        video_info.title,
+                        path.display()
+                    );
+                    return Ok(None);
+                }
+            }
+        }
+
+        self.download(video_url).await.map(Some)
+    }
+
+    /// Wait the configured sleep interval before the next item
+    async fn sleep_between_items(&self) {
+        if let Some(duration) = self.playlist.sleep_duration() {
+            debug!("Sleeping {:?} before the next item", duration);
+            tokio::time::sleep(duration).await;
+        }
+    }
+
+    /// Load the configured session file if it exists for `input`
+    pub fn resume_session(&self, input: &str) -> Result<Option<Session>, RytError> {
+        let Some(path) = &self.options.session_file else {
+            return Ok(None);
+        };
+
+        let session = Session::resume(path, input)?;
+        if let Some(session) = &session {
+            info!(
+                "Resuming session {}: {} done, {} failed, {} pending",
+                path.display(),
+                session.count(ItemStatus::Done),
+                session.count(ItemStatus::Failed),
+                session.count(ItemStatus::Pending)
+            );
+        }
+        Ok(session)
+    }
+
+    /// Create and save a new session in the configured session file
+    pub fn start_session(&self, input: &str, items: Vec<SessionItem>) -> Result<Session, RytError> {
+        let path = self
+            .options
+            .session_file
+            .as_ref()
+            .ok_or_else(|| RytError::SessionError("no session file configured".to_string()))?;
+
+        let mut session = Session::new(path, input, items);
+        session.save()?;
+        Ok(session)
+    }
+
+    /// Create a session item with its planned output path
+    pub fn plan_session_item(&self, video_id: &str, title: &str) -> SessionItem {
+        let item = SessionItem::new(video_id, title);
+        let video_info = VideoInfo::new(video_id.to_string(), title.to_string());
+        match self.determine_output_path(&video_info) {
+            Ok(output_path) => item.with_output_path(output_path),
+            Err(_) => item,
+        }
+    }
+
+    /// Download the remaining items of a session, saving its state after each item
+    ///
+    /// Items left over when a stop condition hits stay pending for the next run.
+    pub async fn download_session(
+        &self,
+        session: &mut Session,
+    ) -> Result<PlaylistDownloadResult, RytError> {
+        self.playlist.validate()?;
+        let mut result = PlaylistDownloadResult::default();
+        let mut last = None;
+        for (index, item) in session
+            .pending(self.options.retry_failed)
+            .into_iter()
+            .enumerate()
+        {
+            if let Some(reason) = self.playlist.stop_reason(last, result.succeeded.len()) {
+                info!("Stopping: {}", reason);
+                break;
+            }
+            if index > 0 {
+                self.sleep_between_items().await;
+            }
+
+            // Items without a planned output path are resolved to check it
+            let title = item.output_path.is_some().then_some(item.title.as_str());
+            let outcome = match self.download_item(&item.url(), &item.video_id, title).await {
+                Ok(Some(info)) => {
+                    session.mark(&item.video_id, ItemStatus::Done, None)?;
+                    result.succeeded.push(info);
+                    ItemOutcome::Downloaded
+                }
+                Ok(None) => {
+                    session.mark(&item.video_id, ItemStatus::Done, None)?;
+                    ItemOutcome::Existing
+                }
+                Err(RytError::AlreadyArchived(_)) => {
+                    info!("Skipping {}: already in the download archive", item.title);
+                    session.mark(&item.video_id, ItemStatus::Done, None)?;
+                    result.archived += 1;
+                    ItemOutcome::Existing
+                }
+                Err(RytError::FilteredOut(_)) => {
+                    session.mark(&item.video_id, ItemStatus::Done, None)?;
+                    ItemOutcome::Skipped
+                }
+                Err(RytError::Cancelled) => {
+                    // The item stays pending for the next run
+                    info!("Stopping: cancelled");
+                    break;
+                }
+                Err(error) => {
+                    warn!("Failed to download {}: {}", item.title, error);
+                    session.mark(&item.video_id, ItemStatus::Failed, Some(error.to_string()))?;
+                    let item = PlaylistItem::new(item.video_id, item.title, index as u32 + 1);
+                    result.failed.push(PlaylistDownloadError { item, error });
+                    ItemOutcome::Failed
+                }
+            };
+            last = Some(outcome);
+        }
+
+        Ok(result)
+    }
+
+    /// Select a format, strongly preferring muxed formats (itag 18/22) to avoid 403
+    /// when the selector asks for plain `best`; other selectors are applied as is.
+    ///
+    /// A requested container is honoured strictly. When no muxed format is
+    /// available in it, the selector is applied to all formats, and only if
+    /// that fails too does selection fall back to a muxed format in another
+    /// container, with a warning either way.
+    ///
+    /// Formats outside the file size limits are skipped, judged by their
+    /// size or an estimate from the bitrate and `duration` in seconds. With
+    /// a strict format, the format selected without limits must fit instead.
+    fn select_preferred_format<'a>(
+        &self,
+        formats: &'a [Format],
+        duration: u32,
+    ) -> Result<&'a Format, RytError> {
+        let limits = SizeLimits::new(&self.options, duration);
+        if !self.options.strict_format || limits.is_unlimited() {
+            return self.select_preferred_format_within(formats, &limits);
+        }
+        let format = self.select_preferred_format_within(formats, &SizeLimits::none(duration))?;
+        if !limits.allows(format) {
+            return Err(limits.rejection(format.approx_filesize(duration)));
+        }
+        Ok(format)
+    }
+
+    /// Select a format like [`Downloader::select_preferred_format`], among
+    /// the formats within `limits`
+    fn select_preferred_format_within<'a>(
+        &self,
+        formats: &'a [Format],
+        limits: &SizeLimits,
+    ) -> Result<&'a Format, RytError> {
+        if self
+            .options
+            .format_selector
+            .as_ref()
+            .is_some_and(|selector| selector.quality == QualitySelector::DolbyAtmos)
+        {
+            // Dolby streams only come as M4A, so the container preference doesn't apply
+            let candidates: Vec<&Format> = formats.iter().filter(|f| limits.allows(f)).collect();
+            return select_dolby_atmos(&candidates);
+        }
+
+        // Merging a separate audio stream only makes sense with an adaptive video
+        if self.merge_audio_selector().is_some() {
+            return self.select_format(formats, limits);
+        }
+
+        // Only plain `best` may settle for a muxed format; any other quality
+        // is up to the selector
+        let default_selector = FormatSelector::new(QualitySelector::Best);
+        let selector = self
+            .options
+            .format_selector
+            .as_ref()
+            .unwrap_or(&default_selector);
+        if selector.quality != QualitySelector::Best {
+            return self.select_format(formats, limits);
+        }
+
+        let ext = selector.extension.as_deref().filter(|ext| !ext.is_empty());
+        // Muxed formats must still pass the other filters, e.g. `[fps>=30]`
+        let mut any_container = selector.clone();
+        any_container.extension = None;
+        let matching = filter_formats(formats, &any_container);
+        // Muxed formats are never 60 fps, so they only win without any
+        if self.options.prefer_60fps && matching.iter().any(|f| f.is_60fps()) {
+            return self.select_format(formats, limits);
+        }
+        let best_muxed = |itags: &[Itag], ext: Option<&str>| {
+            matching
+                .iter()
+                .copied()
+                .filter(|f| itags.contains(&f.itag))
+                .filter(|f| ext.map_or(true, |ext| f.matches_extension(ext)))
+                .filter(|f| limits.allows(f))
+                .max_by_key(|f| f.height.unwrap_or(0))
+        };
+        let (first, second) = if self.prefers_webm() {
+            ([Itag(43), Itag(36)], [Itag(18), Itag(22)])
+        } else {
+            ([Itag(18), Itag(22)], [Itag(43), Itag(36)])
+        };
+        let muxed =
+            |ext: Option<&str>| best_muxed(&first, ext).or_else(|| best_muxed(&second, ext));
+
+        if let Some(format) = muxed(ext) {
+            return Ok(format);
+        }
+
+        let Some(ext) = ext else {
+            return self.select_format(formats, limits);
+        };
+
+        let any_muxed = muxed(None);
+        if any_muxed.is_some() {
+            warn!(
+                "No muxed format available in the {} container, selecting from all {} formats",
+                ext, ext
+            );
+        }
+        match self.select_format(formats, limits) {
+            Ok(format) => Ok(format),
+            Err(e) => match any_muxed {
+                Some(format) => {
+                    warn!(
+                        "No format available in the {} container, falling back to itag {}",
+                        ext, format.itag
+                    );
+                    Ok(format)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Pick the format to try after the ones in `attempted` were refused.
+    ///
+    /// The candidates are the remaining formats of the same player response
+    /// in `format`'s container, so the output file keeps its extension; they
+    /// are ranked by the configured selector like the first choice.
+    fn next_format_candidate(
+        &self,
+        formats: &[Format],
+        format: &Format,
+        attempted: &[Itag],
+        duration: u32,
+    ) -> Option<Format> {
+        let remaining: Vec<Format> = formats
+            .iter()
+            .filter(|f| !attempted.contains(&f.itag) && f.extension() == format.extension())
+            .cloned()
+            .collect();
+        self.select_preferred_format(&remaining, duration)
+            .ok()
+            .cloned()
+    }
+
+    /// Rank the formats to try after `format` is refused, best first, up to
+    /// the configured number of format retries
+    fn rank_format_candidates(
+        &self,
+        formats: &[Format],
+        format: &Format,
+        duration: u32,
+    ) -> Vec<Format> {
+        let mut attempted = vec![format.itag];
+        let mut candidates = Vec::new();
+        while candidates.len() < self.options.format_retries as usize {
+            let Some(next) = self.next_format_candidate(formats, format, &attempted, duration)
+            else {
+                break;
+            };
+            attempted.push(next.itag);
+            candidates.push(next);
+        }
+        candidates
+    }
+
+    /// Select format based on selector, falling back per the configured
+    /// policy when it matches nothing
+    fn select_format<'a>(
+        &self,
+        formats: &'a [Format],
+        limits: &SizeLimits,
+    ) -> Result<&'a Format, RytError> {
+        let default_selector = FormatSelector::new(QualitySelector::Best);
+        let selector = self
+            .options
+            .format_selector
+            .as_ref()
+            .unwrap_or(&default_selector);
+
+        let (format, selection) = select_with_fallback(
+            formats,
+            selector,
+            self.options.format_fallback,
+            |selector| self.select_format_by(formats, selector, limits),
+        )?;
+        if selection != FormatSelection::Exact {
+            warn!("{}", selection);
+        }
+        Ok(format)
+    }
+
+    /// Select format with a given selector
+    fn select_format_by<'a>(
+        &self,
+        formats: &'a [Format],
+        selector: &FormatSelector,
+        limits: &SizeLimits,
+    ) -> Result<&'a Format, RytError> {
+        let mut candidates = filter_formats(formats, selector);
+
+        // Filter by file size, reporting the smallest format over the limit
+        // when nothing else is left
+        let too_large = candidates
+            .iter()
+            .filter_map(|f| f.approx_filesize(limits.duration))
+            .filter(|size| limits.max.is_some_and(|max| *size > max))
+            .min();
+        candidates.retain(|f| {
+            let allowed = limits.allows(f);
+            if !allowed {
+                debug!(
+                    "Rejected itag {}: {} is outside the file size limits",
+                    f.itag,
+                    limits.describe(f)
+                );
+            }
+            allowed
+        });
+        if candidates.is_empty() {
+            return Err(limits.rejection(too_large));
+        }
+
+        // Prefer WebM formats when there are any
+        if self.prefers_webm() && candidates.iter().any(|f| f.is_webm()) {
+            candidates.retain(|f| f.is_webm());
+        }
+
+        // Select by quality
+        match &selector.quality {
+            QualitySelector::Best if !self.options.prefer_60fps => {
+                best_format(candidates.iter().copied())
+            }
+            QualitySelector::Best | QualitySelector::Best60fps => {
+                best_60fps_format(candidates.iter().copied())
+            }
+            QualitySelector::Worst => {
+                candidates.sort_by(|a, b| a.bitrate.cmp(&b.bitrate));
+                candidates.first().copied()
+            }
+            QualitySelector::Itag(target_itag) => {
+                candidates.iter().find(|f| f.itag == *target_itag).copied()
+            }
+            QualitySelector::Height(target_height) => candidates
+                .iter()
+                .filter(|f| f.height.unwrap_or(0) == *target_height)
                 .max_by_key(|f| f.bitrate)
                 .copied(),
             QualitySelector::HeightLessOrEqual(target_height) => candidates
@@ -558,326 +2904,3652 @@ impl Downloader {
                 .copied(),
             QualitySelector::HeightGreaterOrEqual(target_height) => candidates
                 .iter()
-                .filter(|f| f.height.unwrap_or(0) >= *target_height)
-                .max_by_key(|f| f.bitrate)
-                .copied(),
+                .filter(|f| f.height.unwrap_or(0) >= *target_height)
+                .max_by_key(|f| f.bitrate)
+                .copied(),
+            QualitySelector::DolbyAtmos => return select_dolby_atmos(&candidates),
+            QualitySelector::BestVideo => best_video_stream(candidates.iter().copied()),
+            QualitySelector::BestAudio => best_audio_stream(candidates.iter().copied()),
+        }
+        .ok_or(RytError::NoFormatFound)
+    }
+
+    /// Check whether WebM formats are preferred, by option or selector
+    fn prefers_webm(&self) -> bool {
+        self.options.prefer_webm
+            || self
+                .options
+                .format_selector
+                .as_ref()
+                .is_some_and(|selector| selector.prefer_webm)
+    }
+
+    /// Get the selector of the audio stream to merge, if one was requested
+    fn merge_audio_selector(&self) -> Option<FormatSelector> {
+        let mut selector = self
+            .options
+            .format_selector
+            .as_ref()?
+            .audio
+            .as_deref()?
+            .clone();
+        selector.prefer_webm |= self.options.prefer_webm;
+        Some(selector)
+    }
+
+    /// Resolve the final, normalized media URL for a format
+    async fn resolve_media_url(
+        &self,
+        cipher: &Cipher,
+        format: &Format,
+        video_url: &str,
+    ) -> Result<String, RytError> {
+        let final_url = if format.needs_deciphering() {
+            debug!("Format requires deciphering, resolving cipher...");
+            self.emit_status(Status::Deciphering);
+            self.resolve_format_url_with_cipher(cipher, format, video_url)
+                .await?
+        } else {
+            debug!("Format does not require deciphering");
+            resolve_direct_url(cipher, &format.url, video_url).await
+        };
+
+        Ok(normalize_media_url(&final_url, format.itag))
+    }
+
+    /// Resolve format URL with signature deciphering
+    async fn resolve_format_url_with_cipher(
+        &self,
+        cipher: &Cipher,
+        format: &Format,
+        video_url: &str,
+    ) -> Result<String, RytError> {
+        // println!("🔧 Starting cipher resolution for format itag={}", format.itag);
+        let mut final_url = format.url.clone();
+
+        // Handle signature cipher
+        if let Some(sig_cipher) = &format.signature_cipher {
+            // println!("🔧 Parsing signature cipher: {}", sig_cipher);
+
+            // Parse signature cipher parameters
+            let sig_params: std::collections::HashMap<String, String> =
+                url::form_urlencoded::parse(sig_cipher.as_bytes())
+                    .into_owned()
+                    .collect();
+
+            // println!("🔧 Parsed parameters: {:?}", sig_params);
+
+            if let Some(base_url) = sig_params.get("url") {
+                final_url = base_url.clone();
+            }
+
+            if let Some(signature) = sig_params.get("s") {
+                debug!("Deciphering signature: {}", loggable_secret(signature));
+                let deciphered_sig = cipher.decipher_signature(signature, video_url).await?;
+                let param = sig_params.get("sp").map_or("sig", String::as_str);
+                final_url = apply_signature(&final_url, param, &deciphered_sig);
+                debug!(
+                    "URL with deciphered {}: {}",
+                    param,
+                    loggable_url(&final_url)
+                );
+            }
+
+            // Carry an n-parameter from the cipher over to the URL; decoded below
+            if let Some(n_param) = sig_params.get("n") {
+                if !has_query_param(&final_url, "n") {
+                    let separator = if final_url.contains('?') { '&' } else { '?' };
+                    final_url = format!("{}{}n={}", final_url, separator, n_param);
+                }
+            }
+        }
+
+        // Decode the n-parameter wherever it came from
+        final_url = cipher.apply_n_param(&final_url, video_url).await?;
+
+        // Normalize URL parameters similar to Go ytdlp:
+        // - ensure n is decoded if present (handled above already)
+        // - enforce ratebypass=yes
+        // - add alr=yes to encourage stable redirects
+        if let Ok(mut parsed) = url::Url::parse(&final_url) {
+            // Precompute existence flags before taking a mutable borrow
+            let has_ratebypass = parsed.query_pairs().any(|(k, _)| k == "ratebypass");
+            let has_alr = parsed.query_pairs().any(|(k, _)| k == "alr");
+
+            // Add missing params. If sparams contains rqh but rqh parameter is missing, add it
+            let sparams_val = parsed
+                .query_pairs()
+                .find(|(k, _)| k == "sparams")
+                .map(|(_, v)| v.to_string());
+            let has_rqh = parsed.query_pairs().any(|(k, _)| k == "rqh");
+            let sparams_has_rqh = sparams_val.as_ref().map_or(false, |s| s.contains("rqh"));
+
+            debug!(
+                "URL normalization: has_rqh={}, sparams_has_rqh={}, will add rqh={}",
+                has_rqh,
+                sparams_has_rqh,
+                sparams_has_rqh && !has_rqh
+            );
+
+            {
+                let mut qp = parsed.query_pairs_mut();
+                if !has_ratebypass {
+                    qp.append_pair("ratebypass", "yes");
+                    debug!("Added ratebypass=yes");
+                }
+                if !has_alr {
+                    qp.append_pair("alr", "yes");
+                    debug!("Added alr=yes");
+                }
+                // If sparams lists rqh but rqh param is missing, add rqh=1 (CRITICAL for itag 18)
+                if sparams_has_rqh && !has_rqh {
+                    qp.append_pair("rqh", "1");
+                    debug!("✅ Added rqh=1 (required by sparams)");
+                }
+            }
+
+            let s: String = parsed.into();
+            return Ok(s);
+        }
+
+        Ok(final_url)
+    }
+
+    /// Determine the output path a download is written to, moved to a free
+    /// ` (n)` name if unique filenames are on and the file exists.
+    ///
+    /// Only finished files count as taken; a partial `.tmp` file for the
+    /// plain name is resumed rather than avoided, and the partial files of a
+    /// suffixed name follow it, e.g. `Title (1).tmp`.
+    fn claim_output_path(&self, video_info: &VideoInfo) -> Result<PathBuf, RytError> {
+        let output_path = self.determine_output_path(video_info)?;
+        if !self.options.unique_filenames {
+            return Ok(output_path);
+        }
+        let unique = unique_path(&output_path)
+            .map_err(|e| RytError::io(e, "find a free name for", &output_path))?;
+        if unique != output_path {
+            info!(
+                "{} already exists, writing to {}",
+                output_path.display(),
+                unique.display()
+            );
+        }
+        Ok(unique)
+    }
+
+    /// Determine output path for downloaded file
+    fn determine_output_path(&self, video_info: &VideoInfo) -> Result<PathBuf, RytError> {
+        let ext = self.options.desired_ext.as_deref().unwrap_or("mp4");
+        Ok(output_path_for(
+            self.options.output_path.as_deref(),
+            video_info,
+            ext,
+        ))
+    }
+}
+
+/// Get the output file for a download.
+///
+/// A template such as `%(title)s [%(id)s].%(ext)s` is expanded, with the
+/// extension appended if it doesn't use `%(ext)s`. Any other file path is
+/// used as is; for a directory, or the current directory without an output
+/// path, the filename is generated from the title. A path ending with a
+/// separator is a directory even if it doesn't exist yet.
+fn output_path_for(output: Option<&Path>, video_info: &VideoInfo, ext: &str) -> PathBuf {
+    let title = video_info.filename_title();
+    match output {
+        Some(template) if template.to_string_lossy().contains("%(") => {
+            let template = template.to_string_lossy();
+            let mut path = render_template(&template, |name| match name {
+                "ext" => Some(ext.to_string()),
+                name => video_info.template_field(name),
+            });
+            if !template.contains("%(ext)") {
+                path = format!("{}.{}", path, ext);
+            }
+            PathBuf::from(path)
+        }
+        Some(path) if !path.is_dir() && !has_trailing_separator(path) => path.to_path_buf(),
+        Some(dir) => dir.join(to_safe_filename(title, ext)),
+        None => PathBuf::from(to_safe_filename(title, ext)),
+    }
+}
+
+/// Write the video description next to the output file as UTF-8 with LF
+/// newlines, returning the path written
+async fn write_description(
+    video_info: &VideoInfo,
+    output_path: &Path,
+) -> Result<PathBuf, RytError> {
+    let path = output_path.with_extension("description");
+    let description = video_info
+        .description
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+    write_atomic(&path, description)
+        .await
+        .map_err(|e| RytError::io(e, "write", &path))?;
+    Ok(path)
+}
+
+/// Write the video metadata as a yt-dlp style info dict next to the output
+/// file, returning the path written
+async fn write_info_json(video_info: &VideoInfo, output_path: &Path) -> Result<PathBuf, RytError> {
+    let path = output_path.with_extension("info.json");
+    write_json(&path, &video_info.to_yt_dlp_json()).await?;
+    Ok(path)
+}
+
+/// Write the SHA-256 of the output file in `sha256sum` format to a
+/// `.sha256` file next to it, returning the path written
+async fn write_checksum(output_path: &Path) -> Result<PathBuf, RytError> {
+    use tokio::io::AsyncReadExt;
+
+    let mut file = tokio::fs::File::open(output_path)
+        .await
+        .map_err(|e| RytError::io(e, "open", output_path))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| RytError::io(e, "read", output_path))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    let hex: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    let mut path = output_path.as_os_str().to_owned();
+    path.push(".sha256");
+    let path = PathBuf::from(path);
+    let name = output_path
+        .file_name()
+        .map_or("".into(), |name| name.to_string_lossy());
+    write_atomic(&path, format!("{}  {}\n", hex, name))
+        .await
+        .map_err(|e| RytError::io(e, "write", &path))?;
+    Ok(path)
+}
+
+/// Write pretty-printed JSON atomically
+async fn write_json(path: &Path, value: &serde_json::Value) -> Result<(), RytError> {
+    let json = serde_json::to_string_pretty(value)?;
+    write_atomic(path, json)
+        .await
+        .map_err(|e| RytError::io(e, "write", path))
+}
+
+/// Get the directory downloads are written to: the output path if it is a
+/// directory, else its parent unless that is part of a template
+fn output_dir_for(output: Option<&Path>) -> PathBuf {
+    match output {
+        Some(dir) if dir.is_dir() => dir.to_path_buf(),
+        Some(path) => path
+            .parent()
+            .filter(|parent| !parent.to_string_lossy().contains("%("))
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+        None => PathBuf::new(),
+    }
+}
+
+/// Write a shortcut to the video page next to the output file, returning
+/// the path written
+async fn write_link(
+    video_info: &VideoInfo,
+    output_path: &Path,
+    kind: LinkKind,
+) -> Result<PathBuf, RytError> {
+    let path = output_path.with_extension(kind.extension());
+    let content = kind.render(&video_info.webpage_url(), video_info.filename_title());
+    write_atomic(&path, content)
+        .await
+        .map_err(|e| RytError::io(e, "write", &path))?;
+    Ok(path)
+}
+
+/// Get the extension of a thumbnail from its content type, or its URL
+/// when the content type is missing or unknown
+fn thumbnail_extension(url: &str, content_type: Option<&str>) -> &'static str {
+    if let Some(ext) = content_type
+        .map(|content_type| ext_from_mime(base_mime_type(content_type)))
+        .filter(|ext| *ext != "bin")
+    {
+        return ext;
+    }
+
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    match path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()) {
+        Some(ext) if ext == "webp" => "webp",
+        Some(ext) if ext == "png" => "png",
+        _ => "jpg",
+    }
+}
+
+/// Get the part file path for a single stream of a merged download,
+/// e.g. `Title.f248.webm` for `Title.mp4`
+fn stream_part_path(output: &Path, format: &Format) -> PathBuf {
+    output.with_extension(format!("f{}.{}", format.itag.get(), format.extension()))
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decode the n-parameter of a direct URL, keeping the URL as is on failure
+async fn resolve_direct_url(cipher: &Cipher, url: &str, video_url: &str) -> String {
+    match cipher.apply_n_param(url, video_url).await {
+        Ok(url) => url,
+        Err(e) => {
+            warn!("Failed to decode n-parameter: {}", e);
+            url.to_string()
+        }
+    }
+}
+
+/// Try each client of `chain` in turn, returning the first success.
+///
+/// Errors another client may not hit move on to the next client; any other
+/// error, such as a copyright block, ends the search right away. Without
+/// success the last error is returned.
+async fn first_working_client<T, F, Fut>(
+    chain: &[(String, String)],
+    video_id: &str,
+    mut attempt: F,
+) -> Result<T, RytError>
+where
+    F: FnMut(&str, &str) -> Fut,
+    Fut: std::future::Future<Output = Result<T, RytError>>,
+{
+    let mut last_error = RytError::NoFormatFound;
+    for (name, version) in chain {
+        match attempt(name, version).await {
+            Ok(value) => {
+                info!("Resolved {} with the {} client", video_id, name);
+                return Ok(value);
+            }
+            Err(e) if is_client_specific(&e) => {
+                warn!("{} client failed for {}: {}", name, video_id, e);
+                last_error = e;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error)
+}
+
+/// Check whether another InnerTube client may succeed where one failed
+fn is_client_specific(error: &RytError) -> bool {
+    !error.is_permanent()
+        && matches!(
+            error,
+            RytError::AgeRestricted
+                | RytError::RateLimited
+                | RytError::BotguardError(_)
+                | RytError::RequestFailed(_)
+                | RytError::DownloadFailed(_)
+                | RytError::JsonError(_)
+                | RytError::MissingVideoDetails(_)
+                | RytError::MembersOnly
+                | RytError::PaymentRequired { .. }
+                | RytError::ConsentRequired(_)
+                | RytError::NoFormatFound
+        )
+}
+
+/// Check if a media download error may not happen on another CDN host:
+/// persistent 403s, timeouts and failed connections
+fn is_host_specific(error: &RytError) -> bool {
+    match error {
+        RytError::RateLimited | RytError::TimeoutError(_) => true,
+        RytError::DownloadFailed(e) => e.is_connect() || e.is_timeout(),
+        _ => false,
+    }
+}
+
+/// Get the host of a media URL for logging
+fn media_host(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|parsed| parsed.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Check if a URL has the given query parameter
+fn has_query_param(url: &str, name: &str) -> bool {
+    url::Url::parse(url).map_or(false, |parsed| parsed.query_pairs().any(|(k, _)| k == name))
+}
+
+/// Set a deciphered signature on a media URL under `param` (from `sp`).
+///
+/// Stale `sig`/`signature` pairs are dropped, while `lsig` stays as it
+/// signs other parameters. All other pairs keep their original encoding, so
+/// values such as `sparams=expire%2Cei` are not decoded twice.
+fn apply_signature(url: &str, param: &str, signature: &str) -> String {
+    let (base, query) = url.split_once('?').unwrap_or((url, ""));
+    let (query, fragment) = match query.split_once('#') {
+        Some((query, fragment)) => (query, Some(fragment)),
+        None => (query, None),
+    };
+
+    let mut pairs: Vec<String> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| {
+            let key = pair.split_once('=').map_or(*pair, |(key, _)| key);
+            key != "sig" && key != "signature" && key != param
+        })
+        .map(str::to_string)
+        .collect();
+    let encoded: String = url::form_urlencoded::byte_serialize(signature.as_bytes()).collect();
+    pairs.push(format!("{}={}", param, encoded));
+
+    let mut result = format!("{}?{}", base, pairs.join("&"));
+    if let Some(fragment) = fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+    result
+}
+
+/// Add parameters required for stable media downloads (ratebypass, alr, rqh)
+fn normalize_media_url(url: &str, itag: Itag) -> String {
+    let mut parsed = match url::Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return url.to_string(),
+    };
+
+    // Add missing critical params (DO NOT remove rqh!)
+    let sparams_val = parsed
+        .query_pairs()
+        .find(|(k, _)| k == "sparams")
+        .map(|(_, v)| v.to_string());
+    let has_rqh = parsed.query_pairs().any(|(k, _)| k == "rqh");
+    let sparams_has_rqh = sparams_val.as_ref().map_or(false, |s| s.contains("rqh"));
+    let has_ratebypass = parsed.query_pairs().any(|(k, _)| k == "ratebypass");
+    let has_alr = parsed.query_pairs().any(|(k, _)| k == "alr");
+
+    debug!(
+        "Direct URL norm: has_rqh={}, sparams_has_rqh={}, adding={}",
+        has_rqh,
+        sparams_has_rqh,
+        sparams_has_rqh && !has_rqh
+    );
+
+    {
+        let mut qp = parsed.query_pairs_mut();
+        if !has_ratebypass {
+            qp.append_pair("ratebypass", "yes");
+        }
+        if !has_alr {
+            qp.append_pair("alr", "yes");
+        }
+        // CRITICAL FIX: Add rqh=1 if sparams lists it (required for itag=18)
+        if sparams_has_rqh && !has_rqh {
+            qp.append_pair("rqh", "1");
+            debug!("✅ CRITICAL: Added rqh=1 to direct URL (itag={})", itag);
+        }
+    }
+
+    parsed.into()
+}
+
+#[cfg(test)]
+// The infallible builders are deprecated but still covered
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downloader_creation() {
+        let downloader = Downloader::new();
+        assert_eq!(downloader.options.client_name, "ANDROID");
+        assert_eq!(downloader.options.client_version, "20.10.38");
+    }
+
+    #[test]
+    fn test_downloader_with_format() {
+        let downloader = Downloader::new()
+            .with_format("best", "mp4")
+            .with_rate_limit(1024 * 1024); // 1MB/s
+
+        assert!(downloader.options.format_selector.is_some());
+        assert_eq!(downloader.options.rate_limit_bps, Some(1024 * 1024));
+    }
+
+    #[test]
+    fn test_downloader_with_botguard() {
+        let downloader = Downloader::new()
+            .with_botguard(crate::platform::botguard::BotguardMode::Auto)
+            .with_botguard_debug(true);
+
+        assert_eq!(
+            downloader.botguard.mode,
+            crate::platform::botguard::BotguardMode::Auto
+        );
+        assert!(downloader.botguard.debug);
+    }
+
+    #[test]
+    fn test_download_options_default() {
+        let options = DownloadOptions::default();
+        assert_eq!(options.client_name, "ANDROID");
+        assert_eq!(options.client_version, "20.10.38");
+        assert_eq!(options.client_fallback_chain.len(), 4);
+        assert_eq!(
+            options.client_fallback_chain[3],
+            ("WEB".to_string(), "2.20251002".to_string())
+        );
+        assert_eq!(options.timeout, Duration::from_secs(30));
+        assert_eq!(options.connect_timeout, Duration::from_secs(10));
+        assert_eq!(options.read_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(options.ip_family, IpFamily::Any);
+        assert_eq!(options.media_ip_family, IpFamily::Any);
+        assert_eq!(options.api_http2, Http2Mode::Auto);
+        assert_eq!(options.api_accept_encoding, AcceptEncoding::GzipBrotli);
+        assert!(options.dns_overrides.is_empty());
+        assert!(options.traffic_log.is_none());
+        assert_eq!(options.max_retries, 3);
+        assert_eq!(options.fragment_retries, 10);
+        assert_eq!(options.concurrent_fragments, 1);
+        assert!(options.temp_dir.is_none());
+        assert!(!options.keep_temp_on_error);
+        assert!(options.create_dirs);
+        assert!(!options.unique_filenames);
+        assert_eq!(options.write_buffer_size, DEFAULT_WRITE_BUFFER_SIZE);
+        assert!(options.download_archive.is_none());
+        assert_eq!(options.archive_format, ArchiveFormat::Plain);
+        assert!(options.redownload_below.is_none());
+        assert!(options.session_file.is_none());
+        assert!(options.playlist_archive.is_none());
+        assert!(!options.retry_failed);
+        assert!(!options.prefer_webm);
+        assert!(!options.prefer_60fps);
+        assert!(!options.write_thumbnail);
+        assert!(options.thumbnail_format.is_none());
+        assert!(!options.write_description);
+        assert!(!options.write_infojson);
+        assert!(!options.write_playlist_metafiles);
+        assert!(!options.write_subs);
+        assert_eq!(options.sub_lang, DEFAULT_SUB_LANG);
+        assert!(options.write_links.is_empty());
+        assert!(!options.fetch_extra_metadata);
+        assert_eq!(options.format_fallback, FormatFallback::Error);
+        assert_eq!(options.format_retries, DEFAULT_FORMAT_RETRIES);
+        assert_eq!(options.max_filesize, None);
+        assert_eq!(options.min_filesize, None);
+        assert!(!options.reject_unknown_size);
+        assert!(!options.strict_format);
+        assert!(options.match_filter.is_none());
+        assert!(options.geo_bypass_country.is_none());
+        assert!(options.language.is_none());
+        assert!(options.format_selector.is_none());
+        assert!(options.desired_ext.is_none());
+        assert!(options.output_path.is_none());
+        assert!(options.rate_limit_bps.is_none());
+        assert!(options.rate_limit_after.is_none());
+        assert!(!options.global_rate_limit);
+        assert!(options.throttled_rate.is_none());
+    }
+
+    #[test]
+    fn test_botguard_config_default() {
+        let config = BotguardConfig::default();
+        assert_eq!(config.mode, crate::platform::botguard::BotguardMode::Off);
+        assert!(!config.debug);
+        assert_eq!(config.ttl, Duration::from_secs(1800));
+    }
+
+    #[test]
+    fn test_downloader_default() {
+        let downloader = Downloader::default();
+        assert_eq!(downloader.options.client_name, "ANDROID");
+        assert_eq!(downloader.options.client_version, "20.10.38");
+    }
+
+    #[test]
+    fn test_downloader_with_output_path() {
+        let downloader = Downloader::new().with_output_path("/tmp/test.mp4");
+        assert!(downloader.options.output_path.is_some());
+        assert_eq!(
+            downloader.options.output_path.unwrap(),
+            PathBuf::from("/tmp/test.mp4")
+        );
+    }
+
+    #[test]
+    fn test_output_path_for() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("video.mkv");
+        let info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Title".to_string());
+        assert_eq!(output_path_for(Some(&file), &info, "mp4"), file);
+        assert_eq!(
+            output_path_for(Some(dir.path()), &info, "m4a"),
+            dir.path().join("Title.m4a")
+        );
+        assert_eq!(
+            output_path_for(None, &info, "webm"),
+            PathBuf::from("Title.webm")
+        );
+        // A missing directory is recognized by its trailing separator
+        let missing = dir.path().join("youtube").join("2025").join("");
+        assert_eq!(
+            output_path_for(Some(&missing), &info, "mp4"),
+            dir.path().join("youtube").join("2025").join("Title.mp4")
+        );
+    }
+
+    #[test]
+    fn test_output_path_for_template() {
+        let mut info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Title: Part 1/2".to_string());
+        info.author = "Channel".to_string();
+        info.description = "Line one\r\nLine two".to_string();
+
+        let template = Path::new("%(uploader)s/%(title)s [%(id)s].%(ext)s");
+        assert_eq!(
+            output_path_for(Some(template), &info, "webm"),
+            PathBuf::from("Channel/Title_ Part 1_2 [dQw4w9WgXcQ].webm")
+        );
+        // Without %(ext)s the extension is appended
+        let template = Path::new("%(id)s - %(description).8s");
+        assert_eq!(
+            output_path_for(Some(template), &info, "mp4"),
+            PathBuf::from("dQw4w9WgXcQ - Line one.mp4")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_description() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("Title.mp4");
+        let mut info = VideoInfo::new("id".to_string(), "Title".to_string());
+        info.description = "Ünïcödé\r\nsecond\rthird\n".to_string();
+
+        let path = write_description(&info, &output).await.unwrap();
+        assert_eq!(path, dir.path().join("Title.description"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "Ünïcödé\nsecond\nthird\n"
+        );
+        assert!(!dir.path().join("Title.description.tmp").exists());
+
+        // Long descriptions are written in full
+        info.description = "x".repeat(200 * 1024);
+        write_description(&info, &output).await.unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 200 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_write_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("Title.mp4");
+        std::fs::write(&output, b"abc").unwrap();
+
+        let path = write_checksum(&output).await.unwrap();
+        assert_eq!(path, dir.path().join("Title.mp4.sha256"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  Title.mp4\n"
+        );
+
+        assert!(matches!(
+            write_checksum(&dir.path().join("missing.mp4")).await,
+            Err(RytError::Io { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_write_info_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("Title.mp4");
+        let mut info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Title".to_string());
+        info.duration = 212;
+
+        let path = write_info_json(&info, &output).await.unwrap();
+        assert_eq!(path, dir.path().join("Title.info.json"));
+        assert!(!dir.path().join("Title.info.json.tmp").exists());
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            content.contains("\n  \"id\": \"dQw4w9WgXcQ\""),
+            "{}",
+            content
+        );
+        let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(json, info.to_yt_dlp_json());
+    }
+
+    #[test]
+    fn test_output_dir_for() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(output_dir_for(Some(dir.path())), dir.path());
+        assert_eq!(
+            output_dir_for(Some(Path::new("videos/clip.mp4"))),
+            PathBuf::from("videos")
+        );
+        assert_eq!(
+            output_dir_for(Some(Path::new("%(uploader)s/%(title)s.%(ext)s"))),
+            PathBuf::new()
+        );
+        assert_eq!(output_dir_for(Some(Path::new("clip.mp4"))), PathBuf::new());
+        assert_eq!(output_dir_for(None), PathBuf::new());
+    }
+
+    #[tokio::test]
+    async fn test_write_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("Title.mp4");
+        let info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Title".to_string());
+
+        let path = write_link(&info, &output, LinkKind::Url).await.unwrap();
+        assert_eq!(path, dir.path().join("Title.url"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[InternetShortcut]\r\nURL=https://www.youtube.com/watch?v=dQw4w9WgXcQ\r\n"
+        );
+
+        let path = write_link(&info, &output, LinkKind::Desktop).await.unwrap();
+        assert_eq!(path, dir.path().join("Title.desktop"));
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("Name=Title\n"));
+        assert!(content.contains("URL=https://www.youtube.com/watch?v=dQw4w9WgXcQ\n"));
+
+        // Kinds are written once each
+        let downloader = Downloader::new()
+            .with_write_link(LinkKind::Webloc)
+            .with_write_link(LinkKind::Webloc);
+        assert_eq!(downloader.options.write_links, vec![LinkKind::Webloc]);
+    }
+
+    #[test]
+    fn test_thumbnail_extension() {
+        let url = "https://i.ytimg.com/vi_webp/abc/maxresdefault.webp";
+        assert_eq!(thumbnail_extension(url, Some("image/jpeg")), "jpg");
+        assert_eq!(thumbnail_extension(url, Some("image/webp; q=1")), "webp");
+        assert_eq!(thumbnail_extension(url, None), "webp");
+        assert_eq!(
+            thumbnail_extension(url, Some("application/octet-stream")),
+            "webp"
+        );
+        assert_eq!(
+            thumbnail_extension("https://i.ytimg.com/vi/abc/hq.png?sqp=x.webp", None),
+            "png"
+        );
+        assert_eq!(
+            thumbnail_extension("https://i.ytimg.com/vi/abc/default", None),
+            "jpg"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_thumbnail_converts_webp() {
+        let mut server = mockito::Server::new_async().await;
+        let thumbnail = server
+            .mock("GET", "/vi_webp/abc/maxresdefault.webp")
+            .with_status(200)
+            .with_header("content-type", "image/webp")
+            .with_body(include_bytes!("../utils/testdata/thumbnail.webp"))
+            .expect(2)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut video_info = VideoInfo::new("abc".to_string(), "Title".to_string());
+        video_info.thumbnail = Some(format!("{}/vi_webp/abc/maxresdefault.webp", server.url()));
+        let output = dir.path().join("Title.mp4");
+
+        let downloader = Downloader::new();
+        let path = downloader
+            .download_thumbnail(&video_info, &output)
+            .await
+            .unwrap();
+        assert_eq!(path, dir.path().join("Title.webp"));
+
+        let downloader = Downloader::new().with_thumbnail_format(ThumbnailFormat::Jpg);
+        let path = downloader
+            .download_thumbnail(&video_info, &output)
+            .await
+            .unwrap();
+        assert_eq!(path, dir.path().join("Title.jpg"));
+        assert!(!dir.path().join("Title.webp").exists());
+        let decoded = image::load_from_memory(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (1, 1));
+        thumbnail.assert_async().await;
+
+        video_info.thumbnail = None;
+        assert!(downloader
+            .download_thumbnail(&video_info, &output)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_stream_part_path() {
+        let video = container_test_format(248, "video/webm; codecs=\"vp9\"", 1080);
+        let audio = container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0);
+        let output = Path::new("out/Title.mp4");
+        assert_eq!(
+            stream_part_path(output, &video),
+            PathBuf::from("out/Title.f248.webm")
+        );
+        assert_eq!(
+            stream_part_path(output, &audio),
+            PathBuf::from("out/Title.f140.m4a")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_format_with_explicit_format() {
+        let mut server = mockito::Server::new_async().await;
+        let media = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::UrlEncoded("itag".into(), "140".into()))
+            .with_status(200)
+            .with_body("audio data")
+            .create_async()
+            .await;
+
+        let mut format = container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0);
+        format.url = format!("{}/videoplayback?itag=140", server.url());
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("audio.m4a");
+        let downloader = Downloader::new().with_output_path(dir.path().join("ignored.mp4"));
+        let path = downloader
+            .download_format("https://youtu.be/dQw4w9WgXcQ", &format, Some(&output))
+            .await
+            .unwrap();
+
+        media.assert_async().await;
+        assert_eq!(path, output);
+        assert_eq!(std::fs::read(&output).unwrap(), b"audio data");
+        assert!(!dir.path().join("ignored.mp4").exists());
+    }
+
+    #[test]
+    fn test_downloader_with_progress() {
+        let downloader = Downloader::new().with_progress(|_progress| {
+            // Test callback
+        });
+        // Test that with_progress doesn't panic and returns self
+        assert_eq!(downloader.options.client_name, "ANDROID");
+    }
+
+    fn container_test_format(itag: u32, mime_type: &str, height: u32) -> Format {
+        let mut format = Format::new(
+            itag,
+            format!("https://example.com/{}", itag),
+            format!("{}p", height),
+            mime_type.to_string(),
+        );
+        format.height = Some(height);
+        format.bitrate = height * 1000;
+        format
+    }
+
+    #[test]
+    fn test_select_preferred_format_by_container() {
+        let formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(43, "video/webm; codecs=\"vp8.0, vorbis\"", 360),
+            container_test_format(248, "video/webm; codecs=\"vp9\"", 1080),
+            container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0),
+        ];
+
+        let downloader = Downloader::new();
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(18)
+        );
+
+        let downloader = Downloader::new().with_format("best", "mp4");
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(18)
+        );
+
+        let downloader = Downloader::new().with_format("best", "webm");
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(43)
+        );
+    }
+
+    #[test]
+    fn test_select_preferred_format_container_fallback() {
+        let formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(248, "video/webm; codecs=\"vp9\"", 1080),
+            container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0),
+        ];
+
+        // No muxed webm: the selector is applied to all webm formats
+        let downloader = Downloader::new().with_format("best", "webm");
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(248)
+        );
+
+        // audio/mp4 never satisfies mp4, so fall back to the muxed webm
+        let formats = vec![
+            container_test_format(43, "video/webm; codecs=\"vp8.0, vorbis\"", 360),
+            container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0),
+        ];
+        let downloader = Downloader::new().with_format("best", "mp4");
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(43)
+        );
+
+        let formats = vec![container_test_format(
+            140,
+            "audio/mp4; codecs=\"mp4a.40.2\"",
+            0,
+        )];
+        assert!(matches!(
+            downloader.select_preferred_format(&formats, 0),
+            Err(RytError::NoFormatFound)
+        ));
+    }
+
+    #[test]
+    fn test_select_preferred_format_fallback() {
+        let formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(136, "video/mp4; codecs=\"avc1.4d401f\"", 720),
+            container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0),
+        ];
+
+        let downloader = Downloader::new().with_format("bestvideo[height=1080]+bestaudio", "");
+        assert!(matches!(
+            downloader.select_preferred_format(&formats, 0),
+            Err(RytError::NoFormatFound)
+        ));
+        let downloader = downloader.with_format_fallback(FormatFallback::ClosestLower);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(136)
+        );
+    }
+
+    #[test]
+    fn test_select_preferred_format_height_fallback() {
+        let formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(135, "video/mp4; codecs=\"avc1.4d401e\"", 480),
+            container_test_format(22, "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"", 720),
+        ];
+
+        // A missing height is reported, not silently replaced by a muxed format
+        let downloader = Downloader::new().with_format("1080p", "");
+        assert!(matches!(
+            downloader.select_preferred_format(&formats, 0),
+            Err(RytError::NoFormatFound)
+        ));
+        let downloader = downloader.with_format_fallback(FormatFallback::ClosestLower);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(22)
+        );
+
+        let downloader = Downloader::new()
+            .with_format("480p", "")
+            .with_format_fallback(FormatFallback::ClosestLower);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(135)
+        );
+    }
+
+    #[test]
+    fn test_select_preferred_format_filters() {
+        let mut formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(22, "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"", 720),
+            container_test_format(248, "video/webm; codecs=\"vp9\"", 1080),
+            container_test_format(303, "video/webm; codecs=\"vp9\"", 1080),
+            container_test_format(251, "audio/webm; codecs=\"opus\"", 0),
+        ];
+        for format in &mut formats[..3] {
+            format.fps = Some(30);
+        }
+        formats[3].fps = Some(60);
+        let select = |format: &str| {
+            Downloader::new()
+                .with_format(format, "")
+                .select_preferred_format(&formats, 0)
+                .map(|format| format.itag)
+        };
+
+        // Plain best still settles for a muxed format
+        assert_eq!(select("best").unwrap(), Itag(22));
+        assert_eq!(select("best[height<=480]").unwrap(), Itag(18));
+        assert_eq!(select("best[acodec=mp4a]").unwrap(), Itag(22));
+        // Filters the muxed formats fail are applied to every format
+        assert_eq!(select("best[vcodec=vp9]").unwrap(), Itag(303));
+        assert_eq!(select("best[fps>=60]").unwrap(), Itag(303));
+        assert_eq!(select("best[vcodec=vp9][fps<=30]").unwrap(), Itag(248));
+        assert_eq!(select("best[acodec=opus]").unwrap(), Itag(251));
+        // Other qualities never take the muxed shortcut
+        assert_eq!(select("bestvideo").unwrap(), Itag(303));
+        assert_eq!(select("itag=248").unwrap(), Itag(248));
+        assert!(matches!(
+            select("best[vcodec=av01]"),
+            Err(RytError::NoFormatFound)
+        ));
+    }
+
+    #[test]
+    fn test_select_preferred_format_quick_profile() {
+        let formats = vec![
+            container_test_format(43, "video/webm; codecs=\"vp8.0, vorbis\"", 720),
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(248, "video/webm; codecs=\"vp9\"", 1080),
+        ];
+        // The selector of `--profile quick` skips muxed formats without AAC audio
+        let format = Downloader::new()
+            .with_format("best[height<=720][acodec=mp4a]", "")
+            .select_preferred_format(&formats, 0)
+            .unwrap();
+        assert_eq!(format.itag, Itag(18));
+    }
+
+    #[test]
+    fn test_select_preferred_format_estimated_size() {
+        let format = |itag: u32, mime_type: &str, height: u32, bitrate: u32, size: Option<u64>| {
+            let mut format = container_test_format(itag, mime_type, height);
+            format.bitrate = bitrate;
+            format.size = size;
+            format
+        };
+        // Sizes for a ten minute video: 22 about 150 MB and 18 about 37.5 MB
+        // by bitrate, 137 exactly 120 MB and 136 about 75 MB by bitrate
+        let formats = vec![
+            format(
+                22,
+                "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"",
+                720,
+                2_000_000,
+                None,
+            ),
+            format(
+                18,
+                "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                360,
+                500_000,
+                None,
+            ),
+            format(
+                137,
+                "video/mp4; codecs=\"avc1.640028\"",
+                1080,
+                4_000_000,
+                Some(120_000_000),
+            ),
+            format(
+                136,
+                "video/mp4; codecs=\"avc1.4d401f\"",
+                720,
+                1_000_000,
+                None,
+            ),
+        ];
+        let duration = 600;
+
+        // The estimate rules out the muxed 720p
+        let downloader = Downloader::new().with_max_filesize(100_000_000);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, duration)
+                .unwrap()
+                .itag,
+            Itag(18)
+        );
+
+        // The best video is too large, so the largest one that fits is used
+        let downloader = downloader.with_format("bestvideo+bestaudio", "");
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, duration)
+                .unwrap()
+                .itag,
+            Itag(136)
+        );
+        let downloader = downloader.with_max_filesize(130_000_000);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, duration)
+                .unwrap()
+                .itag,
+            Itag(137)
+        );
+
+        // Without a duration, unknown sizes can't be estimated
+        let downloader = downloader
+            .with_max_filesize(100_000_000)
+            .with_reject_unknown_size(true);
+        assert!(matches!(
+            downloader.select_preferred_format(&formats, 0),
+            Err(RytError::FileSizeExceeds {
+                actual: 120_000_000,
+                ..
+            })
+        ));
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, duration)
+                .unwrap()
+                .itag,
+            Itag(136)
+        );
+
+        // With everything over the limit, the smallest size is reported
+        let downloader = downloader.with_max_filesize(30_000_000);
+        match downloader.select_preferred_format(&formats, duration) {
+            Err(RytError::FileSizeExceeds { limit, actual }) => {
+                assert_eq!(limit, 30_000_000);
+                assert_eq!(actual, 37_500_000);
+            }
+            other => panic!("expected FileSizeExceeds, got {:?}", other),
+        }
+
+        // A strict format fails instead of falling through
+        let downloader = Downloader::new()
+            .with_format("bestvideo+bestaudio", "")
+            .with_max_filesize(100_000_000)
+            .with_strict_format(true);
+        match downloader.select_preferred_format(&formats, duration) {
+            Err(RytError::FileSizeExceeds { limit, actual }) => {
+                assert_eq!(limit, 100_000_000);
+                assert_eq!(actual, 120_000_000);
+            }
+            other => panic!("expected FileSizeExceeds, got {:?}", other),
+        }
+        let downloader = downloader.with_max_filesize(130_000_000);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, duration)
+                .unwrap()
+                .itag,
+            Itag(137)
+        );
+    }
+
+    #[test]
+    fn test_select_preferred_format_filesize() {
+        let sized = |itag: u32, mime_type: &str, height: u32, size: Option<u64>| {
+            let mut format = container_test_format(itag, mime_type, height);
+            format.size = size;
+            format
+        };
+        let formats = vec![
+            sized(
+                18,
+                "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                360,
+                Some(40_000_000),
+            ),
+            sized(
+                22,
+                "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"",
+                720,
+                None,
+            ),
+            sized(
+                137,
+                "video/mp4; codecs=\"avc1.640028\"",
+                1080,
+                Some(90_000_000),
+            ),
+            sized(
+                136,
+                "video/mp4; codecs=\"avc1.4d401f\"",
+                720,
+                Some(30_000_000),
+            ),
+        ];
+
+        // The muxed 360p is too large, so selection falls through to the rest
+        let downloader = Downloader::new().with_max_filesize(35_000_000);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(22)
+        );
+        let downloader = downloader.with_reject_unknown_size(true);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(136)
+        );
+
+        let downloader = Downloader::new()
+            .with_min_filesize(50_000_000)
+            .with_reject_unknown_size(true);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(137)
+        );
+
+        // With everything too large, the smallest size is reported
+        let downloader = Downloader::new()
+            .with_max_filesize(10_000_000)
+            .with_reject_unknown_size(true);
+        match downloader.select_preferred_format(&formats, 0) {
+            Err(RytError::FileSizeExceeds { limit, actual }) => {
+                assert_eq!(limit, 10_000_000);
+                assert_eq!(actual, 30_000_000);
+            }
+            other => panic!("expected FileSizeExceeds, got {:?}", other),
+        }
+        let downloader = Downloader::new()
+            .with_min_filesize(100_000_000)
+            .with_reject_unknown_size(true);
+        assert!(matches!(
+            downloader.select_preferred_format(&formats, 0),
+            Err(RytError::NoFormatFound)
+        ));
+    }
+
+    #[test]
+    fn test_select_preferred_format_prefer_webm() {
+        let formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(43, "video/webm; codecs=\"vp8.0, vorbis\"", 360),
+            container_test_format(137, "video/mp4; codecs=\"avc1.640028\"", 1080),
+            container_test_format(248, "video/webm; codecs=\"vp9\"", 1080),
+        ];
+
+        let downloader = Downloader::new().with_prefer_webm(true);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(43)
+        );
+
+        // Without muxed formats, WebM wins among the adaptive ones
+        let downloader = Downloader::new()
+            .with_format("height=1080", "")
+            .with_prefer_webm(true);
+        assert_eq!(
+            downloader
+                .select_format(&formats[2..], &SizeLimits::none(0))
+                .unwrap()
+                .itag,
+            Itag(248)
+        );
+        let downloader = Downloader::new().with_format("height=1080", "");
+        assert_eq!(
+            downloader
+                .select_format(&formats[2..], &SizeLimits::none(0))
+                .unwrap()
+                .itag,
+            Itag(137)
+        );
+    }
+
+    #[test]
+    fn test_select_format_prefer_60fps() {
+        let frame_rate_format = |itag: u32, height: u32, fps: u32, bitrate: u32| {
+            let mut format =
+                container_test_format(itag, "video/mp4; codecs=\"avc1.64002a\"", height);
+            format.fps = Some(fps);
+            format.bitrate = bitrate;
+            format
+        };
+        let formats = vec![
+            frame_rate_format(137, 1080, 30, 5_000_000),
+            frame_rate_format(299, 1080, 60, 4_000_000),
+            frame_rate_format(298, 720, 60, 2_000_000),
+            frame_rate_format(400, 1440, 30, 9_000_000),
+        ];
+
+        let downloader = Downloader::new();
+        assert_eq!(
+            downloader
+                .select_format(&formats, &SizeLimits::none(0))
+                .unwrap()
+                .itag,
+            Itag(400)
+        );
+        assert_eq!(
+            downloader
+                .select_format(&formats[..3], &SizeLimits::none(0))
+                .unwrap()
+                .itag,
+            Itag(299)
+        );
+
+        let downloader = Downloader::new().with_prefer_60fps(true);
+        assert_eq!(
+            downloader
+                .select_format(&formats, &SizeLimits::none(0))
+                .unwrap()
+                .itag,
+            Itag(299)
+        );
+        assert_eq!(
+            downloader
+                .select_format(&formats[3..], &SizeLimits::none(0))
+                .unwrap()
+                .itag,
+            Itag(400)
+        );
+
+        let downloader = Downloader::new().with_format("best60fps", "");
+        assert_eq!(
+            downloader
+                .select_format(&formats, &SizeLimits::none(0))
+                .unwrap()
+                .itag,
+            Itag(299)
+        );
+    }
+
+    #[test]
+    fn test_select_preferred_format_prefer_60fps() {
+        let frame_rate_format = |itag: u32, mime_type: &str, height: u32, fps: u32| {
+            let mut format = container_test_format(itag, mime_type, height);
+            format.fps = Some(fps);
+            format
+        };
+        let formats = vec![
+            frame_rate_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360, 30),
+            frame_rate_format(22, "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"", 720, 30),
+            frame_rate_format(137, "video/mp4; codecs=\"avc1.640028\"", 1080, 30),
+            frame_rate_format(298, "video/mp4; codecs=\"avc1.4d4020\"", 720, 60),
+        ];
+        let select = |downloader: Downloader, formats: &[Format]| {
+            downloader.select_preferred_format(formats, 0).unwrap().itag
+        };
+
+        assert_eq!(select(Downloader::new(), &formats), Itag(22));
+        // The muxed formats don't stop a 60 fps one from winning
+        assert_eq!(
+            select(Downloader::new().with_prefer_60fps(true), &formats),
+            Itag(298)
+        );
+        assert_eq!(
+            select(Downloader::new().with_format("best60fps", ""), &formats),
+            Itag(298)
+        );
+        // Without a 60 fps format, the muxed preference still holds
+        assert_eq!(
+            select(Downloader::new().with_prefer_60fps(true), &formats[..3]),
+            Itag(22)
+        );
+    }
+
+    #[test]
+    fn test_select_preferred_format_preferred_language() {
+        let dubbed = |language: &str, is_default: bool| {
+            let mut format = container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0);
+            format.url = format!("https://example.com/140/{}", language);
+            format.language = Some(language.to_string());
+            format.audio_is_default = is_default;
+            format
+        };
+        let formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            dubbed("en-US", true),
+            dubbed("de-DE", false),
+            dubbed("es-419", false),
+        ];
+        let select = |language: &str| {
+            let mut downloader = Downloader::new();
+            downloader.options.format_selector = Some(
+                FormatSelector::new(QualitySelector::BestAudio).with_preferred_language(language),
+            );
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .url
+                .clone()
+        };
+
+        assert_eq!(select("de"), "https://example.com/140/de-DE");
+        assert_eq!(select("es"), "https://example.com/140/es-419");
+        // Only whole subtags match, so these fall back to the original track
+        assert_eq!(select("e"), "https://example.com/140/en-US");
+        assert_eq!(select("de-AT"), "https://example.com/140/en-US");
+    }
+
+    #[test]
+    fn test_select_preferred_format_merge() {
+        let mut formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(137, "video/mp4; codecs=\"avc1.640028\"", 1080),
+            container_test_format(248, "video/webm; codecs=\"vp9\"", 1080),
+            container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0),
+            container_test_format(251, "audio/webm; codecs=\"opus\"", 0),
+        ];
+        formats[0].audio_codec = Some("mp4a.40.2".to_string());
+
+        // The muxed format is skipped when a separate audio stream is requested
+        let downloader =
+            Downloader::new().with_format("bestvideo[ext=webm]+bestaudio[ext=webm]", "mp4");
+        let video = downloader.select_preferred_format(&formats, 0).unwrap();
+        assert_eq!(video.itag, Itag(248));
+
+        let audio_selector = downloader.merge_audio_selector().unwrap();
+        let audio = crate::platform::select_format(&formats, &audio_selector).unwrap();
+        assert_eq!(audio.itag, Itag(251));
+        assert_eq!(mux_container(video, audio), "webm");
+
+        let downloader = Downloader::new().with_format("bestvideo+bestaudio", "mp4");
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(137)
+        );
+        assert!(Downloader::new().merge_audio_selector().is_none());
+    }
+
+    #[test]
+    fn test_select_preferred_format_dolby_atmos() {
+        let mut formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(140, "audio/mp4; codecs=\"mp4a.40.2\"", 0),
+        ];
+
+        // Muxed formats are not preferred over an explicit Dolby request
+        let downloader = Downloader::new().with_format("dolby", "mp4");
+        let err = downloader.select_preferred_format(&formats, 0).unwrap_err();
+        assert!(matches!(err, RytError::FormatNotAvailable(_)));
+        assert!(err.to_string().contains("AAC"));
+
+        let mut atmos = container_test_format(328, "audio/mp4; codecs=\"ec-3\"", 0);
+        atmos.audio_codec = Some("ec-3".to_string());
+        formats.push(atmos);
+        assert_eq!(
+            downloader
+                .select_preferred_format(&formats, 0)
+                .unwrap()
+                .itag,
+            Itag(328)
+        );
+    }
+
+    #[test]
+    fn test_apply_signature() {
+        let base = "https://rr1.googlevideo.com/videoplayback?expire=1&sparams=expire%2Cei%2Cip";
+
+        // Appended when missing, with the name from sp
+        assert_eq!(
+            apply_signature(base, "sig", "AB=C/D"),
+            format!("{}&sig=AB%3DC%2FD", base)
+        );
+        assert_eq!(
+            apply_signature(base, "signature", "XYZ"),
+            format!("{}&signature=XYZ", base)
+        );
+
+        // Stale signatures are replaced, lsig is kept
+        let stale = format!(
+            "{}&sig=OLD&lsig=AG3C_x%3D&signature=OLDER&mime=video%2Fmp4",
+            base
+        );
+        assert_eq!(
+            apply_signature(&stale, "sig", "NEW"),
+            format!("{}&lsig=AG3C_x%3D&mime=video%2Fmp4&sig=NEW", base)
+        );
+
+        // Without a query the signature starts one
+        assert_eq!(
+            apply_signature("https://example.com/videoplayback", "sig", "NEW"),
+            "https://example.com/videoplayback?sig=NEW"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signature_cipher_round_trip() {
+        let downloader = Downloader::new();
+        let cipher = Cipher::new();
+        cipher
+            .cache_signature("ENCRYPTED==", "AOq0QJ8wRQ=deciphered")
+            .await;
+        let video_url = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+        let media_url = "https://rr1.googlevideo.com/videoplayback?expire=1700000000&ei=a%26b\
+                         &id=o-AB&itag=18&sparams=expire%2Cei%2Cid%2Citag&sig=STALE\
+                         &lsig=AG3C_xAwRQ%3D%3D&lsparams=mh%2Cmm";
+
+        let mut format = Format::new(18, String::new(), "360p".into(), "video/mp4".into());
+        format.signature_cipher = Some(
+            url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("s", "ENCRYPTED==")
+                .append_pair("sp", "sig")
+                .append_pair("url", media_url)
+                .finish(),
+        );
+        // The blob itself encodes the URL's own escapes again
+        assert!(format
+            .signature_cipher
+            .as_ref()
+            .unwrap()
+            .contains("sparams%3Dexpire%252Cei"));
+
+        let resolved = downloader
+            .resolve_media_url(&cipher, &format, video_url)
+            .await
+            .unwrap();
+        let parsed = url::Url::parse(&resolved).unwrap();
+        let param = |name: &str| {
+            parsed
+                .query_pairs()
+                .filter(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(param("sig"), vec!["AOq0QJ8wRQ=deciphered"]);
+        assert_eq!(param("sparams"), vec!["expire,ei,id,itag"]);
+        assert_eq!(param("ei"), vec!["a&b"]);
+        assert_eq!(param("lsig"), vec!["AG3C_xAwRQ=="]);
+        assert!(resolved.contains("sparams=expire%2Cei%2Cid%2Citag"));
+        assert!(!resolved.contains("STALE"));
+    }
+
+    #[tokio::test]
+    async fn test_n_param_resolved_consistently() {
+        let downloader = Downloader::new();
+        let cipher = Cipher::new();
+        cipher.cache_n_parameter("abc123", "xyz789").await;
+        let video_url = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+        let base = "https://rr1.googlevideo.com/videoplayback?id=1&itag=18&sparams=id%2Citag%2Crqh";
+
+        // Direct URL carrying n
+        let direct_url = format!("{}&n=abc123", base);
+        let direct = normalize_media_url(
+            &resolve_direct_url(&cipher, &direct_url, video_url).await,
+            Itag(18),
+        );
+
+        // Same URL behind a signatureCipher
+        let mut ciphered = Format::new(18, String::new(), "360p".into(), "video/mp4".into());
+        ciphered.signature_cipher = Some(
+            url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("url", &direct_url)
+                .append_pair("sp", "sig")
+                .finish(),
+        );
+        let via_cipher = downloader
+            .resolve_media_url(&cipher, &ciphered, video_url)
+            .await
+            .unwrap();
+
+        assert_eq!(direct, via_cipher);
+        let n_values: Vec<String> = url::Url::parse(&direct)
+            .unwrap()
+            .query_pairs()
+            .filter(|(k, _)| k == "n")
+            .map(|(_, v)| v.into_owned())
+            .collect();
+        assert_eq!(n_values, vec!["xyz789"]);
+        assert!(direct.contains("rqh=1"));
+    }
+
+    /// Player response with a single muxed format at `url` or behind `cipher`
+    fn muxed_player_response(url: Option<&str>, cipher: Option<&str>) -> PlayerResponse {
+        serde_json::from_value(serde_json::json!({
+            "videoDetails": {
+                "videoId": "dQw4w9WgXcQ",
+                "title": "Test Video",
+                "lengthSeconds": "212",
+                "author": "Test Author",
+                "shortDescription": "",
+                "thumbnail": { "thumbnails": [] }
+            },
+            "streamingData": {
+                "formats": [{
+                    "itag": 18,
+                    "url": url,
+                    "signatureCipher": cipher,
+                    "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                    "qualityLabel": "360p",
+                    "height": 360
+                }]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cloned_downloaders_resolve_concurrently() {
+        let statuses = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&statuses);
+        let downloader = Downloader::new()
+            .with_status_callback(move |status| recorded.lock().unwrap().push(status));
+        let clone = downloader.clone();
+        assert!(Arc::ptr_eq(&downloader.inner_tube, &clone.inner_tube));
+        assert!(Arc::ptr_eq(&downloader.downloader, &clone.downloader));
+        assert!(Arc::ptr_eq(&downloader.cipher, &clone.cipher));
+
+        let media_url = "https://rr1.googlevideo.com/videoplayback?id=1&itag=18";
+        let (a, b) = tokio::join!(
+            downloader.process_player_response(
+                muxed_player_response(Some(media_url), None),
+                "dQw4w9WgXcQ"
+            ),
+            clone.process_player_response(
+                muxed_player_response(Some(media_url), None),
+                "dQw4w9WgXcQ"
+            )
+        );
+        let (a, b) = (a.unwrap(), b.unwrap());
+        assert_eq!(a.url, b.url);
+        assert_eq!(a.format.itag, Itag(18));
+        // The selected format travels with the video info
+        assert_eq!(
+            a.video_info.selected_format.as_ref().map(|f| f.itag),
+            Some(Itag(18))
+        );
+        assert_eq!(b.format.itag, Itag(18));
+        // Both clones report to the shared status callback
+        downloader.emit_status(Status::Downloading);
+        clone.emit_status(Status::Downloading);
+        assert_eq!(
+            *statuses.lock().unwrap(),
+            vec![Status::Downloading, Status::Downloading]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_in_flight_operations() {
+        let downloader = Downloader::new();
+        let token = downloader.cancellation_token();
+        let operation = OperationGuard::begin(&downloader.lifecycle).unwrap();
+
+        // An interrupted transfer leaves its partial file behind
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("video.mp4");
+        std::fs::write(output.with_extension("tmp"), b"partial").unwrap();
+        let clone = downloader.clone();
+        let transfer = tokio::spawn(async move {
+            let url = "https://rr1.googlevideo.com/videoplayback?id=1";
+            clone
+                .cancellable_transfer(std::future::pending::<Result<(), RytError>>(), url, &output)
+                .await
+        });
+
+        let shutdown = downloader.shutdown();
+        tokio::pin!(shutdown);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut shutdown)
+                .await
+                .is_err(),
+            "shutdown returned with an operation in flight"
+        );
+        assert!(token.is_cancelled());
+        assert!(matches!(transfer.await.unwrap(), Err(RytError::Cancelled)));
+
+        drop(operation);
+        let partial_files = shutdown.await.unwrap();
+        assert_eq!(partial_files, vec![dir.path().join("video.tmp")]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flushes_pending_archive_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("archive.txt");
+        let downloader = Downloader::new().with_download_archive(&archive_path);
+
+        // A download that records its archive entry after shutdown begins
+        let operation = OperationGuard::begin(&downloader.lifecycle).unwrap();
+        let mut archive = downloader.check_archive("dQw4w9WgXcQ").unwrap().unwrap();
+        let append = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            archive.record(ArchiveEntry::new("dQw4w9WgXcQ")).unwrap();
+            drop(operation);
+        });
+
+        assert!(downloader.shutdown().await.unwrap().is_empty());
+        let archived = std::fs::read_to_string(&archive_path).unwrap();
+        assert!(archived.contains("youtube dQw4w9WgXcQ"));
+        append.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_operations_fail_after_shutdown() {
+        let downloader = Downloader::new();
+        let clone = downloader.clone();
+        assert!(downloader.shutdown().await.unwrap().is_empty());
+        assert!(matches!(
+            clone
+                .download("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+                .await,
+            Err(RytError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_dropping_last_downloader_cancels() {
+        let downloader = Downloader::new();
+        let fork = downloader.fork();
+        let token = downloader.cancellation_token();
+        drop(downloader);
+        assert!(!token.is_cancelled());
+        drop(fork);
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_status_callback_sequence() {
+        let mut server = mockito::Server::new_async().await;
+        let media_url = format!("{}/videoplayback?id=1&itag=18", server.url());
+        let player_body = |video_id: &str, format: serde_json::Value| {
+            let mut format_json = serde_json::json!({
+                "itag": 18,
+                "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                "qualityLabel": "360p",
+                "height": 360
+            });
+            format_json
+                .as_object_mut()
+                .unwrap()
+                .extend(format.as_object().unwrap().clone());
+            serde_json::json!({
+                "playabilityStatus": { "status": "OK" },
+                "videoDetails": {
+                    "videoId": video_id,
+                    "title": "Test Video",
+                    "lengthSeconds": "212",
+                    "author": "Test Author",
+                    "shortDescription": "",
+                    "thumbnail": { "thumbnails": [] }
+                },
+                "streamingData": { "formats": [format_json] }
+            })
+            .to_string()
+        };
+        // The first video's URL comes in a signature cipher, the second's is direct
+        let cipher = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("url", &media_url)
+            .append_pair("sp", "sig")
+            .finish();
+        let mut players = Vec::new();
+        for (video_id, format) in [
+            (
+                "dQw4w9WgXcQ",
+                serde_json::json!({ "signatureCipher": cipher }),
+            ),
+            ("jNQXAC9IVRw", serde_json::json!({ "url": media_url })),
+        ] {
+            let player = server
+                .mock("POST", "/youtubei/v1/player")
+                .match_query(mockito::Matcher::Any)
+                .match_body(mockito::Matcher::PartialJson(
+                    serde_json::json!({ "videoId": video_id }),
+                ))
+                .with_header("content-type", "application/json")
+                .with_body(player_body(video_id, format))
+                .expect(1)
+                .create_async()
+                .await;
+            players.push(player);
+        }
+        let media = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::Any)
+            .with_body("video data")
+            .expect(2)
+            .create_async()
+            .await;
+
+        let statuses = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&statuses);
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = mock_inner_tube_downloader(&server)
+            .with_output_path(dir.path().join("first.mp4"))
+            .with_status_callback(move |status| recorded.lock().unwrap().push(status));
+
+        // A ciphered format goes through signature extraction
+        downloader
+            .download("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            .await
+            .unwrap();
+        assert_eq!(
+            *statuses.lock().unwrap(),
+            vec![Status::Resolving, Status::Deciphering, Status::Downloading]
+        );
+
+        // Forks report to the same callback; direct URLs need no deciphering
+        statuses.lock().unwrap().clear();
+        let fork = downloader
+            .fork()
+            .with_output_path(dir.path().join("second.mp4"));
+        fork.download("https://www.youtube.com/watch?v=jNQXAC9IVRw")
+            .await
+            .unwrap();
+        assert_eq!(
+            *statuses.lock().unwrap(),
+            vec![Status::Resolving, Status::Downloading]
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("second.mp4")).unwrap(),
+            b"video data"
+        );
+        for player in players {
+            player.assert_async().await;
+        }
+        media.assert_async().await;
+
+        assert_eq!(Status::Deciphering.label(), "Extracting signature...");
+    }
+
+    #[test]
+    fn test_downloader_with_rate_limit_after() {
+        let downloader = Downloader::new()
+            .with_rate_limit(2048)
+            .with_rate_limit_after(RateLimitGrace::Duration(Duration::from_secs(30)))
+            .with_global_rate_limit(true);
+        assert_eq!(
+            downloader.options.rate_limit_after,
+            Some(RateLimitGrace::Duration(Duration::from_secs(30)))
+        );
+        assert!(downloader.options.global_rate_limit);
+    }
+
+    #[test]
+    fn test_downloader_with_match_filter() {
+        let filter = MatchFilter::parse("duration < 600").unwrap();
+        let downloader = Downloader::new().with_match_filter(filter);
+        let filter = downloader.options.match_filter.as_ref().unwrap();
+        assert_eq!(filter.to_string(), "duration < 600");
+
+        let mut info = VideoInfo::new("id".to_string(), "Title".to_string());
+        info.duration = 900;
+        assert!(!filter.matches(&info));
+    }
+
+    #[test]
+    fn test_downloader_with_rate_limit() {
+        let downloader = Downloader::new().with_rate_limit(2048);
+        assert_eq!(downloader.options.rate_limit_bps, Some(2048));
+        assert_eq!(downloader.rate_limit(), Some(2048));
+        assert_eq!(Downloader::new().rate_limit(), None);
+    }
+
+    #[test]
+    fn test_downloader_with_innertube_client() {
+        let downloader = Downloader::new().with_innertube_client("IOS", "19.29.1");
+        assert_eq!(downloader.options.client_name, "IOS");
+        assert_eq!(downloader.options.client_version, "19.29.1");
+    }
+
+    #[test]
+    fn test_client_chain() {
+        let names = |downloader: &Downloader| {
+            downloader
+                .client_chain()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&Downloader::new()),
+            vec!["ANDROID", "IOS", "TVHTML5", "WEB"]
+        );
+
+        // The configured client goes first and is not tried twice
+        let downloader = Downloader::new().with_innertube_client("WEB", "2.0");
+        assert_eq!(names(&downloader), vec!["WEB", "ANDROID", "IOS", "TVHTML5"]);
+        assert_eq!(
+            downloader.client_chain()[0],
+            ("WEB".to_string(), "2.0".to_string())
+        );
+
+        let downloader = Downloader::new()
+            .with_innertube_client("MWEB", "2.1")
+            .with_client_fallback_chain(vec![("IOS".to_string(), "19.29.1".to_string())]);
+        assert_eq!(names(&downloader), vec!["MWEB", "IOS"]);
+        let downloader = Downloader::new().with_client_fallback_chain(Vec::new());
+        assert_eq!(names(&downloader), vec!["ANDROID"]);
+    }
+
+    #[test]
+    fn test_is_host_specific() {
+        assert!(is_host_specific(&RytError::RateLimited));
+        assert!(is_host_specific(&RytError::TimeoutError(
+            "stalled".to_string()
+        )));
+        assert!(!is_host_specific(&RytError::NeedsUrlRefresh(1024)));
+        assert!(!is_host_specific(&RytError::Throttled(1024)));
+        assert!(!is_host_specific(&RytError::Cancelled));
+        assert_eq!(
+            media_host("https://rr3---sn-4g5e6nze.googlevideo.com/videoplayback?itag=18"),
+            "rr3---sn-4g5e6nze.googlevideo.com"
+        );
+    }
+
+    #[test]
+    fn test_is_client_specific() {
+        for error in [
+            RytError::AgeRestricted,
+            RytError::RateLimited,
+            RytError::NoFormatFound,
+            RytError::BotguardError("token".to_string()),
+            RytError::RequestFailed("HTTP 403".to_string()),
+            RytError::MissingVideoDetails("id".to_string()),
+            // Clients sending the account's cookies may have access
+            RytError::MembersOnly,
+            RytError::PaymentRequired { offer_text: None },
+        ] {
+            assert!(is_client_specific(&error), "{}", error);
+        }
+        for error in [
+            RytError::Private,
+            RytError::VideoUnavailable,
+            RytError::GeoBlocked,
+            RytError::InvalidUrl("x".to_string()),
+            RytError::Blocked {
+                reason: "blocked".to_string(),
+            },
+        ] {
+            assert!(!is_client_specific(&error), "{}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_working_client() {
+        let chain: Vec<(String, String)> = DEFAULT_CLIENT_FALLBACK_CHAIN
+            .iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect();
+
+        // Client-specific failures move on to the next client
+        let mut tried = Vec::new();
+        let result = first_working_client(&chain, "id", |name, _| {
+            tried.push(name.to_string());
+            let result = match name {
+                "ANDROID" => Err(RytError::AgeRestricted),
+                "IOS" => Err(RytError::RequestFailed("HTTP 403".to_string())),
+                _ => Ok(name.to_string()),
+            };
+            async move { result }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "TVHTML5");
+        assert_eq!(tried, ["ANDROID", "IOS", "TVHTML5"]);
+
+        // Every client failing returns the last error
+        let result: Result<(), _> =
+            first_working_client(&chain, "id", |_, _| async { Err(RytError::RateLimited) }).await;
+        assert!(matches!(result, Err(RytError::RateLimited)));
+    }
+
+    /// Downloader whose InnerTube client talks to a mock server
+    fn mock_inner_tube_downloader(server: &mockito::Server) -> Downloader {
+        let mut downloader = Downloader::new();
+        downloader.inner_tube = Arc::new(Mutex::new(
+            InnerTubeClient::new()
+                .with_base_url(&server.url())
+                .with_api_key("test-key")
+                .with_visitor_id("visitor-1"),
+        ));
+        downloader
+    }
+
+    #[tokio::test]
+    async fn test_write_subtitles() {
+        let mut server = mockito::Server::new_async().await;
+        // The tracks of the resolved response are used as they are
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let captions = server
+            .mock("GET", "/api/timedtext")
+            .match_query(mockito::Matcher::UrlEncoded("fmt".into(), "vtt".into()))
+            .with_body("WEBVTT\n\n00:00:00.000 --> 00:00:01.000\nHallo\n")
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("Title.mp4");
+        let mut info = VideoInfo::new("video000001".to_string(), "Title".to_string());
+        info.caption_tracks = vec![crate::platform::CaptionTrack {
+            base_url: format!("{}/api/timedtext?v=video000001&lang=de", server.url()),
+            language_code: "de".to_string(),
+            kind: None,
+        }];
+        let downloader = mock_inner_tube_downloader(&server).with_sub_lang("de");
+        let path = downloader.write_subtitles(&info, &output).await.unwrap();
+        assert_eq!(path, dir.path().join("Title.de.vtt"));
+        let vtt = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(crate::utils::parse_vtt_to_words(&vtt)[0].text, "Hallo");
+        captions.assert_async().await;
+
+        let downloader = downloader.with_sub_lang("fr");
+        let result = downloader.write_subtitles(&info, &output).await;
+        assert!(matches!(result, Err(RytError::NoCaptions(_))));
+        assert!(!dir.path().join("Title.fr.vtt").exists());
+        player.assert_async().await;
+    }
+
+    const NEXT_BODY: &str = r#"{
+        "contents": {"twoColumnWatchNextResults": {
+            "results": {"results": {"contents": [{"videoSecondaryInfoRenderer": {
+                "metadataRowContainer": {"metadataRowContainerRenderer": {"rows": [
+                    {"metadataRowRenderer": {
+                        "title": {"simpleText": "Category"},
+                        "contents": [{"runs": [{"text": "Gaming"}]}]
+                    }}
+                ]}}
+            }}]}},
+            "secondaryResults": {"secondaryResults": {"results": [
+                {"compactVideoRenderer": {"videoId": "related0001", "title": {"simpleText": "One"}}},
+                {"lockupViewModel": {"contentId": "related0002", "contentType": "LOCKUP_CONTENT_TYPE_VIDEO"}}
+            ]}}
+        }},
+        "engagementPanels": [{"engagementPanelSectionListRenderer": {
+            "panelIdentifier": "engagement-panel-comments-section",
+            "header": {"engagementPanelTitleHeaderRenderer": {"contextualInfo": {"runs": [{"text": "1,024"}]}}}
+        }}]
+    }"#;
+
+    #[test]
+    fn test_next_format_candidate() {
+        let formats = vec![
+            container_test_format(18, "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"", 360),
+            container_test_format(22, "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"", 720),
+            container_test_format(43, "video/webm; codecs=\"vp8.0, vorbis\"", 360),
+            container_test_format(136, "video/mp4; codecs=\"avc1.4d401f\"", 720),
+        ];
+        let downloader = Downloader::new();
+        let first = downloader
+            .select_preferred_format(&formats, 0)
+            .unwrap()
+            .clone();
+        assert_eq!(first.itag, Itag(22));
+
+        // Muxed formats still come first, and the container is kept
+        let next = downloader
+            .next_format_candidate(&formats, &first, &[Itag(22)], 0)
+            .unwrap();
+        assert_eq!(next.itag, Itag(18));
+        let next = downloader
+            .next_format_candidate(&formats, &first, &[Itag(22), Itag(18)], 0)
+            .unwrap();
+        assert_eq!(next.itag, Itag(136));
+        assert!(downloader
+            .next_format_candidate(&formats, &first, &[Itag(22), Itag(18), Itag(136)], 0)
+            .is_none());
+
+        // The ranked list holds the same order, capped by the format retries
+        let itags = |candidates: Vec<Format>| candidates.iter().map(|f| f.itag).collect::<Vec<_>>();
+        assert_eq!(
+            itags(downloader.rank_format_candidates(&formats, &first, 0)),
+            vec![Itag(18), Itag(136)]
+        );
+        let downloader = downloader.with_format_retries(1);
+        assert_eq!(
+            itags(downloader.rank_format_candidates(&formats, &first, 0)),
+            vec![Itag(18)]
+        );
+        let downloader = downloader.with_format_retries(0);
+        assert!(downloader
+            .rank_format_candidates(&formats, &first, 0)
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_download_switches_format_after_403() {
+        let mut server = mockito::Server::new_async().await;
+        let media_url = |itag: u32| format!("{}/videoplayback?id=1&itag={}", server.url(), itag);
+        let player_body = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": {
+                "videoId": "dQw4w9WgXcQ",
+                "title": "Test Video",
+                "lengthSeconds": "212",
+                "author": "Test Author",
+                "shortDescription": "",
+                "thumbnail": { "thumbnails": [] }
+            },
+            "streamingData": {
+                "formats": [
+                    {
+                        "itag": 18,
+                        "url": media_url(18),
+                        "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                        "qualityLabel": "360p",
+                        "height": 360
+                    },
+                    {
+                        "itag": 22,
+                        "url": media_url(22),
+                        "mimeType": "video/mp4; codecs=\"avc1.64001F, mp4a.40.2\"",
+                        "qualityLabel": "720p",
+                        "height": 720
+                    }
+                ]
+            }
+        });
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(player_body.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let refused = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::UrlEncoded("itag".into(), "22".into()))
+            .with_status(403)
+            .expect(1)
+            .create_async()
+            .await;
+        let served = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::UrlEncoded("itag".into(), "18".into()))
+            .with_status(200)
+            .with_body("itag 18 data")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("video.mp4");
+        let plans = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&plans);
+        let downloader = mock_inner_tube_downloader(&server)
+            .with_output_path(&output)
+            .with_plan_callback(move |plan| recorded.lock().unwrap().push(plan.clone()));
+        let info = downloader
+            .download("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            .await
+            .unwrap();
+
+        // itag 22 was refused, itag 18 of the same response was downloaded
+        // without another player request
+        assert_eq!(info.selected_format.map(|f| f.itag), Some(Itag(18)));
+        // The plan reports the format that was written, not the refused one
+        let plans = plans.lock().unwrap().clone();
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].format.itag, Itag(18));
+        assert_eq!(plans[0].video_info.title, "Test Video");
+        assert_eq!(plans[0].output_path, output);
+        assert_eq!(std::fs::read(&output).unwrap(), b"itag 18 data");
+        player.assert_async().await;
+        refused.assert_async().await;
+        served.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_download_live_stream_from_hls() {
+        let mut server = mockito::Server::new_async().await;
+        let player_body = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": {
+                "videoId": "dQw4w9WgXcQ",
+                "title": "Live Video",
+                "lengthSeconds": "0",
+                "author": "Test Author",
+                "shortDescription": "",
+                "isLive": true,
+                "isLiveContent": true,
+                "thumbnail": { "thumbnails": [] }
+            },
+            "streamingData": {
+                "formats": [{
+                    "itag": 18,
+                    "url": format!("{}/videoplayback?id=1&itag=18", server.url()),
+                    "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                    "qualityLabel": "360p",
+                    "height": 360
+                }],
+                "hlsManifestUrl": format!("{}/hls/index.m3u8", server.url())
+            }
+        });
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(player_body.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let playlist = (0..8).fold("#EXTM3U\n".to_string(), |playlist, index| {
+            format!("{}#EXTINF:5.0,\nsq/{}\n", playlist, index)
+        });
+        let playlist = server
+            .mock("GET", "/hls/index.m3u8")
+            .with_body(playlist)
+            .expect(1)
+            .create_async()
+            .await;
+        let mut segments = Vec::new();
+        for index in 0..8 {
+            segments.push(
+                server
+                    .mock("GET", format!("/hls/sq/{}", index).as_str())
+                    .with_body(format!("segment-{};", index))
+                    .expect(1)
+                    .create_async()
+                    .await,
+            );
+        }
+        let ranged = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("live.mp4");
+        let downloader = mock_inner_tube_downloader(&server)
+            .with_output_path(&output)
+            .with_concurrent_fragments(4);
+        downloader
+            .download("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            .await
+            .unwrap();
+
+        let expected: String = (0..8).map(|index| format!("segment-{};", index)).collect();
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), expected);
+        player.assert_async().await;
+        playlist.assert_async().await;
+        for segment in segments {
+            segment.assert_async().await;
+        }
+        ranged.assert_async().await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_download_with_external_backend() {
+        let mut server = mockito::Server::new_async().await;
+        let media_url = format!("{}/videoplayback?id=1&itag=18&c=IOS", server.url());
+        let player_body = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": {
+                "videoId": "dQw4w9WgXcQ",
+                "title": "Test Video",
+                "lengthSeconds": "212",
+                "author": "Test Author",
+                "shortDescription": "",
+                "thumbnail": { "thumbnails": [] }
+            },
+            "streamingData": {
+                "formats": [{
+                    "itag": 18,
+                    "url": media_url,
+                    "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                    "qualityLabel": "360p",
+                    "height": 360
+                }]
+            }
+        });
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(player_body.to_string())
+            .create_async()
+            .await;
+        // The media is only fetched by the external program
+        let media = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+
+        // Fake downloader recording its arguments and copying a fixture to
+        // the path following -o
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("fixture.bin"), b"external data").unwrap();
+        let script = dir.path().join("fake-downloader.sh");
+        std::fs::write(
+            &script,
+            format!(
+                "printf '%s\\n' \"$@\" > '{dir}/args.txt'\n\
+                 while [ $# -gt 0 ]; do\n\
+                 if [ \"$1\" = -o ]; then out=\"$2\"; fi\n\
+                 shift\n\
+                 done\n\
+                 cp '{dir}/fixture.bin' \"$out\"\n",
+                dir = dir.path().display()
+            ),
+        )
+        .unwrap();
+
+        let output = dir.path().join("video.mp4");
+        let downloader = mock_inner_tube_downloader(&server)
+            .with_output_path(&output)
+            .with_transfer_backend(
+                crate::download::ExternalDownloader::new("sh")
+                    .with_args([script.display().to_string()]),
+            );
+        downloader
+            .download("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&output).unwrap(), b"external data");
+        let args = std::fs::read_to_string(dir.path().join("args.txt")).unwrap();
+        let args: Vec<&str> = args.lines().collect();
+        assert!(args.contains(&"X-Goog-Visitor-Id: visitor-1"), "{:?}", args);
+        assert!(args
+            .iter()
+            .any(|arg| arg.starts_with("User-Agent: Mozilla/5.0")));
+        assert_eq!(args.last(), Some(&media_url.as_str()));
+        // ryt's own partial file is never created
+        assert!(!dir.path().join("video.tmp").exists());
+        player.assert_async().await;
+        media.assert_async().await;
+    }
+
+    /// Raw HTTP server for `body` that trickles plain GETs at 16 bytes per
+    /// 20 ms and answers Range requests at full speed. Returns the URL and
+    /// the Range headers seen.
+    async fn throttling_media_server(
+        body: &'static [u8],
+    ) -> (String, Arc<std::sync::Mutex<Vec<Option<String>>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/videoplayback?id=1&itag=18",
+            listener.local_addr().unwrap()
+        );
+        let ranges = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = ranges.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let seen = seen.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                    let range = request
+                        .lines()
+                        .find_map(|line| line.strip_prefix("range: bytes="))
+                        .map(|range| range.trim().to_string());
+                    seen.lock().unwrap().push(range.clone());
+
+                    let Some((start, end)) = range.as_deref().and_then(|r| r.split_once('-'))
+                    else {
+                        let head = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        );
+                        let _ = stream.write_all(head.as_bytes()).await;
+                        for piece in body.chunks(16) {
+                            if stream.write_all(piece).await.is_err() {
+                                return;
+                            }
+                            let _ = stream.flush().await;
+                            tokio::time::sleep(Duration::from_millis(20)).await;
+                        }
+                        return;
+                    };
+                    let start: usize = start.parse().unwrap();
+                    let end = end.parse::<usize>().unwrap().min(body.len() - 1);
+                    let head = format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\
+                         Content-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                        end - start + 1,
+                        start,
+                        end,
+                        body.len()
+                    );
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(&body[start..=end]).await;
+                });
+            }
+        });
+        (url, ranges)
+    }
+
+    #[tokio::test]
+    async fn test_download_reresolves_when_throttled() {
+        static BODY: [u8; 4096] = {
+            let mut body = [0u8; 4096];
+            let mut i = 0;
+            while i < body.len() {
+                body[i] = (i % 251) as u8;
+                i += 1;
+            }
+            body
+        };
+        let (media_url, ranges) = throttling_media_server(&BODY).await;
+
+        let mut server = mockito::Server::new_async().await;
+        let player_body = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": {
+                "videoId": "dQw4w9WgXcQ",
+                "title": "Test Video",
+                "lengthSeconds": "212",
+                "author": "Test Author",
+                "shortDescription": "",
+                "thumbnail": { "thumbnails": [] }
+            },
+            "streamingData": {
+                "formats": [{
+                    "itag": 18,
+                    "url": media_url,
+                    "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                    "qualityLabel": "360p",
+                    "height": 360
+                }]
+            }
+        });
+        // Once to resolve, once more after the throttled download
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(player_body.to_string())
+            .expect(2)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("video.mp4");
+        let mut downloader = mock_inner_tube_downloader(&server)
+            .with_output_path(&output)
+            .with_throttled_rate(1024 * 1024);
+        assert_eq!(downloader.options.throttled_rate, Some(1024 * 1024));
+        downloader.downloader = Arc::new(Mutex::new(
+            downloader
+                .build_chunked_downloader()
+                .with_throttle_window(Duration::from_millis(200)),
+        ));
+
+        downloader
+            .download("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&output).unwrap(), BODY);
+        player.assert_async().await;
+        // The trickled stream was aborted and the rest fetched by range
+        // from where it stopped
+        let ranges = ranges.lock().unwrap();
+        assert_eq!(ranges[0], None);
+        assert!(
+            ranges
+                .iter()
+                .flatten()
+                .any(|range| !range.starts_with("0-")),
+            "{:?}",
+            ranges
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extra_metadata_skipped_by_default() {
+        let mut server = mockito::Server::new_async().await;
+        let next = server
+            .mock("POST", "/youtubei/v1/next")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(NEXT_BODY)
+            .expect(0)
+            .create_async()
+            .await;
+
+        let downloader = mock_inner_tube_downloader(&server);
+        let mut info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Test".to_string());
+        downloader.enrich_video_info(&mut info).await;
+        assert_eq!(info.category, None);
+        assert_eq!(info.comment_count, None);
+        next.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_extra_metadata_fetched_when_enabled() {
+        let mut server = mockito::Server::new_async().await;
+        let next = server
+            .mock("POST", "/youtubei/v1/next")
+            .match_query(mockito::Matcher::Any)
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({ "videoId": "dQw4w9WgXcQ" }),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(NEXT_BODY)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let downloader = mock_inner_tube_downloader(&server).with_fetch_extra_metadata(true);
+        let mut info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Test".to_string());
+        downloader.enrich_video_info(&mut info).await;
+        assert_eq!(info.category.as_deref(), Some("Gaming"));
+        assert_eq!(info.comment_count, Some(1_024));
+
+        let related = downloader
+            .get_related("https://www.youtube.com/watch?v=dQw4w9WgXcQ", Some(1))
+            .await
+            .unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].video_id, "related0001");
+        next.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_resolve_falls_back_through_shared_inner_tube() {
+        let mut server = mockito::Server::new_async().await;
+        let android = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .match_header("x-goog-visitor-id", "visitor-1")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "context": { "client": { "clientName": "ANDROID" } }
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"playabilityStatus":{"status":"LOGIN_REQUIRED"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let media_url = "https://rr1.googlevideo.com/videoplayback?id=1&itag=18";
+        let ios_body = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": {
+                "videoId": "dQw4w9WgXcQ",
+                "title": "Test Video",
+                "lengthSeconds": "212",
+                "author": "Test Author",
+                "shortDescription": "",
+                "thumbnail": { "thumbnails": [] }
+            },
+            "streamingData": {
+                "formats": [{
+                    "itag": 18,
+                    "url": media_url,
+                    "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                    "qualityLabel": "360p",
+                    "height": 360
+                }]
+            }
+        });
+        let ios = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .match_header("x-goog-visitor-id", "visitor-1")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "context": { "client": { "clientName": "IOS" } }
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(ios_body.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        // The host doesn't resolve, so requests only arrive through the proxy
+        let config = HttpClientConfig {
+            proxy_url: Some(server.url()),
+            ..HttpClientConfig::default()
+        };
+        let mut downloader = Downloader::new();
+        downloader.inner_tube = Arc::new(Mutex::new(
+            InnerTubeClient::new()
+                .with_http_config(config)
+                .with_base_url("http://youtube.invalid")
+                .with_api_key("test-key")
+                .with_visitor_id("visitor-1"),
+        ));
+
+        let resolution = downloader
+            .resolve("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            .await
+            .unwrap();
+        assert!(resolution.url.starts_with(media_url));
+        assert_eq!(resolution.video_info.title, "Test Video");
+        android.assert_async().await;
+        ios.assert_async().await;
+
+        let inner_tube = downloader.inner_tube.lock().await;
+        assert_eq!(inner_tube.player_request_count(), 2);
+        // The title lookup of a later itag download needs no request
+        assert!(inner_tube.cached_player_response("dQw4w9WgXcQ").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_copyright_block_skips_client_switch() {
+        let fixture = include_str!("../platform/testdata/player_copyright_blocked.json");
+        let chain: Vec<(String, String)> = DEFAULT_CLIENT_FALLBACK_CHAIN
+            .iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect();
+
+        let mut attempts = 0;
+        let result = first_working_client(&chain, "id", |_, _| {
+            attempts += 1;
+            let result = serde_json::from_str::<PlayerResponse>(fixture)
+                .map_err(RytError::from)
+                .and_then(PlayerResponse::check_playability);
+            async move { result }
+        })
+        .await;
+
+        match result {
+            Err(RytError::Blocked { reason }) => assert_eq!(
+                reason,
+                "This video contains content from SME, who has blocked it on copyright grounds."
+            ),
+            other => panic!("Expected a copyright block, got {:?}", other.map(|_| ())),
         }
-        .ok_or(RytError::NoFormatFound)
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_downloader_with_botguard_mode() {
+        let downloader =
+            Downloader::new().with_botguard(crate::platform::botguard::BotguardMode::Force);
+        assert_eq!(
+            downloader.botguard.mode,
+            crate::platform::botguard::BotguardMode::Force
+        );
+    }
+
+    #[test]
+    fn test_downloader_with_botguard_debug() {
+        let downloader = Downloader::new().with_botguard_debug(true);
+        assert!(downloader.botguard.debug);
+    }
+
+    #[test]
+    fn test_downloader_with_botguard_ttl() {
+        let downloader = Downloader::new().with_botguard_ttl(Duration::from_secs(3600));
+        assert_eq!(downloader.botguard.ttl, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_downloader_with_timeout() {
+        let downloader = Downloader::new().with_timeout(Duration::from_secs(60));
+        assert_eq!(downloader.options.timeout, Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_downloader_with_split_timeouts() {
+        let downloader = Downloader::new()
+            .with_connect_timeout(Duration::from_secs(5))
+            .with_read_timeout(Duration::from_secs(60))
+            .with_request_timeout(Duration::from_secs(20));
+        assert_eq!(downloader.options.connect_timeout, Duration::from_secs(5));
+        assert_eq!(
+            downloader.options.read_timeout,
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(downloader.options.timeout, Duration::from_secs(20));
+
+        let media = downloader.downloader.lock().await;
+        assert_eq!(media.config().connect_timeout, Duration::from_secs(5));
+        assert_eq!(media.config().read_timeout, Some(Duration::from_secs(60)));
+        assert!(media.config().request_timeout.is_none());
+    }
+
+    #[test]
+    fn test_downloader_with_cipher_timeout() {
+        assert_eq!(
+            Downloader::new().cipher.cipher_timeout(),
+            DEFAULT_CIPHER_TIMEOUT
+        );
+        let downloader = Downloader::new()
+            .try_with_cipher_timeout(Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(downloader.options.cipher_timeout, Duration::from_secs(2));
+        assert_eq!(downloader.cipher.cipher_timeout(), Duration::from_secs(2));
+        assert!(matches!(
+            Downloader::new().try_with_cipher_timeout(Duration::ZERO),
+            Err(RytError::InvalidOption(_))
+        ));
+
+        let options = DownloadOptions::builder()
+            .with_cipher_timeout(Duration::from_secs(3))
+            .build()
+            .unwrap();
+        let downloader = Downloader::from_options(options).unwrap();
+        assert_eq!(downloader.cipher.cipher_timeout(), Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn test_downloader_cache_stats() {
+        let downloader = Downloader::new();
+        assert_eq!(downloader.cache_stats(), CacheStats::default());
+
+        let _ = downloader.cipher.fetch_player_js("invalid_url").await;
+        assert_eq!(downloader.cache_stats().player_js_misses, 1);
+
+        // Clearing keeps the counters
+        downloader.clear_caches();
+        assert_eq!(downloader.cache_stats().player_js_misses, 1);
+    }
+
+    #[test]
+    fn test_downloader_with_max_retries() {
+        let downloader = Downloader::new().with_max_retries(5);
+        assert_eq!(downloader.options.max_retries, 5);
+    }
+
+    #[test]
+    fn test_downloader_with_temp_dir() {
+        let downloader = Downloader::new()
+            .with_temp_dir("/tmp/ryt")
+            .with_fragment_retries(5);
+        assert_eq!(downloader.options.temp_dir, Some(PathBuf::from("/tmp/ryt")));
+        assert_eq!(downloader.options.fragment_retries, 5);
+    }
+
+    #[test]
+    fn test_downloader_expands_output_and_temp_paths() {
+        std::env::set_var("RYT_TEST_OUTPUT_ROOT", "/srv/ryt");
+        let downloader = Downloader::new()
+            .try_with_output_path("$RYT_TEST_OUTPUT_ROOT/%(title)s.%(ext)s")
+            .unwrap()
+            .with_temp_dir("${RYT_TEST_OUTPUT_ROOT}/tmp");
+        assert_eq!(
+            downloader.options.output_path,
+            Some(PathBuf::from("/srv/ryt/%(title)s.%(ext)s"))
+        );
+        assert_eq!(
+            downloader.options.temp_dir,
+            Some(PathBuf::from("/srv/ryt/tmp"))
+        );
+
+        let home = std::env::var("HOME").unwrap();
+        let downloader = Downloader::new()
+            .try_with_output_path("~/Downloads")
+            .unwrap();
+        assert_eq!(
+            downloader.options.output_path,
+            Some(PathBuf::from(home).join("Downloads"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_downloader_with_keep_temp() {
+        let downloader = Downloader::new().with_keep_temp(true);
+        assert!(downloader.options.keep_temp_on_error);
+        assert!(
+            downloader
+                .downloader
+                .lock()
+                .await
+                .config()
+                .keep_temp_on_error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prepare_output_creates_parent_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("a").join("b").join("video.mp4");
+
+        let downloader = Downloader::new().with_create_dirs(false);
+        downloader.prepare_output(&output, None).await.unwrap();
+        assert!(!output.parent().unwrap().exists());
+
+        let downloader = Downloader::new();
+        // A size no disk can hold only warns
+        downloader
+            .prepare_output(&output, Some(u64::MAX))
+            .await
+            .unwrap();
+        assert!(output.parent().unwrap().is_dir());
+    }
+
+    #[tokio::test]
+    async fn test_prepare_output_error_names_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("file");
+        std::fs::write(&blocker, b"").unwrap();
+        let output = blocker.join("video.mp4");
+
+        let err = Downloader::new()
+            .prepare_output(&output, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RytError::Io {
+                op: "create directory",
+                ..
+            }
+        ));
+        assert!(err.to_string().contains(&blocker.display().to_string()));
+    }
+
+    #[test]
+    fn test_downloader_with_fragment_retries() {
+        let downloader = Downloader::new().with_fragment_retries(20);
+        assert_eq!(downloader.options.fragment_retries, 20);
+        assert_eq!(downloader.options.max_retries, 3);
+    }
+
+    #[tokio::test]
+    async fn test_downloader_with_concurrent_fragments() {
+        let downloader = Downloader::new().with_concurrent_fragments(4);
+        assert_eq!(downloader.options.concurrent_fragments, 4);
+        assert_eq!(
+            downloader
+                .downloader
+                .lock()
+                .await
+                .config()
+                .concurrent_fragments,
+            4
+        );
+    }
+
+    #[test]
+    fn test_downloader_with_write_buffer() {
+        let downloader = Downloader::new().with_write_buffer(1024 * 1024);
+        assert_eq!(downloader.options.write_buffer_size, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_determine_output_path_untitled_video() {
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = Downloader::new().with_output_path(dir.path());
+
+        let untitled = VideoInfo::new("dQw4w9WgXcQ".to_string(), String::new());
+        assert_eq!(
+            downloader.determine_output_path(&untitled).unwrap(),
+            dir.path().join("dQw4w9WgXcQ.mp4")
+        );
+
+        let titled = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Title".to_string());
+        assert_eq!(
+            downloader.determine_output_path(&titled).unwrap(),
+            dir.path().join("Title.mp4")
+        );
+    }
+
+    #[test]
+    fn test_claim_output_path_unique_filenames() {
+        let dir = tempfile::tempdir().unwrap();
+        let info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Title".to_string());
+        let downloader = Downloader::new().with_output_path(dir.path());
+        let unique = Downloader::new()
+            .with_output_path(dir.path())
+            .with_unique_filenames(true);
+
+        // A partial file alone is resumed under the plain name
+        std::fs::write(dir.path().join("Title.tmp"), b"partial").unwrap();
+        assert_eq!(
+            unique.claim_output_path(&info).unwrap(),
+            dir.path().join("Title.mp4")
+        );
+
+        std::fs::write(dir.path().join("Title.mp4"), b"first").unwrap();
+        assert_eq!(
+            downloader.claim_output_path(&info).unwrap(),
+            dir.path().join("Title.mp4")
+        );
+        assert_eq!(
+            unique.claim_output_path(&info).unwrap(),
+            dir.path().join("Title (1).mp4")
+        );
+
+        std::fs::write(dir.path().join("Title (1).mp4"), b"second").unwrap();
+        assert_eq!(
+            unique.claim_output_path(&info).unwrap(),
+            dir.path().join("Title (2).mp4")
+        );
+    }
+
+    #[test]
+    fn test_check_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.txt");
+        std::fs::write(
+            &path,
+            "youtube plainId
+youtube lowResId 2024-05-01T12:30:00Z 18 360
+",
+        )
+        .unwrap();
+
+        // Without an archive nothing is skipped
+        assert!(Downloader::new()
+            .check_archive("plainId")
+            .unwrap()
+            .is_none());
+
+        let downloader = Downloader::new()
+            .with_download_archive(&path)
+            .with_archive_format(ArchiveFormat::Extended);
+        assert!(matches!(
+            downloader.check_archive("plainId"),
+            Err(RytError::AlreadyArchived(id)) if id == "plainId"
+        ));
+        assert!(matches!(
+            downloader.check_archive("lowResId"),
+            Err(RytError::AlreadyArchived(_))
+        ));
+        assert!(downloader.check_archive("newId").unwrap().is_some());
+
+        let downloader = downloader.with_redownload_below(720);
+        assert!(matches!(
+            downloader.check_archive("plainId"),
+            Err(RytError::AlreadyArchived(_))
+        ));
+        assert!(downloader.check_archive("lowResId").unwrap().is_some());
     }
 
-    /// Resolve format URL with signature deciphering
-    async fn resolve_format_url_with_cipher(
-        &self,
-        format: &Format,
-        video_url: &str,
-    ) -> Result<String, RytError> {
-        use crate::platform::cipher::Cipher;
+    #[tokio::test]
+    async fn test_download_skips_archived_video() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.txt");
+        std::fs::write(
+            &path,
+            "youtube dQw4w9WgXcQ
+",
+        )
+        .unwrap();
 
-        // println!("🔧 Starting cipher resolution for format itag={}", format.itag);
-        let cipher = Cipher::new();
-        let mut final_url = format.url.clone();
+        let downloader = Downloader::new().with_download_archive(&path);
+        let result = downloader
+            .download("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+            .await;
+        assert!(matches!(result, Err(RytError::AlreadyArchived(_))));
+    }
 
-        // Handle signature cipher
-        if let Some(sig_cipher) = &format.signature_cipher {
-            // println!("🔧 Parsing signature cipher: {}", sig_cipher);
+    #[tokio::test]
+    async fn test_download_session_marks_items() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("archive.txt");
+        std::fs::write(&archive_path, "youtube dQw4w9WgXcQ\n").unwrap();
+        let session_path = dir.path().join("session.json");
 
-            // Parse signature cipher parameters
-            let sig_params: std::collections::HashMap<String, String> =
-                url::form_urlencoded::parse(sig_cipher.as_bytes())
-                    .into_owned()
-                    .collect();
+        let downloader = Downloader::new()
+            .with_download_archive(&archive_path)
+            .with_session_file(&session_path);
+        let mut done = SessionItem::new("doneId", "Done");
+        done.status = ItemStatus::Done;
+        let items = vec![
+            done,
+            downloader.plan_session_item("dQw4w9WgXcQ", "Archived"),
+        ];
+        let mut session = downloader.start_session("batch", items).unwrap();
 
-            // println!("🔧 Parsed parameters: {:?}", sig_params);
+        // The done item is skipped, the archived one is marked done without downloading
+        let result = downloader.download_session(&mut session).await.unwrap();
+        assert!(result.succeeded.is_empty());
+        assert!(result.failed.is_empty());
+        assert_eq!(session.count(ItemStatus::Done), 2);
 
-            if let Some(base_url) = sig_params.get("url") {
-                final_url = base_url.clone();
-                // println!("🔧 Base URL: {}", final_url);
-            }
+        let saved = Session::load(&session_path).unwrap();
+        assert_eq!(saved.count(ItemStatus::Done), 2);
+        assert_eq!(
+            saved.items[1].output_path,
+            Some(PathBuf::from("Archived.mp4"))
+        );
+    }
 
-            if let Some(signature) = sig_params.get("s") {
-                println!("🔧 Deciphering signature: {}", signature);
-                let deciphered_sig = cipher.decipher_signature(signature, video_url).await?;
-                println!("🔧 Deciphered signature: {}", deciphered_sig);
-
-                // Replace existing sig parameter or add new one
-                let sig_regex = regex::Regex::new(r"[?&]sig=([^&]+)")?;
-                if sig_regex.is_match(&final_url) {
-                    // Replace existing sig parameter
-                    final_url = sig_regex
-                        .replace(&final_url, |caps: &regex::Captures| {
-                            format!(
-                                "{}sig={}",
-                                &caps[0][..caps[0].find('=').unwrap() + 1],
-                                deciphered_sig
-                            )
-                        })
-                        .to_string();
-                    println!("🔧 Replaced sig parameter in URL");
-                } else {
-                    // Add new sig parameter
-                    final_url = format!("{}&sig={}", final_url, deciphered_sig);
-                    println!("🔧 Added sig parameter to URL");
-                }
-                println!("🔧 Final URL with deciphered sig: {}", final_url);
-            }
+    #[tokio::test]
+    async fn test_download_items_skips_playlist_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress.txt");
+        let content = "youtube firstId First video\nyoutube secondId Second video\n";
+        std::fs::write(&path, content).unwrap();
 
-            if let Some(n_param) = sig_params.get("n") {
-                // println!("🔧 Deciphering n-parameter: {}", n_param);
-                let deciphered_n = cipher.decipher_n_parameter(n_param, video_url).await?;
-                // println!("🔧 Deciphered n-parameter: {}", deciphered_n);
+        // Resuming skips recorded items without counting them as existing
+        let downloader = Downloader::new()
+            .with_playlist_archive(&path)
+            .with_break_on_existing(true);
+        let archive = downloader.load_playlist_archive().unwrap();
+        assert_eq!(archive.as_ref().map(PlaylistArchive::len), Some(2));
+        let items = vec![
+            PlaylistItem::new("firstId".to_string(), "First video".to_string(), 1),
+            PlaylistItem::new("secondId".to_string(), "Second video".to_string(), 2),
+        ];
+        let result = downloader
+            .download_items(items, archive, &mut HashSet::new())
+            .await;
+        assert_eq!(result.attempted(), 0);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
 
-                // Add n-parameter to URL
-                if final_url.contains("&n=") {
-                    final_url = final_url.replace("&n=", &format!("&n={}", deciphered_n));
-                } else {
-                    final_url = format!("{}&n={}", final_url, deciphered_n);
-                }
-            }
-        }
+        assert!(Downloader::new().load_playlist_archive().unwrap().is_none());
+    }
 
-        // Handle n-parameter in URL
-        if final_url.contains("&n=") || final_url.contains("?n=") {
-            let n_regex = regex::Regex::new(r"[?&]n=([^&]+)")?;
-            if let Some(captures) = n_regex.captures(&final_url) {
-                if let Some(n_param) = captures.get(1) {
-                    let deciphered_n = cipher
-                        .decipher_n_parameter(n_param.as_str(), video_url)
-                        .await?;
-                    final_url = final_url.replace(n_param.as_str(), &deciphered_n);
-                }
-            }
-        }
+    #[tokio::test]
+    async fn test_download_items_filters_on_playlist_metadata() {
+        let mut server = mockito::Server::new_async().await;
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
 
-        // Normalize URL parameters similar to Go ytdlp:
-        // - ensure n is decoded if present (handled above already)
-        // - enforce ratebypass=yes
-        // - add alr=yes to encourage stable redirects
-        if let Ok(mut parsed) = url::Url::parse(&final_url) {
-            // Precompute existence flags before taking a mutable borrow
-            let has_ratebypass = parsed.query_pairs().any(|(k, _)| k == "ratebypass");
-            let has_alr = parsed.query_pairs().any(|(k, _)| k == "alr");
+        // The listed durations already fail the filter, so nothing is resolved
+        let downloader = mock_inner_tube_downloader(&server)
+            .with_match_filter(MatchFilter::parse("duration < 600 & view_count > 10").unwrap());
+        let items: Vec<PlaylistItem> = [("firstId", 1200), ("secondId", 3000)]
+            .into_iter()
+            .zip(1..)
+            .map(|((id, duration), index)| {
+                let mut item = PlaylistItem::new(id.to_string(), format!("Video {}", index), index);
+                item.duration = duration;
+                item
+            })
+            .collect();
+        let result = downloader
+            .download_items(items, None, &mut HashSet::new())
+            .await;
+        assert_eq!(result.attempted(), 0);
+        assert!(result.failed.is_empty());
+        player.assert_async().await;
+    }
 
-            // Add missing params. If sparams contains rqh but rqh parameter is missing, add it
-            let sparams_val = parsed
-                .query_pairs()
-                .find(|(k, _)| k == "sparams")
-                .map(|(_, v)| v.to_string());
-            let has_rqh = parsed.query_pairs().any(|(k, _)| k == "rqh");
-            let sparams_has_rqh = sparams_val.as_ref().map_or(false, |s| s.contains("rqh"));
-            let current_fvip = parsed
-                .query_pairs()
-                .find(|(k, _)| k == "fvip")
-                .map(|(_, v)| v.to_string());
+    #[tokio::test]
+    async fn test_download_batch_skips_duplicates() {
+        let mut server = mockito::Server::new_async().await;
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "playabilityStatus": { "status": "OK" },
+                    "videoDetails": {
+                        "videoId": "dQw4w9WgXcQ",
+                        "title": "Test Video",
+                        "lengthSeconds": "212",
+                        "author": "Test Author",
+                        "shortDescription": "",
+                        "thumbnail": { "thumbnails": [] }
+                    },
+                    "streamingData": { "formats": [{
+                        "itag": 18,
+                        "url": format!("{}/videoplayback?itag=18", server.url()),
+                        "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                        "qualityLabel": "360p",
+                        "height": 360
+                    }] }
+                })
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let media = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::Any)
+            .with_body("video data")
+            .expect(2)
+            .create_async()
+            .await;
 
-            debug!(
-                "URL normalization: has_rqh={}, sparams_has_rqh={}, will add rqh={}",
-                has_rqh,
-                sparams_has_rqh,
-                sparams_has_rqh && !has_rqh
-            );
+        // Every form of the video names the same one, downloaded once
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = mock_inner_tube_downloader(&server)
+            .with_output_path(dir.path().join("%(title)s.%(ext)s"));
+        let urls = [
+            "dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+        ]
+        .map(str::to_string);
+        let result = downloader.download_batch(&urls).await.unwrap();
+        assert_eq!(result.succeeded.len(), 1);
+        assert_eq!(result.duplicates, 3);
+        assert_eq!(result.attempted(), 1);
+        assert_eq!(
+            std::fs::read(dir.path().join("Test Video.mp4")).unwrap(),
+            b"video data"
+        );
 
-            {
-                let mut qp = parsed.query_pairs_mut();
-                if !has_ratebypass {
-                    qp.append_pair("ratebypass", "yes");
-                    debug!("Added ratebypass=yes");
-                }
-                if !has_alr {
-                    qp.append_pair("alr", "yes");
-                    debug!("Added alr=yes");
-                }
-                // If sparams lists rqh but rqh param is missing, add rqh=1 (CRITICAL for itag 18)
-                if sparams_has_rqh && !has_rqh {
-                    qp.append_pair("rqh", "1");
-                    debug!("✅ Added rqh=1 (required by sparams)");
-                }
-            }
+        // Another batch on the same downloader is a new run
+        let result = downloader.download_batch(&urls[..1]).await.unwrap();
+        assert_eq!((result.succeeded.len(), result.duplicates), (1, 0));
+        player.assert_async().await;
+        media.assert_async().await;
 
-            // Professional 403 mitigation: rotate fvip (front video IP) to get different CDN server
-            // YouTube uses fvip=1..5 for load balancing; rotating helps bypass temporary blocks
-            if let Some(fvip_str) = current_fvip {
-                if let Ok(fvip_num) = fvip_str.parse::<u8>() {
-                    // Cycle through fvip 1-5
-                    let new_fvip = (fvip_num % 5) + 1;
-                    // Rebuild query without fvip
-                    let pairs: Vec<(String, String)> = parsed
-                        .query_pairs()
-                        .filter(|(k, _)| k != "fvip")
-                        .map(|(k, v)| (k.into_owned(), v.into_owned()))
-                        .collect();
-                    {
-                        let mut qp = parsed.query_pairs_mut();
-                        qp.clear()
-                            .extend_pairs(pairs.iter().map(|(k, v)| (k.as_str(), v.as_str())));
-                        qp.append_pair("fvip", &new_fvip.to_string());
-                    }
-                    debug!(
-                        "Rotated fvip from {} to {} for CDN failover",
-                        fvip_num, new_fvip
-                    );
-                }
-            }
+        // Archive hits and duplicates are counted separately
+        let archive_path = dir.path().join("archive.txt");
+        std::fs::write(&archive_path, "youtube dQw4w9WgXcQ\n").unwrap();
+        let result = Downloader::new()
+            .with_download_archive(&archive_path)
+            .download_batch(&urls[1..])
+            .await
+            .unwrap();
+        assert_eq!((result.archived, result.duplicates), (1, 2));
+        assert_eq!(result.attempted(), 0);
+    }
 
-            let s: String = parsed.into();
-            return Ok(s);
-        }
+    #[tokio::test]
+    async fn test_download_batch_continues_past_invalid_lines() {
+        let mut server = mockito::Server::new_async().await;
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "playabilityStatus": { "status": "OK" },
+                    "videoDetails": {
+                        "videoId": "dQw4w9WgXcQ",
+                        "title": "Resolved",
+                        "lengthSeconds": "212",
+                        "author": "Test Author",
+                        "shortDescription": "",
+                        "thumbnail": { "thumbnails": [] }
+                    },
+                    "streamingData": { "formats": [{
+                        "itag": 18,
+                        "url": format!("{}/videoplayback?itag=18", server.url()),
+                        "mimeType": "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                        "qualityLabel": "360p",
+                        "height": 360
+                    }] }
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let media = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
 
-        Ok(final_url)
-    }
+        // The existing file is named after the resolved title, not the URL
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Resolved.mp4"), b"video").unwrap();
+        let downloader = mock_inner_tube_downloader(&server)
+            .with_output_path(dir.path().join("%(title)s.%(ext)s"))
+            .with_break_on_existing(true);
+        let urls = [
+            "https://example.com/not-a-video",
+            "https://youtu.be/dQw4w9WgXcQ",
+            "https://example.com/also-invalid",
+        ]
+        .map(str::to_string);
+        let result = downloader.download_batch(&urls).await.unwrap();
 
-    /// Determine output path for downloaded file
-    fn determine_output_path(&self, video_info: &VideoInfo) -> Result<PathBuf, RytError> {
-        if let Some(output_path) = &self.options.output_path {
-            if output_path.is_dir() {
-                // Generate filename from title
-                let ext = self.options.desired_ext.as_deref().unwrap_or("mp4");
-                let safe_filename = to_safe_filename(&video_info.title, ext);
-                Ok(output_path.join(safe_filename))
-            } else {
-                // Use provided path as-is
-                Ok(output_path.clone())
-            }
-        } else {
-            // Generate filename in current directory
-            let ext = self.options.desired_ext.as_deref().unwrap_or("mp4");
-            let safe_filename = to_safe_filename(&video_info.title, ext);
-            Ok(PathBuf::from(safe_filename))
-        }
+        assert!(result.succeeded.is_empty());
+        let failed: Vec<_> = result
+            .failed
+            .iter()
+            .map(|failure| (failure.item.index, failure.item.title.as_str()))
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                (1, "https://example.com/not-a-video"),
+                (3, "https://example.com/also-invalid"),
+            ]
+        );
+        player.assert_async().await;
+        media.assert_async().await;
     }
-}
 
-impl Default for Downloader {
-    fn default() -> Self {
-        Self::new()
+    #[tokio::test]
+    async fn test_download_playlist_resumes_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let session_path = dir.path().join("session.json");
+        let playlist_url = "https://www.youtube.com/playlist?list=PLtest";
+
+        let mut item = SessionItem::new("doneId", "Done");
+        item.status = ItemStatus::Done;
+        Session::new(&session_path, playlist_url, vec![item])
+            .save()
+            .unwrap();
+
+        // A finished session needs neither playlist resolution nor downloads
+        let downloader = Downloader::new().with_session_file(&session_path);
+        let result = downloader
+            .download_playlist(playlist_url, None)
+            .await
+            .unwrap();
+        assert_eq!(result.attempted(), 0);
+
+        // A session for another input is never overwritten
+        let result = downloader
+            .download_playlist("https://www.youtube.com/playlist?list=PLother", None)
+            .await;
+        assert!(matches!(result, Err(RytError::SessionError(_))));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_download_session_break_on_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("archive.txt");
+        std::fs::write(&archive_path, "youtube dQw4w9WgXcQ\n").unwrap();
 
-    #[test]
-    fn test_downloader_creation() {
-        let downloader = Downloader::new();
-        assert_eq!(downloader.options.client_name, "ANDROID");
-        assert_eq!(downloader.options.client_version, "20.10.38");
+        // The first archived item stops the run; the rest stays pending
+        let downloader = Downloader::new()
+            .with_download_archive(&archive_path)
+            .with_session_file(dir.path().join("archived.json"))
+            .with_break_on_existing(true);
+        let items = vec![
+            downloader.plan_session_item("dQw4w9WgXcQ", "Archived"),
+            downloader.plan_session_item("newVideoId1", "New"),
+        ];
+        let mut session = downloader.start_session("batch", items).unwrap();
+        let result = downloader.download_session(&mut session).await.unwrap();
+        assert!(result.succeeded.is_empty());
+        assert!(result.failed.is_empty());
+        assert_eq!(session.items[0].status, ItemStatus::Done);
+        assert_eq!(session.items[1].status, ItemStatus::Pending);
+
+        // An existing output file counts as well
+        let downloader = Downloader::new()
+            .with_output_path(dir.path())
+            .with_session_file(dir.path().join("existing.json"))
+            .with_break_on_existing(true);
+        let existing = downloader.plan_session_item("existingId1", "Existing");
+        std::fs::write(existing.output_path.as_ref().unwrap(), b"video").unwrap();
+        let items = vec![existing, downloader.plan_session_item("newVideoId1", "New")];
+        let mut session = downloader.start_session("batch", items).unwrap();
+        let result = downloader.download_session(&mut session).await.unwrap();
+        assert!(result.succeeded.is_empty());
+        assert!(result.failed.is_empty());
+        assert_eq!(session.count(ItemStatus::Done), 1);
+        assert_eq!(session.count(ItemStatus::Pending), 1);
     }
 
-    #[test]
-    fn test_downloader_with_format() {
+    #[tokio::test]
+    async fn test_download_session_stop_conditions() {
+        let dir = tempfile::tempdir().unwrap();
         let downloader = Downloader::new()
-            .with_format("best", "mp4")
-            .with_rate_limit(1024 * 1024); // 1MB/s
+            .with_session_file(dir.path().join("session.json"))
+            .with_max_downloads(0);
+        let items = vec![downloader.plan_session_item("newVideoId1", "New")];
+        let mut session = downloader.start_session("batch", items).unwrap();
+        let result = downloader.download_session(&mut session).await.unwrap();
+        assert!(result.succeeded.is_empty());
+        assert!(result.failed.is_empty());
+        assert_eq!(session.count(ItemStatus::Pending), 1);
 
-        assert!(downloader.options.format_selector.is_some());
-        assert_eq!(downloader.options.rate_limit_bps, Some(1024 * 1024));
+        let downloader = downloader
+            .with_break_on_existing(true)
+            .with_playlist_reverse(true);
+        let result = downloader.download_session(&mut session).await;
+        assert!(matches!(result, Err(RytError::ConflictingOptions(_))));
+        let result = downloader
+            .download_playlist("https://www.youtube.com/playlist?list=PLtest", None)
+            .await;
+        assert!(matches!(result, Err(RytError::ConflictingOptions(_))));
     }
 
     #[test]
-    fn test_downloader_with_botguard() {
+    fn test_downloader_playlist_options() {
+        let interval = SleepInterval::new(Duration::from_secs(1), Duration::from_secs(5));
         let downloader = Downloader::new()
-            .with_botguard(crate::platform::botguard::BotguardMode::Auto)
-            .with_botguard_debug(true);
+            .with_sleep_interval(interval)
+            .with_max_downloads(10)
+            .with_break_on_existing(true)
+            .with_playlist_abort_on_error(true);
+        let options = downloader.playlist_options();
+        assert_eq!(options.sleep_interval, Some(interval));
+        assert_eq!(options.max_downloads, Some(10));
+        assert!(options.break_on_existing);
+        assert!(options.abort_on_error);
+        assert!(!options.reverse);
+
+        // Forks keep the run controls
+        assert_eq!(downloader.fork().playlist_options().max_downloads, Some(10));
+
+        let downloader = downloader.with_playlist_options(PlaylistOptions::default());
+        assert!(downloader.playlist_options().max_downloads.is_none());
+    }
 
+    #[tokio::test]
+    async fn test_downloader_with_api_http_version() {
+        let downloader = Downloader::new();
         assert_eq!(
-            downloader.botguard.mode,
-            crate::platform::botguard::BotguardMode::Auto
+            downloader.inner_tube.lock().await.http_config().http2,
+            Http2Mode::Auto
+        );
+
+        for http2 in [Http2Mode::Force, Http2Mode::Off] {
+            let downloader = Downloader::new().with_api_http_version(http2);
+            assert_eq!(downloader.options.api_http2, http2);
+            let api_config = downloader.inner_tube.lock().await.http_config().clone();
+            assert_eq!(api_config.http2, http2);
+            assert!(!api_config.http1_only);
+
+            // Media downloads stay on HTTP/1.1 whatever the API uses
+            let media_config = downloader.downloader.lock().await.media_http_config().await;
+            assert!(media_config.http1_only);
+            assert_eq!(media_config.http2, Http2Mode::Auto);
+        }
+
+        // Rebuilding the API client for other options keeps the version
+        let downloader = Downloader::new()
+            .with_api_http_version(Http2Mode::Off)
+            .with_ip_family(IpFamily::V4);
+        assert_eq!(
+            downloader.inner_tube.lock().await.http_config().http2,
+            Http2Mode::Off
         );
-        assert!(downloader.botguard.debug);
     }
 
-    #[test]
-    fn test_download_options_default() {
-        let options = DownloadOptions::default();
-        assert_eq!(options.client_name, "ANDROID");
-        assert_eq!(options.client_version, "20.10.38");
-        assert_eq!(options.timeout, Duration::from_secs(30));
-        assert_eq!(options.max_retries, 3);
-        assert!(options.format_selector.is_none());
-        assert!(options.desired_ext.is_none());
-        assert!(options.output_path.is_none());
-        assert!(options.rate_limit_bps.is_none());
+    #[tokio::test]
+    async fn test_downloader_with_api_accept_encoding() {
+        let downloader = Downloader::new().with_api_accept_encoding(AcceptEncoding::Gzip);
+        assert_eq!(
+            downloader
+                .inner_tube
+                .lock()
+                .await
+                .http_config()
+                .accept_encoding,
+            AcceptEncoding::Gzip
+        );
+
+        // Media downloads never decode compressed responses
+        let media_config = downloader.downloader.lock().await.media_http_config().await;
+        assert_eq!(media_config.accept_encoding, AcceptEncoding::Identity);
     }
 
-    #[test]
-    fn test_botguard_config_default() {
-        let config = BotguardConfig::default();
-        assert_eq!(config.mode, crate::platform::botguard::BotguardMode::Off);
-        assert!(!config.debug);
-        assert_eq!(config.ttl, Duration::from_secs(1800));
+    #[tokio::test]
+    async fn test_downloader_with_cookie_jar() {
+        let jar = Arc::new(Jar::default());
+        let downloader = Downloader::new()
+            .with_cookie_jar(Arc::clone(&jar))
+            .with_api_accept_encoding(AcceptEncoding::Gzip);
+        // Rebuilding the API client keeps the cookies
+        let api_config = downloader.inner_tube.lock().await.http_config().clone();
+        assert!(api_config
+            .cookie_jar
+            .is_some_and(|config_jar| Arc::ptr_eq(&config_jar, &jar)));
+        assert!(downloader.fork().cookie_jar.is_some());
     }
 
-    #[test]
-    fn test_downloader_default() {
-        let downloader = Downloader::default();
-        assert_eq!(downloader.options.client_name, "ANDROID");
-        assert_eq!(downloader.options.client_version, "20.10.38");
+    #[tokio::test]
+    async fn test_downloader_with_media_ip_family() {
+        let downloader = Downloader::new().with_media_ip_family(IpFamily::V4);
+        assert_eq!(downloader.options.media_ip_family, IpFamily::V4);
+
+        let media_config = downloader.downloader.lock().await.media_http_config().await;
+        assert_eq!(media_config.ip_family, IpFamily::V4);
+        let api_config = downloader.inner_tube.lock().await.http_config().clone();
+        assert_eq!(api_config.ip_family, IpFamily::Any);
+
+        // Rebuilding the clients for other options keeps the constraint
+        let downloader = downloader.with_connect_timeout(Duration::from_secs(5));
+        let media_config = downloader.downloader.lock().await.media_http_config().await;
+        assert_eq!(media_config.ip_family, IpFamily::V4);
+        assert_eq!(
+            downloader.inner_tube.lock().await.http_config().ip_family,
+            IpFamily::Any
+        );
     }
 
-    #[test]
-    fn test_downloader_with_output_path() {
-        let downloader = Downloader::new().with_output_path("/tmp/test.mp4");
-        assert!(downloader.options.output_path.is_some());
+    #[tokio::test]
+    async fn test_downloader_from_options() {
+        let options = DownloadOptions::builder()
+            .with_format("best[height<=480]")
+            .with_request_timeout(Duration::from_secs(7))
+            .with_geo_bypass_country("fr")
+            .with_rate_limit(4096)
+            .build()
+            .unwrap();
+        let downloader = Downloader::from_options(options).unwrap();
         assert_eq!(
-            downloader.options.output_path.unwrap(),
-            PathBuf::from("/tmp/test.mp4")
+            downloader
+                .options
+                .format_selector
+                .as_ref()
+                .unwrap()
+                .height_limit,
+            Some(480)
         );
+        let inner_tube = downloader.inner_tube.lock().await;
+        assert_eq!(
+            inner_tube.http_config().timeout,
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(inner_tube.geo_country(), Some("FR"));
+
+        // Options built by hand are validated too
+        let options = DownloadOptions {
+            timeout: Duration::ZERO,
+            ..DownloadOptions::default()
+        };
+        assert!(matches!(
+            Downloader::from_options(options),
+            Err(RytError::InvalidOption(_))
+        ));
     }
 
-    #[test]
-    fn test_downloader_with_progress() {
-        let downloader = Downloader::new().with_progress(|_progress| {
-            // Test callback
-        });
-        // Test that with_progress doesn't panic and returns self
-        assert_eq!(downloader.options.client_name, "ANDROID");
+    #[tokio::test]
+    async fn test_downloader_from_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ryt.toml");
+        std::fs::write(
+            &path,
+            r#"
+            format = "best[height<=480]"
+            rate_limit_bps = 8192
+            max_retries = 5
+            fragment_retries = 2
+            timeout = "7s"
+            "#,
+        )
+        .unwrap();
+
+        // Builders applied after loading override the file, as command line
+        // options do
+        let downloader = Downloader::from_config(&path)
+            .unwrap()
+            .with_max_retries(9)
+            .try_with_format("best[height<=720]", "mp4")
+            .unwrap();
+        let options = &downloader.options;
+        assert_eq!(options.max_retries, 9);
+        assert_eq!(
+            options.format_selector.as_ref().unwrap().height_limit,
+            Some(720)
+        );
+        assert_eq!(options.fragment_retries, 2);
+        assert_eq!(options.rate_limit_bps, Some(8192));
+        assert_eq!(
+            downloader.inner_tube.lock().await.http_config().timeout,
+            Some(Duration::from_secs(7))
+        );
+
+        // Loaded options are validated
+        std::fs::write(&path, "rate_limit_bps = 10").unwrap();
+        assert!(matches!(
+            Downloader::from_config(&path),
+            Err(RytError::InvalidOption(_))
+        ));
     }
 
     #[test]
-    fn test_downloader_with_rate_limit() {
-        let downloader = Downloader::new().with_rate_limit(2048);
+    fn test_downloader_try_with_builders() {
+        let downloader = Downloader::new()
+            .try_with_format("best[ext=webm]", "mp4")
+            .unwrap()
+            .try_with_output_path("out")
+            .unwrap()
+            .try_with_rate_limit(2048)
+            .unwrap()
+            .try_with_innertube_client("WEB", "2.0")
+            .unwrap()
+            .try_with_request_timeout(Duration::from_secs(3))
+            .unwrap()
+            .try_with_connect_timeout(Duration::from_secs(2))
+            .unwrap()
+            .try_with_read_timeout(Duration::from_secs(1))
+            .unwrap()
+            .try_with_write_buffer(4096)
+            .unwrap()
+            .try_with_geo_bypass_country("jp")
+            .unwrap();
         assert_eq!(downloader.options.rate_limit_bps, Some(2048));
+        assert_eq!(downloader.options.client_name, "WEB");
+        assert_eq!(
+            downloader.options.read_timeout,
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(downloader.options.geo_bypass_country.as_deref(), Some("JP"));
+        assert!(downloader.options.validate().is_ok());
+
+        assert!(matches!(
+            Downloader::new().try_with_format("best[height<=x]", "mp4"),
+            Err(RytError::InvalidFormatSelector(_))
+        ));
+        for result in [
+            Downloader::new().try_with_output_path(""),
+            Downloader::new().try_with_rate_limit(1024),
+            Downloader::new().try_with_innertube_client("", "1.0"),
+            Downloader::new().try_with_request_timeout(Duration::ZERO),
+            Downloader::new().try_with_connect_timeout(Duration::ZERO),
+            Downloader::new().try_with_read_timeout(Duration::ZERO),
+            Downloader::new().try_with_write_buffer(0),
+            Downloader::new().try_with_geo_bypass_country("USA"),
+        ] {
+            assert!(matches!(result, Err(RytError::InvalidOption(_))));
+        }
     }
 
-    #[test]
-    fn test_downloader_with_innertube_client() {
-        let downloader = Downloader::new().with_innertube_client("IOS", "19.29.1");
-        assert_eq!(downloader.options.client_name, "IOS");
-        assert_eq!(downloader.options.client_version, "19.29.1");
+    #[tokio::test]
+    async fn test_downloader_fork_shares_clients() {
+        let parent = Downloader::new()
+            .with_format("best", "webm")
+            .with_botguard(crate::platform::botguard::BotguardMode::Auto);
+        let fork = parent.fork().with_progress(|_| {});
+
+        assert!(Arc::ptr_eq(&parent.inner_tube, &fork.inner_tube));
+        assert!(Arc::ptr_eq(&parent.downloader, &fork.downloader));
+        assert!(Arc::ptr_eq(&parent.cipher, &fork.cipher));
+        assert!(fork.options.format_selector.is_some());
+        assert_eq!(fork.botguard.mode, parent.botguard.mode);
+
+        // Each fork reports progress through its own callback
+        assert!(parent.progress_callback.is_none());
+        assert!(fork.progress_callback.is_some());
+        let media = parent.media_downloader().await;
+        assert!(media.config().progress_callback.is_none());
+        let media = fork.media_downloader().await;
+        assert!(media.config().progress_callback.is_some());
+
+        // Cipher caches are shared
+        assert_eq!(parent.cache_stats(), fork.cache_stats());
     }
 
-    #[test]
-    fn test_downloader_with_botguard_mode() {
-        let downloader =
-            Downloader::new().with_botguard(crate::platform::botguard::BotguardMode::Force);
+    #[tokio::test]
+    async fn test_downloader_with_ip_family() {
+        let downloader = Downloader::new().with_ip_family(IpFamily::V6);
+        assert_eq!(downloader.options.ip_family, IpFamily::V6);
+        assert_eq!(downloader.options.media_ip_family, IpFamily::V6);
         assert_eq!(
-            downloader.botguard.mode,
-            crate::platform::botguard::BotguardMode::Force
+            downloader.inner_tube.lock().await.http_config().ip_family,
+            IpFamily::V6
         );
+        let media_config = downloader.downloader.lock().await.media_http_config().await;
+        assert_eq!(media_config.ip_family, IpFamily::V6);
     }
 
-    #[test]
-    fn test_downloader_with_botguard_debug() {
-        let downloader = Downloader::new().with_botguard_debug(true);
-        assert!(downloader.botguard.debug);
+    #[tokio::test]
+    async fn test_downloader_with_dns_override() {
+        let addrs: Vec<SocketAddr> = vec!["127.0.0.1:443".parse().unwrap()];
+        let downloader = Downloader::new().with_dns_override("rr1.googlevideo.com", addrs.clone());
+        assert_eq!(
+            downloader.options.dns_overrides["rr1.googlevideo.com"],
+            addrs
+        );
+        assert_eq!(
+            downloader
+                .inner_tube
+                .lock()
+                .await
+                .http_config()
+                .dns_overrides["rr1.googlevideo.com"],
+            addrs
+        );
+        let media_config = downloader.downloader.lock().await.media_http_config().await;
+        assert_eq!(media_config.dns_overrides["rr1.googlevideo.com"], addrs);
     }
 
-    #[test]
-    fn test_downloader_with_botguard_ttl() {
-        let downloader = Downloader::new().with_botguard_ttl(Duration::from_secs(3600));
-        assert_eq!(downloader.botguard.ttl, Duration::from_secs(3600));
+    #[tokio::test]
+    async fn test_downloader_with_print_traffic() {
+        let downloader = Downloader::new().with_print_traffic(256);
+        let traffic_log = Some(TrafficLog::new(256));
+        assert_eq!(downloader.options.traffic_log, traffic_log);
+        assert_eq!(
+            downloader.inner_tube.lock().await.http_config().traffic_log,
+            traffic_log
+        );
+        let media_config = downloader.downloader.lock().await.media_http_config().await;
+        assert_eq!(media_config.traffic_log, traffic_log);
     }
 
-    #[test]
-    fn test_downloader_with_timeout() {
-        let downloader = Downloader::new().with_timeout(Duration::from_secs(60));
-        assert_eq!(downloader.options.timeout, Duration::from_secs(60));
+    #[tokio::test]
+    async fn test_downloader_with_locale() {
+        let downloader = Downloader::new()
+            .with_geo_bypass_country("de")
+            .with_language("de");
+        assert_eq!(downloader.options.geo_bypass_country.as_deref(), Some("DE"));
+        assert_eq!(downloader.options.language.as_deref(), Some("de"));
+        assert_eq!(downloader.inner_tube.lock().await.geo_country(), Some("DE"));
+
+        // Rebuilding the InnerTube client keeps the locale
+        let downloader = downloader.with_request_timeout(Duration::from_secs(5));
+        assert_eq!(downloader.inner_tube.lock().await.geo_country(), Some("DE"));
     }
 
     #[test]
-    fn test_downloader_with_max_retries() {
-        let downloader = Downloader::new().with_max_retries(5);
-        assert_eq!(downloader.options.max_retries, 5);
+    fn test_downloader_with_session_file() {
+        let downloader = Downloader::new()
+            .with_session_file("/tmp/session.json")
+            .with_retry_failed(true);
+        assert_eq!(
+            downloader.options.session_file,
+            Some(PathBuf::from("/tmp/session.json"))
+        );
+        assert!(downloader.options.retry_failed);
     }
 
     #[test]
@@ -934,6 +6606,19 @@ mod tests {
         assert_eq!(selector.extension, Some("mp4".to_string()));
     }
 
+    #[test]
+    fn test_downloader_with_format_filters() {
+        let downloader = Downloader::new().with_format("best[height=720][fps>=60]", "mp4");
+        let selector = downloader.options.format_selector.unwrap();
+        assert_eq!(selector.quality, QualitySelector::Best);
+        assert_eq!(selector.height_limit, Some(720));
+        assert_eq!(selector.fps_min, Some(60));
+        assert_eq!(selector.extension, Some("mp4".to_string()));
+
+        let downloader = Downloader::new().with_format("best[tbr>1000]", "mp4");
+        assert!(downloader.options.format_selector.is_none());
+    }
+
     #[test]
     fn test_downloader_with_format_invalid() {
         let downloader = Downloader::new().with_format("invalid", "mp4");
@@ -0,0 +1,509 @@
+//! Metadata filter deciding which videos get downloaded
+//!
+//! A filter is a small expression such as
+//! `duration < 600 & view_count > 1000 | title ~= '(?i)trailer'`. Conditions
+//! joined with `&` must all hold; `|` separates alternatives and binds
+//! weaker than `&`. Numeric fields (`duration`, `view_count`, `like_count`)
+//! support `<`, `<=`, `>`, `>=`, `=` and `!=`; a `?` after the operator lets
+//! the condition pass when the value is unknown. String fields (`title`,
+//! `uploader`) support `=`, `!=` and `~=` for a regex search.
+
+use crate::core::{PlaylistItem, VideoInfo};
+use crate::error::RytError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Numeric video field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumericField {
+    Duration,
+    ViewCount,
+    LikeCount,
+}
+
+impl NumericField {
+    fn value(self, info: &VideoInfo) -> Option<u64> {
+        match self {
+            NumericField::Duration => Some(u64::from(info.duration)),
+            NumericField::ViewCount => info.view_count,
+            NumericField::LikeCount => info.like_count,
+        }
+    }
+}
+
+/// String video field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextField {
+    Title,
+    Uploader,
+}
+
+impl TextField {
+    fn value(self, info: &VideoInfo) -> &str {
+        match self {
+            TextField::Title => &info.title,
+            TextField::Uploader => &info.author,
+        }
+    }
+}
+
+/// Numeric comparison operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl CompareOp {
+    fn compare(self, left: u64, right: u64) -> bool {
+        match self {
+            CompareOp::Lt => left < right,
+            CompareOp::Le => left <= right,
+            CompareOp::Gt => left > right,
+            CompareOp::Ge => left >= right,
+            CompareOp::Eq => left == right,
+            CompareOp::Ne => left != right,
+        }
+    }
+}
+
+/// Single condition of a filter
+#[derive(Debug, Clone)]
+enum Condition {
+    Numeric {
+        field: NumericField,
+        op: CompareOp,
+        value: u64,
+        allow_missing: bool,
+    },
+    Equals {
+        field: TextField,
+        value: String,
+        negate: bool,
+    },
+    Matches {
+        field: TextField,
+        regex: Regex,
+    },
+}
+
+impl Condition {
+    fn matches(&self, info: &VideoInfo) -> bool {
+        match self {
+            Condition::Numeric {
+                field,
+                op,
+                value,
+                allow_missing,
+            } => match field.value(info) {
+                Some(actual) => op.compare(actual, *value),
+                None => *allow_missing,
+            },
+            Condition::Equals {
+                field,
+                value,
+                negate,
+            } => (field.value(info) == value) != *negate,
+            Condition::Matches { field, regex } => regex.is_match(field.value(info)),
+        }
+    }
+
+    /// Check whether a playlist entry carries the value the condition
+    /// looks at
+    fn is_known_for(&self, item: &PlaylistItem) -> bool {
+        match self {
+            Condition::Numeric { field, .. } => {
+                *field == NumericField::Duration && item.duration > 0
+            }
+            Condition::Equals { field, .. } | Condition::Matches { field, .. } => {
+                *field == TextField::Title || !item.author.is_empty()
+            }
+        }
+    }
+}
+
+/// Operators, longest first so `<=` is not read as `<`
+const OPERATORS: &[&str] = &["~=", "<=", ">=", "!=", "==", "<", ">", "="];
+
+/// Parsed metadata filter
+///
+/// Serializes as its expression.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MatchFilter {
+    expr: String,
+    /// The filter matches if every condition of any group matches
+    groups: Vec<Vec<Condition>>,
+}
+
+impl MatchFilter {
+    /// Parse a filter expression
+    pub fn parse(expr: &str) -> Result<Self, RytError> {
+        let groups = split_unquoted(expr, '|')
+            .into_iter()
+            .map(|group| {
+                split_unquoted(group, '&')
+                    .into_iter()
+                    .map(parse_condition)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            expr: expr.trim().to_string(),
+            groups,
+        })
+    }
+
+    /// Check whether a video passes the filter
+    pub fn matches(&self, info: &VideoInfo) -> bool {
+        self.groups
+            .iter()
+            .any(|group| group.iter().all(|condition| condition.matches(info)))
+    }
+
+    /// Check a playlist entry against the filter using only the metadata
+    /// the playlist lists, so failing videos need not be resolved.
+    ///
+    /// Returns `None` if the outcome depends on fields only the full video
+    /// info has, such as the view or like count.
+    pub fn matches_item(&self, item: &PlaylistItem) -> Option<bool> {
+        let mut info = VideoInfo::new(item.video_id.clone(), item.title.clone());
+        info.author = item.author.clone();
+        info.duration = item.duration;
+
+        let mut undecided = false;
+        for group in &self.groups {
+            let mut outcome = Some(true);
+            for condition in group {
+                if !condition.is_known_for(item) {
+                    outcome = None;
+                } else if !condition.matches(&info) {
+                    outcome = Some(false);
+                    break;
+                }
+            }
+            match outcome {
+                Some(true) => return Some(true),
+                Some(false) => {}
+                None => undecided = true,
+            }
+        }
+        (!undecided).then_some(false)
+    }
+
+    /// Check whether the filter looks at the like count, which only the
+    /// `next` endpoint reports
+    pub fn uses_like_count(&self) -> bool {
+        self.groups.iter().flatten().any(|condition| {
+            matches!(
+                condition,
+                Condition::Numeric {
+                    field: NumericField::LikeCount,
+                    ..
+                }
+            )
+        })
+    }
+}
+
+impl fmt::Display for MatchFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl TryFrom<String> for MatchFilter {
+    type Error = RytError;
+
+    fn try_from(expr: String) -> Result<Self, Self::Error> {
+        Self::parse(&expr)
+    }
+}
+
+impl From<MatchFilter> for String {
+    fn from(filter: MatchFilter) -> Self {
+        filter.expr
+    }
+}
+
+/// Split on `separator` outside of quoted values
+fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == separator => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            None => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Parse a condition such as `view_count >? 1000` or `title ~= 'live'`
+fn parse_condition(s: &str) -> Result<Condition, RytError> {
+    let invalid =
+        |reason: &str| RytError::InvalidMatchFilter(format!("{} in '{}'", reason, s.trim()));
+
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(invalid("empty condition"));
+    }
+    let field_len = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    let (field, rest) = s.split_at(field_len);
+    let rest = rest.trim_start();
+    let op = OPERATORS
+        .iter()
+        .find(|op| rest.starts_with(**op))
+        .ok_or_else(|| invalid("missing comparison operator"))?;
+    let rest = &rest[op.len()..];
+    let (allow_missing, rest) = match rest.strip_prefix('?') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let value = unquote(rest.trim());
+
+    let numeric = match field {
+        "duration" => Some(NumericField::Duration),
+        "view_count" => Some(NumericField::ViewCount),
+        "like_count" => Some(NumericField::LikeCount),
+        _ => None,
+    };
+    if let Some(field) = numeric {
+        let op = match *op {
+            "<" => CompareOp::Lt,
+            "<=" => CompareOp::Le,
+            ">" => CompareOp::Gt,
+            ">=" => CompareOp::Ge,
+            "=" | "==" => CompareOp::Eq,
+            "!=" => CompareOp::Ne,
+            _ => return Err(invalid("regex match on a numeric field")),
+        };
+        let value = value
+            .parse()
+            .map_err(|_| invalid(&format!("invalid number '{}'", value)))?;
+        return Ok(Condition::Numeric {
+            field,
+            op,
+            value,
+            allow_missing,
+        });
+    }
+
+    let field = match field {
+        "title" => TextField::Title,
+        "uploader" => TextField::Uploader,
+        "" => return Err(invalid("missing field")),
+        other => {
+            return Err(invalid(&format!(
+                "unknown field '{}' (supported: duration, view_count, like_count, title, uploader)",
+                other
+            )))
+        }
+    };
+    if allow_missing {
+        return Err(invalid("'?' on a string field"));
+    }
+    match *op {
+        "~=" => Ok(Condition::Matches {
+            field,
+            regex: Regex::new(value).map_err(|e| invalid(&format!("invalid regex: {}", e)))?,
+        }),
+        "=" | "==" | "!=" => Ok(Condition::Equals {
+            field,
+            value: value.to_string(),
+            negate: *op == "!=",
+        }),
+        _ => Err(invalid("numeric comparison on a string field")),
+    }
+}
+
+/// Strip matching single or double quotes around a value
+fn unquote(value: &str) -> &str {
+    for quote in ['\'', '"'] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(title: &str, duration: u32, view_count: Option<u64>) -> VideoInfo {
+        let mut info = VideoInfo::new("id".to_string(), title.to_string());
+        info.author = "Some Channel".to_string();
+        info.duration = duration;
+        info.view_count = view_count;
+        info
+    }
+
+    #[test]
+    fn test_numeric_conditions() {
+        let filter = MatchFilter::parse("duration < 600").unwrap();
+        assert!(filter.matches(&video("a", 599, None)));
+        assert!(!filter.matches(&video("a", 600, None)));
+
+        let short = video("a", 60, Some(1000));
+        for (expr, expected) in [
+            ("duration <= 60", true),
+            ("duration >= 61", false),
+            ("duration > 59", true),
+            ("duration = 60", true),
+            ("duration == 61", false),
+            ("duration != 61", true),
+            ("view_count>999", true),
+        ] {
+            assert_eq!(
+                MatchFilter::parse(expr).unwrap().matches(&short),
+                expected,
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let unknown = video("a", 60, None);
+        assert!(!MatchFilter::parse("view_count > 10")
+            .unwrap()
+            .matches(&unknown));
+        assert!(MatchFilter::parse("view_count >? 10")
+            .unwrap()
+            .matches(&unknown));
+        // A known value is compared even with '?'
+        let mut liked = video("a", 60, None);
+        liked.like_count = Some(20);
+        assert!(!MatchFilter::parse("like_count <? 10")
+            .unwrap()
+            .matches(&liked));
+    }
+
+    #[test]
+    fn test_string_conditions() {
+        let info = video("Official Trailer | 4K", 120, None);
+        assert!(MatchFilter::parse("title ~= '(?i)trailer'")
+            .unwrap()
+            .matches(&info));
+        assert!(!MatchFilter::parse("title ~= 'trailer'")
+            .unwrap()
+            .matches(&info));
+        assert!(MatchFilter::parse("uploader = \"Some Channel\"")
+            .unwrap()
+            .matches(&info));
+        assert!(MatchFilter::parse("uploader != 'Other'")
+            .unwrap()
+            .matches(&info));
+    }
+
+    #[test]
+    fn test_compound_conditions() {
+        let filter =
+            MatchFilter::parse("duration < 600 & view_count > 1000 | title ~= 'live|stream'")
+                .unwrap();
+        assert!(filter.matches(&video("a", 300, Some(5000))));
+        assert!(!filter.matches(&video("a", 300, Some(10))));
+        assert!(!filter.matches(&video("a", 900, Some(5000))));
+        // The quoted '|' belongs to the regex, not the expression
+        assert!(filter.matches(&video("Late stream", 900, None)));
+        assert_eq!(
+            filter.to_string(),
+            "duration < 600 & view_count > 1000 | title ~= 'live|stream'"
+        );
+
+        let filter = MatchFilter::parse("duration>=60&duration<=120&uploader~=Channel").unwrap();
+        assert!(filter.matches(&video("a", 90, None)));
+        assert!(!filter.matches(&video("a", 121, None)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for expr in [
+            "",
+            "duration < 600 &",
+            "duration",
+            "< 600",
+            "rating > 4",
+            "duration < ten",
+            "duration ~= 60",
+            "title < 5",
+            "title ~= '('",
+            "title =? x",
+        ] {
+            let result = MatchFilter::parse(expr);
+            assert!(
+                matches!(result, Err(RytError::InvalidMatchFilter(_))),
+                "{}",
+                expr
+            );
+        }
+
+        let err = MatchFilter::parse("rating > 4").unwrap_err();
+        assert!(err.to_string().contains("unknown field 'rating'"));
+    }
+
+    #[test]
+    fn test_matches_item() {
+        let mut item = PlaylistItem::new("id".to_string(), "Official Trailer".to_string(), 1);
+        item.duration = 120;
+        let decide =
+            |expr: &str, item: &PlaylistItem| MatchFilter::parse(expr).unwrap().matches_item(item);
+
+        assert_eq!(decide("duration < 600", &item), Some(true));
+        assert_eq!(decide("duration > 600", &item), Some(false));
+        assert_eq!(
+            decide("title ~= 'Trailer' & duration < 60", &item),
+            Some(false)
+        );
+        // Counts are only known after resolving
+        assert_eq!(decide("view_count > 10", &item), None);
+        assert_eq!(decide("like_count <? 10", &item), None);
+        // A failing known condition decides the group anyway
+        assert_eq!(
+            decide("view_count > 10 & duration > 600", &item),
+            Some(false)
+        );
+        assert_eq!(decide("duration > 600 | view_count > 10", &item), None);
+        assert_eq!(
+            decide("duration > 600 | title ~= 'Trailer'", &item),
+            Some(true)
+        );
+        // Unknown uploader and duration are left to the full check
+        assert_eq!(decide("uploader = 'Some Channel'", &item), None);
+        item.author = "Some Channel".to_string();
+        assert_eq!(decide("uploader = 'Some Channel'", &item), Some(true));
+        item.duration = 0;
+        assert_eq!(decide("duration < 600", &item), None);
+    }
+
+    #[test]
+    fn test_uses_like_count() {
+        assert!(MatchFilter::parse("duration < 60 | like_count > 10")
+            .unwrap()
+            .uses_like_count());
+        assert!(!MatchFilter::parse("duration < 60 & view_count > 10")
+            .unwrap()
+            .uses_like_count());
+    }
+}
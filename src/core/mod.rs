@@ -1,9 +1,17 @@
 //! Core functionality for ryt
 
 pub mod downloader;
+pub mod match_filter;
+pub mod options;
+pub mod playlist;
+pub mod postprocess;
 pub mod progress;
 pub mod video_info;
 
 pub use downloader::*;
+pub use match_filter::*;
+pub use options::*;
+pub use playlist::*;
+pub use postprocess::*;
 pub use progress::*;
 pub use video_info::*;
@@ -0,0 +1,991 @@
+//! Validated construction of download options
+//!
+//! [`DownloadOptions::builder`] collects options from config-driven code and
+//! checks them all at once in [`DownloadOptionsBuilder::build`]:
+//!
+//! ```rust
+//! use ryt::core::{DownloadOptions, Downloader};
+//! use serde::Deserialize;
+//! use std::time::Duration;
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     format: String,
+//!     output: String,
+//!     rate_limit: Option<u64>,
+//!     timeout_secs: u64,
+//!     client: String,
+//!     client_version: String,
+//! }
+//!
+//! let config: Config = toml::from_str(
+//!     r#"
+//!     format = "best[height<=720]"
+//!     output = "./downloads"
+//!     rate_limit = 2097152
+//!     timeout_secs = 30
+//!     client = "ANDROID"
+//!     client_version = "20.10.38"
+//!     "#,
+//! )?;
+//!
+//! let mut builder = DownloadOptions::builder()
+//!     .with_format(&config.format)
+//!     .with_output_path(&config.output)
+//!     .with_request_timeout(Duration::from_secs(config.timeout_secs))
+//!     .with_innertube_client(&config.client, &config.client_version);
+//! if let Some(rate_limit) = config.rate_limit {
+//!     builder = builder.with_rate_limit(rate_limit);
+//! }
+//!
+//! let downloader = Downloader::from_options(builder.build()?)?;
+//! # let _ = downloader;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! When the file can use [`DownloadOptions`]' own field names, load it
+//! directly with [`DownloadOptions::from_file`] or
+//! [`Downloader::from_config`](crate::core::Downloader::from_config):
+//!
+//! ```toml
+//! format = "bestvideo[height<=1080]+bestaudio"
+//! output_path = "~/Videos/%(uploader)s/%(title)s.%(ext)s"
+//! rate_limit_bps = 2097152
+//! client_name = "WEB"
+//! client_version = "2.20251002"
+//! max_retries = 5
+//! timeout = "1m"
+//! read_timeout = "45s"
+//! ```
+
+use crate::core::{DownloadOptions, FormatSelector, MatchFilter};
+use crate::download::RateLimitGrace;
+use crate::error::RytError;
+use crate::platform::{
+    parse_format_selector, AcceptEncoding, FormatFallback, Http2Mode, IpFamily, TrafficLog,
+};
+use crate::utils::{check_template, expand_path, ArchiveFormat, LinkKind, ThumbnailFormat};
+use serde::{Deserialize, Deserializer};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Rate limits must be above this many bytes per second
+pub const MIN_RATE_LIMIT_BPS: u64 = 1024;
+
+/// Check a rate limit in bytes per second
+pub(crate) fn check_rate_limit(bytes_per_second: u64) -> Result<(), RytError> {
+    if bytes_per_second <= MIN_RATE_LIMIT_BPS {
+        return Err(RytError::InvalidOption(format!(
+            "rate limit must be above 1 KB/s, got {} B/s",
+            bytes_per_second
+        )));
+    }
+    Ok(())
+}
+
+/// Check that a timeout is not zero
+pub(crate) fn check_timeout(name: &str, timeout: Duration) -> Result<(), RytError> {
+    if timeout.is_zero() {
+        return Err(RytError::InvalidOption(format!(
+            "{} must be greater than zero",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Check an InnerTube client name and version
+pub(crate) fn check_client(name: &str, version: &str) -> Result<(), RytError> {
+    if name.trim().is_empty() {
+        return Err(RytError::InvalidOption(
+            "InnerTube client name must not be empty".to_string(),
+        ));
+    }
+    if version.trim().is_empty() {
+        return Err(RytError::InvalidOption(format!(
+            "InnerTube client version for {} must not be empty",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Check an output path
+pub(crate) fn check_output_path(path: &Path) -> Result<(), RytError> {
+    if path.as_os_str().is_empty() {
+        return Err(RytError::InvalidOption(
+            "output path must not be empty".to_string(),
+        ));
+    }
+    check_template(&path.to_string_lossy())
+}
+
+/// Check a write buffer size
+pub(crate) fn check_write_buffer(size: usize) -> Result<(), RytError> {
+    if size == 0 {
+        return Err(RytError::InvalidOption(
+            "write buffer size must be greater than zero".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check the number of segments fetched at once
+pub(crate) fn check_concurrent_fragments(count: usize) -> Result<(), RytError> {
+    if count == 0 {
+        return Err(RytError::InvalidOption(
+            "concurrent fragments must be at least 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check a two-letter country code
+pub(crate) fn check_country(country: &str) -> Result<(), RytError> {
+    if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(RytError::InvalidOption(format!(
+            "'{}' is not a two-letter country code",
+            country
+        )));
+    }
+    Ok(())
+}
+
+/// Deserialize a format selector from a selector string such as
+/// `best[height<=720]`, or from its serialized fields
+pub(crate) fn deserialize_format_selector<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<FormatSelector>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Selector {
+        Spec(String),
+        Fields(FormatSelector),
+    }
+
+    match Option::<Selector>::deserialize(deserializer)? {
+        Some(Selector::Spec(spec)) => parse_format_selector(&spec)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        Some(Selector::Fields(selector)) => Ok(Some(selector)),
+        None => Ok(None),
+    }
+}
+
+impl DownloadOptions {
+    /// Create a builder validating options at build time
+    pub fn builder() -> DownloadOptionsBuilder {
+        DownloadOptionsBuilder::default()
+    }
+
+    /// Load options from a TOML file, or a JSON file if the name ends in
+    /// `.json`.
+    ///
+    /// Options missing from the file keep their defaults. Durations may be
+    /// written as `30s` or `1h30m`, and the output path and temp directory
+    /// get `~` and variables expanded. The options are not validated yet;
+    /// [`Downloader::from_options`](crate::core::Downloader::from_options)
+    /// does that.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RytError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| RytError::io(e, "read", path))?;
+        let invalid = |message: String| RytError::InvalidConfig {
+            path: path.to_path_buf(),
+            message,
+        };
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let mut options: DownloadOptions = if is_json {
+            serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?
+        } else {
+            toml::from_str(&content).map_err(|e| invalid(e.message().to_string()))?
+        };
+
+        options.output_path = options.output_path.as_deref().map(expand_path);
+        options.temp_dir = options.temp_dir.as_deref().map(expand_path);
+        Ok(options)
+    }
+
+    /// Check values and combinations of options
+    pub fn validate(&self) -> Result<(), RytError> {
+        if let Some(bytes_per_second) = self.rate_limit_bps {
+            check_rate_limit(bytes_per_second)?;
+        }
+        check_timeout("timeout", self.timeout)?;
+        check_timeout("connect timeout", self.connect_timeout)?;
+        if let Some(read_timeout) = self.read_timeout {
+            check_timeout("read timeout", read_timeout)?;
+        }
+        check_timeout("cipher timeout", self.cipher_timeout)?;
+        check_client(&self.client_name, &self.client_version)?;
+        for (name, version) in &self.client_fallback_chain {
+            check_client(name, version)?;
+        }
+        if let Some(path) = &self.output_path {
+            check_output_path(path)?;
+        }
+        check_write_buffer(self.write_buffer_size)?;
+        check_concurrent_fragments(self.concurrent_fragments)?;
+        if let Some(country) = &self.geo_bypass_country {
+            check_country(country)?;
+        }
+
+        let conflict = |message: &str| Err(RytError::ConflictingOptions(message.to_string()));
+        if self.rate_limit_bps.is_none() {
+            if self.rate_limit_after.is_some() {
+                return conflict("a rate limit grace period needs a rate limit");
+            }
+            if self.global_rate_limit {
+                return conflict("a global rate limit needs a rate limit");
+            }
+        }
+        if let (Some(throttled), Some(limit)) = (self.throttled_rate, self.rate_limit_bps) {
+            if throttled >= limit {
+                return conflict(
+                    "a throttled rate at or above the rate limit would abort every download",
+                );
+            }
+        }
+        if self.thumbnail_format.is_some() && !self.write_thumbnail {
+            return conflict("converting thumbnails needs thumbnails to be written");
+        }
+        if self.redownload_below.is_some() && self.download_archive.is_none() {
+            return conflict("re-downloading below a height needs a download archive");
+        }
+        if self.retry_failed && self.session_file.is_none() {
+            return conflict("retrying failed items needs a session file");
+        }
+        if let (Some(min), Some(max)) = (self.min_filesize, self.max_filesize) {
+            if min > max {
+                return conflict("the minimum file size is above the maximum");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`DownloadOptions`]
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptionsBuilder {
+    options: DownloadOptions,
+    /// Format selector, parsed at build time
+    format: Option<String>,
+}
+
+impl DownloadOptionsBuilder {
+    /// Set format selector, e.g. `best` or `bestvideo[height<=1080]+bestaudio`
+    pub fn with_format(mut self, selector: &str) -> Self {
+        self.format = Some(selector.to_string());
+        self
+    }
+
+    /// Set desired file extension
+    pub fn with_extension(mut self, ext: &str) -> Self {
+        self.options.desired_ext = Some(ext.to_string());
+        self
+    }
+
+    /// Set what to select when the format selector matches nothing
+    pub fn with_format_fallback(mut self, policy: FormatFallback) -> Self {
+        self.options.format_fallback = policy;
+        self
+    }
+
+    /// Set how many other formats to try after a 403 before switching clients
+    pub fn with_format_retries(mut self, retries: u32) -> Self {
+        self.options.format_retries = retries;
+        self
+    }
+
+    /// Set whether WebM formats are preferred over MP4
+    pub fn with_prefer_webm(mut self, prefer_webm: bool) -> Self {
+        self.options.prefer_webm = prefer_webm;
+        self
+    }
+
+    /// Set whether high frame rate formats are preferred for the best quality
+    pub fn with_prefer_60fps(mut self, prefer_60fps: bool) -> Self {
+        self.options.prefer_60fps = prefer_60fps;
+        self
+    }
+
+    /// Skip formats larger than `bytes`
+    pub fn with_max_filesize(mut self, bytes: u64) -> Self {
+        self.options.max_filesize = Some(bytes);
+        self
+    }
+
+    /// Skip formats smaller than `bytes`
+    pub fn with_min_filesize(mut self, bytes: u64) -> Self {
+        self.options.min_filesize = Some(bytes);
+        self
+    }
+
+    /// Set whether formats of unknown size are skipped when a size limit is set
+    pub fn with_reject_unknown_size(mut self, reject: bool) -> Self {
+        self.options.reject_unknown_size = reject;
+        self
+    }
+
+    /// Set whether a selected format outside the size limits is an error
+    /// instead of a reason to pick a smaller one
+    pub fn with_strict_format(mut self, strict: bool) -> Self {
+        self.options.strict_format = strict;
+        self
+    }
+
+    /// Set output path, expanding `~` and environment variables
+    pub fn with_output_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.output_path = Some(expand_path(&path.into()));
+        self
+    }
+
+    /// Set rate limit in bytes per second
+    pub fn with_rate_limit(mut self, bytes_per_second: u64) -> Self {
+        self.options.rate_limit_bps = Some(bytes_per_second);
+        self
+    }
+
+    /// Set threshold (bytes or time) after which rate limiting kicks in
+    pub fn with_rate_limit_after(mut self, grace: RateLimitGrace) -> Self {
+        self.options.rate_limit_after = Some(grace);
+        self
+    }
+
+    /// Share one rate limit across all downloads
+    pub fn with_global_rate_limit(mut self, global: bool) -> Self {
+        self.options.global_rate_limit = global;
+        self
+    }
+
+    /// Re-resolve the media URL when throughput stays below `bytes_per_second`
+    pub fn with_throttled_rate(mut self, bytes_per_second: u64) -> Self {
+        self.options.throttled_rate = Some(bytes_per_second);
+        self
+    }
+
+    /// Set InnerTube client
+    pub fn with_innertube_client(mut self, name: &str, version: &str) -> Self {
+        self.options.client_name = name.to_string();
+        self.options.client_version = version.to_string();
+        self
+    }
+
+    /// Set clients tried after the configured one, as `(name, version)` pairs
+    pub fn with_client_fallback_chain(mut self, chain: Vec<(String, String)>) -> Self {
+        self.options.client_fallback_chain = chain;
+        self
+    }
+
+    /// Set total timeout for API requests
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = timeout;
+        self
+    }
+
+    /// Set connection establishment timeout
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.options.connect_timeout = timeout;
+        self
+    }
+
+    /// Set idle timeout between chunks of a media body
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.options.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how long a player.js execution may run while deciphering
+    pub fn with_cipher_timeout(mut self, timeout: Duration) -> Self {
+        self.options.cipher_timeout = timeout;
+        self
+    }
+
+    /// Set address family for both API requests and media downloads
+    pub fn with_ip_family(mut self, ip_family: IpFamily) -> Self {
+        self.options.ip_family = ip_family;
+        self.options.media_ip_family = ip_family;
+        self
+    }
+
+    /// Set address family for media downloads only
+    pub fn with_media_ip_family(mut self, ip_family: IpFamily) -> Self {
+        self.options.media_ip_family = ip_family;
+        self
+    }
+
+    /// Set HTTP/2 use for API requests; media downloads stay on HTTP/1.1
+    pub fn with_api_http_version(mut self, http2: Http2Mode) -> Self {
+        self.options.api_http2 = http2;
+        self
+    }
+
+    /// Set the compression API requests advertise; media downloads never
+    /// ask for compression
+    pub fn with_api_accept_encoding(mut self, accept_encoding: AcceptEncoding) -> Self {
+        self.options.api_accept_encoding = accept_encoding;
+        self
+    }
+
+    /// Connect to `host` at `addrs` instead of resolving it
+    pub fn with_dns_override(mut self, host: &str, addrs: Vec<SocketAddr>) -> Self {
+        self.options.dns_overrides.insert(host.to_string(), addrs);
+        self
+    }
+
+    /// Log every request and response, showing up to `body_limit` bytes of request bodies
+    pub fn with_print_traffic(mut self, body_limit: usize) -> Self {
+        self.options.traffic_log = Some(TrafficLog::new(body_limit));
+        self
+    }
+
+    /// Set maximum retries
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.options.max_retries = max_retries;
+        self
+    }
+
+    /// Set maximum retries per media fragment
+    pub fn with_fragment_retries(mut self, fragment_retries: u32) -> Self {
+        self.options.fragment_retries = fragment_retries;
+        self
+    }
+
+    /// Set how many segments of a segmented (HLS) download are fetched at once
+    pub fn with_concurrent_fragments(mut self, concurrent_fragments: usize) -> Self {
+        self.options.concurrent_fragments = concurrent_fragments;
+        self
+    }
+
+    /// Set directory for partial and intermediate files, expanding `~` and
+    /// environment variables
+    pub fn with_temp_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.temp_dir = Some(expand_path(&path.into()));
+        self
+    }
+
+    /// Keep partial files of failed downloads for inspection
+    pub fn with_keep_temp(mut self, keep: bool) -> Self {
+        self.options.keep_temp_on_error = keep;
+        self
+    }
+
+    /// Set whether missing parent directories of the output path are created
+    pub fn with_create_dirs(mut self, create_dirs: bool) -> Self {
+        self.options.create_dirs = create_dirs;
+        self
+    }
+
+    /// Set write buffer size in bytes for streamed downloads
+    pub fn with_write_buffer(mut self, size: usize) -> Self {
+        self.options.write_buffer_size = size;
+        self
+    }
+
+    /// Set download archive file
+    pub fn with_download_archive(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.download_archive = Some(path.into());
+        self
+    }
+
+    /// Set download archive line format
+    pub fn with_archive_format(mut self, format: ArchiveFormat) -> Self {
+        self.options.archive_format = format;
+        self
+    }
+
+    /// Re-download archived videos recorded below this height
+    pub fn with_redownload_below(mut self, height: u32) -> Self {
+        self.options.redownload_below = Some(height);
+        self
+    }
+
+    /// Set session file for resumable playlist and batch jobs
+    pub fn with_session_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.session_file = Some(path.into());
+        self
+    }
+
+    /// Set whether failed session items are retried
+    pub fn with_retry_failed(mut self, retry_failed: bool) -> Self {
+        self.options.retry_failed = retry_failed;
+        self
+    }
+
+    /// Set playlist progress file; items downloaded in earlier runs are skipped
+    pub fn with_playlist_archive(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.playlist_archive = Some(expand_path(&path.into()));
+        self
+    }
+
+    /// Set whether the video thumbnail is written next to the downloaded file
+    pub fn with_write_thumbnail(mut self, write_thumbnail: bool) -> Self {
+        self.options.write_thumbnail = write_thumbnail;
+        self
+    }
+
+    /// Set whether the video description is written to a `.description` file
+    pub fn with_write_description(mut self, write_description: bool) -> Self {
+        self.options.write_description = write_description;
+        self
+    }
+
+    /// Set whether the video metadata is written to a `.info.json` file
+    pub fn with_write_infojson(mut self, write_infojson: bool) -> Self {
+        self.options.write_infojson = write_infojson;
+        self
+    }
+
+    /// Set whether playlist downloads also write the playlist metadata to
+    /// `<playlist_id>.info.json`
+    pub fn with_write_playlist_metafiles(mut self, write_playlist_metafiles: bool) -> Self {
+        self.options.write_playlist_metafiles = write_playlist_metafiles;
+        self
+    }
+
+    /// Set whether captions are written to a `.<lang>.vtt` file
+    pub fn with_write_subs(mut self, write_subs: bool) -> Self {
+        self.options.write_subs = write_subs;
+        self
+    }
+
+    /// Set whether the SHA-256 of the downloaded file is written to a
+    /// `.sha256` file
+    pub fn with_write_checksum(mut self, write_checksum: bool) -> Self {
+        self.options.write_checksum = write_checksum;
+        self
+    }
+
+    /// Set the language code of the captions to write
+    pub fn with_sub_lang(mut self, sub_lang: &str) -> Self {
+        self.options.sub_lang = sub_lang.to_string();
+        self
+    }
+
+    /// Also write a shortcut file of `kind` pointing to the video page
+    pub fn with_write_link(mut self, kind: LinkKind) -> Self {
+        if !self.options.write_links.contains(&kind) {
+            self.options.write_links.push(kind);
+        }
+        self
+    }
+
+    /// Set whether the category and comment count are fetched with an extra request
+    pub fn with_fetch_extra_metadata(mut self, fetch: bool) -> Self {
+        self.options.fetch_extra_metadata = fetch;
+        self
+    }
+
+    /// Set format written thumbnails are converted to
+    pub fn with_thumbnail_format(mut self, format: ThumbnailFormat) -> Self {
+        self.options.thumbnail_format = Some(format);
+        self
+    }
+
+    /// Set filter videos must pass to be downloaded
+    pub fn with_match_filter(mut self, filter: MatchFilter) -> Self {
+        self.options.match_filter = Some(filter);
+        self
+    }
+
+    /// Set country sent with API requests, e.g. `US`
+    pub fn with_geo_bypass_country(mut self, country: &str) -> Self {
+        self.options.geo_bypass_country = Some(country.to_uppercase());
+        self
+    }
+
+    /// Set host language sent with API requests, e.g. `en`
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.options.language = Some(language.to_string());
+        self
+    }
+
+    /// Parse the format selector and validate all options
+    pub fn build(self) -> Result<DownloadOptions, RytError> {
+        let mut options = self.options;
+        if let Some(format) = &self.format {
+            let mut selector = parse_format_selector(format)?;
+            if let Some(ext) = options.desired_ext.as_deref().filter(|ext| !ext.is_empty()) {
+                if selector.extension.is_none() {
+                    selector = selector.with_extension(ext);
+                }
+            }
+            options.format_selector = Some(selector);
+        }
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_option(result: Result<DownloadOptions, RytError>) -> String {
+        match result {
+            Err(RytError::InvalidOption(message)) => message,
+            other => panic!("expected InvalidOption, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    fn conflicting(result: Result<DownloadOptions, RytError>) -> String {
+        match result {
+            Err(RytError::ConflictingOptions(message)) => message,
+            other => panic!("expected ConflictingOptions, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_builder_defaults_are_valid() {
+        let options = DownloadOptions::builder().build().unwrap();
+        assert_eq!(options.client_name, "ANDROID");
+        assert!(options.format_selector.is_none());
+        assert!(DownloadOptions::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_builder_sets_options() {
+        let options = DownloadOptions::builder()
+            .with_format("best[height<=720]")
+            .with_extension("webm")
+            .with_output_path("downloads")
+            .with_rate_limit(MIN_RATE_LIMIT_BPS + 1)
+            .with_global_rate_limit(true)
+            .with_request_timeout(Duration::from_secs(5))
+            .with_ip_family(IpFamily::V4)
+            .with_media_ip_family(IpFamily::V6)
+            .with_dns_override("example.com", vec!["127.0.0.1:443".parse().unwrap()])
+            .with_print_traffic(64)
+            .with_write_thumbnail(true)
+            .with_thumbnail_format(ThumbnailFormat::Png)
+            .with_download_archive("archive.txt")
+            .with_redownload_below(720)
+            .with_session_file("session.json")
+            .with_retry_failed(true)
+            .with_geo_bypass_country("de")
+            .with_language("de")
+            .build()
+            .unwrap();
+
+        let selector = options.format_selector.unwrap();
+        assert_eq!(selector.height_limit, Some(720));
+        assert_eq!(selector.extension.as_deref(), Some("webm"));
+        assert_eq!(options.output_path, Some(PathBuf::from("downloads")));
+        assert_eq!(options.rate_limit_bps, Some(MIN_RATE_LIMIT_BPS + 1));
+        assert_eq!(options.timeout, Duration::from_secs(5));
+        assert_eq!(options.ip_family, IpFamily::V4);
+        assert_eq!(options.media_ip_family, IpFamily::V6);
+        assert_eq!(
+            options.dns_overrides["example.com"],
+            vec!["127.0.0.1:443".parse::<SocketAddr>().unwrap()]
+        );
+        assert_eq!(options.traffic_log, Some(TrafficLog::new(64)));
+        assert_eq!(options.redownload_below, Some(720));
+        assert_eq!(options.geo_bypass_country.as_deref(), Some("DE"));
+        assert_eq!(options.language.as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn test_builder_keeps_selector_extension() {
+        let options = DownloadOptions::builder()
+            .with_format("best[ext=mp4]")
+            .with_extension("webm")
+            .build()
+            .unwrap();
+        assert_eq!(
+            options.format_selector.unwrap().extension.as_deref(),
+            Some("mp4")
+        );
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_values() {
+        let message = invalid_option(
+            DownloadOptions::builder()
+                .with_rate_limit(MIN_RATE_LIMIT_BPS)
+                .build(),
+        );
+        assert!(message.contains("1 KB/s"));
+        assert!(DownloadOptions::builder()
+            .with_rate_limit(0)
+            .build()
+            .is_err());
+
+        let message = invalid_option(
+            DownloadOptions::builder()
+                .with_request_timeout(Duration::ZERO)
+                .build(),
+        );
+        assert_eq!(message, "timeout must be greater than zero");
+        let message = invalid_option(
+            DownloadOptions::builder()
+                .with_connect_timeout(Duration::ZERO)
+                .build(),
+        );
+        assert!(message.starts_with("connect timeout"));
+        let message = invalid_option(
+            DownloadOptions::builder()
+                .with_read_timeout(Duration::ZERO)
+                .build(),
+        );
+        assert!(message.starts_with("read timeout"));
+        let message = invalid_option(
+            DownloadOptions::builder()
+                .with_cipher_timeout(Duration::ZERO)
+                .build(),
+        );
+        assert!(message.starts_with("cipher timeout"));
+
+        let message = invalid_option(
+            DownloadOptions::builder()
+                .with_innertube_client(" ", "1.0")
+                .build(),
+        );
+        assert!(message.contains("client name"));
+        let message = invalid_option(
+            DownloadOptions::builder()
+                .with_innertube_client("WEB", "")
+                .build(),
+        );
+        assert!(message.contains("client version for WEB"));
+        let message = invalid_option(
+            DownloadOptions::builder()
+                .with_client_fallback_chain(vec![("IOS".to_string(), String::new())])
+                .build(),
+        );
+        assert!(message.contains("client version for IOS"));
+
+        let message = invalid_option(DownloadOptions::builder().with_output_path("").build());
+        assert!(message.contains("output path"));
+        assert!(matches!(
+            DownloadOptions::builder()
+                .with_output_path("%(uploader)s/%(title.%(ext)s")
+                .build(),
+            Err(RytError::InvalidTemplate(_))
+        ));
+        assert!(DownloadOptions::builder()
+            .with_output_path("%(uploader)s/%(title)s.%(ext)s")
+            .build()
+            .is_ok());
+        let message = invalid_option(DownloadOptions::builder().with_write_buffer(0).build());
+        assert!(message.contains("write buffer"));
+        for country in ["USA", "U", "1A", ""] {
+            let message = invalid_option(
+                DownloadOptions::builder()
+                    .with_geo_bypass_country(country)
+                    .build(),
+            );
+            assert!(message.contains("two-letter"), "{}", country);
+        }
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_format() {
+        let result = DownloadOptions::builder()
+            .with_format("best[height<=abc]")
+            .build();
+        assert!(matches!(result, Err(RytError::InvalidFormatSelector(_))));
+    }
+
+    #[test]
+    fn test_builder_rejects_incompatible_pairs() {
+        let message = conflicting(
+            DownloadOptions::builder()
+                .with_rate_limit_after(RateLimitGrace::Bytes(1024))
+                .build(),
+        );
+        assert!(message.contains("grace period"));
+        let message = conflicting(
+            DownloadOptions::builder()
+                .with_global_rate_limit(true)
+                .build(),
+        );
+        assert!(message.contains("global rate limit"));
+        let message = conflicting(
+            DownloadOptions::builder()
+                .with_thumbnail_format(ThumbnailFormat::Jpg)
+                .build(),
+        );
+        assert!(message.contains("thumbnails"));
+        let message = conflicting(
+            DownloadOptions::builder()
+                .with_redownload_below(720)
+                .build(),
+        );
+        assert!(message.contains("download archive"));
+        let message = conflicting(DownloadOptions::builder().with_retry_failed(true).build());
+        assert!(message.contains("session file"));
+        let message = conflicting(
+            DownloadOptions::builder()
+                .with_min_filesize(2048)
+                .with_max_filesize(1024)
+                .build(),
+        );
+        assert!(message.contains("minimum file size"));
+        let message = conflicting(
+            DownloadOptions::builder()
+                .with_rate_limit(50_000)
+                .with_throttled_rate(50_000)
+                .build(),
+        );
+        assert!(message.contains("throttled rate"));
+
+        // Each pair is fine once its requirement is set
+        assert!(DownloadOptions::builder()
+            .with_rate_limit(4096)
+            .with_rate_limit_after(RateLimitGrace::Bytes(1024))
+            .with_global_rate_limit(true)
+            .with_throttled_rate(1024)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_catches_direct_edits() {
+        let mut options = DownloadOptions::default();
+        options.client_name.clear();
+        assert!(matches!(
+            options.validate(),
+            Err(RytError::InvalidOption(_))
+        ));
+
+        let options = DownloadOptions {
+            read_timeout: None,
+            ..DownloadOptions::default()
+        };
+        assert!(options.validate().is_ok());
+
+        let options = DownloadOptions {
+            concurrent_fragments: 0,
+            ..DownloadOptions::default()
+        };
+        assert!(matches!(
+            options.validate(),
+            Err(RytError::InvalidOption(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_file_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ryt.toml");
+        std::fs::write(
+            &path,
+            r#"
+            format = "bestvideo[height<=1080]+bestaudio"
+            output = "downloads/%(title)s.%(ext)s"
+            rate_limit_bps = 2097152
+            rate_limit_after = { duration = "10s" }
+            client_name = "WEB"
+            client_version = "2.20251002"
+            max_retries = 5
+            timeout = "1m30s"
+            connect_timeout = 5
+            ip_family = "v4"
+            write_links = ["url", "desktop"]
+            match_filter = "duration < 600"
+            "#,
+        )
+        .unwrap();
+
+        let options = DownloadOptions::from_file(&path).unwrap();
+        let selector = options.format_selector.as_ref().unwrap();
+        assert_eq!(selector.height_limit, Some(1080));
+        assert!(selector.audio.is_some());
+        assert_eq!(
+            options.output_path,
+            Some(PathBuf::from("downloads/%(title)s.%(ext)s"))
+        );
+        assert_eq!(options.rate_limit_bps, Some(2_097_152));
+        assert_eq!(
+            options.rate_limit_after,
+            Some(RateLimitGrace::Duration(Duration::from_secs(10)))
+        );
+        assert_eq!(options.client_name, "WEB");
+        assert_eq!(options.max_retries, 5);
+        assert_eq!(options.timeout, Duration::from_secs(90));
+        assert_eq!(options.connect_timeout, Duration::from_secs(5));
+        assert_eq!(options.ip_family, IpFamily::V4);
+        assert_eq!(options.write_links, vec![LinkKind::Url, LinkKind::Desktop]);
+        assert_eq!(options.match_filter.unwrap().to_string(), "duration < 600");
+        // Unset options keep their defaults
+        assert_eq!(options.fragment_retries, 10);
+        assert_eq!(options.concurrent_fragments, 1);
+        assert_eq!(
+            options.read_timeout,
+            DownloadOptions::default().read_timeout
+        );
+    }
+
+    #[test]
+    fn test_from_file_round_trip() {
+        let options = DownloadOptions::builder()
+            .with_format("best[height<=720][ext=mp4]")
+            .with_output_path("out")
+            .with_rate_limit(MIN_RATE_LIMIT_BPS + 1)
+            .with_rate_limit_after(RateLimitGrace::Bytes(1 << 20))
+            .with_request_timeout(Duration::from_millis(2500))
+            .with_innertube_client("IOS", "19.29.1")
+            .with_max_retries(8)
+            .with_print_traffic(64)
+            .with_archive_format(ArchiveFormat::Extended)
+            .with_write_thumbnail(true)
+            .with_thumbnail_format(ThumbnailFormat::Jpg)
+            .with_dns_override("example.com", vec!["127.0.0.1:443".parse().unwrap()])
+            .build()
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("ryt.toml");
+        let json_path = dir.path().join("ryt.json");
+        std::fs::write(&toml_path, toml::to_string(&options).unwrap()).unwrap();
+        std::fs::write(&json_path, serde_json::to_string(&options).unwrap()).unwrap();
+
+        for path in [toml_path, json_path] {
+            let loaded = DownloadOptions::from_file(&path).unwrap();
+            assert!(loaded.validate().is_ok());
+            let selector = loaded.format_selector.as_ref().unwrap();
+            assert_eq!(selector.height_limit, Some(720));
+            assert_eq!(selector.extension.as_deref(), Some("mp4"));
+            assert_eq!(
+                loaded.rate_limit_after,
+                Some(RateLimitGrace::Bytes(1 << 20))
+            );
+            assert_eq!(loaded.timeout, Duration::from_millis(2500));
+            assert_eq!(loaded.client_name, "IOS");
+            assert_eq!(loaded.max_retries, 8);
+            assert_eq!(loaded.traffic_log, Some(TrafficLog::new(64)));
+            assert_eq!(loaded.archive_format, ArchiveFormat::Extended);
+            assert_eq!(loaded.thumbnail_format, Some(ThumbnailFormat::Jpg));
+            assert_eq!(loaded.dns_overrides, options.dns_overrides);
+            assert_eq!(
+                serde_json::to_value(&loaded).unwrap(),
+                serde_json::to_value(&options).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ryt.toml");
+        let load = |content: &str| {
+            std::fs::write(&path, content).unwrap();
+            match DownloadOptions::from_file(&path) {
+                Err(RytError::InvalidConfig { message, .. }) => message,
+                other => panic!("expected InvalidConfig, got {:?}", other.map(|_| ())),
+            }
+        };
+
+        assert!(load("rate_limit = 4096").contains("unknown field `rate_limit`"));
+        assert!(load("timeout = \"5 parsecs\"").contains("unknown unit 'parsecs'"));
+        assert!(load("format = \"best[tbr>5]\"").contains("tbr"));
+        assert!(load("match_filter = \"< 5\"").contains("missing field"));
+        assert!(matches!(
+            DownloadOptions::from_file(dir.path().join("missing.toml")),
+            Err(RytError::Io { op: "read", .. })
+        ));
+    }
+}
@@ -0,0 +1,568 @@
+//! Playlist and batch run controls
+
+use crate::core::video_info::{PlaylistItem, VideoInfo};
+use crate::error::RytError;
+use crate::utils::parse_duration;
+use rand::Rng;
+use std::time::Duration;
+
+/// Random wait between two playlist or batch items
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SleepInterval {
+    /// Minimum wait
+    pub min: Duration,
+    /// Maximum wait
+    pub max: Duration,
+}
+
+impl SleepInterval {
+    /// Create a sleep interval; `max` is raised to `min` if lower
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max: max.max(min),
+        }
+    }
+
+    /// Parse `MIN[-MAX]`, each either plain seconds (e.g. `5`, `1.5`) or a
+    /// duration (e.g. `30s`, `2min`)
+    pub fn parse(s: &str) -> Option<Self> {
+        let (min, max) = match s.split_once('-') {
+            Some((min, max)) => (parse_duration(min).ok()?, parse_duration(max).ok()?),
+            None => {
+                let min = parse_duration(s).ok()?;
+                (min, min)
+            }
+        };
+        (max >= min).then_some(Self { min, max })
+    }
+
+    /// Pick a random duration within the interval
+    pub fn sample(&self) -> Duration {
+        if self.max <= self.min {
+            return self.min;
+        }
+        let millis = rand::thread_rng().gen_range(self.min.as_millis()..=self.max.as_millis());
+        Duration::from_millis(millis as u64)
+    }
+}
+
+/// Range of playlist positions, 1-based; negative positions count from the end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ItemRange {
+    start: i64,
+    /// Last position, or `None` for the end of the playlist
+    end: Option<i64>,
+}
+
+/// Selection of playlist items, e.g. `1,3,5-8,10-`
+///
+/// Each comma-separated part is a position, a range `START-END` or an open
+/// range `START-`. Positions are 1-based and negative ones count from the
+/// end, so `-1` is the last item and `-3-` the last three. A range whose
+/// start comes after its end selects items in reverse, e.g. `8-5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistItemsSpec {
+    spec: String,
+    ranges: Vec<ItemRange>,
+}
+
+impl PlaylistItemsSpec {
+    /// Parse a playlist items specification
+    pub fn parse(spec: &str) -> Result<Self, RytError> {
+        let ranges = spec
+            .split(',')
+            .map(|part| parse_item_range(part.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            spec: spec.trim().to_string(),
+            ranges,
+        })
+    }
+
+    /// Get the highest position needed, or `None` if the whole playlist must
+    /// be fetched (open or negative ranges)
+    pub fn max_position(&self) -> Option<usize> {
+        self.ranges
+            .iter()
+            .map(|range| match range.end {
+                Some(end) if end > 0 && range.start > 0 => Some(range.start.max(end) as usize),
+                _ => None,
+            })
+            .try_fold(0, |max, position| position.map(|p| max.max(p)))
+    }
+
+    /// Get the selected 0-based indices for a playlist of `len` items, in
+    /// selection order and without duplicates
+    pub fn indices(&self, len: usize) -> Vec<usize> {
+        let len = len as i64;
+        // Negative positions count from the end; -1 is the last item
+        let resolve = |position: i64| {
+            if position < 0 {
+                len + 1 + position
+            } else {
+                position
+            }
+        };
+        let mut indices = Vec::new();
+        let mut push = |position: i64| {
+            let index = (position - 1) as usize;
+            if (1..=len).contains(&position) && !indices.contains(&index) {
+                indices.push(index);
+            }
+        };
+
+        for range in &self.ranges {
+            let start = resolve(range.start);
+            match range.end.map(resolve) {
+                Some(end) if start > end => (end.max(1)..=start.min(len)).rev().for_each(&mut push),
+                end => (start.max(1)..=end.unwrap_or(len).min(len)).for_each(&mut push),
+            }
+        }
+        indices
+    }
+
+    /// Keep only the selected items, in selection order
+    pub fn select<T: Clone>(&self, items: &[T]) -> Vec<T> {
+        self.indices(items.len())
+            .into_iter()
+            .map(|index| items[index].clone())
+            .collect()
+    }
+}
+
+impl std::fmt::Display for PlaylistItemsSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+/// Parse `N`, `START-END` or `START-`, each position optionally negative
+fn parse_item_range(part: &str) -> Result<ItemRange, RytError> {
+    let invalid =
+        |reason: &str| RytError::InvalidPlaylistItems(format!("{} in '{}'", reason, part));
+    let parse_position = |s: &str| -> Result<i64, RytError> {
+        match s.parse::<i64>() {
+            Ok(0) => Err(invalid("positions start at 1")),
+            Ok(position) => Ok(position),
+            Err(_) => Err(invalid("invalid position")),
+        }
+    };
+
+    if part.is_empty() {
+        return Err(invalid("empty item"));
+    }
+    // Skip a leading minus so "-3-5" splits after the start position
+    let sign_len = usize::from(part.starts_with('-'));
+    match part[sign_len..].find('-') {
+        None => {
+            let position = parse_position(part)?;
+            Ok(ItemRange {
+                start: position,
+                end: Some(position),
+            })
+        }
+        Some(offset) => {
+            let (start, end) = part.split_at(sign_len + offset);
+            let end = &end[1..];
+            Ok(ItemRange {
+                start: parse_position(start)?,
+                end: if end.is_empty() {
+                    None
+                } else {
+                    Some(parse_position(end)?)
+                },
+            })
+        }
+    }
+}
+
+/// Outcome of a single playlist or batch item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemOutcome {
+    /// Downloaded successfully
+    Downloaded,
+    /// Skipped because it is archived or its output file exists
+    Existing,
+    /// Skipped because it does not pass the match filter, or is members-only
+    /// or paid
+    Skipped,
+    /// Download failed
+    Failed,
+}
+
+/// A playlist or batch item that failed to download
+#[derive(Debug)]
+pub struct PlaylistDownloadError {
+    /// The failed item
+    pub item: PlaylistItem,
+    /// Why it failed
+    pub error: RytError,
+}
+
+/// Result of a playlist or batch run
+///
+/// Items skipped as already downloaded or filtered out are in neither list.
+#[derive(Debug, Default)]
+pub struct PlaylistDownloadResult {
+    /// Videos downloaded successfully, in order
+    pub succeeded: Vec<VideoInfo>,
+    /// Items that failed, in order
+    pub failed: Vec<PlaylistDownloadError>,
+    /// Items skipped because they are in the download archive
+    pub archived: usize,
+    /// Items skipped because the same video came up earlier in the run
+    pub duplicates: usize,
+    /// Items skipped because they are for channel members only
+    pub members_only: usize,
+    /// Items skipped because they have to be rented or bought
+    pub payment_required: usize,
+}
+
+impl PlaylistDownloadResult {
+    /// Get the number of items that were downloaded or failed
+    pub fn attempted(&self) -> usize {
+        self.succeeded.len() + self.failed.len()
+    }
+
+    /// Check if items failed and none was downloaded
+    pub fn all_failed(&self) -> bool {
+        self.succeeded.is_empty() && !self.failed.is_empty()
+    }
+}
+
+/// Controls for playlist and batch runs
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistOptions {
+    /// Wait a random duration from this interval between items
+    pub sleep_interval: Option<SleepInterval>,
+    /// Stop after this many successful downloads
+    pub max_downloads: Option<usize>,
+    /// Stop the whole run at the first archived or existing item
+    pub break_on_existing: bool,
+    /// Stop the whole run at the first failed item
+    pub abort_on_error: bool,
+    /// Process playlist items in reverse order
+    pub reverse: bool,
+    /// Only process these playlist items
+    pub items: Option<PlaylistItemsSpec>,
+}
+
+impl PlaylistOptions {
+    /// Check the options for combinations that can't work together
+    pub fn validate(&self) -> Result<(), RytError> {
+        if self.break_on_existing && self.reverse {
+            // Reversed, the oldest items come first and are usually the ones
+            // already downloaded, so the run would stop before any new upload
+            return Err(RytError::ConflictingOptions(
+                "--break-on-existing stops at the first known item, which is the oldest one \
+                 with --playlist-reverse; drop one of the two"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Get the number of playlist items to fetch for a run limited to
+    /// `limit` items, or `None` for all of them
+    pub fn fetch_limit(&self, limit: Option<usize>) -> Option<usize> {
+        match &self.items {
+            Some(items) => items.max_position(),
+            None => limit,
+        }
+    }
+
+    /// Select, limit and order fetched playlist items for a run
+    pub fn apply<T: Clone>(&self, mut items: Vec<T>, limit: Option<usize>) -> Vec<T> {
+        if let Some(spec) = &self.items {
+            items = spec.select(&items);
+        }
+        if let Some(limit) = limit {
+            items.truncate(limit);
+        }
+        if self.reverse {
+            items.reverse();
+        }
+        items
+    }
+
+    /// Get the wait before the next item, if a sleep interval is set
+    pub fn sleep_duration(&self) -> Option<Duration> {
+        self.sleep_interval.map(|interval| interval.sample())
+    }
+
+    /// Get the reason to stop before the next item, given the outcome of
+    /// the previous item and the number of successful downloads so far
+    pub fn stop_reason(&self, last: Option<ItemOutcome>, downloaded: usize) -> Option<String> {
+        if self.break_on_existing && last == Some(ItemOutcome::Existing) {
+            return Some("reached an already downloaded item".to_string());
+        }
+        if self.abort_on_error && last == Some(ItemOutcome::Failed) {
+            return Some("an item failed to download".to_string());
+        }
+        match self.max_downloads {
+            Some(max) if downloaded >= max => {
+                Some(format!("reached the maximum of {} downloads", max))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ItemOutcome::{Downloaded, Existing, Failed, Skipped};
+
+    /// Run mocked items until a stop condition hits, returning the processed ones
+    fn run(options: &PlaylistOptions, items: &[ItemOutcome]) -> usize {
+        let mut last = None;
+        let mut downloaded = 0;
+        let mut processed = 0;
+        for outcome in items {
+            if options.stop_reason(last, downloaded).is_some() {
+                break;
+            }
+            if *outcome == ItemOutcome::Downloaded {
+                downloaded += 1;
+            }
+            last = Some(*outcome);
+            processed += 1;
+        }
+        processed
+    }
+
+    #[test]
+    fn test_max_downloads_counts_successes_only() {
+        let options = PlaylistOptions {
+            max_downloads: Some(2),
+            ..PlaylistOptions::default()
+        };
+        let items = [Downloaded, Failed, Existing, Downloaded, Downloaded];
+        assert_eq!(run(&options, &items), 4);
+
+        let options = PlaylistOptions {
+            max_downloads: Some(0),
+            ..PlaylistOptions::default()
+        };
+        assert_eq!(run(&options, &items), 0);
+        assert_eq!(run(&PlaylistOptions::default(), &items), 5);
+    }
+
+    #[test]
+    fn test_break_on_existing() {
+        let options = PlaylistOptions {
+            break_on_existing: true,
+            ..PlaylistOptions::default()
+        };
+        assert_eq!(
+            run(&options, &[Downloaded, Failed, Existing, Downloaded]),
+            3
+        );
+        assert_eq!(run(&options, &[Downloaded, Downloaded]), 2);
+        // Filtered out items are not already downloaded ones
+        assert_eq!(run(&options, &[Skipped, Skipped, Downloaded]), 3);
+        assert!(options
+            .stop_reason(Some(Existing), 0)
+            .unwrap()
+            .contains("already downloaded"));
+
+        // Without the flag, existing items are skipped
+        let options = PlaylistOptions::default();
+        assert_eq!(run(&options, &[Existing, Downloaded]), 2);
+    }
+
+    #[test]
+    fn test_abort_on_error() {
+        let options = PlaylistOptions {
+            abort_on_error: true,
+            ..PlaylistOptions::default()
+        };
+        assert_eq!(run(&options, &[Downloaded, Failed, Downloaded]), 2);
+        assert_eq!(run(&options, &[Existing, Skipped, Downloaded]), 3);
+        assert!(options
+            .stop_reason(Some(Failed), 0)
+            .unwrap()
+            .contains("failed"));
+
+        // Without the flag, failed items are skipped
+        let options = PlaylistOptions::default();
+        assert_eq!(run(&options, &[Failed, Failed, Downloaded]), 3);
+    }
+
+    #[test]
+    fn test_playlist_download_result_counts() {
+        let mut result = PlaylistDownloadResult::default();
+        assert_eq!(result.attempted(), 0);
+        assert!(!result.all_failed());
+
+        result.failed.push(PlaylistDownloadError {
+            item: PlaylistItem::new("id".to_string(), "Title".to_string(), 1),
+            error: RytError::VideoUnavailable,
+        });
+        assert_eq!(result.attempted(), 1);
+        assert!(result.all_failed());
+
+        result
+            .succeeded
+            .push(VideoInfo::new("id2".to_string(), "Other".to_string()));
+        assert_eq!(result.attempted(), 2);
+        assert!(!result.all_failed());
+    }
+
+    #[test]
+    fn test_validate_rejects_break_on_existing_with_reverse() {
+        let options = PlaylistOptions {
+            break_on_existing: true,
+            reverse: true,
+            ..PlaylistOptions::default()
+        };
+        let err = options.validate().unwrap_err();
+        assert!(matches!(err, RytError::ConflictingOptions(_)));
+        assert!(err.to_string().contains("--playlist-reverse"));
+
+        let options = PlaylistOptions {
+            reverse: true,
+            ..PlaylistOptions::default()
+        };
+        assert!(options.validate().is_ok());
+    }
+
+    fn indices(spec: &str, len: usize) -> Vec<usize> {
+        PlaylistItemsSpec::parse(spec).unwrap().indices(len)
+    }
+
+    #[test]
+    fn test_playlist_items_single_positions() {
+        assert_eq!(indices("1", 10), vec![0]);
+        assert_eq!(indices("1,3,5", 10), vec![0, 2, 4]);
+        assert_eq!(indices(" 3 , 1 ", 10), vec![2, 0]);
+        // Out of range positions select nothing
+        assert_eq!(indices("11", 10), Vec::<usize>::new());
+        // Duplicates are selected once
+        assert_eq!(indices("2,2,1-3", 10), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn test_playlist_items_ranges() {
+        assert_eq!(indices("5-8", 10), vec![4, 5, 6, 7]);
+        assert_eq!(
+            indices("1,3,5-8,10-", 12),
+            vec![0, 2, 4, 5, 6, 7, 9, 10, 11]
+        );
+        assert_eq!(indices("8-", 10), vec![7, 8, 9]);
+        // Ranges are clamped to the playlist
+        assert_eq!(indices("8-20", 10), vec![7, 8, 9]);
+        assert_eq!(indices("20-", 10), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_playlist_items_reverse_ranges() {
+        assert_eq!(indices("8-5", 10), vec![7, 6, 5, 4]);
+        assert_eq!(indices("12-9", 10), vec![9, 8]);
+        assert_eq!(indices("-1-1", 4), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_playlist_items_negative_positions() {
+        assert_eq!(indices("-1", 10), vec![9]);
+        assert_eq!(indices("-3-", 10), vec![7, 8, 9]);
+        assert_eq!(indices("-3--2", 10), vec![7, 8]);
+        assert_eq!(indices("2--2", 5), vec![1, 2, 3]);
+        assert_eq!(indices("1,-1", 1), vec![0]);
+        assert_eq!(indices("-20", 10), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_playlist_items_parse_errors() {
+        for spec in ["", "1,", "0", "a", "1-b", "1-2-3", "--1", "1.5", "3-0"] {
+            assert!(
+                matches!(
+                    PlaylistItemsSpec::parse(spec),
+                    Err(RytError::InvalidPlaylistItems(_))
+                ),
+                "{}",
+                spec
+            );
+        }
+        let err = PlaylistItemsSpec::parse("1,0").unwrap_err();
+        assert!(err.to_string().contains("positions start at 1"));
+        assert_eq!(
+            PlaylistItemsSpec::parse(" 1,3-5 ").unwrap().to_string(),
+            "1,3-5"
+        );
+    }
+
+    #[test]
+    fn test_playlist_items_max_position() {
+        let max = |spec: &str| PlaylistItemsSpec::parse(spec).unwrap().max_position();
+        assert_eq!(max("1,3,5-8"), Some(8));
+        assert_eq!(max("8-5"), Some(8));
+        assert_eq!(max("1,10-"), None);
+        assert_eq!(max("-1"), None);
+    }
+
+    #[test]
+    fn test_playlist_options_apply() {
+        let items: Vec<u32> = (1..=10).collect();
+        let options = PlaylistOptions {
+            items: Some(PlaylistItemsSpec::parse("2,4-6").unwrap()),
+            ..PlaylistOptions::default()
+        };
+        assert_eq!(options.fetch_limit(Some(3)), Some(6));
+        assert_eq!(options.apply(items.clone(), None), vec![2, 4, 5, 6]);
+        assert_eq!(options.apply(items.clone(), Some(2)), vec![2, 4]);
+
+        let options = PlaylistOptions {
+            reverse: true,
+            ..options
+        };
+        assert_eq!(options.apply(items.clone(), None), vec![6, 5, 4, 2]);
+
+        let options = PlaylistOptions::default();
+        assert_eq!(options.fetch_limit(Some(3)), Some(3));
+        assert_eq!(options.apply(items, None).len(), 10);
+    }
+
+    #[test]
+    fn test_sleep_interval_parse() {
+        assert_eq!(
+            SleepInterval::parse("5"),
+            Some(SleepInterval::new(
+                Duration::from_secs(5),
+                Duration::from_secs(5)
+            ))
+        );
+        assert_eq!(
+            SleepInterval::parse("1.5-30s"),
+            Some(SleepInterval::new(
+                Duration::from_millis(1500),
+                Duration::from_secs(30)
+            ))
+        );
+        assert_eq!(
+            SleepInterval::parse("1min-2min"),
+            Some(SleepInterval::new(
+                Duration::from_secs(60),
+                Duration::from_secs(120)
+            ))
+        );
+        assert_eq!(SleepInterval::parse("30-5"), None);
+        assert_eq!(SleepInterval::parse("-5"), None);
+        assert_eq!(SleepInterval::parse("soon"), None);
+    }
+
+    #[test]
+    fn test_sleep_interval_sample() {
+        let interval = SleepInterval::new(Duration::from_secs(1), Duration::from_secs(3));
+        for _ in 0..50 {
+            let duration = interval.sample();
+            assert!(duration >= interval.min && duration <= interval.max);
+        }
+
+        let fixed = SleepInterval::new(Duration::from_secs(2), Duration::from_secs(1));
+        assert_eq!(fixed.max, fixed.min);
+        assert_eq!(fixed.sample(), Duration::from_secs(2));
+        assert_eq!(PlaylistOptions::default().sleep_duration(), None);
+    }
+}
@@ -0,0 +1,324 @@
+//! Postprocessing (ffmpeg) support
+
+use crate::core::progress::{Progress, ProgressPhase};
+use crate::core::video_info::Format;
+use crate::error::RytError;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+
+/// Parser for ffmpeg `-progress pipe:` output
+///
+/// ffmpeg writes blocks of `key=value` lines terminated by
+/// `progress=continue` or `progress=end`. One `Progress` event is produced
+/// per block, measured in milliseconds of media processed.
+pub struct FfmpegProgressParser {
+    progress: Progress,
+    out_time_ms: u64,
+}
+
+impl FfmpegProgressParser {
+    /// Create a new parser
+    ///
+    /// Without a known duration the events carry `total_size == 0`, which
+    /// consumers should render as an indeterminate spinner.
+    pub fn new(phase: ProgressPhase, total_duration: Option<Duration>) -> Self {
+        let total_ms = total_duration
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+
+        Self {
+            progress: Progress::with_phase(total_ms, phase),
+            out_time_ms: 0,
+        }
+    }
+
+    /// Feed a single output line, returning an event at the end of each block
+    pub fn feed_line(&mut self, line: &str) -> Option<Progress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+
+        match key.trim() {
+            // Despite its name, out_time_ms is reported in microseconds
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<u64>() {
+                    self.out_time_ms = micros / 1000;
+                }
+                None
+            }
+            "progress" => {
+                let processed = if value == "end" && self.progress.total_size > 0 {
+                    self.progress.total_size
+                } else {
+                    self.out_time_ms
+                };
+                self.progress.update(processed);
+                Some(self.progress.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse a complete chunk of ffmpeg progress output
+    pub fn parse(mut self, output: &str) -> Vec<Progress> {
+        output
+            .lines()
+            .filter_map(|line| self.feed_line(line))
+            .collect()
+    }
+}
+
+/// Get the ffmpeg audio codec for muxing `audio` into `container`.
+///
+/// Dolby Atmos (E-AC-3) is always copied, since transcoding it drops the
+/// Atmos metadata. Other streams are copied unless MP4 can't hold them.
+pub fn mux_audio_codec(audio: &Format, container: &str) -> &'static str {
+    if audio.is_dolby_atmos() {
+        return "copy";
+    }
+
+    let codec = audio.audio_codec.as_deref().unwrap_or_default();
+    if container == "mp4" && (codec.contains("opus") || codec.contains("vorbis")) {
+        "aac"
+    } else {
+        "copy"
+    }
+}
+
+/// Get the container for muxing `video` and `audio`: WebM when both streams
+/// are WebM (VP9/AV1 + Opus), MP4 otherwise
+pub fn mux_container(video: &Format, audio: &Format) -> &'static str {
+    if video.is_webm() && audio.is_webm() {
+        "webm"
+    } else {
+        "mp4"
+    }
+}
+
+/// Build ffmpeg arguments muxing a video and an audio file into `output`
+pub fn mux_args(video: &Path, audio: &Path, audio_format: &Format, output: &Path) -> Vec<String> {
+    let container = output
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("mp4");
+
+    vec![
+        "-y".to_string(),
+        "-i".to_string(),
+        video.display().to_string(),
+        "-i".to_string(),
+        audio.display().to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+        "-map".to_string(),
+        "1:a:0".to_string(),
+        "-c:v".to_string(),
+        "copy".to_string(),
+        "-c:a".to_string(),
+        mux_audio_codec(audio_format, container).to_string(),
+        output.display().to_string(),
+    ]
+}
+
+/// Run ffmpeg with the given arguments, reporting progress through `callback`
+pub async fn run_ffmpeg(
+    args: &[String],
+    phase: ProgressPhase,
+    total_duration: Option<Duration>,
+    callback: Option<Arc<dyn Fn(Progress) + Send + Sync>>,
+) -> Result<(), RytError> {
+    let mut child = Command::new("ffmpeg")
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-loglevel",
+            "error",
+            "-progress",
+            "pipe:1",
+        ])
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // Both pipes are drained together, so ffmpeg never blocks on a full
+    // stderr pipe while progress is being read from stdout
+    let mut parser = FfmpegProgressParser::new(phase, total_duration);
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+    let read_progress = async {
+        if let Some(stdout) = stdout {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Some(line) = lines.next_line().await? {
+                if let Some(progress) = parser.feed_line(&line) {
+                    if let Some(callback) = &callback {
+                        callback(progress);
+                    }
+                }
+            }
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    let read_errors = async {
+        let mut errors = Vec::new();
+        if let Some(mut stderr) = stderr {
+            stderr.read_to_end(&mut errors).await?;
+        }
+        Ok::<_, std::io::Error>(errors)
+    };
+    let (progress, errors) = tokio::join!(read_progress, read_errors);
+    let errors = match progress.and(errors) {
+        Ok(errors) => errors,
+        Err(e) => {
+            // Don't leave ffmpeg running when its output can't be read
+            let _ = child.kill().await;
+            return Err(e.into());
+        }
+    };
+
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(RytError::Generic(format!(
+            "ffmpeg failed ({}): {}",
+            status,
+            String::from_utf8_lossy(&errors).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_OUTPUT: &str = "frame=120\n\
+fps=0.00\n\
+out_time_us=2500000\n\
+out_time_ms=2500000\n\
+out_time=00:00:02.500000\n\
+speed=5.0x\n\
+progress=continue\n\
+frame=240\n\
+out_time_us=5000000\n\
+out_time_ms=5000000\n\
+out_time=00:00:05.000000\n\
+progress=continue\n\
+frame=400\n\
+out_time_us=9980000\n\
+out_time_ms=9980000\n\
+progress=end\n";
+
+    #[test]
+    fn test_parse_sample_output() {
+        let parser = FfmpegProgressParser::new(ProgressPhase::Merge, Some(Duration::from_secs(10)));
+        let events = parser.parse(SAMPLE_OUTPUT);
+
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|p| p.phase == ProgressPhase::Merge));
+        assert!(events.iter().all(|p| p.total_size == 10_000));
+
+        assert_eq!(events[0].downloaded_size, 2_500);
+        assert_eq!(events[0].percent, 25.0);
+        assert_eq!(events[1].downloaded_size, 5_000);
+        assert_eq!(events[1].percent, 50.0);
+
+        // progress=end always reports completion
+        assert_eq!(events[2].downloaded_size, 10_000);
+        assert!(events[2].is_complete());
+    }
+
+    #[test]
+    fn test_parse_unknown_duration() {
+        let parser = FfmpegProgressParser::new(ProgressPhase::Remux, None);
+        let events = parser.parse(SAMPLE_OUTPUT);
+
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|p| p.total_size == 0));
+        assert_eq!(events[1].downloaded_size, 5_000);
+        assert_eq!(events[2].downloaded_size, 9_980);
+        assert_eq!(events[2].percent, 0.0);
+    }
+
+    fn audio_format(itag: u32, codec: &str) -> Format {
+        let mut format = Format::new(
+            itag,
+            String::new(),
+            String::new(),
+            format!("audio/mp4; codecs=\"{}\"", codec),
+        );
+        format.audio_codec = Some(codec.to_string());
+        format
+    }
+
+    #[test]
+    fn test_mux_audio_codec() {
+        let atmos = audio_format(328, "ec-3");
+        assert_eq!(mux_audio_codec(&atmos, "mp4"), "copy");
+        assert_eq!(mux_audio_codec(&atmos, "mkv"), "copy");
+
+        let aac = audio_format(140, "mp4a.40.2");
+        assert_eq!(mux_audio_codec(&aac, "mp4"), "copy");
+
+        let mut opus = audio_format(251, "opus");
+        opus.mime_type = "audio/webm; codecs=\"opus\"".to_string();
+        assert_eq!(mux_audio_codec(&opus, "mp4"), "aac");
+        assert_eq!(mux_audio_codec(&opus, "webm"), "copy");
+    }
+
+    #[test]
+    fn test_mux_container() {
+        let vp9 = Format::new(
+            248,
+            String::new(),
+            String::new(),
+            "video/webm; codecs=\"vp9\"".to_string(),
+        );
+        let avc = Format::new(
+            137,
+            String::new(),
+            String::new(),
+            "video/mp4; codecs=\"avc1.640028\"".to_string(),
+        );
+        let mut opus = audio_format(251, "opus");
+        opus.mime_type = "audio/webm; codecs=\"opus\"".to_string();
+        let aac = audio_format(140, "mp4a.40.2");
+
+        assert_eq!(mux_container(&vp9, &opus), "webm");
+        assert_eq!(mux_container(&vp9, &aac), "mp4");
+        assert_eq!(mux_container(&avc, &opus), "mp4");
+        assert_eq!(mux_container(&avc, &aac), "mp4");
+    }
+
+    #[test]
+    fn test_mux_args_copies_dolby_audio() {
+        let args = mux_args(
+            Path::new("video.mp4"),
+            Path::new("audio.m4a"),
+            &audio_format(328, "ec-3"),
+            Path::new("out.mp4"),
+        );
+        let codec = args.iter().position(|arg| arg == "-c:a").unwrap();
+        assert_eq!(args[codec + 1], "copy");
+        assert_eq!(args.last().unwrap(), "out.mp4");
+        assert!(args.windows(2).any(|w| w == ["-i", "audio.m4a"]));
+    }
+
+    #[test]
+    fn test_feed_line_ignores_invalid_values() {
+        let mut parser = FfmpegProgressParser::new(ProgressPhase::Embed, None);
+
+        assert!(parser.feed_line("out_time_us=N/A").is_none());
+        assert!(parser.feed_line("out_time_ms=-23000").is_none());
+        assert!(parser.feed_line("garbage").is_none());
+        assert!(parser.feed_line("").is_none());
+
+        let progress = parser.feed_line("progress=continue").unwrap();
+        assert_eq!(progress.downloaded_size, 0);
+        assert_eq!(progress.phase, ProgressPhase::Embed);
+    }
+}
@@ -1,7 +1,82 @@
 //! Progress tracking for downloads
 
+use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
+/// Number of instantaneous speed samples kept for smoothing
+pub const SPEED_SAMPLES: usize = 20;
+
+/// Default smoothing factor for the displayed speed
+pub const DEFAULT_SPEED_SMOOTHING_ALPHA: f64 = 0.2;
+
+/// Progress reporting configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressConfig {
+    /// EMA smoothing factor in `(0, 1]`; higher values react faster
+    pub speed_smoothing_alpha: f64,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            speed_smoothing_alpha: DEFAULT_SPEED_SMOOTHING_ALPHA,
+        }
+    }
+}
+
+/// Phase of work a progress event refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressPhase {
+    /// Downloading media bytes
+    #[default]
+    Download,
+    /// Merging separate video and audio streams
+    Merge,
+    /// Remuxing into a different container
+    Remux,
+    /// Embedding metadata, thumbnails or subtitles
+    Embed,
+}
+
+impl ProgressPhase {
+    /// Get human-readable label for the phase
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProgressPhase::Download => "Downloading...",
+            ProgressPhase::Merge => "Merging...",
+            ProgressPhase::Remux => "Remuxing...",
+            ProgressPhase::Embed => "Embedding...",
+        }
+    }
+
+    /// Check if phase is a postprocessing phase
+    pub fn is_postprocess(&self) -> bool {
+        !matches!(self, ProgressPhase::Download)
+    }
+}
+
+/// Stage of a download reported before any bytes arrive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Fetching video metadata and formats
+    Resolving,
+    /// Deciphering the signature of the selected format
+    Deciphering,
+    /// Downloading media bytes
+    Downloading,
+}
+
+impl Status {
+    /// Get human-readable label for the status
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Resolving => "Resolving video...",
+            Status::Deciphering => "Extracting signature...",
+            Status::Downloading => "Downloading...",
+        }
+    }
+}
+
 /// Progress information for a download
 #[derive(Debug, Clone)]
 pub struct Progress {
@@ -17,23 +92,54 @@ pub struct Progress {
     pub eta: Option<Duration>,
     /// Time when download started
     pub start_time: Instant,
+    /// Phase this progress refers to
+    ///
+    /// For postprocessing phases sizes are measured in milliseconds of media
+    /// processed rather than bytes.
+    pub phase: ProgressPhase,
+    /// EMA smoothing factor used by [`Progress::smoothed_speed_bps`]
+    pub speed_smoothing_alpha: f64,
+    /// Recent instantaneous speed samples, oldest first
+    speed_samples: VecDeque<f64>,
+    /// Time and size of the last sample
+    last_sample: (Instant, u64),
 }
 
 impl Progress {
     /// Create a new progress tracker
     pub fn new(total_size: u64) -> Self {
+        let start_time = Instant::now();
         Self {
             total_size,
             downloaded_size: 0,
             percent: 0.0,
             speed: None,
             eta: None,
-            start_time: Instant::now(),
+            start_time,
+            phase: ProgressPhase::Download,
+            speed_smoothing_alpha: DEFAULT_SPEED_SMOOTHING_ALPHA,
+            speed_samples: VecDeque::with_capacity(SPEED_SAMPLES),
+            last_sample: (start_time, 0),
+        }
+    }
+
+    /// Create a new progress tracker for the given phase
+    pub fn with_phase(total_size: u64, phase: ProgressPhase) -> Self {
+        Self {
+            phase,
+            ..Self::new(total_size)
         }
     }
 
+    /// Set the smoothing factor used for the displayed speed
+    pub fn with_speed_smoothing_alpha(mut self, alpha: f64) -> Self {
+        self.speed_smoothing_alpha = alpha;
+        self
+    }
+
     /// Update progress with new downloaded size
     pub fn update(&mut self, downloaded_size: u64) {
+        self.record_sample(downloaded_size);
         self.downloaded_size = downloaded_size;
         self.percent = if self.total_size > 0 {
             (downloaded_size as f64 / self.total_size as f64) * 100.0
@@ -55,6 +161,68 @@ impl Progress {
         }
     }
 
+    /// Record an instantaneous speed sample since the previous update
+    fn record_sample(&mut self, downloaded_size: u64) {
+        let now = Instant::now();
+        let (last_time, last_size) = self.last_sample;
+        let elapsed = now.duration_since(last_time).as_secs_f64();
+        if elapsed <= 0.0 {
+            return;
+        }
+
+        let bytes = downloaded_size.saturating_sub(last_size);
+        self.push_speed_sample(bytes as f64 / elapsed);
+        self.last_sample = (now, downloaded_size);
+    }
+
+    /// Push a speed sample in bytes per second, dropping the oldest when full
+    pub fn push_speed_sample(&mut self, bytes_per_second: f64) {
+        if self.speed_samples.len() == SPEED_SAMPLES {
+            self.speed_samples.pop_front();
+        }
+        self.speed_samples.push_back(bytes_per_second);
+    }
+
+    /// Get the recent speed samples, oldest first
+    pub fn speed_samples(&self) -> impl Iterator<Item = f64> + '_ {
+        self.speed_samples.iter().copied()
+    }
+
+    /// Exponential moving average of the recent speed samples.
+    ///
+    /// `alpha` is clamped to `(0, 1]`; `1.0` returns the latest sample.
+    /// Falls back to the average speed since start when no samples exist.
+    pub fn ema_speed_bps(&self, alpha: f64) -> u64 {
+        let alpha = if alpha.is_finite() && alpha > 0.0 {
+            alpha.min(1.0)
+        } else {
+            DEFAULT_SPEED_SMOOTHING_ALPHA
+        };
+
+        let mut samples = self.speed_samples.iter();
+        match samples.next() {
+            Some(&first) => samples
+                .fold(first, |ema, &sample| alpha * sample + (1.0 - alpha) * ema)
+                .max(0.0) as u64,
+            None => self.speed.unwrap_or(0.0) as u64,
+        }
+    }
+
+    /// Speed smoothed with the configured factor
+    pub fn smoothed_speed_bps(&self) -> u64 {
+        self.ema_speed_bps(self.speed_smoothing_alpha)
+    }
+
+    /// Estimated time remaining based on the EMA speed for `alpha`
+    pub fn ema_eta(&self, alpha: f64) -> Option<Duration> {
+        let speed = self.ema_speed_bps(alpha);
+        if speed == 0 || self.total_size <= self.downloaded_size {
+            return None;
+        }
+        let remaining = self.total_size - self.downloaded_size;
+        Some(Duration::from_secs(remaining / speed))
+    }
+
     /// Check if download is complete
     pub fn is_complete(&self) -> bool {
         self.total_size > 0 && self.downloaded_size >= self.total_size
@@ -141,6 +309,16 @@ pub fn format_duration(duration: Duration) -> String {
     }
 }
 
+/// Format a length in seconds as a clock, `MM:SS` or `HH:MM:SS` from an hour
+pub fn format_clock(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,6 +334,19 @@ mod tests {
         assert!(!progress.is_complete());
     }
 
+    #[test]
+    fn test_progress_with_phase() {
+        let progress = Progress::new(1000);
+        assert_eq!(progress.phase, ProgressPhase::Download);
+        assert!(!progress.phase.is_postprocess());
+
+        let progress = Progress::with_phase(1000, ProgressPhase::Merge);
+        assert_eq!(progress.total_size, 1000);
+        assert_eq!(progress.phase, ProgressPhase::Merge);
+        assert!(progress.phase.is_postprocess());
+        assert_eq!(progress.phase.label(), "Merging...");
+    }
+
     #[test]
     fn test_progress_update() {
         let mut progress = Progress::new(1000);
@@ -184,6 +375,75 @@ mod tests {
         assert!(progress.speed.unwrap() > 0.0);
     }
 
+    #[test]
+    fn test_ema_speed_bps() {
+        let mut progress = Progress::new(10_000);
+        assert_eq!(progress.ema_speed_bps(0.2), 0);
+
+        progress.push_speed_sample(100.0);
+        progress.push_speed_sample(200.0);
+        // 0.5 * 200 + 0.5 * 100
+        assert_eq!(progress.ema_speed_bps(0.5), 150);
+        // 0.2 * 200 + 0.8 * 100
+        assert_eq!(progress.ema_speed_bps(0.2), 120);
+        assert_eq!(progress.ema_speed_bps(1.0), 200);
+        assert_eq!(progress.smoothed_speed_bps(), 120);
+        assert_eq!(progress.ema_eta(1.0), Some(Duration::from_secs(50)));
+
+        // Out of range factors fall back to the default or are clamped
+        assert_eq!(progress.ema_speed_bps(0.0), 120);
+        assert_eq!(progress.ema_speed_bps(f64::NAN), 120);
+        assert_eq!(progress.ema_speed_bps(3.0), 200);
+
+        let progress = Progress::new(0).with_speed_smoothing_alpha(0.5);
+        assert_eq!(progress.speed_smoothing_alpha, 0.5);
+    }
+
+    #[test]
+    fn test_ema_speed_dampens_spikes() {
+        let mut progress = Progress::new(0);
+        for _ in 0..10 {
+            progress.push_speed_sample(1000.0);
+        }
+        progress.push_speed_sample(10_000.0);
+
+        let smoothed = progress.ema_speed_bps(0.2);
+        assert!(smoothed < 3000, "smoothed speed {} too jumpy", smoothed);
+        assert!(progress.ema_speed_bps(0.5) > smoothed);
+    }
+
+    #[test]
+    fn test_speed_samples_ring_buffer() {
+        let mut progress = Progress::new(0);
+        for i in 0..(SPEED_SAMPLES + 5) {
+            progress.push_speed_sample(i as f64);
+        }
+
+        let samples: Vec<f64> = progress.speed_samples().collect();
+        assert_eq!(samples.len(), SPEED_SAMPLES);
+        assert_eq!(samples[0], 5.0);
+        assert_eq!(samples[SPEED_SAMPLES - 1], (SPEED_SAMPLES + 4) as f64);
+    }
+
+    #[test]
+    fn test_update_records_speed_samples() {
+        let mut progress = Progress::new(1000);
+        thread::sleep(Duration::from_millis(10));
+        progress.update(100);
+        thread::sleep(Duration::from_millis(10));
+        progress.update(200);
+
+        assert_eq!(progress.speed_samples().count(), 2);
+        assert!(progress.ema_speed_bps(0.2) > 0);
+        assert!(progress.ema_eta(0.5).is_some());
+    }
+
+    #[test]
+    fn test_progress_config_default() {
+        let config = ProgressConfig::default();
+        assert_eq!(config.speed_smoothing_alpha, DEFAULT_SPEED_SMOOTHING_ALPHA);
+    }
+
     #[test]
     fn test_format_bytes() {
         assert_eq!(format_bytes(0), "0 B");
@@ -203,6 +463,18 @@ mod tests {
         assert_eq!(format_duration(Duration::from_secs(3660)), "1h 1m");
     }
 
+    #[test]
+    fn test_format_clock() {
+        assert_eq!(format_clock(0), "00:00");
+        assert_eq!(format_clock(7), "00:07");
+        assert_eq!(format_clock(212), "03:32");
+        assert_eq!(format_clock(3599), "59:59");
+        assert_eq!(format_clock(3600), "01:00:00");
+        assert_eq!(format_clock(3661), "01:01:01");
+        assert_eq!(format_clock(36_000 + 45), "10:00:45");
+        assert_eq!(format_clock(100 * 3600), "100:00:00");
+    }
+
     #[test]
     fn test_progress_edge_cases() {
         // Test with zero total size
@@ -280,6 +552,11 @@ mod tests {
 
     #[test]
     fn test_format_duration_edge_cases() {
+        // Test seconds
+        assert_eq!(format_duration(Duration::from_secs(0)), "0s");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59s");
+        assert_eq!(format_duration(Duration::from_secs(3599)), "59m 59s");
+
         // Test exact minute boundaries
         assert_eq!(format_duration(Duration::from_secs(120)), "2m");
         assert_eq!(format_duration(Duration::from_secs(180)), "3m");
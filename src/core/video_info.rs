@@ -1,6 +1,13 @@
 //! Video information structures
 
+use crate::core::progress::format_clock;
+use crate::core::Downloader;
+use crate::error::RytError;
+use crate::platform::formats::{compare_formats_by_quality, Itag};
+use crate::platform::innertube::CaptionTrack;
+use crate::utils::url::canonical_video_url;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// Video information and metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,13 +32,47 @@ pub struct VideoInfo {
     pub view_count: Option<u64>,
     /// Video like count
     pub like_count: Option<u64>,
+    /// Video comment count
+    pub comment_count: Option<u64>,
     /// Video tags
     pub tags: Vec<String>,
     /// Video category
     pub category: Option<String>,
+    /// Whether the video is (or was) a live stream
+    #[serde(default)]
+    pub is_live_content: bool,
+    /// Whether the video is currently streaming live
+    #[serde(default)]
+    pub is_live: bool,
+    /// Whether the video is a finished live stream
+    #[serde(default)]
+    pub was_live: bool,
+    /// Whether the video is a scheduled live stream or premiere
+    #[serde(default)]
+    pub is_upcoming: bool,
+    /// Format selected for download
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected_format: Option<Format>,
+    /// Visitor data of the player response, for diagnostics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visitor_data: Option<VisitorData>,
+    /// Caption tracks of the player response, so captions can be written
+    /// without requesting it again
+    #[serde(skip)]
+    pub caption_tracks: Vec<CaptionTrack>,
 }
 
 impl VideoInfo {
+    /// Fetch metadata for a video URL with a default [`Downloader`]
+    ///
+    /// Nothing is downloaded; use a configured downloader's
+    /// [`fetch_info`](Downloader::fetch_info) for client, cookie or format
+    /// options.
+    pub async fn from_url(url: &str) -> Result<VideoInfo, RytError> {
+        let plan = Downloader::new().fetch_info(url).await?;
+        Ok(plan.video_info)
+    }
+
     /// Create a new VideoInfo
     pub fn new(id: String, title: String) -> Self {
         Self {
@@ -45,9 +86,127 @@ impl VideoInfo {
             upload_date: None,
             view_count: None,
             like_count: None,
+            comment_count: None,
             tags: Vec::new(),
             category: None,
+            is_live_content: false,
+            is_live: false,
+            was_live: false,
+            is_upcoming: false,
+            selected_format: None,
+            visitor_data: None,
+            caption_tracks: Vec::new(),
+        }
+    }
+
+    /// Get the title to name files after, falling back to the video ID
+    pub fn filename_title(&self) -> &str {
+        if self.title.trim().is_empty() {
+            &self.id
+        } else {
+            &self.title
+        }
+    }
+
+    /// Get the watch page URL of the video
+    pub fn webpage_url(&self) -> String {
+        canonical_video_url(&self.id)
+    }
+
+    /// Get the duration as `MM:SS` or `HH:MM:SS`, `live` for live streams
+    /// without a length and `??:??` when the length is unknown
+    pub fn duration_string(&self) -> String {
+        match self.duration {
+            0 if self.is_live || self.is_upcoming => "live".to_string(),
+            0 => "??:??".to_string(),
+            seconds => format_clock(seconds as u64),
+        }
+    }
+
+    /// Fields [`VideoInfo::template_field`] knows
+    pub const TEMPLATE_FIELDS: &'static [&'static str] = &[
+        "id",
+        "title",
+        "uploader",
+        "channel",
+        "description",
+        "duration",
+        "duration_string",
+        "upload_date",
+        "view_count",
+        "like_count",
+    ];
+
+    /// Get the value of an output template field, e.g. `title` for `%(title)s`
+    pub fn template_field(&self, name: &str) -> Option<String> {
+        match name {
+            "id" => Some(self.id.clone()),
+            "title" => Some(self.filename_title().to_string()),
+            "uploader" | "channel" => Some(self.author.clone()).filter(|a| !a.is_empty()),
+            "description" => Some(self.description.clone()),
+            "duration" => Some(self.duration.to_string()),
+            "duration_string" => Some(self.duration_string()),
+            "upload_date" => self.upload_date.clone(),
+            "view_count" => self.view_count.map(|count| count.to_string()),
+            "like_count" => self.like_count.map(|count| count.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Convert to a yt-dlp style info dict, as written to `.info.json`
+    pub fn to_yt_dlp_json(&self) -> serde_json::Value {
+        let live_status = if self.is_upcoming {
+            "is_upcoming"
+        } else if self.is_live {
+            "is_live"
+        } else if self.was_live {
+            "was_live"
+        } else {
+            "not_live"
+        };
+        let mut info = serde_json::json!({
+            "_type": "video",
+            "id": self.id,
+            "title": self.title,
+            "fulltitle": self.title,
+            "uploader": self.author,
+            "channel": self.author,
+            "duration": self.duration,
+            "duration_string": self.duration_string(),
+            "description": self.description,
+            "thumbnail": self.thumbnail,
+            // yt-dlp dates are YYYYMMDD
+            "upload_date": self.upload_date.as_ref().map(|date| date.replace('-', "")),
+            "view_count": self.view_count,
+            "like_count": self.like_count,
+            "comment_count": self.comment_count,
+            "tags": self.tags,
+            "categories": self.category.iter().collect::<Vec<_>>(),
+            "is_live": self.is_live,
+            "was_live": self.was_live,
+            "live_status": live_status,
+            "webpage_url": self.webpage_url(),
+            "original_url": self.webpage_url(),
+            "extractor": "youtube",
+            "extractor_key": "Youtube",
+            "formats": self
+                .formats
+                .iter()
+                .map(|format| format.to_yt_dlp_json(self.duration))
+                .collect::<Vec<_>>(),
+        });
+        // The chosen format's fields are repeated at the top level
+        if let (Some(format), serde_json::Value::Object(info)) = (&self.selected_format, &mut info)
+        {
+            if let serde_json::Value::Object(fields) = format.to_yt_dlp_json(self.duration) {
+                for (key, value) in fields {
+                    if key != "url" {
+                        info.entry(key).or_insert(value);
+                    }
+                }
+            }
         }
+        info
     }
 
     /// Get the best available format
@@ -59,7 +218,7 @@ impl VideoInfo {
     pub fn formats_by_extension(&self, extension: &str) -> Vec<&Format> {
         self.formats
             .iter()
-            .filter(|f| f.mime_type.contains(extension))
+            .filter(|f| f.matches_extension(extension))
             .collect()
     }
 
@@ -67,10 +226,17 @@ impl VideoInfo {
     pub fn formats_by_quality(&self, quality: &str) -> Vec<&Format> {
         self.formats
             .iter()
-            .filter(|f| f.quality == quality)
+            .filter(|f| f.quality_label() == quality)
             .collect()
     }
 
+    /// Get all formats sorted by quality (best first), leaving `formats` as is
+    pub fn formats_sorted_by_quality(&self) -> Vec<&Format> {
+        let mut formats: Vec<&Format> = self.formats.iter().collect();
+        formats.sort_by(|a, b| compare_formats_by_quality(a, b));
+        formats
+    }
+
     /// Get the total size of all formats
     pub fn total_size(&self) -> u64 {
         self.formats.iter().map(|f| f.size.unwrap_or(0)).sum()
@@ -87,11 +253,34 @@ impl VideoInfo {
     }
 }
 
+/// Visitor data YouTube assigned to a session, redacted in debug output
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VisitorData(String);
+
+impl VisitorData {
+    /// Wrap visitor data
+    pub fn new(visitor_data: impl Into<String>) -> Self {
+        Self(visitor_data.into())
+    }
+
+    /// Get the visitor data
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for VisitorData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VisitorData(…)")
+    }
+}
+
 /// Video format information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Format {
     /// YouTube format ID (itag)
-    pub itag: u32,
+    pub itag: Itag,
     /// Direct download URL
     pub url: String,
     /// Quality label (e.g., "720p", "1080p")
@@ -118,8 +307,11 @@ pub struct Format {
     pub audio_sample_rate: Option<u32>,
     /// Audio channels
     pub audio_channels: Option<u32>,
-    /// Language code
+    /// Language code of the audio track, e.g. `de-DE`
     pub language: Option<String>,
+    /// Whether the audio is the video's original track among several dubs
+    #[serde(default)]
+    pub audio_is_default: bool,
     /// Format note/description
     pub note: Option<String>,
 }
@@ -128,7 +320,7 @@ impl Format {
     /// Create a new Format
     pub fn new(itag: u32, url: String, quality: String, mime_type: String) -> Self {
         Self {
-            itag,
+            itag: Itag(itag),
             url,
             quality,
             mime_type,
@@ -143,56 +335,222 @@ impl Format {
             audio_sample_rate: None,
             audio_channels: None,
             language: None,
+            audio_is_default: false,
             note: None,
         }
     }
 
     /// Check if format is progressive (video+audio combined)
     pub fn is_progressive(&self) -> bool {
-        self.mime_type.starts_with("video/")
-            && (self.mime_type.contains("mp4") || self.mime_type.contains("webm"))
-            && self.audio_codec.is_some()
-            && self.video_codec.is_some()
+        self.has_video() && self.has_audio()
     }
 
     /// Check if format is adaptive (video or audio only)
     pub fn is_adaptive(&self) -> bool {
-        (self.mime_type.starts_with("video/") && self.audio_codec.is_none())
-            || self.mime_type.starts_with("audio/")
+        self.is_video_only() || self.is_audio_only()
     }
 
     /// Check if format is video-only
     pub fn is_video_only(&self) -> bool {
-        self.mime_type.starts_with("video/") && self.audio_codec.is_none()
+        self.has_video() && !self.has_audio()
     }
 
     /// Check if format is audio-only
     pub fn is_audio_only(&self) -> bool {
-        self.mime_type.starts_with("audio/")
+        self.has_audio() && !self.has_video()
+    }
+
+    /// Check if the format carries a video stream.
+    ///
+    /// A stream is present if its codec field is set or the MIME type's
+    /// `codecs` parameter lists it. Without any codec information the MIME
+    /// type decides, so a bare `video/mp4` counts as video.
+    fn has_video(&self) -> bool {
+        codec_present(&self.video_codec)
+            || self.mime_stream_codecs().0.is_some()
+            || (!self.has_codec_info() && self.mime_type.starts_with("video/"))
+    }
+
+    /// Check if the format carries an audio stream, see [`Format::has_video`]
+    fn has_audio(&self) -> bool {
+        codec_present(&self.audio_codec)
+            || self.mime_stream_codecs().1.is_some()
+            || (!self.has_codec_info() && self.mime_type.starts_with("audio/"))
+    }
+
+    /// Check if either codec field or the MIME type names a codec
+    fn has_codec_info(&self) -> bool {
+        codec_present(&self.video_codec)
+            || codec_present(&self.audio_codec)
+            || !crate::utils::mime::mime_codecs(&self.mime_type).is_empty()
+    }
+
+    /// Get the video and audio codecs listed in the MIME type's `codecs`
+    /// parameter
+    fn mime_stream_codecs(&self) -> (Option<&str>, Option<&str>) {
+        let codecs = crate::utils::mime::mime_codecs(&self.mime_type);
+        if self.mime_type.starts_with("video/") {
+            // Muxed formats list the video codec first
+            (codecs.first().copied(), codecs.get(1).copied())
+        } else if self.mime_type.starts_with("audio/") {
+            (None, codecs.first().copied())
+        } else {
+            (None, None)
+        }
+    }
+
+    /// Check if format is stored in a WebM container
+    pub fn is_webm(&self) -> bool {
+        self.mime_type.contains("webm")
+    }
+
+    /// Check if format is a Dolby Atmos (E-AC-3) audio stream
+    pub fn is_dolby_atmos(&self) -> bool {
+        self.mime_type.contains("audio/mp4")
+            && self
+                .audio_codec
+                .as_deref()
+                .is_some_and(|codec| codec.contains("ec-3"))
+    }
+
+    /// Check if format has a high frame rate (48 fps and above)
+    pub fn is_60fps(&self) -> bool {
+        self.fps.map_or(false, |f| f >= 48)
+    }
+
+    /// Check if format is an HDR video stream.
+    ///
+    /// YouTube marks HDR in the quality label (`1080p60 HDR`); otherwise the
+    /// codec decides: VP9 profile 2, or AV1 with a PQ or HLG transfer
+    /// function.
+    pub fn is_hdr(&self) -> bool {
+        if self.quality.to_ascii_uppercase().contains("HDR") {
+            return true;
+        }
+        let Some(codec) = self.video_codec_name() else {
+            return false;
+        };
+        let codec = codec.to_ascii_lowercase();
+        if codec.starts_with("vp09.02") || codec.starts_with("vp9.2") {
+            return true;
+        }
+        codec.starts_with("av01") && matches!(codec.split('.').nth(7), Some("16") | Some("18"))
+    }
+
+    /// Get the video codec, e.g. `avc1.640028`, preferring the MIME type's
+    /// `codecs` parameter
+    pub fn video_codec_name(&self) -> Option<&str> {
+        if !self.mime_type.starts_with("video/") {
+            return None;
+        }
+        // Muxed formats list the video codec first
+        crate::utils::mime::mime_codecs(&self.mime_type)
+            .first()
+            .copied()
+            .or(self.video_codec.as_deref())
+    }
+
+    /// Get the audio codec, e.g. `opus`, preferring the MIME type's `codecs`
+    /// parameter
+    pub fn audio_codec_name(&self) -> Option<&str> {
+        let codecs = crate::utils::mime::mime_codecs(&self.mime_type);
+        let codec = if self.mime_type.starts_with("audio/") {
+            codecs.first()
+        } else {
+            codecs.get(1)
+        };
+        codec.copied().or(self.audio_codec.as_deref())
+    }
+
+    /// Convert to a yt-dlp style format dict for a video of `duration`
+    /// seconds
+    pub fn to_yt_dlp_json(&self, duration: u32) -> serde_json::Value {
+        let vcodec = if self.has_video() {
+            self.video_codec_name().unwrap_or("unknown")
+        } else {
+            "none"
+        };
+        let acodec = if self.has_audio() {
+            self.audio_codec_name().unwrap_or("unknown")
+        } else {
+            "none"
+        };
+        serde_json::json!({
+            "format_id": self.itag.to_string(),
+            "format_note": self.note.clone().unwrap_or_else(|| self.quality_label()),
+            "url": self.url,
+            "ext": self.extension(),
+            "width": self.width,
+            "height": self.height,
+            "fps": self.fps,
+            "vcodec": vcodec,
+            "acodec": acodec,
+            "asr": self.audio_sample_rate,
+            "audio_channels": self.audio_channels,
+            "language": self.language,
+            "tbr": f64::from(self.bitrate) / 1000.0,
+            "filesize": self.size,
+            "filesize_approx": self.approx_filesize(duration),
+            "dynamic_range": if self.is_hdr() { "HDR" } else { "SDR" },
+        })
     }
 
     /// Get file extension from MIME type
     pub fn extension(&self) -> &'static str {
-        crate::utils::mime::ext_from_mime(&self.mime_type)
+        crate::utils::mime::ext_from_mime(crate::utils::mime::base_mime_type(&self.mime_type))
     }
 
     /// Get container format
     pub fn container(&self) -> &'static str {
-        crate::utils::mime::get_container_format(&self.mime_type)
+        crate::utils::mime::get_container_format(crate::utils::mime::base_mime_type(
+            &self.mime_type,
+        ))
+    }
+
+    /// Check if the format is stored in the container of a file extension
+    pub fn matches_extension(&self, extension: &str) -> bool {
+        crate::utils::mime::mime_matches_ext(&self.mime_type, extension)
     }
 
     /// Check if format needs signature deciphering
+    ///
+    /// Besides a separate `signatureCipher`, some responses embed signature
+    /// or n-parameters in the URL query; an empty URL can only come from
+    /// deciphering the cipher.
     pub fn needs_deciphering(&self) -> bool {
+        const CIPHER_PARAMS: [&str; 3] = ["n", "sig", "signature"];
         self.signature_cipher.is_some()
-            || self.url.contains("&n=")
-            || self.url.contains("?n=")
             || self.url.is_empty()
+            || CIPHER_PARAMS.iter().any(|param| {
+                self.url.contains(&format!("&{}=", param))
+                    || self.url.contains(&format!("?{}=", param))
+            })
+    }
+
+    /// Get the quality label, e.g. `1080p60`.
+    ///
+    /// YouTube's `qualityLabel` is missing for many adaptive formats, so
+    /// without it the label is derived from the height, with the frame rate
+    /// appended above 30 fps. Audio-only formats are labelled `audio only`.
+    pub fn quality_label(&self) -> String {
+        if !self.quality.is_empty() {
+            return self.quality.clone();
+        }
+        match (self.height.filter(|height| *height > 0), self.fps) {
+            (Some(height), Some(fps)) if fps > 30 && self.has_video() => {
+                format!("{}p{}", height, fps)
+            }
+            (Some(height), _) if self.has_video() => format!("{}p", height),
+            _ if self.is_audio_only() => "audio only".to_string(),
+            _ => String::new(),
+        }
     }
 
     /// Get human-readable quality string
     pub fn quality_string(&self) -> String {
-        if !self.quality.is_empty() {
-            self.quality.clone()
+        let label = self.quality_label();
+        if !label.is_empty() {
+            label
         } else if let (Some(width), Some(height)) = (self.width, self.height) {
             format!("{}x{}", width, height)
         } else {
@@ -209,6 +567,15 @@ impl Format {
         }
     }
 
+    /// Get the file size, or an estimate from the bitrate and a duration in
+    /// seconds when the size isn't known
+    pub fn approx_filesize(&self, duration: u32) -> Option<u64> {
+        self.size.or_else(|| {
+            let bits = u64::from(self.bitrate) * u64::from(duration);
+            (bits > 0).then_some(bits / 8)
+        })
+    }
+
     /// Get human-readable bitrate string
     pub fn bitrate_string(&self) -> String {
         if self.bitrate > 0 {
@@ -254,12 +621,73 @@ impl PlaylistItem {
 
     /// Get the YouTube URL for this video
     pub fn url(&self) -> String {
-        format!("https://www.youtube.com/watch?v={}", self.video_id)
+        canonical_video_url(&self.video_id)
+    }
+}
+
+/// Video found through search or discovery (e.g. trending)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// Video ID
+    pub video_id: String,
+    /// Video title
+    pub title: String,
+    /// Channel name
+    pub author: String,
+    /// Video duration in seconds
+    pub duration: u32,
+    /// View count
+    pub view_count: Option<u64>,
+    /// Publish time as shown by YouTube (e.g. "2 days ago")
+    pub published_at: Option<String>,
+    /// Video thumbnail URL
+    pub thumbnail: Option<String>,
+}
+
+impl SearchResult {
+    /// Get the YouTube URL for this video
+    pub fn url(&self) -> String {
+        canonical_video_url(&self.video_id)
+    }
+}
+
+/// Playlist listed on a channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistMetadata {
+    /// Playlist ID
+    pub playlist_id: String,
+    /// Playlist title
+    pub title: String,
+    /// Number of videos, if shown
+    pub video_count: Option<u64>,
+    /// Playlist thumbnail URL
+    pub thumbnail: Option<String>,
+}
+
+impl PlaylistMetadata {
+    /// Get the YouTube URL for this playlist
+    pub fn url(&self) -> String {
+        format!("https://www.youtube.com/playlist?list={}", self.playlist_id)
+    }
+
+    /// Convert to a yt-dlp style playlist info dict, without entries
+    pub fn to_yt_dlp_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "_type": "playlist",
+            "id": self.playlist_id,
+            "title": self.title,
+            "playlist_count": self.video_count,
+            "thumbnail": self.thumbnail,
+            "webpage_url": self.url(),
+            "original_url": self.url(),
+            "extractor": "youtube:tab",
+            "extractor_key": "YoutubeTab",
+        })
     }
 }
 
 /// Format selector for choosing video formats
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FormatSelector {
     /// Quality selector
     pub quality: QualitySelector,
@@ -270,7 +698,22 @@ pub struct FormatSelector {
     /// Minimum height constraint
     pub height_min: Option<u32>,
     /// Preferred itag
-    pub preferred_itag: Option<u32>,
+    pub preferred_itag: Option<Itag>,
+    /// Prefer WebM formats when any candidate is WebM
+    #[serde(default)]
+    pub prefer_webm: bool,
+    /// Maximum frame rate constraint
+    pub fps_limit: Option<u32>,
+    /// Minimum frame rate constraint
+    pub fps_min: Option<u32>,
+    /// Video codec prefix, e.g. `avc1` or `vp9`
+    pub video_codec: Option<String>,
+    /// Audio codec prefix, e.g. `mp4a` or `opus`
+    pub audio_codec: Option<String>,
+    /// Audio language code, e.g. `de` or `de-DE`
+    pub preferred_language: Option<String>,
+    /// Audio stream to merge with the selected video stream
+    pub audio: Option<Box<FormatSelector>>,
 }
 
 impl FormatSelector {
@@ -282,6 +725,13 @@ impl FormatSelector {
             height_limit: None,
             height_min: None,
             preferred_itag: None,
+            prefer_webm: false,
+            fps_limit: None,
+            fps_min: None,
+            video_codec: None,
+            audio_codec: None,
+            preferred_language: None,
+            audio: None,
         }
     }
 
@@ -303,28 +753,81 @@ impl FormatSelector {
         self
     }
 
+    /// Set frame rate limit
+    pub fn with_fps_limit(mut self, fps: u32) -> Self {
+        self.fps_limit = Some(fps);
+        self
+    }
+
+    /// Set minimum frame rate
+    pub fn with_fps_min(mut self, fps: u32) -> Self {
+        self.fps_min = Some(fps);
+        self
+    }
+
+    /// Set video codec prefix
+    pub fn with_video_codec(mut self, codec: &str) -> Self {
+        self.video_codec = Some(codec.to_string());
+        self
+    }
+
+    /// Set audio codec prefix
+    pub fn with_audio_codec(mut self, codec: &str) -> Self {
+        self.audio_codec = Some(codec.to_string());
+        self
+    }
+
+    /// Prefer audio in a language, e.g. `de` for any German track or `de-DE`;
+    /// without such audio the video's original track is used
+    pub fn with_preferred_language(mut self, lang_code: &str) -> Self {
+        self.preferred_language = Some(lang_code.to_string());
+        self
+    }
+
     /// Set preferred itag
     pub fn with_itag(mut self, itag: u32) -> Self {
-        self.preferred_itag = Some(itag);
+        self.preferred_itag = Some(Itag(itag));
+        self
+    }
+
+    /// Set whether WebM formats are preferred
+    pub fn with_prefer_webm(mut self, prefer_webm: bool) -> Self {
+        self.prefer_webm = prefer_webm;
+        self
+    }
+
+    /// Set audio stream to merge with the selected video stream
+    pub fn with_audio(mut self, audio: FormatSelector) -> Self {
+        self.audio = Some(Box::new(audio));
         self
     }
 }
 
 /// Quality selection criteria
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum QualitySelector {
     /// Best quality available
     Best,
+    /// Best quality available at 48 fps or more, or [`QualitySelector::Best`]
+    /// when there is no such format
+    Best60fps,
     /// Worst quality available
     Worst,
     /// Specific itag
-    Itag(u32),
+    Itag(Itag),
     /// Specific height
     Height(u32),
     /// Height less than or equal to
     HeightLessOrEqual(u32),
     /// Height greater than or equal to
     HeightGreaterOrEqual(u32),
+    /// Best Dolby Atmos audio stream
+    DolbyAtmos,
+    /// Best video-only stream
+    BestVideo,
+    /// Best audio-only stream
+    BestAudio,
 }
 
 impl QualitySelector {
@@ -334,14 +837,19 @@ impl QualitySelector {
 
         match s.as_str() {
             "best" => Ok(QualitySelector::Best),
+            "best60" | "best60fps" => Ok(QualitySelector::Best60fps),
             "worst" => Ok(QualitySelector::Worst),
+            "dolby" | "atmos" | "dolby-atmos" => Ok(QualitySelector::DolbyAtmos),
+            "bestvideo" => Ok(QualitySelector::BestVideo),
+            "bestaudio" => Ok(QualitySelector::BestAudio),
             _ => {
-                if s.starts_with("itag=") {
-                    let itag_str = &s[5..];
-                    let itag = itag_str
-                        .parse::<u32>()
-                        .map_err(|_| format!("Invalid itag: {}", itag_str))?;
-                    Ok(QualitySelector::Itag(itag))
+                if let Some(height) = s
+                    .strip_suffix('p')
+                    .and_then(|height| height.parse::<u32>().ok())
+                {
+                    Ok(QualitySelector::Height(height))
+                } else if s.starts_with("itag") || s.starts_with(|c: char| c.is_ascii_digit()) {
+                    s.parse::<Itag>().map(QualitySelector::Itag)
                 } else if s.starts_with("height<=") {
                     let height_str = &s[8..];
                     let height = height_str
@@ -368,6 +876,13 @@ impl QualitySelector {
     }
 }
 
+/// Check if a codec field names a codec; YouTube sometimes sends it empty
+fn codec_present(codec: &Option<String>) -> bool {
+    codec
+        .as_deref()
+        .is_some_and(|codec| !codec.trim().is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -380,6 +895,56 @@ mod tests {
         assert!(info.formats.is_empty());
     }
 
+    #[test]
+    fn test_video_info_filename_title() {
+        let info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Test Video".to_string());
+        assert_eq!(info.filename_title(), "Test Video");
+
+        let info = VideoInfo::new("dQw4w9WgXcQ".to_string(), " ".to_string());
+        assert_eq!(info.filename_title(), "dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn test_formats_sorted_by_quality() {
+        let mut info = VideoInfo::new("test_id".to_string(), "Test Video".to_string());
+        let mut audio = Format::new(140, String::new(), "tiny".into(), "audio/mp4".into());
+        audio.bitrate = 128_000;
+        let mut hd = Format::new(22, String::new(), "hd720".into(), "video/mp4".into());
+        hd.height = Some(720);
+        let mut sd = Format::new(18, String::new(), "medium".into(), "video/mp4".into());
+        sd.height = Some(360);
+        info.formats = vec![audio, hd, sd];
+
+        let sorted: Vec<u32> = info
+            .formats_sorted_by_quality()
+            .iter()
+            .map(|f| f.itag.get())
+            .collect();
+        assert_eq!(sorted, vec![22, 18, 140]);
+        // The formats themselves keep their order
+        let original: Vec<u32> = info.formats.iter().map(|f| f.itag.get()).collect();
+        assert_eq!(original, vec![140, 22, 18]);
+    }
+
+    #[tokio::test]
+    async fn test_from_url_rejects_invalid_url() {
+        let result = VideoInfo::from_url("not a url").await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_visitor_data_is_redacted_in_debug_output() {
+        let mut info = VideoInfo::new("test_id".to_string(), "Test Video".to_string());
+        info.visitor_data = Some(VisitorData::new("CgtGaXJzdFZpc2l0"));
+        assert!(!format!("{:?}", info).contains("CgtGaXJzdFZpc2l0"));
+        assert!(format!("{:?}", info).contains("VisitorData(…)"));
+
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains(r#""visitor_data":"CgtGaXJzdFZpc2l0""#));
+        let parsed: VideoInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.visitor_data, info.visitor_data);
+    }
+
     #[test]
     fn test_format_creation() {
         let format = Format::new(
@@ -388,7 +953,7 @@ mod tests {
             "720p".to_string(),
             "video/mp4".to_string(),
         );
-        assert_eq!(format.itag, 22);
+        assert_eq!(format.itag, Itag(22));
         assert_eq!(format.quality, "720p");
         assert_eq!(format.mime_type, "video/mp4");
     }
@@ -427,13 +992,45 @@ mod tests {
             QualitySelector::from_str("best").unwrap(),
             QualitySelector::Best
         );
+        assert_eq!(
+            QualitySelector::from_str("best60fps").unwrap(),
+            QualitySelector::Best60fps
+        );
+        assert_eq!(
+            QualitySelector::from_str("Best60").unwrap(),
+            QualitySelector::Best60fps
+        );
         assert_eq!(
             QualitySelector::from_str("worst").unwrap(),
             QualitySelector::Worst
         );
+        assert_eq!(
+            QualitySelector::from_str("bestvideo").unwrap(),
+            QualitySelector::BestVideo
+        );
+        assert_eq!(
+            QualitySelector::from_str("bestaudio").unwrap(),
+            QualitySelector::BestAudio
+        );
+        assert_eq!(
+            QualitySelector::from_str("dolby").unwrap(),
+            QualitySelector::DolbyAtmos
+        );
+        assert_eq!(
+            QualitySelector::from_str("Dolby-Atmos").unwrap(),
+            QualitySelector::DolbyAtmos
+        );
         assert_eq!(
             QualitySelector::from_str("itag=22").unwrap(),
-            QualitySelector::Itag(22)
+            QualitySelector::Itag(Itag(22))
+        );
+        assert_eq!(
+            QualitySelector::from_str("itag:299").unwrap(),
+            QualitySelector::Itag(Itag(299))
+        );
+        assert_eq!(
+            QualitySelector::from_str("299").unwrap(),
+            QualitySelector::Itag(Itag(299))
         );
         assert_eq!(
             QualitySelector::from_str("height<=720").unwrap(),
@@ -447,6 +1044,10 @@ mod tests {
             QualitySelector::from_str("height=1080").unwrap(),
             QualitySelector::Height(1080)
         );
+        assert_eq!(
+            QualitySelector::from_str("1080p").unwrap(),
+            QualitySelector::Height(1080)
+        );
 
         assert!(QualitySelector::from_str("invalid").is_err());
     }
@@ -546,7 +1147,11 @@ mod tests {
         format.quality = String::new();
         format.width = Some(1280);
         format.height = Some(720);
-        assert_eq!(format.quality_string(), "1280x720");
+        assert_eq!(format.quality_string(), "720p");
+
+        // Test with empty quality and no height to derive a label from
+        format.height = Some(0);
+        assert_eq!(format.quality_string(), "1280x0");
 
         // Test with empty quality and no dimensions
         format.width = None;
@@ -564,6 +1169,228 @@ mod tests {
         assert_eq!(format.bitrate_string(), "1000 kbps");
     }
 
+    #[test]
+    fn test_format_approx_filesize() {
+        let mut format = Format::new(
+            18,
+            "url".to_string(),
+            "360p".to_string(),
+            "video/mp4".to_string(),
+        );
+        assert_eq!(format.approx_filesize(600), None);
+
+        // 500 kbps for ten minutes
+        format.bitrate = 500_000;
+        assert_eq!(format.approx_filesize(600), Some(37_500_000));
+        assert_eq!(format.approx_filesize(0), None);
+
+        // A known size wins over the estimate
+        format.size = Some(40_000_000);
+        assert_eq!(format.approx_filesize(600), Some(40_000_000));
+        assert_eq!(format.approx_filesize(0), Some(40_000_000));
+    }
+
+    #[test]
+    fn test_to_yt_dlp_json() {
+        let mut info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Test Video".to_string());
+        info.author = "Uploader".to_string();
+        info.duration = 212;
+        info.upload_date = Some("2009-10-25".to_string());
+        info.category = Some("Music".to_string());
+        let mut format = Format::new(
+            18,
+            "https://example.com/18".to_string(),
+            "360p".to_string(),
+            r#"video/mp4; codecs="avc1.42001E, mp4a.40.2""#.to_string(),
+        );
+        format.height = Some(360);
+        format.bitrate = 500_000;
+        info.formats = vec![format.clone()];
+        info.selected_format = Some(format);
+
+        let json = info.to_yt_dlp_json();
+        assert_eq!(json["_type"], "video");
+        assert_eq!(json["id"], "dQw4w9WgXcQ");
+        assert_eq!(json["uploader"], "Uploader");
+        assert_eq!(json["upload_date"], "20091025");
+        assert_eq!(json["categories"], serde_json::json!(["Music"]));
+        assert_eq!(json["live_status"], "not_live");
+        assert_eq!(json["duration_string"], "03:32");
+        assert_eq!(
+            json["webpage_url"],
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+        );
+        assert_eq!(json["format_id"], "18");
+        assert_eq!(json["ext"], "mp4");
+        assert!(json.get("url").is_none());
+
+        let formats = json["formats"].as_array().unwrap();
+        assert_eq!(formats.len(), 1);
+        assert_eq!(formats[0]["vcodec"], "avc1.42001E");
+        assert_eq!(formats[0]["acodec"], "mp4a.40.2");
+        assert_eq!(formats[0]["tbr"], 500.0);
+        assert_eq!(formats[0]["filesize_approx"], 13_250_000);
+        assert_eq!(formats[0]["url"], "https://example.com/18");
+    }
+
+    #[test]
+    fn test_playlist_metadata_to_yt_dlp_json() {
+        let metadata = PlaylistMetadata {
+            playlist_id: "PLxyz".to_string(),
+            title: "Mix".to_string(),
+            video_count: Some(12),
+            thumbnail: None,
+        };
+        let json = metadata.to_yt_dlp_json();
+        assert_eq!(json["_type"], "playlist");
+        assert_eq!(json["id"], "PLxyz");
+        assert_eq!(json["playlist_count"], 12);
+        assert_eq!(
+            json["webpage_url"],
+            "https://www.youtube.com/playlist?list=PLxyz"
+        );
+    }
+
+    #[test]
+    fn test_format_needs_deciphering_fixtures() {
+        const CDN: &str = "https://rr3---sn-4g5e6nze.googlevideo.com/videoplayback";
+        let fixture = |itag: u32, url: String, cipher: Option<&str>| {
+            let mut format = Format::new(
+                itag,
+                url,
+                String::new(),
+                "video/mp4; codecs=\"avc1.4d401e\"".to_string(),
+            );
+            format.signature_cipher = cipher.map(str::to_string);
+            format
+        };
+
+        let cases = [
+            // ANDROID muxed format with a plain, already signed URL
+            (
+                fixture(
+                    18,
+                    format!("{}?expire=1700000000&ei=abc&ip=0.0.0.0&id=o-AB&itag=18&source=youtube&mime=video%2Fmp4&lsig=AG3C_x", CDN),
+                    None,
+                ),
+                false,
+            ),
+            // IOS adaptive format, no cipher parameters
+            (
+                fixture(
+                    137,
+                    format!("{}?expire=1700000000&itag=137&c=IOS&sparams=expire%2Citag&lsparams=mh%2Cmm", CDN),
+                    None,
+                ),
+                false,
+            ),
+            // WEB format with a throttling n-parameter
+            (
+                fixture(
+                    22,
+                    format!("{}?expire=1700000000&itag=22&n=Xy7_abCdEfG&c=WEB", CDN),
+                    None,
+                ),
+                true,
+            ),
+            // n-parameter as the first query parameter
+            (fixture(136, format!("{}?n=Xy7_abCdEfG&itag=136", CDN), None), true),
+            // Signature embedded in the query instead of signatureCipher
+            (
+                fixture(
+                    140,
+                    format!("{}?expire=1700000000&itag=140&sig=AOq0QJ8wRQIhAK", CDN),
+                    None,
+                ),
+                true,
+            ),
+            // Legacy full signature parameter name
+            (
+                fixture(
+                    251,
+                    format!("{}?itag=251&signature=2E853B992F6CC.A1B2", CDN),
+                    None,
+                ),
+                true,
+            ),
+            // Signature as the first query parameter
+            (fixture(248, format!("{}?sig=AOq0QJ8wRQIhAK&itag=248", CDN), None), true),
+            // WEB format behind signatureCipher, no URL of its own
+            (
+                fixture(
+                    18,
+                    String::new(),
+                    Some("s=%3DQ%3DAOq0QJ8wRQIhAK&sp=sig&url=https%3A%2F%2Frr3---sn-4g5e6nze.googlevideo.com%2Fvideoplayback%3Fitag%3D18"),
+                ),
+                true,
+            ),
+            // signatureCipher alongside a URL
+            (
+                fixture(
+                    399,
+                    format!("{}?itag=399", CDN),
+                    Some("s=AOq0QJ8w&sp=sig&url=https%3A%2F%2Fexample.com"),
+                ),
+                true,
+            ),
+            // Empty URL without cipher only comes from deciphering
+            (fixture(278, String::new(), None), true),
+            // Parameters merely ending in n, sig or signature don't count
+            (
+                fixture(
+                    134,
+                    format!("{}?itag=134&lsig=AG3C_x&ipbsig=1&mn=sn-4g5e6nze&xsignature=0", CDN),
+                    None,
+                ),
+                false,
+            ),
+            // "n" inside a path or fragment is not a parameter
+            (
+                fixture(
+                    160,
+                    "https://redirector.googlevideo.com/n=1/videoplayback?itag=160".to_string(),
+                    None,
+                ),
+                false,
+            ),
+        ];
+
+        for (format, expected) in cases {
+            assert_eq!(
+                format.needs_deciphering(),
+                expected,
+                "itag {} url {:?} cipher {:?}",
+                format.itag,
+                format.url,
+                format.signature_cipher
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_matches_extension() {
+        let video = Format::new(
+            137,
+            "url".to_string(),
+            "1080p".to_string(),
+            "video/mp4; codecs=\"avc1.640028\"".to_string(),
+        );
+        let audio = Format::new(
+            140,
+            "url".to_string(),
+            "audio".to_string(),
+            "audio/mp4; codecs=\"mp4a.40.2\"".to_string(),
+        );
+
+        assert_eq!(video.container(), "mp4");
+        assert_eq!(video.extension(), "mp4");
+        assert_eq!(audio.extension(), "m4a");
+        assert!(video.matches_extension("mp4"));
+        assert!(!audio.matches_extension("mp4"));
+        assert!(audio.matches_extension("m4a"));
+        assert!(!video.matches_extension("webm"));
+    }
+
     #[test]
     fn test_format_audio_only() {
         let format = Format::new(
@@ -579,6 +1406,181 @@ mod tests {
         assert!(format.is_audio_only());
     }
 
+    #[test]
+    fn test_format_is_webm() {
+        let video = Format::new(
+            248,
+            "url".to_string(),
+            "1080p".to_string(),
+            "video/webm; codecs=\"vp9\"".to_string(),
+        );
+        let audio = Format::new(
+            251,
+            "url".to_string(),
+            "audio".to_string(),
+            "audio/webm; codecs=\"opus\"".to_string(),
+        );
+        let mp4 = Format::new(
+            18,
+            "url".to_string(),
+            "360p".to_string(),
+            "video/mp4".to_string(),
+        );
+
+        assert!(video.is_webm());
+        assert!(audio.is_webm());
+        assert!(!mp4.is_webm());
+        assert_eq!(video.container(), "webm");
+        assert_eq!(video.extension(), "webm");
+        assert_eq!(audio.extension(), "webm");
+    }
+
+    #[test]
+    fn test_format_quality_label_derived() {
+        let video = |height: Option<u32>, fps: Option<u32>| {
+            let mut format = Format::new(
+                137,
+                "url".to_string(),
+                String::new(),
+                "video/mp4; codecs=\"avc1.640028\"".to_string(),
+            );
+            format.height = height;
+            format.fps = fps;
+            format.quality_label()
+        };
+
+        for (height, fps, label) in [
+            (144, None, "144p"),
+            (360, Some(25), "360p"),
+            (720, Some(30), "720p"),
+            (720, Some(48), "720p48"),
+            (1080, Some(50), "1080p50"),
+            (1080, Some(60), "1080p60"),
+            (2160, Some(60), "2160p60"),
+            (4320, Some(30), "4320p"),
+        ] {
+            assert_eq!(
+                video(Some(height), fps),
+                label,
+                "{} at {:?} fps",
+                height,
+                fps
+            );
+        }
+        assert_eq!(video(None, Some(60)), "");
+        assert_eq!(video(Some(0), None), "");
+
+        let audio = Format::new(
+            140,
+            "url".to_string(),
+            String::new(),
+            "audio/mp4; codecs=\"mp4a.40.2\"".to_string(),
+        );
+        assert_eq!(audio.quality_label(), "audio only");
+        assert_eq!(audio.quality_string(), "audio only");
+    }
+
+    #[test]
+    fn test_format_quality_label_prefers_youtube_label() {
+        let mut format = Format::new(
+            299,
+            "url".to_string(),
+            "1080p60 HDR".to_string(),
+            "video/mp4; codecs=\"avc1.64002a\"".to_string(),
+        );
+        format.height = Some(1080);
+        format.fps = Some(60);
+        assert_eq!(format.quality_label(), "1080p60 HDR");
+
+        let mut info = VideoInfo::new("id".to_string(), "title".to_string());
+        format.quality = String::new();
+        info.formats.push(format);
+        assert_eq!(info.formats_by_quality("1080p60").len(), 1);
+    }
+
+    #[test]
+    fn test_format_is_60fps() {
+        let mut format = Format::new(
+            299,
+            "url".to_string(),
+            "1080p60".to_string(),
+            "video/mp4; codecs=\"avc1.64002a\"".to_string(),
+        );
+        assert!(!format.is_60fps());
+        for (fps, high) in [
+            (24, false),
+            (30, false),
+            (47, false),
+            (48, true),
+            (60, true),
+        ] {
+            format.fps = Some(fps);
+            assert_eq!(format.is_60fps(), high, "{} fps", fps);
+        }
+    }
+
+    #[test]
+    fn test_format_is_hdr() {
+        let format = |quality: &str, mime_type: &str| {
+            Format::new(
+                337,
+                "url".to_string(),
+                quality.to_string(),
+                mime_type.to_string(),
+            )
+        };
+
+        assert!(format("2160p60 HDR", "video/webm; codecs=\"vp9\"").is_hdr());
+        assert!(format(
+            "1080p",
+            "video/webm; codecs=\"vp09.02.51.10.01.09.16.09.00\""
+        )
+        .is_hdr());
+        assert!(format(
+            "1080p",
+            "video/mp4; codecs=\"av01.0.09M.10.0.110.09.16.09.0\""
+        )
+        .is_hdr());
+        assert!(format(
+            "1080p",
+            "video/mp4; codecs=\"av01.0.09M.10.0.110.09.18.09.0\""
+        )
+        .is_hdr());
+
+        assert!(!format("1080p60", "video/webm; codecs=\"vp9\"").is_hdr());
+        assert!(!format("1080p", "video/webm; codecs=\"vp09.00.41.08\"").is_hdr());
+        assert!(!format("1080p", "video/mp4; codecs=\"av01.0.08M.08\"").is_hdr());
+        assert!(!format(
+            "1080p",
+            "video/mp4; codecs=\"av01.0.09M.10.0.110.09.01.09.0\""
+        )
+        .is_hdr());
+        assert!(!format("audio", "audio/webm; codecs=\"opus\"").is_hdr());
+    }
+
+    #[test]
+    fn test_format_is_dolby_atmos() {
+        let mut atmos = Format::new(
+            328,
+            "url".to_string(),
+            "audio".to_string(),
+            "audio/mp4; codecs=\"ec-3\"".to_string(),
+        );
+        atmos.audio_codec = Some("ec-3".to_string());
+        assert!(atmos.is_dolby_atmos());
+
+        let mut aac = atmos.clone();
+        aac.audio_codec = Some("mp4a.40.2".to_string());
+        assert!(!aac.is_dolby_atmos());
+
+        // E-AC-3 outside an MP4 audio stream is not counted
+        let mut other = atmos.clone();
+        other.mime_type = "video/mp4".to_string();
+        assert!(!other.is_dolby_atmos());
+        atmos.audio_codec = None;
+        assert!(!atmos.is_dolby_atmos());
+    }
+
     #[test]
     fn test_format_progressive_with_codecs() {
         let mut format = Format::new(
@@ -596,6 +1598,146 @@ mod tests {
         assert!(!format.is_audio_only());
     }
 
+    #[test]
+    fn test_format_stream_kinds() {
+        // (audio_codec, video_codec) -> (progressive, video-only, audio-only)
+        let codecs = [None, Some(""), Some("codec")];
+        for audio in codecs {
+            for video in codecs {
+                let mut format = Format::new(
+                    22,
+                    "url".to_string(),
+                    "720p".to_string(),
+                    "video/mp4".to_string(),
+                );
+                // Empty codec fields count as missing
+                format.audio_codec = audio.map(str::to_string);
+                format.video_codec = video.map(str::to_string);
+
+                let has_audio = audio.is_some_and(|codec| !codec.is_empty());
+                let has_video = video.is_some_and(|codec| !codec.is_empty());
+                let expected = match (has_audio, has_video) {
+                    (true, true) => (true, false, false),
+                    (false, true) => (false, true, false),
+                    (true, false) => (false, false, true),
+                    // No codec information: the video/ MIME type decides
+                    (false, false) => (false, true, false),
+                };
+                assert_eq!(
+                    (
+                        format.is_progressive(),
+                        format.is_video_only(),
+                        format.is_audio_only()
+                    ),
+                    expected,
+                    "audio {:?}, video {:?}",
+                    audio,
+                    video
+                );
+                assert_eq!(format.is_adaptive(), !expected.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_stream_kinds_from_mime() {
+        let format =
+            |mime: &str| Format::new(0, "url".to_string(), String::new(), mime.to_string());
+        assert!(format("audio/mp4").is_audio_only());
+        assert!(format("audio/webm; codecs=\"opus\"").is_audio_only());
+        assert!(format("video/webm; codecs=\"vp9\"").is_video_only());
+        assert!(format("video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"").is_progressive());
+        assert!(format("video/3gpp; codecs=\"mp4v.20.3, mp4a.40.2\"").is_progressive());
+
+        let unknown = format("application/octet-stream");
+        assert!(!unknown.is_progressive());
+        assert!(!unknown.is_video_only());
+        assert!(!unknown.is_audio_only());
+
+        // Codec fields complement the MIME type
+        let mut muxed = format("video/mp4; codecs=\"avc1.42001E\"");
+        assert!(muxed.is_video_only());
+        muxed.audio_codec = Some("mp4a.40.2".to_string());
+        assert!(muxed.is_progressive());
+    }
+
+    #[test]
+    fn test_template_fields() {
+        let mut info = VideoInfo::new("dQw4w9WgXcQ".to_string(), "Title".to_string());
+        info.author = "Channel".to_string();
+        info.upload_date = Some("20091025".to_string());
+        info.view_count = Some(100);
+        info.like_count = Some(10);
+        for name in VideoInfo::TEMPLATE_FIELDS {
+            assert!(info.template_field(name).is_some(), "{}", name);
+        }
+        assert_eq!(info.template_field("uploader").as_deref(), Some("Channel"));
+        assert_eq!(info.template_field("ext"), None);
+
+        // Unknown values are missing rather than empty
+        let info = VideoInfo::new("id".to_string(), String::new());
+        assert_eq!(info.template_field("title").as_deref(), Some("id"));
+        assert_eq!(info.template_field("uploader"), None);
+        assert_eq!(info.template_field("view_count"), None);
+    }
+
+    #[test]
+    fn test_duration_string() {
+        let mut info = VideoInfo::new("id".to_string(), "Title".to_string());
+        assert_eq!(info.duration_string(), "??:??");
+        info.is_live = true;
+        assert_eq!(info.duration_string(), "live");
+        info.is_live = false;
+
+        for (duration, expected) in [
+            (5, "00:05"),
+            (212, "03:32"),
+            (3599, "59:59"),
+            (3600, "01:00:00"),
+            (5025, "01:23:45"),
+            (40_000, "11:06:40"),
+        ] {
+            info.duration = duration;
+            assert_eq!(info.duration_string(), expected);
+            assert_eq!(
+                info.template_field("duration_string").as_deref(),
+                Some(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_codec_names() {
+        let muxed = Format::new(
+            18,
+            "url".to_string(),
+            "360p".to_string(),
+            "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"".to_string(),
+        );
+        assert_eq!(muxed.video_codec_name(), Some("avc1.42001E"));
+        assert_eq!(muxed.audio_codec_name(), Some("mp4a.40.2"));
+
+        let audio = Format::new(
+            251,
+            "url".to_string(),
+            String::new(),
+            "audio/webm; codecs=\"opus\"".to_string(),
+        );
+        assert_eq!(audio.video_codec_name(), None);
+        assert_eq!(audio.audio_codec_name(), Some("opus"));
+
+        // Without a codecs parameter the format fields are used
+        let mut plain = Format::new(
+            22,
+            "url".to_string(),
+            "720p".to_string(),
+            "video/mp4".to_string(),
+        );
+        plain.video_codec = Some("avc1".to_string());
+        assert_eq!(plain.video_codec_name(), Some("avc1"));
+        assert_eq!(plain.audio_codec_name(), None);
+    }
+
     #[test]
     fn test_format_progressive_webm() {
         let mut format = Format::new(
@@ -632,7 +1774,17 @@ mod tests {
         assert_eq!(selector.extension, Some("mp4".to_string()));
         assert_eq!(selector.height_limit, Some(1080));
         assert_eq!(selector.height_min, Some(480));
-        assert_eq!(selector.preferred_itag, Some(22));
+        assert_eq!(selector.preferred_itag, Some(Itag(22)));
+        assert!(!selector.prefer_webm);
+        assert!(selector.audio.is_none());
+
+        let selector = selector
+            .with_prefer_webm(true)
+            .with_audio(FormatSelector::new(QualitySelector::BestAudio).with_extension("webm"));
+        assert!(selector.prefer_webm);
+        let audio = selector.audio.unwrap();
+        assert_eq!(audio.quality, QualitySelector::BestAudio);
+        assert_eq!(audio.extension.as_deref(), Some("webm"));
     }
 
     #[test]
@@ -655,6 +1807,8 @@ mod tests {
 
         // Test invalid itag
         assert!(QualitySelector::from_str("itag=abc").is_err());
+        assert!(QualitySelector::from_str("itag:").is_err());
+        assert!(QualitySelector::from_str("22abc").is_err());
 
         // Test invalid height
         assert!(QualitySelector::from_str("height=abc").is_err());
@@ -703,6 +1857,6 @@ mod tests {
 
         // Test best_format
         let best = info.best_format().unwrap();
-        assert_eq!(best.itag, 22); // Format with higher bitrate (1000)
+        assert_eq!(best.itag, Itag(22)); // Format with higher bitrate (1000)
     }
 }
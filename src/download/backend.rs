@@ -0,0 +1,468 @@
+//! Transfer backends: the built-in chunked downloader or an external
+//! program such as aria2c
+
+use crate::core::progress::Progress;
+use crate::download::ChunkedDownloader;
+use crate::error::RytError;
+use crate::utils::parse_size;
+use crate::utils::url::loggable_url;
+use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use tracing::{debug, info};
+
+/// Media transfer handed to a backend
+pub struct TransferRequest<'a> {
+    /// Media URL
+    pub url: &'a str,
+    /// Path of the finished file
+    pub output_path: &'a Path,
+    /// Headers the media server expects, as name and value
+    pub headers: Vec<(String, String)>,
+    /// Callback for progress reports
+    pub progress: Option<Arc<dyn Fn(Progress) + Send + Sync>>,
+}
+
+/// Backend downloading media URLs to files
+#[async_trait::async_trait]
+pub trait TransferBackend: Send + Sync {
+    /// Download the request's URL to its output path
+    async fn transfer(&self, request: &TransferRequest<'_>) -> Result<(), RytError>;
+
+    /// Check if the backend keeps its own partial files. Other backends
+    /// write to a temp file that ryt renames once the transfer succeeds.
+    fn manages_partials(&self) -> bool {
+        false
+    }
+}
+
+/// The built-in backend, sending its own headers and reporting progress to
+/// its own callback
+#[async_trait::async_trait]
+impl TransferBackend for ChunkedDownloader {
+    async fn transfer(&self, request: &TransferRequest<'_>) -> Result<(), RytError> {
+        self.download(request.url, request.output_path).await
+    }
+
+    fn manages_partials(&self) -> bool {
+        true
+    }
+}
+
+/// External program downloading media, such as aria2c, curl or wget.
+///
+/// The program gets the configured arguments, then a `--header` argument per
+/// required header, then the output path in the form it expects and the URL.
+/// Programs other than aria2c, curl and wget are passed `-o <path>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalDownloader {
+    program: String,
+    args: Vec<String>,
+}
+
+impl ExternalDownloader {
+    /// Create a backend running `program`, looked up in `PATH` unless it
+    /// is a path
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Set extra arguments passed before the ones ryt adds
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Get the program run
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Get the program name without directory or extension, e.g. `aria2c`
+    fn name(&self) -> &str {
+        Path::new(&self.program)
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.program)
+    }
+
+    /// Build the arguments for a transfer
+    pub fn command_args(&self, request: &TransferRequest<'_>) -> Vec<String> {
+        let mut args = self.args.clone();
+        for (name, value) in &request.headers {
+            args.push("--header".to_string());
+            args.push(format!("{}: {}", name, value));
+        }
+
+        let output = request.output_path;
+        let output_args: Vec<String> = match self.name() {
+            "aria2c" => {
+                let dir = output
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                let file_name = output.file_name().unwrap_or(output.as_os_str());
+                vec![
+                    "--continue=true".to_string(),
+                    "--allow-overwrite=true".to_string(),
+                    "--auto-file-renaming=false".to_string(),
+                    "--summary-interval=1".to_string(),
+                    format!("--dir={}", dir.display()),
+                    format!("--out={}", file_name.to_string_lossy()),
+                ]
+            }
+            "curl" => vec![
+                "--silent".to_string(),
+                "--show-error".to_string(),
+                "--fail".to_string(),
+                "--location".to_string(),
+                "--continue-at".to_string(),
+                "-".to_string(),
+                "--output".to_string(),
+                output.display().to_string(),
+            ],
+            "wget" => vec![
+                "--no-verbose".to_string(),
+                "--continue".to_string(),
+                "--output-document".to_string(),
+                output.display().to_string(),
+            ],
+            _ => vec!["-o".to_string(), output.display().to_string()],
+        };
+        args.extend(output_args);
+        args.push(request.url.to_string());
+        args
+    }
+
+    /// Turn an unsuccessful exit into an error, using the last line the
+    /// program printed as the message
+    fn exit_error(&self, status: ExitStatus, last_line: Option<&str>) -> RytError {
+        // aria2c reports timeouts with exit code 2
+        if self.name() == "aria2c" && status.code() == Some(2) {
+            return RytError::TimeoutError(format!("{} timed out", self.program));
+        }
+        let status = match status.code() {
+            Some(code) => format!("exited with code {}", code),
+            None => "was killed by a signal".to_string(),
+        };
+        self.failure(match last_line {
+            Some(line) => format!("{}: {}", status, line),
+            None => status,
+        })
+    }
+
+    /// Build an error for this program
+    fn failure(&self, message: String) -> RytError {
+        RytError::ExternalDownloaderFailed {
+            program: self.program.clone(),
+            message,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TransferBackend for ExternalDownloader {
+    async fn transfer(&self, request: &TransferRequest<'_>) -> Result<(), RytError> {
+        info!(
+            "Downloading {} with {}",
+            loggable_url(request.url),
+            self.program
+        );
+        let mut child = Command::new(&self.program)
+            .args(self.command_args(request))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| self.failure(format!("could not be started: {}", e)))?;
+
+        // Drain stderr alongside stdout so neither pipe fills up
+        let stderr = child.stderr.take();
+        let stderr = tokio::spawn(async move {
+            let mut output = String::new();
+            if let Some(mut stderr) = stderr {
+                let _ = stderr.read_to_string(&mut output).await;
+            }
+            output
+        });
+
+        let mut progress: Option<Progress> = None;
+        let mut last_stdout_line = None;
+        if let Some(stdout) = child.stdout.take() {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match parse_aria2c_progress(&line) {
+                    Some((downloaded, total)) => {
+                        let progress = progress.get_or_insert_with(|| Progress::new(total));
+                        progress.total_size = total;
+                        progress.update(downloaded);
+                        if let Some(callback) = &request.progress {
+                            callback(progress.clone());
+                        }
+                    }
+                    None if !line.trim().is_empty() => {
+                        debug!("{}: {}", self.name(), line.trim());
+                        last_stdout_line = Some(line.trim().to_string());
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| self.failure(format!("could not be waited for: {}", e)))?;
+        let stderr = stderr.await.unwrap_or_default();
+        if !status.success() {
+            let last_line = stderr
+                .lines()
+                .rev()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .or(last_stdout_line.as_deref());
+            return Err(self.exit_error(status, last_line));
+        }
+        if tokio::fs::metadata(request.output_path).await.is_err() {
+            return Err(self.failure(format!(
+                "finished without writing {}",
+                request.output_path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// External programs resume from their own partial files
+    fn manages_partials(&self) -> bool {
+        true
+    }
+}
+
+/// Parse the downloaded and total bytes from an aria2c progress readout
+/// such as `[#2089b0 400KiB/33MiB(1%) CN:1 DL:115KiB ETA:4m51s]`
+pub fn parse_aria2c_progress(line: &str) -> Option<(u64, u64)> {
+    let readout = &line[line.find("[#")?..];
+    let sizes = readout.split_whitespace().nth(1)?;
+    let sizes = sizes.split('(').next()?;
+    let (downloaded, total) = sizes.split_once('/')?;
+    Some((parse_size(downloaded).ok()?, parse_size(total).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    /// Shell script standing in for a downloader: it records its arguments
+    /// one per line in `args.txt` and copies `fixture.bin` to the path
+    /// following `-o`
+    #[cfg(unix)]
+    fn fake_downloader(dir: &Path, extra: &str) -> ExternalDownloader {
+        std::fs::write(dir.join("fixture.bin"), b"media bytes").unwrap();
+        let script = dir.join("fake-downloader.sh");
+        std::fs::write(
+            &script,
+            format!(
+                "printf '%s\\n' \"$@\" > '{dir}/args.txt'\n\
+                 {extra}\n\
+                 while [ $# -gt 0 ]; do\n\
+                 if [ \"$1\" = -o ]; then out=\"$2\"; fi\n\
+                 shift\n\
+                 done\n\
+                 cp '{dir}/fixture.bin' \"$out\"\n",
+                dir = dir.display(),
+                extra = extra
+            ),
+        )
+        .unwrap();
+        ExternalDownloader::new("sh").with_args([script.display().to_string()])
+    }
+
+    #[cfg(unix)]
+    fn request<'a>(url: &'a str, output_path: &'a Path) -> TransferRequest<'a> {
+        TransferRequest {
+            url,
+            output_path,
+            headers: vec![
+                ("User-Agent".to_string(), "Mozilla/5.0".to_string()),
+                ("X-Goog-Visitor-Id".to_string(), "visitor-1".to_string()),
+            ],
+            progress: None,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_external_downloader_passes_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = fake_downloader(dir.path(), "");
+        let output = dir.path().join("video.mp4");
+        let url = "https://rr1.googlevideo.com/videoplayback?itag=18&sig=abc";
+
+        backend.transfer(&request(url, &output)).await.unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), b"media bytes");
+
+        let args = std::fs::read_to_string(dir.path().join("args.txt")).unwrap();
+        let args: Vec<&str> = args.lines().collect();
+        assert_eq!(
+            args,
+            [
+                "--header",
+                "User-Agent: Mozilla/5.0",
+                "--header",
+                "X-Goog-Visitor-Id: visitor-1",
+                "-o",
+                output.to_str().unwrap(),
+                url,
+            ]
+        );
+        assert!(backend.manages_partials());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_external_downloader_reports_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = fake_downloader(
+            dir.path(),
+            "echo '[#2089b0 512KiB/1MiB(50%) CN:1 DL:115KiB ETA:4s]'\n\
+             echo '[#2089b0 1MiB/1MiB(100%) CN:1 DL:120KiB]'",
+        );
+        let output = dir.path().join("video.mp4");
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let seen = reports.clone();
+        let mut request = request("https://example.com/v", &output);
+        request.progress = Some(Arc::new(move |progress: Progress| {
+            seen.lock()
+                .unwrap()
+                .push((progress.downloaded_size, progress.total_size));
+        }));
+
+        backend.transfer(&request).await.unwrap();
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [(512 * 1024, 1024 * 1024), (1024 * 1024, 1024 * 1024)]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_external_downloader_exit_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = fake_downloader(
+            dir.path(),
+            "echo 'errorCode=3 Resource not found' >&2\nexit 3",
+        );
+        let output = dir.path().join("video.mp4");
+
+        let result = backend
+            .transfer(&request("https://example.com/v", &output))
+            .await;
+        match result {
+            Err(RytError::ExternalDownloaderFailed { program, message }) => {
+                assert_eq!(program, "sh");
+                assert_eq!(
+                    message,
+                    "exited with code 3: errorCode=3 Resource not found"
+                );
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // Exiting successfully without a file is a failure too
+        let backend = fake_downloader(dir.path(), "exit 0");
+        let result = backend
+            .transfer(&request("https://example.com/v", &output))
+            .await;
+        assert!(matches!(
+            result,
+            Err(RytError::ExternalDownloaderFailed { message, .. }) if message.starts_with("finished without writing")
+        ));
+
+        let backend = ExternalDownloader::new("ryt-no-such-downloader");
+        let result = backend
+            .transfer(&request("https://example.com/v", &output))
+            .await;
+        assert!(matches!(
+            result,
+            Err(RytError::ExternalDownloaderFailed { message, .. }) if message.starts_with("could not be started")
+        ));
+    }
+
+    #[test]
+    fn test_command_args_per_program() {
+        let output = PathBuf::from("/downloads/Title.mp4");
+        let request = TransferRequest {
+            url: "https://example.com/v",
+            output_path: &output,
+            headers: vec![("User-Agent".to_string(), "ua".to_string())],
+            progress: None,
+        };
+
+        let aria2c = ExternalDownloader::new("/usr/bin/aria2c").with_args(["-x16", "-s16"]);
+        assert_eq!(aria2c.program(), "/usr/bin/aria2c");
+        assert_eq!(
+            aria2c.command_args(&request),
+            [
+                "-x16",
+                "-s16",
+                "--header",
+                "User-Agent: ua",
+                "--continue=true",
+                "--allow-overwrite=true",
+                "--auto-file-renaming=false",
+                "--summary-interval=1",
+                "--dir=/downloads",
+                "--out=Title.mp4",
+                "https://example.com/v",
+            ]
+        );
+
+        let curl = ExternalDownloader::new("curl").command_args(&request);
+        assert_eq!(
+            &curl[curl.len() - 3..],
+            ["--output", "/downloads/Title.mp4", "https://example.com/v"]
+        );
+        assert!(curl.contains(&"--fail".to_string()));
+
+        let wget = ExternalDownloader::new("wget").command_args(&request);
+        assert!(wget.contains(&"--output-document".to_string()));
+
+        let relative = PathBuf::from("Title.mp4");
+        let request = TransferRequest {
+            output_path: &relative,
+            ..request
+        };
+        let aria2c = ExternalDownloader::new("aria2c").command_args(&request);
+        assert!(aria2c.contains(&"--dir=.".to_string()));
+    }
+
+    #[test]
+    fn test_parse_aria2c_progress() {
+        assert_eq!(
+            parse_aria2c_progress("[#2089b0 400KiB/33MiB(1%) CN:1 DL:115KiB ETA:4m51s]"),
+            Some((400 * 1024, 33 * 1024 * 1024))
+        );
+        assert_eq!(
+            parse_aria2c_progress(" *** [#1 0B/0B CN:1 DL:0B]"),
+            Some((0, 0))
+        );
+        assert_eq!(
+            parse_aria2c_progress("[#2089b0 1.5MiB/2.0MiB(75%) CN:4 DL:1.1MiB]"),
+            Some((1536 * 1024, 2 * 1024 * 1024))
+        );
+        assert_eq!(parse_aria2c_progress("Download Progress Summary"), None);
+        assert_eq!(parse_aria2c_progress("[#2089b0 SEED(0.0) CN:1]"), None);
+    }
+}
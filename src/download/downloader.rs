@@ -1,24 +1,89 @@
 //! Chunked downloader implementation
 
-use crate::core::progress::Progress;
+use crate::core::progress::{Progress, ProgressConfig};
+use crate::download::hls::{parse_playlist, select_variant, HlsPlaylist};
 use crate::error::RytError;
-use crate::platform::client::VideoClient;
-use std::path::Path;
+use crate::platform::client::{
+    AcceptEncoding, HttpClientConfig, IpFamily, VideoClient, DEFAULT_CONNECT_TIMEOUT,
+    MEDIA_USER_AGENT,
+};
+use crate::platform::traffic::TrafficLog;
+use crate::utils::duration_serde;
+use crate::utils::metrics;
+use crate::utils::temp::{kept_partial_path, move_file, partial_file_path};
+use crate::utils::url::loggable_url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::sync::{Mutex, Semaphore};
+
+/// Consecutive 403s on the same chunk before giving up on the URL
+const MAX_CDN_403_RETRIES: u32 = 3;
+
+/// Default delay before retrying a chunk after a 403 with another client
+pub const DEFAULT_CDN_403_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Default write buffer size for streamed downloads
+pub const DEFAULT_WRITE_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Default idle timeout between chunks of a media body
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default window over which throughput is measured for throttle detection
+pub const DEFAULT_THROTTLE_WINDOW: Duration = Duration::from_secs(10);
 
 /// Chunked downloader configuration
 #[derive(Clone)]
 pub struct DownloaderConfig {
     /// Chunk size in bytes
     pub chunk_size: u64,
-    /// Maximum retries per chunk
+    /// Write buffer size in bytes for streamed downloads
+    pub write_buffer_size: usize,
+    /// Maximum retries for requests
     pub max_retries: u32,
+    /// Maximum retries per fragment (chunk or segment)
+    pub fragment_retries: u32,
+    /// Delay before retrying a chunk after a 403 with another client
+    pub cdn_403_retry_delay: Duration,
+    /// Segments of a segmented download fetched at once
+    pub concurrent_fragments: usize,
     /// Rate limit in bytes per second
     pub rate_limit_bps: Option<u64>,
+    /// Only start rate limiting a download after this threshold
+    pub rate_limit_after: Option<RateLimitGrace>,
+    /// Share one rate limit bucket across all downloads
+    pub global_rate_limit: bool,
+    /// Abort with `RytError::Throttled` when throughput stays below this
+    /// many bytes per second for a whole throttle window
+    pub throttled_rate: Option<u64>,
+    /// Window over which throughput is measured for throttle detection
+    pub throttle_window: Duration,
+    /// Directory for partial files (defaults to next to the output file)
+    pub temp_dir: Option<PathBuf>,
+    /// Connection establishment timeout
+    pub connect_timeout: Duration,
+    /// Idle timeout between chunks of the body (`None` = wait forever)
+    pub read_timeout: Option<Duration>,
+    /// Total timeout per media request (`None` = unlimited)
+    pub request_timeout: Option<Duration>,
+    /// Address family for media connections
+    pub ip_family: IpFamily,
+    /// Keep partial files of failed downloads (renamed to `.part`) instead of removing them
+    pub keep_temp_on_error: bool,
+    /// Fixed addresses for media hosts, bypassing DNS
+    pub dns_overrides: HashMap<String, Vec<SocketAddr>>,
+    /// Log media requests and responses (`--print-traffic`)
+    pub traffic_log: Option<TrafficLog>,
+    /// Visitor data sent as `X-Goog-Visitor-Id` with media requests of
+    /// clients that need it
+    pub visitor_data: Option<String>,
+    /// Progress reporting settings
+    pub progress: ProgressConfig,
     /// Progress callback
     pub progress_callback: Option<Arc<dyn Fn(Progress) + Send + Sync>>,
 }
@@ -27,8 +92,26 @@ impl Default for DownloaderConfig {
     fn default() -> Self {
         Self {
             chunk_size: 1024 * 1024, // 1MB
+            write_buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
             max_retries: 3,
+            fragment_retries: 10,
+            cdn_403_retry_delay: DEFAULT_CDN_403_RETRY_DELAY,
+            concurrent_fragments: 1,
             rate_limit_bps: None,
+            rate_limit_after: None,
+            global_rate_limit: false,
+            throttled_rate: None,
+            throttle_window: DEFAULT_THROTTLE_WINDOW,
+            temp_dir: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            request_timeout: None,
+            ip_family: IpFamily::Any,
+            keep_temp_on_error: false,
+            dns_overrides: HashMap::new(),
+            traffic_log: None,
+            visitor_data: None,
+            progress: ProgressConfig::default(),
             progress_callback: None,
         }
     }
@@ -43,8 +126,26 @@ mod tests {
     fn test_downloader_config_default() {
         let config = DownloaderConfig::default();
         assert_eq!(config.chunk_size, 1024 * 1024); // 1MB
+        assert_eq!(config.write_buffer_size, DEFAULT_WRITE_BUFFER_SIZE);
         assert_eq!(config.max_retries, 3);
+        assert_eq!(config.fragment_retries, 10);
+        assert_eq!(config.cdn_403_retry_delay, DEFAULT_CDN_403_RETRY_DELAY);
+        assert_eq!(config.concurrent_fragments, 1);
         assert!(config.rate_limit_bps.is_none());
+        assert!(config.rate_limit_after.is_none());
+        assert!(!config.global_rate_limit);
+        assert!(config.throttled_rate.is_none());
+        assert_eq!(config.throttle_window, DEFAULT_THROTTLE_WINDOW);
+        assert!(config.temp_dir.is_none());
+        assert_eq!(config.connect_timeout, Duration::from_secs(10));
+        assert_eq!(config.read_timeout, Some(Duration::from_secs(30)));
+        assert!(config.request_timeout.is_none());
+        assert_eq!(config.ip_family, IpFamily::Any);
+        assert!(!config.keep_temp_on_error);
+        assert!(config.dns_overrides.is_empty());
+        assert!(config.traffic_log.is_none());
+        assert!(config.visitor_data.is_none());
+        assert_eq!(config.progress.speed_smoothing_alpha, 0.2);
         assert!(config.progress_callback.is_none());
     }
 
@@ -210,8 +311,1401 @@ mod tests {
         assert_eq!(limiter.bytes_per_second, 0);
         assert_eq!(limiter.bytes_sent, 0);
 
-        // Note: wait_if_needed with zero rate limit will panic due to division by zero
-        // This is documented behavior - zero rate limit should not be used
+        // A zero rate limit never throttles
+        let mut limiter = RateLimiter::new(0);
+        assert_eq!(limiter.reserve(1000, Instant::now()), Duration::ZERO);
+        assert_eq!(limiter.bytes_sent, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_download_ranged_refreshes_url_mid_download() {
+        use mockito::Matcher;
+
+        let mut server = mockito::Server::new_async().await;
+        let content = b"0123456789abcdef";
+
+        let mut mocks = Vec::new();
+        for token in ["old", "new"] {
+            mocks.push(
+                server
+                    .mock("GET", "/videoplayback")
+                    .match_query(Matcher::UrlEncoded("token".into(), token.into()))
+                    .match_header("range", "bytes=0-1")
+                    .with_status(206)
+                    .with_header("content-range", "bytes 0-1/16")
+                    .with_body(&content[0..2])
+                    .create_async()
+                    .await,
+            );
+        }
+        // The old URL serves the first 8 bytes, then starts rejecting ranges
+        for (range, body) in [("bytes=0-3", &content[0..4]), ("bytes=4-7", &content[4..8])] {
+            mocks.push(
+                server
+                    .mock("GET", "/videoplayback")
+                    .match_query(Matcher::UrlEncoded("token".into(), "old".into()))
+                    .match_header("range", range)
+                    .with_status(206)
+                    .with_body(body)
+                    .create_async()
+                    .await,
+            );
+        }
+        mocks.push(
+            server
+                .mock("GET", "/videoplayback")
+                .match_query(Matcher::UrlEncoded("token".into(), "old".into()))
+                .match_header("range", "bytes=8-11")
+                .with_status(403)
+                .create_async()
+                .await,
+        );
+        for (range, body) in [
+            ("bytes=8-11", &content[8..12]),
+            ("bytes=12-15", &content[12..16]),
+        ] {
+            mocks.push(
+                server
+                    .mock("GET", "/videoplayback")
+                    .match_query(Matcher::UrlEncoded("token".into(), "new".into()))
+                    .match_header("range", range)
+                    .with_status(206)
+                    .with_body(body)
+                    .create_async()
+                    .await,
+            );
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let downloader = ChunkedDownloader::new();
+
+        let old_url = format!("{}/videoplayback?token=old", server.url());
+        let result = downloader.download_ranged(&old_url, &output_path, 4).await;
+        assert!(matches!(result, Err(RytError::NeedsUrlRefresh(8))));
+        assert!(!output_path.exists());
+        assert_eq!(
+            std::fs::read(output_path.with_extension("tmp")).unwrap(),
+            &content[0..8]
+        );
+
+        let new_url = format!("{}/videoplayback?token=new", server.url());
+        downloader
+            .download_ranged(&new_url, &output_path, 4)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), content);
+    }
+
+    /// Serve 8 bytes whose only chunk fails `failures` times before succeeding
+    async fn flaky_fragment_server(failures: usize) -> (mockito::ServerGuard, Vec<mockito::Mock>) {
+        let mut server = mockito::Server::new_async().await;
+        let mocks = vec![
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-1")
+                .with_status(206)
+                .with_header("content-range", "bytes 0-1/8")
+                .with_body("01")
+                .create_async()
+                .await,
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-7")
+                .with_status(503)
+                .expect(failures)
+                .create_async()
+                .await,
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-7")
+                .with_status(206)
+                .with_body("01234567")
+                .create_async()
+                .await,
+        ];
+        (server, mocks)
+    }
+
+    #[tokio::test]
+    async fn test_cdn_403_retries_same_range_with_next_client() {
+        let mut server = mockito::Server::new_async().await;
+        let _mocks = vec![
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-1")
+                .with_status(206)
+                .with_header("content-range", "bytes 0-1/8")
+                .with_body("01")
+                .create_async()
+                .await,
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-7")
+                .with_status(403)
+                .expect(MAX_CDN_403_RETRIES as usize - 1)
+                .create_async()
+                .await,
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-7")
+                .with_status(206)
+                .with_body("01234567")
+                .create_async()
+                .await,
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let downloader = ChunkedDownloader::new().with_cdn_403_retry_delay(Duration::ZERO);
+        let url = format!("{}/videoplayback", server.url());
+        downloader
+            .download_ranged(&url, &output_path, 16)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"01234567");
+
+        // Each 403 moved on to the next client type
+        let clients = crate::platform::client::ClientType::all();
+        assert_eq!(
+            downloader.video_client.lock().await.current_client_type(),
+            clients[(MAX_CDN_403_RETRIES as usize - 1) % clients.len()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cdn_403_gives_up_after_limit() {
+        let mut server = mockito::Server::new_async().await;
+        let mocks = vec![
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-1")
+                .with_status(206)
+                .with_header("content-range", "bytes 0-1/8")
+                .with_body("01")
+                .create_async()
+                .await,
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-7")
+                .with_status(403)
+                .expect(MAX_CDN_403_RETRIES as usize)
+                .create_async()
+                .await,
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let downloader = ChunkedDownloader::new().with_cdn_403_retry_delay(Duration::ZERO);
+        let url = format!("{}/videoplayback", server.url());
+        let result = downloader.download_ranged(&url, &output_path, 16).await;
+        assert!(matches!(result, Err(RytError::RateLimited)));
+        mocks[1].assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_cdn_403_count_resets_after_other_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let range = |status: usize, hits: usize| {
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-7")
+                .with_status(status)
+                .expect(hits)
+        };
+        let probe = server
+            .mock("GET", "/videoplayback")
+            .match_header("range", "bytes=0-1")
+            .with_status(206)
+            .with_header("content-range", "bytes 0-1/8")
+            .with_body("01")
+            .create_async()
+            .await;
+        // Two 403s, another error, then two more 403s stay below the limit
+        let first = range(403, MAX_CDN_403_RETRIES as usize - 1)
+            .create_async()
+            .await;
+        let failed = range(500, 1).create_async().await;
+        let second = range(403, MAX_CDN_403_RETRIES as usize - 1)
+            .create_async()
+            .await;
+        let served = range(206, 1).with_body("01234567").create_async().await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let downloader = ChunkedDownloader::new().with_cdn_403_retry_delay(Duration::ZERO);
+        let url = format!("{}/videoplayback", server.url());
+        downloader
+            .download_ranged(&url, &output_path, 16)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"01234567");
+        for mock in [probe, first, failed, second, served] {
+            mock.assert_async().await;
+        }
+    }
+
+    /// Writer recording its data and counting `poll_write` calls
+    #[derive(Default)]
+    struct CountingWriter {
+        writes: usize,
+        data: Vec<u8>,
+    }
+
+    impl AsyncWrite for CountingWriter {
+        fn poll_write(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            self.writes += 1;
+            self.data.extend_from_slice(buf);
+            std::task::Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Stream 256 KiB in 1 KiB chunks through a write buffer, returning the write count
+    async fn count_stream_writes(buffer_size: usize) -> usize {
+        let chunks: Vec<Result<Vec<u8>, RytError>> =
+            (0..256).map(|i| Ok(vec![i as u8; 1024])).collect();
+        let mut writer = BufWriter::with_capacity(buffer_size, CountingWriter::default());
+        let mut progress = Vec::new();
+
+        let written = write_stream(
+            futures_util::stream::iter(chunks),
+            &mut writer,
+            None,
+            None,
+            None,
+            |downloaded| progress.push(downloaded),
+        )
+        .await
+        .unwrap();
+        writer.flush().await.unwrap();
+
+        let inner = writer.into_inner();
+        assert_eq!(written, 256 * 1024);
+        assert_eq!(inner.data.len(), 256 * 1024);
+        assert!(inner.data[..1024].iter().all(|&b| b == 0));
+        assert!(inner.data[255 * 1024..].iter().all(|&b| b == 255));
+        assert_eq!(progress.len(), 256);
+        assert_eq!(progress.last(), Some(&(256 * 1024)));
+        inner.writes
+    }
+
+    #[tokio::test]
+    async fn test_write_buffer_reduces_write_calls() {
+        assert_eq!(count_stream_writes(0).await, 256);
+        assert_eq!(count_stream_writes(64 * 1024).await, 4);
+        assert_eq!(count_stream_writes(DEFAULT_WRITE_BUFFER_SIZE).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_charges_rate_limiter() {
+        let limiter = Arc::new(Mutex::new(RateLimiter::new(1024 * 1024)));
+        let chunks: Vec<Result<Vec<u8>, RytError>> = vec![Ok(vec![0; 1000]), Ok(vec![0; 24])];
+        let mut writer = CountingWriter::default();
+
+        let written = write_stream(
+            futures_util::stream::iter(chunks),
+            &mut writer,
+            Some(&limiter),
+            None,
+            None,
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(written, 1024);
+        assert_eq!(limiter.lock().await.bytes_sent, 1024);
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_read_timeout() {
+        use futures_util::StreamExt;
+
+        let chunks: Vec<Result<Vec<u8>, RytError>> = vec![Ok(b"0123".to_vec())];
+        let stream = futures_util::stream::iter(chunks).chain(futures_util::stream::pending());
+        let mut writer = CountingWriter::default();
+
+        let result = write_stream(
+            stream,
+            &mut writer,
+            None,
+            None,
+            Some(Duration::from_millis(50)),
+            |_| {},
+        )
+        .await;
+
+        match result {
+            Err(e @ RytError::TimeoutError(_)) => assert!(e.is_retryable()),
+            other => panic!("expected TimeoutError, got {:?}", other),
+        }
+        assert_eq!(writer.data, b"0123");
+    }
+
+    #[test]
+    fn test_throttle_detector() {
+        let start = Instant::now();
+        let mut detector = ThrottleDetector::new(1000, Duration::from_secs(10));
+        detector.window_start = start;
+
+        // Nothing is decided before a whole window has passed
+        assert!(!detector.record_at(100, start + Duration::from_secs(5)));
+        // 20 KB in 10 s is fast enough, and starts a new window
+        assert!(!detector.record_at(19_900, start + Duration::from_secs(10)));
+        assert_eq!(detector.window_bytes, 0);
+        // 5 KB in the next 10 s is not
+        assert!(!detector.record_at(5_000, start + Duration::from_secs(15)));
+        assert!(detector.record_at(0, start + Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn test_throttle_detector_off_below_rate_limit() {
+        let downloader = ChunkedDownloader::new().with_throttled_rate(50_000);
+        assert!(downloader.throttle_detector().is_some());
+        assert!(ChunkedDownloader::new().throttle_detector().is_none());
+
+        // A rate limit at or below the threshold would always trip it
+        let limited = downloader.with_rate_limit(50_000);
+        assert!(limited.throttle_detector().is_none());
+        let limited = limited.with_rate_limit(200_000);
+        assert!(limited.throttle_detector().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_throttled() {
+        use futures_util::StreamExt;
+
+        // 10 bytes every 20 ms is far below 1 MB/s
+        let stream = futures_util::stream::iter(0..50)
+            .then(|_| async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok::<_, RytError>(vec![0u8; 10])
+            })
+            .boxed();
+        let mut writer = CountingWriter::default();
+
+        let result = write_stream(
+            stream,
+            &mut writer,
+            None,
+            Some(ThrottleDetector::new(
+                1024 * 1024,
+                Duration::from_millis(100),
+            )),
+            None,
+            |_| {},
+        )
+        .await;
+
+        match result {
+            Err(e @ RytError::Throttled(offset)) => {
+                assert!(e.is_retryable());
+                assert!(offset > 0 && offset < 500, "{}", offset);
+                assert_eq!(writer.data.len() as u64, offset);
+            }
+            other => panic!("expected Throttled, got {:?}", other),
+        }
+    }
+
+    /// Raw HTTP server for `body` that answers Range requests with 206 and
+    /// stalls the first `stalls` responses longer than `stall_after` bytes
+    /// after sending that many bytes. Returns the URL and the Range headers seen.
+    async fn stalling_server(
+        body: &'static [u8],
+        stall_after: usize,
+        stalls: usize,
+    ) -> (String, Arc<std::sync::Mutex<Vec<Option<String>>>>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/videoplayback", listener.local_addr().unwrap());
+        let ranges = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let stalls_left = Arc::new(AtomicUsize::new(stalls));
+
+        let seen = ranges.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let seen = seen.clone();
+                let stalls_left = stalls_left.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                    let range = request
+                        .lines()
+                        .find_map(|line| line.strip_prefix("range: bytes="))
+                        .map(|range| range.trim().to_string());
+                    seen.lock().unwrap().push(range.clone());
+
+                    let (status, start, end) =
+                        match range.as_deref().and_then(|r| r.split_once('-')) {
+                            Some((start, end)) => {
+                                let start: usize = start.parse().unwrap();
+                                let end = end.parse::<usize>().unwrap().min(body.len() - 1);
+                                ("206 Partial Content", start, end)
+                            }
+                            None => ("200 OK", 0, body.len() - 1),
+                        };
+                    let mut head = format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+                        status,
+                        end - start + 1
+                    );
+                    if range.is_some() {
+                        head +=
+                            &format!("Content-Range: bytes {}-{}/{}\r\n", start, end, body.len());
+                    }
+                    head += "\r\n";
+                    let _ = stream.write_all(head.as_bytes()).await;
+
+                    let stall = end - start + 1 > stall_after
+                        && stalls_left
+                            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                            .is_ok();
+                    if stall {
+                        let _ = stream.write_all(&body[start..start + stall_after]).await;
+                        let _ = stream.flush().await;
+                        tokio::time::sleep(Duration::from_secs(30)).await;
+                    } else {
+                        let _ = stream.write_all(&body[start..=end]).await;
+                    }
+                });
+            }
+        });
+
+        (url, ranges)
+    }
+
+    /// Raw HTTP server announcing 16 bytes that closes every connection after 4
+    async fn truncating_server() -> String {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/videoplayback", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 16\r\n\r\n0123")
+                    .await;
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_create_error_names_partial_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("missing").join("video.mp4");
+
+        let downloader = ChunkedDownloader::new();
+        let err = downloader
+            .download("http://127.0.0.1:9/videoplayback", &output_path)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RytError::Io { op: "create", .. }));
+        let partial = dir.path().join("missing").join("video.tmp");
+        assert!(
+            err.to_string().contains(&partial.display().to_string()),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_keeps_partial_when_configured() {
+        let url = truncating_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+
+        let downloader = ChunkedDownloader::new().with_keep_temp(true);
+        assert!(downloader.download(&url, &output_path).await.is_err());
+        assert!(!output_path.exists());
+        assert!(!dir.path().join("video.tmp").exists());
+        assert_eq!(
+            std::fs::read(dir.path().join("video.tmp.part")).unwrap(),
+            b"0123"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_removes_partial_by_default() {
+        let url = truncating_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+
+        let downloader = ChunkedDownloader::new();
+        assert!(downloader.download(&url, &output_path).await.is_err());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_resumes_with_ranges() {
+        let (url, ranges) = stalling_server(b"0123456789abcdef", 4, 1).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+
+        let downloader = ChunkedDownloader::new().with_read_timeout(Duration::from_millis(200));
+        downloader.download(&url, &output_path).await.unwrap();
+
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"0123456789abcdef");
+        let ranges = ranges.lock().unwrap().clone();
+        assert_eq!(ranges[0], None);
+        // The range download picks up after the bytes received before the stall
+        assert_eq!(ranges.last().unwrap().as_deref(), Some("4-15"));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_chunk_is_retried() {
+        let (url, ranges) = stalling_server(b"0123456789abcdef", 2, 1).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+
+        let downloader = ChunkedDownloader::new()
+            .with_read_timeout(Duration::from_millis(200))
+            .with_fragment_retries(2);
+        downloader
+            .download_ranged(&url, &output_path, 8)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"0123456789abcdef");
+        let ranges: Vec<_> = ranges.lock().unwrap().iter().flatten().cloned().collect();
+        // The retry resumes after the two bytes written before the stall
+        assert_eq!(ranges, vec!["0-1", "0-7", "2-7", "8-15"]);
+    }
+
+    /// Allocator recording the largest allocation made on threads that opted in
+    mod peak_alloc {
+        use std::alloc::{GlobalAlloc, Layout, System};
+        use std::cell::Cell;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        pub struct PeakAllocator;
+
+        static LARGEST: AtomicUsize = AtomicUsize::new(0);
+
+        thread_local! {
+            static TRACKING: Cell<bool> = const { Cell::new(false) };
+        }
+
+        unsafe impl GlobalAlloc for PeakAllocator {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                record(layout.size());
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+
+            unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+                record(new_size);
+                System.realloc(ptr, layout, new_size)
+            }
+        }
+
+        fn record(size: usize) {
+            if TRACKING.try_with(Cell::get).unwrap_or(false) {
+                LARGEST.fetch_max(size, Ordering::Relaxed);
+            }
+        }
+
+        /// Start tracking allocations on the current thread
+        pub fn start() {
+            LARGEST.store(0, Ordering::Relaxed);
+            TRACKING.with(|tracking| tracking.set(true));
+        }
+
+        /// Stop tracking and return the largest allocation seen
+        pub fn stop() -> usize {
+            TRACKING.with(|tracking| tracking.set(false));
+            LARGEST.load(Ordering::Relaxed)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: peak_alloc::PeakAllocator = peak_alloc::PeakAllocator;
+
+    /// Byte at `offset` of the generated test content
+    fn pattern_byte(offset: usize) -> u8 {
+        (offset % 251) as u8
+    }
+
+    /// Raw HTTP server for `len` bytes of generated content that answers
+    /// Range requests with 206, writing the body from a small reused buffer
+    async fn pattern_server(len: usize) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/videoplayback", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 64 * 1024];
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                    let (start, end) = request
+                        .lines()
+                        .find_map(|line| line.strip_prefix("range: bytes="))
+                        .and_then(|range| range.trim().split_once('-'))
+                        .map(|(start, end)| {
+                            let end = end.parse::<usize>().unwrap().min(len - 1);
+                            (start.parse::<usize>().unwrap(), end)
+                        })
+                        .unwrap_or((0, len - 1));
+
+                    let head = format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                        end - start + 1,
+                        start,
+                        end,
+                        len
+                    );
+                    if stream.write_all(head.as_bytes()).await.is_err() {
+                        return;
+                    }
+
+                    let mut offset = start;
+                    while offset <= end {
+                        let n = buf.len().min(end - offset + 1);
+                        for (i, byte) in buf[..n].iter_mut().enumerate() {
+                            *byte = pattern_byte(offset + i);
+                        }
+                        if stream.write_all(&buf[..n]).await.is_err() {
+                            return;
+                        }
+                        offset += n;
+                    }
+                });
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn test_download_ranged_memory_stays_bounded() {
+        const LEN: usize = 50 * 1024 * 1024;
+        const CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+        let url = pattern_server(LEN).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let downloader = ChunkedDownloader::new();
+
+        // The test runtime runs client and server on this thread
+        peak_alloc::start();
+        let result = downloader
+            .download_ranged(&url, &output_path, CHUNK_SIZE)
+            .await;
+        let largest = peak_alloc::stop();
+        result.unwrap();
+
+        // Buffering a chunk would need an allocation of at least the chunk size
+        assert!(
+            largest < 2 * 1024 * 1024,
+            "largest allocation was {} bytes",
+            largest
+        );
+
+        let data = std::fs::read(&output_path).unwrap();
+        assert_eq!(data.len(), LEN);
+        assert!(data
+            .iter()
+            .enumerate()
+            .all(|(offset, &byte)| byte == pattern_byte(offset)));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_chunk_fails_after_retries() {
+        let (url, _ranges) = stalling_server(b"0123456789abcdef", 2, 10).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+
+        let downloader = ChunkedDownloader::new()
+            .with_read_timeout(Duration::from_millis(100))
+            .with_fragment_retries(2);
+        let result = downloader.download_ranged(&url, &output_path, 8).await;
+        assert!(matches!(result, Err(RytError::TimeoutError(_))));
+    }
+
+    #[test]
+    fn test_chunked_downloader_timeouts() {
+        let downloader = ChunkedDownloader::new()
+            .with_connect_timeout(Duration::from_secs(5))
+            .with_read_timeout(Duration::from_secs(60))
+            .with_request_timeout(Duration::from_secs(3600));
+        assert_eq!(downloader.config.connect_timeout, Duration::from_secs(5));
+        assert_eq!(
+            downloader.config.read_timeout,
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            downloader.config.request_timeout,
+            Some(Duration::from_secs(3600))
+        );
+    }
+
+    #[test]
+    fn test_chunked_downloader_fork() {
+        let downloader = ChunkedDownloader::new()
+            .with_rate_limit(1000)
+            .with_global_rate_limit(true);
+        let fork = downloader.fork().with_progress_callback(|_| {});
+
+        assert!(Arc::ptr_eq(&downloader.video_client, &fork.video_client));
+        assert!(Arc::ptr_eq(
+            downloader.rate_limiter.as_ref().unwrap(),
+            fork.rate_limiter.as_ref().unwrap()
+        ));
+        assert!(fork.config.progress_callback.is_some());
+        assert!(downloader.config.progress_callback.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chunked_downloader_ip_family() {
+        let downloader = ChunkedDownloader::new();
+        assert_eq!(
+            downloader.media_http_config().await.ip_family,
+            IpFamily::Any
+        );
+
+        let downloader = ChunkedDownloader::with_config(DownloaderConfig {
+            ip_family: IpFamily::V4,
+            ..DownloaderConfig::default()
+        });
+        let http_config = downloader.media_http_config().await;
+        assert_eq!(http_config.ip_family, IpFamily::V4);
+        assert!(http_config.http1_only);
+        assert!(http_config.prefer_ipv6);
+
+        let downloader = ChunkedDownloader::new().with_ip_family(IpFamily::V6);
+        assert_eq!(downloader.media_http_config().await.ip_family, IpFamily::V6);
+    }
+
+    #[test]
+    fn test_chunked_downloader_speed_smoothing() {
+        let downloader = ChunkedDownloader::new().with_speed_smoothing(0.5);
+        assert_eq!(downloader.config.progress.speed_smoothing_alpha, 0.5);
+        assert_eq!(downloader.new_progress(100).speed_smoothing_alpha, 0.5);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_metrics_after_mocked_download() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+        use std::collections::HashMap;
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        ::metrics::with_local_recorder(&recorder, || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                let (server, _mocks) = flaky_fragment_server(1).await;
+                let dir = tempfile::tempdir().unwrap();
+                let output_path = dir.path().join("video.mp4");
+                let downloader = ChunkedDownloader::new().with_fragment_retries(2);
+                let url = format!("{}/videoplayback", server.url());
+                downloader
+                    .download_ranged(&url, &output_path, 16)
+                    .await
+                    .unwrap();
+            });
+        });
+
+        let mut counters: HashMap<String, u64> = HashMap::new();
+        for (key, _, _, value) in snapshotter.snapshot().into_vec() {
+            if let DebugValue::Counter(count) = value {
+                *counters.entry(key.key().name().to_string()).or_default() += count;
+            }
+        }
+        assert_eq!(counters.get(metrics::BYTES_DOWNLOADED), Some(&8));
+        assert_eq!(counters.get(metrics::RETRIES), Some(&1));
+        assert!(counters.get(metrics::CLIENT_SWITCHES).copied().unwrap_or(0) >= 1);
+    }
+
+    #[test]
+    fn test_validate_content_range() {
+        assert_eq!(
+            validate_content_range(0, 99, "bytes 0-99/1000").unwrap(),
+            1000
+        );
+        assert_eq!(
+            validate_content_range(100, 199, "bytes 100-199/*").unwrap(),
+            0
+        );
+        // Last chunk may be shorter than requested
+        assert_eq!(
+            validate_content_range(900, 1099, "bytes 900-999/1000").unwrap(),
+            1000
+        );
+
+        // Different range than requested
+        assert!(matches!(
+            validate_content_range(100, 199, "bytes 0-999/1000"),
+            Err(RytError::RangeMismatch(_))
+        ));
+        assert!(validate_content_range(100, 199, "bytes 100-149/1000").is_err());
+        assert!(validate_content_range(100, 199, "bytes 100-299/1000").is_err());
+
+        // Malformed headers
+        assert!(validate_content_range(0, 99, "").is_err());
+        assert!(validate_content_range(0, 99, "bytes */1000").is_err());
+        assert!(validate_content_range(0, 99, "bytes 99-0/1000").is_err());
+        assert!(validate_content_range(0, 99, "bytes 0-1000/1000").is_err());
+    }
+
+    #[test]
+    fn test_parse_unsatisfied_range() {
+        assert_eq!(parse_unsatisfied_range("bytes */8"), Some(8));
+        assert_eq!(parse_unsatisfied_range(" bytes */1024 "), Some(1024));
+        assert_eq!(parse_unsatisfied_range("bytes */*"), None);
+        assert_eq!(parse_unsatisfied_range("bytes 0-1/8"), None);
+        assert_eq!(parse_unsatisfied_range(""), None);
+    }
+
+    #[test]
+    fn test_range_param_rewriting() {
+        let url = "https://rr1.googlevideo.com/videoplayback?id=1&range=0-999&sig=a%3Db&clen=5000";
+        assert!(has_range_param(url));
+        assert_eq!(query_param(url, "clen"), Some("5000"));
+        assert_eq!(
+            with_range_param(url, 1000, 1999),
+            "https://rr1.googlevideo.com/videoplayback?id=1&range=1000-1999&sig=a%3Db&clen=5000"
+        );
+
+        assert!(!has_range_param("https://host/videoplayback?id=1&rangex=1"));
+        assert!(!has_range_param("https://host/videoplayback"));
+        assert_eq!(query_param("https://host/v?id=1", "clen"), None);
+    }
+
+    #[tokio::test]
+    async fn test_download_rewrites_range_query_instead_of_header() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for (range, body) in [("0-3", "0123"), ("4-7", "4567"), ("8-9", "89")] {
+            mocks.push(
+                server
+                    .mock("GET", "/videoplayback")
+                    .match_query(mockito::Matcher::AllOf(vec![
+                        mockito::Matcher::UrlEncoded("range".into(), range.into()),
+                        mockito::Matcher::UrlEncoded("id".into(), "1".into()),
+                    ]))
+                    .match_header("range", mockito::Matcher::Missing)
+                    .with_body(body)
+                    .expect(1)
+                    .create_async()
+                    .await,
+            );
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let downloader = ChunkedDownloader::new().with_chunk_size(4);
+        let url = format!("{}/videoplayback?id=1&range=0-9&clen=10", server.url());
+        downloader.download(&url, &output_path).await.unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"0123456789");
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+
+        // Without clen, chunks continue until one comes up short
+        let output_path = dir.path().join("video2.mp4");
+        let url = format!("{}/videoplayback?id=1&range=0-9", server.url());
+        let mut _mocks = Vec::new();
+        for (range, body) in [("0-3", "0123"), ("4-7", "4567"), ("8-11", "89")] {
+            _mocks.push(
+                server
+                    .mock("GET", "/videoplayback")
+                    .match_query(mockito::Matcher::UrlEncoded("range".into(), range.into()))
+                    .match_header("range", mockito::Matcher::Missing)
+                    .with_body(body)
+                    .create_async()
+                    .await,
+            );
+        }
+        downloader.download(&url, &output_path).await.unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"0123456789");
+    }
+
+    #[tokio::test]
+    async fn test_download_sends_visitor_id_for_mobile_clients() {
+        let mut server = mockito::Server::new_async().await;
+        let ios = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::UrlEncoded("c".into(), "IOS".into()))
+            .match_header("x-goog-visitor-id", "visitor123")
+            .with_body("0123456789")
+            .expect(1)
+            .create_async()
+            .await;
+        let web = server
+            .mock("GET", "/videoplayback")
+            .match_query(mockito::Matcher::UrlEncoded("c".into(), "WEB".into()))
+            .match_header("x-goog-visitor-id", mockito::Matcher::Missing)
+            .with_body("0123456789")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = ChunkedDownloader::new()
+            .with_chunk_size(16)
+            .with_visitor_data("visitor123");
+        for client in ["IOS", "WEB"] {
+            let output_path = dir.path().join(format!("{}.mp4", client));
+            let url = format!(
+                "{}/videoplayback?c={}&range=0-9&clen=10",
+                server.url(),
+                client
+            );
+            downloader.download(&url, &output_path).await.unwrap();
+            assert_eq!(std::fs::read(&output_path).unwrap(), b"0123456789");
+        }
+        ios.assert_async().await;
+        web.assert_async().await;
+
+        assert!(needs_visitor_id(
+            "https://host/videoplayback?c=ANDROID_VR&id=1"
+        ));
+        assert!(!needs_visitor_id("https://host/videoplayback?c=TVHTML5"));
+        assert!(!needs_visitor_id("https://host/videoplayback?id=1"));
+
+        let headers = downloader.media_headers("https://host/videoplayback?c=IOS");
+        assert_eq!(
+            headers[0],
+            ("User-Agent".to_string(), MEDIA_USER_AGENT.to_string())
+        );
+        assert_eq!(
+            headers[1],
+            ("X-Goog-Visitor-Id".to_string(), "visitor123".to_string())
+        );
+        assert_eq!(
+            downloader
+                .media_headers("https://host/videoplayback?c=WEB")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_chunk_window() {
+        let data = b"0123456789";
+        let take = |data_start, start, end| {
+            let mut window = ChunkWindow::new(data_start, start, end).unwrap();
+            (window.take(data).to_vec(), window)
+        };
+
+        assert_eq!(take(0, 4, 7).0, b"4567");
+        assert!(take(0, 4, 7).1.is_full());
+        assert_eq!(take(0, 8, 15).0, b"89");
+        assert!(!take(0, 8, 15).1.is_full());
+        assert_eq!(take(4, 4, 5).0, b"45");
+        assert!(ChunkWindow::new(5, 4, 7).is_err());
+        let (taken, window) = take(0, 10, 12);
+        assert!(taken.is_empty());
+        assert!(!window.has_started());
+
+        // Frames split anywhere across the window boundaries
+        let mut window = ChunkWindow::new(0, 3, 6).unwrap();
+        let taken: Vec<u8> = [&data[..2], &data[2..5], &data[5..]]
+            .iter()
+            .flat_map(|frame| window.take(frame).to_vec())
+            .collect();
+        assert_eq!(taken, b"3456");
+        assert!(window.is_full());
+    }
+
+    #[tokio::test]
+    async fn test_download_ranged_realigns_mismatched_ranges() {
+        let mut server = mockito::Server::new_async().await;
+        let content = b"0123456789abcdef";
+        let _mocks = vec![
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-1")
+                .with_status(206)
+                .with_header("content-range", "bytes 0-1/16")
+                .with_body(&content[0..2])
+                .create_async()
+                .await,
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=0-7")
+                .with_status(206)
+                .with_header("content-range", "bytes 0-7/16")
+                .with_body(&content[0..8])
+                .create_async()
+                .await,
+            // A cache that ignores the range and replays the whole file
+            server
+                .mock("GET", "/videoplayback")
+                .match_header("range", "bytes=8-15")
+                .with_status(206)
+                .with_header("content-range", "bytes 0-15/16")
+                .with_body(content)
+                .create_async()
+                .await,
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let downloader = ChunkedDownloader::new();
+        let url = format!("{}/videoplayback", server.url());
+        downloader
+            .download_ranged(&url, &output_path, 8)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), content);
+    }
+
+    #[tokio::test]
+    async fn test_fragment_retries() {
+        let failures = 2;
+
+        // Enough fragment retries: completes on the last attempt
+        let (server, _mocks) = flaky_fragment_server(failures).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let downloader = ChunkedDownloader::new()
+            .with_max_retries(1)
+            .with_fragment_retries(failures as u32 + 1);
+        let url = format!("{}/videoplayback", server.url());
+        downloader
+            .download_ranged(&url, &output_path, 16)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"01234567");
+
+        // Too few fragment retries: gives up
+        let (server, _mocks) = flaky_fragment_server(failures).await;
+        let output_path = dir.path().join("video2.mp4");
+        let downloader = ChunkedDownloader::new().with_fragment_retries(failures as u32);
+        let url = format!("{}/videoplayback", server.url());
+        assert!(downloader
+            .download_ranged(&url, &output_path, 16)
+            .await
+            .is_err());
+        assert!(!output_path.exists());
+    }
+
+    /// Serve `count` segments whose segment 2 fails `failures` times before
+    /// succeeding, so later segments complete before it
+    async fn segment_server(
+        count: usize,
+        failures: usize,
+    ) -> (mockito::ServerGuard, Vec<mockito::Mock>) {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = vec![
+            server
+                .mock("GET", "/seg/2")
+                .with_status(503)
+                .expect(failures)
+                .create_async()
+                .await,
+        ];
+        for index in 0..count {
+            mocks.push(
+                server
+                    .mock("GET", format!("/seg/{}", index).as_str())
+                    .with_status(200)
+                    .with_body(format!("segment-{};", index))
+                    .expect(1)
+                    .create_async()
+                    .await,
+            );
+        }
+        (server, mocks)
+    }
+
+    #[tokio::test]
+    async fn test_download_segments_in_order() {
+        let (server, mocks) = segment_server(8, 2).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.ts");
+        let urls: Vec<String> = (0..8)
+            .map(|index| format!("{}/seg/{}", server.url(), index))
+            .collect();
+
+        let downloader = ChunkedDownloader::new()
+            .with_concurrent_fragments(4)
+            .with_fragment_retries(3);
+        downloader
+            .download_segments(&urls, &output_path)
+            .await
+            .unwrap();
+
+        let expected: String = (0..8).map(|index| format!("segment-{};", index)).collect();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), expected);
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_segments_fails_after_fragment_retries() {
+        let (server, _mocks) = segment_server(8, 2).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.ts");
+        let urls: Vec<String> = (0..8)
+            .map(|index| format!("{}/seg/{}", server.url(), index))
+            .collect();
+
+        let downloader = ChunkedDownloader::new()
+            .with_concurrent_fragments(4)
+            .with_fragment_retries(2);
+        assert!(downloader
+            .download_segments(&urls, &output_path)
+            .await
+            .is_err());
+        assert!(!output_path.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        assert!(downloader
+            .download_segments(&[], &output_path)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_with_concurrent_fragments() {
+        let downloader = ChunkedDownloader::new().with_concurrent_fragments(4);
+        assert_eq!(downloader.config.concurrent_fragments, 4);
+        assert_eq!(downloader.fragment_slots.available_permits(), 4);
+        let downloader = ChunkedDownloader::new().with_concurrent_fragments(0);
+        assert_eq!(downloader.config.concurrent_fragments, 1);
+        assert_eq!(downloader.fragment_slots.available_permits(), 1);
+
+        // Forks draw from the same permits
+        let fork = downloader.fork();
+        assert!(Arc::ptr_eq(
+            &fork.fragment_slots,
+            &downloader.fragment_slots
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_download_hls() {
+        let mut server = mockito::Server::new_async().await;
+        let master = format!(
+            "#EXTM3U\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=600000,RESOLUTION=640x360\n\
+             {0}/360/index.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=3000000,RESOLUTION=1280x720\n\
+             {0}/720/index.m3u8\n",
+            server.url()
+        );
+        let master_mock = server
+            .mock("GET", "/master.m3u8")
+            .with_body(master)
+            .create_async()
+            .await;
+        let media = (0..4).fold(
+            "#EXTM3U\n#EXT-X-TARGETDURATION:5\n".to_string(),
+            |playlist, index| format!("{}#EXTINF:5.0,\n../seg/{}\n", playlist, index),
+        );
+        let media_mock = server
+            .mock("GET", "/360/index.m3u8")
+            .with_body(media + "#EXT-X-ENDLIST\n")
+            .create_async()
+            .await;
+        let mut segment_mocks = Vec::new();
+        for index in 0..4 {
+            segment_mocks.push(
+                server
+                    .mock("GET", format!("/seg/{}", index).as_str())
+                    .with_body(format!("segment-{};", index))
+                    .expect(1)
+                    .create_async()
+                    .await,
+            );
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("live.ts");
+        let downloader = ChunkedDownloader::new().with_concurrent_fragments(2);
+        let url = format!("{}/master.m3u8", server.url());
+        downloader
+            .download_hls(&url, &output_path, Some(480))
+            .await
+            .unwrap();
+
+        let expected: String = (0..4).map(|index| format!("segment-{};", index)).collect();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), expected);
+        master_mock.assert_async().await;
+        media_mock.assert_async().await;
+        for mock in segment_mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_ranged_resumes_from_temp_dir() {
+        let mut server = mockito::Server::new_async().await;
+        let _content_length = server
+            .mock("GET", "/videoplayback")
+            .match_header("range", "bytes=0-1")
+            .with_status(206)
+            .with_header("content-range", "bytes 0-1/8")
+            .with_body("01")
+            .create_async()
+            .await;
+        // Only the remaining bytes are served
+        let _rest = server
+            .mock("GET", "/videoplayback")
+            .match_header("range", "bytes=4-7")
+            .with_status(206)
+            .with_body("4567")
+            .create_async()
+            .await;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        let output_path = output_dir.path().join("video.mp4");
+        let url = format!("{}/videoplayback?itag=18&sig=old", server.url());
+
+        // Partial left by an earlier run with a different signature
+        let earlier_url = format!("{}/videoplayback?itag=18&sig=older", server.url());
+        let partial = partial_file_path(Some(temp_dir.path()), &output_path, &earlier_url);
+        std::fs::write(&partial, b"0123").unwrap();
+
+        let downloader = ChunkedDownloader::new().with_temp_dir(temp_dir.path());
+        downloader
+            .download_ranged(&url, &output_path, 4)
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"01234567");
+        assert!(!partial.exists());
+        assert!(!output_path.with_extension("tmp").exists());
+    }
+
+    /// Server whose size probe fails, so the size is only learned from a 416
+    async fn unsatisfiable_resume_server(resume_offset: usize) -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/videoplayback")
+            .match_header("range", "bytes=0-1")
+            .with_status(500)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/videoplayback")
+            .match_header(
+                "range",
+                mockito::Matcher::Regex(format!("^bytes={}-", resume_offset)),
+            )
+            .with_status(416)
+            .with_header("content-range", "bytes */8")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/videoplayback")
+            .match_header("range", "bytes=0-7")
+            .with_status(206)
+            .with_header("content-range", "bytes 0-7/8")
+            .with_body("01234567")
+            .create_async()
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_download_ranged_416_finalizes_complete_partial() {
+        let server = unsatisfiable_resume_server(8).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let url = format!("{}/videoplayback", server.url());
+        let partial = partial_file_path(None, &output_path, &url);
+        std::fs::write(&partial, b"01234567").unwrap();
+
+        let downloader = ChunkedDownloader::new().with_fragment_retries(1);
+        downloader
+            .download_ranged(&url, &output_path, 16)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"01234567");
+        assert!(!partial.exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_ranged_416_restarts_oversized_partial() {
+        let server = unsatisfiable_resume_server(12).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let url = format!("{}/videoplayback", server.url());
+        let partial = partial_file_path(None, &output_path, &url);
+        std::fs::write(&partial, b"0123456789ab").unwrap();
+
+        let downloader = ChunkedDownloader::new().with_fragment_retries(1);
+        downloader
+            .download_ranged(&url, &output_path, 16)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"01234567");
+        assert!(!partial.exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_ranged_known_size_oversized_partial() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/videoplayback")
+            .match_header("range", "bytes=0-1")
+            .with_status(206)
+            .with_header("content-range", "bytes 0-1/4")
+            .with_body("01")
+            .create_async()
+            .await;
+        let _rest = server
+            .mock("GET", "/videoplayback")
+            .match_header("range", "bytes=0-3")
+            .with_status(206)
+            .with_header("content-range", "bytes 0-3/4")
+            .with_body("0123")
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("video.mp4");
+        let url = format!("{}/videoplayback", server.url());
+        let partial = partial_file_path(None, &output_path, &url);
+        std::fs::write(&partial, b"corrupted").unwrap();
+
+        let downloader = ChunkedDownloader::new();
+        downloader
+            .download_ranged(&url, &output_path, 4)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"0123");
     }
 
     #[tokio::test]
@@ -225,6 +1719,118 @@ mod tests {
         let elapsed = start.elapsed();
         assert!(elapsed < Duration::from_millis(100));
     }
+
+    fn assert_wait_ms(actual: Duration, expected_ms: u64) {
+        let actual_ms = actual.as_secs_f64() * 1000.0;
+        assert!(
+            (actual_ms - expected_ms as f64).abs() < 1.0,
+            "expected ~{}ms, got {:?}",
+            expected_ms,
+            actual
+        );
+    }
+
+    /// Feed `(offset_ms, bytes)` chunks to a limiter, sleeping as told, and
+    /// return the simulated finish time
+    fn simulate_load(limiter: &mut RateLimiter, chunks: &[(u64, u64)]) -> Duration {
+        let origin = limiter.started_at;
+        let mut clock = Duration::ZERO;
+        for &(offset_ms, bytes) in chunks {
+            clock = clock.max(Duration::from_millis(offset_ms));
+            clock += limiter.reserve(bytes, origin + clock);
+        }
+        clock
+    }
+
+    #[test]
+    fn test_token_bucket_steady_rate_with_variable_chunks() {
+        let mut limiter = RateLimiter::new(1000);
+        let chunks = [(0, 100), (0, 900), (0, 50), (0, 1950), (0, 1000)];
+
+        // 4000 bytes at 1000 B/s take 4s however they are split
+        assert_wait_ms(simulate_load(&mut limiter, &chunks), 4000);
+        assert_eq!(limiter.bytes_sent, 4000);
+    }
+
+    #[test]
+    fn test_token_bucket_bursty_load() {
+        let mut limiter = RateLimiter::new(1000);
+
+        // A single large burst is paid off in full
+        assert_wait_ms(simulate_load(&mut limiter, &[(0, 5000)]), 5000);
+
+        // Idle time refills at most one second of burst
+        let t = limiter.started_at + Duration::from_secs(60);
+        assert_eq!(limiter.reserve(1000, t), Duration::ZERO);
+        assert_wait_ms(limiter.reserve(500, t), 500);
+    }
+
+    #[test]
+    fn test_token_bucket_does_not_reset_on_each_call() {
+        let mut limiter = RateLimiter::new(1000);
+        let start = limiter.started_at;
+
+        // Chunks arriving faster than the rate accumulate debt
+        let first = limiter.reserve(1000, start);
+        let second = limiter.reserve(1000, start + Duration::from_millis(100));
+        assert_wait_ms(first, 1000);
+        assert_wait_ms(second, 1900);
+    }
+
+    #[test]
+    fn test_rate_limit_grace_bytes() {
+        let mut limiter = RateLimiter::new(1000).with_grace(Some(RateLimitGrace::Bytes(3000)));
+        let start = limiter.started_at;
+
+        assert_eq!(limiter.reserve(2000, start), Duration::ZERO);
+        // Only the 500 bytes past the threshold are charged
+        assert_wait_ms(limiter.reserve(1500, start), 500);
+        assert_wait_ms(limiter.reserve(1000, start), 1500);
+    }
+
+    #[test]
+    fn test_rate_limit_grace_duration() {
+        let mut limiter = RateLimiter::new(1000)
+            .with_grace(Some(RateLimitGrace::Duration(Duration::from_secs(30))));
+        let start = limiter.started_at;
+
+        assert_eq!(limiter.reserve(50_000, start), Duration::ZERO);
+        assert_eq!(
+            limiter.reserve(50_000, start + Duration::from_secs(29)),
+            Duration::ZERO
+        );
+        // Grace bytes are never charged; the bucket refills from the last chunk
+        let t = start + Duration::from_secs(30);
+        assert_eq!(limiter.reserve(1000, t), Duration::ZERO);
+        assert_wait_ms(limiter.reserve(1000, t), 1000);
+        assert_eq!(limiter.bytes_sent, 102_000);
+    }
+
+    #[test]
+    fn test_download_rate_limiter_per_download_or_global() {
+        let downloader = ChunkedDownloader::new().with_rate_limit(1000);
+        let a = downloader.download_rate_limiter().unwrap();
+        let b = downloader.download_rate_limiter().unwrap();
+        assert!(!Arc::ptr_eq(&a, &b));
+
+        let downloader = downloader.with_global_rate_limit(true);
+        let a = downloader.download_rate_limiter().unwrap();
+        let b = downloader.download_rate_limiter().unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+
+        assert!(ChunkedDownloader::new().download_rate_limiter().is_none());
+    }
+
+    #[test]
+    fn test_with_rate_limit_after() {
+        let downloader = ChunkedDownloader::new()
+            .with_rate_limit(1000)
+            .with_rate_limit_after(RateLimitGrace::Bytes(20 * 1024 * 1024));
+        assert_eq!(
+            downloader.config.rate_limit_after,
+            Some(RateLimitGrace::Bytes(20 * 1024 * 1024))
+        );
+    }
 }
 
 /// Chunked downloader
@@ -232,44 +1838,143 @@ pub struct ChunkedDownloader {
     video_client: Arc<Mutex<VideoClient>>,
     config: DownloaderConfig,
     rate_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    /// Permits for segment requests, shared with forks so concurrent
+    /// downloads together fetch at most `concurrent_fragments` segments
+    fragment_slots: Arc<Semaphore>,
 }
 
-/// Rate limiter for controlling download speed
+/// Threshold after which rate limiting kicks in for a download
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitGrace {
+    /// Limit only after this many bytes
+    Bytes(u64),
+    /// Limit only after this much time
+    Duration(#[serde(with = "duration_serde")] Duration),
+}
+
+/// Token-bucket rate limiter for controlling download speed
 struct RateLimiter {
     bytes_per_second: u64,
-    last_update: std::time::Instant,
+    /// Available tokens in bytes; negative while a burst is being paid off
+    tokens: f64,
+    last_refill: Instant,
     bytes_sent: u64,
+    grace: Option<RateLimitGrace>,
+    started_at: Instant,
 }
 
 impl RateLimiter {
     fn new(bytes_per_second: u64) -> Self {
+        let now = Instant::now();
         Self {
             bytes_per_second,
-            last_update: std::time::Instant::now(),
+            tokens: 0.0,
+            last_refill: now,
             bytes_sent: 0,
+            grace: None,
+            started_at: now,
         }
     }
 
+    fn with_grace(mut self, grace: Option<RateLimitGrace>) -> Self {
+        self.grace = grace;
+        self
+    }
+
     async fn wait_if_needed(&mut self, bytes: u64) {
-        let now = std::time::Instant::now();
-        let elapsed = now.duration_since(self.last_update);
+        let wait_time = self.reserve(bytes, Instant::now());
+        if wait_time > Duration::from_millis(1) {
+            tokio::time::sleep(wait_time).await;
+        }
+    }
 
-        // Calculate how many bytes we should have sent by now
-        let expected_bytes = (elapsed.as_secs_f64() * self.bytes_per_second as f64) as u64;
+    /// Account for `bytes` sent at `now` and return how long to wait
+    fn reserve(&mut self, bytes: u64, now: Instant) -> Duration {
+        let sent_before = self.bytes_sent;
+        self.bytes_sent += bytes;
 
-        if self.bytes_sent + bytes > expected_bytes {
-            // We're going too fast, need to wait
-            let excess_bytes = (self.bytes_sent + bytes) - expected_bytes;
-            let wait_time =
-                Duration::from_secs_f64(excess_bytes as f64 / self.bytes_per_second as f64);
+        if self.bytes_per_second == 0 {
+            return Duration::ZERO;
+        }
 
-            if wait_time > Duration::from_millis(1) {
-                tokio::time::sleep(wait_time).await;
+        // Only bytes past the grace threshold are charged
+        let limited_bytes = match self.grace {
+            Some(RateLimitGrace::Bytes(threshold)) => {
+                self.bytes_sent.saturating_sub(threshold.max(sent_before))
+            }
+            Some(RateLimitGrace::Duration(grace))
+                if now.saturating_duration_since(self.started_at) < grace =>
+            {
+                0
             }
+            _ => bytes,
+        };
+        if limited_bytes == 0 {
+            self.last_refill = now;
+            return Duration::ZERO;
         }
 
-        self.bytes_sent += bytes;
-        self.last_update = now;
+        // Refill for the time since the last call, allowing at most one second of burst
+        let rate = self.bytes_per_second as f64;
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate);
+        self.last_refill = now;
+
+        self.tokens -= limited_bytes as f64;
+        if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// Detector for throughput staying below a minimum rate.
+///
+/// Throughput is averaged over consecutive windows, so short dips don't
+/// count; only a whole window below the minimum does.
+#[derive(Debug)]
+struct ThrottleDetector {
+    min_bytes_per_second: u64,
+    window: Duration,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl ThrottleDetector {
+    fn new(min_bytes_per_second: u64, window: Duration) -> Self {
+        Self {
+            min_bytes_per_second,
+            window,
+            window_start: Instant::now(),
+            window_bytes: 0,
+        }
+    }
+
+    /// Account for `bytes` written and check if the download is throttled
+    fn record(&mut self, bytes: u64) -> bool {
+        self.record_at(bytes, Instant::now())
+    }
+
+    /// Account for `bytes` written at `now` and check if the window that
+    /// just ended averaged below the minimum rate
+    fn record_at(&mut self, bytes: u64, now: Instant) -> bool {
+        self.window_bytes += bytes;
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < self.window {
+            return false;
+        }
+
+        let rate = self.window_bytes as f64 / elapsed.as_secs_f64();
+        if rate < self.min_bytes_per_second as f64 {
+            return true;
+        }
+        self.window_start = now;
+        self.window_bytes = 0;
+        false
     }
 }
 
@@ -281,44 +1986,113 @@ impl ChunkedDownloader {
 
     /// Create a new chunked downloader with configuration
     pub fn with_config(config: DownloaderConfig) -> Self {
-        // Create HTTP/1.1-only client for media downloads (matches Go ytdlp line 182)
-        let mut http_config = crate::platform::client::HttpClientConfig::default();
-        http_config.http1_only = true; // Force HTTP/1.1 for media downloads
-        http_config.client_type = crate::platform::client::ClientType::Chrome;
-        let video_client = Arc::new(Mutex::new(VideoClient::with_config(http_config)));
-
-        let rate_limiter = config
-            .rate_limit_bps
-            .map(|bps| Arc::new(Mutex::new(RateLimiter::new(bps))));
+        let video_client = Self::new_media_client(&config);
+        let rate_limiter = Self::new_rate_limiter(&config);
+        let fragment_slots = Arc::new(Semaphore::new(config.concurrent_fragments.max(1)));
 
         Self {
             video_client,
             config,
             rate_limiter,
+            fragment_slots,
         }
     }
 
+    /// Get downloader configuration
+    pub fn config(&self) -> &DownloaderConfig {
+        &self.config
+    }
+
+    /// Create a downloader sharing this one's media client, global rate
+    /// limit and segment request permits
+    pub fn fork(&self) -> Self {
+        Self {
+            video_client: Arc::clone(&self.video_client),
+            config: self.config.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            fragment_slots: Arc::clone(&self.fragment_slots),
+        }
+    }
+
+    /// Create the HTTP client for media downloads
+    fn new_media_client(config: &DownloaderConfig) -> Arc<Mutex<VideoClient>> {
+        // Create HTTP/1.1-only client for media downloads (matches Go ytdlp line 182)
+        let http_config = HttpClientConfig {
+            timeout: config.request_timeout,
+            connect_timeout: config.connect_timeout,
+            ip_family: config.ip_family,
+            dns_overrides: config.dns_overrides.clone(),
+            traffic_log: config.traffic_log,
+            prefer_ipv6: true, // Ranged chunks try IPv6 first, then IPv4
+            http1_only: true,  // Force HTTP/1.1 for media downloads
+            accept_encoding: AcceptEncoding::Identity, // Never decode media
+            client_type: crate::platform::client::ClientType::Chrome,
+            ..HttpClientConfig::default()
+        };
+        Arc::new(Mutex::new(VideoClient::with_config(http_config)))
+    }
+
     /// Download a file from URL to local path.
     /// Strategy: streaming without Range to avoid 403 on YouTube CDN.
+    /// A stream that stalls for longer than the read timeout is resumed
+    /// with range requests from the bytes already written. URLs carrying
+    /// their own `range=` parameter only serve that range, so they are
+    /// always downloaded in chunks.
     pub async fn download(&self, url: &str, output_path: &Path) -> Result<(), RytError> {
         use tracing::{info, warn};
 
-        info!("Starting download from URL: {}", url);
+        info!("Starting download from URL: {}", loggable_url(url));
+        if has_range_param(url) {
+            info!("URL carries a range parameter, downloading with range rewriting");
+            return self
+                .download_ranged(url, output_path, self.config.chunk_size)
+                .await;
+        }
         // Always use streaming without Range
-        let tmp_path = output_path.with_extension("tmp");
-        let mut file = File::create(&tmp_path).await?;
-
-        match self.download_without_chunking(url, &mut file).await {
+        let tmp_path = self.partial_path(output_path, url);
+        let mut file = File::create(&tmp_path)
+            .await
+            .map_err(|e| RytError::io(e, "create", &tmp_path))?;
+        let rate_limiter = self.download_rate_limiter();
+
+        match self
+            .download_without_chunking(url, &mut file, rate_limiter.as_ref())
+            .await
+        {
             Ok(()) => {
-                file.flush().await?;
+                file.flush()
+                    .await
+                    .map_err(|e| RytError::io(e, "write", &tmp_path))?;
                 drop(file);
-                tokio::fs::rename(&tmp_path, output_path).await?;
+                finalize_partial(&tmp_path, output_path).await?;
                 info!("Download completed successfully");
                 Ok(())
             }
+            Err(e @ RytError::TimeoutError(_)) => {
+                warn!(
+                    "Streaming download stalled: {}, resuming with range requests",
+                    e
+                );
+                file.flush()
+                    .await
+                    .map_err(|e| RytError::io(e, "write", &tmp_path))?;
+                drop(file);
+                self.download_ranged(url, output_path, self.config.chunk_size)
+                    .await
+            }
+            Err(e @ RytError::Throttled(_)) => {
+                // Keep the partial so a re-resolved URL can resume it
+                warn!("Streaming download aborted: {}", e);
+                file.flush()
+                    .await
+                    .map_err(|e| RytError::io(e, "write", &tmp_path))?;
+                Err(e)
+            }
             Err(e) => {
-                warn!("Streaming download failed: {}, cleaning up temp file", e);
-                let _ = tokio::fs::remove_file(&tmp_path).await;
+                let e = with_write_context(e, &tmp_path);
+                warn!("Streaming download failed: {}", e);
+                drop(file);
+                self.discard_partial(&tmp_path).await;
                 Err(e)
             }
         }
@@ -329,18 +2103,51 @@ impl ChunkedDownloader {
         &self,
         url: &str,
         output_path: &Path,
+    ) -> Result<(), RytError> {
+        self.download_ranged(url, output_path, self.config.chunk_size)
+            .await
+    }
+
+    /// Download using sequential range requests of `chunk_size` bytes.
+    ///
+    /// For URLs with a `range=` query parameter each chunk rewrites that
+    /// parameter instead of sending a `Range` header, which googlevideo
+    /// rejects in combination. Resumes from an existing temp file. If the URL expires part-way
+    /// through, the partial data is kept and `RytError::NeedsUrlRefresh` is
+    /// returned so the caller can call again with a fresh URL.
+    pub async fn download_ranged(
+        &self,
+        url: &str,
+        output_path: &Path,
+        chunk_size: u64,
+    ) -> Result<(), RytError> {
+        let tmp_path = self.partial_path(output_path, url);
+        self.download_ranged_via(url, output_path, &tmp_path, chunk_size)
+            .await
+            .map_err(|e| with_write_context(e, &tmp_path))
+    }
+
+    /// Download using range requests into the partial file `tmp_path`
+    async fn download_ranged_via(
+        &self,
+        url: &str,
+        output_path: &Path,
+        tmp_path: &Path,
+        chunk_size: u64,
     ) -> Result<(), RytError> {
         use tracing::warn;
-        // Check if file exists and get its size
-        let tmp_path = output_path.with_extension("tmp");
-        let existing_size = if tmp_path.exists() {
-            tokio::fs::metadata(&tmp_path).await?.len()
+        // Check if a partial file exists and get its size
+        let mut existing_size = if tmp_path.exists() {
+            tokio::fs::metadata(tmp_path)
+                .await
+                .map_err(|e| RytError::io(e, "read metadata of", tmp_path))?
+                .len()
         } else {
             0
         };
 
         // Try to get total content length, but if all attempts fail (403), proceed with chunked anyway
-        let total_size = match self.get_content_length(url).await {
+        let mut total_size = match self.get_content_length(url).await {
             Ok(size) => size,
             Err(_e) => {
                 warn!("Could not determine content length (all clients failed), proceeding with chunked download");
@@ -348,73 +2155,307 @@ impl ChunkedDownloader {
             }
         };
 
-        if total_size > 0 && existing_size >= total_size {
-            // File is already complete
-            return Ok(());
+        if total_size > 0 && existing_size > total_size {
+            // A partial larger than the content can't be trusted
+            warn!(
+                "Partial file has {} bytes but content is {} bytes, restarting",
+                existing_size, total_size
+            );
+            tokio::fs::remove_file(tmp_path)
+                .await
+                .map_err(|e| RytError::io(e, "remove", tmp_path))?;
+            existing_size = 0;
         }
 
         // Open temp file for appending
-        let mut file = tokio::fs::OpenOptions::new()
+        let file = tokio::fs::OpenOptions::new()
             .create(true)
             .append(true)
-            .open(&tmp_path)
-            .await?;
+            .open(tmp_path)
+            .await
+            .map_err(|e| RytError::io(e, "open", tmp_path))?;
+
+        // Download remaining chunks, streaming each one straight to disk
+        let mut progress = self.new_progress(total_size);
+        progress.update(existing_size);
+        let mut sink = ChunkSink {
+            writer: BufWriter::with_capacity(self.config.write_buffer_size, file),
+            rate_limiter: self.download_rate_limiter(),
+            throttle: self.throttle_detector(),
+            progress,
+            on_progress: self.config.progress_callback.as_deref(),
+        };
+
+        while sink.position() < total_size || total_size == 0 {
+            let start = sink.position();
+            let end = if total_size > 0 {
+                (start + chunk_size - 1).min(total_size - 1)
+            } else {
+                // Unknown size: request bounded chunk
+                start + chunk_size - 1
+            };
+
+            // Download chunk with retry; keep partial data if the URL needs refreshing
+            if let Err(e) = self.download_chunk_with_retry(url, end, &mut sink).await {
+                sink.writer.flush().await?;
+                return Err(e);
+            }
+
+            // Learn the total size from Content-Range if the initial probe failed
+            total_size = sink.progress.total_size;
+            if total_size > 0 && start >= total_size {
+                if start == total_size {
+                    // The partial already holds the whole content
+                    break;
+                }
+                warn!(
+                    "Partial file has {} bytes but content is {} bytes, restarting",
+                    start, total_size
+                );
+                sink.writer.flush().await?;
+                sink.writer.get_mut().set_len(0).await?;
+                sink.progress = self.new_progress(total_size);
+                continue;
+            }
+
+            // If unknown size and we got less than chunk_size, we're probably done
+            let chunk_len = sink.position() - start;
+            if total_size == 0 && chunk_len < chunk_size {
+                break;
+            }
+            if chunk_len == 0 {
+                sink.writer.flush().await?;
+                return Err(RytError::RangeMismatch(format!(
+                    "no data received for bytes {}-{}",
+                    start, end
+                )));
+            }
+        }
+
+        // Flush and sync file
+        let downloaded = sink.position();
+        sink.writer.flush().await?;
+        let file = sink.writer.into_inner();
+        file.sync_all().await?;
+
+        // Finalize: rename temp -> final only if we actually wrote data
+        drop(file);
+        if (total_size == 0 && downloaded > 0) || (total_size > 0 && downloaded >= total_size) {
+            finalize_partial(tmp_path, output_path).await?;
+            return Ok(());
+        }
+
+        // Nothing downloaded — clean up and return error
+        self.discard_partial(tmp_path).await;
+        Err(RytError::Generic("Empty download (0 bytes)".to_string()))
+    }
+
+    /// Download an HLS stream into one file.
+    ///
+    /// A master playlist's variant with the most bandwidth at or below
+    /// `max_height` is downloaded; a media playlist is downloaded as is.
+    /// Segments are fetched as in [`download_segments`](Self::download_segments).
+    pub async fn download_hls(
+        &self,
+        playlist_url: &str,
+        output_path: &Path,
+        max_height: Option<u32>,
+    ) -> Result<(), RytError> {
+        use tracing::info;
+
+        info!("Fetching HLS playlist {}", loggable_url(playlist_url));
+        let segment_urls = match self.fetch_playlist(playlist_url).await? {
+            HlsPlaylist::Media(segment_urls) => segment_urls,
+            HlsPlaylist::Master(variants) => {
+                let variant =
+                    select_variant(&variants, max_height).ok_or(RytError::NoFormatFound)?;
+                info!(
+                    "Selected HLS variant of {} bps{}",
+                    variant.bandwidth,
+                    variant
+                        .height
+                        .map(|height| format!(", {}p", height))
+                        .unwrap_or_default()
+                );
+                match self.fetch_playlist(&variant.url).await? {
+                    HlsPlaylist::Media(segment_urls) => segment_urls,
+                    HlsPlaylist::Master(_) => {
+                        return Err(RytError::FormatError(
+                            "HLS variant is another master playlist".to_string(),
+                        ))
+                    }
+                }
+            }
+        };
+        self.write_segments(playlist_url, &segment_urls, output_path)
+            .await
+    }
 
-        // Download remaining chunks
-        let mut downloaded = existing_size;
-        let mut progress = Progress::new(total_size);
-        progress.update(downloaded);
+    /// Fetch and parse an HLS playlist
+    async fn fetch_playlist(&self, url: &str) -> Result<HlsPlaylist, RytError> {
+        let data = self.fetch_segment_with_retry(0, url).await?;
+        parse_playlist(&String::from_utf8_lossy(&data), url)
+    }
 
-        while downloaded < total_size || total_size == 0 {
-            let start = downloaded;
-            let end = if total_size > 0 {
-                (start + self.config.chunk_size - 1).min(total_size - 1)
-            } else {
-                // Unknown size: request bounded chunk
-                start + self.config.chunk_size - 1
-            };
+    /// Download the segments of a segmented stream into one file.
+    ///
+    /// Up to `concurrent_fragments` segments are fetched at once, across
+    /// this downloader and its forks. Segments finishing early wait in a
+    /// buffer keyed by their index until all segments before them are
+    /// written, so the output keeps playlist order and at most that many
+    /// segments are held in memory. Each segment is retried up to
+    /// `fragment_retries` times before the download fails.
+    pub async fn download_segments(
+        &self,
+        segment_urls: &[String],
+        output_path: &Path,
+    ) -> Result<(), RytError> {
+        let Some(first_url) = segment_urls.first() else {
+            return Err(RytError::Generic("No segments to download".to_string()));
+        };
+        self.write_segments(first_url, segment_urls, output_path)
+            .await
+    }
 
-            // Download chunk with retry
-            let chunk_data = self.download_chunk_with_retry(url, start, end).await?;
+    /// Download segments into one file, with a partial file named after `source_url`
+    async fn write_segments(
+        &self,
+        source_url: &str,
+        segment_urls: &[String],
+        output_path: &Path,
+    ) -> Result<(), RytError> {
+        use futures_util::stream::{FuturesUnordered, StreamExt};
+        use std::collections::BTreeMap;
+        use tracing::{info, warn};
 
-            // Write chunk to file
-            file.write_all(&chunk_data).await?;
+        let concurrency = self.config.concurrent_fragments.max(1);
+        info!(
+            "Downloading {} segments, {} at a time",
+            segment_urls.len(),
+            concurrency
+        );
 
-            // Update progress
-            downloaded += chunk_data.len() as u64;
-            progress.update(downloaded);
+        let tmp_path = self.partial_path(output_path, source_url);
+        let file = File::create(&tmp_path)
+            .await
+            .map_err(|e| RytError::io(e, "create", &tmp_path))?;
+        let mut sink = ChunkSink {
+            writer: BufWriter::with_capacity(self.config.write_buffer_size, file),
+            rate_limiter: self.download_rate_limiter(),
+            throttle: self.throttle_detector(),
+            progress: self.new_progress(0),
+            on_progress: self.config.progress_callback.as_deref(),
+        };
 
-            // Report progress
-            if let Some(callback) = &self.config.progress_callback {
-                callback(progress.clone());
+        let result: Result<(), RytError> = async {
+            let mut in_flight = FuturesUnordered::new();
+            let mut completed = BTreeMap::new();
+            let mut next_to_fetch = 0;
+            let mut next_to_write = 0;
+            loop {
+                // Fetching stays within `concurrency` segments of the next one to write
+                while next_to_fetch < segment_urls.len()
+                    && next_to_fetch < next_to_write + concurrency
+                {
+                    let index = next_to_fetch;
+                    in_flight.push(async move {
+                        let url = &segment_urls[index];
+                        (index, self.fetch_segment_with_retry(index, url).await)
+                    });
+                    next_to_fetch += 1;
+                }
+                let Some((index, data)) = in_flight.next().await else {
+                    break;
+                };
+                completed.insert(index, data?);
+                while let Some(data) = completed.remove(&next_to_write) {
+                    sink.write(&data).await?;
+                    next_to_write += 1;
+                }
             }
+            sink.writer.flush().await?;
+            Ok(())
+        }
+        .await;
 
-            // Rate limiting
-            if let Some(rate_limiter) = &self.rate_limiter {
-                let mut limiter = rate_limiter.lock().await;
-                limiter.wait_if_needed(chunk_data.len() as u64).await;
+        match result {
+            Ok(()) => {
+                drop(sink);
+                finalize_partial(&tmp_path, output_path).await?;
+                info!("Segmented download completed successfully");
+                Ok(())
             }
-
-            // If unknown size and we got less than chunk_size, we're probably done
-            if total_size == 0 && (chunk_data.len() as u64) < self.config.chunk_size {
-                break;
+            Err(e) => {
+                let e = with_write_context(e, &tmp_path);
+                warn!("Segmented download failed: {}", e);
+                drop(sink);
+                self.discard_partial(&tmp_path).await;
+                Err(e)
             }
         }
+    }
 
-        // Flush and sync file
-        file.flush().await?;
-        file.sync_all().await?;
+    /// Remove the partial file of a failed download, or keep it as `.part`
+    /// for inspection if configured
+    async fn discard_partial(&self, tmp_path: &Path) {
+        use tracing::warn;
 
-        // Finalize: rename temp -> final only if we actually wrote data
-        drop(file);
-        if (total_size == 0 && downloaded > 0) || (total_size > 0 && downloaded >= total_size) {
-            tokio::fs::rename(&tmp_path, output_path).await?;
-            return Ok(());
+        if !self.config.keep_temp_on_error {
+            let _ = tokio::fs::remove_file(tmp_path).await;
+            return;
+        }
+        let kept_path = kept_partial_path(tmp_path);
+        match tokio::fs::rename(tmp_path, &kept_path).await {
+            Ok(()) => warn!("Keeping partial file {}", kept_path.display()),
+            Err(e) => warn!("Failed to keep partial file {}: {}", tmp_path.display(), e),
         }
+    }
 
-        // Nothing downloaded — clean up and return error
-        let _ = tokio::fs::remove_file(&tmp_path).await;
-        Err(RytError::Generic("Empty download (0 bytes)".to_string()))
+    /// Create the rate limiter shared by all downloads, if any
+    fn new_rate_limiter(config: &DownloaderConfig) -> Option<Arc<Mutex<RateLimiter>>> {
+        config.rate_limit_bps.map(|bps| {
+            Arc::new(Mutex::new(
+                RateLimiter::new(bps).with_grace(config.rate_limit_after),
+            ))
+        })
+    }
+
+    /// Get the rate limiter for a single download.
+    ///
+    /// Each download gets its own bucket unless the rate limit is global.
+    fn download_rate_limiter(&self) -> Option<Arc<Mutex<RateLimiter>>> {
+        if self.config.global_rate_limit {
+            self.rate_limiter.clone()
+        } else {
+            Self::new_rate_limiter(&self.config)
+        }
+    }
+
+    /// Create the throttle detector for one download, if configured.
+    ///
+    /// A rate limit at or below the threshold would trip it on every
+    /// download, so detection is off then.
+    fn throttle_detector(&self) -> Option<ThrottleDetector> {
+        let min_bytes_per_second = self.config.throttled_rate?;
+        if self
+            .config
+            .rate_limit_bps
+            .is_some_and(|limit| limit <= min_bytes_per_second)
+        {
+            tracing::debug!(
+                "Rate limit is at or below the throttled rate, not detecting throttling"
+            );
+            return None;
+        }
+        Some(ThrottleDetector::new(
+            min_bytes_per_second,
+            self.config.throttle_window,
+        ))
+    }
+
+    /// Get the partial file path for a download
+    fn partial_path(&self, output_path: &Path, url: &str) -> PathBuf {
+        partial_file_path(self.config.temp_dir.as_deref(), output_path, url)
     }
 
     /// Get content length of the file
@@ -422,10 +2463,21 @@ impl ChunkedDownloader {
         use crate::platform::client::ClientType;
         use tracing::warn;
 
+        if has_range_param(url) {
+            // A probe would only describe the range in the URL; `clen` gives
+            // the full size, otherwise chunks continue until one comes up short
+            return query_param(url, "clen")
+                .and_then(|clen| clen.parse().ok())
+                .ok_or_else(|| {
+                    RytError::Generic("Could not determine content length".to_string())
+                });
+        }
+
         // Try all available client types
         let available_clients = ClientType::all();
+        let attempts = (self.config.fragment_retries as usize).max(1);
 
-        for (attempt, client_type) in available_clients.iter().enumerate() {
+        for (attempt, client_type) in available_clients.iter().cycle().take(attempts).enumerate() {
             // Switch to specific client type
             {
                 let mut video_client = self.video_client.lock().await;
@@ -437,9 +2489,13 @@ impl ChunkedDownloader {
             // Try GET request with Range header (YouTube doesn't support HEAD well)
             // Use simple media request to avoid 403 errors
             let response = video_client
-                .create_simple_media_request(reqwest::Method::GET, url)
-                .header("Range", "bytes=0-1")
-                .send()
+                .send(
+                    self.with_visitor_header(
+                        url,
+                        video_client.create_simple_media_request(reqwest::Method::GET, url),
+                    )
+                    .header("Range", "bytes=0-1"),
+                )
                 .await;
 
             match response {
@@ -460,7 +2516,7 @@ impl ChunkedDownloader {
             }
 
             // Exponential backoff
-            if attempt < available_clients.len() - 1 {
+            if attempt < attempts - 1 {
                 let delay = Duration::from_millis(100 * (1 << (attempt % 3)));
                 tokio::time::sleep(delay).await;
             }
@@ -502,45 +2558,142 @@ impl ChunkedDownloader {
         Ok(0)
     }
 
-    /// Download a single chunk with retry logic
-    async fn download_chunk_with_retry(
+    /// Fetch a segment, retrying failures up to `fragment_retries` times
+    async fn fetch_segment_with_retry(&self, index: usize, url: &str) -> Result<Vec<u8>, RytError> {
+        use tracing::warn;
+        let mut attempt = 0;
+
+        loop {
+            match self.fetch_segment(url).await {
+                Ok(data) => return Ok(data),
+                Err(e) => {
+                    attempt += 1;
+                    warn!(
+                        "Segment {} download attempt {} failed: {}",
+                        index, attempt, e
+                    );
+                    if attempt >= self.config.fragment_retries {
+                        return Err(e);
+                    }
+
+                    metrics::retry("fragment");
+                    let delay = Duration::from_millis(200 * (1 << (attempt - 1).min(5)));
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Fetch a whole segment into memory, once a segment request permit is free
+    async fn fetch_segment(&self, url: &str) -> Result<Vec<u8>, RytError> {
+        let _permit = self
+            .fragment_slots
+            .acquire()
+            .await
+            .map_err(|_| RytError::Cancelled)?;
+        // A clone sends the request, so other segments needn't wait for the lock
+        let video_client = self.video_client.lock().await.clone();
+        let response = video_client
+            .send_media_request(reqwest::Method::GET, url, |request| {
+                self.with_visitor_header(url, request)
+            })
+            .await?;
+
+        let response = response
+            .error_for_status()
+            .map_err(RytError::DownloadFailed)?;
+        let mut data = Vec::with_capacity(response.content_length().unwrap_or(0) as usize);
+        let mut stream = response.bytes_stream();
+        while let Some(frame) = next_chunk(&mut stream, self.config.read_timeout).await? {
+            data.extend_from_slice(&frame?);
+        }
+        Ok(data)
+    }
+
+    /// Download the chunk from the sink position up to `end` with retries.
+    ///
+    /// A chunk that fails part-way resumes after the bytes already written
+    /// instead of requesting the whole range again.
+    async fn download_chunk_with_retry<W: AsyncWrite + Unpin>(
         &self,
         url: &str,
-        start: u64,
         end: u64,
-    ) -> Result<Vec<u8>, RytError> {
+        sink: &mut ChunkSink<'_, W>,
+    ) -> Result<(), RytError> {
         use tracing::warn;
-        let mut last_error = None;
+        let mut attempt = 0;
+        // 403s in a row; any other outcome starts the count over
+        let mut cdn_403_count = 0;
+
+        loop {
+            match self.download_chunk(url, end, sink).await {
+                Ok(()) => return Ok(()),
+                // Retrying the same URL won't speed it up
+                Err(e @ RytError::Throttled(_)) => return Err(e),
+                Err(RytError::RateLimited) => {
+                    let start = sink.position();
+                    cdn_403_count += 1;
+                    if cdn_403_count >= MAX_CDN_403_RETRIES {
+                        if start > 0 {
+                            // Retrying the same URL won't help once it has expired mid-download
+                            warn!("403 on chunk {}-{}, media URL needs refreshing", start, end);
+                            return Err(RytError::NeedsUrlRefresh(start));
+                        }
+                        return Err(RytError::RateLimited);
+                    }
 
-        for attempt in 0..self.config.max_retries {
-            match self.download_chunk(url, start, end).await {
-                Ok(data) => return Ok(data),
+                    metrics::retry("cdn_403");
+                    let client_type = self.video_client.lock().await.switch_client();
+                    warn!(
+                        "403 on chunk {}-{} ({}/{}), retrying with {:?} client",
+                        start, end, cdn_403_count, MAX_CDN_403_RETRIES, client_type
+                    );
+                    tokio::time::sleep(self.config.cdn_403_retry_delay).await;
+                }
                 Err(e) => {
+                    cdn_403_count = 0;
+                    attempt += 1;
                     warn!(
-                        "Chunk download attempt {} failed for bytes {}-{}: {}",
-                        attempt + 1,
-                        start,
+                        "Chunk download attempt {} failed at byte {} of {}: {}",
+                        attempt,
+                        sink.position(),
                         end,
                         e
                     );
-                    last_error = Some(e);
-
-                    // Exponential backoff
-                    if attempt < self.config.max_retries - 1 {
-                        let delay = Duration::from_millis(200 * (1 << attempt));
-                        tokio::time::sleep(delay).await;
+                    if attempt >= self.config.fragment_retries {
+                        return Err(e);
                     }
+
+                    metrics::retry("fragment");
+                    // Exponential backoff, capped so high fragment retry counts stay responsive
+                    let delay = Duration::from_millis(200 * (1 << (attempt - 1).min(5)));
+                    tokio::time::sleep(delay).await;
                 }
             }
         }
-
-        Err(last_error.unwrap_or(RytError::Generic("Chunk download failed".to_string())))
     }
 
-    /// Download a single chunk
-    async fn download_chunk(&self, url: &str, start: u64, end: u64) -> Result<Vec<u8>, RytError> {
+    /// Download bytes from the sink position up to `end`, streaming them into the sink.
+    ///
+    /// Data is written as it arrives, so after a failure the sink position
+    /// tells how much of the chunk landed on disk. The total content size is
+    /// recorded in the sink's progress when the server reports it.
+    async fn download_chunk<W: AsyncWrite + Unpin>(
+        &self,
+        url: &str,
+        end: u64,
+        sink: &mut ChunkSink<'_, W>,
+    ) -> Result<(), RytError> {
         use tracing::{debug, warn};
+        let start = sink.position();
         let range_header = format!("bytes={}-{}", start, end);
+        // A range in the query replaces the Range header
+        let query_range = has_range_param(url);
+        let chunk_url = if query_range {
+            with_range_param(url, start, end)
+        } else {
+            url.to_string()
+        };
 
         debug!("Acquiring video_client lock for chunk download");
         let video_client = self.video_client.lock().await;
@@ -551,9 +2704,14 @@ impl ChunkedDownloader {
 
         // Use simple media request to avoid 403 errors from YouTube
         let response = video_client
-            .create_simple_media_request(reqwest::Method::GET, url)
-            .header("Range", range_header)
-            .send()
+            .send_media_request(reqwest::Method::GET, &chunk_url, |request| {
+                let request = self.with_visitor_header(url, request);
+                if query_range {
+                    request
+                } else {
+                    request.header("Range", range_header.as_str())
+                }
+            })
             .await?;
 
         // Release lock immediately after sending request
@@ -566,6 +2724,28 @@ impl ChunkedDownloader {
             status, start, end
         );
 
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            // The range starts at or past the end of the content, e.g. when
+            // resuming a partial that is already complete
+            let total = response
+                .headers()
+                .get("content-range")
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_unsatisfied_range)
+                .ok_or_else(|| {
+                    RytError::RangeMismatch(format!(
+                        "range {}-{} not satisfiable and content size unknown",
+                        start, end
+                    ))
+                })?;
+            debug!(
+                "Range {}-{} not satisfiable, content is {} bytes",
+                start, end, total
+            );
+            sink.progress.total_size = total;
+            return Ok(());
+        }
+
         if !status.is_success() && status != 206 {
             if status.as_u16() == 403 {
                 warn!("403 Forbidden for range request {}-{}", start, end);
@@ -580,14 +2760,63 @@ impl ChunkedDownloader {
             )));
         }
 
-        let data = response.bytes().await?;
+        let (data_start, total) = if query_range {
+            // The body is exactly the range asked for in the query
+            (start, 0)
+        } else if status != 206 {
+            // The server ignored the Range header and sends the whole content
+            warn!(
+                "Got status {} instead of 206 for range {}-{}, extracting requested bytes",
+                status, start, end
+            );
+            (0, response.content_length().unwrap_or(0))
+        } else {
+            let content_range = response
+                .headers()
+                .get("content-range")
+                .and_then(|value| value.to_str().ok());
+            match content_range {
+                Some(header) => match validate_content_range(start, end, header) {
+                    Ok(total) => (start, total),
+                    Err(e) => {
+                        warn!("{}, realigning data to the requested range", e);
+                        let (actual_start, _, total) = parse_content_range(header).ok_or(e)?;
+                        (actual_start, total.unwrap_or(0))
+                    }
+                },
+                None => (start, 0),
+            }
+        };
+        if sink.progress.total_size == 0 && total > 0 {
+            sink.progress.total_size = total;
+        }
+
+        let mut window = ChunkWindow::new(data_start, start, end)?;
+        let mut stream = response.bytes_stream();
+        while !window.is_full() {
+            let Some(frame) = next_chunk(&mut stream, self.config.read_timeout).await? else {
+                break;
+            };
+            let frame = frame?;
+            let data = window.take(&frame);
+            if !data.is_empty() {
+                sink.write(data).await?;
+            }
+        }
+
+        if !window.has_started() {
+            return Err(RytError::RangeMismatch(format!(
+                "received data at byte {} does not cover requested start {}",
+                data_start, start
+            )));
+        }
         debug!(
             "Downloaded {} bytes for range {}-{}",
-            data.len(),
+            sink.position() - start,
             start,
             end
         );
-        Ok(data.to_vec())
+        Ok(())
     }
 
     /// Set progress callback
@@ -602,7 +2831,33 @@ impl ChunkedDownloader {
     /// Set rate limit
     pub fn with_rate_limit(mut self, bytes_per_second: u64) -> Self {
         self.config.rate_limit_bps = Some(bytes_per_second);
-        self.rate_limiter = Some(Arc::new(Mutex::new(RateLimiter::new(bytes_per_second))));
+        self.rate_limiter = Self::new_rate_limiter(&self.config);
+        self
+    }
+
+    /// Set threshold after which rate limiting kicks in
+    pub fn with_rate_limit_after(mut self, grace: RateLimitGrace) -> Self {
+        self.config.rate_limit_after = Some(grace);
+        self.rate_limiter = Self::new_rate_limiter(&self.config);
+        self
+    }
+
+    /// Share one rate limit bucket across all downloads
+    pub fn with_global_rate_limit(mut self, global: bool) -> Self {
+        self.config.global_rate_limit = global;
+        self
+    }
+
+    /// Abort with `RytError::Throttled` when throughput stays below
+    /// `bytes_per_second` for a whole throttle window
+    pub fn with_throttled_rate(mut self, bytes_per_second: u64) -> Self {
+        self.config.throttled_rate = Some(bytes_per_second);
+        self
+    }
+
+    /// Set window over which throughput is measured for throttle detection
+    pub fn with_throttle_window(mut self, window: Duration) -> Self {
+        self.config.throttle_window = window;
         self
     }
 
@@ -612,25 +2867,146 @@ impl ChunkedDownloader {
         self
     }
 
+    /// Set write buffer size for streamed downloads
+    pub fn with_write_buffer_size(mut self, write_buffer_size: usize) -> Self {
+        self.config.write_buffer_size = write_buffer_size;
+        self
+    }
+
     /// Set max retries
     pub fn with_max_retries(mut self, max_retries: u32) -> Self {
         self.config.max_retries = max_retries;
         self
     }
 
+    /// Set max retries per fragment
+    pub fn with_fragment_retries(mut self, fragment_retries: u32) -> Self {
+        self.config.fragment_retries = fragment_retries;
+        self
+    }
+
+    /// Set the delay before retrying a chunk after a 403 with another client
+    pub fn with_cdn_403_retry_delay(mut self, delay: Duration) -> Self {
+        self.config.cdn_403_retry_delay = delay;
+        self
+    }
+
+    /// Set how many segments of a segmented download are fetched at once
+    pub fn with_concurrent_fragments(mut self, concurrent_fragments: usize) -> Self {
+        self.config.concurrent_fragments = concurrent_fragments.max(1);
+        self.fragment_slots = Arc::new(Semaphore::new(self.config.concurrent_fragments));
+        self
+    }
+
+    /// Set directory for partial files
+    pub fn with_temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.config.temp_dir = Some(temp_dir.into());
+        self
+    }
+
+    /// Set connection establishment timeout
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = timeout;
+        self.video_client = Self::new_media_client(&self.config);
+        self
+    }
+
+    /// Set idle timeout between chunks of the body
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.config.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set total timeout per media request
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = Some(timeout);
+        self.video_client = Self::new_media_client(&self.config);
+        self
+    }
+
+    /// Set address family for media connections
+    pub fn with_ip_family(mut self, ip_family: IpFamily) -> Self {
+        self.config.ip_family = ip_family;
+        self.video_client = Self::new_media_client(&self.config);
+        self
+    }
+
+    /// Keep partial files of failed downloads for inspection
+    pub fn with_keep_temp(mut self, keep: bool) -> Self {
+        self.config.keep_temp_on_error = keep;
+        self
+    }
+
+    /// Set visitor data for media requests of clients that need it (IOS, ANDROID)
+    pub fn with_visitor_data(mut self, visitor_data: &str) -> Self {
+        self.config.visitor_data = Some(visitor_data.to_string());
+        self
+    }
+
+    /// Get the headers media requests for `url` need, for handing the
+    /// transfer to another backend
+    pub fn media_headers(&self, url: &str) -> Vec<(String, String)> {
+        let mut headers = vec![("User-Agent".to_string(), MEDIA_USER_AGENT.to_string())];
+        if let Some(visitor_data) = &self.config.visitor_data {
+            if needs_visitor_id(url) {
+                headers.push(("X-Goog-Visitor-Id".to_string(), visitor_data.clone()));
+            }
+        }
+        headers
+    }
+
+    /// Add the visitor data header to a media request if its URL was issued
+    /// to a client that needs it
+    fn with_visitor_header(
+        &self,
+        url: &str,
+        request: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder {
+        match &self.config.visitor_data {
+            Some(visitor_data) if needs_visitor_id(url) => {
+                request.header("X-Goog-Visitor-Id", visitor_data)
+            }
+            _ => request,
+        }
+    }
+
+    /// Get the HTTP configuration of the media client
+    pub async fn media_http_config(&self) -> HttpClientConfig {
+        self.video_client.lock().await.config().clone()
+    }
+
+    /// Set speed smoothing factor for reported progress
+    pub fn with_speed_smoothing(mut self, alpha: f64) -> Self {
+        self.config.progress.speed_smoothing_alpha = alpha;
+        self
+    }
+
+    /// Create a progress tracker using the configured smoothing
+    fn new_progress(&self, total_size: u64) -> Progress {
+        Progress::new(total_size)
+            .with_speed_smoothing_alpha(self.config.progress.speed_smoothing_alpha)
+    }
+
     /// Download without chunking when content length is unknown
-    async fn download_without_chunking(&self, url: &str, file: &mut File) -> Result<(), RytError> {
+    async fn download_without_chunking(
+        &self,
+        url: &str,
+        file: &mut File,
+        rate_limiter: Option<&Arc<Mutex<RateLimiter>>>,
+    ) -> Result<(), RytError> {
         use crate::platform::client::ClientType;
         use tracing::{debug, info, warn};
 
-        info!("Downloading without chunking from: {}", url);
+        info!("Downloading without chunking from: {}", loggable_url(url));
 
         // Try with current client first
         // Use simple media request for googlevideo.com to avoid 403 errors from browser-specific headers
         let video_client = self.video_client.lock().await;
         let response = video_client
-            .create_simple_media_request(reqwest::Method::GET, url)
-            .send()
+            .send(self.with_visitor_header(
+                url,
+                video_client.create_simple_media_request(reqwest::Method::GET, url),
+            ))
             .await;
 
         match response {
@@ -640,7 +3016,9 @@ impl ChunkedDownloader {
                     // Success! Continue with this response
                     drop(video_client); // Release lock
                     debug!("Download successful with current client, processing response...");
-                    return self.process_successful_response(resp, file).await;
+                    return self
+                        .process_successful_response(resp, file, rate_limiter)
+                        .await;
                 } else if status.as_u16() == 403 {
                     drop(video_client);
                     warn!("403 Forbidden on streaming GET, falling back to chunked");
@@ -681,8 +3059,10 @@ impl ChunkedDownloader {
 
             let video_client = self.video_client.lock().await;
             let response = video_client
-                .create_simple_media_request(reqwest::Method::GET, url)
-                .send()
+                .send(self.with_visitor_header(
+                    url,
+                    video_client.create_simple_media_request(reqwest::Method::GET, url),
+                ))
                 .await;
 
             match response {
@@ -695,7 +3075,9 @@ impl ChunkedDownloader {
                             "Download successful with client {:?}, processing response...",
                             client_type
                         );
-                        return self.process_successful_response(resp, file).await;
+                        return self
+                            .process_successful_response(resp, file, rate_limiter)
+                            .await;
                     } else {
                         // If 403, stop header-only switching and propagate upwards to allow URL regeneration
                         if status.as_u16() == 403 {
@@ -738,46 +3120,314 @@ impl ChunkedDownloader {
         &self,
         response: reqwest::Response,
         file: &mut File,
+        rate_limiter: Option<&Arc<Mutex<RateLimiter>>>,
     ) -> Result<(), RytError> {
-        use futures_util::StreamExt;
-        use tracing::{debug, info};
+        use tracing::info;
+
+        let mut writer = BufWriter::with_capacity(self.config.write_buffer_size, &mut *file);
+        // Keep one tracker so speed samples accumulate across chunks
+        let mut progress = self.new_progress(response.content_length().unwrap_or(0));
+        let downloaded = write_stream(
+            response.bytes_stream(),
+            &mut writer,
+            rate_limiter,
+            self.throttle_detector(),
+            self.config.read_timeout,
+            |downloaded| {
+                // Report progress if callback is available
+                if let Some(callback) = &self.config.progress_callback {
+                    progress.update(downloaded);
+                    callback(progress.clone());
+                }
+            },
+        )
+        .await;
+
+        // Flush even on failure so a stalled download can resume from the written bytes
+        writer.flush().await?;
+        drop(writer);
+        let downloaded = downloaded?;
+        file.sync_all().await?;
 
-        let mut stream = response.bytes_stream();
-        let mut downloaded = 0u64;
+        info!("Download completed: {} bytes", downloaded);
+        Ok(())
+    }
+}
 
-        while let Some(chunk_result) = stream.next().await {
-            let chunk = chunk_result?;
-            let chunk_size = chunk.len();
+impl Default for ChunkedDownloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-            file.write_all(&chunk).await?;
-            downloaded += chunk_size as u64;
+/// Move a finished partial file to the output path
+async fn finalize_partial(tmp_path: &Path, output_path: &Path) -> Result<(), RytError> {
+    move_file(tmp_path, output_path)
+        .await
+        .map_err(|e| RytError::io(e, "move download to", output_path))
+}
 
-            debug!("Downloaded {} bytes, total: {}", chunk_size, downloaded);
+/// Attach the partial file path to a bare IO error from writing it
+fn with_write_context(error: RytError, tmp_path: &Path) -> RytError {
+    match error {
+        RytError::IoError(source) => RytError::io(source, "write", tmp_path),
+        error => error,
+    }
+}
 
-            // Report progress if callback is available
-            if let Some(callback) = &self.config.progress_callback {
-                let mut progress = Progress::new(0); // Unknown total size
-                progress.update(downloaded);
-                callback(progress);
-            }
+/// Copy a byte stream into `writer`, returning the number of bytes written.
+///
+/// The rate limiter is charged for each chunk once it has been written, and
+/// `on_progress` receives the running total. The caller flushes `writer`.
+/// Fails with a retryable `TimeoutError` if no chunk arrives for `read_timeout`,
+/// and with `Throttled` at the bytes written once `throttle` trips.
+async fn write_stream<S, B, E, W>(
+    mut stream: S,
+    writer: &mut W,
+    rate_limiter: Option<&Arc<Mutex<RateLimiter>>>,
+    mut throttle: Option<ThrottleDetector>,
+    read_timeout: Option<Duration>,
+    mut on_progress: impl FnMut(u64),
+) -> Result<u64, RytError>
+where
+    S: futures_util::Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    RytError: From<E>,
+    W: AsyncWrite + Unpin,
+{
+    let mut downloaded = 0u64;
+    while let Some(chunk_result) = next_chunk(&mut stream, read_timeout).await? {
+        let chunk = chunk_result?;
+        let chunk = chunk.as_ref();
+
+        writer.write_all(chunk).await?;
+        let written = chunk.len() as u64;
+        downloaded += written;
+        metrics::bytes_downloaded(written);
+
+        tracing::debug!("Downloaded {} bytes, total: {}", written, downloaded);
+        on_progress(downloaded);
+
+        // Rate limiting
+        if let Some(rate_limiter) = rate_limiter {
+            let mut limiter = rate_limiter.lock().await;
+            limiter.wait_if_needed(written).await;
+        }
 
-            // Rate limiting
-            if let Some(rate_limiter) = &self.rate_limiter {
-                let mut limiter = rate_limiter.lock().await;
-                limiter.wait_if_needed(chunk_size as u64).await;
-            }
+        if throttle.as_mut().is_some_and(|t| t.record(written)) {
+            return Err(RytError::Throttled(downloaded));
         }
+    }
 
-        file.flush().await?;
-        file.sync_all().await?;
+    Ok(downloaded)
+}
 
-        info!("Download completed: {} bytes", downloaded);
-        Ok(())
+/// Get the next item of a stream, failing if none arrives for `read_timeout`.
+///
+/// reqwest only offers a total request timeout, which would cut off long
+/// media downloads, so the idle timeout is applied per read instead.
+async fn next_chunk<S>(
+    stream: &mut S,
+    read_timeout: Option<Duration>,
+) -> Result<Option<S::Item>, RytError>
+where
+    S: futures_util::Stream + Unpin,
+{
+    use futures_util::StreamExt;
+
+    match read_timeout {
+        Some(read_timeout) => tokio::time::timeout(read_timeout, stream.next())
+            .await
+            .map_err(|_| {
+                RytError::TimeoutError(format!("no data received for {:?}", read_timeout))
+            }),
+        None => Ok(stream.next().await),
     }
 }
 
-impl Default for ChunkedDownloader {
-    fn default() -> Self {
-        Self::new()
+/// Validate a `Content-Range` response header against the requested range.
+///
+/// A shorter range is accepted when it ends at the last byte of the content.
+/// Returns the total content size, or 0 if the server reports it as `*`.
+pub fn validate_content_range(
+    request_start: u64,
+    request_end: u64,
+    response_header: &str,
+) -> Result<u64, RytError> {
+    let (start, end, total) = parse_content_range(response_header).ok_or_else(|| {
+        RytError::RangeMismatch(format!(
+            "invalid Content-Range header {:?}",
+            response_header
+        ))
+    })?;
+
+    let ends_at_eof = total.map_or(true, |total| end + 1 == total);
+    if start != request_start || end > request_end || (end < request_end && !ends_at_eof) {
+        return Err(RytError::RangeMismatch(format!(
+            "requested bytes {}-{}, got {}",
+            request_start,
+            request_end,
+            response_header.trim()
+        )));
+    }
+
+    Ok(total.unwrap_or(0))
+}
+
+/// Parse `bytes X-Y/total` into its parts; the total is `None` if unknown
+fn parse_content_range(header: &str) -> Option<(u64, u64, Option<u64>)> {
+    let range = header.trim().strip_prefix("bytes")?.trim_start();
+    let (range, total) = range.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start: u64 = start.trim().parse().ok()?;
+    let end: u64 = end.trim().parse().ok()?;
+    let total = match total.trim() {
+        "*" => None,
+        total => Some(total.parse::<u64>().ok()?),
+    };
+
+    if end < start || total.map_or(false, |total| end >= total) {
+        return None;
+    }
+    Some((start, end, total))
+}
+
+/// Check whether a media URL selects its own byte range with `range=`
+fn has_range_param(url: &str) -> bool {
+    query_param(url, "range").is_some()
+}
+
+/// Get the raw value of a query parameter
+/// Check if a media URL was issued to a client whose media requests carry
+/// the visitor ID (`c=IOS`, `c=ANDROID` and their variants)
+fn needs_visitor_id(url: &str) -> bool {
+    query_param(url, "c")
+        .is_some_and(|client| client.starts_with("IOS") || client.starts_with("ANDROID"))
+}
+
+fn query_param<'a>(url: &'a str, name: &str) -> Option<&'a str> {
+    let (_, query) = url.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
+/// Replace the `range=` parameter of `url` with `start-end`, leaving the
+/// other parameters byte for byte as they are
+fn with_range_param(url: &str, start: u64, end: u64) -> String {
+    let range = format!("range={}-{}", start, end);
+    let Some((base, query)) = url.split_once('?') else {
+        return format!("{}?{}", url, range);
+    };
+    let mut replaced = false;
+    let pairs: Vec<&str> = query
+        .split('&')
+        .filter_map(|pair| {
+            if !pair.starts_with("range=") {
+                return Some(pair);
+            }
+            // Keep only the first occurrence, in its original position
+            let first = !replaced;
+            replaced = true;
+            first.then_some(range.as_str())
+        })
+        .collect();
+    format!("{}?{}", base, pairs.join("&"))
+}
+
+/// Parse the `bytes */total` header of a 416 response into the total size
+fn parse_unsatisfied_range(header: &str) -> Option<u64> {
+    let range = header.trim().strip_prefix("bytes")?.trim_start();
+    range.strip_prefix("*/")?.trim().parse().ok()
+}
+
+/// Window selecting the requested `start..=end` range out of a body that
+/// begins at `data_start`, applied frame by frame as the body streams in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChunkWindow {
+    /// Bytes still to drop before the requested start
+    skip: u64,
+    /// Bytes of the requested range still to take
+    remaining: u64,
+}
+
+impl ChunkWindow {
+    /// Create a window, failing if the data begins after the requested start
+    fn new(data_start: u64, start: u64, end: u64) -> Result<Self, RytError> {
+        let skip = start.checked_sub(data_start).ok_or_else(|| {
+            RytError::RangeMismatch(format!(
+                "received data at byte {} does not cover requested start {}",
+                data_start, start
+            ))
+        })?;
+
+        Ok(Self {
+            skip,
+            remaining: end - start + 1,
+        })
+    }
+
+    /// Take the part of the next frame that falls into the window
+    fn take<'a>(&mut self, frame: &'a [u8]) -> &'a [u8] {
+        let skipped = self.skip.min(frame.len() as u64) as usize;
+        self.skip -= skipped as u64;
+        let frame = &frame[skipped..];
+
+        let len = self.remaining.min(frame.len() as u64) as usize;
+        self.remaining -= len as u64;
+        &frame[..len]
+    }
+
+    /// Check if the data has reached the requested start
+    fn has_started(&self) -> bool {
+        self.skip == 0
+    }
+
+    /// Check if the whole requested range has been taken
+    fn is_full(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// Destination of a ranged download.
+///
+/// Progress, the rate limiter and the throttle detector are updated per
+/// streamed frame, and the position always matches the bytes handed to the
+/// writer.
+struct ChunkSink<'a, W> {
+    writer: W,
+    rate_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    throttle: Option<ThrottleDetector>,
+    progress: Progress,
+    on_progress: Option<&'a (dyn Fn(Progress) + Send + Sync)>,
+}
+
+impl<W: AsyncWrite + Unpin> ChunkSink<'_, W> {
+    /// Get the offset of the next byte to write
+    fn position(&self) -> u64 {
+        self.progress.downloaded_size
+    }
+
+    /// Write a frame, then report progress, charge the rate limiter and
+    /// check for throttling
+    async fn write(&mut self, data: &[u8]) -> Result<(), RytError> {
+        self.writer.write_all(data).await?;
+        let written = data.len() as u64;
+        metrics::bytes_downloaded(written);
+
+        self.progress.update(self.position() + written);
+        if let Some(callback) = self.on_progress {
+            callback(self.progress.clone());
+        }
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            let mut limiter = rate_limiter.lock().await;
+            limiter.wait_if_needed(written).await;
+        }
+
+        if self.throttle.as_mut().is_some_and(|t| t.record(written)) {
+            return Err(RytError::Throttled(self.position()));
+        }
+        Ok(())
     }
 }
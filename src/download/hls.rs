@@ -0,0 +1,237 @@
+//! HLS playlist parsing for segmented downloads
+
+use crate::error::RytError;
+use url::Url;
+
+/// Variant stream of an HLS master playlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HlsVariant {
+    pub url: String,
+    pub bandwidth: u64,
+    pub height: Option<u32>,
+}
+
+/// Kind of an HLS playlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HlsPlaylist {
+    /// Master playlist listing variant streams
+    Master(Vec<HlsVariant>),
+    /// Media playlist listing segment URLs in playback order, starting with
+    /// the initialization segment if there is one
+    Media(Vec<String>),
+}
+
+/// Parse an HLS playlist, resolving its URLs against `base_url`
+pub fn parse_playlist(playlist: &str, base_url: &str) -> Result<HlsPlaylist, RytError> {
+    let base = Url::parse(base_url)?;
+    let mut lines = playlist
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    if lines.next() != Some("#EXTM3U") {
+        return Err(RytError::FormatError(
+            "HLS playlist does not start with #EXTM3U".to_string(),
+        ));
+    }
+
+    let mut variants = Vec::new();
+    let mut segments = Vec::new();
+    // Attributes of the variant whose URL is on the next line
+    let mut pending_variant: Option<(u64, Option<u32>)> = None;
+    for line in lines {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            let attributes = parse_attributes(attributes);
+            let bandwidth = attribute(&attributes, "BANDWIDTH")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0);
+            let height = attribute(&attributes, "RESOLUTION")
+                .and_then(|value| value.split_once('x'))
+                .and_then(|(_, height)| height.parse().ok());
+            pending_variant = Some((bandwidth, height));
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MAP:") {
+            let attributes = parse_attributes(attributes);
+            if let Some(uri) = attribute(&attributes, "URI") {
+                segments.push(base.join(uri)?.to_string());
+            }
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-KEY:") {
+            let attributes = parse_attributes(attributes);
+            if attribute(&attributes, "METHOD").is_some_and(|method| method != "NONE") {
+                return Err(RytError::FormatError(
+                    "encrypted HLS segments are not supported".to_string(),
+                ));
+            }
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some((bandwidth, height)) = pending_variant.take() {
+            variants.push(HlsVariant {
+                url: base.join(line)?.to_string(),
+                bandwidth,
+                height,
+            });
+        } else {
+            segments.push(base.join(line)?.to_string());
+        }
+    }
+
+    if !variants.is_empty() {
+        Ok(HlsPlaylist::Master(variants))
+    } else if !segments.is_empty() {
+        Ok(HlsPlaylist::Media(segments))
+    } else {
+        Err(RytError::FormatError(
+            "HLS playlist lists no streams or segments".to_string(),
+        ))
+    }
+}
+
+/// Pick the variant with the most bandwidth at or below `max_height`, or
+/// the smallest one if none fits
+pub fn select_variant(variants: &[HlsVariant], max_height: Option<u32>) -> Option<&HlsVariant> {
+    let fits = |variant: &&HlsVariant| {
+        max_height.map_or(true, |max| {
+            variant.height.map_or(true, |height| height <= max)
+        })
+    };
+    variants
+        .iter()
+        .filter(fits)
+        .max_by_key(|variant| variant.bandwidth)
+        .or_else(|| variants.iter().min_by_key(|variant| variant.bandwidth))
+}
+
+/// Split an attribute list such as `BANDWIDTH=1280000,CODECS="avc1,mp4a"`
+/// into names and values, with quotes removed
+fn parse_attributes(list: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    let mut rest = list;
+    while let Some((name, value)) = rest.split_once('=') {
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, next)) => (value, next.trim_start_matches(',')),
+                None => (quoted, ""),
+            },
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        attributes.push((name.trim(), value));
+        rest = next;
+    }
+    attributes
+}
+
+/// Get the value of an attribute
+fn attribute<'a>(attributes: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(attribute, _)| *attribute == name)
+        .map(|(_, value)| *value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "https://manifest.googlevideo.com/api/manifest/hls_variant/id/1/index.m3u8";
+
+    #[test]
+    fn test_parse_attributes() {
+        assert_eq!(
+            parse_attributes(
+                r#"BANDWIDTH=1280000,CODECS="avc1.4d401f,mp4a.40.2",RESOLUTION=1280x720"#
+            ),
+            vec![
+                ("BANDWIDTH", "1280000"),
+                ("CODECS", "avc1.4d401f,mp4a.40.2"),
+                ("RESOLUTION", "1280x720"),
+            ]
+        );
+        assert!(parse_attributes("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_master_playlist() {
+        let playlist = "#EXTM3U\n\
+                        #EXT-X-INDEPENDENT-SEGMENTS\n\
+                        #EXT-X-STREAM-INF:BANDWIDTH=628000,CODECS=\"avc1.4d401e,mp4a.40.2\",RESOLUTION=640x360\n\
+                        https://manifest.googlevideo.com/hls_playlist/itag/93/index.m3u8\n\
+                        #EXT-X-STREAM-INF:BANDWIDTH=2969000,CODECS=\"avc1.4d401f,mp4a.40.2\",RESOLUTION=1280x720\n\
+                        720/index.m3u8\n";
+        let HlsPlaylist::Master(variants) = parse_playlist(playlist, BASE).unwrap() else {
+            panic!("Expected a master playlist");
+        };
+        assert_eq!(
+            variants,
+            vec![
+                HlsVariant {
+                    url: "https://manifest.googlevideo.com/hls_playlist/itag/93/index.m3u8"
+                        .to_string(),
+                    bandwidth: 628000,
+                    height: Some(360),
+                },
+                HlsVariant {
+                    url: "https://manifest.googlevideo.com/api/manifest/hls_variant/id/1/720/index.m3u8"
+                        .to_string(),
+                    bandwidth: 2969000,
+                    height: Some(720),
+                },
+            ]
+        );
+
+        assert_eq!(select_variant(&variants, None).unwrap().height, Some(720));
+        assert_eq!(
+            select_variant(&variants, Some(480)).unwrap().height,
+            Some(360)
+        );
+        // Nothing fits: the smallest variant is the closest
+        assert_eq!(
+            select_variant(&variants, Some(240)).unwrap().height,
+            Some(360)
+        );
+        assert!(select_variant(&[], None).is_none());
+    }
+
+    #[test]
+    fn test_parse_media_playlist() {
+        let playlist = "#EXTM3U\n\
+                        #EXT-X-VERSION:3\n\
+                        #EXT-X-TARGETDURATION:5\n\
+                        #EXT-X-MEDIA-SEQUENCE:0\n\
+                        #EXT-X-MAP:URI=\"init.mp4\"\n\
+                        #EXTINF:5.0,\n\
+                        sq/0/seg.ts\n\
+                        #EXTINF:5.0,\n\
+                        https://rr1---sn-abc.googlevideo.com/sq/1/seg.ts\n\
+                        #EXT-X-ENDLIST\n";
+        assert_eq!(
+            parse_playlist(playlist, BASE).unwrap(),
+            HlsPlaylist::Media(vec![
+                "https://manifest.googlevideo.com/api/manifest/hls_variant/id/1/init.mp4"
+                    .to_string(),
+                "https://manifest.googlevideo.com/api/manifest/hls_variant/id/1/sq/0/seg.ts"
+                    .to_string(),
+                "https://rr1---sn-abc.googlevideo.com/sq/1/seg.ts".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_playlist_errors() {
+        assert!(matches!(
+            parse_playlist("not a playlist", BASE),
+            Err(RytError::FormatError(_))
+        ));
+        assert!(matches!(
+            parse_playlist("#EXTM3U\n#EXT-X-ENDLIST\n", BASE),
+            Err(RytError::FormatError(_))
+        ));
+        let encrypted = "#EXTM3U\n\
+                         #EXT-X-KEY:METHOD=AES-128,URI=\"key\"\n\
+                         #EXTINF:5.0,\n\
+                         seg.ts\n";
+        assert!(matches!(
+            parse_playlist(encrypted, BASE),
+            Err(RytError::FormatError(_))
+        ));
+        let unencrypted = "#EXTM3U\n#EXT-X-KEY:METHOD=NONE\n#EXTINF:5.0,\nseg.ts\n";
+        assert!(parse_playlist(unencrypted, BASE).is_ok());
+    }
+}
@@ -1,9 +1,15 @@
 //! Download system for ryt
 
+pub mod backend;
 pub mod downloader;
+pub mod hls;
 pub mod progress;
 pub mod retry;
+pub mod session;
 
+pub use backend::*;
 pub use downloader::*;
+pub use hls::*;
 pub use progress::*;
 pub use retry::*;
+pub use session::*;
@@ -1,16 +1,21 @@
 //! Retry logic for downloads
 
 use crate::error::RytError;
+use crate::utils::duration_serde;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 /// Retry configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RetryConfig {
     /// Maximum number of retries
     pub max_retries: u32,
     /// Initial delay between retries
+    #[serde(with = "duration_serde")]
     pub initial_delay: Duration,
     /// Maximum delay between retries
+    #[serde(with = "duration_serde")]
     pub max_delay: Duration,
     /// Backoff multiplier
     pub backoff_multiplier: f64,
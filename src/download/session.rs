@@ -0,0 +1,340 @@
+//! Resumable session state for large batch downloads
+//!
+//! A session file records the resolved item list of a batch or playlist job
+//! together with a per-item status, so an interrupted run can pick up where it
+//! stopped without resolving everything again. The file is JSON and carries a
+//! format version; files written by a newer version are rejected instead of
+//! being misread.
+
+use crate::error::RytError;
+use crate::utils::url::canonical_video_url;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Current session file format version
+pub const SESSION_VERSION: u32 = 1;
+
+/// Status of a session item
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemStatus {
+    /// Not processed yet
+    #[default]
+    Pending,
+    /// Downloaded (or already in the download archive)
+    Done,
+    /// Download failed
+    Failed,
+}
+
+/// Single item of a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionItem {
+    /// YouTube video ID
+    pub video_id: String,
+    /// Video title
+    pub title: String,
+    /// Planned output path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<PathBuf>,
+    /// Processing status
+    #[serde(default)]
+    pub status: ItemStatus,
+    /// Error message of the last failed attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SessionItem {
+    /// Create a pending item
+    pub fn new(video_id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            video_id: video_id.into(),
+            title: title.into(),
+            output_path: None,
+            status: ItemStatus::Pending,
+            error: None,
+        }
+    }
+
+    /// Set planned output path
+    pub fn with_output_path(mut self, output_path: impl Into<PathBuf>) -> Self {
+        self.output_path = Some(output_path.into());
+        self
+    }
+
+    /// Get watch URL of the item
+    pub fn url(&self) -> String {
+        canonical_video_url(&self.video_id)
+    }
+}
+
+/// Session state backed by a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Format version
+    pub version: u32,
+    /// Input URL (or URLs) the session was created for
+    pub input: String,
+    /// When the session was created
+    pub created_at: DateTime<Utc>,
+    /// When the session was last saved
+    pub updated_at: DateTime<Utc>,
+    /// Resolved items in download order
+    pub items: Vec<SessionItem>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Session {
+    /// Create a new session; nothing is written until [`Session::save`]
+    pub fn new(
+        path: impl Into<PathBuf>,
+        input: impl Into<String>,
+        items: Vec<SessionItem>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            version: SESSION_VERSION,
+            input: input.into(),
+            created_at: now,
+            updated_at: now,
+            items,
+            path: path.into(),
+        }
+    }
+
+    /// Load a session file
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, RytError> {
+        let path = path.into();
+        let content = std::fs::read_to_string(&path)?;
+        let mut session = parse_session(&content)
+            .map_err(|e| RytError::SessionError(format!("{}: {}", path.display(), e)))?;
+        session.path = path;
+        Ok(session)
+    }
+
+    /// Load the session for `input` if the file exists.
+    ///
+    /// Fails if the file belongs to a different input, so the state of another
+    /// job is never overwritten.
+    pub fn resume(path: impl Into<PathBuf>, input: &str) -> Result<Option<Self>, RytError> {
+        let path = path.into();
+        let session = match Self::load(&path) {
+            Ok(session) => session,
+            Err(RytError::IoError(e)) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if session.input != input {
+            return Err(RytError::SessionError(format!(
+                "{} belongs to {}, not {}",
+                path.display(),
+                session.input,
+                input
+            )));
+        }
+        Ok(Some(session))
+    }
+
+    /// Get the session file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the session atomically via a temporary file and rename
+    pub fn save(&mut self) -> Result<(), RytError> {
+        self.updated_at = Utc::now();
+        let json = serde_json::to_string_pretty(self)?;
+
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Update the status of an item and save the session
+    pub fn mark(
+        &mut self,
+        video_id: &str,
+        status: ItemStatus,
+        error: Option<String>,
+    ) -> Result<(), RytError> {
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.video_id == video_id)
+            .ok_or_else(|| {
+                RytError::SessionError(format!("{} is not part of the session", video_id))
+            })?;
+        item.status = status;
+        item.error = error;
+        self.save()
+    }
+
+    /// Get the items still to process, including failed ones if `retry_failed`
+    pub fn pending(&self, retry_failed: bool) -> Vec<SessionItem> {
+        self.items
+            .iter()
+            .filter(|item| match item.status {
+                ItemStatus::Pending => true,
+                ItemStatus::Failed => retry_failed,
+                ItemStatus::Done => false,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Count items with the given status
+    pub fn count(&self, status: ItemStatus) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.status == status)
+            .count()
+    }
+}
+
+/// Parse session JSON, checking the format version first
+fn parse_session(content: &str) -> Result<Session, RytError> {
+    let value: serde_json::Value = serde_json::from_str(content)?;
+    let version = value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| RytError::SessionError("missing format version".to_string()))?;
+    if version == 0 || version > SESSION_VERSION as u64 {
+        return Err(RytError::SessionError(format!(
+            "unsupported format version {} (supported: {})",
+            version, SESSION_VERSION
+        )));
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "https://www.youtube.com/playlist?list=PL123";
+
+    fn items(count: usize) -> Vec<SessionItem> {
+        (0..count)
+            .map(|i| {
+                SessionItem::new(format!("video{}", i), format!("Video {}", i))
+                    .with_output_path(format!("Video {}.mp4", i))
+            })
+            .collect()
+    }
+
+    /// Process pending items, "crashing" after `limit` of them
+    fn run(session: &mut Session, retry_failed: bool, limit: usize) -> Vec<String> {
+        let mut processed = Vec::new();
+        for item in session.pending(retry_failed).into_iter().take(limit) {
+            let (status, error) = if item.video_id == "video3" && !retry_failed {
+                (ItemStatus::Failed, Some("HTTP 403".to_string()))
+            } else {
+                (ItemStatus::Done, None)
+            };
+            session.mark(&item.video_id, status, error).unwrap();
+            processed.push(item.video_id);
+        }
+        processed
+    }
+
+    #[test]
+    fn test_session_resume_after_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+
+        let mut session = Session::new(&path, INPUT, items(6));
+        session.save().unwrap();
+        let processed = run(&mut session, false, 4);
+        assert_eq!(processed, ["video0", "video1", "video2", "video3"]);
+        drop(session);
+
+        let mut session = Session::resume(&path, INPUT).unwrap().unwrap();
+        assert_eq!(session.count(ItemStatus::Done), 3);
+        assert_eq!(session.count(ItemStatus::Failed), 1);
+        assert_eq!(session.items[3].error.as_deref(), Some("HTTP 403"));
+
+        let processed = run(&mut session, false, usize::MAX);
+        assert_eq!(processed, ["video4", "video5"]);
+        assert!(session.pending(false).is_empty());
+
+        // Failed items are only picked up again on request
+        let mut session = Session::resume(&path, INPUT).unwrap().unwrap();
+        let processed = run(&mut session, true, usize::MAX);
+        assert_eq!(processed, ["video3"]);
+        assert_eq!(session.count(ItemStatus::Done), 6);
+        assert_eq!(session.items[3].error, None);
+    }
+
+    #[test]
+    fn test_session_save_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("session.json");
+
+        let mut session = Session::new(&path, INPUT, items(2));
+        session.save().unwrap();
+        assert!(!dir.path().join("nested").join("session.json.tmp").exists());
+
+        let loaded = Session::load(&path).unwrap();
+        assert_eq!(loaded.version, SESSION_VERSION);
+        assert_eq!(loaded.input, INPUT);
+        assert_eq!(loaded.items, session.items);
+        assert_eq!(loaded.path(), path.as_path());
+        assert_eq!(
+            loaded.items[1].url(),
+            "https://www.youtube.com/watch?v=video1"
+        );
+    }
+
+    #[test]
+    fn test_session_resume_missing_or_foreign() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        assert!(Session::resume(&path, INPUT).unwrap().is_none());
+
+        Session::new(&path, INPUT, items(1)).save().unwrap();
+        let result = Session::resume(&path, "https://www.youtube.com/watch?v=other");
+        assert!(matches!(result, Err(RytError::SessionError(_))));
+    }
+
+    #[test]
+    fn test_session_mark_unknown_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut session = Session::new(dir.path().join("session.json"), INPUT, items(1));
+        let result = session.mark("missing", ItemStatus::Done, None);
+        assert!(matches!(result, Err(RytError::SessionError(_))));
+    }
+
+    #[test]
+    fn test_parse_session_versions() {
+        let minimal = r#"{"version":1,"input":"x","created_at":"2024-05-01T12:30:00Z",
+            "updated_at":"2024-05-01T12:30:00Z","items":[{"video_id":"abc","title":"A"}]}"#;
+        let session = parse_session(minimal).unwrap();
+        assert_eq!(session.items[0].status, ItemStatus::Pending);
+        assert_eq!(session.items[0].output_path, None);
+
+        let newer = minimal.replace("\"version\":1", "\"version\":2");
+        assert!(matches!(
+            parse_session(&newer),
+            Err(RytError::SessionError(_))
+        ));
+        let unversioned = minimal.replace("\"version\":1,", "");
+        assert!(matches!(
+            parse_session(&unversioned),
+            Err(RytError::SessionError(_))
+        ));
+        assert!(matches!(
+            parse_session("not json"),
+            Err(RytError::JsonError(_))
+        ));
+    }
+}
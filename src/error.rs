@@ -1,7 +1,14 @@
 //! Error types for ryt
 
+use std::path::PathBuf;
 use thiserror::Error;
 
+/// Exit code for a members-only video
+pub const EXIT_MEMBERS_ONLY: i32 = 3;
+
+/// Exit code for a video that has to be rented or bought
+pub const EXIT_PAYMENT_REQUIRED: i32 = 4;
+
 /// Main error type for ryt operations
 #[derive(Debug, Error)]
 pub enum RytError {
@@ -20,12 +27,24 @@ pub enum RytError {
     #[error("Video unavailable")]
     VideoUnavailable,
 
+    #[error("Video blocked: {reason}")]
+    Blocked { reason: String },
+
+    #[error("Members-only video")]
+    MembersOnly,
+
+    #[error("Video requires payment{}", .offer_text.as_ref().map(|text| format!(": {}", text)).unwrap_or_default())]
+    PaymentRequired { offer_text: Option<String> },
+
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
 
     #[error("No suitable format found")]
     NoFormatFound,
 
+    #[error("Smallest suitable format is {actual} bytes, over the {limit} byte limit")]
+    FileSizeExceeds { limit: u64, actual: u64 },
+
     #[error("Download failed: {0}")]
     DownloadFailed(#[from] reqwest::Error),
 
@@ -38,6 +57,13 @@ pub enum RytError {
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
+    #[error("Failed to {op} {}: {source}", .path.display())]
+    Io {
+        source: std::io::Error,
+        path: PathBuf,
+        op: &'static str,
+    },
+
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
@@ -47,6 +73,9 @@ pub enum RytError {
     #[error("Regex error: {0}")]
     RegexError(#[from] regex::Error),
 
+    #[error("Image error: {0}")]
+    ImageError(#[from] image::ImageError),
+
     #[error("Botguard error: {0}")]
     BotguardError(String),
 
@@ -65,11 +94,90 @@ pub enum RytError {
     #[error("Rate limit error: {0}")]
     RateLimitError(String),
 
+    #[error("Media URL expired at byte {0}, needs refresh")]
+    NeedsUrlRefresh(u64),
+
+    #[error("Download throttled at byte {0}, needs re-resolving")]
+    Throttled(u64),
+
+    #[error("Content-Range mismatch: {0}")]
+    RangeMismatch(String),
+
+    #[error("Consent required: {0}")]
+    ConsentRequired(String),
+
+    #[error("Already recorded in the download archive: {0}")]
+    AlreadyArchived(String),
+
+    #[error("Player response has no video details: {0}")]
+    MissingVideoDetails(String),
+
+    #[error("Session error: {0}")]
+    SessionError(String),
+
+    #[error("{0} format not available (fall back to an AAC format such as itag 140)")]
+    FormatNotAvailable(String),
+
+    #[error("No captions available in language {0}")]
+    NoCaptions(String),
+
+    #[error("External downloader {program} failed: {message}")]
+    ExternalDownloaderFailed { program: String, message: String },
+
+    #[error("Failed to load browser cookies: {0}")]
+    BrowserCookies(String),
+
+    #[error("Conflicting options: {0}")]
+    ConflictingOptions(String),
+
+    #[error("Invalid match filter: {0}")]
+    InvalidMatchFilter(String),
+
+    #[error("Skipped by match filter: {0}")]
+    FilteredOut(String),
+
+    #[error("Request failed: {0}")]
+    RequestFailed(String),
+
+    #[error("Invalid format selector: {0}")]
+    InvalidFormatSelector(String),
+
+    #[error("Invalid option: {0}")]
+    InvalidOption(String),
+
+    #[error("Invalid config file {}: {message}", .path.display())]
+    InvalidConfig { path: PathBuf, message: String },
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Invalid playlist items: {0}")]
+    InvalidPlaylistItems(String),
+
+    #[error("Invalid output template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("Update failed: {0}")]
+    UpdateFailed(String),
+
+    #[error("Cancelled")]
+    Cancelled,
+
     #[error("Generic error: {0}")]
     Generic(String),
 }
 
 impl RytError {
+    /// Wrap an IO error with the operation and path that failed, e.g.
+    /// `.map_err(|e| RytError::io(e, "create", &path))`
+    pub fn io(source: std::io::Error, op: &'static str, path: impl Into<PathBuf>) -> Self {
+        RytError::Io {
+            source,
+            path: path.into(),
+            op,
+        }
+    }
+
     /// Check if error is retryable
     pub fn is_retryable(&self) -> bool {
         matches!(
@@ -78,6 +186,9 @@ impl RytError {
                 | RytError::TimeoutError(_)
                 | RytError::RateLimited
                 | RytError::AgeRestricted
+                | RytError::NeedsUrlRefresh(_)
+                | RytError::Throttled(_)
+                | RytError::RangeMismatch(_)
         )
     }
 
@@ -90,8 +201,113 @@ impl RytError {
                 | RytError::AgeRestricted
                 | RytError::Private
                 | RytError::VideoUnavailable
+                | RytError::ConsentRequired(_)
+                | RytError::MissingVideoDetails(_)
+                | RytError::Blocked { .. }
+                | RytError::MembersOnly
+                | RytError::PaymentRequired { .. }
+        )
+    }
+
+    /// Check if error is permanent for the video, so neither retries nor
+    /// other clients can help
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            RytError::Private
+                | RytError::VideoUnavailable
+                | RytError::Blocked { .. }
+                | RytError::InvalidUrl(_)
         )
     }
+
+    /// Get the process exit code for the error. Videos the account has no
+    /// access to get their own codes, so scripts can tell them from failures.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RytError::MembersOnly => EXIT_MEMBERS_ONLY,
+            RytError::PaymentRequired { .. } => EXIT_PAYMENT_REQUIRED,
+            _ => 1,
+        }
+    }
+
+    /// Copy the error for another caller waiting on the same operation.
+    ///
+    /// Errors wrapping a source that can't be cloned (HTTP, JSON, image)
+    /// become [`RytError::RequestFailed`] with the original message.
+    pub fn replicate(&self) -> RytError {
+        match self {
+            RytError::GeoBlocked => RytError::GeoBlocked,
+            RytError::RateLimited => RytError::RateLimited,
+            RytError::AgeRestricted => RytError::AgeRestricted,
+            RytError::Private => RytError::Private,
+            RytError::VideoUnavailable => RytError::VideoUnavailable,
+            RytError::Blocked { reason } => RytError::Blocked {
+                reason: reason.clone(),
+            },
+            RytError::MembersOnly => RytError::MembersOnly,
+            RytError::PaymentRequired { offer_text } => RytError::PaymentRequired {
+                offer_text: offer_text.clone(),
+            },
+            RytError::InvalidUrl(s) => RytError::InvalidUrl(s.clone()),
+            RytError::NoFormatFound => RytError::NoFormatFound,
+            RytError::FileSizeExceeds { limit, actual } => RytError::FileSizeExceeds {
+                limit: *limit,
+                actual: *actual,
+            },
+            RytError::ParseError(e) => RytError::ParseError(e.clone()),
+            RytError::ApiKeyNotFound => RytError::ApiKeyNotFound,
+            RytError::IoError(e) => RytError::IoError(std::io::Error::new(e.kind(), e.to_string())),
+            RytError::Io { source, path, op } => RytError::Io {
+                source: std::io::Error::new(source.kind(), source.to_string()),
+                path: path.clone(),
+                op: *op,
+            },
+            RytError::UrlError(e) => RytError::UrlError(*e),
+            RytError::RegexError(e) => RytError::RegexError(e.clone()),
+            RytError::DownloadFailed(_) | RytError::JsonError(_) | RytError::ImageError(_) => {
+                RytError::RequestFailed(self.to_string())
+            }
+            RytError::BotguardError(s) => RytError::BotguardError(s.clone()),
+            RytError::CipherError(s) => RytError::CipherError(s.clone()),
+            RytError::FormatError(s) => RytError::FormatError(s.clone()),
+            RytError::PlaylistError(s) => RytError::PlaylistError(s.clone()),
+            RytError::TimeoutError(s) => RytError::TimeoutError(s.clone()),
+            RytError::RateLimitError(s) => RytError::RateLimitError(s.clone()),
+            RytError::NeedsUrlRefresh(offset) => RytError::NeedsUrlRefresh(*offset),
+            RytError::Throttled(offset) => RytError::Throttled(*offset),
+            RytError::RangeMismatch(s) => RytError::RangeMismatch(s.clone()),
+            RytError::ConsentRequired(s) => RytError::ConsentRequired(s.clone()),
+            RytError::AlreadyArchived(s) => RytError::AlreadyArchived(s.clone()),
+            RytError::MissingVideoDetails(s) => RytError::MissingVideoDetails(s.clone()),
+            RytError::SessionError(s) => RytError::SessionError(s.clone()),
+            RytError::FormatNotAvailable(s) => RytError::FormatNotAvailable(s.clone()),
+            RytError::NoCaptions(s) => RytError::NoCaptions(s.clone()),
+            RytError::ExternalDownloaderFailed { program, message } => {
+                RytError::ExternalDownloaderFailed {
+                    program: program.clone(),
+                    message: message.clone(),
+                }
+            }
+            RytError::BrowserCookies(s) => RytError::BrowserCookies(s.clone()),
+            RytError::ConflictingOptions(s) => RytError::ConflictingOptions(s.clone()),
+            RytError::InvalidMatchFilter(s) => RytError::InvalidMatchFilter(s.clone()),
+            RytError::FilteredOut(s) => RytError::FilteredOut(s.clone()),
+            RytError::RequestFailed(s) => RytError::RequestFailed(s.clone()),
+            RytError::InvalidFormatSelector(s) => RytError::InvalidFormatSelector(s.clone()),
+            RytError::InvalidOption(s) => RytError::InvalidOption(s.clone()),
+            RytError::InvalidConfig { path, message } => RytError::InvalidConfig {
+                path: path.clone(),
+                message: message.clone(),
+            },
+            RytError::InvalidInput(s) => RytError::InvalidInput(s.clone()),
+            RytError::InvalidPlaylistItems(s) => RytError::InvalidPlaylistItems(s.clone()),
+            RytError::InvalidTemplate(s) => RytError::InvalidTemplate(s.clone()),
+            RytError::UpdateFailed(s) => RytError::UpdateFailed(s.clone()),
+            RytError::Cancelled => RytError::Cancelled,
+            RytError::Generic(s) => RytError::Generic(s.clone()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -99,6 +315,46 @@ mod tests {
     use super::*;
     use std::io::ErrorKind;
 
+    #[test]
+    fn test_io_error_with_path() {
+        let err = RytError::io(
+            std::io::Error::new(ErrorKind::NotFound, "No such file or directory"),
+            "create",
+            "/missing/video.tmp",
+        );
+        assert_eq!(
+            err.to_string(),
+            "Failed to create /missing/video.tmp: No such file or directory"
+        );
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_replicate() {
+        let blocked = RytError::Blocked {
+            reason: "copyright".to_string(),
+        };
+        assert!(matches!(
+            blocked.replicate(),
+            RytError::Blocked { reason } if reason == "copyright"
+        ));
+        assert!(matches!(
+            RytError::GeoBlocked.replicate(),
+            RytError::GeoBlocked
+        ));
+
+        let io = RytError::IoError(std::io::Error::new(ErrorKind::NotFound, "gone"));
+        match io.replicate() {
+            RytError::IoError(e) => assert_eq!(e.kind(), ErrorKind::NotFound),
+            other => panic!("Expected IoError, got {:?}", other),
+        }
+
+        let json = RytError::JsonError(serde_json::from_str::<u32>("x").unwrap_err());
+        let copy = json.replicate();
+        assert!(matches!(copy, RytError::RequestFailed(_)));
+        assert_eq!(copy.to_string(), format!("Request failed: {}", json));
+    }
+
     #[test]
     fn test_ryt_error_variants() {
         // Test basic error variants
@@ -117,11 +373,40 @@ mod tests {
         let video_unavailable = RytError::VideoUnavailable;
         assert_eq!(format!("{}", video_unavailable), "Video unavailable");
 
+        let blocked = RytError::Blocked {
+            reason:
+                "This video contains content from SME, who has blocked it on copyright grounds."
+                    .to_string(),
+        };
+        assert_eq!(
+            format!("{}", blocked),
+            "Video blocked: This video contains content from SME, who has blocked it on copyright grounds."
+        );
+
+        assert_eq!(format!("{}", RytError::MembersOnly), "Members-only video");
+        assert_eq!(
+            format!("{}", RytError::PaymentRequired { offer_text: None }),
+            "Video requires payment"
+        );
+        let payment = RytError::PaymentRequired {
+            offer_text: Some("Rent from $3.99".to_string()),
+        };
+        assert_eq!(
+            format!("{}", payment),
+            "Video requires payment: Rent from $3.99"
+        );
+
         let no_format = RytError::NoFormatFound;
         assert_eq!(format!("{}", no_format), "No suitable format found");
 
         let api_key_not_found = RytError::ApiKeyNotFound;
         assert_eq!(format!("{}", api_key_not_found), "API key not found");
+
+        let cookies = RytError::BrowserCookies("no Firefox profile with cookies found".to_string());
+        assert_eq!(
+            format!("{}", cookies),
+            "Failed to load browser cookies: no Firefox profile with cookies found"
+        );
     }
 
     #[test]
@@ -166,6 +451,135 @@ mod tests {
             "Rate limit error: Test rate limit error"
         );
 
+        let needs_url_refresh = RytError::NeedsUrlRefresh(1024);
+        assert_eq!(
+            format!("{}", needs_url_refresh),
+            "Media URL expired at byte 1024, needs refresh"
+        );
+
+        let throttled = RytError::Throttled(2048);
+        assert_eq!(
+            format!("{}", throttled),
+            "Download throttled at byte 2048, needs re-resolving"
+        );
+
+        let range_mismatch = RytError::RangeMismatch("requested bytes 0-1".to_string());
+        assert_eq!(
+            format!("{}", range_mismatch),
+            "Content-Range mismatch: requested bytes 0-1"
+        );
+
+        let consent_required =
+            RytError::ConsentRequired("https://www.youtube.com/watch".to_string());
+        assert_eq!(
+            format!("{}", consent_required),
+            "Consent required: https://www.youtube.com/watch"
+        );
+
+        let already_archived = RytError::AlreadyArchived("dQw4w9WgXcQ".to_string());
+        assert_eq!(
+            format!("{}", already_archived),
+            "Already recorded in the download archive: dQw4w9WgXcQ"
+        );
+
+        let missing_video_details = RytError::MissingVideoDetails("dQw4w9WgXcQ".to_string());
+        assert_eq!(
+            format!("{}", missing_video_details),
+            "Player response has no video details: dQw4w9WgXcQ"
+        );
+
+        let session_error = RytError::SessionError("unsupported version 9".to_string());
+        assert_eq!(
+            format!("{}", session_error),
+            "Session error: unsupported version 9"
+        );
+
+        let format_not_available = RytError::FormatNotAvailable("Dolby Atmos".to_string());
+        assert_eq!(
+            format!("{}", format_not_available),
+            "Dolby Atmos format not available (fall back to an AAC format such as itag 140)"
+        );
+
+        let no_captions = RytError::NoCaptions("de".to_string());
+        assert_eq!(
+            format!("{}", no_captions),
+            "No captions available in language de"
+        );
+
+        let external = RytError::ExternalDownloaderFailed {
+            program: "aria2c".to_string(),
+            message: "exited with code 3".to_string(),
+        };
+        assert_eq!(
+            format!("{}", external),
+            "External downloader aria2c failed: exited with code 3"
+        );
+
+        let conflicting_options = RytError::ConflictingOptions("--a and --b".to_string());
+        assert_eq!(
+            format!("{}", conflicting_options),
+            "Conflicting options: --a and --b"
+        );
+
+        let invalid_match_filter =
+            RytError::InvalidMatchFilter("missing field in '< 5'".to_string());
+        assert_eq!(
+            format!("{}", invalid_match_filter),
+            "Invalid match filter: missing field in '< 5'"
+        );
+
+        let filtered_out = RytError::FilteredOut("Title does not pass 'duration < 60'".to_string());
+        assert_eq!(
+            format!("{}", filtered_out),
+            "Skipped by match filter: Title does not pass 'duration < 60'"
+        );
+
+        let request_failed =
+            RytError::RequestFailed("HTTP 503 Service Unavailable after 3 attempt(s)".to_string());
+        assert_eq!(
+            format!("{}", request_failed),
+            "Request failed: HTTP 503 Service Unavailable after 3 attempt(s)"
+        );
+
+        let invalid_format_selector =
+            RytError::InvalidFormatSelector("unsupported filter key 'tbr'".to_string());
+        assert_eq!(
+            format!("{}", invalid_format_selector),
+            "Invalid format selector: unsupported filter key 'tbr'"
+        );
+
+        let invalid_option =
+            RytError::InvalidOption("timeout must be greater than zero".to_string());
+        assert_eq!(
+            format!("{}", invalid_option),
+            "Invalid option: timeout must be greater than zero"
+        );
+
+        let invalid_config = RytError::InvalidConfig {
+            path: PathBuf::from("ryt.toml"),
+            message: "unknown field `rate_limit`".to_string(),
+        };
+        assert_eq!(
+            format!("{}", invalid_config),
+            "Invalid config file ryt.toml: unknown field `rate_limit`"
+        );
+        assert!(matches!(
+            invalid_config.replicate(),
+            RytError::InvalidConfig { path, .. } if path == PathBuf::from("ryt.toml")
+        ));
+
+        let invalid_playlist_items = RytError::InvalidPlaylistItems("0".to_string());
+        assert_eq!(
+            format!("{}", invalid_playlist_items),
+            "Invalid playlist items: 0"
+        );
+
+        let invalid_template = RytError::InvalidTemplate("unknown field 'foo'".to_string());
+        assert_eq!(
+            format!("{}", invalid_template),
+            "Invalid output template: unknown field 'foo'"
+        );
+
         let generic_error = RytError::Generic("Test generic error".to_string());
         assert_eq!(
             format!("{}", generic_error),
@@ -236,11 +650,16 @@ mod tests {
         assert!(RytError::RateLimited.is_retryable());
         assert!(RytError::AgeRestricted.is_retryable());
         assert!(RytError::TimeoutError("test".to_string()).is_retryable());
+        assert!(RytError::NeedsUrlRefresh(1024).is_retryable());
+        assert!(RytError::Throttled(1024).is_retryable());
+        assert!(RytError::RangeMismatch("test".to_string()).is_retryable());
 
         // Test non-retryable errors
         assert!(!RytError::GeoBlocked.is_retryable());
         assert!(!RytError::Private.is_retryable());
         assert!(!RytError::VideoUnavailable.is_retryable());
+        assert!(!RytError::MembersOnly.is_retryable());
+        assert!(!RytError::PaymentRequired { offer_text: None }.is_retryable());
         assert!(!RytError::InvalidUrl("test".to_string()).is_retryable());
         assert!(!RytError::NoFormatFound.is_retryable());
         assert!(!RytError::ApiKeyNotFound.is_retryable());
@@ -249,6 +668,37 @@ mod tests {
         assert!(!RytError::FormatError("test".to_string()).is_retryable());
         assert!(!RytError::PlaylistError("test".to_string()).is_retryable());
         assert!(!RytError::RateLimitError("test".to_string()).is_retryable());
+        assert!(!RytError::ConsentRequired("test".to_string()).is_retryable());
+        assert!(!RytError::AlreadyArchived("test".to_string()).is_retryable());
+        assert!(!RytError::MissingVideoDetails("test".to_string()).is_retryable());
+        assert!(!RytError::SessionError("test".to_string()).is_retryable());
+        assert!(!RytError::FormatNotAvailable("x".to_string()).is_retryable());
+        assert!(!RytError::NoCaptions("de".to_string()).is_retryable());
+        assert!(!RytError::ExternalDownloaderFailed {
+            program: "aria2c".to_string(),
+            message: "test".to_string(),
+        }
+        .is_retryable());
+        assert!(!RytError::BrowserCookies("test".to_string()).is_retryable());
+        assert!(!RytError::ConflictingOptions("test".to_string()).is_retryable());
+        assert!(!RytError::InvalidMatchFilter("missing field in '< 5'".to_string()).is_retryable());
+        assert!(
+            !RytError::FilteredOut("Title does not pass 'duration < 60'".to_string())
+                .is_retryable()
+        );
+        assert!(!RytError::RequestFailed("test".to_string()).is_retryable());
+        assert!(!RytError::InvalidFormatSelector("test".to_string()).is_retryable());
+        assert!(
+            !RytError::InvalidOption("timeout must be greater than zero".to_string())
+                .is_retryable()
+        );
+        assert!(!RytError::InvalidConfig {
+            path: PathBuf::from("ryt.toml"),
+            message: "test".to_string(),
+        }
+        .is_retryable());
+        assert!(!RytError::InvalidPlaylistItems("0".to_string()).is_retryable());
+        assert!(!RytError::InvalidTemplate("test".to_string()).is_retryable());
         assert!(!RytError::Generic("test".to_string()).is_retryable());
 
         // Test DownloadFailed (should be retryable)
@@ -262,6 +712,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_is_permanent() {
+        assert!(RytError::Private.is_permanent());
+        assert!(RytError::VideoUnavailable.is_permanent());
+        assert!(RytError::InvalidUrl("test".to_string()).is_permanent());
+        let blocked = RytError::Blocked {
+            reason: "test".to_string(),
+        };
+        assert!(blocked.is_permanent());
+        assert!(!blocked.is_retryable());
+
+        // Geo-blocking depends on the country asked from
+        assert!(!RytError::GeoBlocked.is_permanent());
+        assert!(!RytError::AgeRestricted.is_permanent());
+        assert!(!RytError::RateLimited.is_permanent());
+        assert!(!RytError::NoFormatFound.is_permanent());
+        // Another client, signed in, may have access
+        assert!(!RytError::MembersOnly.is_permanent());
+        assert!(!RytError::PaymentRequired { offer_text: None }.is_permanent());
+        assert!(!RytError::Generic("test".to_string()).is_permanent());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(RytError::MembersOnly.exit_code(), EXIT_MEMBERS_ONLY);
+        assert_eq!(
+            RytError::PaymentRequired {
+                offer_text: Some("Buy".to_string())
+            }
+            .exit_code(),
+            EXIT_PAYMENT_REQUIRED
+        );
+        assert_ne!(EXIT_MEMBERS_ONLY, EXIT_PAYMENT_REQUIRED);
+        assert_eq!(RytError::Private.exit_code(), 1);
+        assert_eq!(RytError::VideoUnavailable.exit_code(), 1);
+        assert_eq!(RytError::Generic("test".to_string()).exit_code(), 1);
+    }
+
     #[test]
     fn test_is_youtube_error() {
         // Test YouTube-specific errors
@@ -270,6 +758,14 @@ mod tests {
         assert!(RytError::AgeRestricted.is_youtube_error());
         assert!(RytError::Private.is_youtube_error());
         assert!(RytError::VideoUnavailable.is_youtube_error());
+        assert!(RytError::ConsentRequired("test".to_string()).is_youtube_error());
+        assert!(RytError::MissingVideoDetails("test".to_string()).is_youtube_error());
+        assert!(RytError::Blocked {
+            reason: "test".to_string()
+        }
+        .is_youtube_error());
+        assert!(RytError::MembersOnly.is_youtube_error());
+        assert!(RytError::PaymentRequired { offer_text: None }.is_youtube_error());
 
         // Test non-YouTube errors
         assert!(!RytError::InvalidUrl("test".to_string()).is_youtube_error());
@@ -307,6 +803,39 @@ mod tests {
             RytError::PlaylistError("test".to_string()),
             RytError::TimeoutError("test".to_string()),
             RytError::RateLimitError("test".to_string()),
+            RytError::NeedsUrlRefresh(0),
+            RytError::Throttled(0),
+            RytError::RangeMismatch("test".to_string()),
+            RytError::ConsentRequired("test".to_string()),
+            RytError::AlreadyArchived("test".to_string()),
+            RytError::MissingVideoDetails("test".to_string()),
+            RytError::SessionError("test".to_string()),
+            RytError::FormatNotAvailable("x".to_string()),
+            RytError::NoCaptions("de".to_string()),
+            RytError::ExternalDownloaderFailed {
+                program: "aria2c".to_string(),
+                message: "test".to_string(),
+            },
+            RytError::BrowserCookies("test".to_string()),
+            RytError::ConflictingOptions("test".to_string()),
+            RytError::InvalidMatchFilter("missing field in '< 5'".to_string()),
+            RytError::FilteredOut("Title does not pass 'duration < 60'".to_string()),
+            RytError::RequestFailed("test".to_string()),
+            RytError::InvalidFormatSelector("test".to_string()),
+            RytError::InvalidOption("timeout must be greater than zero".to_string()),
+            RytError::InvalidConfig {
+                path: PathBuf::from("ryt.toml"),
+                message: "test".to_string(),
+            },
+            RytError::InvalidPlaylistItems("0".to_string()),
+            RytError::Blocked {
+                reason: "test".to_string(),
+            },
+            RytError::MembersOnly,
+            RytError::PaymentRequired {
+                offer_text: Some("test".to_string()),
+            },
+            RytError::InvalidTemplate("test".to_string()),
             RytError::Generic("test".to_string()),
         ];
 
@@ -334,6 +863,39 @@ mod tests {
             RytError::PlaylistError("test".to_string()),
             RytError::TimeoutError("test".to_string()),
             RytError::RateLimitError("test".to_string()),
+            RytError::NeedsUrlRefresh(0),
+            RytError::Throttled(0),
+            RytError::RangeMismatch("test".to_string()),
+            RytError::ConsentRequired("test".to_string()),
+            RytError::AlreadyArchived("test".to_string()),
+            RytError::MissingVideoDetails("test".to_string()),
+            RytError::SessionError("test".to_string()),
+            RytError::FormatNotAvailable("x".to_string()),
+            RytError::NoCaptions("de".to_string()),
+            RytError::ExternalDownloaderFailed {
+                program: "aria2c".to_string(),
+                message: "test".to_string(),
+            },
+            RytError::BrowserCookies("test".to_string()),
+            RytError::ConflictingOptions("test".to_string()),
+            RytError::InvalidMatchFilter("missing field in '< 5'".to_string()),
+            RytError::FilteredOut("Title does not pass 'duration < 60'".to_string()),
+            RytError::RequestFailed("test".to_string()),
+            RytError::InvalidFormatSelector("test".to_string()),
+            RytError::InvalidOption("timeout must be greater than zero".to_string()),
+            RytError::InvalidConfig {
+                path: PathBuf::from("ryt.toml"),
+                message: "test".to_string(),
+            },
+            RytError::InvalidPlaylistItems("0".to_string()),
+            RytError::Blocked {
+                reason: "test".to_string(),
+            },
+            RytError::MembersOnly,
+            RytError::PaymentRequired {
+                offer_text: Some("test".to_string()),
+            },
+            RytError::InvalidTemplate("test".to_string()),
             RytError::Generic("test".to_string()),
         ];
 
@@ -32,9 +32,9 @@
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let mut downloader = Downloader::new()
-//!         .with_format("best", "mp4")
-//!         .with_output_path("./downloads");
+//!     let downloader = Downloader::new()
+//!         .try_with_format("best", "mp4")?
+//!         .try_with_output_path("./downloads")?;
 //!     
 //!     let info = downloader.download("VIDEO_URL").await?;
 //!     println!("Downloaded: {}", info.title);
@@ -42,6 +42,22 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! Metadata alone can be fetched without configuring a downloader:
+//!
+//! ```rust,no_run
+//! use ryt::VideoInfo;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let info = VideoInfo::from_url("https://www.youtube.com/watch?v=VIDEO_ID").await?;
+//!     for format in info.formats_sorted_by_quality() {
+//!         println!("{} {}", format.itag, format.quality_label());
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
 
 pub mod cli;
 pub mod core;
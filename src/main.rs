@@ -1,59 +1,302 @@
 //! Main entry point for ryt CLI
 
-use clap::Parser;
-use ryt::cli::output::OutputFormatter;
-use ryt::cli::Args;
-use ryt::core::{Downloader, Progress};
+use ryt::cli::output::{format_info_field, render_print_lines, OutputFormatter, PrintTemplate};
+use ryt::cli::{Args, Command, Updater, VerbosityLevel};
+use ryt::core::progress::format_duration;
+use ryt::core::{
+    DownloadPlan, Downloader, MatchFilter, PlaylistDownloadResult, PlaylistItemsSpec, Progress,
+    Status,
+};
+use ryt::download::{ItemStatus, SessionItem};
 use ryt::platform::botguard::BotguardMode;
+use ryt::platform::{IpFamily, TrendingCategory};
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    init_logging()?;
-
+async fn main() {
     // Parse command line arguments
-    let args = Args::parse();
+    let mut args = Args::parse_with_explicit();
+    if let Some(profile) = args.profile {
+        profile.apply(&mut args);
+    }
 
-    info!("Starting ryt with args: {:?}", args);
+    // Initialize logging
+    init_logging(args.verbosity_level(), args.print_traffic);
+    ryt::utils::set_redact_urls(!args.no_redact_urls);
 
     // Initialize output formatter
     let formatter = Arc::new(OutputFormatter::new(args.verbosity_level()));
 
+    let print_cache_stats = args.print_cache_stats;
+    let mut downloader = None;
+    let result = run(args, Arc::clone(&formatter), &mut downloader).await;
+
+    // Shut down however the run ended, so interrupted downloads unwind and
+    // their archive and session writes land before the summary
+    if let Some(downloader) = downloader {
+        let stats = downloader.cache_stats();
+        match downloader.shutdown().await {
+            Ok(partial_files) => {
+                for path in partial_files {
+                    formatter.warning(&format!("Partial download left at {}", path.display()));
+                }
+            }
+            Err(e) => formatter.warning(&e.to_string()),
+        }
+        if print_cache_stats {
+            formatter.print_cache_stats(&stats);
+        }
+    }
+
+    // Report failures as a single line on stderr, also in quiet mode
+    if let Err(e) = result {
+        let code = match e.downcast_ref::<ExitStatus>() {
+            Some(status) => status.0,
+            None => {
+                formatter.error(&e.to_string());
+                e.downcast_ref::<ryt::RytError>()
+                    .map_or(1, ryt::RytError::exit_code)
+            }
+        };
+        std::process::exit(code);
+    }
+}
+
+/// Failure that has already been reported, ending the run with an exit code
+#[derive(Debug)]
+struct ExitStatus(i32);
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "exit status {}", self.0)
+    }
+}
+
+impl std::error::Error for ExitStatus {}
+
+/// Run the command line, leaving the downloader in `slot` once it is
+/// configured so `main` can shut it down
+async fn run(
+    args: Args,
+    formatter: Arc<OutputFormatter>,
+    slot: &mut Option<Downloader>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Starting ryt with args: {:?}", args);
+
+    // Check --print templates before fetching anything
+    let print_templates = args
+        .print
+        .iter()
+        .map(|template| PrintTemplate::parse(template))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The update subcommand does not talk to YouTube
+    if let Some(Command::Update { check }) = &args.command {
+        return handle_update(*check, &formatter).await;
+    }
+
+    // Collect URLs from the command line and batch file
+    let urls = args.urls()?;
+
     // Handle special commands
-    if args.url.is_empty() {
+    if urls.is_empty() && args.command.is_none() {
         formatter.print_help();
         return Ok(());
     }
 
-    // Create downloader
-    let mut downloader = Downloader::new();
+    // Start metrics exporter
+    if let Some(addr) = args.metrics_listen {
+        start_metrics_exporter(addr, &formatter)?;
+    }
+
+    // Create downloader, starting from the config file if given. Options
+    // given on the command line override it; defaulted ones only apply
+    // without a config file.
+    let mut downloader = match &args.config {
+        Some(path) => Downloader::from_config(ryt::utils::expand_path(path))?,
+        None => Downloader::new(),
+    };
 
-    // Configure format
+    // Configure format, rejecting invalid selectors up front
     if let (Some(format), Some(ext)) = (&args.format, &args.ext) {
-        downloader = downloader.with_format(format, ext);
+        downloader = downloader.try_with_format(format, ext)?;
     } else if let Some(format) = &args.format {
-        downloader = downloader.with_format(format, "mp4");
+        downloader = downloader.try_with_format(format, "mp4")?;
     } else if let Some(ext) = &args.ext {
-        downloader = downloader.with_format("best", ext);
+        downloader = downloader.try_with_format("best", ext)?;
+    }
+    if args.overrides_config("format_fallback") {
+        downloader = downloader.with_format_fallback(args.format_fallback);
+    }
+    if args.prefer_webm {
+        downloader = downloader.with_prefer_webm(true);
+    }
+    if args.prefer_60fps {
+        downloader = downloader.with_prefer_60fps(true);
+    }
+    if let Some(bytes) = args.parse_max_filesize() {
+        downloader = downloader.with_max_filesize(bytes);
+    }
+    if let Some(bytes) = args.parse_min_filesize() {
+        downloader = downloader.with_min_filesize(bytes);
+    }
+    if args.reject_unknown_size {
+        downloader = downloader.with_reject_unknown_size(true);
+    }
+    if args.strict_format {
+        downloader = downloader.with_strict_format(true);
     }
 
     // Configure output path
     if let Some(output) = &args.output {
-        downloader = downloader.with_output_path(output);
+        downloader = downloader.try_with_output_path(output)?;
+    }
+
+    // Configure thumbnails
+    if let Some(write) = args.write_thumbnail {
+        downloader = downloader.with_write_thumbnail(write);
+    }
+    if let Some(write) = args.write_description {
+        downloader = downloader.with_write_description(write);
+    }
+    if let Some(write) = args.write_infojson {
+        downloader = downloader.with_write_infojson(write);
+    }
+    if args.write_playlist_metafiles {
+        downloader = downloader.with_write_playlist_metafiles(true);
+    }
+    if let Some(write) = args.write_subs {
+        downloader = downloader.with_write_subs(write);
+    }
+    if let Some(write) = args.write_checksum {
+        downloader = downloader.with_write_checksum(write);
+    }
+    if let Some(lang) = &args.sub_lang {
+        downloader = downloader.with_sub_lang(lang);
+    }
+    for kind in args.link_kinds() {
+        downloader = downloader.with_write_link(kind);
+    }
+    if let Some(fetch) = args.fetch_extra_metadata {
+        downloader = downloader.with_fetch_extra_metadata(fetch);
+    }
+    if let Some(format) = args.convert_thumbnails {
+        if args.write_thumbnail != Some(true) {
+            formatter.warning("--convert-thumbnails has no effect without --write-thumbnail");
+        }
+        downloader = downloader.with_thumbnail_format(format);
     }
 
-    // Configure rate limit
+    // Configure temp directory and clean up leftovers from earlier runs
+    if let Some(temp_dir) = &args.temp_dir {
+        let temp_dir = &ryt::utils::expand_path(temp_dir);
+        std::fs::create_dir_all(temp_dir)?;
+        if let Some(max_age) = args.cleanup_temp_age() {
+            // Cleanup is best effort and never stops the run
+            match ryt::utils::temp::cleanup_temp_dir(temp_dir, max_age).await {
+                Ok(removed) => debug!("Removed {} stale temp files", removed),
+                Err(e) => formatter.warning(&format!("Failed to clean up temp files: {}", e)),
+            }
+        }
+        downloader = downloader.with_temp_dir(temp_dir);
+    } else if args.cleanup_temp.is_some() {
+        formatter.warning("--cleanup-temp has no effect without --temp-dir");
+    }
+    if args.keep_fragments {
+        downloader = downloader.with_keep_temp(true);
+    }
+    if args.no_create_dirs {
+        downloader = downloader.with_create_dirs(false);
+    }
+
+    // Configure rate limit. The grace period and sharing also apply to a
+    // rate limit from the config file.
     if let Some(rate_limit) = args.parse_rate_limit() {
-        downloader = downloader.with_rate_limit(rate_limit);
+        downloader = downloader.try_with_rate_limit(rate_limit)?;
+    }
+    if downloader.rate_limit().is_some() {
+        if let Some(grace) = args.parse_rate_limit_after() {
+            downloader = downloader.with_rate_limit_after(grace);
+        }
+        if args.global_rate_limit {
+            downloader = downloader.with_global_rate_limit(true);
+        }
+    } else if args.rate_limit_after.is_some() || args.global_rate_limit {
+        formatter.warning(
+            "--rate-limit-after and --global-rate-limit have no effect without --rate-limit",
+        );
+    }
+    if let Some(throttled_rate) = args.parse_throttled_rate() {
+        if args
+            .parse_rate_limit()
+            .is_some_and(|rate_limit| rate_limit <= throttled_rate)
+        {
+            formatter.warning("--throttled-rate has no effect at or above --rate-limit");
+        } else {
+            downloader = downloader.with_throttled_rate(throttled_rate);
+        }
+    }
+    if let Some(external) = args.external_downloader() {
+        if args.rate_limit.is_some() || args.throttled_rate.is_some() {
+            formatter.warning(&format!(
+                "--rate-limit and --throttled-rate do not apply to downloads with {}",
+                external.program()
+            ));
+        }
+        downloader = downloader.with_transfer_backend(external);
+    }
+    if let Some(browser) = &args.cookies_from_browser {
+        let jar = ryt::utils::extract_from_browser(browser)?;
+        downloader = downloader.with_cookie_jar(Arc::new(jar));
+    }
+
+    // Configure download archive
+    if let Some(archive) = &args.download_archive {
+        let format = match args.download_archive_format {
+            ryt::cli::args::ArchiveFormat::Plain => ryt::utils::ArchiveFormat::Plain,
+            ryt::cli::args::ArchiveFormat::Extended => ryt::utils::ArchiveFormat::Extended,
+        };
+        downloader = downloader.with_download_archive(archive);
+        if args.overrides_config("download_archive_format") {
+            downloader = downloader.with_archive_format(format);
+        }
+        if let Some(height) = args.redownload_if_below {
+            downloader = downloader.with_redownload_below(height);
+        }
+    } else if args.redownload_if_below.is_some() {
+        formatter.warning("--redownload-if-below has no effect without --download-archive");
+    }
+
+    // Configure resumable session
+    if let Some(session_file) = &args.session_file {
+        downloader = downloader.with_session_file(session_file);
+        if args.retry_failed {
+            downloader = downloader.with_retry_failed(true);
+        }
+    } else if args.retry_failed {
+        formatter.warning("--retry-failed has no effect without --session-file");
+    }
+    if let Some(playlist_archive) = &args.playlist_archive {
+        if args.session_file.is_some() {
+            formatter.warning("--playlist-archive has no effect with --session-file");
+        } else {
+            downloader =
+                downloader.with_playlist_archive(ryt::utils::expand_path(playlist_archive));
+        }
+    }
+
+    // Configure write buffer
+    if let Some(size) = args.parse_buffer_size() {
+        downloader = downloader.try_with_write_buffer(size)?;
     }
 
     // Configure InnerTube client
     if let (Some(name), Some(version)) = (&args.client_name, &args.client_version) {
-        downloader = downloader.with_innertube_client(name, version);
+        downloader = downloader.try_with_innertube_client(name, version)?;
     }
 
     // Configure Botguard
@@ -68,10 +311,69 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_botguard_debug(args.debug_botguard)
         .with_botguard_ttl(args.botguard_ttl_duration());
 
-    // Configure timeout and retries
+    // Configure timeouts and retries
+    if args.overrides_config("timeout") {
+        downloader = downloader.try_with_request_timeout(args.timeout_duration())?;
+    }
+    if args.overrides_config("retries") {
+        downloader = downloader.with_max_retries(args.retries);
+    }
+    if args.overrides_config("fragment_retries") {
+        downloader = downloader.with_fragment_retries(args.fragment_retries);
+    }
+    if args.overrides_config("concurrent_fragments") {
+        downloader = downloader.with_concurrent_fragments(args.concurrent_fragments as usize);
+    }
+    if let Some(socket_timeout) = args.socket_timeout_duration() {
+        downloader = downloader
+            .try_with_connect_timeout(socket_timeout)?
+            .try_with_read_timeout(socket_timeout)?;
+    }
+
+    // Configure address family; by default media chunks try IPv6 before IPv4
+    if args.force_ipv4 {
+        downloader = downloader.with_ip_family(IpFamily::V4);
+    } else if args.force_ipv6 {
+        downloader = downloader.with_ip_family(IpFamily::V6);
+    }
+    if args.force_ipv4_for_media {
+        downloader = downloader.with_media_ip_family(IpFamily::V4);
+    }
+    for entry in &args.resolve {
+        downloader = downloader.with_dns_override(&entry.host, entry.addrs.clone());
+    }
+    if args.print_traffic {
+        downloader = downloader.with_print_traffic(args.traffic_body_limit);
+    }
+
+    // Configure request locale
+    if let Some(country) = args.parse_geo_bypass_country() {
+        downloader = downloader.try_with_geo_bypass_country(&country)?;
+    }
+    if let Some(language) = &args.language {
+        downloader = downloader.with_language(language);
+    }
+
+    // Configure playlist and batch run controls
+    if let Some(interval) = args.parse_sleep_interval() {
+        downloader = downloader.with_sleep_interval(interval);
+    }
+    if let Some(max_downloads) = args.max_downloads {
+        downloader = downloader.with_max_downloads(max_downloads);
+    }
     downloader = downloader
-        .with_timeout(args.timeout_duration())
-        .with_max_retries(args.retries);
+        .with_break_on_existing(args.break_on_existing)
+        .with_playlist_abort_on_error(args.playlist_abort_on_error)
+        .with_playlist_reverse(args.playlist_reverse);
+    if let Some(spec) = &args.playlist_items {
+        downloader = downloader.with_playlist_items(PlaylistItemsSpec::parse(spec)?);
+    }
+    downloader.playlist_options().validate()?;
+
+    // Configure match filter
+    if let Some(expr) = &args.match_filter {
+        downloader = downloader.with_match_filter(MatchFilter::parse(expr)?);
+    }
 
     // Configure progress callback
     if !args.no_progress {
@@ -81,186 +383,584 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         });
     }
 
-    // Handle playlist downloads
-    if args.is_playlist() {
-        return handle_playlist_download(downloader, &args, formatter).await;
+    let fields = args.info_fields();
+
+    let prints_info = args.dump_json || !fields.is_empty() || !print_templates.is_empty();
+
+    // Only the requested lines go to stdout, so the output can be piped
+    let print_plan = {
+        let formatter = formatter.clone();
+        let dump_json = args.dump_json;
+        Arc::new(move |plan: &DownloadPlan| {
+            let info = &plan.video_info;
+            if dump_json {
+                match serde_json::to_string(info) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => formatter.error(&format!("Failed to serialize metadata: {}", e)),
+                }
+            }
+            for field in &fields {
+                println!("{}", format_info_field(*field, info));
+            }
+            for line in render_print_lines(&print_templates, info, &plan.format) {
+                println!("{}", line);
+            }
+        })
+    };
+    // With --no-simulate the lines of each video are printed once it has
+    // downloaded, from the format that was written
+    if args.no_simulate {
+        let print_plan = Arc::clone(&print_plan);
+        downloader = downloader.with_plan_callback(move |plan| print_plan(plan));
+    }
+
+    // Report stages before the progress bar shows up, keeping printed metadata clean
+    if !args.no_progress && !prints_info {
+        let formatter_clone = formatter.clone();
+        downloader = downloader.with_status_callback(move |status: Status| {
+            if status != Status::Downloading {
+                formatter_clone.info(status.label());
+            }
+        });
+    }
+
+    let downloader = &*slot.insert(downloader);
+
+    // Handle listing subcommands with the configured downloader, so proxy,
+    // cookie, client and timeout options apply to them too
+    if let Some(Command::Trending {
+        region,
+        category,
+        limit,
+    }) = &args.command
+    {
+        return handle_trending(downloader, &args, region, *category, *limit, &formatter).await;
+    }
+    if let Some(Command::WatchNext { url, count }) = &args.command {
+        return handle_watch_next(downloader, &args, url, *count, &formatter).await;
+    }
+    if let Some(Command::ChannelPlaylists { url, limit }) = &args.command {
+        return handle_channel_playlists(downloader, &args, url, *limit, &formatter).await;
+    }
+
+    // Handle metadata printing (no downloads)
+    if prints_info && !args.no_simulate {
+        let failed = handle_print_info(downloader, &args, &urls, &formatter, |plan| {
+            print_plan(plan)
+        })
+        .await;
+        if failed > 0 {
+            return Err(ExitStatus(1).into());
+        }
+        return Ok(());
     }
 
-    // Handle single video download
-    handle_single_download(downloader, &args, formatter).await
+    // The first Ctrl-C stops downloads cleanly, the second exits at once
+    let cancel = downloader.cancellation_token();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\nInterrupted, stopping downloads (press Ctrl-C again to force)");
+            cancel.cancel();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        }
+    });
+
+    if args.simulate || args.skip_download {
+        // Handle simulate mode (no downloads)
+        handle_simulate(downloader, &args, &urls, &formatter).await
+    } else if args.session_file.is_some() {
+        handle_session_download(downloader, &urls, &args, &formatter).await
+    } else if urls.len() > 1
+        && !args.print_url
+        && !urls.iter().any(|url| args.is_playlist_target(url))
+    {
+        // Batches of plain videos share the run controls across all items
+        handle_batch_download(downloader, &urls, &args, &formatter).await
+    } else {
+        // Videos handled by an earlier input of this run are skipped
+        let mut seen = HashSet::new();
+        for url in &urls {
+            if args.is_playlist_target(url) {
+                // Handle playlist downloads
+                handle_playlist_download(downloader, url, &args, &formatter, &mut seen).await?;
+            } else {
+                // Handle single video download
+                handle_single_download(downloader, url, &args, &formatter, &mut seen).await?;
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Handle single video download
 async fn handle_single_download(
-    mut downloader: Downloader,
+    downloader: &Downloader,
+    url: &str,
     args: &Args,
-    formatter: Arc<OutputFormatter>,
+    formatter: &OutputFormatter,
+    seen: &mut HashSet<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let start_time = Instant::now();
 
     // Print URL only mode
     if args.print_url {
         debug!("Print URL mode enabled");
-        let (final_url, _video_info) = downloader.resolve_url(&args.url).await?;
+        let (final_url, _video_info) = downloader.resolve_url(url).await?;
         println!("{}", final_url);
         return Ok(());
     }
 
+    // A video already handled by an earlier input of this run is skipped
+    if let Ok(video_id) = ryt::utils::extract_video_id(url) {
+        if !seen.insert(video_id) {
+            formatter.info(&format!("Skipping {}: already handled in this run", url));
+            return Ok(());
+        }
+    }
+
     // Print download start
-    formatter.print_download_start(&args.url, "auto-generated filename");
-    info!("Starting download for URL: {}", args.url);
+    formatter.print_download_start(url, "auto-generated filename");
+    info!("Starting download for URL: {}", url);
 
     // Download video
-    let video_info = downloader.download(&args.url).await?;
+    let video_info = match downloader.download(url).await {
+        Ok(video_info) => video_info,
+        Err(ryt::RytError::AlreadyArchived(video_id)) => {
+            formatter.info(&format!(
+                "{} has already been recorded in the archive",
+                video_id
+            ));
+            return Ok(());
+        }
+        Err(ryt::RytError::FilteredOut(reason)) => {
+            formatter.info(&format!("Skipped: {}", reason));
+            return Ok(());
+        }
+        Err(ryt::RytError::Blocked { reason }) => {
+            // YouTube's reason names who blocked the video, so print it as is
+            formatter.error(&reason);
+            return Err(ExitStatus(1).into());
+        }
+        Err(e) => return Err(e.into()),
+    };
     info!("Download completed successfully");
 
     // Print completion
     let duration = start_time.elapsed();
-    formatter.print_download_complete("downloaded file", duration);
+    formatter.print_download_complete(
+        "downloaded file",
+        duration,
+        video_info.selected_format.as_ref(),
+    );
 
     // Print video info
-    formatter.print_video_info(
-        &video_info.title,
-        &video_info.author,
-        video_info.duration,
-        video_info.formats.len(),
-    );
+    formatter.print_video_info(&video_info);
 
     Ok(())
 }
 
 /// Handle playlist download
 async fn handle_playlist_download(
-    mut downloader: Downloader,
+    downloader: &Downloader,
+    url: &str,
     args: &Args,
-    formatter: Arc<OutputFormatter>,
+    formatter: &OutputFormatter,
+    seen: &mut HashSet<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let start_time = Instant::now();
 
     // Extract playlist ID
-    let playlist_id = ryt::utils::url::extract_playlist_id(&args.url)?;
+    let playlist_id = ryt::utils::url::extract_playlist_id(url)?;
     info!("Processing playlist: {}", playlist_id);
 
     // Print playlist info
     formatter.print_playlist_info(&playlist_id, 0, Some(args.limit));
 
     // Download playlist
-    let limit = if args.limit > 0 {
-        Some(args.limit)
-    } else {
-        None
+    let result = downloader
+        .download_playlist_deduped(url, playlist_limit(args), seen)
+        .await?;
+    info!(
+        "Playlist download completed in {}: {} videos, {} failed",
+        format_duration(start_time.elapsed()),
+        result.succeeded.len(),
+        result.failed.len()
+    );
+
+    finish_playlist_run(&result, args, formatter)
+}
+
+/// Handle a batch of video downloads
+async fn handle_batch_download(
+    downloader: &Downloader,
+    urls: &[String],
+    args: &Args,
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let start_time = Instant::now();
+
+    let result = downloader.download_batch(urls).await?;
+    info!(
+        "Batch download completed in {}: {} videos, {} failed",
+        format_duration(start_time.elapsed()),
+        result.succeeded.len(),
+        result.failed.len()
+    );
+
+    finish_playlist_run(&result, args, formatter)
+}
+
+/// Print the items and summary of a playlist or batch run
+///
+/// Fails with exit status 1 when every attempted item failed, or when any
+/// item failed with `--playlist-abort-on-error`.
+fn finish_playlist_run(
+    result: &PlaylistDownloadResult,
+    args: &Args,
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (index, video_info) in result.succeeded.iter().enumerate() {
+        formatter.print_playlist_item(index, result.succeeded.len(), &video_info.title);
+    }
+    formatter.print_playlist_summary(result);
+
+    if result.all_failed() || (args.playlist_abort_on_error && !result.failed.is_empty()) {
+        return Err(ExitStatus(1).into());
+    }
+    Ok(())
+}
+
+/// Handle playlist and batch downloads tracked in a session file
+///
+/// An existing session for the same input is resumed without resolving the
+/// inputs again; otherwise the inputs are expanded into a new session.
+async fn handle_session_download(
+    downloader: &Downloader,
+    urls: &[String],
+    args: &Args,
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let start_time = Instant::now();
+    let input = urls.join(" ");
+
+    let mut session = match downloader.resume_session(&input)? {
+        Some(session) => session,
+        None => {
+            let mut items = Vec::new();
+            let mut seen = HashSet::new();
+            for (url, title) in collect_targets(downloader, args, urls).await? {
+                let video_id = ryt::utils::extract_video_id(&url)?;
+                if !seen.insert(video_id.clone()) {
+                    info!(
+                        "Skipping {}: already in the session",
+                        title.as_deref().unwrap_or(&url)
+                    );
+                    continue;
+                }
+                items.push(match &title {
+                    Some(title) => downloader.plan_session_item(&video_id, title),
+                    // The output path isn't known until the video is resolved
+                    None => SessionItem::new(&video_id, &url),
+                });
+            }
+            downloader.start_session(&input, items)?
+        }
     };
-    let video_infos = downloader.download_playlist(&args.url, limit).await?;
-    info!("Playlist download completed: {} videos", video_infos.len());
 
-    // Print completion
-    let duration = start_time.elapsed();
-    formatter.success(&format!(
-        "Downloaded {} videos in {}",
-        video_infos.len(),
-        format_duration(duration)
-    ));
+    let result = downloader.download_session(&mut session).await?;
+    let failed = session.count(ItemStatus::Failed);
+    info!(
+        "Session download completed in {}: {} of {} done, {} failed",
+        format_duration(start_time.elapsed()),
+        session.count(ItemStatus::Done),
+        session.items.len(),
+        failed
+    );
+
+    if failed > 0 {
+        formatter.warning(&format!(
+            "Failed items are kept in {}, rerun with --retry-failed to retry them",
+            session.path().display()
+        ));
+    }
+    finish_playlist_run(&result, args, formatter)
+}
 
-    // Print summary
-    for (index, video_info) in video_infos.iter().enumerate() {
-        formatter.print_playlist_item(index, video_infos.len(), &video_info.title);
+/// Expand URLs into (video URL, title) targets, resolving playlists to their
+/// items; the title of a video given by URL is unknown until it is resolved
+async fn collect_targets(
+    downloader: &Downloader,
+    args: &Args,
+    urls: &[String],
+) -> Result<Vec<(String, Option<String>)>, Box<dyn std::error::Error>> {
+    let mut targets = Vec::new();
+    for url in urls {
+        if args.is_playlist_target(url) {
+            let items = downloader
+                .get_playlist_items(url, playlist_limit(args))
+                .await?;
+            targets.extend(items.into_iter().map(|item| (item.url(), Some(item.title))));
+        } else {
+            targets.push((url.clone(), None));
+        }
     }
+    Ok(targets)
+}
 
+/// Install the metrics recorder and serve /metrics on `addr`
+#[cfg(feature = "metrics")]
+fn start_metrics_exporter(
+    addr: std::net::SocketAddr,
+    _formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let handle = Downloader::install_metrics_recorder()?;
+    tokio::spawn(async move {
+        if let Err(e) = ryt::utils::metrics::serve_metrics(addr, handle).await {
+            tracing::error!("Metrics exporter on {} failed: {}", addr, e);
+        }
+    });
+    info!("Serving metrics on http://{}/metrics", addr);
     Ok(())
 }
 
-/// Initialize logging system
-fn init_logging() -> Result<(), Box<dyn std::error::Error>> {
-    // Get log level from environment or default to info
-    let log_level = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
+/// Warn that metrics are unavailable in this build
+#[cfg(not(feature = "metrics"))]
+fn start_metrics_exporter(
+    _addr: std::net::SocketAddr,
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    formatter.warning("--metrics-listen requires ryt to be built with the `metrics` feature");
+    Ok(())
+}
 
-    // Parse log level
-    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(&log_level));
+/// Handle --get-*, --dump-json and --print flags: print metadata for each
+/// video in input order, expanding playlists into their items
+///
+/// Returns the number of videos whose metadata could not be fetched.
+async fn handle_print_info(
+    downloader: &Downloader,
+    args: &Args,
+    urls: &[String],
+    formatter: &OutputFormatter,
+    print: impl Fn(&DownloadPlan),
+) -> usize {
+    let targets = match collect_targets(downloader, args, urls).await {
+        Ok(targets) => targets,
+        Err(e) => {
+            formatter.error(&e.to_string());
+            return 1;
+        }
+    };
 
-    // Initialize tracing subscriber
-    tracing_subscriber::registry()
-        .with(filter)
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_target(false)
-                .with_thread_ids(true)
-                .with_file(true)
-                .with_line_number(true)
-                .compact(),
-        )
-        .init();
+    let mut failed = 0;
+    for (url, title) in &targets {
+        match downloader.fetch_info(url).await {
+            Ok(plan) => print(&plan),
+            Err(e) => {
+                failed += 1;
+                formatter.error(&format!("{}: {}", title.as_deref().unwrap_or(url), e));
+            }
+        }
+    }
+
+    failed
+}
+
+/// Handle the trending subcommand: list trending videos, one per line
+async fn handle_trending(
+    downloader: &Downloader,
+    args: &Args,
+    region: &str,
+    category: TrendingCategory,
+    limit: usize,
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = (limit > 0).then_some(limit);
+
+    let results = downloader.get_trending(region, category, limit).await?;
+    if results.is_empty() {
+        formatter.warning(&format!("No trending videos found for region {}", region));
+    }
+
+    for result in &results {
+        if args.dump_json {
+            println!("{}", serde_json::to_string(result)?);
+        } else {
+            println!("{}\t{}\t{}", result.url(), result.title, result.author);
+        }
+    }
 
     Ok(())
 }
 
-/// Format duration as human-readable string
-fn format_duration(duration: std::time::Duration) -> String {
-    let total_seconds = duration.as_secs();
-
-    if total_seconds < 60 {
-        format!("{}s", total_seconds)
-    } else if total_seconds < 3600 {
-        let minutes = total_seconds / 60;
-        let seconds = total_seconds % 60;
-        if seconds == 0 {
-            format!("{}m", minutes)
+/// Handle the watch-next subcommand: list queued videos, one URL per line
+async fn handle_watch_next(
+    downloader: &Downloader,
+    args: &Args,
+    url: &str,
+    count: usize,
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = (count > 0).then_some(count);
+
+    let results = downloader.get_watch_next(url, limit).await?;
+    if results.is_empty() {
+        formatter.warning(&format!("No queued videos found for {}", url));
+    }
+
+    for result in &results {
+        if args.dump_json {
+            println!("{}", serde_json::to_string(result)?);
         } else {
-            format!("{}m {}s", minutes, seconds)
+            println!("{}", result.url());
         }
-    } else {
-        let hours = total_seconds / 3600;
-        let minutes = (total_seconds % 3600) / 60;
-        if minutes == 0 {
-            format!("{}h", hours)
+    }
+
+    Ok(())
+}
+
+/// Handle the channel-playlists subcommand: list playlist URLs and titles
+async fn handle_channel_playlists(
+    downloader: &Downloader,
+    args: &Args,
+    url: &str,
+    limit: usize,
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = (limit > 0).then_some(limit);
+
+    let playlists = downloader.get_channel_playlists(url, limit).await?;
+    if playlists.is_empty() {
+        formatter.warning(&format!("No playlists found for {}", url));
+    }
+
+    for playlist in &playlists {
+        if args.dump_json {
+            println!("{}", serde_json::to_string(playlist)?);
         } else {
-            format!("{}h {}m", hours, minutes)
+            println!("{}\t{}", playlist.url(), playlist.title);
         }
     }
+
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::time::Duration;
+/// Handle the update subcommand: install the latest release over this binary
+async fn handle_update(
+    check: bool,
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let updater = Updater::new().with_token(std::env::var("GITHUB_TOKEN").ok());
+    let release = updater.latest_release().await?;
+    if !updater.is_update(&release) {
+        formatter.success(&format!(
+            "ryt {} is up to date (latest release: {})",
+            updater.current_version(),
+            release.version()
+        ));
+        return Ok(());
+    }
 
-    #[test]
-    fn test_format_duration_seconds() {
-        assert_eq!(format_duration(Duration::from_secs(30)), "30s");
-        assert_eq!(format_duration(Duration::from_secs(59)), "59s");
-        assert_eq!(format_duration(Duration::from_secs(0)), "0s");
+    formatter.info(&format!(
+        "ryt {} is available (installed: {})",
+        release.version(),
+        updater.current_version()
+    ));
+    if let Some(changelog) = release.changelog() {
+        println!("{}", changelog);
+        println!();
+    }
+    if check {
+        return Ok(());
     }
 
-    #[test]
-    fn test_format_duration_minutes() {
-        assert_eq!(format_duration(Duration::from_secs(60)), "1m");
-        assert_eq!(format_duration(Duration::from_secs(90)), "1m 30s");
-        assert_eq!(format_duration(Duration::from_secs(120)), "2m");
-        assert_eq!(format_duration(Duration::from_secs(150)), "2m 30s");
-        assert_eq!(format_duration(Duration::from_secs(3599)), "59m 59s");
+    let executable = std::env::current_exe()?;
+    updater.install(&release, &executable).await?;
+    formatter.success(&format!(
+        "Updated ryt to {} at {}",
+        release.version(),
+        executable.display()
+    ));
+    Ok(())
+}
+
+/// Handle simulate mode: print what would be downloaded
+async fn handle_simulate(
+    downloader: &Downloader,
+    args: &Args,
+    urls: &[String],
+    formatter: &OutputFormatter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let targets = collect_targets(downloader, args, urls).await?;
+
+    let mut planned = 0;
+    let mut failed = 0;
+    let mut total_size = 0u64;
+
+    for (url, title) in &targets {
+        match downloader.fetch_info(url).await {
+            Ok(plan) => {
+                planned += 1;
+                total_size += plan.format.size.unwrap_or(0);
+                formatter.print_dry_run(
+                    &plan.video_info.title,
+                    &plan.format.quality_label(),
+                    plan.format.extension(),
+                    plan.format.size,
+                    &plan.output_path.display().to_string(),
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                formatter.print_dry_run_error(title.as_deref().unwrap_or(url), &e.to_string());
+            }
+        }
     }
 
-    #[test]
-    fn test_format_duration_hours() {
-        assert_eq!(format_duration(Duration::from_secs(3600)), "1h");
-        assert_eq!(format_duration(Duration::from_secs(3660)), "1h 1m");
-        assert_eq!(format_duration(Duration::from_secs(7200)), "2h");
-        assert_eq!(format_duration(Duration::from_secs(7260)), "2h 1m");
-        assert_eq!(format_duration(Duration::from_secs(7320)), "2h 2m");
+    if targets.len() > 1 || failed > 0 {
+        formatter.print_dry_run_summary(planned, failed, total_size);
     }
 
-    #[test]
-    fn test_format_duration_edge_cases() {
-        // Test exact hour boundaries
-        assert_eq!(format_duration(Duration::from_secs(3600)), "1h");
-        assert_eq!(format_duration(Duration::from_secs(7200)), "2h");
+    Ok(())
+}
 
-        // Test exact minute boundaries
-        assert_eq!(format_duration(Duration::from_secs(60)), "1m");
-        assert_eq!(format_duration(Duration::from_secs(120)), "2m");
+/// Get playlist item limit (0 means all)
+fn playlist_limit(args: &Args) -> Option<usize> {
+    if args.limit > 0 {
+        Some(args.limit)
+    } else {
+        None
+    }
+}
 
-        // Test large durations
-        assert_eq!(format_duration(Duration::from_secs(3661)), "1h 1m");
-        assert_eq!(format_duration(Duration::from_secs(3721)), "1h 2m");
+/// Initialize logging system
+fn init_logging(verbosity: VerbosityLevel, print_traffic: bool) {
+    // RUST_LOG wins; otherwise log at info, or not at all in quiet mode
+    let default_level = if verbosity == VerbosityLevel::Quiet {
+        "off"
+    } else {
+        "info"
+    };
+    let mut filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(default_level));
+    // --print-traffic is shown even in quiet mode
+    if print_traffic {
+        filter = filter.add_directive("ryt::traffic=info".parse().expect("valid directive"));
     }
+
+    // Initialize tracing subscriber
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_thread_ids(true)
+                .with_file(true)
+                .with_line_number(true)
+                .with_writer(std::io::stderr)
+                .compact(),
+        )
+        .init();
 }
@@ -2,10 +2,12 @@
 
 use crate::error::RytError;
 use crate::utils::cache::MultiLevelCache;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 /// Botguard mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum BotguardMode {
     /// Disabled
     Off,
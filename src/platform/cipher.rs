@@ -1,13 +1,22 @@
 //! Signature cipher deciphering for video platform
 
 use crate::error::RytError;
-use crate::utils::cache::{new_async_cache, MemoryCache, MultiLevelCache};
-use deno_core::{FastString, JsRuntime, RuntimeOptions};
+use crate::platform::client::fetch_html_with_consent;
+use crate::utils::cache::{new_async_cache, CacheStats, MemoryCache, MultiLevelCache};
+use crate::utils::{loggable_secret, metrics};
+use deno_core::{v8, FastString, JsRuntime, RuntimeOptions};
 use regex::Regex;
 use reqwest::Client;
-use std::sync::Arc;
-use std::time::Duration;
-use tracing::debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tracing::{debug, debug_span, field, warn, Span};
+
+/// Default limit on how long a player.js execution may run
+pub const DEFAULT_CIPHER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest player.js accepted for full execution
+pub const MAX_PLAYER_JS_SIZE: usize = 10 * 1024 * 1024;
 
 /// Signature cipher decipherer
 pub struct Cipher {
@@ -15,6 +24,7 @@ pub struct Cipher {
     async_cache: Arc<moka::future::Cache<String, String>>,
     multi_cache: MultiLevelCache,
     http_client: Client,
+    js_timeout: Duration,
 }
 
 #[derive(Clone)]
@@ -31,13 +41,24 @@ impl Cipher {
             async_cache: Arc::new(new_async_cache(Duration::from_secs(600))), // 10 minutes
             multi_cache: MultiLevelCache::new(),
             http_client: Client::new(),
+            js_timeout: DEFAULT_CIPHER_TIMEOUT,
         }
     }
 
+    /// Set how long a player.js execution may run before it is terminated
+    pub fn with_cipher_timeout(mut self, timeout: Duration) -> Self {
+        self.js_timeout = timeout;
+        self
+    }
+
+    /// Get how long a player.js execution may run
+    pub fn cipher_timeout(&self) -> Duration {
+        self.js_timeout
+    }
+
     /// Fetch player.js URL from video page
     pub async fn fetch_player_js_url(&self, video_url: &str) -> Result<String, RytError> {
-        let response = self.http_client.get(video_url).send().await?;
-        let html = response.text().await?;
+        let html = fetch_html_with_consent(video_url, || self.http_client.get(video_url)).await?;
 
         // Extract player.js URL from HTML
         let player_js_regex = Regex::new(r#""jsUrl":"([^"]+)""#)?;
@@ -72,11 +93,18 @@ impl Cipher {
             }
         }
 
-        // Fetch from network
-        let response = self.http_client.get(player_js_url).send().await?;
-        let content = response.text().await?;
+        // Fetch from network; concurrent misses for the same URL share one request
+        let http_client = self.http_client.clone();
+        let url = player_js_url.to_string();
+        let content = self
+            .multi_cache
+            .get_or_fetch_player_js(player_js_url, async move {
+                let response = http_client.get(&url).send().await?;
+                Ok(response.text().await?)
+            })
+            .await?;
 
-        // Cache in both systems
+        // Keep legacy cache in sync
         self.cache.insert(
             player_js_url.to_string(),
             CachedPlayer {
@@ -85,58 +113,82 @@ impl Cipher {
             },
             Duration::from_secs(600),
         );
-        self.multi_cache
-            .set_player_js(player_js_url, content.clone())
-            .await;
 
         Ok(content)
     }
 
     /// Decipher signature using multiple methods
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(
+            signature_len = signature.len(),
+            video_url = %video_url,
+            player_js_url = field::Empty,
+        )
+    )]
     pub async fn decipher_signature(
         &self,
         signature: &str,
         video_url: &str,
     ) -> Result<String, RytError> {
-        debug!("Deciphering signature: {}", signature);
+        debug!("Deciphering signature: {}", loggable_secret(signature));
 
         // Check multi-level cache first
         if let Some(cached) = self.multi_cache.get_signature(signature).await {
             debug!("Signature cache hit");
+            metrics::cipher_cache("signature", true);
             return Ok(cached);
         }
 
         // Check legacy cache
         if let Some(cached) = self.async_cache.get(signature).await {
             debug!("Legacy signature cache hit");
+            metrics::cipher_cache("signature", true);
             // Update multi-level cache
             self.multi_cache
                 .set_signature(signature, cached.clone())
                 .await;
             return Ok(cached);
         }
+        metrics::cipher_cache("signature", false);
 
         // Get player.js URL and content
         let player_js_url = self.fetch_player_js_url(video_url).await?;
+        Span::current().record("player_js_url", player_js_url.as_str());
         let player_js = self.fetch_player_js(&player_js_url).await?;
         debug!("Fetched player.js for signature deciphering");
 
         // Try different deciphering methods - prioritize JS engine like Go ytdlp
-        let deciphered = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current()
-                .block_on(async { self.decipher_with_full_js(signature, &player_js).await })
-        })
+        let deciphered = in_attempt_span(
+            debug_span!("decipher_with_full_js", success = field::Empty),
+            || {
+                tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current()
+                        .block_on(async { self.decipher_with_full_js(signature, &player_js).await })
+                })
+            },
+        )
         .or_else(|_| {
             debug!("Full JS deciphering failed, trying minimal JS");
-            self.decipher_with_minimal_js(signature, &player_js)
+            in_attempt_span(
+                debug_span!("decipher_with_minimal_js", success = field::Empty),
+                || self.decipher_with_minimal_js(signature, &player_js),
+            )
         })
         .or_else(|_| {
             debug!("Minimal JS deciphering failed, trying regex");
-            self.decipher_with_regex(signature, &player_js)
+            in_attempt_span(
+                debug_span!("decipher_with_regex", success = field::Empty),
+                || self.decipher_with_regex(signature, &player_js),
+            )
         })
         .or_else(|_| {
             debug!("Regex deciphering failed, trying pattern fallback");
-            self.decipher_with_pattern_fallback(signature, &player_js)
+            in_attempt_span(
+                debug_span!("decipher_with_pattern_fallback", success = field::Empty),
+                || self.decipher_with_pattern_fallback(signature, &player_js),
+            )
         })?;
 
         debug!("Signature deciphered successfully");
@@ -159,23 +211,26 @@ impl Cipher {
         video_url: &str,
     ) -> Result<String, RytError> {
         let cache_key = format!("n:{}", n_param);
-        debug!("Deciphering n-parameter: {}", n_param);
+        debug!("Deciphering n-parameter: {}", loggable_secret(n_param));
 
         // Check multi-level cache first
         if let Some(cached) = self.multi_cache.get_signature(&cache_key).await {
             debug!("N-parameter cache hit");
+            metrics::cipher_cache("n", true);
             return Ok(cached);
         }
 
         // Check legacy cache
         if let Some(cached) = self.async_cache.get(&cache_key).await {
             debug!("Legacy n-parameter cache hit");
+            metrics::cipher_cache("n", true);
             // Update multi-level cache
             self.multi_cache
                 .set_signature(&cache_key, cached.clone())
                 .await;
             return Ok(cached);
         }
+        metrics::cipher_cache("n", false);
 
         // Get player.js URL and content
         let player_js_url = self.fetch_player_js_url(video_url).await?;
@@ -233,6 +288,59 @@ impl Cipher {
         Ok(result)
     }
 
+    /// Decipher the n-parameter of a media URL and rewrite it in place
+    ///
+    /// URLs without an `n` query parameter are returned unchanged.
+    pub async fn apply_n_param(&self, url: &str, video_url: &str) -> Result<String, RytError> {
+        let mut parsed = match url::Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(_) => return Ok(url.to_string()),
+        };
+
+        let n_param = match parsed
+            .query_pairs()
+            .find(|(k, _)| k == "n")
+            .map(|(_, v)| v.into_owned())
+        {
+            Some(n_param) => n_param,
+            None => return Ok(url.to_string()),
+        };
+
+        let deciphered_n = self.decipher_n_parameter(&n_param, video_url).await?;
+
+        // Collect pairs first (immutable borrow), then rebuild keeping parameter order
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .map(|(k, v)| {
+                let v = if k == "n" {
+                    deciphered_n.clone()
+                } else {
+                    v.into_owned()
+                };
+                (k.into_owned(), v)
+            })
+            .collect();
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+
+        Ok(parsed.into())
+    }
+
+    /// Seed the signature cache (for tests that must not fetch player.js)
+    #[cfg(test)]
+    pub(crate) async fn cache_signature(&self, signature: &str, deciphered: &str) {
+        self.multi_cache
+            .set_signature(signature, deciphered.to_string())
+            .await;
+    }
+
+    /// Seed the n-parameter cache (for tests that must not fetch player.js)
+    #[cfg(test)]
+    pub(crate) async fn cache_n_parameter(&self, n_param: &str, deciphered: &str) {
+        self.multi_cache
+            .set_signature(&format!("n:{}", n_param), deciphered.to_string())
+            .await;
+    }
+
     /// Apply ncode transformation based on function body
     fn apply_ncode_transformation(
         &self,
@@ -1385,17 +1493,28 @@ impl Cipher {
         player_js: &str,
     ) -> Result<String, RytError> {
         debug!("Executing full player.js ({} chars)", player_js.len());
+        if player_js.len() > MAX_PLAYER_JS_SIZE {
+            return Err(RytError::CipherError(format!(
+                "player.js too large for execution ({} bytes, limit {})",
+                player_js.len(),
+                MAX_PLAYER_JS_SIZE
+            )));
+        }
 
-        // Create JavaScript runtime
+        // Create JavaScript runtime, terminated if it runs too long
         let mut runtime = JsRuntime::new(RuntimeOptions::default());
+        let watchdog = Watchdog::start(runtime.v8_isolate(), self.js_timeout);
 
         // Execute the full player.js
+        let started = Instant::now();
         let js_code_fast = FastString::from(player_js.to_string());
-        runtime
-            .execute_script("<player>", js_code_fast)
-            .map_err(|e| {
+        let executed = runtime.execute_script("<player>", js_code_fast);
+        debug!("player.js executed in {:?}", started.elapsed());
+        executed.map_err(|e| {
+            watchdog.error().unwrap_or_else(|| {
                 RytError::CipherError(format!("Full player.js execution error: {:?}", e))
-            })?;
+            })
+        })?;
 
         // Try to find and call the decipher function
         // Look for common decipher function names
@@ -1424,11 +1543,8 @@ impl Cipher {
                             let result_str = local_value.to_rust_string_lossy(scope);
                             debug!(
                                 "Successfully called decipher function '{}' with result: {}",
-                                name, result_str
-                            );
-                            println!(
-                                "[DEBUG] JS engine called function '{}' and got: {}",
-                                name, result_str
+                                name,
+                                loggable_secret(&result_str)
                             );
                             return Ok(result_str);
                         }
@@ -1441,6 +1557,9 @@ impl Cipher {
                     debug!("Failed to call function '{}': {:?}", name, e);
                 }
             }
+            if let Some(e) = watchdog.error() {
+                return Err(e);
+            }
         }
 
         Err(RytError::CipherError(
@@ -1459,8 +1578,9 @@ impl Cipher {
         let function_name = self.find_decipher_function_name(player_js)?;
         debug!("Found decipher function name: {}", function_name);
 
-        // Create JavaScript runtime
+        // Create JavaScript runtime, terminated if it runs too long
         let mut runtime = JsRuntime::new(RuntimeOptions::default());
+        let watchdog = Watchdog::start(runtime.v8_isolate(), self.js_timeout);
 
         // Try to create a minimal working environment
         // First, try to find the transform object and create a minimal version
@@ -1480,7 +1600,10 @@ impl Cipher {
                         let scope = &mut runtime.handle_scope();
                         let local_value = result_value.open(scope);
                         let result_str = local_value.to_rust_string_lossy(scope);
-                        debug!("Minimal JS execution successful: {}", result_str);
+                        debug!(
+                            "Minimal JS execution successful: {}",
+                            loggable_secret(&result_str)
+                        );
                         return Ok(result_str);
                     }
                 }
@@ -1514,21 +1637,26 @@ impl Cipher {
         runtime
             .execute_script("<extracted>", js_fast)
             .map_err(|e| {
-                RytError::CipherError(format!("Extracted function execution error: {:?}", e))
+                watchdog.error().unwrap_or_else(|| {
+                    RytError::CipherError(format!("Extracted function execution error: {:?}", e))
+                })
             })?;
 
         // Call the function with the signature
         let call_code = format!("{}(\"{}\")", extracted_name, signature);
         let call_fast = FastString::from(call_code);
-        let result = runtime
-            .execute_script("<call>", call_fast)
-            .map_err(|e| RytError::CipherError(format!("Function call error: {:?}", e)))?;
+        let result = runtime.execute_script("<call>", call_fast).map_err(|e| {
+            watchdog
+                .error()
+                .unwrap_or_else(|| RytError::CipherError(format!("Function call error: {:?}", e)))
+        })?;
 
         // Convert result to string
-        let result_value = runtime
-            .resolve(result)
-            .await
-            .map_err(|e| RytError::CipherError(format!("Result resolution error: {:?}", e)))?;
+        let result_value = runtime.resolve(result).await.map_err(|e| {
+            watchdog.error().unwrap_or_else(|| {
+                RytError::CipherError(format!("Result resolution error: {:?}", e))
+            })
+        })?;
 
         let scope = &mut runtime.handle_scope();
         let local_value = result_value.open(scope);
@@ -2083,11 +2211,16 @@ impl Cipher {
         Err(RytError::CipherError("Pattern fallback failed".to_string()))
     }
 
-    /// Clear caches
+    /// Clear player.js, signature and n-parameter caches
     pub fn clear_caches(&self) {
         self.cache.clear();
-        // Note: moka cache doesn't have a clear method in the version we're using
-        // We'll let it expire naturally
+        self.async_cache.invalidate_all();
+        self.multi_cache.invalidate_all();
+    }
+
+    /// Get cache entry counts and hit/miss counters
+    pub fn cache_stats(&self) -> CacheStats {
+        self.multi_cache.get_stats()
     }
 }
 
@@ -2097,10 +2230,119 @@ impl Default for Cipher {
     }
 }
 
+/// Terminates a JS runtime's execution once a timeout passes.
+///
+/// The timer runs on its own thread, since a script stuck in a loop never
+/// yields back to the async runtime. Dropping the watchdog stops the timer
+/// and joins its thread.
+struct Watchdog {
+    stop: Option<mpsc::Sender<()>>,
+    timer: Option<std::thread::JoinHandle<()>>,
+    fired: Arc<AtomicBool>,
+    /// Shared with the timer thread until it ends
+    running: Arc<()>,
+}
+
+impl Watchdog {
+    /// Start the timer for the isolate of a runtime
+    fn start(isolate: &mut v8::OwnedIsolate, timeout: Duration) -> Self {
+        let handle = isolate.thread_safe_handle();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let fired = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&fired);
+        let running = Arc::new(());
+        let alive = Arc::clone(&running);
+        let timer = std::thread::spawn(move || {
+            let _alive = alive;
+            // A disconnect means the watchdog was dropped in time
+            if let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                warn!("player.js execution exceeded {:?}, terminating it", timeout);
+                flag.store(true, Ordering::SeqCst);
+                handle.terminate_execution();
+            }
+        });
+        Self {
+            stop: Some(stop),
+            timer: Some(timer),
+            fired,
+            running,
+        }
+    }
+
+    /// Get the timeout error if execution was terminated
+    fn error(&self) -> Option<RytError> {
+        self.fired
+            .load(Ordering::SeqCst)
+            .then(|| RytError::CipherError("player.js execution timed out".to_string()))
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        // Disconnecting wakes the timer, so the join doesn't wait for the timeout
+        drop(self.stop.take());
+        if let Some(timer) = self.timer.take() {
+            if timer.join().is_err() {
+                warn!("player.js watchdog thread panicked");
+            }
+        }
+    }
+}
+
+/// Run a deciphering attempt inside `span`, recording whether it succeeded
+fn in_attempt_span<T>(
+    span: Span,
+    attempt: impl FnOnce() -> Result<T, RytError>,
+) -> Result<T, RytError> {
+    let _entered = span.enter();
+    let result = attempt();
+    span.record("success", result.is_ok());
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_attempt_spans_record_success() {
+        use crate::platform::testing::Captured;
+        use tracing_subscriber::fmt::format::FmtSpan;
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_max_level(tracing::Level::DEBUG)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let failed: Result<String, RytError> = in_attempt_span(
+                debug_span!("decipher_with_regex", success = field::Empty),
+                || Err(RytError::CipherError("no match".to_string())),
+            );
+            assert!(failed.is_err());
+            let won = in_attempt_span(
+                debug_span!("decipher_with_pattern_fallback", success = field::Empty),
+                || Ok("deciphered".to_string()),
+            );
+            assert_eq!(won.unwrap(), "deciphered");
+        });
+
+        let log = captured.contents();
+        assert!(
+            log.contains("decipher_with_regex{success=false}"),
+            "{}",
+            log
+        );
+        assert!(
+            log.contains("decipher_with_pattern_fallback{success=true}"),
+            "{}",
+            log
+        );
+    }
+
     #[test]
     fn test_cipher_creation() {
         let _cipher = Cipher::new();
@@ -2157,6 +2399,40 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_fetch_player_js_url_behind_consent_page() {
+        use mockito::Matcher;
+
+        let consent_page = r#"<html><body><form action="https://consent.youtube.com/save" method="POST"><input type="hidden" name="continue" value="https://www.youtube.com/watch?v=abc"></form></body></html>"#;
+        let mut server = mockito::Server::new_async().await;
+        let consent = server
+            .mock("GET", "/watch")
+            .match_header("cookie", Matcher::Missing)
+            .with_body(consent_page)
+            .expect(1)
+            .create_async()
+            .await;
+        let watch = server
+            .mock("GET", "/watch")
+            .match_header("cookie", Matcher::Regex("CONSENT=YES".to_string()))
+            .with_body(r#"<script>var cfg = {"jsUrl":"/s/player/abc123/player_ias.vflset/en_US/base.js"};</script>"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let cipher = Cipher::new();
+        let url = cipher
+            .fetch_player_js_url(&format!("{}/watch", server.url()))
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://www.youtube.com/s/player/abc123/player_ias.vflset/en_US/base.js"
+        );
+        consent.assert_async().await;
+        watch.assert_async().await;
+    }
+
     #[tokio::test]
     async fn test_fetch_player_js_invalid_url() {
         let cipher = Cipher::new();
@@ -2186,11 +2462,92 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_clear_caches() {
+    #[tokio::test]
+    async fn test_apply_n_param() {
+        let cipher = Cipher::new();
+        cipher.cache_n_parameter("abc123", "xyz789").await;
+        let video_url = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+
+        let url = "https://rr1.googlevideo.com/videoplayback?id=1&n=abc123&itag=18";
+        assert_eq!(
+            cipher.apply_n_param(url, video_url).await.unwrap(),
+            "https://rr1.googlevideo.com/videoplayback?id=1&n=xyz789&itag=18"
+        );
+
+        // Leading n parameter is detected too
+        let url = "https://rr1.googlevideo.com/videoplayback?n=abc123&id=1";
+        assert_eq!(
+            cipher.apply_n_param(url, video_url).await.unwrap(),
+            "https://rr1.googlevideo.com/videoplayback?n=xyz789&id=1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_n_param_without_n() {
+        let cipher = Cipher::new();
+        let url = "https://rr1.googlevideo.com/videoplayback?id=1&itag=18";
+        assert_eq!(cipher.apply_n_param(url, "invalid_url").await.unwrap(), url);
+        assert_eq!(
+            cipher
+                .apply_n_param("not a url", "invalid_url")
+                .await
+                .unwrap(),
+            "not a url"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clear_caches() {
         let cipher = Cipher::new();
-        // Should not panic
+        cipher
+            .multi_cache
+            .set_signature("cached_sig", "deciphered".to_string())
+            .await;
+        cipher
+            .async_cache
+            .insert("cached_sig".to_string(), "deciphered".to_string())
+            .await;
+        assert_eq!(
+            cipher
+                .decipher_signature("cached_sig", "invalid_url")
+                .await
+                .unwrap(),
+            "deciphered"
+        );
+
         cipher.clear_caches();
+        assert!(cipher
+            .decipher_signature("cached_sig", "invalid_url")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cache_stats_counts_hits_and_misses() {
+        let cipher = Cipher::new();
+        assert_eq!(cipher.cache_stats().signature_hits, 0);
+
+        cipher
+            .multi_cache
+            .set_signature("cached_sig", "deciphered".to_string())
+            .await;
+        cipher
+            .decipher_signature("cached_sig", "invalid_url")
+            .await
+            .unwrap();
+        cipher
+            .decipher_signature("cached_sig", "invalid_url")
+            .await
+            .unwrap();
+        let _ = cipher.decipher_signature("other_sig", "invalid_url").await;
+
+        let _ = cipher.fetch_player_js("invalid_url").await;
+
+        let stats = cipher.cache_stats();
+        assert_eq!(stats.signature_hits, 2);
+        assert_eq!(stats.signature_misses, 1);
+        assert_eq!(stats.player_js_hits, 0);
+        assert_eq!(stats.player_js_misses, 1);
     }
 
     #[test]
@@ -2250,6 +2607,56 @@ mod tests {
         assert!(result2.is_err());
     }
 
+    #[tokio::test]
+    async fn test_execute_full_player_js_times_out() {
+        let cipher = Cipher::new();
+        let started = Instant::now();
+        let err = cipher
+            .execute_full_player_js("abc", "while (true) {}")
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(6));
+        assert_eq!(
+            err.to_string(),
+            "Cipher error: player.js execution timed out"
+        );
+
+        // A short timeout is honored too
+        let cipher = Cipher::new().with_cipher_timeout(Duration::from_millis(100));
+        let started = Instant::now();
+        let err = cipher
+            .execute_full_player_js("abc", "for (;;) { Math.random(); }")
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[test]
+    fn test_watchdog_joins_its_thread() {
+        let mut runtime = JsRuntime::new(RuntimeOptions::default());
+        let watchdog = Watchdog::start(runtime.v8_isolate(), Duration::from_secs(60));
+        let running = Arc::clone(&watchdog.running);
+        assert_eq!(Arc::strong_count(&running), 3);
+
+        // Dropping it ends the timer thread at once, not after the timeout
+        let started = Instant::now();
+        drop(watchdog);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(Arc::strong_count(&running), 1, "watchdog thread leaked");
+    }
+
+    #[tokio::test]
+    async fn test_execute_full_player_js_rejects_oversized_player() {
+        let cipher = Cipher::new();
+        let player_js = " ".repeat(MAX_PLAYER_JS_SIZE + 1);
+        let err = cipher
+            .execute_full_player_js("abc", &player_js)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("too large"));
+    }
+
     #[test]
     fn test_cipher_clone() {
         let _cipher = Cipher::new();
@@ -1,7 +1,14 @@
 //! HTTP client for video platform API requests
 
 use crate::error::RytError;
+use crate::platform::traffic::TrafficLog;
+use reqwest::cookie::Jar;
 use reqwest::{Client, ClientBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
@@ -96,11 +103,96 @@ impl ClientType {
     }
 }
 
+/// Address family used for outgoing connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpFamily {
+    /// Use whatever the system resolver and stack prefer
+    #[default]
+    Any,
+    /// Only connect over IPv4
+    V4,
+    /// Only connect over IPv6
+    V6,
+}
+
+/// HTTP/2 use for a client that isn't forced to HTTP/1.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Http2Mode {
+    /// Negotiate HTTP/2 over TLS when the server offers it
+    #[default]
+    Auto,
+    /// Always speak HTTP/2, without negotiation
+    Force,
+    /// Only speak HTTP/1.1
+    Off,
+}
+
+/// Compression advertised in the `Accept-Encoding` header of API requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AcceptEncoding {
+    /// Accept gzip and brotli
+    #[default]
+    GzipBrotli,
+    /// Only accept gzip
+    Gzip,
+    /// Only accept brotli
+    Brotli,
+    /// Ask for uncompressed responses
+    Identity,
+}
+
+impl AcceptEncoding {
+    /// Get the `Accept-Encoding` header value
+    pub fn header_value(&self) -> &'static str {
+        match self {
+            AcceptEncoding::GzipBrotli => "gzip, br",
+            AcceptEncoding::Gzip => "gzip",
+            AcceptEncoding::Brotli => "br",
+            AcceptEncoding::Identity => "identity",
+        }
+    }
+
+    /// Check if gzip responses are accepted and decoded
+    pub fn gzip(&self) -> bool {
+        matches!(self, AcceptEncoding::GzipBrotli | AcceptEncoding::Gzip)
+    }
+
+    /// Check if brotli responses are accepted and decoded
+    pub fn brotli(&self) -> bool {
+        matches!(self, AcceptEncoding::GzipBrotli | AcceptEncoding::Brotli)
+    }
+}
+
+impl IpFamily {
+    /// Get the unspecified local address that binds sockets to this family
+    pub fn local_address(&self) -> Option<IpAddr> {
+        match self {
+            IpFamily::Any => None,
+            IpFamily::V4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpFamily::V6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+}
+
+/// Default timeout for establishing connections
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default total timeout for API requests
+pub const DEFAULT_API_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connect timeout for the IPv6 attempt before falling back to IPv4
+pub const IPV6_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// HTTP client configuration
 #[derive(Debug, Clone)]
 pub struct HttpClientConfig {
-    /// Request timeout
-    pub timeout: Duration,
+    /// Total request timeout (`None` = unlimited)
+    pub timeout: Option<Duration>,
+    /// Connection establishment timeout
+    pub connect_timeout: Duration,
     /// Maximum retries
     pub max_retries: u32,
     /// User agent string
@@ -115,6 +207,24 @@ pub struct HttpClientConfig {
     pub switching_strategy: ClientSwitchingStrategy,
     /// Force HTTP/1.1 only (disable HTTP/2)
     pub http1_only: bool,
+    /// HTTP/2 use, unless `http1_only` is set
+    pub http2: Http2Mode,
+    /// Compression advertised and decoded for API requests. Media requests
+    /// always ask for `identity`, since compression would break range math.
+    pub accept_encoding: AcceptEncoding,
+    /// Address family for outgoing connections
+    pub ip_family: IpFamily,
+    /// Try IPv6 first and fall back to IPv4 on connection failure
+    /// (media requests with [`IpFamily::Any`] only)
+    pub prefer_ipv6: bool,
+    /// Fixed addresses for hosts, bypassing DNS. The port of each address is
+    /// ignored; connections use the port from the request URL.
+    pub dns_overrides: HashMap<String, Vec<SocketAddr>>,
+    /// Log every request and response (`--print-traffic`)
+    pub traffic_log: Option<TrafficLog>,
+    /// Cookies sent with requests and updated from responses, e.g. from
+    /// `--cookies-from-browser`
+    pub cookie_jar: Option<Arc<Jar>>,
 }
 
 /// Client switching strategy
@@ -141,7 +251,8 @@ impl Default for ClientSwitchingStrategy {
 impl Default for HttpClientConfig {
     fn default() -> Self {
         Self {
-            timeout: Duration::from_secs(30),
+            timeout: Some(DEFAULT_API_TIMEOUT),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
             max_retries: 3,
             user_agent: None,
             proxy_url: None,
@@ -149,13 +260,33 @@ impl Default for HttpClientConfig {
             enable_client_switching: true,
             switching_strategy: ClientSwitchingStrategy::default(),
             http1_only: false, // HTTP/2 by default
+            http2: Http2Mode::Auto,
+            accept_encoding: AcceptEncoding::default(),
+            ip_family: IpFamily::Any,
+            prefer_ipv6: false,
+            dns_overrides: HashMap::new(),
+            traffic_log: None,
+            cookie_jar: None,
         }
     }
 }
 
-/// YouTube HTTP client
+/// IPv6-only and IPv4-only clients for media requests that prefer IPv6
+struct DualStack {
+    ipv6: Client,
+    ipv4: Client,
+    /// Set once an IPv6 connection failed; later requests go straight to IPv4
+    ipv6_failed: AtomicBool,
+}
+
+/// YouTube HTTP client.
+///
+/// Clones share connection pools and the IPv6 fallback state, so a clone
+/// can send requests while the original is locked elsewhere.
+#[derive(Clone)]
 pub struct VideoClient {
     client: Client,
+    dual_stack: Option<Arc<DualStack>>,
     config: HttpClientConfig,
     current_client_index: usize,
     client_switch_count: u32,
@@ -169,36 +300,22 @@ impl VideoClient {
 
     /// Create a new YouTube client with custom configuration
     pub fn with_config(config: HttpClientConfig) -> Self {
-        let mut builder = ClientBuilder::new()
-            .timeout(config.timeout)
-            .gzip(true)
-            .brotli(true);
-
-        // Force HTTP/1.1 if requested (for media downloads, matches Go ytdlp)
-        if config.http1_only {
-            builder = builder.http1_only();
-        }
-
-        // Set user agent
-        if let Some(user_agent) = &config.user_agent {
-            builder = builder.user_agent(user_agent);
-        } else {
-            // Default Android user agent
-            builder = builder
-                .user_agent("com.google.android.youtube/20.10.38 (Linux; U; Android 11) gzip");
-        }
-
-        // Set proxy
-        if let Some(proxy_url) = &config.proxy_url {
-            if let Ok(proxy) = reqwest::Proxy::all(proxy_url) {
-                builder = builder.proxy(proxy);
-            }
-        }
-
-        let client = builder.build().expect("Failed to build HTTP client");
+        let client = build_client(&config, config.ip_family, config.connect_timeout);
+        let dual_stack = (config.prefer_ipv6 && config.ip_family == IpFamily::Any).then(|| {
+            Arc::new(DualStack {
+                ipv6: build_client(
+                    &config,
+                    IpFamily::V6,
+                    config.connect_timeout.min(IPV6_CONNECT_TIMEOUT),
+                ),
+                ipv4: build_client(&config, IpFamily::V4, config.connect_timeout),
+                ipv6_failed: AtomicBool::new(false),
+            })
+        });
 
         Self {
             client,
+            dual_stack,
             config,
             current_client_index: 0,
             client_switch_count: 0,
@@ -224,6 +341,7 @@ impl VideoClient {
         let available_clients = ClientType::all();
         self.current_client_index = (self.current_client_index + 1) % available_clients.len();
         self.client_switch_count += 1;
+        crate::utils::metrics::client_switch();
 
         let new_client_type = available_clients[self.current_client_index];
         self.config.client_type = new_client_type;
@@ -239,6 +357,7 @@ impl VideoClient {
     pub fn switch_to_client(&mut self, client_type: ClientType) {
         self.config.client_type = client_type;
         self.client_switch_count += 1;
+        crate::utils::metrics::client_switch();
 
         // Update index
         let available_clients = ClientType::all();
@@ -267,7 +386,9 @@ impl VideoClient {
             ClientSwitchingStrategy::OnError => {
                 if let Some(err) = error {
                     match err {
-                        RytError::RateLimited | RytError::BotguardError(_) => {
+                        RytError::RateLimited
+                        | RytError::Throttled(_)
+                        | RytError::BotguardError(_) => {
                             // Switch to mobile client for better success rate
                             if self.config.client_type.is_web() {
                                 self.switch_to_client(ClientType::Android);
@@ -357,13 +478,24 @@ impl VideoClient {
         &self.config
     }
 
+    /// Send a request, logging the exchange if traffic logging is configured
+    pub async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        send_request(request, self.config.traffic_log.as_ref()).await
+    }
+
     /// Create a request with common YouTube headers
     pub fn create_request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
         self.client
             .request(method, url)
             .header("Accept", "*/*")
             .header("Accept-Language", "en-US,en;q=0.9")
-            .header("Accept-Encoding", "gzip, deflate, br")
+            .header(
+                "Accept-Encoding",
+                self.config.accept_encoding.header_value(),
+            )
             .header("Connection", "keep-alive")
             .header("Cache-Control", "no-cache")
             .header("DNT", "1")
@@ -389,15 +521,49 @@ impl VideoClient {
         method: reqwest::Method,
         url: &str,
     ) -> reqwest::RequestBuilder {
-        // Use minimal headers for media downloads to avoid 403 errors
-        // Match Go ytdlp exactly: User-Agent, Accept, Accept-Encoding, Connection, Cache-Control
-        self.client
-            .request(method, url)
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/135.0.0.0 Safari/537.36")
-            .header("Accept", "*/*")
-            .header("Accept-Encoding", "identity")
-            .header("Connection", "keep-alive")
-            .header("Cache-Control", "no-cache")
+        simple_media_request(&self.client, method, url)
+    }
+
+    /// Send a simple media request, trying IPv6 before IPv4 if configured.
+    ///
+    /// `customize` adds request specific headers such as `Range`. An IPv6
+    /// connection failure is remembered, so later requests use IPv4 directly.
+    pub async fn send_media_request(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        customize: impl Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let Some(dual_stack) = &self.dual_stack else {
+            return self
+                .send(customize(self.create_simple_media_request(method, url)))
+                .await;
+        };
+
+        if !dual_stack.ipv6_failed.load(Ordering::Relaxed) {
+            let request = simple_media_request(&dual_stack.ipv6, method.clone(), url);
+            match self.send(customize(request)).await {
+                Err(e) if e.is_connect() => {
+                    warn!("IPv6 connection failed ({}), falling back to IPv4", e);
+                    dual_stack.ipv6_failed.store(true, Ordering::Relaxed);
+                }
+                result => return result,
+            }
+        }
+
+        self.send(customize(simple_media_request(
+            &dual_stack.ipv4,
+            method,
+            url,
+        )))
+        .await
+    }
+
+    /// Check if media requests go over IPv4 after an IPv6 failure
+    pub fn ipv6_failed(&self) -> bool {
+        self.dual_stack
+            .as_ref()
+            .is_some_and(|dual_stack| dual_stack.ipv6_failed.load(Ordering::Relaxed))
     }
 
     /// Create a request with realistic browser headers for specific client type
@@ -492,7 +658,7 @@ impl VideoClient {
             .header("User-Agent", user_agent)
             .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7")
             .header("Accept-Language", "en-US,en;q=0.9")
-            .header("Accept-Encoding", "gzip, deflate, br")
+            .header("Accept-Encoding", self.config.accept_encoding.header_value())
             .header("Connection", "keep-alive")
             .header("Cache-Control", "no-cache")
             .header("DNT", "1")
@@ -644,80 +810,84 @@ impl VideoClient {
         request
     }
 
-    /// Execute request with retry logic and client switching
-    pub async fn execute_with_retry<T>(
-        &mut self,
-        request: reqwest::RequestBuilder,
-    ) -> Result<T, RytError>
+    /// Execute an API request with retries, backoff and client switching
+    ///
+    /// `build` creates the request on every attempt, so a retry after a
+    /// client switch carries the new client's headers. 408, 429 and 5xx
+    /// responses are retried, as is 403 when client switching is enabled;
+    /// 404, 401 and other client errors fail right away.
+    pub async fn execute_with_retry<T, F>(&mut self, build: F) -> Result<T, RytError>
     where
         T: serde::de::DeserializeOwned,
+        F: Fn(&VideoClient) -> reqwest::RequestBuilder,
     {
-        let mut last_error = None;
+        let attempts = self.config.max_retries.max(1);
+        let mut last_failure = String::new();
 
-        for attempt in 0..self.config.max_retries {
-            debug!(
-                "HTTP request attempt {}/{}",
-                attempt + 1,
-                self.config.max_retries
-            );
+        for attempt in 1..=attempts {
+            debug!("HTTP request attempt {}/{}", attempt, attempts);
 
-            match request.try_clone().unwrap().send().await {
+            match self.send(build(&*self)).await {
+                Ok(response) if response.status().is_success() => {
+                    debug!("HTTP request successful");
+                    return Ok(response.json().await?);
+                }
                 Ok(response) => {
-                    if response.status().is_success() {
-                        debug!("HTTP request successful");
-                        return Ok(response.json().await?);
-                    } else if response.status() == 403 {
-                        // Check if this is a botguard challenge
-                        let response_text = response.text().await.unwrap_or_default();
-                        if response_text.contains("botguard") || response_text.contains("challenge")
-                        {
-                            warn!("Botguard challenge detected");
-                            let error =
-                                RytError::BotguardError("Botguard challenge detected".to_string());
-                            // Try switching client if enabled
-                            if self.config.enable_client_switching {
-                                self.switch_client_by_strategy(Some(&error));
-                            }
+                    let status = response.status();
+                    let client_switching = self.config.enable_client_switching;
+                    if status == reqwest::StatusCode::NOT_FOUND {
+                        warn!("Video unavailable (404)");
+                        return Err(RytError::VideoUnavailable);
+                    }
+                    if status == reqwest::StatusCode::FORBIDDEN {
+                        let error = forbidden_error(response).await;
+                        if !client_switching {
                             return Err(error);
                         }
-                        warn!("Rate limited (403), switching client");
-                        let error = RytError::RateLimited;
-                        // Try switching client if enabled
-                        if self.config.enable_client_switching {
-                            self.switch_client_by_strategy(Some(&error));
-                        }
-                        return Err(error);
-                    } else if response.status() == 404 {
-                        warn!("Video unavailable (404), switching client");
-                        let error = RytError::VideoUnavailable;
-                        // Try switching client if enabled
-                        if self.config.enable_client_switching {
-                            self.switch_client_by_strategy(Some(&error));
-                        }
-                        return Err(error);
+                        warn!("Request forbidden (403), switching client");
+                        self.switch_client_by_strategy(Some(&error));
+                    } else if is_retryable_status(status, client_switching) {
+                        warn!("HTTP request failed with status: {}", status);
                     } else {
-                        warn!("HTTP request failed with status: {}", response.status());
-                        last_error = Some(RytError::DownloadFailed(reqwest::Error::from(
-                            response.error_for_status().unwrap_err(),
+                        warn!("HTTP request failed with status: {}, not retrying", status);
+                        return Err(RytError::RequestFailed(format!(
+                            "HTTP {} after {} attempt(s)",
+                            status, attempt
                         )));
                     }
+                    last_failure = format!("HTTP {}", status);
                 }
+                // A request that can't be built fails the same way on every attempt
+                Err(e) if e.is_builder() => return Err(RytError::DownloadFailed(e)),
                 Err(e) => {
                     warn!("HTTP request error: {}", e);
-                    last_error = Some(RytError::DownloadFailed(e));
+                    last_failure = e.to_string();
                 }
             }
 
             // Exponential backoff
-            if attempt < self.config.max_retries - 1 {
-                let delay = Duration::from_millis(200 * (1 << attempt));
+            if attempt < attempts {
+                let delay = Duration::from_millis(200 * (1 << (attempt - 1)));
                 debug!("Retrying in {:?}", delay);
                 tokio::time::sleep(delay).await;
             }
         }
 
         error!("All retry attempts failed");
-        Err(last_error.unwrap_or(RytError::Generic("Request failed".to_string())))
+        Err(RytError::RequestFailed(format!(
+            "{} after {} attempt(s)",
+            last_failure, attempts
+        )))
+    }
+
+    /// Fetch an HTML page with realistic headers, getting past the consent interstitial
+    pub async fn fetch_html(&self, url: &str) -> Result<String, RytError> {
+        fetch_html_logged(
+            url,
+            || self.create_realistic_request(reqwest::Method::GET, url),
+            self.config.traffic_log.as_ref(),
+        )
+        .await
     }
 }
 
@@ -727,34 +897,555 @@ impl Default for VideoClient {
     }
 }
 
+/// Check if an API response status is worth retrying
+///
+/// 403 is only retried when client switching is enabled, since the retry then goes
+/// out with another client's headers.
+fn is_retryable_status(status: reqwest::StatusCode, client_switching: bool) -> bool {
+    status == reqwest::StatusCode::REQUEST_TIMEOUT
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+        || (status == reqwest::StatusCode::FORBIDDEN && client_switching)
+}
+
+/// Get the error for a 403 API response, telling Botguard challenges apart
+async fn forbidden_error(response: reqwest::Response) -> RytError {
+    let body = response.text().await.unwrap_or_default();
+    if body.contains("botguard") || body.contains("challenge") {
+        warn!("Botguard challenge detected");
+        RytError::BotguardError("Botguard challenge detected".to_string())
+    } else {
+        RytError::RateLimited
+    }
+}
+
+/// Send a request through `traffic_log` if given
+async fn send_request(
+    request: reqwest::RequestBuilder,
+    traffic_log: Option<&TrafficLog>,
+) -> Result<reqwest::Response, reqwest::Error> {
+    match traffic_log {
+        Some(traffic_log) => traffic_log.send(request).await,
+        None => request.send().await,
+    }
+}
+
+/// Build a reqwest client for `config`, restricted to `ip_family`
+fn build_client(
+    config: &HttpClientConfig,
+    ip_family: IpFamily,
+    connect_timeout: Duration,
+) -> Client {
+    let mut builder = ClientBuilder::new()
+        .connect_timeout(connect_timeout)
+        .gzip(config.accept_encoding.gzip())
+        .brotli(config.accept_encoding.brotli());
+
+    if let Some(timeout) = config.timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    // Force HTTP/1.1 if requested (for media downloads, matches Go ytdlp)
+    if config.http1_only || config.http2 == Http2Mode::Off {
+        builder = builder.http1_only();
+    } else if config.http2 == Http2Mode::Force {
+        builder = builder.http2_prior_knowledge();
+    }
+
+    // Bind to the unspecified address of the family to restrict connections to it
+    if let Some(local_address) = ip_family.local_address() {
+        builder = builder.local_address(local_address);
+    }
+
+    // Pin overridden hosts to fixed addresses instead of resolving them
+    for (host, addrs) in &config.dns_overrides {
+        builder = builder.resolve_to_addrs(host, addrs);
+    }
+
+    // Set user agent
+    if let Some(user_agent) = &config.user_agent {
+        builder = builder.user_agent(user_agent);
+    } else {
+        // Default Android user agent
+        builder =
+            builder.user_agent("com.google.android.youtube/20.10.38 (Linux; U; Android 11) gzip");
+    }
+
+    if let Some(jar) = &config.cookie_jar {
+        builder = builder.cookie_provider(Arc::clone(jar));
+    }
+
+    // Set proxy
+    if let Some(proxy_url) = &config.proxy_url {
+        if let Ok(proxy) = reqwest::Proxy::all(proxy_url) {
+            builder = builder.proxy(proxy);
+        }
+    }
+
+    builder.build().expect("Failed to build HTTP client")
+}
+
+/// User agent of media requests
+pub const MEDIA_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/135.0.0.0 Safari/537.36";
+
+/// Create a request with the minimal headers media servers expect
+fn simple_media_request(
+    client: &Client,
+    method: reqwest::Method,
+    url: &str,
+) -> reqwest::RequestBuilder {
+    // Use minimal headers for media downloads to avoid 403 errors
+    // Match Go ytdlp exactly: User-Agent, Accept, Accept-Encoding, Connection, Cache-Control.
+    // Never ask for compression whatever the client config says: byte
+    // offsets of ranges and resumes refer to the uncompressed media
+    client
+        .request(method, url)
+        .header("User-Agent", MEDIA_USER_AGENT)
+        .header("Accept", "*/*")
+        .header("Accept-Encoding", "identity")
+        .header("Connection", "keep-alive")
+        .header("Cache-Control", "no-cache")
+}
+
+/// Host serving the EU consent (cookie-wall) interstitial
+const CONSENT_HOST: &str = "consent.youtube.com";
+
+/// Cookies that mark the consent interstitial as answered
+pub const CONSENT_COOKIES: &str = "SOCS=CAI; CONSENT=YES+cb";
+
+/// Check if a response is the consent interstitial instead of the requested page
+pub fn is_consent_page(final_url: &str, html: &str) -> bool {
+    let redirected = url::Url::parse(final_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host == CONSENT_HOST))
+        .unwrap_or(false);
+
+    redirected || html.contains("action=\"https://consent.youtube.com/")
+}
+
+/// Fetch an HTML page, getting past the consent interstitial.
+///
+/// `request` builds the request for `url`. When the response turns out to be
+/// the consent page, the request is sent once more with [`CONSENT_COOKIES`];
+/// if that is answered with the consent page too, `ConsentRequired` is returned.
+pub async fn fetch_html_with_consent<F>(url: &str, request: F) -> Result<String, RytError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    fetch_html_logged(url, request, None).await
+}
+
+/// Fetch an HTML page like [`fetch_html_with_consent`], logging the traffic if configured
+async fn fetch_html_logged<F>(
+    url: &str,
+    request: F,
+    traffic_log: Option<&TrafficLog>,
+) -> Result<String, RytError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let (final_url, status, html) = send_for_html(request(), traffic_log).await?;
+    if !is_consent_page(&final_url, &html) {
+        return html_or_status_error(url, status, html);
+    }
+
+    debug!(
+        "Consent interstitial returned for {}, retrying with consent cookies",
+        url
+    );
+    let (final_url, status, html) = send_for_html(
+        request().header(reqwest::header::COOKIE, CONSENT_COOKIES),
+        traffic_log,
+    )
+    .await?;
+    if is_consent_page(&final_url, &html) {
+        warn!("Consent interstitial could not be bypassed for {}", url);
+        return Err(RytError::ConsentRequired(url.to_string()));
+    }
+
+    html_or_status_error(url, status, html)
+}
+
+/// Send a request and read its final URL, status and body
+async fn send_for_html(
+    request: reqwest::RequestBuilder,
+    traffic_log: Option<&TrafficLog>,
+) -> Result<(String, reqwest::StatusCode, String), RytError> {
+    let response = send_request(request, traffic_log).await?;
+    let final_url = response.url().to_string();
+    let status = response.status();
+    let html = response.text().await?;
+    Ok((final_url, status, html))
+}
+
+/// Return the page body, or an error for an unsuccessful status
+fn html_or_status_error(
+    url: &str,
+    status: reqwest::StatusCode,
+    html: String,
+) -> Result<String, RytError> {
+    if status.is_success() {
+        Ok(html)
+    } else {
+        Err(RytError::Generic(format!(
+            "Failed to fetch {}: {}",
+            url, status
+        )))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Trimmed consent.youtube.com interstitial as served to EU visitors
+    const CONSENT_PAGE_FIXTURE: &str = r#"<!DOCTYPE html><html lang="en"><head><title>Before you continue to YouTube</title></head><body><form action="https://consent.youtube.com/save" method="POST"><input type="hidden" name="gl" value="DE"><input type="hidden" name="continue" value="https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;cbrd=1"><input type="hidden" name="set_eom" value="true"><button aria-label="Accept all">Accept all</button></form></body></html>"#;
+
+    async fn consent_server(
+        accepted_body: &str,
+        accept_cookies: bool,
+    ) -> (mockito::ServerGuard, Vec<mockito::Mock>) {
+        use mockito::Matcher;
+
+        let mut server = mockito::Server::new_async().await;
+        let cookie_body = if accept_cookies {
+            accepted_body
+        } else {
+            CONSENT_PAGE_FIXTURE
+        };
+        let mocks = vec![
+            server
+                .mock("GET", "/watch")
+                .match_header("cookie", Matcher::Missing)
+                .with_status(200)
+                .with_body(CONSENT_PAGE_FIXTURE)
+                .expect(1)
+                .create_async()
+                .await,
+            server
+                .mock("GET", "/watch")
+                .match_header("cookie", Matcher::Regex("SOCS=CAI".to_string()))
+                .with_status(200)
+                .with_body(cookie_body)
+                .expect(1)
+                .create_async()
+                .await,
+        ];
+        (server, mocks)
+    }
+
+    #[test]
+    fn test_is_consent_page() {
+        assert!(is_consent_page(
+            "https://www.youtube.com/watch?v=x",
+            CONSENT_PAGE_FIXTURE
+        ));
+        assert!(is_consent_page(
+            "https://consent.youtube.com/m?continue=https%3A%2F%2Fwww.youtube.com",
+            ""
+        ));
+        assert!(!is_consent_page(
+            "https://www.youtube.com/watch?v=x",
+            r#"<html>"jsUrl":"/s/player/abc/base.js"</html>"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_html_retries_with_consent_cookies() {
+        let (server, mocks) = consent_server("<html>watch page</html>", true).await;
+        let url = format!("{}/watch", server.url());
+
+        let html = VideoClient::new().fetch_html(&url).await.unwrap();
+        assert_eq!(html, "<html>watch page</html>");
+        for mock in &mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_html_consent_required() {
+        let (server, mocks) = consent_server("", false).await;
+        let url = format!("{}/watch", server.url());
+
+        let result = VideoClient::new().fetch_html(&url).await;
+        assert!(matches!(result, Err(RytError::ConsentRequired(u)) if u == url));
+        for mock in &mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_html_without_consent_page() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/watch")
+            .with_status(200)
+            .with_body("<html>watch page</html>")
+            .expect(1)
+            .create_async()
+            .await;
+        let url = format!("{}/watch", server.url());
+
+        let html = VideoClient::new().fetch_html(&url).await.unwrap();
+        assert_eq!(html, "<html>watch page</html>");
+        mock.assert_async().await;
+    }
+
+    /// Client for retry tests, starting as a web client that switches to
+    /// Android on errors
+    fn retry_test_client(max_retries: u32, enable_client_switching: bool) -> VideoClient {
+        VideoClient::with_config(HttpClientConfig {
+            max_retries,
+            enable_client_switching,
+            switching_strategy: ClientSwitchingStrategy::OnError,
+            ..HttpClientConfig::default()
+        })
+    }
+
+    async fn post_player(
+        client: &mut VideoClient,
+        url: &str,
+    ) -> Result<serde_json::Value, RytError> {
+        client
+            .execute_with_retry(|client| {
+                client
+                    .create_innertube_request(url)
+                    .json(&serde_json::json!({"videoId": "x"}))
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_dns_override_connects_to_pinned_address() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/page")
+            .match_header(
+                "host",
+                mockito::Matcher::Regex("^ryt-test\\.invalid".to_string()),
+            )
+            .with_status(200)
+            .with_body("pinned")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let addr: SocketAddr = server.host_with_port().parse().unwrap();
+        assert!(addr.ip().is_loopback());
+        let config = HttpClientConfig {
+            dns_overrides: HashMap::from([("ryt-test.invalid".to_string(), vec![addr])]),
+            ..HttpClientConfig::default()
+        };
+        let client = VideoClient::with_config(config);
+        // `.invalid` never resolves, so the response can only come from the pinned address
+        let url = format!("http://ryt-test.invalid:{}/page", addr.port());
+        assert_eq!(client.fetch_html(&url).await.unwrap(), "pinned");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_rebuilds_request_after_client_switch() {
+        let mut server = mockito::Server::new_async().await;
+        let web = server
+            .mock("POST", "/player")
+            .match_query(mockito::Matcher::Any)
+            .match_header("x-youtube-client-name", "1")
+            .with_status(403)
+            .expect(1)
+            .create_async()
+            .await;
+        let android = server
+            .mock("POST", "/player")
+            .match_query(mockito::Matcher::Any)
+            .match_header("x-youtube-client-name", "3")
+            .with_status(200)
+            .with_body(r#"{"ok": true}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut client = retry_test_client(3, true);
+        let url = format!("{}/player", server.url());
+        let response = post_player(&mut client, &url).await.unwrap();
+        assert_eq!(response["ok"], true);
+        assert_eq!(client.current_client_type(), ClientType::Android);
+        web.assert_async().await;
+        android.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_retries_server_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let failing = server
+            .mock("POST", "/player")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let ok = server
+            .mock("POST", "/player")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body("{}")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut client = retry_test_client(3, false);
+        let url = format!("{}/player", server.url());
+        assert!(post_player(&mut client, &url).await.is_ok());
+        failing.assert_async().await;
+        ok.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_reports_last_status_and_attempts() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/player")
+            .match_query(mockito::Matcher::Any)
+            .with_status(429)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut client = retry_test_client(2, true);
+        let url = format!("{}/player", server.url());
+        let err = post_player(&mut client, &url).await.unwrap_err();
+        assert!(matches!(err, RytError::RequestFailed(_)));
+        assert_eq!(
+            err.to_string(),
+            "Request failed: HTTP 429 Too Many Requests after 2 attempt(s)"
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_fatal_statuses() {
+        let mut server = mockito::Server::new_async().await;
+        let not_found = server
+            .mock("POST", "/missing")
+            .match_query(mockito::Matcher::Any)
+            .with_status(404)
+            .expect(1)
+            .create_async()
+            .await;
+        let unauthorized = server
+            .mock("POST", "/private")
+            .match_query(mockito::Matcher::Any)
+            .with_status(401)
+            .expect(1)
+            .create_async()
+            .await;
+        let forbidden = server
+            .mock("POST", "/forbidden")
+            .match_query(mockito::Matcher::Any)
+            .with_status(403)
+            .with_body("please solve the challenge")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut client = retry_test_client(3, true);
+        let url = format!("{}/missing", server.url());
+        assert!(matches!(
+            post_player(&mut client, &url).await,
+            Err(RytError::VideoUnavailable)
+        ));
+        let url = format!("{}/private", server.url());
+        let err = post_player(&mut client, &url).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("HTTP 401 Unauthorized after 1 attempt(s)"));
+
+        // Without client switching a 403 is final
+        let mut client = retry_test_client(3, false);
+        let url = format!("{}/forbidden", server.url());
+        assert!(matches!(
+            post_player(&mut client, &url).await,
+            Err(RytError::BotguardError(_))
+        ));
+        assert_eq!(client.client_switch_count(), 0);
+
+        not_found.assert_async().await;
+        unauthorized.assert_async().await;
+        forbidden.assert_async().await;
+    }
+
+    #[test]
+    fn test_is_retryable_status() {
+        use reqwest::StatusCode;
+
+        for status in [
+            StatusCode::REQUEST_TIMEOUT,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            assert!(is_retryable_status(status, false), "{}", status);
+        }
+        for status in [
+            StatusCode::BAD_REQUEST,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::NOT_FOUND,
+        ] {
+            assert!(!is_retryable_status(status, true), "{}", status);
+        }
+        assert!(is_retryable_status(StatusCode::FORBIDDEN, true));
+        assert!(!is_retryable_status(StatusCode::FORBIDDEN, false));
+    }
+
     #[test]
     fn test_client_creation() {
         let client = VideoClient::new();
-        assert_eq!(client.config().timeout, Duration::from_secs(30));
+        assert_eq!(client.config().timeout, Some(Duration::from_secs(30)));
         assert_eq!(client.config().max_retries, 3);
     }
 
     #[test]
     fn test_client_with_config() {
         let config = HttpClientConfig {
-            timeout: Duration::from_secs(60),
+            timeout: Some(Duration::from_secs(60)),
+            connect_timeout: Duration::from_secs(5),
             max_retries: 5,
             user_agent: Some("Custom Agent".to_string()),
             proxy_url: None,
             client_type: ClientType::Chrome,
             http1_only: false,
+            http2: Http2Mode::Off,
+            accept_encoding: AcceptEncoding::Identity,
             enable_client_switching: true,
             switching_strategy: ClientSwitchingStrategy::Smart,
+            ip_family: IpFamily::V4,
+            prefer_ipv6: false,
+            dns_overrides: HashMap::new(),
+            traffic_log: Some(TrafficLog::new(64)),
+            cookie_jar: None,
         };
 
         let client = VideoClient::with_config(config);
-        assert_eq!(client.config().timeout, Duration::from_secs(60));
+        assert_eq!(client.config().timeout, Some(Duration::from_secs(60)));
+        assert_eq!(client.config().connect_timeout, Duration::from_secs(5));
         assert_eq!(client.config().max_retries, 5);
         assert_eq!(client.config().user_agent, Some("Custom Agent".to_string()));
+        assert_eq!(client.config().ip_family, IpFamily::V4);
+        assert_eq!(client.config().http2, Http2Mode::Off);
+        assert_eq!(client.config().traffic_log, Some(TrafficLog::new(64)));
+    }
+
+    #[test]
+    fn test_ip_family_local_address() {
+        assert_eq!(IpFamily::default(), IpFamily::Any);
+        assert_eq!(IpFamily::Any.local_address(), None);
+        assert_eq!(
+            IpFamily::V4.local_address(),
+            Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+        );
+        assert_eq!(
+            IpFamily::V6.local_address(),
+            Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED))
+        );
     }
 
     #[test]
@@ -828,7 +1519,8 @@ mod tests {
     #[test]
     fn test_http_client_config_default() {
         let config = HttpClientConfig::default();
-        assert_eq!(config.timeout, Duration::from_secs(30));
+        assert_eq!(config.timeout, Some(DEFAULT_API_TIMEOUT));
+        assert_eq!(config.connect_timeout, DEFAULT_CONNECT_TIMEOUT);
         assert_eq!(config.max_retries, 3);
         assert_eq!(config.user_agent, None);
         assert_eq!(config.proxy_url, None);
@@ -836,12 +1528,152 @@ mod tests {
         assert!(config.enable_client_switching);
         assert_eq!(config.switching_strategy, ClientSwitchingStrategy::Smart);
         assert!(!config.http1_only);
+        assert_eq!(config.http2, Http2Mode::Auto);
+        assert_eq!(config.accept_encoding, AcceptEncoding::GzipBrotli);
+        assert_eq!(config.ip_family, IpFamily::Any);
+        assert!(!config.prefer_ipv6);
+    }
+
+    #[tokio::test]
+    async fn test_cookie_jar() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/page")
+            .match_header("cookie", "SID=abc")
+            .with_body("signed in")
+            .create_async()
+            .await;
+
+        let url = format!("{}/page", server.url());
+        let jar = Arc::new(Jar::default());
+        jar.add_cookie_str("SID=abc; Path=/", &url.parse().unwrap());
+        let client = VideoClient::with_config(HttpClientConfig {
+            cookie_jar: Some(jar),
+            ..HttpClientConfig::default()
+        });
+        assert_eq!(client.fetch_html(&url).await.unwrap(), "signed in");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_accept_encoding() {
+        let mut server = mockito::Server::new_async().await;
+        let url = format!("{}/youtubei/v1/player", server.url());
+        let media_url = format!("{}/videoplayback", server.url());
+
+        for (accept_encoding, expected) in [
+            (AcceptEncoding::GzipBrotli, "gzip, br"),
+            (AcceptEncoding::Gzip, "gzip"),
+            (AcceptEncoding::Brotli, "br"),
+            (AcceptEncoding::Identity, "identity"),
+        ] {
+            assert_eq!(accept_encoding.header_value(), expected);
+            let api = server
+                .mock("POST", "/youtubei/v1/player")
+                .match_header("accept-encoding", expected)
+                .expect(2)
+                .create_async()
+                .await;
+            // Media requests never ask for compression
+            let media = server
+                .mock("GET", "/videoplayback")
+                .match_header("accept-encoding", "identity")
+                .expect(2)
+                .create_async()
+                .await;
+
+            let client = VideoClient::with_config(HttpClientConfig {
+                accept_encoding,
+                ..HttpClientConfig::default()
+            });
+            for request in [
+                client.create_request(reqwest::Method::POST, &url),
+                client.create_realistic_request(reqwest::Method::POST, &url),
+            ] {
+                assert_eq!(client.send(request).await.unwrap().status(), 200);
+            }
+            let request = client.create_simple_media_request(reqwest::Method::GET, &media_url);
+            assert_eq!(client.send(request).await.unwrap().status(), 200);
+            let response = client
+                .send_media_request(reqwest::Method::GET, &media_url, |request| {
+                    request.header("Range", "bytes=0-3")
+                })
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200);
+            api.assert_async().await;
+            media.assert_async().await;
+            api.remove_async().await;
+            media.remove_async().await;
+        }
+
+        assert!(AcceptEncoding::GzipBrotli.gzip() && AcceptEncoding::GzipBrotli.brotli());
+        assert!(AcceptEncoding::Gzip.gzip() && !AcceptEncoding::Gzip.brotli());
+        assert!(!AcceptEncoding::Identity.gzip() && !AcceptEncoding::Identity.brotli());
+    }
+
+    #[tokio::test]
+    async fn test_send_media_request_falls_back_to_ipv4() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/videoplayback")
+            .match_header("range", "bytes=0-3")
+            .with_status(206)
+            .with_body("data")
+            .expect(2)
+            .create_async()
+            .await;
+        // The mock server only listens on 127.0.0.1, so IPv6 cannot connect
+        let url = format!("{}/videoplayback", server.url());
+        let client = VideoClient::with_config(HttpClientConfig {
+            prefer_ipv6: true,
+            ..HttpClientConfig::default()
+        });
+        assert!(!client.ipv6_failed());
+
+        for _ in 0..2 {
+            let response = client
+                .send_media_request(reqwest::Method::GET, &url, |request| {
+                    request.header("Range", "bytes=0-3")
+                })
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 206);
+            assert_eq!(response.text().await.unwrap(), "data");
+        }
+        assert!(client.ipv6_failed());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_send_media_request_without_dual_stack() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/videoplayback")
+            .with_status(200)
+            .create_async()
+            .await;
+        let url = format!("{}/videoplayback", server.url());
+
+        // A forced family disables the IPv6 attempt
+        let client = VideoClient::with_config(HttpClientConfig {
+            prefer_ipv6: true,
+            ip_family: IpFamily::V4,
+            ..HttpClientConfig::default()
+        });
+        let response = client
+            .send_media_request(reqwest::Method::GET, &url, |request| request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(!client.ipv6_failed());
+        mock.assert_async().await;
     }
 
     #[test]
     fn test_video_client_default() {
         let client = VideoClient::default();
-        assert_eq!(client.config().timeout, Duration::from_secs(30));
+        assert_eq!(client.config().timeout, Some(Duration::from_secs(30)));
         assert_eq!(client.config().max_retries, 3);
     }
 
@@ -929,6 +1761,14 @@ mod tests {
 
         // Should switch to Android for rate limiting
         assert_eq!(new_type, ClientType::Android);
+
+        // and for throttled media downloads
+        let mut client = VideoClient::with_config(HttpClientConfig {
+            switching_strategy: ClientSwitchingStrategy::OnError,
+            ..HttpClientConfig::default()
+        });
+        let new_type = client.switch_client_by_strategy(Some(&RytError::Throttled(1024)));
+        assert_eq!(new_type, ClientType::Android);
     }
 
     #[test]
@@ -2,17 +2,203 @@
 
 use crate::core::video_info::{Format, FormatSelector, QualitySelector};
 use crate::error::RytError;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Known metadata of an itag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItagInfo {
+    /// Default container (file extension)
+    pub container: &'static str,
+    /// Quality label of video itags
+    pub quality: Option<&'static str>,
+    /// Video codec family
+    pub video_codec: Option<&'static str>,
+    /// Audio codec family
+    pub audio_codec: Option<&'static str>,
+}
 
-/// Select the best format based on selector criteria
-pub fn select_format<'a>(
-    formats: &'a [Format],
-    selector: &FormatSelector,
-) -> Result<&'a Format, RytError> {
+impl ItagInfo {
+    const fn progressive(
+        container: &'static str,
+        quality: &'static str,
+        video_codec: &'static str,
+        audio_codec: &'static str,
+    ) -> Self {
+        Self {
+            container,
+            quality: Some(quality),
+            video_codec: Some(video_codec),
+            audio_codec: Some(audio_codec),
+        }
+    }
+
+    const fn video(container: &'static str, quality: &'static str, codec: &'static str) -> Self {
+        Self {
+            container,
+            quality: Some(quality),
+            video_codec: Some(codec),
+            audio_codec: None,
+        }
+    }
+
+    const fn audio(container: &'static str, codec: &'static str) -> Self {
+        Self {
+            container,
+            quality: None,
+            video_codec: None,
+            audio_codec: Some(codec),
+        }
+    }
+}
+
+/// Static table of well-known itags
+const ITAG_TABLE: &[(u32, ItagInfo)] = &[
+    // Progressive (video+audio)
+    (5, ItagInfo::progressive("flv", "240p", "h263", "mp3")),
+    (17, ItagInfo::progressive("3gp", "144p", "mp4v", "aac")),
+    (18, ItagInfo::progressive("mp4", "360p", "avc1", "aac")),
+    (22, ItagInfo::progressive("mp4", "720p", "avc1", "aac")),
+    (36, ItagInfo::progressive("3gp", "240p", "mp4v", "aac")),
+    (43, ItagInfo::progressive("webm", "360p", "vp8", "vorbis")),
+    // Video-only MP4 (H.264)
+    (133, ItagInfo::video("mp4", "240p", "avc1")),
+    (134, ItagInfo::video("mp4", "360p", "avc1")),
+    (135, ItagInfo::video("mp4", "480p", "avc1")),
+    (136, ItagInfo::video("mp4", "720p", "avc1")),
+    (137, ItagInfo::video("mp4", "1080p", "avc1")),
+    (160, ItagInfo::video("mp4", "144p", "avc1")),
+    (264, ItagInfo::video("mp4", "1440p", "avc1")),
+    (266, ItagInfo::video("mp4", "2160p", "avc1")),
+    (298, ItagInfo::video("mp4", "720p60", "avc1")),
+    (299, ItagInfo::video("mp4", "1080p60", "avc1")),
+    // Video-only WebM (VP9)
+    (242, ItagInfo::video("webm", "240p", "vp9")),
+    (243, ItagInfo::video("webm", "360p", "vp9")),
+    (244, ItagInfo::video("webm", "480p", "vp9")),
+    (247, ItagInfo::video("webm", "720p", "vp9")),
+    (248, ItagInfo::video("webm", "1080p", "vp9")),
+    (271, ItagInfo::video("webm", "1440p", "vp9")),
+    (278, ItagInfo::video("webm", "144p", "vp9")),
+    (313, ItagInfo::video("webm", "2160p", "vp9")),
+    (302, ItagInfo::video("webm", "720p60", "vp9")),
+    (303, ItagInfo::video("webm", "1080p60", "vp9")),
+    (308, ItagInfo::video("webm", "1440p60", "vp9")),
+    (315, ItagInfo::video("webm", "2160p60", "vp9")),
+    // Video-only MP4 (AV1)
+    (394, ItagInfo::video("mp4", "144p", "av01")),
+    (395, ItagInfo::video("mp4", "240p", "av01")),
+    (396, ItagInfo::video("mp4", "360p", "av01")),
+    (397, ItagInfo::video("mp4", "480p", "av01")),
+    (398, ItagInfo::video("mp4", "720p", "av01")),
+    (399, ItagInfo::video("mp4", "1080p", "av01")),
+    (400, ItagInfo::video("mp4", "1440p", "av01")),
+    (401, ItagInfo::video("mp4", "2160p", "av01")),
+    // Audio-only
+    (139, ItagInfo::audio("m4a", "aac")),
+    (140, ItagInfo::audio("m4a", "aac")),
+    (141, ItagInfo::audio("m4a", "aac")),
+    (171, ItagInfo::audio("webm", "vorbis")),
+    (249, ItagInfo::audio("webm", "opus")),
+    (250, ItagInfo::audio("webm", "opus")),
+    (251, ItagInfo::audio("webm", "opus")),
+    (328, ItagInfo::audio("m4a", "ec-3")),
+];
+
+/// YouTube format ID.
+///
+/// Serializes as a plain number. `Display` adds the known metadata, e.g.
+/// `18 (mp4 360p avc1+aac)`; unknown itags render as the bare number.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Itag(pub u32);
+
+impl Itag {
+    /// Get the numeric value
+    pub fn get(self) -> u32 {
+        self.0
+    }
+
+    /// Get known metadata from the static itag table
+    pub fn info(self) -> Option<&'static ItagInfo> {
+        ITAG_TABLE
+            .iter()
+            .find(|(itag, _)| *itag == self.0)
+            .map(|(_, info)| info)
+    }
+
+    /// Check if this is a known progressive (video+audio) itag
+    pub fn is_progressive(self) -> bool {
+        self.info()
+            .is_some_and(|info| info.video_codec.is_some() && info.audio_codec.is_some())
+    }
+
+    /// Get the default container of a known itag
+    pub fn default_container(self) -> Option<&'static str> {
+        self.info().map(|info| info.container)
+    }
+}
+
+impl From<u32> for Itag {
+    fn from(itag: u32) -> Self {
+        Self(itag)
+    }
+}
+
+impl From<Itag> for u32 {
+    fn from(itag: Itag) -> Self {
+        itag.0
+    }
+}
+
+impl fmt::Display for Itag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        let Some(info) = self.info() else {
+            return Ok(());
+        };
+
+        let codecs = match (info.video_codec, info.audio_codec) {
+            (Some(video), Some(audio)) => format!("{}+{}", video, audio),
+            (Some(video), None) => video.to_string(),
+            (None, Some(audio)) => audio.to_string(),
+            (None, None) => String::new(),
+        };
+        let quality = info.quality.unwrap_or("audio");
+        write!(f, " ({} {} {})", info.container, quality, codecs)
+    }
+}
+
+impl FromStr for Itag {
+    type Err = String;
+
+    /// Parse `itag:N`, `itag=N` or a bare `N`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let value = s
+            .strip_prefix("itag:")
+            .or_else(|| s.strip_prefix("itag="))
+            .unwrap_or(s);
+        value
+            .trim()
+            .parse::<u32>()
+            .map(Itag)
+            .map_err(|_| format!("Invalid itag: {}", value))
+    }
+}
+
+/// Get the formats that pass a selector's container, height, frame rate,
+/// codec, language and itag filters, in their original order
+pub fn filter_formats<'a>(formats: &'a [Format], selector: &FormatSelector) -> Vec<&'a Format> {
     let mut candidates: Vec<&Format> = formats.iter().collect();
 
-    // Filter by extension
+    // Filter by container
     if let Some(ext) = &selector.extension {
-        candidates.retain(|f| f.mime_type.contains(ext));
+        candidates.retain(|f| f.matches_extension(ext));
     }
 
     // Filter by height constraints
@@ -36,25 +222,82 @@ pub fn select_format<'a>(
         });
     }
 
+    // Filter by frame rate constraints
+    if let Some(fps_limit) = selector.fps_limit {
+        candidates.retain(|f| f.fps.is_some_and(|fps| fps <= fps_limit));
+    }
+
+    if let Some(fps_min) = selector.fps_min {
+        candidates.retain(|f| f.fps.is_some_and(|fps| fps >= fps_min));
+    }
+
+    // Filter by codecs
+    if let Some(codec) = &selector.video_codec {
+        candidates.retain(|f| {
+            f.video_codec_name()
+                .is_some_and(|c| codec_matches(c, codec))
+        });
+    }
+
+    if let Some(codec) = &selector.audio_codec {
+        candidates.retain(|f| {
+            f.audio_codec_name()
+                .is_some_and(|c| codec_matches(c, codec))
+        });
+    }
+
+    // Keep audio in the preferred language, or the original track without any.
+    // Formats without a track are taken as English; video-only ones are kept
+    if let Some(lang) = &selector.preferred_language {
+        let in_language =
+            |f: &&Format| language_matches(f.language.as_deref().unwrap_or("en"), lang);
+        if candidates
+            .iter()
+            .any(|f| !f.is_video_only() && in_language(f))
+        {
+            candidates.retain(|f| f.is_video_only() || in_language(f));
+        } else if candidates.iter().any(|f| f.audio_is_default) {
+            candidates.retain(|f| f.is_video_only() || f.audio_is_default);
+        }
+    }
+
     // Filter by preferred itag
     if let Some(preferred_itag) = selector.preferred_itag {
         candidates.retain(|f| f.itag == preferred_itag);
     }
 
+    candidates
+}
+
+/// Select the best format based on selector criteria
+pub fn select_format<'a>(
+    formats: &'a [Format],
+    selector: &FormatSelector,
+) -> Result<&'a Format, RytError> {
+    let mut candidates = filter_formats(formats, selector);
+
+    // Prefer WebM formats when there are any
+    if selector.prefer_webm && candidates.iter().any(|f| f.is_webm()) {
+        candidates.retain(|f| f.is_webm());
+    }
+
     if candidates.is_empty() {
         return Err(RytError::NoFormatFound);
     }
 
     // Select by quality criteria
     match &selector.quality {
-        QualitySelector::Best => {
+        QualitySelector::Best | QualitySelector::Best60fps => {
+            if selector.quality == QualitySelector::Best60fps
+                && candidates.iter().any(|f| f.is_60fps())
+            {
+                candidates.retain(|f| f.is_60fps());
+            }
             // Prioritize progressive formats (video+audio combined)
             if let Some(progressive) = candidates.iter().find(|f| f.is_progressive()) {
                 return Ok(progressive);
             }
-            // Then sort by bitrate
-            candidates.sort_by(|a, b| b.bitrate.cmp(&a.bitrate));
-            Ok(candidates.first().unwrap())
+            best_format(candidates.iter().copied()).ok_or(RytError::NoFormatFound)
         }
         QualitySelector::Worst => {
             candidates.sort_by(|a, b| a.bitrate.cmp(&b.bitrate));
@@ -83,7 +326,353 @@ pub fn select_format<'a>(
             .max_by_key(|f| f.bitrate)
             .copied()
             .ok_or(RytError::NoFormatFound),
+        QualitySelector::DolbyAtmos => select_dolby_atmos(&candidates),
+        QualitySelector::BestVideo => {
+            best_video_stream(candidates.iter().copied()).ok_or(RytError::NoFormatFound)
+        }
+        QualitySelector::BestAudio => {
+            best_audio_stream(candidates.iter().copied()).ok_or(RytError::NoFormatFound)
+        }
+    }
+}
+
+/// What to select when a format selector matches nothing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FormatFallback {
+    /// Fail, reporting that no format was found
+    #[default]
+    Error,
+    /// Nearest height below the requested one, or above if none is below
+    ClosestLower,
+    /// Nearest height either way, the lower one on a tie
+    Closest,
+    /// Best format, ignoring height, frame rate and itag constraints
+    Best,
+}
+
+/// How a format was selected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatSelection {
+    /// The selector matched
+    Exact,
+    /// Nothing matched the selector, so a fallback policy picked the format
+    Fallback {
+        /// Requested quality, e.g. `1080p`
+        requested: String,
+        /// Quality of the chosen format, e.g. `720p`
+        chosen: String,
+    },
+}
+
+impl FormatSelection {
+    /// Describe a fallback from `selector` to `chosen`
+    pub fn fallback(selector: &FormatSelector, chosen: &Format) -> Self {
+        let requested = match (requested_height(selector), &selector.quality) {
+            (Some(height), _) => format!("{}p", height),
+            (None, QualitySelector::Itag(itag)) => format!("itag {}", itag),
+            (None, _) => "the requested format".to_string(),
+        };
+        FormatSelection::Fallback {
+            requested,
+            chosen: chosen.quality_string(),
+        }
+    }
+}
+
+impl fmt::Display for FormatSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatSelection::Exact => f.write_str("Exact match"),
+            FormatSelection::Fallback { requested, chosen } => write!(
+                f,
+                "No format matches {}, falling back to {}",
+                requested, chosen
+            ),
+        }
+    }
+}
+
+/// Select a format, falling back per `policy` when the selector matches nothing
+pub fn select_format_with_fallback<'a>(
+    formats: &'a [Format],
+    selector: &FormatSelector,
+    policy: FormatFallback,
+) -> Result<(&'a Format, FormatSelection), RytError> {
+    select_with_fallback(formats, selector, policy, |selector| {
+        select_format(formats, selector)
+    })
+}
+
+/// Select a format with `select`, trying the [`fallback_ladder`] of `policy`
+/// when it finds nothing for `selector`.
+///
+/// This lets callers with their own selection rules, such as file size
+/// limits, share the fallback policy.
+pub fn select_with_fallback<'a>(
+    formats: &[Format],
+    selector: &FormatSelector,
+    policy: FormatFallback,
+    select: impl Fn(&FormatSelector) -> Result<&'a Format, RytError>,
+) -> Result<(&'a Format, FormatSelection), RytError> {
+    match select(selector) {
+        Ok(format) => Ok((format, FormatSelection::Exact)),
+        Err(RytError::NoFormatFound) => fallback_ladder(formats, selector, policy)
+            .iter()
+            .find_map(|relaxed| select(relaxed).ok())
+            .map(|format| (format, FormatSelection::fallback(selector, format)))
+            .ok_or(RytError::NoFormatFound),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get the relaxed selectors to try in order when `selector` matches nothing.
+///
+/// Height-seeking policies try each available height in order of preference,
+/// keeping the other filters; without a requested height they behave like
+/// [`FormatFallback::Best`].
+pub fn fallback_ladder(
+    formats: &[Format],
+    selector: &FormatSelector,
+    policy: FormatFallback,
+) -> Vec<FormatSelector> {
+    let mut relaxed = selector.clone();
+    relaxed.preferred_itag = None;
+    relaxed.height_min = None;
+    relaxed.height_limit = None;
+    relaxed.quality = match &selector.quality {
+        QualitySelector::Itag(_)
+        | QualitySelector::Height(_)
+        | QualitySelector::HeightLessOrEqual(_)
+        | QualitySelector::HeightGreaterOrEqual(_) => QualitySelector::Best,
+        quality => quality.clone(),
+    };
+
+    let requested = requested_height(selector);
+    let best = || {
+        let mut best = relaxed.clone();
+        best.fps_min = None;
+        best.fps_limit = None;
+        vec![best]
+    };
+    let requested = match (policy, requested) {
+        (FormatFallback::Error, _) => return Vec::new(),
+        (FormatFallback::Best, _) | (_, None) => return best(),
+        (_, Some(requested)) => requested,
+    };
+
+    let mut heights: Vec<u32> = formats
+        .iter()
+        .filter_map(|f| f.height)
+        .filter(|height| *height > 0)
+        .collect();
+    heights.sort_unstable();
+    heights.dedup();
+    match policy {
+        FormatFallback::ClosestLower => {
+            // Highest below first, then lowest above
+            heights.sort_by_key(|height| (*height > requested, height.abs_diff(requested)));
+        }
+        _ => heights.sort_by_key(|height| (height.abs_diff(requested), *height)),
+    }
+
+    heights
+        .into_iter()
+        .map(|height| {
+            let mut step = relaxed.clone();
+            step.height_min = Some(height);
+            step.height_limit = Some(height);
+            step
+        })
+        .collect()
+}
+
+/// Get the height a selector asks for, if any
+fn requested_height(selector: &FormatSelector) -> Option<u32> {
+    match selector.quality {
+        QualitySelector::Height(height)
+        | QualitySelector::HeightLessOrEqual(height)
+        | QualitySelector::HeightGreaterOrEqual(height) => Some(height),
+        _ => selector.height_limit.or(selector.height_min),
+    }
+}
+
+/// Get the best format by height, breaking ties by frame rate, HDR and then
+/// bitrate
+pub fn best_format<'a>(formats: impl IntoIterator<Item = &'a Format>) -> Option<&'a Format> {
+    formats
+        .into_iter()
+        .max_by_key(|f| (f.height.unwrap_or(0), f.is_60fps(), f.is_hdr(), f.bitrate))
+}
+
+/// Get the best format at 48 fps or more, or the best of any frame rate when
+/// there is none
+pub fn best_60fps_format<'a>(
+    formats: impl IntoIterator<Item = &'a Format> + Clone,
+) -> Option<&'a Format> {
+    best_format(formats.clone().into_iter().filter(|f| f.is_60fps()))
+        .or_else(|| best_format(formats))
+}
+
+/// Get the best video-only stream, by height and then bitrate
+pub fn best_video_stream<'a>(formats: impl IntoIterator<Item = &'a Format>) -> Option<&'a Format> {
+    formats
+        .into_iter()
+        .filter(|f| f.is_video_only())
+        .max_by_key(|f| (f.height.unwrap_or(0), f.bitrate))
+}
+
+/// Get the best audio-only stream by bitrate
+pub fn best_audio_stream<'a>(formats: impl IntoIterator<Item = &'a Format>) -> Option<&'a Format> {
+    formats
+        .into_iter()
+        .filter(|f| f.is_audio_only())
+        .max_by_key(|f| f.bitrate)
+}
+
+/// Check if a codec such as `avc1.640028` starts with `prefix`, ignoring case
+fn codec_matches(codec: &str, prefix: &str) -> bool {
+    codec
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+/// Check if a language tag such as `en-US` is `wanted`, e.g. `en` or
+/// `en-US`, ignoring case. Subtags left out of `wanted` match any value,
+/// but `en` never matches `eo` or `enm`
+fn language_matches(language: &str, wanted: &str) -> bool {
+    let mut subtags = language.split(['-', '_', '.']);
+    wanted.split(['-', '_']).all(|wanted| {
+        subtags
+            .next()
+            .is_some_and(|subtag| subtag.eq_ignore_ascii_case(wanted))
+    })
+}
+
+/// Parse a yt-dlp style format string into a selector.
+///
+/// Accepts the [`QualitySelector`] forms plus `bestvideo` and `bestaudio`,
+/// filters in brackets, and `video+audio` to merge two streams, e.g.
+/// `bestvideo[height<=1080][ext=mp4]+bestaudio[ext=m4a]` or
+/// `best[height=720][fps>=60]`. Supported filters are `ext`, `vcodec` and
+/// `acodec` with `=` (codecs match by prefix), and `height` and `fps` with
+/// `=`, `<`, `<=`, `>` and `>=`.
+pub fn parse_format_selector(s: &str) -> Result<FormatSelector, RytError> {
+    let mut parts = s.split('+');
+    let video = parse_stream_selector(parts.next().unwrap_or_default())?;
+    match (parts.next(), parts.next()) {
+        (None, _) => Ok(video),
+        (Some(audio), None) => Ok(video.with_audio(parse_stream_selector(audio)?)),
+        (Some(_), Some(_)) => Err(RytError::InvalidFormatSelector(format!(
+            "at most two streams can be merged in '{}'",
+            s
+        ))),
+    }
+}
+
+/// Parse a single stream selector such as `bestaudio[ext=m4a]`
+fn parse_stream_selector(s: &str) -> Result<FormatSelector, RytError> {
+    let s = s.trim();
+    let (name, mut rest) = s.split_at(s.find('[').unwrap_or(s.len()));
+    let name = if name.trim().is_empty() { "best" } else { name };
+    let quality = QualitySelector::from_str(name).map_err(RytError::InvalidFormatSelector)?;
+    let mut selector = FormatSelector::new(quality);
+
+    while let Some(body) = rest.strip_prefix('[') {
+        let (filter, after) = body.split_once(']').ok_or_else(|| {
+            RytError::InvalidFormatSelector(format!("unterminated filter in '{}'", s))
+        })?;
+        apply_format_filter(&mut selector, filter)?;
+        rest = after.trim_start();
+    }
+
+    if !rest.is_empty() {
+        return Err(RytError::InvalidFormatSelector(format!(
+            "unexpected '{}' in '{}'",
+            rest, s
+        )));
     }
+    Ok(selector)
+}
+
+/// Filter comparison operators, longest first so `<=` is not read as `<`
+const FILTER_OPERATORS: &[&str] = &["<=", ">=", "<", ">", "="];
+
+/// Apply a bracketed filter such as `height<=1080` to a selector
+fn apply_format_filter(selector: &mut FormatSelector, filter: &str) -> Result<(), RytError> {
+    let invalid =
+        |reason: String| RytError::InvalidFormatSelector(format!("{} in [{}]", reason, filter));
+
+    let (key, op, value) = FILTER_OPERATORS
+        .iter()
+        .find_map(|op| {
+            filter
+                .split_once(op)
+                .map(|(key, value)| (key.trim(), *op, value.trim()))
+        })
+        .ok_or_else(|| invalid("missing comparison operator".to_string()))?;
+
+    match key {
+        "ext" | "vcodec" | "acodec" => {
+            if op != "=" {
+                return Err(invalid(format!("'{}' only supports '='", key)));
+            }
+            let value = value.to_lowercase();
+            match key {
+                "ext" => selector.extension = Some(value),
+                "vcodec" => selector.video_codec = Some(value),
+                _ => selector.audio_codec = Some(value),
+            }
+        }
+        "height" | "fps" => {
+            let value: u32 = value
+                .parse()
+                .map_err(|_| invalid(format!("invalid number '{}'", value)))?;
+            let (min, max) = match op {
+                "<=" => (None, Some(value)),
+                "<" => (
+                    None,
+                    Some(
+                        value
+                            .checked_sub(1)
+                            .ok_or_else(|| invalid("no value is below 0".to_string()))?,
+                    ),
+                ),
+                ">=" => (Some(value), None),
+                ">" => (Some(value.saturating_add(1)), None),
+                _ => (Some(value), Some(value)),
+            };
+            let (current_min, current_max) = if key == "height" {
+                (&mut selector.height_min, &mut selector.height_limit)
+            } else {
+                (&mut selector.fps_min, &mut selector.fps_limit)
+            };
+            // Several filters on one key narrow the range
+            if let Some(min) = min {
+                *current_min = Some(current_min.map_or(min, |current| current.max(min)));
+            }
+            if let Some(max) = max {
+                *current_max = Some(current_max.map_or(max, |current| current.min(max)));
+            }
+        }
+        _ => {
+            return Err(invalid(format!(
+                "unsupported filter key '{}' (supported: ext, height, fps, vcodec, acodec)",
+                key
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Select the best Dolby Atmos stream out of the candidates
+pub fn select_dolby_atmos<'a>(candidates: &[&'a Format]) -> Result<&'a Format, RytError> {
+    candidates
+        .iter()
+        .filter(|f| f.is_dolby_atmos())
+        .max_by_key(|f| f.bitrate)
+        .copied()
+        .ok_or_else(|| RytError::FormatNotAvailable("Dolby Atmos".to_string()))
 }
 
 /// Get the best progressive format (video+audio combined)
@@ -120,7 +709,10 @@ pub fn get_formats_by_container<'a>(formats: &'a [Format], container: &str) -> V
 
 /// Get formats by quality
 pub fn get_formats_by_quality<'a>(formats: &'a [Format], quality: &str) -> Vec<&'a Format> {
-    formats.iter().filter(|f| f.quality == quality).collect()
+    formats
+        .iter()
+        .filter(|f| f.quality_label() == quality)
+        .collect()
 }
 
 /// Get formats by height range
@@ -155,15 +747,18 @@ pub fn get_formats_by_bitrate_range(
 
 /// Sort formats by quality (best first)
 pub fn sort_formats_by_quality(formats: &mut [Format]) {
-    formats.sort_by(|a, b| {
-        // First by height (if available)
-        match (a.height, b.height) {
-            (Some(a_h), Some(b_h)) => b_h.cmp(&a_h),
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            (None, None) => b.bitrate.cmp(&a.bitrate),
-        }
-    });
+    formats.sort_by(compare_formats_by_quality);
+}
+
+/// Order two formats by quality, best first: by height, then by bitrate for
+/// formats without one
+pub fn compare_formats_by_quality(a: &Format, b: &Format) -> std::cmp::Ordering {
+    match (a.height, b.height) {
+        (Some(a_h), Some(b_h)) => b_h.cmp(&a_h),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.bitrate.cmp(&a.bitrate),
+    }
 }
 
 /// Sort formats by bitrate (highest first)
@@ -222,6 +817,10 @@ pub fn get_format_stats(formats: &[Format]) -> FormatStats {
             stats.audio_only_formats += 1;
         }
 
+        if format.is_60fps() {
+            stats.high_fps_formats += 1;
+        }
+
         if let Some(height) = format.height {
             if height > stats.max_height {
                 stats.max_height = height;
@@ -254,6 +853,7 @@ pub struct FormatStats {
     pub progressive_formats: usize,
     pub video_only_formats: usize,
     pub audio_only_formats: usize,
+    pub high_fps_formats: usize,
     pub total_bitrate: u32,
     pub avg_bitrate: u32,
     pub max_bitrate: u32,
@@ -305,7 +905,7 @@ mod tests {
     fn create_test_formats() -> Vec<Format> {
         vec![
             Format {
-                itag: 22,
+                itag: Itag(22),
                 url: "http://example.com/22".to_string(),
                 quality: "720p".to_string(),
                 mime_type: "video/mp4".to_string(),
@@ -320,10 +920,11 @@ mod tests {
                 audio_sample_rate: Some(44100),
                 audio_channels: Some(2),
                 language: None,
+                audio_is_default: false,
                 note: None,
             },
             Format {
-                itag: 18,
+                itag: Itag(18),
                 url: "http://example.com/18".to_string(),
                 quality: "360p".to_string(),
                 mime_type: "video/mp4".to_string(),
@@ -338,10 +939,11 @@ mod tests {
                 audio_sample_rate: Some(44100),
                 audio_channels: Some(2),
                 language: None,
+                audio_is_default: false,
                 note: None,
             },
             Format {
-                itag: 137,
+                itag: Itag(137),
                 url: "http://example.com/137".to_string(),
                 quality: "1080p".to_string(),
                 mime_type: "video/mp4".to_string(),
@@ -356,6 +958,7 @@ mod tests {
                 audio_sample_rate: None,
                 audio_channels: None,
                 language: None,
+                audio_is_default: false,
                 note: None,
             },
         ]
@@ -367,7 +970,174 @@ mod tests {
         let selector = FormatSelector::new(QualitySelector::Best);
 
         let selected = select_format(&formats, &selector).unwrap();
-        assert_eq!(selected.itag, 22); // Best progressive format
+        assert_eq!(selected.itag, Itag(22)); // Best progressive format
+    }
+
+    fn create_frame_rate_formats() -> Vec<Format> {
+        [
+            (
+                299,
+                "1080p60",
+                "video/mp4; codecs=\"avc1.64002a\"",
+                1080,
+                60,
+                4_000_000,
+            ),
+            (
+                137,
+                "1080p",
+                "video/mp4; codecs=\"avc1.640028\"",
+                1080,
+                30,
+                5_000_000,
+            ),
+            (
+                337,
+                "1080p60 HDR",
+                "video/webm; codecs=\"vp9.2\"",
+                1080,
+                60,
+                3_000_000,
+            ),
+            (
+                298,
+                "720p60",
+                "video/mp4; codecs=\"avc1.4d4020\"",
+                720,
+                60,
+                2_000_000,
+            ),
+            (
+                136,
+                "720p",
+                "video/mp4; codecs=\"avc1.4d401f\"",
+                720,
+                30,
+                9_000_000,
+            ),
+        ]
+        .into_iter()
+        .map(|(itag, quality, mime, height, fps, bitrate)| {
+            let mut format = Format::new(
+                itag,
+                format!("http://example.com/{}", itag),
+                quality.to_string(),
+                mime.to_string(),
+            );
+            format.height = Some(height);
+            format.fps = Some(fps);
+            format.bitrate = bitrate;
+            format
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_select_format_best_tie_break() {
+        let formats = create_frame_rate_formats();
+        let best = FormatSelector::new(QualitySelector::Best);
+
+        // Same height: 60 fps beats 30 fps, then HDR beats SDR, despite bitrate
+        assert_eq!(select_format(&formats, &best).unwrap().itag, Itag(337));
+        assert_eq!(select_format(&formats[..2], &best).unwrap().itag, Itag(299));
+
+        // Height still comes first
+        assert_eq!(select_format(&formats[3..], &best).unwrap().itag, Itag(298));
+        let mut formats = formats;
+        formats[4].height = Some(1440);
+        assert_eq!(select_format(&formats, &best).unwrap().itag, Itag(136));
+    }
+
+    #[test]
+    fn test_select_format_best_60fps() {
+        let mut formats = create_frame_rate_formats();
+        formats[4].height = Some(1440);
+        let selector = FormatSelector::new(QualitySelector::Best60fps);
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(337));
+
+        // Without 60 fps formats, any frame rate will do
+        let selector = selector.with_height_limit(1080);
+        assert_eq!(
+            select_format(&formats[1..2], &selector).unwrap().itag,
+            Itag(137)
+        );
+
+        assert_eq!(best_60fps_format(&formats).unwrap().itag, Itag(337));
+        assert_eq!(best_60fps_format(&formats[4..]).unwrap().itag, Itag(136));
+        assert!(best_60fps_format(&formats[..0]).is_none());
+    }
+
+    #[test]
+    fn test_get_format_stats_high_fps() {
+        let stats = get_format_stats(&create_frame_rate_formats());
+        assert_eq!(stats.total_formats, 5);
+        assert_eq!(stats.high_fps_formats, 3);
+    }
+
+    #[test]
+    fn test_select_format_with_fallback() {
+        let formats = create_test_formats();
+        let select = |selector: &str, policy| {
+            let selector = parse_format_selector(selector).unwrap();
+            select_format_with_fallback(&formats, &selector, policy)
+                .map(|(format, selection)| (format.itag, selection))
+        };
+        let fallback = |requested: &str, chosen: &str| FormatSelection::Fallback {
+            requested: requested.to_string(),
+            chosen: chosen.to_string(),
+        };
+
+        // Exact matches don't fall back
+        assert_eq!(
+            select("720p", FormatFallback::ClosestLower).unwrap(),
+            (Itag(22), FormatSelection::Exact)
+        );
+
+        // The nearest height below wins
+        assert_eq!(
+            select("480p", FormatFallback::ClosestLower).unwrap(),
+            (Itag(18), fallback("480p", "360p"))
+        );
+        assert_eq!(
+            select("best[height=900]", FormatFallback::ClosestLower).unwrap(),
+            (Itag(22), fallback("900p", "720p"))
+        );
+
+        // Nothing below, so the nearest height above
+        assert_eq!(
+            select("240p", FormatFallback::ClosestLower).unwrap(),
+            (Itag(18), fallback("240p", "360p"))
+        );
+
+        // Nearest either way, lower on a tie
+        assert_eq!(
+            select("1000p", FormatFallback::Closest).unwrap().0,
+            Itag(137)
+        );
+        assert_eq!(select("900p", FormatFallback::Closest).unwrap().0, Itag(22));
+
+        assert_eq!(
+            select("480p", FormatFallback::Best).unwrap(),
+            (Itag(22), fallback("480p", "720p"))
+        );
+        assert_eq!(
+            select("itag=999", FormatFallback::ClosestLower).unwrap(),
+            (Itag(22), fallback("itag 999", "720p"))
+        );
+
+        assert_eq!(
+            fallback("1080p", "720p").to_string(),
+            "No format matches 1080p, falling back to 720p"
+        );
+        assert!(matches!(
+            select("480p", FormatFallback::Error),
+            Err(RytError::NoFormatFound)
+        ));
+        // Filters other than height still apply
+        assert!(matches!(
+            select("best[height=480][ext=webm]", FormatFallback::ClosestLower),
+            Err(RytError::NoFormatFound)
+        ));
     }
 
     #[test]
@@ -376,16 +1146,16 @@ mod tests {
         let selector = FormatSelector::new(QualitySelector::Worst);
 
         let selected = select_format(&formats, &selector).unwrap();
-        assert_eq!(selected.itag, 18); // Worst progressive format
+        assert_eq!(selected.itag, Itag(18)); // Worst progressive format
     }
 
     #[test]
     fn test_select_format_itag() {
         let formats = create_test_formats();
-        let selector = FormatSelector::new(QualitySelector::Itag(137));
+        let selector = FormatSelector::new(QualitySelector::Itag(Itag(137)));
 
         let selected = select_format(&formats, &selector).unwrap();
-        assert_eq!(selected.itag, 137);
+        assert_eq!(selected.itag, Itag(137));
     }
 
     #[test]
@@ -401,14 +1171,14 @@ mod tests {
     fn test_get_best_progressive_format() {
         let formats = create_test_formats();
         let best = get_best_progressive_format(&formats).unwrap();
-        assert_eq!(best.itag, 22);
+        assert_eq!(best.itag, Itag(22));
     }
 
     #[test]
     fn test_get_best_video_format() {
         let formats = create_test_formats();
         let best = get_best_video_format(&formats).unwrap();
-        assert_eq!(best.itag, 137);
+        assert_eq!(best.itag, Itag(137));
     }
 
     #[test]
@@ -420,6 +1190,7 @@ mod tests {
         assert_eq!(stats.progressive_formats, 2);
         assert_eq!(stats.video_only_formats, 1);
         assert_eq!(stats.audio_only_formats, 0);
+        assert_eq!(stats.high_fps_formats, 0);
         assert_eq!(stats.max_height, 1080);
         assert_eq!(stats.min_height, 360);
         assert_eq!(stats.max_bitrate, 5000000);
@@ -432,7 +1203,7 @@ mod tests {
         let selector = FormatSelector::new(QualitySelector::Height(720));
 
         let selected = select_format(&formats, &selector).unwrap();
-        assert_eq!(selected.itag, 22);
+        assert_eq!(selected.itag, Itag(22));
         assert_eq!(selected.height, Some(720));
     }
 
@@ -463,6 +1234,105 @@ mod tests {
         assert!(selected.mime_type.contains("mp4"));
     }
 
+    fn create_mixed_container_formats() -> Vec<Format> {
+        let mut formats = Vec::new();
+        for (itag, mime, height, video, audio, bitrate) in [
+            (
+                18,
+                "video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"",
+                Some(360),
+                true,
+                true,
+                500_000,
+            ),
+            (
+                43,
+                "video/webm; codecs=\"vp8.0, vorbis\"",
+                Some(360),
+                true,
+                true,
+                400_000,
+            ),
+            (
+                137,
+                "video/mp4; codecs=\"avc1.640028\"",
+                Some(1080),
+                true,
+                false,
+                4_000_000,
+            ),
+            (
+                248,
+                "video/webm; codecs=\"vp9\"",
+                Some(1080),
+                true,
+                false,
+                3_000_000,
+            ),
+            (
+                140,
+                "audio/mp4; codecs=\"mp4a.40.2\"",
+                None,
+                false,
+                true,
+                9_000_000,
+            ),
+            (
+                251,
+                "audio/webm; codecs=\"opus\"",
+                None,
+                false,
+                true,
+                8_000_000,
+            ),
+        ] {
+            let mut format = Format::new(
+                itag,
+                format!("http://example.com/{}", itag),
+                String::new(),
+                mime.to_string(),
+            );
+            format.height = height;
+            format.bitrate = bitrate;
+            format.video_codec = video.then(|| "video".to_string());
+            format.audio_codec = audio.then(|| "audio".to_string());
+            formats.push(format);
+        }
+        formats
+    }
+
+    #[test]
+    fn test_select_format_ext_mp4_mixed() {
+        let formats = create_mixed_container_formats();
+
+        let selector = FormatSelector::new(QualitySelector::Best).with_extension("mp4");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(18));
+
+        // audio/mp4 never satisfies an mp4 video request
+        let selector = FormatSelector::new(QualitySelector::Worst).with_extension("mp4");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(18));
+
+        let selector = FormatSelector::new(QualitySelector::Itag(Itag(140))).with_extension("mp4");
+        assert!(select_format(&formats, &selector).is_err());
+
+        let selector = FormatSelector::new(QualitySelector::Itag(Itag(140))).with_extension("m4a");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(140));
+    }
+
+    #[test]
+    fn test_select_format_ext_webm_mixed() {
+        let formats = create_mixed_container_formats();
+
+        let selector = FormatSelector::new(QualitySelector::Best).with_extension("webm");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(43));
+
+        let selector = FormatSelector::new(QualitySelector::Height(1080)).with_extension("webm");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(248));
+
+        let selector = FormatSelector::new(QualitySelector::Worst).with_extension("webm");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(43));
+    }
+
     #[test]
     fn test_select_format_with_height_min() {
         let formats = create_test_formats();
@@ -472,31 +1342,332 @@ mod tests {
         assert!(selected.height.unwrap_or(0) >= 720);
     }
 
+    #[test]
+    fn test_select_format_dolby_atmos() {
+        let mut formats = create_test_formats();
+        let selector = FormatSelector::new(QualitySelector::DolbyAtmos);
+        assert!(matches!(
+            select_format(&formats, &selector),
+            Err(RytError::FormatNotAvailable(_))
+        ));
+
+        for (itag, bitrate) in [(328, 384_000), (140, 128_000)] {
+            let mut format = Format::new(
+                itag,
+                format!("http://example.com/{}", itag),
+                String::new(),
+                "audio/mp4".to_string(),
+            );
+            format.bitrate = bitrate;
+            format.audio_codec = Some(if itag == 328 { "ec-3" } else { "mp4a.40.2" }.to_string());
+            formats.push(format);
+        }
+        let selected = select_format(&formats, &selector).unwrap();
+        assert_eq!(selected.itag, Itag(328));
+        assert!(selected.is_dolby_atmos());
+    }
+
+    #[test]
+    fn test_select_format_prefer_webm() {
+        let formats = create_mixed_container_formats();
+
+        let selector = FormatSelector::new(QualitySelector::Height(1080)).with_prefer_webm(true);
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(248));
+
+        let selector = FormatSelector::new(QualitySelector::BestAudio).with_prefer_webm(true);
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(251));
+
+        // Without WebM candidates the preference is ignored
+        let selector = FormatSelector::new(QualitySelector::BestAudio)
+            .with_prefer_webm(true)
+            .with_extension("m4a");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(140));
+    }
+
+    #[test]
+    fn test_select_best_video_and_audio_streams() {
+        let formats = create_mixed_container_formats();
+
+        let selector = FormatSelector::new(QualitySelector::BestVideo);
+        let video = select_format(&formats, &selector).unwrap();
+        assert!(video.is_video_only());
+        assert_eq!(video.height, Some(1080));
+
+        let selector = FormatSelector::new(QualitySelector::BestAudio);
+        assert!(select_format(&formats, &selector).unwrap().is_audio_only());
+    }
+
+    #[test]
+    fn test_parse_format_selector_merge() {
+        let selector = parse_format_selector("bestvideo[ext=webm]+bestaudio[ext=webm]").unwrap();
+        assert_eq!(selector.quality, QualitySelector::BestVideo);
+        assert_eq!(selector.extension.as_deref(), Some("webm"));
+        let audio = selector.audio.as_deref().unwrap();
+        assert_eq!(audio.quality, QualitySelector::BestAudio);
+        assert_eq!(audio.extension.as_deref(), Some("webm"));
+        assert!(audio.audio.is_none());
+
+        let formats = create_mixed_container_formats();
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(248));
+        assert_eq!(select_format(&formats, audio).unwrap().itag, Itag(251));
+    }
+
+    #[test]
+    fn test_parse_format_selector_single() {
+        let selector = parse_format_selector("best").unwrap();
+        assert_eq!(selector.quality, QualitySelector::Best);
+        assert!(selector.extension.is_none());
+        assert!(selector.audio.is_none());
+
+        let selector = parse_format_selector("itag=22").unwrap();
+        assert_eq!(selector.quality, QualitySelector::Itag(Itag(22)));
+
+        let selector = parse_format_selector("[ext=MP4]").unwrap();
+        assert_eq!(selector.quality, QualitySelector::Best);
+        assert_eq!(selector.extension.as_deref(), Some("mp4"));
+
+        let selector = parse_format_selector("bestaudio [ext=m4a]").unwrap();
+        assert_eq!(selector.quality, QualitySelector::BestAudio);
+        assert_eq!(selector.extension.as_deref(), Some("m4a"));
+    }
+
+    #[test]
+    fn test_parse_format_selector_filters() {
+        let selector =
+            parse_format_selector("bestvideo[height<=1080][ext=mp4]+bestaudio[ext=m4a]").unwrap();
+        assert_eq!(selector.quality, QualitySelector::BestVideo);
+        assert_eq!(selector.height_limit, Some(1080));
+        assert_eq!(selector.extension.as_deref(), Some("mp4"));
+        let audio = selector.audio.as_deref().unwrap();
+        assert_eq!(audio.extension.as_deref(), Some("m4a"));
+
+        let formats = create_mixed_container_formats();
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(137));
+        assert_eq!(select_format(&formats, audio).unwrap().itag, Itag(140));
+
+        let selector = parse_format_selector("best[height=720][fps>=60]").unwrap();
+        assert_eq!(selector.height_min, Some(720));
+        assert_eq!(selector.height_limit, Some(720));
+        assert_eq!(selector.fps_min, Some(60));
+        assert_eq!(selector.fps_limit, None);
+
+        // Strict bounds and repeated keys narrow the range
+        let selector = parse_format_selector("best[height>360][height<1080][height<=720]").unwrap();
+        assert_eq!(selector.height_min, Some(361));
+        assert_eq!(selector.height_limit, Some(720));
+
+        let selector = parse_format_selector("worst[vcodec=AVC1]").unwrap();
+        assert_eq!(selector.quality, QualitySelector::Worst);
+        assert_eq!(selector.video_codec.as_deref(), Some("avc1"));
+    }
+
+    #[test]
+    fn test_select_format_fps_and_codec_filters() {
+        let mut formats = create_mixed_container_formats();
+        for format in &mut formats {
+            format.fps = format.height.map(|_| 30);
+        }
+        let mut hfr = Format::new(
+            299,
+            "http://example.com/299".to_string(),
+            String::new(),
+            "video/mp4; codecs=\"avc1.64002a\"".to_string(),
+        );
+        hfr.height = Some(1080);
+        hfr.fps = Some(60);
+        hfr.bitrate = 1_000_000;
+        hfr.video_codec = Some("video".to_string());
+        formats.push(hfr);
+
+        let select = |spec: &str| {
+            select_format(&formats, &parse_format_selector(spec).unwrap()).map(|f| f.itag)
+        };
+        assert_eq!(select("bestvideo[fps>=60]").unwrap(), Itag(299));
+        assert_eq!(select("bestvideo[fps<60]").unwrap(), Itag(137));
+        assert_eq!(select("bestvideo[vcodec=vp9]").unwrap(), Itag(248));
+        assert_eq!(select("worst[vcodec=avc1]").unwrap(), Itag(18));
+        assert_eq!(select("bestaudio[acodec=opus]").unwrap(), Itag(251));
+        assert_eq!(select("bestaudio[acodec=mp4a]").unwrap(), Itag(140));
+        assert!(matches!(
+            select("bestvideo[vcodec=av01]"),
+            Err(RytError::NoFormatFound)
+        ));
+    }
+
+    #[test]
+    fn test_parse_format_selector_errors() {
+        for spec in [
+            "bestvideo[ext=webm",
+            "bestaudio[ext=m4a",
+            "bestvideo[ext=webm]x",
+            "bestvideo+bestaudio+best",
+            "unknown",
+            "best[height]",
+            "best[height<=tall]",
+            "best[height<0]",
+            "best[ext>=mp4]",
+            "best[tbr>1000]",
+        ] {
+            assert!(
+                matches!(
+                    parse_format_selector(spec),
+                    Err(RytError::InvalidFormatSelector(_))
+                ),
+                "{}",
+                spec
+            );
+        }
+
+        let err = parse_format_selector("best[tbr>1000]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid format selector: unsupported filter key 'tbr' (supported: ext, height, \
+             fps, vcodec, acodec) in [tbr>1000]"
+        );
+    }
+
+    #[test]
+    fn test_itag_display() {
+        assert_eq!(Itag(18).to_string(), "18 (mp4 360p avc1+aac)");
+        assert_eq!(Itag(299).to_string(), "299 (mp4 1080p60 avc1)");
+        assert_eq!(Itag(251).to_string(), "251 (webm audio opus)");
+        assert_eq!(Itag(328).to_string(), "328 (m4a audio ec-3)");
+        assert_eq!(Itag(999).to_string(), "999");
+    }
+
+    #[test]
+    fn test_itag_from_str() {
+        assert_eq!("299".parse::<Itag>(), Ok(Itag(299)));
+        assert_eq!("itag:299".parse::<Itag>(), Ok(Itag(299)));
+        assert_eq!("itag=22".parse::<Itag>(), Ok(Itag(22)));
+        assert_eq!(" itag: 18 ".parse::<Itag>(), Ok(Itag(18)));
+        assert!("itag:abc".parse::<Itag>().is_err());
+        assert!("best".parse::<Itag>().is_err());
+        assert!("".parse::<Itag>().is_err());
+    }
+
+    #[test]
+    fn test_itag_helpers() {
+        assert!(Itag(18).is_progressive());
+        assert!(Itag(43).is_progressive());
+        assert!(!Itag(137).is_progressive());
+        assert!(!Itag(140).is_progressive());
+        assert!(!Itag(999).is_progressive());
+
+        assert_eq!(Itag(22).default_container(), Some("mp4"));
+        assert_eq!(Itag(248).default_container(), Some("webm"));
+        assert_eq!(Itag(140).default_container(), Some("m4a"));
+        assert_eq!(Itag(999).default_container(), None);
+        assert_eq!(Itag(140).info().unwrap().audio_codec, Some("aac"));
+    }
+
+    #[test]
+    fn test_itag_conversions_and_serde() {
+        let itag = Itag::from(137u32);
+        assert_eq!(itag, Itag(137));
+        assert_eq!(u32::from(itag), 137);
+        assert_eq!(itag.get(), 137);
+
+        assert_eq!(serde_json::to_string(&itag).unwrap(), "137");
+        assert_eq!(serde_json::from_str::<Itag>("22").unwrap(), Itag(22));
+    }
+
     #[test]
     fn test_select_format_with_preferred_itag() {
         let formats = create_test_formats();
         let selector = FormatSelector::new(QualitySelector::Best).with_itag(18);
 
         let selected = select_format(&formats, &selector).unwrap();
-        assert_eq!(selected.itag, 18);
+        assert_eq!(selected.itag, Itag(18));
     }
 
     #[test]
     fn test_select_format_no_candidates() {
         let formats = create_test_formats();
-        let selector = FormatSelector::new(QualitySelector::Itag(999));
+        let selector = FormatSelector::new(QualitySelector::Itag(Itag(999)));
 
         let result = select_format(&formats, &selector);
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), RytError::NoFormatFound));
     }
 
+    /// Audio-only itag 140 in `language`, plus a video-only stream
+    fn dubbed_formats(tracks: &[(&str, bool)]) -> Vec<Format> {
+        let mut formats: Vec<Format> = tracks
+            .iter()
+            .map(|(language, is_default)| {
+                let mut format = Format::new(
+                    140,
+                    format!("http://example.com/140/{}", language),
+                    "audio".to_string(),
+                    "audio/mp4; codecs=\"mp4a.40.2\"".to_string(),
+                );
+                format.language = Some(language.to_string());
+                format.audio_is_default = *is_default;
+                format
+            })
+            .collect();
+        formats.push(Format::new(
+            137,
+            "http://example.com/137".to_string(),
+            "1080p".to_string(),
+            "video/mp4; codecs=\"avc1.640028\"".to_string(),
+        ));
+        formats
+    }
+
+    #[test]
+    fn test_select_format_preferred_language() {
+        let formats = dubbed_formats(&[("en-US", true), ("de-DE", false), ("fr-FR", false)]);
+        let audio = |lang: &str| {
+            let selector =
+                FormatSelector::new(QualitySelector::BestAudio).with_preferred_language(lang);
+            select_format(&formats, &selector).unwrap().url.clone()
+        };
+        assert_eq!(audio("de"), "http://example.com/140/de-DE");
+        assert_eq!(audio("FR-fr"), "http://example.com/140/fr-FR");
+        // Without the language, the original track is used
+        assert_eq!(audio("ja"), "http://example.com/140/en-US");
+
+        // Video-only streams are unaffected
+        let selector =
+            FormatSelector::new(QualitySelector::BestVideo).with_preferred_language("de");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(137));
+    }
+
+    #[test]
+    fn test_language_matches() {
+        assert!(language_matches("de", "de"));
+        assert!(language_matches("de-DE", "de"));
+        assert!(language_matches("en-US.4", "en-us"));
+        assert!(language_matches("pt_BR", "pt-BR"));
+        assert!(!language_matches("en", "e"));
+        assert!(!language_matches("enm", "en"));
+        assert!(!language_matches("en-GB", "en-US"));
+        assert!(!language_matches("en", "en-US"));
+    }
+
+    #[test]
+    fn test_select_format_preferred_language_defaults() {
+        // Audio without a track counts as English
+        let mut formats = create_test_formats();
+        let selector = FormatSelector::new(QualitySelector::Best).with_preferred_language("en");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(22));
+
+        // With no match and no original track, every format stays a candidate
+        let selector = FormatSelector::new(QualitySelector::Best).with_preferred_language("de");
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(22));
+
+        formats[1].language = Some("de".to_string());
+        assert_eq!(select_format(&formats, &selector).unwrap().itag, Itag(18));
+    }
+
     #[test]
     fn test_get_best_audio_format() {
         let mut formats = create_test_formats();
         // Add an audio-only format
         formats.push(Format {
-            itag: 140,
+            itag: Itag(140),
             url: "http://example.com/140".to_string(),
             quality: "audio".to_string(),
             mime_type: "audio/mp4".to_string(),
@@ -511,11 +1682,12 @@ mod tests {
             audio_sample_rate: Some(44100),
             audio_channels: Some(2),
             language: None,
+            audio_is_default: false,
             note: None,
         });
 
         let best = get_best_audio_format(&formats).unwrap();
-        assert_eq!(best.itag, 140);
+        assert_eq!(best.itag, Itag(140));
     }
 
     #[test]
@@ -530,7 +1702,7 @@ mod tests {
         let formats = create_test_formats();
         let hd_formats = get_formats_by_quality(&formats, "720p");
         assert_eq!(hd_formats.len(), 1);
-        assert_eq!(hd_formats[0].itag, 22);
+        assert_eq!(hd_formats[0].itag, Itag(22));
     }
 
     #[test]
@@ -538,7 +1710,7 @@ mod tests {
         let formats = create_test_formats();
         let medium_formats = get_formats_by_height_range(&formats, 400, 800);
         assert_eq!(medium_formats.len(), 1);
-        assert_eq!(medium_formats[0].itag, 22);
+        assert_eq!(medium_formats[0].itag, Itag(22));
     }
 
     #[test]
@@ -554,9 +1726,9 @@ mod tests {
         sort_formats_by_quality(&mut formats);
 
         // Should be sorted by height (descending)
-        assert_eq!(formats[0].itag, 137); // 1080p
-        assert_eq!(formats[1].itag, 22); // 720p
-        assert_eq!(formats[2].itag, 18); // 360p
+        assert_eq!(formats[0].itag, Itag(137)); // 1080p
+        assert_eq!(formats[1].itag, Itag(22)); // 720p
+        assert_eq!(formats[2].itag, Itag(18)); // 360p
     }
 
     #[test]
@@ -565,9 +1737,9 @@ mod tests {
         sort_formats_by_bitrate(&mut formats);
 
         // Should be sorted by bitrate (descending)
-        assert_eq!(formats[0].itag, 137); // 5000000
-        assert_eq!(formats[1].itag, 22); // 2000000
-        assert_eq!(formats[2].itag, 18); // 1000000
+        assert_eq!(formats[0].itag, Itag(137)); // 5000000
+        assert_eq!(formats[1].itag, Itag(22)); // 2000000
+        assert_eq!(formats[2].itag, Itag(18)); // 1000000
     }
 
     #[test]
@@ -576,9 +1748,9 @@ mod tests {
         sort_formats_by_size(&mut formats);
 
         // Should be sorted by size (descending)
-        assert_eq!(formats[0].itag, 137); // 200000000
-        assert_eq!(formats[1].itag, 22); // 100000000
-        assert_eq!(formats[2].itag, 18); // 50000000
+        assert_eq!(formats[0].itag, Itag(137)); // 200000000
+        assert_eq!(formats[1].itag, Itag(22)); // 100000000
+        assert_eq!(formats[2].itag, Itag(18)); // 50000000
     }
 
     #[test]
@@ -621,6 +1793,7 @@ mod tests {
         assert_eq!(stats.progressive_formats, 0);
         assert_eq!(stats.video_only_formats, 0);
         assert_eq!(stats.audio_only_formats, 0);
+        assert_eq!(stats.high_fps_formats, 0);
         assert_eq!(stats.total_bitrate, 0);
         assert_eq!(stats.avg_bitrate, 0);
         assert_eq!(stats.max_bitrate, 0);
@@ -637,6 +1810,7 @@ mod tests {
             progressive_formats: 0,
             video_only_formats: 0,
             audio_only_formats: 0,
+            high_fps_formats: 0,
             total_bitrate: 0,
             avg_bitrate: 0,
             max_bitrate: 0,
@@ -658,6 +1832,7 @@ mod tests {
         assert_eq!(stats.progressive_formats, 0);
         assert_eq!(stats.video_only_formats, 0);
         assert_eq!(stats.audio_only_formats, 0);
+        assert_eq!(stats.high_fps_formats, 0);
         assert_eq!(stats.total_bitrate, 0);
         assert_eq!(stats.avg_bitrate, 0);
         assert_eq!(stats.max_bitrate, 0);
@@ -677,7 +1852,7 @@ mod tests {
 
         // Test with formats that have no height
         let no_height_formats = vec![Format {
-            itag: 999,
+            itag: Itag(999),
             url: "http://example.com/999".to_string(),
             quality: "unknown".to_string(),
             mime_type: "video/mp4".to_string(),
@@ -692,6 +1867,7 @@ mod tests {
             audio_sample_rate: None,
             audio_channels: None,
             language: None,
+            audio_is_default: false,
             note: None,
         }];
 
@@ -1,12 +1,118 @@
 //! InnerTube API client for video platform
 
-use crate::core::video_info::{Format, PlaylistItem};
+use crate::core::video_info::{Format, PlaylistItem, PlaylistMetadata, SearchResult};
 use crate::error::RytError;
-use crate::platform::client::VideoClient;
+use crate::platform::client::{HttpClientConfig, VideoClient};
+use crate::platform::formats::Itag;
+use crate::utils::cache::MemoryCache;
+use crate::utils::parse_length_seconds;
+use clap::ValueEnum;
 use regex::Regex;
+use reqwest::cookie::CookieStore;
 use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::Duration;
+use tokio::sync::broadcast;
 use tracing::{debug, info, warn};
 
+/// Country geo-blocked player requests are retried from
+pub const GEO_BYPASS_COUNTRY: &str = "US";
+
+/// Origin of InnerTube API requests
+const YOUTUBE_ORIGIN: &str = "https://www.youtube.com";
+
+/// How long a finished player request answers callers of the same request
+const SETTLED_RESULT_TTL: Duration = Duration::from_secs(5);
+
+/// Outcome of a player request, shared with callers waiting on it
+type SharedPlayerResult = Result<PlayerResponse, Arc<RytError>>;
+
+/// Player requests in flight by request key
+type InFlight = Mutex<HashMap<String, broadcast::Sender<SharedPlayerResult>>>;
+
+/// Player requests in flight across all clients, so concurrent requests for
+/// the same video from clients with the same identity share one response
+fn in_flight() -> &'static InFlight {
+    static IN_FLIGHT: OnceLock<InFlight> = OnceLock::new();
+    IN_FLIGHT.get_or_init(Default::default)
+}
+
+/// Results of recently finished player requests by request key, so callers
+/// arriving just after a request finished don't send it again
+fn settled_results() -> &'static MemoryCache<String, SharedPlayerResult> {
+    static SETTLED: OnceLock<MemoryCache<String, SharedPlayerResult>> = OnceLock::new();
+    SETTLED.get_or_init(MemoryCache::new)
+}
+
+/// Keep the result of a finished player request for later callers.
+///
+/// Retryable errors are left out, so a retry sends a new request.
+fn settle(key: &str, result: &SharedPlayerResult) {
+    if result.as_ref().is_err_and(|e| e.is_retryable()) {
+        return;
+    }
+    let settled = settled_results();
+    settled.cleanup_expired();
+    settled.insert(key.to_string(), result.clone(), SETTLED_RESULT_TTL);
+}
+
+/// Role of a caller in a deduplicated player request
+enum Flight {
+    /// Sends the request and broadcasts its result
+    Lead(broadcast::Sender<SharedPlayerResult>, InFlightGuard),
+    /// Waits for the result of the caller sending the request
+    Follow(broadcast::Receiver<SharedPlayerResult>),
+    /// Takes the result of a request that just finished
+    Settled(SharedPlayerResult),
+}
+
+/// Removes an in-flight entry once its request finished or was dropped
+struct InFlightGuard {
+    key: String,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        in_flight()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.key);
+    }
+}
+
+/// Lead the request for `key`, or follow the one already in flight
+fn join_flight(key: &str) -> Flight {
+    let mut in_flight = in_flight().lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(sender) = in_flight.get(key) {
+        return Flight::Follow(sender.subscribe());
+    }
+    // A leader settles its result before leaving, so checking under the
+    // lock catches requests that finished since the caller's fast path
+    if let Some(result) = settled_results().get(&key.to_string()) {
+        return Flight::Settled(result);
+    }
+    let (sender, _) = broadcast::channel(1);
+    in_flight.insert(key.to_string(), sender.clone());
+    Flight::Lead(
+        sender,
+        InFlightGuard {
+            key: key.to_string(),
+        },
+    )
+}
+
+/// Client and country a player request is sent as
+#[derive(Debug, Clone, Copy)]
+struct PlayerProfile<'a> {
+    name: &'a str,
+    version: &'a str,
+    /// Country sent as `context.client.gl`
+    country: Option<&'a str>,
+}
+
 /// InnerTube API client
 pub struct InnerTubeClient {
     http_client: VideoClient,
@@ -14,6 +120,22 @@ pub struct InnerTubeClient {
     client_version: String,
     api_key: Option<String>,
     visitor_id: Option<String>,
+    /// Visitor data captured from the first player or browse response
+    session_visitor_data: Option<String>,
+    /// Host language sent as `context.client.hl`
+    language: Option<String>,
+    /// Country sent as `context.client.gl`
+    geo_country: Option<String>,
+    /// Origin API requests are sent to
+    base_url: String,
+    /// Player responses by video ID, as `(client name, response)` pairs in
+    /// the order they were fetched
+    player_cache: HashMap<String, Vec<(String, PlayerResponse)>>,
+    /// Number of player requests sent
+    player_requests: u64,
+    /// Keys of player requests whose settled results this client used and
+    /// that had not expired when it last looked
+    settled_keys: HashSet<String>,
 }
 
 impl InnerTubeClient {
@@ -25,6 +147,13 @@ impl InnerTubeClient {
             client_version: "20.10.38".to_string(),
             api_key: None,
             visitor_id: None,
+            session_visitor_data: None,
+            language: None,
+            geo_country: None,
+            base_url: YOUTUBE_ORIGIN.to_string(),
+            player_cache: HashMap::new(),
+            player_requests: 0,
+            settled_keys: HashSet::new(),
         }
     }
 
@@ -35,12 +164,117 @@ impl InnerTubeClient {
         self
     }
 
+    /// Set HTTP client configuration (timeouts, proxy, user agent)
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Self {
+        self.http_client = VideoClient::with_config(config);
+        self
+    }
+
+    /// Get HTTP client configuration
+    pub fn http_config(&self) -> &HttpClientConfig {
+        self.http_client.config()
+    }
+
     /// Set visitor ID
     pub fn with_visitor_id(mut self, visitor_id: &str) -> Self {
         self.visitor_id = Some(visitor_id.to_string());
         self
     }
 
+    /// Get the visitor data sent as `X-Goog-Visitor-Id`: the ID set with
+    /// [`with_visitor_id`](Self::with_visitor_id), else the visitor data of
+    /// the first response in this session
+    pub fn visitor_data(&self) -> Option<&str> {
+        self.visitor_id
+            .as_deref()
+            .or(self.session_visitor_data.as_deref())
+    }
+
+    /// Keep the visitor data of a response if none was captured yet
+    fn capture_visitor_data(&mut self, visitor_data: Option<&str>) {
+        if self.session_visitor_data.is_none() {
+            if let Some(visitor_data) = visitor_data.filter(|v| !v.is_empty()) {
+                debug!("Using visitor data from response for later requests");
+                self.session_visitor_data = Some(visitor_data.to_string());
+            }
+        }
+    }
+
+    /// Send API requests to another origin, e.g. a mock server
+    #[cfg(test)]
+    pub(crate) fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set API key, skipping its extraction from YouTube HTML
+    #[cfg(test)]
+    pub(crate) fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Get the number of player requests sent so far
+    pub fn player_request_count(&self) -> u64 {
+        self.player_requests
+    }
+
+    /// Get the first player response cached for a video from any client
+    pub fn cached_player_response(&self, video_id: &str) -> Option<&PlayerResponse> {
+        self.player_cache
+            .get(video_id)
+            .and_then(|responses| responses.first())
+            .map(|(_, response)| response)
+    }
+
+    /// Drop cached player responses, e.g. before resolving media URLs that
+    /// must not be expired
+    pub fn clear_player_cache(&mut self) {
+        self.player_cache.clear();
+        for key in self.settled_keys.drain() {
+            settled_results().remove(&key);
+        }
+    }
+
+    /// Set request locale: host language `hl` (e.g. `en`) and country `gl`
+    /// (e.g. `US`); an empty value leaves the parameter unset
+    pub fn with_locale(mut self, hl: &str, gl: &str) -> Self {
+        self.language = (!hl.is_empty()).then(|| hl.to_string());
+        self.geo_country = (!gl.is_empty()).then(|| gl.to_uppercase());
+        self
+    }
+
+    /// Get country sent with requests
+    pub fn geo_country(&self) -> Option<&str> {
+        self.geo_country.as_deref()
+    }
+
+    /// Add the configured locale to the client context of a request body.
+    ///
+    /// A country already in the body, such as the trending region, is kept.
+    fn apply_locale(&self, body: &mut serde_json::Value) {
+        self.apply_locale_for(body, self.geo_country.as_deref());
+    }
+
+    /// Add the host language and `country` to a request body's client
+    /// context
+    fn apply_locale_for(&self, body: &mut serde_json::Value, country: Option<&str>) {
+        let Some(client) = body
+            .pointer_mut("/context/client")
+            .and_then(serde_json::Value::as_object_mut)
+        else {
+            return;
+        };
+        if let Some(hl) = &self.language {
+            client.insert("hl".to_string(), serde_json::Value::from(hl.as_str()));
+        }
+        if let Some(gl) = country {
+            client
+                .entry("gl")
+                .or_insert_with(|| serde_json::Value::from(gl));
+        }
+    }
+
     /// Switch client for error handling
     pub fn switch_client_for_error(&mut self, error: &RytError) {
         self.http_client.switch_client_by_strategy(Some(error));
@@ -72,18 +306,14 @@ impl InnerTubeClient {
 
             debug!("Trying to extract API key from: {}", source);
 
-            let response = self
-                .http_client
-                .create_realistic_request(reqwest::Method::GET, &source)
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                warn!("Failed to fetch {}: {}", source, response.status());
-                continue;
-            }
-
-            let body = response.text().await?;
+            let body = match self.http_client.fetch_html(&source).await {
+                Ok(body) => body,
+                Err(e @ RytError::ConsentRequired(_)) => return Err(e),
+                Err(e) => {
+                    warn!("{}", e);
+                    continue;
+                }
+            };
 
             // Extract API key if not found yet
             if self.api_key.is_none() {
@@ -111,10 +341,146 @@ impl InnerTubeClient {
         Ok(())
     }
 
-    /// Get player response for a video
+    /// Get player response for a video.
+    ///
+    /// Concurrent calls for the same video, client and locale from any
+    /// InnerTube client with the same cookies, visitor data and proxy share
+    /// a single request and its result. Calls made
+    /// shortly after it finished take the same result without a request,
+    /// unless the error was retryable or the player cache was cleared.
     pub async fn get_player_response(
         &mut self,
         video_id: &str,
+    ) -> Result<PlayerResponse, RytError> {
+        let (name, version) = (self.client_name.clone(), self.client_version.clone());
+        let country = self.geo_country.clone();
+        let profile = PlayerProfile {
+            name: &name,
+            version: &version,
+            country: country.as_deref(),
+        };
+        self.player_response_as(video_id, profile).await
+    }
+
+    /// Get player response for a video as `profile`, sharing requests as
+    /// [`get_player_response`](Self::get_player_response) describes
+    async fn player_response_as(
+        &mut self,
+        video_id: &str,
+        profile: PlayerProfile<'_>,
+    ) -> Result<PlayerResponse, RytError> {
+        let key = format!(
+            "{} {:016x} {} {} {} {} {}",
+            self.base_url,
+            self.identity(),
+            profile.name,
+            profile.version,
+            self.language.as_deref().unwrap_or_default(),
+            profile.country.unwrap_or_default(),
+            video_id
+        );
+        // Forget keys whose results expired, so the set stays small
+        let settled = settled_results();
+        self.settled_keys.retain(|key| settled.contains_key(key));
+        self.settled_keys.insert(key.clone());
+        if let Some(result) = settled_results().get(&key) {
+            debug!("Using settled player request for {}", video_id);
+            return self.take_shared_result(result);
+        }
+        loop {
+            match join_flight(&key) {
+                Flight::Lead(sender, guard) => {
+                    let result = self.fetch_player_response(video_id, profile).await;
+                    let shared = match &result {
+                        Ok(response) => Ok(response.clone()),
+                        Err(e) => Err(Arc::new(e.replicate())),
+                    };
+                    settle(&key, &shared);
+                    drop(guard);
+                    // No receivers just means nobody was waiting
+                    let _ = sender.send(shared);
+                    if let Ok(response) = &result {
+                        self.capture_visitor_data(response.visitor_data());
+                    }
+                    return result;
+                }
+                Flight::Follow(mut receiver) => {
+                    debug!("Waiting for in-flight player request for {}", video_id);
+                    match receiver.recv().await {
+                        Ok(result) => return self.take_shared_result(result),
+                        // The leading caller was dropped before finishing
+                        Err(_) => continue,
+                    }
+                }
+                Flight::Settled(result) => {
+                    debug!("Using settled player request for {}", video_id);
+                    return self.take_shared_result(result);
+                }
+            }
+        }
+    }
+
+    /// Hash of what identifies this client to YouTube: its cookies, visitor
+    /// data and proxy. Player responses depend on them (members-only access,
+    /// age checks, stream URLs bound to a session or IP), so only clients
+    /// that agree on all of them share player requests.
+    fn identity(&self) -> u64 {
+        let config = self.http_client.config();
+        let cookies = config.cookie_jar.as_ref().and_then(|jar| {
+            let url = reqwest::Url::parse(&self.base_url).ok()?;
+            jar.cookies(&url)
+        });
+        let mut hasher = DefaultHasher::new();
+        cookies
+            .as_ref()
+            .map(|cookies| cookies.as_bytes())
+            .hash(&mut hasher);
+        self.visitor_data().hash(&mut hasher);
+        config.proxy_url.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Take the result of a player request another caller sent
+    fn take_shared_result(
+        &mut self,
+        result: SharedPlayerResult,
+    ) -> Result<PlayerResponse, RytError> {
+        match result {
+            Ok(response) => {
+                self.capture_visitor_data(response.visitor_data());
+                Ok(response)
+            }
+            Err(e) => Err(e.replicate()),
+        }
+    }
+
+    /// Send a player request for a video, retrying once with the content
+    /// checks acknowledged if the video is behind a content warning
+    async fn fetch_player_response(
+        &mut self,
+        video_id: &str,
+        profile: PlayerProfile<'_>,
+    ) -> Result<PlayerResponse, RytError> {
+        let response = self.send_player_request(video_id, profile, false).await?;
+        if !response.needs_content_check() {
+            return response.check_playability();
+        }
+        info!(
+            "Video {} is behind a content warning, retrying with content checks",
+            video_id
+        );
+        self.send_player_request(video_id, profile, true)
+            .await?
+            .check_playability()
+    }
+
+    /// Send a single player request, setting `contentCheckOk` and
+    /// `racyCheckOk` if `content_checks` is true
+    async fn send_player_request(
+        &mut self,
+        video_id: &str,
+        profile: PlayerProfile<'_>,
+        content_checks: bool,
     ) -> Result<PlayerResponse, RytError> {
         info!("Fetching player response for video ID: {}", video_id);
 
@@ -122,7 +488,7 @@ impl InnerTubeClient {
         self.ensure_api_key(video_id).await?;
 
         // Build client context based on client type
-        let client_context = if self.client_name == "ANDROID" {
+        let client_context = if profile.name == "ANDROID" {
             serde_json::json!({
                 "clientName": "ANDROID",
                 "clientVersion": "20.10.38",
@@ -133,8 +499,8 @@ impl InnerTubeClient {
             })
         } else {
             serde_json::json!({
-                "clientName": self.client_name,
-                "clientVersion": self.client_version,
+                "clientName": profile.name,
+                "clientVersion": profile.version,
                 "userAgent": "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
                 "mainAppWebInfo": {
                     "graftUrl": format!("https://www.youtube.com/watch?v={}", video_id),
@@ -144,15 +510,20 @@ impl InnerTubeClient {
             })
         };
 
-        let request_body = serde_json::json!({
+        let mut request_body = serde_json::json!({
             "context": {
                 "client": client_context
             },
             "videoId": video_id
         });
+        if content_checks {
+            request_body["contentCheckOk"] = serde_json::Value::Bool(true);
+            request_body["racyCheckOk"] = serde_json::Value::Bool(true);
+        }
+        self.apply_locale_for(&mut request_body, profile.country);
 
         let api_key = self.api_key.as_ref().unwrap();
-        let url = format!("https://www.youtube.com/youtubei/v1/player?key={}", api_key);
+        let url = format!("{}/youtubei/v1/player?key={}", self.base_url, api_key);
 
         debug!("Using API key: {}...", &api_key[..10]);
         debug!("Request URL: {}", url);
@@ -161,73 +532,107 @@ impl InnerTubeClient {
             serde_json::to_string_pretty(&request_body).unwrap_or_default()
         );
 
-        let mut request = self.http_client.create_innertube_request(&url);
-
-        // Add Android-specific headers
-        if self.client_name == "ANDROID" {
-            request = request
-                .header("X-YouTube-Client-Name", "3")
-                .header("X-YouTube-Client-Version", "20.10.38")
-                .header(
-                    "User-Agent",
-                    "com.google.android.youtube/20.10.38 (Linux; U; Android 11) gzip",
-                );
-        }
-
-        if let Some(visitor_id) = &self.visitor_id {
-            request = request.header("x-goog-visitor-id", visitor_id);
-        }
-
+        let is_android = profile.name == "ANDROID";
+        let visitor_id = self.visitor_data().map(str::to_string);
+        self.player_requests += 1;
         let response: PlayerResponse = self
             .http_client
-            .execute_with_retry(request.json(&request_body))
+            .execute_with_retry(|client| {
+                let mut request = client.create_innertube_request(&url);
+
+                // Add Android-specific headers
+                if is_android {
+                    request = request
+                        .header("X-YouTube-Client-Name", "3")
+                        .header("X-YouTube-Client-Version", "20.10.38")
+                        .header(
+                            "User-Agent",
+                            "com.google.android.youtube/20.10.38 (Linux; U; Android 11) gzip",
+                        );
+                }
+
+                if let Some(visitor_id) = &visitor_id {
+                    request = request.header("x-goog-visitor-id", visitor_id);
+                }
+
+                request.json(&request_body)
+            })
             .await?;
 
         debug!("Player response received successfully");
 
-        // Check playability status
-        if let Some(playability_status) = &response.playability_status {
-            match playability_status.status.as_str() {
-                "ERROR" => {
-                    if let Some(reason) = &playability_status.reason {
-                        warn!("Video playability error: {}", reason);
-                        let reason_lower = reason.to_lowercase();
-                        if reason_lower.contains("geograph")
-                            || reason_lower.contains("available in your country")
-                        {
-                            return Err(RytError::GeoBlocked);
-                        }
-                        if reason_lower.contains("rate limit") || reason_lower.contains("quota") {
-                            return Err(RytError::RateLimited);
-                        }
-                        Err(RytError::VideoUnavailable)
-                    } else {
-                        warn!("Video playability error: unknown reason");
-                        Err(RytError::VideoUnavailable)
-                    }
-                }
-                "LOGIN_REQUIRED" => {
-                    warn!("Age restriction detected, this may require client switching");
-                    Err(RytError::AgeRestricted)
-                }
-                "UNPLAYABLE" => {
-                    if let Some(reason) = &playability_status.reason {
-                        let reason_lower = reason.to_lowercase();
-                        if reason_lower.contains("private") {
-                            Err(RytError::Private)
-                        } else {
-                            Err(RytError::VideoUnavailable)
-                        }
-                    } else {
-                        Err(RytError::VideoUnavailable)
-                    }
-                }
-                _ => Ok(response),
+        Ok(response)
+    }
+
+    /// Get player response, retrying once from [`GEO_BYPASS_COUNTRY`] if the
+    /// video is geo-blocked for the configured country
+    pub async fn get_player_response_with_geo_bypass(
+        &mut self,
+        video_id: &str,
+    ) -> Result<PlayerResponse, RytError> {
+        let (name, version) = (self.client_name.clone(), self.client_version.clone());
+        self.player_response_with_geo_bypass(video_id, (&name, &version))
+            .await
+    }
+
+    /// Get player response as `client`, retrying once from
+    /// [`GEO_BYPASS_COUNTRY`] if the video is geo-blocked
+    async fn player_response_with_geo_bypass(
+        &mut self,
+        video_id: &str,
+        client: (&str, &str),
+    ) -> Result<PlayerResponse, RytError> {
+        let country = self.geo_country.clone();
+        let profile = PlayerProfile {
+            name: client.0,
+            version: client.1,
+            country: country.as_deref(),
+        };
+        match self.player_response_as(video_id, profile).await {
+            Err(RytError::GeoBlocked) if profile.country != Some(GEO_BYPASS_COUNTRY) => {
+                warn!(
+                    "Video is geo-blocked, retrying with country {}",
+                    GEO_BYPASS_COUNTRY
+                );
+                let profile = PlayerProfile {
+                    country: Some(GEO_BYPASS_COUNTRY),
+                    ..profile
+                };
+                self.player_response_as(video_id, profile).await
             }
-        } else {
-            // No playability status, assume OK
-            Ok(response)
+            result => result,
+        }
+    }
+
+    /// Get player response as another client, e.g. `("IOS", "19.29.1")`.
+    ///
+    /// The request goes through this client's HTTP client, visitor ID and
+    /// locale, without changing the configured client. Responses are cached
+    /// per video, so asking the same client again doesn't send another
+    /// request.
+    pub async fn get_player_response_as(
+        &mut self,
+        video_id: &str,
+        profile: (&str, &str),
+    ) -> Result<PlayerResponse, RytError> {
+        let (name, _) = profile;
+        let cached = self
+            .player_cache
+            .get(video_id)
+            .and_then(|responses| responses.iter().find(|(client, _)| client == name));
+        if let Some((_, response)) = cached {
+            debug!("Using cached {} player response for {}", name, video_id);
+            return Ok(response.clone());
         }
+
+        let response = self
+            .player_response_with_geo_bypass(video_id, profile)
+            .await?;
+        self.player_cache
+            .entry(video_id.to_string())
+            .or_default()
+            .push((name.to_string(), response.clone()));
+        Ok(response)
     }
 
     /// Get playlist items
@@ -254,17 +659,11 @@ impl InnerTubeClient {
             "params": "6gPTAUNwc0RRUXh4Zz09"
         });
 
-        let mut request = self
-            .http_client
-            .create_innertube_request("https://www.youtube.com/youtubei/v1/browse");
-
-        if let Some(visitor_id) = &self.visitor_id {
-            request = request.header("x-goog-visitor-id", visitor_id);
-        }
-
         let response: BrowseResponse = self
-            .http_client
-            .execute_with_retry(request.json(&request_body))
+            .post_innertube(
+                &format!("{}/youtubei/v1/browse", self.base_url),
+                &request_body,
+            )
             .await?;
 
         // Parse playlist items from response
@@ -300,7 +699,7 @@ impl InnerTubeClient {
                                 .first()
                                 .map(|r| r.text.clone())
                                 .unwrap_or_default(),
-                            duration: video.length_seconds.parse().unwrap_or(0),
+                            duration: parse_length_seconds(&video.length_seconds),
                             index: index as u32,
                             thumbnail: video.thumbnail.thumbnails.first().map(|t| t.url.clone()),
                             description: None,
@@ -319,12 +718,216 @@ impl InnerTubeClient {
         Ok(items)
     }
 
+    /// Get the playlists listed on a channel's Playlists tab
+    ///
+    /// Follows continuation tokens until `limit` playlists are found or the
+    /// tab has no more pages.
+    pub async fn get_channel_playlists(
+        &mut self,
+        channel_id: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<PlaylistMetadata>, RytError> {
+        info!("Fetching playlists of channel {}", channel_id);
+
+        // Channel tabs are only served to web clients
+        let url = format!("{}/youtubei/v1/browse", self.base_url);
+        let mut request_body = serde_json::json!({
+            "context": web_client_context(None),
+            "browseId": channel_id,
+            "params": CHANNEL_PLAYLISTS_PARAMS
+        });
+
+        let mut playlists = Vec::new();
+        loop {
+            let response: ChannelPlaylistsResponse =
+                self.post_innertube(&url, &request_body).await?;
+            let page = response.playlists();
+            let continuation = response.continuation();
+            debug!(
+                "Channel playlists page: {} playlists, more: {}",
+                page.len(),
+                continuation.is_some()
+            );
+            playlists.extend(page);
+
+            if limit.is_some_and(|limit| playlists.len() >= limit) {
+                break;
+            }
+            match continuation {
+                Some(token) => {
+                    request_body = serde_json::json!({
+                        "context": web_client_context(None),
+                        "continuation": token
+                    });
+                }
+                None => break,
+            }
+        }
+
+        if let Some(limit) = limit {
+            playlists.truncate(limit);
+        }
+        Ok(playlists)
+    }
+
+    /// Get a playlist's title, video count and thumbnail
+    pub async fn get_playlist_metadata(
+        &mut self,
+        playlist_id: &str,
+    ) -> Result<PlaylistMetadata, RytError> {
+        info!("Fetching metadata of playlist {}", playlist_id);
+
+        let request_body = serde_json::json!({
+            "context": web_client_context(None),
+            "browseId": format!("VL{}", playlist_id)
+        });
+        let response: PlaylistBrowseResponse = self
+            .post_innertube(
+                &format!("{}/youtubei/v1/browse", self.base_url),
+                &request_body,
+            )
+            .await?;
+
+        Ok(response.to_playlist_metadata(playlist_id))
+    }
+
+    /// Get a video's captions in a language as raw timedtext XML
+    pub async fn get_video_captions(
+        &mut self,
+        video_id: &str,
+        lang: &str,
+    ) -> Result<String, RytError> {
+        self.get_video_captions_as(video_id, lang, CaptionFormat::Srv1)
+            .await
+    }
+
+    /// Get a video's captions in a language and format
+    pub async fn get_video_captions_as(
+        &mut self,
+        video_id: &str,
+        lang: &str,
+        format: CaptionFormat,
+    ) -> Result<String, RytError> {
+        let player_response = self.get_player_response(video_id).await?;
+        let track = player_response
+            .caption_track(lang)
+            .ok_or_else(|| RytError::NoCaptions(lang.to_string()))?;
+        info!(
+            "Fetching {} captions of {} as {}",
+            lang,
+            video_id,
+            format.as_str()
+        );
+
+        self.get_caption_track(track, format).await
+    }
+
+    /// Get the captions of a track, e.g. one of
+    /// [`PlayerResponse::caption_tracks`], in a format
+    pub async fn get_caption_track(
+        &self,
+        track: &CaptionTrack,
+        format: CaptionFormat,
+    ) -> Result<String, RytError> {
+        let request = self
+            .http_client
+            .create_request(reqwest::Method::GET, &track.url(format));
+        let response = self.http_client.send(request).await?.error_for_status()?;
+        Ok(response.text().await?)
+    }
+
+    /// Get trending videos for a region (ISO 3166-1 alpha-2 code, e.g. "US")
+    pub async fn get_trending(
+        &mut self,
+        region: &str,
+        category: TrendingCategory,
+    ) -> Result<Vec<SearchResult>, RytError> {
+        info!(
+            "Fetching trending videos for region {} ({:?})",
+            region, category
+        );
+
+        // The trending feed is only served to web clients
+        let mut request_body = serde_json::json!({
+            "context": web_client_context(Some(region)),
+            "browseId": "FEtrending"
+        });
+        if let Some(params) = category.params() {
+            request_body["params"] = serde_json::Value::from(params);
+        }
+
+        let response: TrendingResponse = self
+            .post_innertube(
+                &format!("{}/youtubei/v1/browse", self.base_url),
+                &request_body,
+            )
+            .await?;
+
+        Ok(response.search_results())
+    }
+
+    /// Get the "Up Next" queue for a video, autoplay entries first
+    pub async fn get_watch_next(&mut self, video_id: &str) -> Result<Vec<SearchResult>, RytError> {
+        info!("Fetching watch next queue for video ID: {}", video_id);
+        Ok(self.get_next_response(video_id).await?.search_results())
+    }
+
+    /// Get the `next` endpoint response for a video, which carries related
+    /// videos, the comment count and the category
+    pub async fn get_next_response(
+        &mut self,
+        video_id: &str,
+    ) -> Result<WatchNextResponse, RytError> {
+        debug!("Fetching next response for video ID: {}", video_id);
+
+        let request_body = serde_json::json!({
+            "context": web_client_context(None),
+            "videoId": video_id
+        });
+
+        self.post_innertube(
+            &format!("{}/youtubei/v1/next", self.base_url),
+            &request_body,
+        )
+        .await
+    }
+
+    /// POST a JSON body to an InnerTube endpoint
+    async fn post_innertube<T>(
+        &mut self,
+        url: &str,
+        body: &serde_json::Value,
+    ) -> Result<T, RytError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let mut body = body.clone();
+        self.apply_locale(&mut body);
+        let body = &body;
+        let visitor_id = self.visitor_data().map(str::to_string);
+        let response: serde_json::Value = self
+            .http_client
+            .execute_with_retry(|client| {
+                let mut request = client.create_innertube_request(url);
+                if let Some(visitor_id) = &visitor_id {
+                    request = request.header("x-goog-visitor-id", visitor_id);
+                }
+                request.json(body)
+            })
+            .await?;
+        self.capture_visitor_data(
+            response
+                .pointer("/responseContext/visitorData")
+                .and_then(serde_json::Value::as_str),
+        );
+        Ok(serde_json::from_value(response)?)
+    }
+
     /// Get visitor ID from YouTube main page
     pub async fn get_visitor_id(&self) -> Result<String, RytError> {
-        let response = self
-            .http_client
-            .create_request(reqwest::Method::GET, "https://www.youtube.com")
-            .send()
+        let client = &self.http_client;
+        let response = client
+            .send(client.create_request(reqwest::Method::GET, "https://www.youtube.com"))
             .await?;
 
         let html = response.text().await?;
@@ -347,16 +950,153 @@ impl InnerTubeClient {
             "Failed to extract visitor ID".to_string(),
         ))
     }
+
+    /// Get the player response embedded in the video's embed page.
+    ///
+    /// Last resort for age-restricted videos when every client asks for a
+    /// login. Fails unless the embedded response carries streaming data.
+    pub async fn get_embed_player_response(
+        &self,
+        video_id: &str,
+    ) -> Result<PlayerResponse, RytError> {
+        let url = format!("https://www.youtube.com/embed/{}", video_id);
+        info!("Trying embed page player response: {}", url);
+
+        let html = self.http_client.fetch_html(&url).await?;
+        let response = extract_initial_player_response(&html)?;
+        if response.streaming_data.is_none() {
+            warn!("Embed page player response has no streaming data");
+            return Err(RytError::AgeRestricted);
+        }
+        Ok(response)
+    }
 }
 
+/// Extract the `ytInitialPlayerResponse` object assigned in a YouTube page
+pub fn extract_initial_player_response(html: &str) -> Result<PlayerResponse, RytError> {
+    let json = extract_json_object(html, "ytInitialPlayerResponse").ok_or_else(|| {
+        RytError::Generic("ytInitialPlayerResponse not found in page".to_string())
+    })?;
+    Ok(serde_json::from_str(json)?)
+}
+
+/// Extract the JSON object assigned to `name` in a page script, e.g.
+/// `var name = {...};` or `window["name"] = {...};`.
+///
+/// The object is delimited by matching braces, skipping braces inside
+/// string literals, so nested objects and strings like `"}"` are handled.
+/// Occurrences of `name` that are not followed by an object assignment are
+/// skipped.
+pub fn extract_json_object<'a>(html: &'a str, name: &str) -> Option<&'a str> {
+    let mut search_from = 0;
+    while let Some(pos) = html[search_from..].find(name) {
+        let after_name = search_from + pos + name.len();
+        search_from = after_name;
+
+        let rest = html[after_name..].trim_start_matches(['"', '\'', ']']);
+        let Some(rest) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        if !rest.starts_with('{') {
+            continue;
+        }
+        if let Some(len) = json_object_len(rest) {
+            return Some(&rest[..len]);
+        }
+    }
+    None
+}
+
+/// Get the byte length of the JSON object at the start of `s`, if complete
+fn json_object_len(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// WEB client context for endpoints that are only served to web clients
+fn web_client_context(region: Option<&str>) -> serde_json::Value {
+    let mut client = serde_json::json!({
+        "clientName": "WEB",
+        "clientVersion": "2.20251002.00.00",
+        "hl": "en"
+    });
+    if let Some(region) = region {
+        client["gl"] = serde_json::Value::from(region.to_uppercase());
+    }
+    serde_json::json!({ "client": client })
+}
+
+/// Browse params selecting a channel's Playlists tab
+const CHANNEL_PLAYLISTS_PARAMS: &str = "Ell4aQ==";
+
 impl Default for InnerTubeClient {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Trending feed category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TrendingCategory {
+    /// Everything trending right now
+    #[default]
+    Now,
+    /// Music charts
+    Music,
+    /// Gaming
+    Gaming,
+    /// Movie trailers
+    Movies,
+    /// Technology
+    Technology,
+}
+
+impl TrendingCategory {
+    /// Get the browse `params` selecting the category's sub-page.
+    ///
+    /// These are base64 protobufs naming the feed corpus (e.g. `ytma_charts`).
+    pub fn params(&self) -> Option<&'static str> {
+        match self {
+            TrendingCategory::Now => None,
+            TrendingCategory::Music => Some("4gINGgt5dG1hX2NoYXJ0cw=="),
+            TrendingCategory::Gaming => Some("4gIcGhpnYW1pbmdfY29ycHVzX21vc3RfcG9wdWxhcg=="),
+            TrendingCategory::Movies => Some("4gIKGgh0cmFpbGVycw=="),
+            TrendingCategory::Technology => {
+                Some("4gIgGh50ZWNobm9sb2d5X2NvcnB1c19tb3N0X3BvcHVsYXI=")
+            }
+        }
+    }
+}
+
 /// Player response from InnerTube API
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct PlayerResponse {
     #[serde(rename = "responseContext")]
     pub response_context: Option<ResponseContext>,
@@ -366,36 +1106,180 @@ pub struct PlayerResponse {
     pub video_details: Option<VideoDetails>,
     #[serde(rename = "streamingData")]
     pub streaming_data: Option<StreamingData>,
+    pub microformat: Option<Microformat>,
+    pub captions: Option<Captions>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ResponseContext {
-    #[serde(rename = "visitorData")]
+impl PlayerResponse {
+    /// Get the visitor data YouTube assigned to this response
+    pub fn visitor_data(&self) -> Option<&str> {
+        self.response_context.as_ref()?.visitor_data.as_deref()
+    }
+
+    /// Get the caption tracks of every language
+    pub fn caption_tracks(&self) -> &[CaptionTrack] {
+        self.captions
+            .as_ref()
+            .and_then(|captions| captions.player_captions_tracklist_renderer.as_ref())
+            .map_or(&[], |tracklist| &tracklist.caption_tracks)
+    }
+
+    /// Get the caption track for a language, preferring uploaded captions
+    /// over automatic ones
+    pub fn caption_track(&self, lang: &str) -> Option<&CaptionTrack> {
+        select_caption_track(self.caption_tracks(), lang)
+    }
+}
+
+/// Pick the caption track for a language from `tracks`, preferring uploaded
+/// captions over automatic ones
+pub fn select_caption_track<'a>(
+    tracks: &'a [CaptionTrack],
+    lang: &str,
+) -> Option<&'a CaptionTrack> {
+    let mut matching = tracks.iter().filter(|t| t.language_code == lang);
+    let first = matching.next()?;
+    if !first.is_automatic() {
+        return Some(first);
+    }
+    Some(matching.find(|t| !t.is_automatic()).unwrap_or(first))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Captions {
+    #[serde(rename = "playerCaptionsTracklistRenderer")]
+    pub player_captions_tracklist_renderer: Option<CaptionTracklist>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaptionTracklist {
+    #[serde(rename = "captionTracks", default)]
+    pub caption_tracks: Vec<CaptionTrack>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaptionTrack {
+    #[serde(rename = "baseUrl")]
+    pub base_url: String,
+    #[serde(rename = "languageCode")]
+    pub language_code: String,
+    /// `asr` for automatic captions
+    pub kind: Option<String>,
+}
+
+impl CaptionTrack {
+    /// Check whether the captions were generated by speech recognition
+    pub fn is_automatic(&self) -> bool {
+        self.kind.as_deref() == Some("asr")
+    }
+
+    /// Get the timedtext URL for a caption format
+    pub fn url(&self, format: CaptionFormat) -> String {
+        format!("{}&fmt={}", self.base_url, format.as_str())
+    }
+}
+
+/// Format of a timedtext caption download
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptionFormat {
+    /// WebVTT, with per-word timing for automatic captions
+    Vtt,
+    /// YouTube's raw XML format
+    Srv1,
+}
+
+impl CaptionFormat {
+    /// Get the `fmt` parameter value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptionFormat::Vtt => "vtt",
+            CaptionFormat::Srv1 => "srv1",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResponseContext {
+    #[serde(rename = "visitorData")]
     pub visitor_data: Option<String>,
     #[serde(rename = "serviceTrackingParams")]
     pub service_tracking_params: Option<Vec<ServiceTrackingParam>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ServiceTrackingParam {
     pub service: String,
     #[serde(rename = "params")]
     pub params: Vec<Param>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Param {
     pub key: String,
     pub value: String,
 }
 
-#[derive(Debug, Deserialize)]
+/// Check if a lowercased playability reason is a copyright or legal
+/// takedown. Unlike geo-blocking these hold for every client and country.
+fn is_takedown_reason(reason_lower: &str) -> bool {
+    reason_lower.contains("who has blocked it")
+        || reason_lower.contains("copyright")
+        || reason_lower.contains("legal complaint")
+}
+
+/// Check if a lowercased playability reason is a content warning that
+/// `contentCheckOk`/`racyCheckOk` lets through
+fn is_content_check_reason(reason_lower: &str) -> bool {
+    reason_lower.contains("confirm your age")
+        || reason_lower.contains("inappropriate")
+        || reason_lower.contains("offensive")
+        || reason_lower.contains("self-harm")
+}
+
+/// Check if a lowercased playability reason says the video is for
+/// channel members only
+fn is_members_only_reason(reason_lower: &str) -> bool {
+    reason_lower.contains("join this channel") || reason_lower.contains("members-only")
+}
+
+/// Check if a lowercased playability reason says the video has to be
+/// rented or bought
+fn is_payment_reason(reason_lower: &str) -> bool {
+    reason_lower.contains("requires payment")
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct PlayabilityStatus {
     pub status: String,
     pub reason: Option<String>,
+    #[serde(rename = "errorScreen")]
+    pub error_screen: Option<ErrorScreen>,
 }
 
-#[derive(Debug, Deserialize)]
+impl PlayabilityStatus {
+    /// Get the offer of a video that has to be rented or bought
+    pub fn ypc_offer(&self) -> Option<&YpcOfferRenderer> {
+        self.error_screen.as_ref()?.ypc_offer.as_ref()
+    }
+}
+
+/// Screen shown instead of the player
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorScreen {
+    #[serde(rename = "playerLegacyDesktopYpcOfferRenderer")]
+    pub ypc_offer: Option<YpcOfferRenderer>,
+}
+
+/// Rental or purchase offer for a paid video
+#[derive(Debug, Clone, Deserialize)]
+pub struct YpcOfferRenderer {
+    #[serde(rename = "itemTitle")]
+    pub item_title: Option<String>,
+    #[serde(rename = "offerDescription")]
+    pub offer_description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct VideoDetails {
     #[serde(rename = "videoId")]
     pub video_id: String,
@@ -406,30 +1290,83 @@ pub struct VideoDetails {
     #[serde(rename = "shortDescription")]
     pub short_description: String,
     pub thumbnail: Thumbnail,
+    #[serde(rename = "isLiveContent", default)]
+    pub is_live_content: bool,
+    #[serde(rename = "isLive", default)]
+    pub is_live: bool,
+    #[serde(rename = "isUpcoming", default)]
+    pub is_upcoming: bool,
+    #[serde(rename = "viewCount", default)]
+    pub view_count: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Microformat {
+    #[serde(rename = "playerMicroformatRenderer")]
+    pub player_microformat_renderer: Option<PlayerMicroformatRenderer>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlayerMicroformatRenderer {
+    pub title: Option<TextContent>,
+    pub description: Option<TextContent>,
+    #[serde(rename = "ownerChannelName")]
+    pub owner_channel_name: Option<String>,
+    #[serde(rename = "lengthSeconds")]
+    pub length_seconds: Option<String>,
+    pub thumbnail: Option<Thumbnail>,
+    #[serde(rename = "viewCount")]
+    pub view_count: Option<String>,
+    #[serde(rename = "liveBroadcastDetails")]
+    pub live_broadcast_details: Option<LiveBroadcastDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LiveBroadcastDetails {
+    #[serde(rename = "isLiveNow", default)]
+    pub is_live_now: bool,
+    #[serde(rename = "startTimestamp")]
+    pub start_timestamp: Option<String>,
+    #[serde(rename = "endTimestamp")]
+    pub end_timestamp: Option<String>,
+}
+
+/// Live stream status of a video
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiveStatus {
+    /// Currently streaming live
+    pub is_live: bool,
+    /// Finished live stream (VOD of a live stream)
+    pub was_live: bool,
+    /// Scheduled live stream or premiere that has not started
+    pub is_upcoming: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Thumbnail {
     pub thumbnails: Vec<ThumbnailInfo>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ThumbnailInfo {
     pub url: String,
     pub width: u32,
     pub height: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct StreamingData {
     pub formats: Option<Vec<FormatData>>,
     #[serde(rename = "adaptiveFormats")]
     pub adaptive_formats: Option<Vec<FormatData>>,
+    /// HLS master playlist of live streams
+    #[serde(rename = "hlsManifestUrl")]
+    pub hls_manifest_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct FormatData {
-    pub itag: u32,
+    pub itag: Itag,
     pub url: Option<String>,
     #[serde(rename = "mimeType")]
     pub mime_type: String,
@@ -451,9 +1388,184 @@ pub struct FormatData {
     pub audio_sample_rate: Option<serde_json::Value>,
     #[serde(rename = "audioChannels")]
     pub audio_channels: Option<serde_json::Value>,
+    #[serde(rename = "audioTrack")]
+    pub audio_track: Option<AudioTrack>,
+}
+
+/// Audio track of a format, for videos with several dubbed tracks
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioTrack {
+    /// Language code and track number, e.g. `de-DE.3`
+    pub id: String,
+    /// Track name as shown in the player, e.g. `German (Germany)`
+    #[serde(default)]
+    pub display_name: String,
+    /// Whether this is the video's original track
+    #[serde(default)]
+    pub audio_is_default: bool,
+}
+
+impl AudioTrack {
+    /// Get the language code from the track ID, e.g. `de-DE`
+    pub fn language_code(&self) -> &str {
+        self.id.split('.').next().unwrap_or_default()
+    }
 }
 
 impl PlayerResponse {
+    /// Turn an unplayable status into the matching error
+    pub fn check_playability(self) -> Result<Self, RytError> {
+        let Some(playability_status) = &self.playability_status else {
+            // No playability status, assume OK
+            return Ok(self);
+        };
+        let reason = playability_status.reason.as_deref();
+        match playability_status.status.as_str() {
+            "ERROR" => {
+                let Some(reason) = reason else {
+                    warn!("Video playability error: unknown reason");
+                    return Err(RytError::VideoUnavailable);
+                };
+                warn!("Video playability error: {}", reason);
+                let reason_lower = reason.to_lowercase();
+                if is_takedown_reason(&reason_lower) {
+                    return Err(RytError::Blocked {
+                        reason: reason.to_string(),
+                    });
+                }
+                if reason_lower.contains("geograph")
+                    || reason_lower.contains("available in your country")
+                {
+                    return Err(RytError::GeoBlocked);
+                }
+                if reason_lower.contains("rate limit") || reason_lower.contains("quota") {
+                    return Err(RytError::RateLimited);
+                }
+                Err(RytError::VideoUnavailable)
+            }
+            "LOGIN_REQUIRED" => {
+                warn!("Age restriction detected, this may require client switching");
+                Err(RytError::AgeRestricted)
+            }
+            // Only these specific reasons, which YouTube gives when the
+            // session has no access, so signed-in members aren't affected
+            "UNPLAYABLE" if playability_status.ypc_offer().is_some() => {
+                Err(RytError::PaymentRequired {
+                    offer_text: playability_status
+                        .ypc_offer()
+                        .and_then(|offer| offer.offer_description.clone()),
+                })
+            }
+            "UNPLAYABLE" => match reason {
+                Some(reason) if is_members_only_reason(&reason.to_lowercase()) => {
+                    Err(RytError::MembersOnly)
+                }
+                Some(reason) if is_payment_reason(&reason.to_lowercase()) => {
+                    Err(RytError::PaymentRequired { offer_text: None })
+                }
+                Some(reason) if is_takedown_reason(&reason.to_lowercase()) => {
+                    Err(RytError::Blocked {
+                        reason: reason.to_string(),
+                    })
+                }
+                Some(reason) if reason.to_lowercase().contains("private") => Err(RytError::Private),
+                Some(reason) if is_content_check_reason(&reason.to_lowercase()) => {
+                    warn!("Video is behind a content warning: {}", reason);
+                    Err(RytError::AgeRestricted)
+                }
+                _ => Err(RytError::VideoUnavailable),
+            },
+            _ => Ok(self),
+        }
+    }
+
+    /// Check if the video is unplayable only until its content warning is
+    /// acknowledged
+    pub fn needs_content_check(&self) -> bool {
+        self.playability_status.as_ref().is_some_and(|status| {
+            status.status == "UNPLAYABLE"
+                && status
+                    .reason
+                    .as_deref()
+                    .is_some_and(|reason| is_content_check_reason(&reason.to_lowercase()))
+        })
+    }
+
+    /// Get the HLS master playlist URL, given for live streams
+    pub fn hls_manifest_url(&self) -> Option<&str> {
+        self.streaming_data
+            .as_ref()
+            .and_then(|data| data.hls_manifest_url.as_deref())
+    }
+
+    /// Get live stream status from videoDetails and microformat
+    pub fn live_status(&self) -> LiveStatus {
+        let details = self.video_details.as_ref();
+        let broadcast = self
+            .microformat
+            .as_ref()
+            .and_then(|m| m.player_microformat_renderer.as_ref())
+            .and_then(|r| r.live_broadcast_details.as_ref());
+
+        let is_upcoming = details.map_or(false, |d| d.is_upcoming);
+        let is_live = !is_upcoming
+            && (details.map_or(false, |d| d.is_live) || broadcast.map_or(false, |b| b.is_live_now));
+        let was_live = !is_upcoming
+            && !is_live
+            && (details.map_or(false, |d| d.is_live_content)
+                || broadcast.map_or(false, |b| b.end_timestamp.is_some()));
+
+        LiveStatus {
+            is_live,
+            was_live,
+            is_upcoming,
+        }
+    }
+
+    /// Get video details, falling back to the microformat when `videoDetails`
+    /// is absent (as on some error responses that still parse)
+    pub fn details(&self, video_id: &str) -> Result<VideoDetails, RytError> {
+        if let Some(details) = &self.video_details {
+            return Ok(details.clone());
+        }
+
+        let microformat = self
+            .microformat
+            .as_ref()
+            .and_then(|m| m.player_microformat_renderer.as_ref())
+            .filter(|r| r.title.is_some())
+            .ok_or_else(|| RytError::MissingVideoDetails(video_id.to_string()))?;
+        warn!(
+            "Player response for {} has no videoDetails, using microformat",
+            video_id
+        );
+
+        let broadcast = microformat.live_broadcast_details.as_ref();
+        Ok(VideoDetails {
+            video_id: video_id.to_string(),
+            title: microformat
+                .title
+                .as_ref()
+                .map(TextContent::text)
+                .unwrap_or_default(),
+            author: microformat.owner_channel_name.clone().unwrap_or_default(),
+            length_seconds: microformat.length_seconds.clone().unwrap_or_default(),
+            short_description: microformat
+                .description
+                .as_ref()
+                .map(TextContent::text)
+                .unwrap_or_default(),
+            thumbnail: microformat.thumbnail.clone().unwrap_or(Thumbnail {
+                thumbnails: Vec::new(),
+            }),
+            is_live_content: broadcast.is_some(),
+            is_live: broadcast.map_or(false, |b| b.is_live_now),
+            is_upcoming: false,
+            view_count: microformat.view_count.clone(),
+        })
+    }
+
     /// Parse formats from player response
     pub fn parse_formats(&self) -> Result<Vec<Format>, RytError> {
         let mut formats = Vec::new();
@@ -488,8 +1600,18 @@ impl PlayerResponse {
                                 .and_then(|s| s.parse().ok())
                                 .or_else(|| v.as_u64().map(|n| n as u32))
                         }),
-                        language: None,
-                        note: None,
+                        language: format_data
+                            .audio_track
+                            .as_ref()
+                            .map(|track| track.language_code().to_string()),
+                        audio_is_default: format_data
+                            .audio_track
+                            .as_ref()
+                            .is_some_and(|track| track.audio_is_default),
+                        note: format_data
+                            .audio_track
+                            .as_ref()
+                            .map(|track| track.display_name.clone()),
                     });
                 }
             }
@@ -523,8 +1645,18 @@ impl PlayerResponse {
                                 .and_then(|s| s.parse().ok())
                                 .or_else(|| v.as_u64().map(|n| n as u32))
                         }),
-                        language: None,
-                        note: None,
+                        language: format_data
+                            .audio_track
+                            .as_ref()
+                            .map(|track| track.language_code().to_string()),
+                        audio_is_default: format_data
+                            .audio_track
+                            .as_ref()
+                            .is_some_and(|track| track.audio_is_default),
+                        note: format_data
+                            .audio_track
+                            .as_ref()
+                            .map(|track| track.display_name.clone()),
                     });
                 }
             }
@@ -619,9 +1751,943 @@ pub struct BylineText {
     pub runs: Vec<TextRun>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct TextRun {
-    pub text: String,
+#[derive(Debug, Deserialize)]
+pub struct TextRun {
+    pub text: String,
+}
+
+/// Browse response for the trending feed
+#[derive(Debug, Deserialize)]
+pub struct TrendingResponse {
+    pub contents: Option<TrendingContents>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingContents {
+    #[serde(rename = "twoColumnBrowseResultsRenderer")]
+    pub two_column_browse_results_renderer: Option<TrendingTabs>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingTabs {
+    #[serde(default)]
+    pub tabs: Vec<TrendingTab>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingTab {
+    #[serde(rename = "tabRenderer")]
+    pub tab_renderer: Option<TrendingTabRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingTabRenderer {
+    #[serde(default)]
+    pub selected: bool,
+    pub content: Option<TrendingTabContent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingTabContent {
+    #[serde(rename = "richGridRenderer")]
+    pub rich_grid_renderer: Option<RichGridRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RichGridRenderer {
+    #[serde(default)]
+    pub contents: Vec<RichGridContent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RichGridContent {
+    #[serde(rename = "richItemRenderer")]
+    pub rich_item_renderer: Option<RichItemRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RichItemRenderer {
+    pub content: RichItemContent,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RichItemContent {
+    #[serde(rename = "videoRenderer")]
+    pub video_renderer: Option<VideoRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VideoRenderer {
+    #[serde(rename = "videoId")]
+    pub video_id: String,
+    pub title: Option<TextContent>,
+    #[serde(rename = "ownerText")]
+    pub owner_text: Option<TextContent>,
+    #[serde(rename = "lengthText")]
+    pub length_text: Option<TextContent>,
+    #[serde(rename = "viewCountText")]
+    pub view_count_text: Option<TextContent>,
+    #[serde(rename = "publishedTimeText")]
+    pub published_time_text: Option<TextContent>,
+    pub thumbnail: Option<Thumbnail>,
+}
+
+/// Browse response for a playlist page, read for the playlist's metadata
+#[derive(Debug, Deserialize)]
+pub struct PlaylistBrowseResponse {
+    pub metadata: Option<PlaylistBrowseMetadata>,
+    pub header: Option<PlaylistBrowseHeader>,
+    pub microformat: Option<PlaylistMicroformat>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaylistBrowseMetadata {
+    #[serde(rename = "playlistMetadataRenderer")]
+    pub playlist_metadata_renderer: Option<PlaylistMetadataRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaylistMetadataRenderer {
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaylistBrowseHeader {
+    #[serde(rename = "playlistHeaderRenderer")]
+    pub playlist_header_renderer: Option<PlaylistHeaderRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaylistHeaderRenderer {
+    pub title: Option<TextContent>,
+    #[serde(rename = "numVideosText")]
+    pub num_videos_text: Option<TextContent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaylistMicroformat {
+    #[serde(rename = "microformatDataRenderer")]
+    pub microformat_data_renderer: Option<MicroformatDataRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MicroformatDataRenderer {
+    pub thumbnail: Option<Thumbnail>,
+}
+
+/// Browse response for a channel's Playlists tab or one of its continuations
+#[derive(Debug, Deserialize)]
+pub struct ChannelPlaylistsResponse {
+    pub contents: Option<ChannelBrowseContents>,
+    #[serde(rename = "onResponseReceivedActions", default)]
+    pub on_response_received_actions: Vec<ResponseReceivedAction>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelBrowseContents {
+    #[serde(rename = "twoColumnBrowseResultsRenderer")]
+    pub two_column_browse_results_renderer: Option<ChannelTabs>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelTabs {
+    #[serde(default)]
+    pub tabs: Vec<ChannelTab>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelTab {
+    #[serde(rename = "tabRenderer")]
+    pub tab_renderer: Option<ChannelTabRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelTabRenderer {
+    #[serde(default)]
+    pub selected: bool,
+    pub content: Option<ChannelTabContent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelTabContent {
+    #[serde(rename = "sectionListRenderer")]
+    pub section_list_renderer: Option<ChannelSectionList>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelSectionList {
+    #[serde(default)]
+    pub contents: Vec<ChannelSection>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelSection {
+    #[serde(rename = "itemSectionRenderer")]
+    pub item_section_renderer: Option<ChannelItemSection>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelItemSection {
+    #[serde(default)]
+    pub contents: Vec<ChannelSectionItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelSectionItem {
+    #[serde(rename = "gridRenderer")]
+    pub grid_renderer: Option<GridRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GridRenderer {
+    #[serde(default)]
+    pub items: Vec<GridItem>,
+}
+
+/// Grid entry: a playlist or the token for the next page
+#[derive(Debug, Deserialize)]
+pub struct GridItem {
+    #[serde(rename = "gridPlaylistRenderer")]
+    pub grid_playlist_renderer: Option<GridPlaylistRenderer>,
+    #[serde(rename = "continuationItemRenderer")]
+    pub continuation_item_renderer: Option<ContinuationItemRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GridPlaylistRenderer {
+    #[serde(rename = "playlistId")]
+    pub playlist_id: String,
+    pub title: Option<TextContent>,
+    #[serde(rename = "videoCountText")]
+    pub video_count_text: Option<TextContent>,
+    pub thumbnail: Option<Thumbnail>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContinuationItemRenderer {
+    #[serde(rename = "continuationEndpoint")]
+    pub continuation_endpoint: Option<ContinuationEndpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContinuationEndpoint {
+    #[serde(rename = "continuationCommand")]
+    pub continuation_command: Option<ContinuationCommand>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContinuationCommand {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResponseReceivedAction {
+    #[serde(rename = "appendContinuationItemsAction")]
+    pub append_continuation_items_action: Option<AppendContinuationItemsAction>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppendContinuationItemsAction {
+    #[serde(rename = "continuationItems", default)]
+    pub continuation_items: Vec<GridItem>,
+}
+
+/// Response of the `next` endpoint
+#[derive(Debug, Deserialize)]
+pub struct WatchNextResponse {
+    pub contents: Option<WatchNextContents>,
+    #[serde(rename = "engagementPanels", default)]
+    pub engagement_panels: Vec<EngagementPanel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WatchNextContents {
+    #[serde(rename = "twoColumnWatchNextResults")]
+    pub two_column_watch_next_results: Option<TwoColumnWatchNextResults>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoColumnWatchNextResults {
+    pub results: Option<PrimaryResults>,
+    #[serde(rename = "secondaryResults")]
+    pub secondary_results: Option<SecondaryResults>,
+}
+
+/// Main column of the watch page, below the player
+#[derive(Debug, Deserialize)]
+pub struct PrimaryResults {
+    pub results: Option<PrimaryResultsList>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrimaryResultsList {
+    #[serde(default)]
+    pub contents: Vec<PrimaryResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrimaryResult {
+    #[serde(rename = "videoPrimaryInfoRenderer")]
+    pub video_primary_info_renderer: Option<VideoPrimaryInfoRenderer>,
+    #[serde(rename = "videoSecondaryInfoRenderer")]
+    pub video_secondary_info_renderer: Option<VideoSecondaryInfoRenderer>,
+}
+
+/// Title, view count and like button of a video
+#[derive(Debug, Deserialize)]
+pub struct VideoPrimaryInfoRenderer {
+    #[serde(rename = "videoActions")]
+    pub video_actions: Option<VideoActions>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VideoActions {
+    #[serde(rename = "menuRenderer")]
+    pub menu_renderer: Option<VideoActionsMenu>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VideoActionsMenu {
+    /// Like, share and other buttons, whose layout changes often
+    #[serde(rename = "topLevelButtons", default)]
+    pub top_level_buttons: Vec<serde_json::Value>,
+}
+
+/// Places of the like button's label, e.g. "like this video along with
+/// 1,234 other people" or "1,234 likes", in the layouts seen so far
+const LIKE_LABEL_POINTERS: &[&str] = &[
+    "/segmentedLikeDislikeButtonViewModel/likeButtonViewModel/likeButtonViewModel/toggleButtonViewModel/toggleButtonViewModel/defaultButtonViewModel/buttonViewModel/accessibilityText",
+    "/segmentedLikeDislikeButtonRenderer/likeButton/toggleButtonRenderer/defaultText/accessibility/accessibilityData/label",
+    "/toggleButtonRenderer/defaultText/accessibility/accessibilityData/label",
+];
+
+/// Channel, description and metadata rows (e.g. the category) of a video
+#[derive(Debug, Deserialize)]
+pub struct VideoSecondaryInfoRenderer {
+    #[serde(rename = "metadataRowContainer")]
+    pub metadata_row_container: Option<MetadataRowContainer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetadataRowContainer {
+    #[serde(rename = "metadataRowContainerRenderer")]
+    pub metadata_row_container_renderer: Option<MetadataRowContainerRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetadataRowContainerRenderer {
+    #[serde(default)]
+    pub rows: Vec<MetadataRow>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetadataRow {
+    #[serde(rename = "metadataRowRenderer")]
+    pub metadata_row_renderer: Option<MetadataRowRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetadataRowRenderer {
+    pub title: Option<TextContent>,
+    #[serde(default)]
+    pub contents: Vec<TextContent>,
+}
+
+/// Side panel of the watch page, such as the comments section
+#[derive(Debug, Deserialize)]
+pub struct EngagementPanel {
+    #[serde(rename = "engagementPanelSectionListRenderer")]
+    pub engagement_panel_section_list_renderer: Option<EngagementPanelSectionListRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EngagementPanelSectionListRenderer {
+    #[serde(rename = "panelIdentifier")]
+    pub panel_identifier: Option<String>,
+    #[serde(rename = "targetId")]
+    pub target_id: Option<String>,
+    pub header: Option<EngagementPanelHeader>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EngagementPanelHeader {
+    #[serde(rename = "engagementPanelTitleHeaderRenderer")]
+    pub engagement_panel_title_header_renderer: Option<EngagementPanelTitleHeaderRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EngagementPanelTitleHeaderRenderer {
+    pub title: Option<TextContent>,
+    /// Count shown next to the title, e.g. "1,234" for comments
+    #[serde(rename = "contextualInfo")]
+    pub contextual_info: Option<TextContent>,
+}
+
+/// Panel identifier of the comments section
+const COMMENTS_PANEL_ID: &str = "engagement-panel-comments-section";
+
+#[derive(Debug, Deserialize)]
+pub struct SecondaryResults {
+    #[serde(rename = "secondaryResults", alias = "secondaryResultsRenderer")]
+    pub secondary_results_renderer: Option<SecondaryResultsRenderer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecondaryResultsRenderer {
+    #[serde(default)]
+    pub results: Vec<SecondaryResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecondaryResult {
+    #[serde(rename = "compactVideoRenderer")]
+    pub compact_video_renderer: Option<CompactVideoRenderer>,
+    #[serde(rename = "compactAutoplayRenderer")]
+    pub compact_autoplay_renderer: Option<CompactAutoplayRenderer>,
+    #[serde(rename = "lockupViewModel")]
+    pub lockup_view_model: Option<LockupViewModel>,
+}
+
+impl SecondaryResult {
+    /// Convert a video entry of either renderer variant to a search result
+    fn to_search_result(&self) -> Option<SearchResult> {
+        match (&self.compact_video_renderer, &self.lockup_view_model) {
+            (Some(video), _) => Some(video.to_search_result()),
+            (None, Some(lockup)) => lockup.to_search_result(),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Autoplay section holding the video that plays next
+#[derive(Debug, Deserialize)]
+pub struct CompactAutoplayRenderer {
+    #[serde(default)]
+    pub contents: Vec<SecondaryResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompactVideoRenderer {
+    #[serde(rename = "videoId")]
+    pub video_id: String,
+    pub title: Option<TextContent>,
+    #[serde(rename = "longBylineText")]
+    pub long_byline_text: Option<TextContent>,
+    #[serde(rename = "shortBylineText")]
+    pub short_byline_text: Option<TextContent>,
+    #[serde(rename = "lengthText")]
+    pub length_text: Option<TextContent>,
+    #[serde(rename = "viewCountText")]
+    pub view_count_text: Option<TextContent>,
+    #[serde(rename = "publishedTimeText")]
+    pub published_time_text: Option<TextContent>,
+    pub thumbnail: Option<Thumbnail>,
+}
+
+/// Entry of the newer view-model layout used for related videos
+#[derive(Debug, Deserialize)]
+pub struct LockupViewModel {
+    #[serde(rename = "contentId")]
+    pub content_id: String,
+    /// e.g. `LOCKUP_CONTENT_TYPE_VIDEO` or `LOCKUP_CONTENT_TYPE_PLAYLIST`
+    #[serde(rename = "contentType")]
+    pub content_type: Option<String>,
+    pub metadata: Option<LockupMetadata>,
+    #[serde(rename = "contentImage")]
+    pub content_image: Option<LockupContentImage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockupMetadata {
+    #[serde(rename = "lockupMetadataViewModel")]
+    pub lockup_metadata_view_model: Option<LockupMetadataViewModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockupMetadataViewModel {
+    pub title: Option<ViewModelText>,
+    pub metadata: Option<ContentMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContentMetadata {
+    #[serde(rename = "contentMetadataViewModel")]
+    pub content_metadata_view_model: Option<ContentMetadataViewModel>,
+}
+
+/// Metadata lines under the title: the channel, then views and publish time
+#[derive(Debug, Deserialize)]
+pub struct ContentMetadataViewModel {
+    #[serde(rename = "metadataRows", default)]
+    pub metadata_rows: Vec<ContentMetadataRow>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContentMetadataRow {
+    #[serde(rename = "metadataParts", default)]
+    pub metadata_parts: Vec<ContentMetadataPart>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContentMetadataPart {
+    pub text: Option<ViewModelText>,
+}
+
+/// Text of a view model, given as `content`
+#[derive(Debug, Deserialize)]
+pub struct ViewModelText {
+    #[serde(default)]
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockupContentImage {
+    #[serde(rename = "thumbnailViewModel")]
+    pub thumbnail_view_model: Option<ThumbnailViewModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailViewModel {
+    pub image: Option<ViewModelImage>,
+    #[serde(default)]
+    pub overlays: Vec<ThumbnailOverlay>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViewModelImage {
+    #[serde(default)]
+    pub sources: Vec<ViewModelImageSource>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViewModelImageSource {
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailOverlay {
+    #[serde(rename = "thumbnailOverlayBadgeViewModel")]
+    pub thumbnail_overlay_badge_view_model: Option<ThumbnailOverlayBadgeViewModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailOverlayBadgeViewModel {
+    #[serde(rename = "thumbnailBadges", default)]
+    pub thumbnail_badges: Vec<ThumbnailBadge>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailBadge {
+    #[serde(rename = "thumbnailBadgeViewModel")]
+    pub thumbnail_badge_view_model: Option<ThumbnailBadgeViewModel>,
+}
+
+/// Badge drawn over the thumbnail, e.g. the length "4:05"
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailBadgeViewModel {
+    pub text: Option<String>,
+}
+
+impl LockupViewModel {
+    /// Convert to a search result, or `None` for non-video entries
+    pub fn to_search_result(&self) -> Option<SearchResult> {
+        if self
+            .content_type
+            .as_deref()
+            .is_some_and(|t| t != "LOCKUP_CONTENT_TYPE_VIDEO")
+        {
+            return None;
+        }
+
+        let metadata = self
+            .metadata
+            .as_ref()
+            .and_then(|m| m.lockup_metadata_view_model.as_ref());
+        let rows = metadata
+            .and_then(|m| m.metadata.as_ref())
+            .and_then(|m| m.content_metadata_view_model.as_ref())
+            .map(|m| m.metadata_rows.as_slice())
+            .unwrap_or_default();
+        let part = |row: usize, part: usize| {
+            rows.get(row)
+                .and_then(|r| r.metadata_parts.get(part))
+                .and_then(|p| p.text.as_ref())
+                .map(|t| t.content.clone())
+        };
+        let thumbnail = self
+            .content_image
+            .as_ref()
+            .and_then(|i| i.thumbnail_view_model.as_ref());
+        let length = thumbnail
+            .into_iter()
+            .flat_map(|t| t.overlays.iter())
+            .filter_map(|o| o.thumbnail_overlay_badge_view_model.as_ref())
+            .flat_map(|b| b.thumbnail_badges.iter())
+            .filter_map(|b| b.thumbnail_badge_view_model.as_ref())
+            .find_map(|b| b.text.clone());
+
+        Some(SearchResult {
+            video_id: self.content_id.clone(),
+            title: metadata
+                .and_then(|m| m.title.as_ref())
+                .map(|t| t.content.clone())
+                .unwrap_or_default(),
+            author: part(0, 0).unwrap_or_default(),
+            duration: length.map(|t| parse_length_text(&t)).unwrap_or(0),
+            view_count: part(1, 0).and_then(|t| parse_approx_count(&t)),
+            published_at: part(1, 1),
+            thumbnail: thumbnail
+                .and_then(|t| t.image.as_ref())
+                .and_then(|i| i.sources.last())
+                .map(|s| s.url.clone()),
+        })
+    }
+}
+
+impl WatchNextResponse {
+    /// Get the related videos, numbered from 1 in the order YouTube lists them
+    pub fn related_videos(&self) -> Vec<PlaylistItem> {
+        self.search_results()
+            .into_iter()
+            .enumerate()
+            .map(|(i, result)| {
+                let mut item = PlaylistItem::new(result.video_id, result.title, i as u32 + 1);
+                item.author = result.author;
+                item.duration = result.duration;
+                item.thumbnail = result.thumbnail;
+                item
+            })
+            .collect()
+    }
+
+    /// Get the comment count from the header of the comments panel
+    pub fn comment_count(&self) -> Option<u64> {
+        self.engagement_panels
+            .iter()
+            .filter_map(|p| p.engagement_panel_section_list_renderer.as_ref())
+            .filter(|p| {
+                p.panel_identifier.as_deref() == Some(COMMENTS_PANEL_ID)
+                    || p.target_id.as_deref() == Some(COMMENTS_PANEL_ID)
+            })
+            .filter_map(|p| p.header.as_ref())
+            .filter_map(|h| h.engagement_panel_title_header_renderer.as_ref())
+            .filter_map(|h| h.contextual_info.as_ref())
+            .find_map(|info| parse_approx_count(&info.text()))
+    }
+
+    /// Get the like count from the like button's label
+    pub fn like_count(&self) -> Option<u64> {
+        self.contents
+            .as_ref()
+            .and_then(|c| c.two_column_watch_next_results.as_ref())
+            .and_then(|r| r.results.as_ref())
+            .and_then(|r| r.results.as_ref())
+            .into_iter()
+            .flat_map(|r| r.contents.iter())
+            .filter_map(|c| c.video_primary_info_renderer.as_ref())
+            .filter_map(|r| r.video_actions.as_ref())
+            .filter_map(|a| a.menu_renderer.as_ref())
+            .flat_map(|m| m.top_level_buttons.iter())
+            .flat_map(|button| LIKE_LABEL_POINTERS.iter().map(|p| button.pointer(p)))
+            .find_map(|label| label.and_then(serde_json::Value::as_str))
+            .and_then(parse_view_count)
+    }
+
+    /// Get the category from the metadata rows below the description
+    pub fn category(&self) -> Option<String> {
+        self.contents
+            .as_ref()
+            .and_then(|c| c.two_column_watch_next_results.as_ref())
+            .and_then(|r| r.results.as_ref())
+            .and_then(|r| r.results.as_ref())
+            .into_iter()
+            .flat_map(|r| r.contents.iter())
+            .filter_map(|c| c.video_secondary_info_renderer.as_ref())
+            .filter_map(|r| r.metadata_row_container.as_ref())
+            .filter_map(|c| c.metadata_row_container_renderer.as_ref())
+            .flat_map(|c| c.rows.iter())
+            .filter_map(|r| r.metadata_row_renderer.as_ref())
+            .find(|r| r.title.as_ref().map(TextContent::text).as_deref() == Some("Category"))
+            .and_then(|r| r.contents.first())
+            .map(TextContent::text)
+            .filter(|category| !category.is_empty())
+    }
+
+    /// Get the queued videos, autoplay entries first
+    pub fn search_results(&self) -> Vec<SearchResult> {
+        let results = self
+            .contents
+            .as_ref()
+            .and_then(|c| c.two_column_watch_next_results.as_ref())
+            .and_then(|r| r.secondary_results.as_ref())
+            .and_then(|r| r.secondary_results_renderer.as_ref())
+            .map(|r| r.results.as_slice())
+            .unwrap_or_default();
+
+        let autoplay = results
+            .iter()
+            .filter_map(|r| r.compact_autoplay_renderer.as_ref())
+            .flat_map(|autoplay| autoplay.contents.iter());
+        let queue = results
+            .iter()
+            .filter(|r| r.compact_autoplay_renderer.is_none());
+
+        let mut seen = std::collections::HashSet::new();
+        autoplay
+            .chain(queue)
+            .filter_map(SecondaryResult::to_search_result)
+            .filter(|video| seen.insert(video.video_id.clone()))
+            .collect()
+    }
+}
+
+impl CompactVideoRenderer {
+    /// Convert to a search result
+    pub fn to_search_result(&self) -> SearchResult {
+        let text = |content: &Option<TextContent>| content.as_ref().map(TextContent::text);
+        SearchResult {
+            video_id: self.video_id.clone(),
+            title: text(&self.title).unwrap_or_default(),
+            author: text(&self.long_byline_text)
+                .or_else(|| text(&self.short_byline_text))
+                .unwrap_or_default(),
+            duration: text(&self.length_text)
+                .map(|t| parse_length_text(&t))
+                .unwrap_or(0),
+            view_count: text(&self.view_count_text).and_then(|t| parse_view_count(&t)),
+            published_at: text(&self.published_time_text),
+            thumbnail: self
+                .thumbnail
+                .as_ref()
+                .and_then(|t| t.thumbnails.last())
+                .map(|t| t.url.clone()),
+        }
+    }
+}
+
+/// Text given either as `simpleText` or as `runs`
+#[derive(Debug, Deserialize)]
+pub struct TextContent {
+    #[serde(rename = "simpleText")]
+    pub simple_text: Option<String>,
+    #[serde(default)]
+    pub runs: Vec<TextRun>,
+}
+
+impl TextContent {
+    /// Get the full text
+    pub fn text(&self) -> String {
+        match &self.simple_text {
+            Some(text) => text.clone(),
+            None => self.runs.iter().map(|r| r.text.as_str()).collect(),
+        }
+    }
+}
+
+impl TrendingResponse {
+    /// Get the videos of the selected tab's grid
+    pub fn search_results(&self) -> Vec<SearchResult> {
+        let tabs = self
+            .contents
+            .as_ref()
+            .and_then(|c| c.two_column_browse_results_renderer.as_ref())
+            .map(|r| r.tabs.as_slice())
+            .unwrap_or_default();
+        let tab = tabs
+            .iter()
+            .filter_map(|t| t.tab_renderer.as_ref())
+            .find(|t| t.selected)
+            .or_else(|| tabs.iter().find_map(|t| t.tab_renderer.as_ref()));
+
+        tab.and_then(|t| t.content.as_ref())
+            .and_then(|c| c.rich_grid_renderer.as_ref())
+            .map(|grid| {
+                grid.contents
+                    .iter()
+                    .filter_map(|c| c.rich_item_renderer.as_ref())
+                    .filter_map(|item| item.content.video_renderer.as_ref())
+                    .map(VideoRenderer::to_search_result)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl PlaylistBrowseResponse {
+    /// Convert to playlist metadata, preferring the metadata renderer's title
+    /// over the header's
+    pub fn to_playlist_metadata(&self, playlist_id: &str) -> PlaylistMetadata {
+        let header = self
+            .header
+            .as_ref()
+            .and_then(|h| h.playlist_header_renderer.as_ref());
+        let title = self
+            .metadata
+            .as_ref()
+            .and_then(|m| m.playlist_metadata_renderer.as_ref())
+            .and_then(|r| r.title.clone())
+            .or_else(|| header.and_then(|h| h.title.as_ref()).map(TextContent::text))
+            .unwrap_or_default();
+        let video_count = header
+            .and_then(|h| h.num_videos_text.as_ref())
+            .and_then(|text| {
+                let digits: String = text.text().chars().filter(|c| c.is_ascii_digit()).collect();
+                digits.parse().ok()
+            });
+        let thumbnail = self
+            .microformat
+            .as_ref()
+            .and_then(|m| m.microformat_data_renderer.as_ref())
+            .and_then(|r| r.thumbnail.as_ref())
+            .and_then(|t| t.thumbnails.last())
+            .map(|t| t.url.clone());
+
+        PlaylistMetadata {
+            playlist_id: playlist_id.to_string(),
+            title,
+            video_count,
+            thumbnail,
+        }
+    }
+}
+
+impl ChannelPlaylistsResponse {
+    /// Get the grid entries of the selected tab, or of a continuation page
+    fn grid_items(&self) -> Vec<&GridItem> {
+        let tabs = self
+            .contents
+            .as_ref()
+            .and_then(|c| c.two_column_browse_results_renderer.as_ref())
+            .map(|r| r.tabs.as_slice())
+            .unwrap_or_default();
+        let tab = tabs
+            .iter()
+            .filter_map(|t| t.tab_renderer.as_ref())
+            .find(|t| t.selected)
+            .or_else(|| tabs.iter().find_map(|t| t.tab_renderer.as_ref()));
+
+        let grid_items = tab
+            .and_then(|t| t.content.as_ref())
+            .and_then(|c| c.section_list_renderer.as_ref())
+            .map(|list| list.contents.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|s| s.item_section_renderer.as_ref())
+            .flat_map(|s| s.contents.iter())
+            .filter_map(|i| i.grid_renderer.as_ref())
+            .flat_map(|grid| grid.items.iter());
+        let continuation_items = self
+            .on_response_received_actions
+            .iter()
+            .filter_map(|a| a.append_continuation_items_action.as_ref())
+            .flat_map(|a| a.continuation_items.iter());
+        grid_items.chain(continuation_items).collect()
+    }
+
+    /// Get the playlists on this page
+    pub fn playlists(&self) -> Vec<PlaylistMetadata> {
+        self.grid_items()
+            .into_iter()
+            .filter_map(|item| item.grid_playlist_renderer.as_ref())
+            .map(GridPlaylistRenderer::to_playlist_metadata)
+            .collect()
+    }
+
+    /// Get the token for the next page, if any
+    pub fn continuation(&self) -> Option<String> {
+        self.grid_items()
+            .into_iter()
+            .filter_map(|item| item.continuation_item_renderer.as_ref())
+            .filter_map(|c| c.continuation_endpoint.as_ref())
+            .filter_map(|e| e.continuation_command.as_ref())
+            .map(|command| command.token.clone())
+            .find(|token| !token.is_empty())
+    }
+}
+
+impl GridPlaylistRenderer {
+    /// Convert to playlist metadata
+    pub fn to_playlist_metadata(&self) -> PlaylistMetadata {
+        let text = |content: &Option<TextContent>| content.as_ref().map(TextContent::text);
+        PlaylistMetadata {
+            playlist_id: self.playlist_id.clone(),
+            title: text(&self.title).unwrap_or_default(),
+            video_count: text(&self.video_count_text).and_then(|t| {
+                let digits: String = t.chars().filter(|c| c.is_ascii_digit()).collect();
+                digits.parse().ok()
+            }),
+            thumbnail: self
+                .thumbnail
+                .as_ref()
+                .and_then(|t| t.thumbnails.last())
+                .map(|t| t.url.clone()),
+        }
+    }
+}
+
+impl VideoRenderer {
+    /// Convert to a search result
+    pub fn to_search_result(&self) -> SearchResult {
+        let text = |content: &Option<TextContent>| content.as_ref().map(TextContent::text);
+        SearchResult {
+            video_id: self.video_id.clone(),
+            title: text(&self.title).unwrap_or_default(),
+            author: text(&self.owner_text).unwrap_or_default(),
+            duration: text(&self.length_text)
+                .map(|t| parse_length_text(&t))
+                .unwrap_or(0),
+            view_count: text(&self.view_count_text).and_then(|t| parse_view_count(&t)),
+            published_at: text(&self.published_time_text),
+            thumbnail: self
+                .thumbnail
+                .as_ref()
+                .and_then(|t| t.thumbnails.last())
+                .map(|t| t.url.clone()),
+        }
+    }
+}
+
+/// Parse a length text like "1:02:03" into seconds
+fn parse_length_text(text: &str) -> u32 {
+    text.trim()
+        .split(':')
+        .try_fold(0u32, |total, part| {
+            part.parse::<u32>().ok().map(|value| total * 60 + value)
+        })
+        .unwrap_or(0)
+}
+
+/// Parse a possibly abbreviated count like "1.2K", "3M views" or "1,234"
+fn parse_approx_count(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let number_len = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(text.len());
+    let (number, rest) = text.split_at(number_len);
+    let number = number.replace(',', "");
+    if number.is_empty() {
+        return parse_view_count(text);
+    }
+    let multiplier = match rest.chars().next().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => 1_000.0,
+        Some('M') => 1_000_000.0,
+        Some('B') => 1_000_000_000.0,
+        _ => 1.0,
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .map(|value| (value * multiplier).round() as u64)
+}
+
+/// Parse a view count text like "1,234,567 views" into a number
+fn parse_view_count(text: &str) -> Option<u64> {
+    let digits: String = text.chars().filter(|c| c.is_ascii_digit()).collect();
+    if digits.is_empty() {
+        // "No views"
+        return text.to_lowercase().contains("no views").then_some(0);
+    }
+    digits.parse().ok()
 }
 
 #[cfg(test)]
@@ -652,6 +2718,44 @@ mod tests {
         assert_eq!(client.visitor_id, Some("test_visitor_123".to_string()));
     }
 
+    #[test]
+    fn test_innertube_client_with_locale() {
+        let client = InnerTubeClient::new().with_locale("de", "at");
+        assert_eq!(client.language.as_deref(), Some("de"));
+        assert_eq!(client.geo_country(), Some("AT"));
+
+        let client = InnerTubeClient::new().with_locale("en", "");
+        assert_eq!(client.language.as_deref(), Some("en"));
+        assert_eq!(client.geo_country(), None);
+    }
+
+    #[test]
+    fn test_apply_locale() {
+        let client = InnerTubeClient::new().with_locale("fr", "CA");
+        let mut body = serde_json::json!({
+            "context": { "client": { "clientName": "ANDROID" } },
+            "videoId": "x"
+        });
+        client.apply_locale(&mut body);
+        assert_eq!(body["context"]["client"]["hl"], "fr");
+        assert_eq!(body["context"]["client"]["gl"], "CA");
+
+        // An explicit region wins over the configured country
+        let mut body = serde_json::json!({ "context": web_client_context(Some("jp")) });
+        client.apply_locale(&mut body);
+        assert_eq!(body["context"]["client"]["hl"], "fr");
+        assert_eq!(body["context"]["client"]["gl"], "JP");
+
+        // Without a locale, bodies are left alone
+        let mut body = serde_json::json!({ "context": { "client": {} } });
+        InnerTubeClient::new().apply_locale(&mut body);
+        assert_eq!(body, serde_json::json!({ "context": { "client": {} } }));
+
+        let mut body = serde_json::json!({ "browseId": "x" });
+        client.apply_locale(&mut body);
+        assert_eq!(body, serde_json::json!({ "browseId": "x" }));
+    }
+
     #[test]
     fn test_innertube_client_chaining() {
         let client = InnerTubeClient::new()
@@ -691,7 +2795,7 @@ mod tests {
         assert!(format.is_ok());
 
         let format = format.unwrap();
-        assert_eq!(format.itag, 22);
+        assert_eq!(format.itag, Itag(22));
         assert_eq!(
             format.url,
             Some("https://example.com/video.mp4".to_string())
@@ -786,7 +2890,7 @@ mod tests {
         assert!(streaming_data.formats.is_some());
         let formats = streaming_data.formats.unwrap();
         assert_eq!(formats.len(), 1);
-        assert_eq!(formats[0].itag, 22);
+        assert_eq!(formats[0].itag, Itag(22));
     }
 
     #[test]
@@ -797,6 +2901,7 @@ mod tests {
             "lengthSeconds": "212",
             "author": "Test Author",
             "shortDescription": "Test description",
+            "viewCount": "1234567",
             "thumbnail": {
                 "thumbnails": [
                     {
@@ -818,6 +2923,191 @@ mod tests {
         assert_eq!(details.author, "Test Author");
         assert_eq!(details.short_description, "Test description");
         assert_eq!(details.thumbnail.thumbnails.len(), 1);
+        assert_eq!(details.view_count.as_deref(), Some("1234567"));
+        assert!(!details.is_live_content);
+    }
+
+    #[test]
+    fn test_video_details_live_content_deserialization() {
+        let json = r#"{
+            "videoId": "dQw4w9WgXcQ",
+            "title": "Stream VOD",
+            "lengthSeconds": "7200",
+            "author": "Test Author",
+            "shortDescription": "",
+            "isLiveContent": true,
+            "thumbnail": {
+                "thumbnails": []
+            }
+        }"#;
+
+        let details: VideoDetails = serde_json::from_str(json).unwrap();
+        assert!(details.is_live_content);
+        assert_eq!(details.view_count, None);
+    }
+
+    #[test]
+    fn test_player_response_details() {
+        let json = r#"{
+            "videoDetails": {
+                "videoId": "dQw4w9WgXcQ",
+                "title": "Test Video",
+                "lengthSeconds": "212",
+                "author": "Test Author",
+                "shortDescription": "",
+                "thumbnail": { "thumbnails": [] }
+            }
+        }"#;
+        let response: PlayerResponse = serde_json::from_str(json).unwrap();
+        let details = response.details("dQw4w9WgXcQ").unwrap();
+        assert_eq!(details.title, "Test Video");
+        assert_eq!(details.author, "Test Author");
+    }
+
+    #[test]
+    fn test_player_response_details_from_microformat() {
+        let json = r#"{
+            "playabilityStatus": { "status": "OK" },
+            "microformat": {
+                "playerMicroformatRenderer": {
+                    "title": { "simpleText": "Microformat Title" },
+                    "description": { "simpleText": "Description" },
+                    "ownerChannelName": "Channel",
+                    "lengthSeconds": "212",
+                    "viewCount": "42",
+                    "thumbnail": {
+                        "thumbnails": [
+                            { "url": "https://example.com/thumb.jpg", "width": 120, "height": 90 }
+                        ]
+                    }
+                }
+            }
+        }"#;
+        let response: PlayerResponse = serde_json::from_str(json).unwrap();
+        assert!(response.video_details.is_none());
+
+        let details = response.details("dQw4w9WgXcQ").unwrap();
+        assert_eq!(details.video_id, "dQw4w9WgXcQ");
+        assert_eq!(details.title, "Microformat Title");
+        assert_eq!(details.author, "Channel");
+        assert_eq!(details.length_seconds, "212");
+        assert_eq!(details.short_description, "Description");
+        assert_eq!(details.thumbnail.thumbnails.len(), 1);
+        assert_eq!(details.view_count.as_deref(), Some("42"));
+        assert!(!details.is_live_content);
+    }
+
+    #[test]
+    fn test_player_response_details_absent() {
+        let json = r#"{
+            "playabilityStatus": { "status": "OK" },
+            "streamingData": {
+                "formats": [
+                    { "itag": 18, "url": "https://example.com/video.mp4", "mimeType": "video/mp4" }
+                ]
+            }
+        }"#;
+        let response: PlayerResponse = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            response.details("dQw4w9WgXcQ"),
+            Err(RytError::MissingVideoDetails(id)) if id == "dQw4w9WgXcQ"
+        ));
+
+        // A microformat without a title is not enough to name the video
+        let json = r#"{"microformat": {"playerMicroformatRenderer": {"lengthSeconds": "10"}}}"#;
+        let response: PlayerResponse = serde_json::from_str(json).unwrap();
+        assert!(response.details("abc").is_err());
+    }
+
+    /// Player response fixture with the given videoDetails flags and liveBroadcastDetails
+    fn live_fixture(details_flags: &str, broadcast: &str) -> PlayerResponse {
+        let json = format!(
+            r#"{{
+                "videoDetails": {{
+                    "videoId": "dQw4w9WgXcQ",
+                    "title": "Video",
+                    "lengthSeconds": "0",
+                    "author": "Test Author",
+                    "shortDescription": "",
+                    "thumbnail": {{ "thumbnails": [] }}
+                    {}
+                }},
+                "microformat": {{
+                    "playerMicroformatRenderer": {{
+                        {}
+                    }}
+                }}
+            }}"#,
+            details_flags, broadcast
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_live_status_live_stream() {
+        let response = live_fixture(
+            r#", "isLive": true, "isLiveContent": true"#,
+            r#""liveBroadcastDetails": {
+                "isLiveNow": true,
+                "startTimestamp": "2024-01-01T10:00:00+00:00"
+            }"#,
+        );
+        assert_eq!(
+            response.live_status(),
+            LiveStatus {
+                is_live: true,
+                was_live: false,
+                is_upcoming: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_live_status_vod_of_live() {
+        let response = live_fixture(
+            r#", "isLiveContent": true"#,
+            r#""liveBroadcastDetails": {
+                "isLiveNow": false,
+                "startTimestamp": "2024-01-01T10:00:00+00:00",
+                "endTimestamp": "2024-01-01T12:00:00+00:00"
+            }"#,
+        );
+        assert_eq!(
+            response.live_status(),
+            LiveStatus {
+                is_live: false,
+                was_live: true,
+                is_upcoming: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_live_status_upcoming() {
+        let response = live_fixture(
+            r#", "isUpcoming": true, "isLiveContent": true"#,
+            r#""liveBroadcastDetails": {
+                "isLiveNow": false,
+                "startTimestamp": "2030-01-01T10:00:00+00:00"
+            }"#,
+        );
+        assert_eq!(
+            response.live_status(),
+            LiveStatus {
+                is_live: false,
+                was_live: false,
+                is_upcoming: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_live_status_regular_video() {
+        let response = live_fixture("", "");
+        assert_eq!(response.live_status(), LiveStatus::default());
+
+        let response: PlayerResponse = serde_json::from_str("{}").unwrap();
+        assert_eq!(response.live_status(), LiveStatus::default());
     }
 
     #[test]
@@ -854,8 +3144,18 @@ mod tests {
         assert!(streaming_data.formats.is_some());
         let formats = streaming_data.formats.unwrap();
         assert_eq!(formats.len(), 2);
-        assert_eq!(formats[0].itag, 22);
-        assert_eq!(formats[1].itag, 18);
+        assert_eq!(formats[0].itag, Itag(22));
+        assert_eq!(formats[1].itag, Itag(18));
+        assert!(streaming_data.hls_manifest_url.is_none());
+
+        let response: PlayerResponse = serde_json::from_str(
+            r#"{"streamingData":{"hlsManifestUrl":"https://manifest.googlevideo.com/api/manifest/hls_variant/id/1/file/index.m3u8"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            response.hls_manifest_url(),
+            Some("https://manifest.googlevideo.com/api/manifest/hls_variant/id/1/file/index.m3u8")
+        );
     }
 
     #[test]
@@ -910,7 +3210,7 @@ mod tests {
         assert!(format.is_ok());
 
         let format = format.unwrap();
-        assert_eq!(format.itag, 22);
+        assert_eq!(format.itag, Itag(22));
         assert_eq!(format.url, None);
         assert_eq!(format.mime_type, "video/mp4");
         assert_eq!(format.bitrate, None);
@@ -939,7 +3239,7 @@ mod tests {
         assert!(format.is_ok());
 
         let format = format.unwrap();
-        assert_eq!(format.itag, 137);
+        assert_eq!(format.itag, Itag(137));
         assert_eq!(
             format.url,
             Some("https://example.com/video.mp4".to_string())
@@ -1058,4 +3358,1214 @@ mod tests {
         // Should not panic
         assert_eq!(client.client_name, "ANDROID");
     }
+
+    /// Trimmed FEtrending browse response: a selected "Now" tab with a
+    /// rich grid holding two videos, an ad slot and a continuation item
+    const TRENDING_FIXTURE: &str = r#"{
+        "contents": {
+            "twoColumnBrowseResultsRenderer": {
+                "tabs": [
+                    {
+                        "tabRenderer": {
+                            "title": "Now",
+                            "selected": true,
+                            "content": {
+                                "richGridRenderer": {
+                                    "contents": [
+                                        {
+                                            "richItemRenderer": {
+                                                "content": {
+                                                    "videoRenderer": {
+                                                        "videoId": "dQw4w9WgXcQ",
+                                                        "thumbnail": {
+                                                            "thumbnails": [
+                                                                { "url": "https://i.ytimg.com/vi/dQw4w9WgXcQ/default.jpg", "width": 120, "height": 90 },
+                                                                { "url": "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg", "width": 480, "height": 360 }
+                                                            ]
+                                                        },
+                                                        "title": { "runs": [{ "text": "Never Gonna " }, { "text": "Give You Up" }] },
+                                                        "ownerText": { "runs": [{ "text": "Rick Astley" }] },
+                                                        "publishedTimeText": { "simpleText": "2 days ago" },
+                                                        "lengthText": { "simpleText": "3:33" },
+                                                        "viewCountText": { "simpleText": "1,234,567 views" }
+                                                    }
+                                                }
+                                            }
+                                        },
+                                        {
+                                            "richItemRenderer": {
+                                                "content": { "adSlotRenderer": {} }
+                                            }
+                                        },
+                                        {
+                                            "richItemRenderer": {
+                                                "content": {
+                                                    "videoRenderer": {
+                                                        "videoId": "jNQXAC9IVRw",
+                                                        "title": { "runs": [{ "text": "Me at the zoo" }] },
+                                                        "ownerText": { "runs": [{ "text": "jawed" }] },
+                                                        "lengthText": { "simpleText": "1:02:03" },
+                                                        "viewCountText": { "simpleText": "No views" }
+                                                    }
+                                                }
+                                            }
+                                        },
+                                        { "continuationItemRenderer": {} }
+                                    ]
+                                }
+                            }
+                        }
+                    },
+                    { "tabRenderer": { "title": "Music", "selected": false } }
+                ]
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_trending_response_search_results() {
+        let response: TrendingResponse = serde_json::from_str(TRENDING_FIXTURE).unwrap();
+        let results = response.search_results();
+        assert_eq!(results.len(), 2);
+
+        let first = &results[0];
+        assert_eq!(first.video_id, "dQw4w9WgXcQ");
+        assert_eq!(first.title, "Never Gonna Give You Up");
+        assert_eq!(first.author, "Rick Astley");
+        assert_eq!(first.duration, 213);
+        assert_eq!(first.view_count, Some(1_234_567));
+        assert_eq!(first.published_at.as_deref(), Some("2 days ago"));
+        assert_eq!(
+            first.thumbnail.as_deref(),
+            Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg")
+        );
+        assert_eq!(first.url(), "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+
+        let second = &results[1];
+        assert_eq!(second.video_id, "jNQXAC9IVRw");
+        assert_eq!(second.duration, 3723);
+        assert_eq!(second.view_count, Some(0));
+        assert_eq!(second.published_at, None);
+        assert_eq!(second.thumbnail, None);
+    }
+
+    #[test]
+    fn test_trending_response_without_grid() {
+        let response: TrendingResponse = serde_json::from_str("{}").unwrap();
+        assert!(response.search_results().is_empty());
+
+        let response: TrendingResponse = serde_json::from_str(
+            r#"{"contents": {"twoColumnBrowseResultsRenderer": {"tabs": [{"tabRenderer": {"selected": true}}]}}}"#,
+        )
+        .unwrap();
+        assert!(response.search_results().is_empty());
+    }
+
+    #[test]
+    fn test_trending_category_params() {
+        assert_eq!(TrendingCategory::default(), TrendingCategory::Now);
+        assert_eq!(TrendingCategory::Now.params(), None);
+        assert_eq!(
+            TrendingCategory::Music.params(),
+            Some("4gINGgt5dG1hX2NoYXJ0cw==")
+        );
+        assert!(TrendingCategory::Gaming.params().is_some());
+        assert!(TrendingCategory::Movies.params().is_some());
+        assert!(TrendingCategory::Technology.params().is_some());
+    }
+
+    #[test]
+    fn test_parse_length_and_view_count_text() {
+        assert_eq!(parse_length_text("0:59"), 59);
+        assert_eq!(parse_length_text("10:32"), 632);
+        assert_eq!(parse_length_text("1:02:03"), 3723);
+        assert_eq!(parse_length_text("LIVE"), 0);
+
+        assert_eq!(parse_view_count("1,234,567 views"), Some(1_234_567));
+        assert_eq!(parse_view_count("1 view"), Some(1));
+        assert_eq!(parse_view_count("No views"), Some(0));
+        assert_eq!(parse_view_count(""), None);
+    }
+
+    /// Trimmed `next` response: an autoplay section followed by the queue,
+    /// which repeats the autoplay video and contains a non-video item
+    const WATCH_NEXT_FIXTURE: &str = r#"{
+        "contents": {
+            "twoColumnWatchNextResults": {
+                "secondaryResults": {
+                    "secondaryResults": {
+                        "results": [
+                            {
+                                "compactAutoplayRenderer": {
+                                    "contents": [
+                                        {
+                                            "compactVideoRenderer": {
+                                                "videoId": "autoplay001",
+                                                "title": { "simpleText": "Autoplay Video" },
+                                                "longBylineText": { "runs": [{ "text": "Channel A" }] },
+                                                "lengthText": { "simpleText": "4:05" },
+                                                "viewCountText": { "simpleText": "12,345 views" }
+                                            }
+                                        }
+                                    ]
+                                }
+                            },
+                            {
+                                "compactVideoRenderer": {
+                                    "videoId": "queued00001",
+                                    "title": { "simpleText": "Queued Video" },
+                                    "shortBylineText": { "runs": [{ "text": "Channel B" }] },
+                                    "publishedTimeText": { "simpleText": "1 year ago" },
+                                    "lengthText": { "simpleText": "12:00" },
+                                    "thumbnail": {
+                                        "thumbnails": [
+                                            { "url": "https://i.ytimg.com/vi/queued00001/hqdefault.jpg", "width": 168, "height": 94 }
+                                        ]
+                                    }
+                                }
+                            },
+                            { "compactRadioRenderer": { "playlistId": "RDqueued" } },
+                            {
+                                "compactVideoRenderer": {
+                                    "videoId": "autoplay001",
+                                    "title": { "simpleText": "Autoplay Video" }
+                                }
+                            },
+                            { "continuationItemRenderer": {} }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_watch_next_response_search_results() {
+        let response: WatchNextResponse = serde_json::from_str(WATCH_NEXT_FIXTURE).unwrap();
+        let results = response.search_results();
+        let ids: Vec<&str> = results.iter().map(|r| r.video_id.as_str()).collect();
+        assert_eq!(ids, vec!["autoplay001", "queued00001"]);
+
+        assert_eq!(results[0].title, "Autoplay Video");
+        assert_eq!(results[0].author, "Channel A");
+        assert_eq!(results[0].duration, 245);
+        assert_eq!(results[0].view_count, Some(12_345));
+
+        assert_eq!(results[1].author, "Channel B");
+        assert_eq!(results[1].duration, 720);
+        assert_eq!(results[1].published_at.as_deref(), Some("1 year ago"));
+        assert_eq!(
+            results[1].thumbnail.as_deref(),
+            Some("https://i.ytimg.com/vi/queued00001/hqdefault.jpg")
+        );
+    }
+
+    /// Trimmed `next` response in the view-model layout, with the comments
+    /// panel and the category row
+    const NEXT_LOCKUP_FIXTURE: &str = r#"{
+        "contents": {
+            "twoColumnWatchNextResults": {
+                "results": {
+                    "results": {
+                        "contents": [
+                            {
+                                "videoPrimaryInfoRenderer": {
+                                    "videoActions": {
+                                        "menuRenderer": {
+                                            "topLevelButtons": [{
+                                                "segmentedLikeDislikeButtonViewModel": {
+                                                    "likeButtonViewModel": { "likeButtonViewModel": {
+                                                        "toggleButtonViewModel": { "toggleButtonViewModel": {
+                                                            "defaultButtonViewModel": { "buttonViewModel": {
+                                                                "title": "1.2K",
+                                                                "accessibilityText": "like this video along with 1,234 other people"
+                                                            } }
+                                                        } }
+                                                    } }
+                                                }
+                                            }]
+                                        }
+                                    }
+                                }
+                            },
+                            {
+                                "videoSecondaryInfoRenderer": {
+                                    "metadataRowContainer": {
+                                        "metadataRowContainerRenderer": {
+                                            "rows": [
+                                                { "richMetadataRowRenderer": {} },
+                                                {
+                                                    "metadataRowRenderer": {
+                                                        "title": { "simpleText": "Category" },
+                                                        "contents": [{ "runs": [{ "text": "Music" }] }]
+                                                    }
+                                                }
+                                            ]
+                                        }
+                                    }
+                                }
+                            }
+                        ]
+                    }
+                },
+                "secondaryResults": {
+                    "secondaryResults": {
+                        "results": [
+                            {
+                                "lockupViewModel": {
+                                    "contentId": "lockup00001",
+                                    "contentType": "LOCKUP_CONTENT_TYPE_VIDEO",
+                                    "contentImage": {
+                                        "thumbnailViewModel": {
+                                            "image": {
+                                                "sources": [
+                                                    { "url": "https://i.ytimg.com/vi/lockup00001/hqdefault.jpg" }
+                                                ]
+                                            },
+                                            "overlays": [
+                                                {
+                                                    "thumbnailOverlayBadgeViewModel": {
+                                                        "thumbnailBadges": [
+                                                            { "thumbnailBadgeViewModel": { "text": "1:02:03" } }
+                                                        ]
+                                                    }
+                                                }
+                                            ]
+                                        }
+                                    },
+                                    "metadata": {
+                                        "lockupMetadataViewModel": {
+                                            "title": { "content": "Lockup Video" },
+                                            "metadata": {
+                                                "contentMetadataViewModel": {
+                                                    "metadataRows": [
+                                                        { "metadataParts": [{ "text": { "content": "Channel C" } }] },
+                                                        {
+                                                            "metadataParts": [
+                                                                { "text": { "content": "1.2M views" } },
+                                                                { "text": { "content": "3 weeks ago" } }
+                                                            ]
+                                                        }
+                                                    ]
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            },
+                            {
+                                "lockupViewModel": {
+                                    "contentId": "PLmix",
+                                    "contentType": "LOCKUP_CONTENT_TYPE_PLAYLIST"
+                                }
+                            },
+                            {
+                                "compactVideoRenderer": {
+                                    "videoId": "compact0001",
+                                    "title": { "simpleText": "Compact Video" },
+                                    "lengthText": { "simpleText": "0:45" }
+                                }
+                            }
+                        ]
+                    }
+                }
+            }
+        },
+        "engagementPanels": [
+            {
+                "engagementPanelSectionListRenderer": {
+                    "panelIdentifier": "engagement-panel-structured-description",
+                    "header": {
+                        "engagementPanelTitleHeaderRenderer": {
+                            "title": { "runs": [{ "text": "Description" }] },
+                            "contextualInfo": { "runs": [{ "text": "99" }] }
+                        }
+                    }
+                }
+            },
+            {
+                "engagementPanelSectionListRenderer": {
+                    "panelIdentifier": "engagement-panel-comments-section",
+                    "header": {
+                        "engagementPanelTitleHeaderRenderer": {
+                            "title": { "runs": [{ "text": "Comments" }] },
+                            "contextualInfo": { "runs": [{ "text": "2.4K" }] }
+                        }
+                    }
+                }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_next_response_lockup_view_model() {
+        let response: WatchNextResponse = serde_json::from_str(NEXT_LOCKUP_FIXTURE).unwrap();
+        let related = response.related_videos();
+        let ids: Vec<&str> = related.iter().map(|r| r.video_id.as_str()).collect();
+        // The playlist entry is skipped; both renderer variants are kept
+        assert_eq!(ids, vec!["lockup00001", "compact0001"]);
+        assert_eq!(related[0].index, 1);
+        assert_eq!(related[0].title, "Lockup Video");
+        assert_eq!(related[0].author, "Channel C");
+        assert_eq!(related[0].duration, 3723);
+        assert_eq!(
+            related[0].thumbnail.as_deref(),
+            Some("https://i.ytimg.com/vi/lockup00001/hqdefault.jpg")
+        );
+        assert_eq!(related[1].index, 2);
+        assert_eq!(related[1].duration, 45);
+
+        let results = response.search_results();
+        assert_eq!(results[0].view_count, Some(1_200_000));
+        assert_eq!(results[0].published_at.as_deref(), Some("3 weeks ago"));
+
+        assert_eq!(response.comment_count(), Some(2_400));
+        assert_eq!(response.like_count(), Some(1_234));
+        assert_eq!(response.category().as_deref(), Some("Music"));
+
+        // The older renderer layout labels the count as "N likes"
+        let response: WatchNextResponse = serde_json::from_value(serde_json::json!({
+            "contents": { "twoColumnWatchNextResults": { "results": { "results": { "contents": [{
+                "videoPrimaryInfoRenderer": { "videoActions": { "menuRenderer": {
+                    "topLevelButtons": [
+                        { "buttonRenderer": {} },
+                        { "segmentedLikeDislikeButtonRenderer": { "likeButton": { "toggleButtonRenderer": {
+                            "defaultText": { "accessibility": { "accessibilityData": { "label": "56,789 likes" } } }
+                        } } } }
+                    ]
+                } } }
+            }] } } } }
+        }))
+        .unwrap();
+        assert_eq!(response.like_count(), Some(56_789));
+    }
+
+    #[test]
+    fn test_next_response_compact_renderer_metadata() {
+        let response: WatchNextResponse = serde_json::from_str(WATCH_NEXT_FIXTURE).unwrap();
+        let related = response.related_videos();
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[0].video_id, "autoplay001");
+        assert_eq!(related[0].author, "Channel A");
+        assert_eq!(related[1].duration, 720);
+
+        // Neither a comments panel, a like button nor a category row
+        assert_eq!(response.comment_count(), None);
+        assert_eq!(response.like_count(), None);
+        assert_eq!(response.category(), None);
+    }
+
+    #[test]
+    fn test_parse_approx_count() {
+        assert_eq!(parse_approx_count("1,234"), Some(1_234));
+        assert_eq!(parse_approx_count("2.4K"), Some(2_400));
+        assert_eq!(parse_approx_count("1.2M views"), Some(1_200_000));
+        assert_eq!(parse_approx_count("3B"), Some(3_000_000_000));
+        assert_eq!(parse_approx_count("12 views"), Some(12));
+        assert_eq!(parse_approx_count("No views"), Some(0));
+        assert_eq!(parse_approx_count(""), None);
+    }
+
+    #[test]
+    fn test_watch_next_response_renderer_alias() {
+        let response: WatchNextResponse = serde_json::from_str(
+            r#"{"contents": {"twoColumnWatchNextResults": {"secondaryResults": {
+                "secondaryResultsRenderer": {"results": [
+                    {"compactVideoRenderer": {"videoId": "abc"}}
+                ]}
+            }}}}"#,
+        )
+        .unwrap();
+        let results = response.search_results();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].video_id, "abc");
+        assert_eq!(results[0].title, "");
+
+        let response: WatchNextResponse = serde_json::from_str("{}").unwrap();
+        assert!(response.search_results().is_empty());
+    }
+
+    #[test]
+    fn test_check_playability() {
+        let check = |status: &str, reason: Option<&str>| {
+            let response: PlayerResponse = serde_json::from_value(serde_json::json!({
+                "playabilityStatus": { "status": status, "reason": reason }
+            }))
+            .unwrap();
+            response.check_playability().map(|_| ())
+        };
+
+        assert!(check("OK", None).is_ok());
+        assert!(matches!(
+            check("LOGIN_REQUIRED", Some("Sign in to confirm your age")),
+            Err(RytError::AgeRestricted)
+        ));
+        assert!(matches!(
+            check("UNPLAYABLE", Some("This video is private")),
+            Err(RytError::Private)
+        ));
+        assert!(matches!(
+            check(
+                "UNPLAYABLE",
+                Some("The following content may be inappropriate for some users.")
+            ),
+            Err(RytError::AgeRestricted)
+        ));
+        assert!(matches!(
+            check(
+                "ERROR",
+                Some("The uploader has not made this video available in your country")
+            ),
+            Err(RytError::GeoBlocked)
+        ));
+        assert!(matches!(
+            check("ERROR", None),
+            Err(RytError::VideoUnavailable)
+        ));
+        assert!(matches!(
+            check(
+                "UNPLAYABLE",
+                Some("Join this channel to get access to members-only content like this video, and other exclusive perks.")
+            ),
+            Err(RytError::MembersOnly)
+        ));
+        assert!(matches!(
+            check("UNPLAYABLE", Some("This video requires payment to watch.")),
+            Err(RytError::PaymentRequired { offer_text: None })
+        ));
+        // Other unplayable videos stay unavailable
+        assert!(matches!(
+            check("UNPLAYABLE", Some("This video is unavailable")),
+            Err(RytError::VideoUnavailable)
+        ));
+
+        // Takedowns keep YouTube's reason and are not mistaken for geo-blocks
+        for (status, reason) in [
+            (
+                "UNPLAYABLE",
+                "This video contains content from SME, who has blocked it in your country on copyright grounds.",
+            ),
+            (
+                "ERROR",
+                "This video is no longer available due to a copyright claim by Studio.",
+            ),
+            (
+                "UNPLAYABLE",
+                "This content is not available on this country domain due to a legal complaint from the government.",
+            ),
+        ] {
+            match check(status, Some(reason)) {
+                Err(RytError::Blocked { reason: actual }) => assert_eq!(actual, reason),
+                other => panic!("Expected Blocked for '{}', got {:?}", reason, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_playability_members_only_fixture() {
+        let response: PlayerResponse =
+            serde_json::from_str(include_str!("testdata/player_members_only.json")).unwrap();
+        let err = response.check_playability().unwrap_err();
+        assert!(matches!(err, RytError::MembersOnly), "{:?}", err);
+        assert!(!err.is_permanent());
+    }
+
+    #[test]
+    fn test_check_playability_payment_required_fixture() {
+        let response: PlayerResponse =
+            serde_json::from_str(include_str!("testdata/player_payment_required.json")).unwrap();
+        match response.check_playability() {
+            Err(RytError::PaymentRequired { offer_text }) => assert_eq!(
+                offer_text.as_deref(),
+                Some("Rent for $3.99 or buy for $9.99. Includes HD.")
+            ),
+            other => panic!("Expected PaymentRequired, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_playability_accessible_member_video() {
+        // A signed-in member gets a normal response for the same video
+        let response: PlayerResponse = serde_json::from_value(serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": {
+                "videoId": "memberVideo",
+                "title": "Members only stream",
+                "author": "Channel",
+                "lengthSeconds": "600",
+                "shortDescription": "",
+                "thumbnail": { "thumbnails": [] }
+            }
+        }))
+        .unwrap();
+        assert!(response.check_playability().is_ok());
+    }
+
+    #[test]
+    fn test_check_playability_fixture() {
+        let response: PlayerResponse =
+            serde_json::from_str(include_str!("testdata/player_copyright_blocked.json")).unwrap();
+        let err = response.check_playability().unwrap_err();
+        assert!(matches!(err, RytError::Blocked { .. }));
+        assert!(err.is_permanent());
+        assert_eq!(
+            err.to_string(),
+            "Video blocked: This video contains content from SME, who has blocked it on copyright grounds."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_player_response_retries_with_content_checks() {
+        let mut server = mockito::Server::new_async().await;
+        let warning = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("testdata/player_content_check.json"))
+            .expect(1)
+            .create_async()
+            .await;
+        let checked = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "videoId": "video1",
+                "contentCheckOk": true,
+                "racyCheckOk": true
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"playabilityStatus":{"status":"OK"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut client = InnerTubeClient::new()
+            .with_base_url(&server.url())
+            .with_api_key("test-key");
+        let response = client.get_player_response("video1").await.unwrap();
+        assert!(!response.needs_content_check());
+        assert_eq!(client.player_request_count(), 2);
+        warning.assert_async().await;
+        checked.assert_async().await;
+
+        let response: PlayerResponse =
+            serde_json::from_str(include_str!("testdata/player_content_check.json")).unwrap();
+        assert!(response.needs_content_check());
+        let response: PlayerResponse =
+            serde_json::from_str(include_str!("testdata/player_copyright_blocked.json")).unwrap();
+        assert!(!response.needs_content_check());
+    }
+
+    #[tokio::test]
+    async fn test_get_player_response_as_caches_per_video() {
+        let mut server = mockito::Server::new_async().await;
+        let player = |client: &str| {
+            server
+                .mock("POST", "/youtubei/v1/player")
+                .match_query(mockito::Matcher::Any)
+                .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                    "context": { "client": { "clientName": client } }
+                })))
+                .with_header("content-type", "application/json")
+                .with_body(r#"{"playabilityStatus":{"status":"OK"}}"#)
+        };
+        let ios = player("IOS").expect(2).create_async().await;
+
+        let mut client = InnerTubeClient::new()
+            .with_base_url(&server.url())
+            .with_api_key("test-key");
+        for _ in 0..2 {
+            client
+                .get_player_response_as("video1", ("IOS", "19.29.1"))
+                .await
+                .unwrap();
+        }
+        // The configured client is never switched
+        assert_eq!(client.client_name, "ANDROID");
+        assert_eq!(client.player_request_count(), 1);
+        assert!(client.cached_player_response("video1").is_some());
+        assert!(client.cached_player_response("video2").is_none());
+
+        // Another video gets its own entry next to the first
+        let web = player("WEB").expect(1).create_async().await;
+        client
+            .get_player_response_as("video2", ("IOS", "19.29.1"))
+            .await
+            .unwrap();
+        client
+            .get_player_response_as("video1", ("WEB", "2.20240726.00.00"))
+            .await
+            .unwrap();
+        assert_eq!(client.player_request_count(), 3);
+        assert!(client.cached_player_response("video2").is_some());
+        // The first response fetched for a video is the one returned
+        let cached = client.cached_player_response("video1").unwrap();
+        assert!(std::ptr::eq(cached, &client.player_cache["video1"][0].1));
+        assert_eq!(client.player_cache["video1"][0].0, "IOS");
+        client
+            .get_player_response_as("video1", ("IOS", "19.29.1"))
+            .await
+            .unwrap();
+        assert_eq!(client.player_request_count(), 3);
+
+        client.clear_player_cache();
+        assert!(client.cached_player_response("video1").is_none());
+        assert!(client.cached_player_response("video2").is_none());
+        ios.assert_async().await;
+        web.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_player_requests_share_one_response() {
+        let mut server = mockito::Server::new_async().await;
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"playabilityStatus":{"status":"OK"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = || {
+            InnerTubeClient::new()
+                .with_base_url(&server.url())
+                .with_api_key("test-key")
+        };
+        let (mut first, mut second) = (client(), client());
+        // The first future registers its request before the second is polled
+        let (a, b) = tokio::join!(
+            first.get_player_response("video1"),
+            second.get_player_response("video1")
+        );
+        assert!(a.is_ok());
+        assert!(b.is_ok());
+        assert_eq!(first.player_request_count(), 1);
+        assert_eq!(second.player_request_count(), 0);
+
+        // Right after it finished, the result is still shared
+        let mut third = client();
+        third.get_player_response("video1").await.unwrap();
+        assert_eq!(third.player_request_count(), 0);
+        player.assert_async().await;
+
+        // Clearing the player cache asks for a fresh response
+        let fresh = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"playabilityStatus":{"status":"OK"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        second.clear_player_cache();
+        second.get_player_response("video1").await.unwrap();
+        assert_eq!(second.player_request_count(), 1);
+        fresh.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_player_requests_share_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"playabilityStatus":{"status":"UNPLAYABLE","reason":"This video is private"}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = || {
+            InnerTubeClient::new()
+                .with_base_url(&server.url())
+                .with_api_key("test-key")
+        };
+        let (mut first, mut second) = (client(), client());
+        let (a, b) = tokio::join!(
+            first.get_player_response("video1"),
+            second.get_player_response("video1")
+        );
+        assert!(matches!(a, Err(RytError::Private)));
+        assert!(matches!(b, Err(RytError::Private)));
+
+        // A caller arriving after the request finished gets the same error
+        let mut third = client();
+        let c = third.get_player_response("video1").await;
+        assert!(matches!(c, Err(RytError::Private)));
+        assert_eq!(third.player_request_count(), 0);
+        player.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_player_requests_are_not_shared_across_cookies() {
+        let mut server = mockito::Server::new_async().await;
+        let player = |cookie: &str, body: &str| {
+            server
+                .mock("POST", "/youtubei/v1/player")
+                .match_query(mockito::Matcher::Any)
+                .match_header("cookie", cookie)
+                .with_header("content-type", "application/json")
+                .with_body(body)
+        };
+        let member = player("SID=member", r#"{"playabilityStatus":{"status":"OK"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let anonymous = player(
+            "SID=anonymous",
+            r#"{"playabilityStatus":{"status":"UNPLAYABLE","reason":"This video is private"}}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+        let client = |sid: &str| {
+            let jar = Arc::new(reqwest::cookie::Jar::default());
+            jar.add_cookie_str(&format!("SID={}", sid), &server.url().parse().unwrap());
+            InnerTubeClient::new()
+                .with_http_config(HttpClientConfig {
+                    cookie_jar: Some(jar),
+                    ..Default::default()
+                })
+                .with_base_url(&server.url())
+                .with_api_key("test-key")
+        };
+        let (mut first, mut second) = (client("member"), client("anonymous"));
+        let (a, b) = tokio::join!(
+            first.get_player_response("video1"),
+            second.get_player_response("video1")
+        );
+        assert!(a.is_ok());
+        assert!(matches!(b, Err(RytError::Private)));
+        assert_eq!(first.player_request_count(), 1);
+        assert_eq!(second.player_request_count(), 1);
+
+        // A client with the same cookies still shares the settled result
+        let mut third = client("member");
+        third.get_player_response("video1").await.unwrap();
+        assert_eq!(third.player_request_count(), 0);
+        member.assert_async().await;
+        anonymous.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_retryable_player_errors_are_not_settled() {
+        let mut server = mockito::Server::new_async().await;
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"playabilityStatus":{"status":"LOGIN_REQUIRED"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut client = InnerTubeClient::new()
+            .with_base_url(&server.url())
+            .with_api_key("test-key");
+        for _ in 0..2 {
+            let result = client.get_player_response("video1").await;
+            assert!(matches!(result, Err(RytError::AgeRestricted)));
+        }
+        assert_eq!(client.player_request_count(), 2);
+        player.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_visitor_data_is_echoed_on_later_requests() {
+        let mut server = mockito::Server::new_async().await;
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .match_header("x-goog-visitor-id", mockito::Matcher::Missing)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"responseContext":{"visitorData":"CgtGaXJzdFZpc2l0"},"playabilityStatus":{"status":"OK"}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let next = server
+            .mock("POST", "/youtubei/v1/next")
+            .match_query(mockito::Matcher::Any)
+            .match_header("x-goog-visitor-id", "CgtGaXJzdFZpc2l0")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"responseContext":{"visitorData":"Q2hhbmdlZA"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut client = InnerTubeClient::new()
+            .with_base_url(&server.url())
+            .with_api_key("test-key");
+        let response = client.get_player_response("video1").await.unwrap();
+        assert_eq!(response.visitor_data(), Some("CgtGaXJzdFZpc2l0"));
+        assert_eq!(client.visitor_data(), Some("CgtGaXJzdFZpc2l0"));
+
+        // Only the first response's visitor data is used
+        client.get_next_response("video1").await.unwrap();
+        client.get_next_response("video1").await.unwrap();
+        assert_eq!(client.visitor_data(), Some("CgtGaXJzdFZpc2l0"));
+        player.assert_async().await;
+        next.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_explicit_visitor_id_wins_over_response() {
+        let mut server = mockito::Server::new_async().await;
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .match_header("x-goog-visitor-id", "explicit")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"responseContext":{"visitorData":"fromResponse"},"playabilityStatus":{"status":"OK"}}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut client = InnerTubeClient::new()
+            .with_base_url(&server.url())
+            .with_api_key("test-key")
+            .with_visitor_id("explicit");
+        client.get_player_response("video1").await.unwrap();
+        client.get_player_response("video2").await.unwrap();
+        assert_eq!(client.visitor_data(), Some("explicit"));
+        player.assert_async().await;
+    }
+
+    /// Channel Playlists tab holding two playlists and a continuation item
+    fn channel_playlists_page() -> serde_json::Value {
+        serde_json::json!({
+            "contents": { "twoColumnBrowseResultsRenderer": { "tabs": [
+                { "tabRenderer": { "title": "Videos" } },
+                { "tabRenderer": { "selected": true, "content": { "sectionListRenderer": {
+                    "contents": [{ "itemSectionRenderer": { "contents": [{ "gridRenderer": {
+                        "items": [
+                            { "gridPlaylistRenderer": {
+                                "playlistId": "PLfirst",
+                                "title": { "runs": [{ "text": "First playlist" }] },
+                                "videoCountText": { "runs": [{ "text": "1,234" }, { "text": " videos" }] },
+                                "thumbnail": { "thumbnails": [
+                                    { "url": "https://i.ytimg.com/small.jpg", "width": 120, "height": 90 },
+                                    { "url": "https://i.ytimg.com/large.jpg", "width": 480, "height": 360 }
+                                ] }
+                            } },
+                            { "gridPlaylistRenderer": {
+                                "playlistId": "PLsecond",
+                                "title": { "simpleText": "Second" }
+                            } },
+                            { "continuationItemRenderer": { "continuationEndpoint": {
+                                "continuationCommand": { "token": "page2" }
+                            } } }
+                        ]
+                    } }] } }]
+                } } } }
+            ] } }
+        })
+    }
+
+    #[test]
+    fn test_channel_playlists_response() {
+        let response: ChannelPlaylistsResponse =
+            serde_json::from_value(channel_playlists_page()).unwrap();
+        let playlists = response.playlists();
+        assert_eq!(playlists.len(), 2);
+        assert_eq!(playlists[0].playlist_id, "PLfirst");
+        assert_eq!(playlists[0].title, "First playlist");
+        assert_eq!(playlists[0].video_count, Some(1234));
+        assert_eq!(
+            playlists[0].thumbnail.as_deref(),
+            Some("https://i.ytimg.com/large.jpg")
+        );
+        assert_eq!(
+            playlists[0].url(),
+            "https://www.youtube.com/playlist?list=PLfirst"
+        );
+        assert_eq!(playlists[1].title, "Second");
+        assert_eq!(playlists[1].video_count, None);
+        assert_eq!(response.continuation().as_deref(), Some("page2"));
+
+        // Continuation pages append items through a response action
+        let response: ChannelPlaylistsResponse = serde_json::from_value(serde_json::json!({
+            "onResponseReceivedActions": [{ "appendContinuationItemsAction": {
+                "continuationItems": [{ "gridPlaylistRenderer": { "playlistId": "PLthird" } }]
+            } }]
+        }))
+        .unwrap();
+        let playlists = response.playlists();
+        assert_eq!(playlists.len(), 1);
+        assert_eq!(playlists[0].playlist_id, "PLthird");
+        assert_eq!(response.continuation(), None);
+
+        let response: ChannelPlaylistsResponse =
+            serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(response.playlists().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_playlist_metadata() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/youtubei/v1/browse")
+            .match_query(mockito::Matcher::Any)
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({ "browseId": "VLPLxyz" }),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "metadata": { "playlistMetadataRenderer": { "title": "Road trip" } },
+                    "header": { "playlistHeaderRenderer": {
+                        "title": { "simpleText": "Header title" },
+                        "numVideosText": { "runs": [{ "text": "1,024" }, { "text": " videos" }] }
+                    } },
+                    "microformat": { "microformatDataRenderer": { "thumbnail": { "thumbnails": [
+                        { "url": "https://i.ytimg.com/small.jpg", "width": 120, "height": 90 },
+                        { "url": "https://i.ytimg.com/large.jpg", "width": 480, "height": 360 }
+                    ] } } }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let mut client = InnerTubeClient::new()
+            .with_base_url(&server.url())
+            .with_api_key("test-key");
+        let metadata = client.get_playlist_metadata("PLxyz").await.unwrap();
+        mock.assert_async().await;
+        assert_eq!(metadata.playlist_id, "PLxyz");
+        assert_eq!(metadata.title, "Road trip");
+        assert_eq!(metadata.video_count, Some(1024));
+        assert_eq!(
+            metadata.thumbnail.as_deref(),
+            Some("https://i.ytimg.com/large.jpg")
+        );
+
+        let response: PlaylistBrowseResponse =
+            serde_json::from_value(serde_json::json!({})).unwrap();
+        let metadata = response.to_playlist_metadata("PLempty");
+        assert_eq!(metadata.title, "");
+        assert_eq!(metadata.video_count, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_video_captions() {
+        let mut server = mockito::Server::new_async().await;
+        let base_url = format!("{}/api/timedtext?v=video1", server.url());
+        let player = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "playabilityStatus": { "status": "OK" },
+                    "captions": { "playerCaptionsTracklistRenderer": { "captionTracks": [
+                        { "baseUrl": format!("{}&lang=en&kind=asr", base_url), "languageCode": "en", "kind": "asr" },
+                        { "baseUrl": format!("{}&lang=en", base_url), "languageCode": "en" },
+                        { "baseUrl": format!("{}&lang=de", base_url), "languageCode": "de" }
+                    ] } }
+                })
+                .to_string(),
+            )
+            .expect(3)
+            .create_async()
+            .await;
+        let vtt = server
+            .mock("GET", "/api/timedtext")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("lang".into(), "en".into()),
+                mockito::Matcher::UrlEncoded("fmt".into(), "vtt".into()),
+            ]))
+            .with_body("WEBVTT\n\n00:00:00.000 --> 00:00:01.000\nHello\n")
+            .create_async()
+            .await;
+        let xml = server
+            .mock("GET", "/api/timedtext")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("lang".into(), "de".into()),
+                mockito::Matcher::UrlEncoded("fmt".into(), "srv1".into()),
+            ]))
+            .with_body(r#"<transcript><text start="0" dur="1">Hallo</text></transcript>"#)
+            .create_async()
+            .await;
+
+        let mut client = InnerTubeClient::new()
+            .with_base_url(&server.url())
+            .with_api_key("test-key");
+        let captions = client
+            .get_video_captions_as("video1", "en", CaptionFormat::Vtt)
+            .await
+            .unwrap();
+        assert!(captions.starts_with("WEBVTT"));
+        let captions = client.get_video_captions("video1", "de").await.unwrap();
+        assert!(captions.contains("Hallo"));
+        let result = client.get_video_captions("video1", "fr").await;
+        assert!(matches!(result, Err(RytError::NoCaptions(lang)) if lang == "fr"));
+        player.assert_async().await;
+        vtt.assert_async().await;
+        xml.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_channel_playlists_follows_continuations() {
+        let mut server = mockito::Server::new_async().await;
+        let first = server
+            .mock("POST", "/youtubei/v1/browse")
+            .match_query(mockito::Matcher::Any)
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "browseId": "UCchannel",
+                "params": CHANNEL_PLAYLISTS_PARAMS,
+                "context": { "client": { "clientName": "WEB" } }
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(channel_playlists_page().to_string())
+            .expect(2)
+            .create_async()
+            .await;
+        let second = server
+            .mock("POST", "/youtubei/v1/browse")
+            .match_query(mockito::Matcher::Any)
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({ "continuation": "page2" }),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "onResponseReceivedActions": [{ "appendContinuationItemsAction": {
+                        "continuationItems": [{ "gridPlaylistRenderer": { "playlistId": "PLthird" } }]
+                    } }]
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut client = InnerTubeClient::new()
+            .with_base_url(&server.url())
+            .with_api_key("test-key");
+        let playlists = client
+            .get_channel_playlists("UCchannel", None)
+            .await
+            .unwrap();
+        let ids: Vec<_> = playlists.iter().map(|p| p.playlist_id.as_str()).collect();
+        assert_eq!(ids, ["PLfirst", "PLsecond", "PLthird"]);
+
+        // A limit reached on the first page needs no continuation request
+        let playlists = client
+            .get_channel_playlists("UCchannel", Some(1))
+            .await
+            .unwrap();
+        assert_eq!(playlists.len(), 1);
+        first.assert_async().await;
+        second.assert_async().await;
+    }
+
+    const EMBED_PAGE: &str = include_str!("testdata/embed_page.html");
+
+    #[test]
+    fn test_extract_initial_player_response_from_embed_page() {
+        let response = extract_initial_player_response(EMBED_PAGE).unwrap();
+        let details = response.details("dQw4w9WgXcQ").unwrap();
+        assert_eq!(details.title, "Braces {inside} and \"quotes\" \\ test");
+        assert_eq!(details.author, "Tester }{");
+
+        let formats = response.parse_formats().unwrap();
+        assert_eq!(formats.len(), 2);
+        assert_eq!(formats[0].itag, Itag(18));
+        assert_eq!(formats[1].itag, Itag(140));
+        assert!(formats[1].url.ends_with("&sig=}{"));
+    }
+
+    #[test]
+    fn test_parse_formats_reads_audio_tracks() {
+        let response: PlayerResponse = serde_json::from_value(serde_json::json!({
+            "streamingData": { "adaptiveFormats": [
+                {
+                    "itag": 140,
+                    "url": "https://rr1.googlevideo.com/videoplayback?itag=140&xtags=lang%3Den",
+                    "mimeType": "audio/mp4; codecs=\"mp4a.40.2\"",
+                    "audioTrack": {
+                        "displayName": "English (United States) original",
+                        "id": "en-US.4",
+                        "audioIsDefault": true
+                    }
+                },
+                {
+                    "itag": 140,
+                    "url": "https://rr1.googlevideo.com/videoplayback?itag=140&xtags=lang%3Dde",
+                    "mimeType": "audio/mp4; codecs=\"mp4a.40.2\"",
+                    "audioTrack": { "displayName": "German (Germany)", "id": "de-DE.3" }
+                },
+                {
+                    "itag": 137,
+                    "url": "https://rr1.googlevideo.com/videoplayback?itag=137",
+                    "mimeType": "video/mp4; codecs=\"avc1.640028\""
+                }
+            ] }
+        }))
+        .unwrap();
+
+        let formats = response.parse_formats().unwrap();
+        assert_eq!(formats[0].language.as_deref(), Some("en-US"));
+        assert!(formats[0].audio_is_default);
+        assert_eq!(
+            formats[0].note.as_deref(),
+            Some("English (United States) original")
+        );
+        assert_eq!(formats[1].language.as_deref(), Some("de-DE"));
+        assert!(!formats[1].audio_is_default);
+        assert_eq!(formats[2].language, None);
+        assert_eq!(formats[2].note, None);
+    }
+
+    #[test]
+    fn test_extract_json_object() {
+        // Nested objects and braces in strings, including escaped quotes
+        let html = r#"<script>var ytInitialPlayerResponse = {"a":{"b":"}\"{"},"c":[{}]};var x = 1;</script>"#;
+        assert_eq!(
+            extract_json_object(html, "ytInitialPlayerResponse"),
+            Some(r#"{"a":{"b":"}\"{"},"c":[{}]}"#)
+        );
+
+        // Mentions without an assignment are skipped
+        let html = r#"if (window.ytInitialPlayerResponse) {} window["ytInitialPlayerResponse"] = {"ok":true};"#;
+        assert_eq!(
+            extract_json_object(html, "ytInitialPlayerResponse"),
+            Some(r#"{"ok":true}"#)
+        );
+
+        // Unterminated or missing objects
+        assert_eq!(
+            extract_json_object(
+                r#"ytInitialPlayerResponse = {"a":"}"#,
+                "ytInitialPlayerResponse"
+            ),
+            None
+        );
+        assert_eq!(
+            extract_json_object("ytInitialPlayerResponse = null;", "ytInitialPlayerResponse"),
+            None
+        );
+        assert!(matches!(
+            extract_initial_player_response("<html></html>"),
+            Err(RytError::Generic(_))
+        ));
+    }
+
+    #[test]
+    fn test_web_client_context() {
+        let context = web_client_context(Some("de"));
+        assert_eq!(context["client"]["clientName"], "WEB");
+        assert_eq!(context["client"]["gl"], "DE");
+
+        let context = web_client_context(None);
+        assert!(context["client"].get("gl").is_none());
+    }
 }
@@ -5,9 +5,14 @@ pub mod cipher;
 pub mod client;
 pub mod formats;
 pub mod innertube;
+pub mod traffic;
+
+#[cfg(test)]
+mod testing;
 
 pub use botguard::*;
 pub use cipher::*;
 pub use client::*;
 pub use formats::*;
 pub use innertube::*;
+pub use traffic::*;
@@ -0,0 +1,217 @@
+//! HTTP traffic logging for `--print-traffic`
+//!
+//! Every request sent through a [`VideoClient`](crate::platform::VideoClient)
+//! with a [`TrafficLog`] is logged with its method, URL, headers and body, and
+//! every response with its status and headers. Cookies and keys are redacted,
+//! as are media URL signatures unless URL redaction is turned off.
+
+use crate::utils::loggable_url;
+use reqwest::header::HeaderMap;
+use reqwest::{Request, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use tracing::info;
+
+/// Default number of request body bytes logged
+pub const DEFAULT_TRAFFIC_BODY_LIMIT: usize = 1024;
+
+/// Headers whose values are never logged
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "set-cookie",
+    "x-goog-api-key",
+];
+
+/// Query parameters whose values are never logged
+const SENSITIVE_PARAMS: &[&str] = &["key"];
+
+/// Placeholder for redacted values
+const REDACTED: &str = "…";
+
+/// Logger for the HTTP exchanges of a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficLog {
+    body_limit: usize,
+}
+
+impl TrafficLog {
+    /// Create a traffic log showing up to `body_limit` bytes of request bodies
+    pub fn new(body_limit: usize) -> Self {
+        Self { body_limit }
+    }
+
+    /// Get the number of request body bytes logged
+    pub fn body_limit(&self) -> usize {
+        self.body_limit
+    }
+
+    /// Send a request, logging it and its response
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        for line in self.request_lines(&request) {
+            info!(target: "ryt::traffic", "{}", line);
+        }
+
+        let result = client.execute(request).await;
+        match &result {
+            Ok(response) => {
+                for line in response_lines(response) {
+                    info!(target: "ryt::traffic", "{}", line);
+                }
+            }
+            Err(e) => info!(target: "ryt::traffic", "< error: {}", e),
+        }
+        result
+    }
+
+    /// Format the request line, headers and truncated body
+    fn request_lines(&self, request: &Request) -> Vec<String> {
+        let mut lines = vec![format!(
+            "> {} {}",
+            request.method(),
+            redact_url(request.url().as_str())
+        )];
+        lines.extend(header_lines('>', request.headers()));
+        if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+            lines.push(format!(
+                "> body ({} bytes): {}",
+                body.len(),
+                truncate_body(body, self.body_limit)
+            ));
+        }
+        lines
+    }
+}
+
+/// Format the status line and headers of a response
+fn response_lines(response: &Response) -> Vec<String> {
+    let mut lines = vec![format!(
+        "< {:?} {} {}",
+        response.version(),
+        response.status(),
+        redact_url(response.url().as_str())
+    )];
+    lines.extend(header_lines('<', response.headers()));
+    lines
+}
+
+/// Format headers as `name: value` lines, redacting sensitive values
+fn header_lines(direction: char, headers: &HeaderMap) -> Vec<String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                Cow::Borrowed(REDACTED)
+            } else {
+                String::from_utf8_lossy(value.as_bytes())
+            };
+            format!("{} {}: {}", direction, name, value)
+        })
+        .collect()
+}
+
+/// Redact keys, and media URL signatures unless turned off
+fn redact_url(url: &str) -> String {
+    let url = loggable_url(url);
+    let Some((base, query)) = url.split_once('?') else {
+        return url.into_owned();
+    };
+    let query: Vec<Cow<'_, str>> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if SENSITIVE_PARAMS.contains(&key) => {
+                Cow::Owned(format!("{}={}", key, REDACTED))
+            }
+            _ => Cow::Borrowed(pair),
+        })
+        .collect();
+    format!("{}?{}", base, query.join("&"))
+}
+
+/// Show at most `limit` bytes of a body, marking the cut
+fn truncate_body(body: &[u8], limit: usize) -> String {
+    let shown = String::from_utf8_lossy(&body[..body.len().min(limit)]);
+    if body.len() > limit {
+        format!("{}…", shown)
+    } else {
+        shown.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::testing::Captured;
+
+    #[tokio::test]
+    async fn test_send_logs_request_and_response() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/youtubei/v1/player")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_header("set-cookie", "VISITOR_INFO1_LIVE=secret")
+            .with_body("{}")
+            .create_async()
+            .await;
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let request = reqwest::Client::new()
+            .post(format!("{}/youtubei/v1/player?key=apikey", server.url()))
+            .header("Cookie", "SID=secret")
+            .header("X-Goog-Visitor-Id", "visitor")
+            .body(r#"{"videoId":"dQw4w9WgXcQ","context":{}}"#);
+        let response = TrafficLog::new(20).send(request).await.unwrap();
+        assert_eq!(response.status(), 200);
+        mock.assert_async().await;
+
+        let log = captured.contents();
+        assert!(log.contains("> POST http://"), "{}", log);
+        assert!(log.contains("/youtubei/v1/player?key=…"), "{}", log);
+        assert!(log.contains("> x-goog-visitor-id: visitor"), "{}", log);
+        assert!(log.contains("> cookie: …"), "{}", log);
+        assert!(
+            log.contains(r#"> body (38 bytes): {"videoId":"dQw4w9Wg…"#),
+            "{}",
+            log
+        );
+        assert!(log.contains("< HTTP/1.1 200 OK"), "{}", log);
+        assert!(log.contains("< set-cookie: …"), "{}", log);
+        assert!(!log.contains("secret"), "{}", log);
+        assert!(!log.contains("apikey"), "{}", log);
+    }
+
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url("https://www.youtube.com/youtubei/v1/player?key=abc&prettyPrint=false"),
+            "https://www.youtube.com/youtubei/v1/player?key=…&prettyPrint=false"
+        );
+        assert_eq!(
+            redact_url("https://rr1.googlevideo.com/videoplayback?itag=18&sig=abc"),
+            "https://rr1.googlevideo.com/videoplayback?itag=18&sig=…"
+        );
+        assert_eq!(
+            redact_url("https://www.youtube.com/"),
+            "https://www.youtube.com/"
+        );
+    }
+
+    #[test]
+    fn test_truncate_body() {
+        assert_eq!(truncate_body(b"short", 10), "short");
+        assert_eq!(truncate_body(b"exactly10!", 10), "exactly10!");
+        assert_eq!(truncate_body(b"a longer body", 8), "a longer…");
+        assert_eq!(truncate_body(b"anything", 0), "…");
+    }
+}
@@ -0,0 +1,412 @@
+//! Download archive of already downloaded videos
+//!
+//! The plain format stores one `youtube <id>` line per video. The extended
+//! format appends when the video was downloaded and in which format:
+//! `youtube <id> <iso8601-timestamp> <itag> <height>`. Both are accepted when
+//! reading, and extra columns are ignored.
+//!
+//! A [`PlaylistArchive`] tracks the progress of one playlist instead, with
+//! `youtube <id> <title>` lines so the file is readable at a glance.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extractor name written for YouTube videos
+pub const ARCHIVE_EXTRACTOR: &str = "youtube";
+
+/// Archive line format used when recording downloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArchiveFormat {
+    /// `youtube <id>`
+    #[default]
+    Plain,
+    /// `youtube <id> <iso8601-timestamp> <itag> <height>`
+    Extended,
+}
+
+/// Single download archive entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Extractor name (always `youtube` for entries written by ryt)
+    pub extractor: String,
+    /// Video ID
+    pub video_id: String,
+    /// When the video was downloaded
+    pub downloaded_at: Option<DateTime<Utc>>,
+    /// Itag of the downloaded format
+    pub itag: Option<u32>,
+    /// Height of the downloaded format
+    pub height: Option<u32>,
+}
+
+impl ArchiveEntry {
+    /// Create a new entry for a YouTube video
+    pub fn new(video_id: impl Into<String>) -> Self {
+        Self {
+            extractor: ARCHIVE_EXTRACTOR.to_string(),
+            video_id: video_id.into(),
+            downloaded_at: None,
+            itag: None,
+            height: None,
+        }
+    }
+
+    /// Set download time
+    pub fn with_downloaded_at(mut self, downloaded_at: DateTime<Utc>) -> Self {
+        self.downloaded_at = Some(downloaded_at);
+        self
+    }
+
+    /// Set downloaded format
+    pub fn with_format(mut self, itag: u32, height: Option<u32>) -> Self {
+        self.itag = Some(itag);
+        self.height = height;
+        self
+    }
+
+    /// Parse an archive line in either format.
+    ///
+    /// Unparseable extended columns are treated as unknown. Returns `None`
+    /// for blank lines and lines without a video ID.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut columns = line.split_whitespace();
+        let extractor = columns.next()?;
+        let video_id = columns.next()?;
+
+        let downloaded_at = columns
+            .next()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc));
+        let itag = columns.next().and_then(|itag| itag.parse().ok());
+        let height = columns
+            .next()
+            .and_then(|height| height.parse().ok())
+            .filter(|&height| height > 0);
+
+        Some(Self {
+            extractor: extractor.to_string(),
+            video_id: video_id.to_string(),
+            downloaded_at,
+            itag,
+            height,
+        })
+    }
+
+    /// Format the entry as an archive line (without newline).
+    ///
+    /// Unknown extended columns are written as `-`, or `0` for the height.
+    pub fn to_line(&self, format: ArchiveFormat) -> String {
+        match format {
+            ArchiveFormat::Plain => format!("{} {}", self.extractor, self.video_id),
+            ArchiveFormat::Extended => format!(
+                "{} {} {} {} {}",
+                self.extractor,
+                self.video_id,
+                self.downloaded_at
+                    .map(|ts| ts.to_rfc3339_opts(SecondsFormat::Secs, true))
+                    .unwrap_or_else(|| "-".to_string()),
+                self.itag
+                    .map(|itag| itag.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                self.height.unwrap_or(0)
+            ),
+        }
+    }
+
+    /// Check if the entry counts as downloaded for a minimum height.
+    ///
+    /// Entries without a recorded height (e.g. plain lines) always count.
+    pub fn satisfies(&self, min_height: Option<u32>) -> bool {
+        match (min_height, self.height) {
+            (Some(min_height), Some(height)) => height >= min_height,
+            _ => true,
+        }
+    }
+}
+
+/// Download archive backed by a file
+#[derive(Debug, Clone)]
+pub struct DownloadArchive {
+    path: PathBuf,
+    format: ArchiveFormat,
+    entries: HashMap<String, ArchiveEntry>,
+}
+
+impl DownloadArchive {
+    /// Load an archive, starting empty if the file does not exist
+    pub fn load(path: impl Into<PathBuf>, format: ArchiveFormat) -> io::Result<Self> {
+        let path = path.into();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            entries: parse_archive(&content),
+            path,
+            format,
+        })
+    }
+
+    /// Get the archive file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the entry for a video
+    pub fn get(&self, video_id: &str) -> Option<&ArchiveEntry> {
+        self.entries.get(video_id)
+    }
+
+    /// Check if a video is archived at `min_height` or better
+    pub fn is_done(&self, video_id: &str, min_height: Option<u32>) -> bool {
+        self.get(video_id)
+            .is_some_and(|entry| entry.satisfies(min_height))
+    }
+
+    /// Get the number of archived videos
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the archive is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Append an entry to the archive file in the archive's format
+    pub fn record(&mut self, entry: ArchiveEntry) -> io::Result<()> {
+        append_line(&self.path, &entry.to_line(self.format))?;
+        self.entries.insert(entry.video_id.clone(), entry);
+        Ok(())
+    }
+}
+
+/// Playlist progress file recording each downloaded item with its title
+#[derive(Debug, Clone)]
+pub struct PlaylistArchive {
+    path: PathBuf,
+    video_ids: HashSet<String>,
+}
+
+impl PlaylistArchive {
+    /// Load a playlist archive, starting empty if the file does not exist
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            video_ids: parse_archive(&content).into_keys().collect(),
+            path,
+        })
+    }
+
+    /// Get the archive file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check if a video has been downloaded
+    pub fn contains(&self, video_id: &str) -> bool {
+        self.video_ids.contains(video_id)
+    }
+
+    /// Get the number of downloaded videos
+    pub fn len(&self) -> usize {
+        self.video_ids.len()
+    }
+
+    /// Check if the archive is empty
+    pub fn is_empty(&self) -> bool {
+        self.video_ids.is_empty()
+    }
+
+    /// Append a `youtube <id> <title>` line for a downloaded video
+    pub fn record(&mut self, video_id: &str, title: &str) -> io::Result<()> {
+        // Keep the title on one line
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        let line = format!("{} {} {}", ARCHIVE_EXTRACTOR, video_id, title);
+        append_line(&self.path, line.trim_end())?;
+        self.video_ids.insert(video_id.to_string());
+        Ok(())
+    }
+}
+
+/// Append a line to a file, creating it and its parent directories
+fn append_line(path: &Path, line: &str) -> io::Result<()> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)
+}
+
+/// Parse archive content into entries by video ID; later lines win
+pub fn parse_archive(content: &str) -> HashMap<String, ArchiveEntry> {
+    content
+        .lines()
+        .filter_map(ArchiveEntry::parse)
+        .map(|entry| (entry.video_id.clone(), entry))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn timestamp() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_entry_round_trip_extended() {
+        let entry = ArchiveEntry::new("dQw4w9WgXcQ")
+            .with_downloaded_at(timestamp())
+            .with_format(18, Some(360));
+        let line = entry.to_line(ArchiveFormat::Extended);
+        assert_eq!(line, "youtube dQw4w9WgXcQ 2024-05-01T12:30:00Z 18 360");
+        assert_eq!(ArchiveEntry::parse(&line), Some(entry));
+    }
+
+    #[test]
+    fn test_entry_round_trip_plain() {
+        let entry = ArchiveEntry::new("dQw4w9WgXcQ");
+        let line = entry.to_line(ArchiveFormat::Plain);
+        assert_eq!(line, "youtube dQw4w9WgXcQ");
+        assert_eq!(ArchiveEntry::parse(&line), Some(entry));
+
+        // Plain lines drop the extended columns
+        let entry = entry.with_format(22, Some(720));
+        assert_eq!(entry.to_line(ArchiveFormat::Plain), "youtube dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn test_entry_unknown_columns() {
+        let entry = ArchiveEntry::new("abc").with_downloaded_at(timestamp());
+        let line = entry.to_line(ArchiveFormat::Extended);
+        assert_eq!(line, "youtube abc 2024-05-01T12:30:00Z - 0");
+        assert_eq!(ArchiveEntry::parse(&line), Some(entry));
+
+        let parsed = ArchiveEntry::parse("youtube abc garbage x y extra").unwrap();
+        assert_eq!(parsed.video_id, "abc");
+        assert_eq!(parsed.downloaded_at, None);
+        assert_eq!(parsed.itag, None);
+        assert_eq!(parsed.height, None);
+
+        assert_eq!(ArchiveEntry::parse(""), None);
+        assert_eq!(ArchiveEntry::parse("youtube"), None);
+    }
+
+    #[test]
+    fn test_parse_mixed_archive() {
+        let content = "youtube aaa\n\
+                       youtube bbb 2024-05-01T12:30:00Z 18 360 extra columns\n\
+                       \n\
+                       youtube ccc 2024-05-01T14:30:00+02:00 137 1080\n\
+                       youtube aaa 2024-06-01T00:00:00Z 22 720\n";
+        let entries = parse_archive(content);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries["aaa"].height, Some(720));
+        assert_eq!(entries["bbb"].itag, Some(18));
+        assert_eq!(entries["bbb"].downloaded_at, Some(timestamp()));
+        assert_eq!(entries["ccc"].downloaded_at, Some(timestamp()));
+        assert_eq!(entries["ccc"].height, Some(1080));
+    }
+
+    #[test]
+    fn test_satisfies_min_height() {
+        let plain = ArchiveEntry::new("a");
+        let low = ArchiveEntry::new("b").with_format(18, Some(360));
+        let high = ArchiveEntry::new("c").with_format(137, Some(1080));
+
+        assert!(plain.satisfies(Some(720)));
+        assert!(low.satisfies(None));
+        assert!(!low.satisfies(Some(720)));
+        assert!(high.satisfies(Some(720)));
+        assert!(low.satisfies(Some(360)));
+    }
+
+    #[test]
+    fn test_download_archive_load_and_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.txt");
+        std::fs::write(&path, "youtube old\n").unwrap();
+
+        let mut archive = DownloadArchive::load(&path, ArchiveFormat::Extended).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert!(archive.is_done("old", Some(1080)));
+        assert!(!archive.is_done("new", None));
+
+        archive
+            .record(
+                ArchiveEntry::new("new")
+                    .with_downloaded_at(timestamp())
+                    .with_format(18, Some(360)),
+            )
+            .unwrap();
+        assert!(archive.is_done("new", None));
+        assert!(!archive.is_done("new", Some(720)));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "youtube old\nyoutube new 2024-05-01T12:30:00Z 18 360\n"
+        );
+
+        let reloaded = DownloadArchive::load(&path, ArchiveFormat::Plain).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.get("new").unwrap().height, Some(360));
+    }
+
+    #[test]
+    fn test_download_archive_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sub").join("archive.txt");
+
+        let mut archive = DownloadArchive::load(&path, ArchiveFormat::Plain).unwrap();
+        assert!(archive.is_empty());
+
+        archive.record(ArchiveEntry::new("abc")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "youtube abc\n");
+    }
+
+    #[test]
+    fn test_playlist_archive_load_and_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("playlists").join("progress.txt");
+
+        let mut archive = PlaylistArchive::load(&path).unwrap();
+        assert!(archive.is_empty());
+        archive.record("first", "Top 10 Rust tips").unwrap();
+        archive.record("second", "Multi\nline  title ").unwrap();
+        archive.record("third", "").unwrap();
+        assert!(archive.contains("first"));
+        assert!(!archive.contains("fourth"));
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "youtube first Top 10 Rust tips\nyoutube second Multi line title\nyoutube third\n"
+        );
+
+        let reloaded = PlaylistArchive::load(&path).unwrap();
+        assert_eq!(reloaded.len(), 3);
+        assert!(reloaded.contains("second"));
+        assert!(reloaded.contains("third"));
+        assert_eq!(reloaded.path(), path);
+    }
+}
@@ -3,6 +3,8 @@
 use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -52,6 +54,13 @@ where
         );
     }
 
+    pub fn contains_key(&self, key: &K) -> bool {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key)
+            .is_some_and(|cached_value| cached_value.expires_at > Instant::now())
+    }
+
     pub fn remove(&self, key: &K) -> Option<V> {
         let mut cache = self.cache.lock().unwrap();
         cache.remove(key).map(|cached_value| cached_value.value)
@@ -106,6 +115,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::RytError;
     use std::thread;
     use std::time::Duration;
 
@@ -321,6 +331,83 @@ mod tests {
         let _stats = cache.get_stats();
     }
 
+    #[tokio::test]
+    async fn test_multi_level_cache_get_or_fetch_player_js_coalesces() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let cache = MultiLevelCache::new();
+        let fetches = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                let fetches = fetches.clone();
+                tokio::spawn(async move {
+                    cache
+                        .get_or_fetch_player_js("player_url", async move {
+                            fetches.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok("player_js_content".to_string())
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), "player_js_content");
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            cache.get_player_js("player_url").await,
+            Some("player_js_content".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multi_level_cache_get_or_fetch_player_js_shares_error_variant() {
+        let cache = MultiLevelCache::new();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move {
+                    cache
+                        .get_or_fetch_player_js("player_url", async {
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Err(RytError::RateLimited)
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        // Every waiter gets the typed error, not just the one that fetched
+        for task in tasks {
+            let err = task.await.unwrap().unwrap_err();
+            assert!(matches!(err, RytError::RateLimited), "{:?}", err);
+            assert!(err.is_retryable());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multi_level_cache_get_or_fetch_player_js_error_not_cached() {
+        let cache = MultiLevelCache::new();
+
+        let result = cache
+            .get_or_fetch_player_js("player_url", async {
+                Err(RytError::CipherError("fetch failed".to_string()))
+            })
+            .await;
+        assert!(matches!(result, Err(RytError::CipherError(_))));
+        assert_eq!(cache.get_player_js("player_url").await, None);
+
+        let result = cache
+            .get_or_fetch_player_js("player_url", async { Ok("content".to_string()) })
+            .await;
+        assert_eq!(result.unwrap(), "content");
+    }
+
     #[test]
     fn test_cache_stats_serialization() {
         let stats = CacheStats {
@@ -328,6 +415,10 @@ mod tests {
             signature_entries: 20,
             visitor_id_entries: 30,
             botguard_entries: 40,
+            player_js_hits: 1,
+            player_js_misses: 2,
+            signature_hits: 3,
+            signature_misses: 4,
         };
 
         // Test serialization
@@ -343,6 +434,48 @@ mod tests {
         assert_eq!(deserialized.signature_entries, 20);
         assert_eq!(deserialized.visitor_id_entries, 30);
         assert_eq!(deserialized.botguard_entries, 40);
+        assert_eq!(deserialized, stats);
+
+        // Stats serialized before the hit/miss counters existed still parse
+        let old: CacheStats = serde_json::from_str(
+            r#"{"player_js_entries":1,"signature_entries":2,"visitor_id_entries":3,"botguard_entries":4}"#,
+        )
+        .unwrap();
+        assert_eq!(old.player_js_hits, 0);
+        assert_eq!(old.signature_misses, 0);
+    }
+
+    #[tokio::test]
+    async fn test_multi_level_cache_hit_miss_counters() {
+        let cache = MultiLevelCache::new();
+
+        assert_eq!(cache.get_player_js("url").await, None);
+        cache.set_player_js("url", "content".to_string()).await;
+        assert!(cache.get_player_js("url").await.is_some());
+        assert!(cache.get_player_js("url").await.is_some());
+
+        assert_eq!(cache.get_signature("sig").await, None);
+        assert_eq!(cache.get_signature("sig").await, None);
+        cache.set_signature("sig", "deciphered".to_string()).await;
+        assert!(cache.get_signature("sig").await.is_some());
+
+        // Other caches don't count towards the player.js/signature counters
+        let _ = cache.get_visitor_id("key").await;
+
+        let stats = cache.get_stats();
+        assert_eq!(stats.player_js_hits, 2);
+        assert_eq!(stats.player_js_misses, 1);
+        assert_eq!(stats.signature_hits, 1);
+        assert_eq!(stats.signature_misses, 2);
+
+        // Clones share counters, and clearing keeps them
+        let clone = cache.clone();
+        assert_eq!(clone.get_player_js("other").await, None);
+        cache.clear_all().await;
+        assert_eq!(cache.get_player_js("url").await, None);
+        let stats = cache.get_stats();
+        assert_eq!(stats.player_js_hits, 2);
+        assert_eq!(stats.player_js_misses, 3);
     }
 }
 
@@ -357,6 +490,24 @@ pub struct MultiLevelCache {
     visitor_id_cache: Arc<Cache<String, String>>,
     /// Botguard token cache (30 minutes)
     botguard_cache: Arc<Cache<String, String>>,
+    /// Lookup hit/miss counters
+    counters: Arc<CacheCounters>,
+}
+
+/// Hit/miss counters for cache lookups
+#[derive(Debug, Default)]
+struct CacheCounters {
+    player_js_hits: AtomicU64,
+    player_js_misses: AtomicU64,
+    signature_hits: AtomicU64,
+    signature_misses: AtomicU64,
+}
+
+/// Count a lookup as a hit or miss, passing the result through
+fn record_lookup<V>(result: Option<V>, hits: &AtomicU64, misses: &AtomicU64) -> Option<V> {
+    let counter = if result.is_some() { hits } else { misses };
+    counter.fetch_add(1, Ordering::Relaxed);
+    result
 }
 
 impl MultiLevelCache {
@@ -383,12 +534,17 @@ impl MultiLevelCache {
                     .time_to_live(Duration::from_secs(1800)) // 30 minutes
                     .build(),
             ),
+            counters: Arc::new(CacheCounters::default()),
         }
     }
 
     /// Get player.js content
     pub async fn get_player_js(&self, url: &str) -> Option<String> {
-        self.player_js_cache.get(url).await
+        record_lookup(
+            self.player_js_cache.get(url).await,
+            &self.counters.player_js_hits,
+            &self.counters.player_js_misses,
+        )
     }
 
     /// Set player.js content
@@ -396,9 +552,28 @@ impl MultiLevelCache {
         self.player_js_cache.insert(url.to_string(), content).await;
     }
 
+    /// Get player.js content, running `fetch` on a miss.
+    ///
+    /// Concurrent callers for the same URL share a single in-flight fetch,
+    /// and each gets its own copy of a failed fetch's error; failed fetches
+    /// are not cached.
+    pub async fn get_or_fetch_player_js<F>(&self, url: &str, fetch: F) -> crate::Result<String>
+    where
+        F: Future<Output = crate::Result<String>>,
+    {
+        self.player_js_cache
+            .try_get_with(url.to_string(), fetch)
+            .await
+            .map_err(|err| Arc::try_unwrap(err).unwrap_or_else(|shared| shared.replicate()))
+    }
+
     /// Get signature
     pub async fn get_signature(&self, signature: &str) -> Option<String> {
-        self.signature_cache.get(signature).await
+        record_lookup(
+            self.signature_cache.get(signature).await,
+            &self.counters.signature_hits,
+            &self.counters.signature_misses,
+        )
     }
 
     /// Set signature
@@ -430,8 +605,14 @@ impl MultiLevelCache {
         self.botguard_cache.insert(key.to_string(), token).await;
     }
 
-    /// Clear all caches
+    /// Clear all caches; hit/miss counters are kept
     pub async fn clear_all(&self) {
+        self.invalidate_all();
+    }
+
+    /// Clear all caches without waiting, for callers outside async code;
+    /// hit/miss counters are kept
+    pub fn invalidate_all(&self) {
         self.player_js_cache.invalidate_all();
         self.signature_cache.invalidate_all();
         self.visitor_id_cache.invalidate_all();
@@ -445,17 +626,29 @@ impl MultiLevelCache {
             signature_entries: self.signature_cache.entry_count(),
             visitor_id_entries: self.visitor_id_cache.entry_count(),
             botguard_entries: self.botguard_cache.entry_count(),
+            player_js_hits: self.counters.player_js_hits.load(Ordering::Relaxed),
+            player_js_misses: self.counters.player_js_misses.load(Ordering::Relaxed),
+            signature_hits: self.counters.signature_hits.load(Ordering::Relaxed),
+            signature_misses: self.counters.signature_misses.load(Ordering::Relaxed),
         }
     }
 }
 
 /// Cache statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CacheStats {
     pub player_js_entries: u64,
     pub signature_entries: u64,
     pub visitor_id_entries: u64,
     pub botguard_entries: u64,
+    #[serde(default)]
+    pub player_js_hits: u64,
+    #[serde(default)]
+    pub player_js_misses: u64,
+    #[serde(default)]
+    pub signature_hits: u64,
+    #[serde(default)]
+    pub signature_misses: u64,
 }
 
 impl Default for MultiLevelCache {
@@ -0,0 +1,952 @@
+//! Cookies read from installed browsers for `--cookies-from-browser`
+//!
+//! Chromium-based browsers (Chrome, Chromium, Edge, Brave) and Firefox keep
+//! cookies in an SQLite database, Safari in a `Cookies.binarycookies` file.
+//! The database is copied before reading, since a running browser keeps it
+//! locked. Only YouTube and Google cookies are loaded.
+//!
+//! Chromium encrypts cookie values with AES-128-CBC under a key derived from
+//! a password in the system keyring (`v10`/`v11` values). Decrypting them
+//! needs the `cookie-decryption` feature; without it, or on Windows, where
+//! the key is protected by DPAPI, encrypted cookies are skipped.
+
+use crate::error::RytError;
+use crate::utils::path::home_dir;
+use reqwest::cookie::Jar;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// Browsers `--cookies-from-browser` can read
+pub const SUPPORTED_BROWSERS: &[&str] =
+    &["brave", "chrome", "chromium", "edge", "firefox", "safari"];
+
+/// Domains whose cookies are loaded
+const COOKIE_DOMAINS: &[&str] = &["youtube.com", "google.com"];
+
+/// Seconds from 1601-01-01, the Chromium epoch, to the Unix epoch
+const CHROMIUM_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// Seconds from the Unix epoch to 2001-01-01, the Mac epoch
+const MAC_EPOCH_OFFSET: f64 = 978_307_200.0;
+
+/// Cookie as stored by a browser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserCookie {
+    /// Host the cookie belongs to; a leading dot makes it a domain cookie
+    pub host: String,
+    pub name: String,
+    pub value: String,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    /// Expiry as Unix seconds, `None` for session cookies
+    pub expires: Option<i64>,
+}
+
+impl BrowserCookie {
+    /// Check if the cookie has expired at `now` (Unix seconds)
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Get the cookie as a `Set-Cookie` header value and the URL it is set
+    /// from, as [`Jar::add_cookie_str`] takes them
+    fn to_set_cookie(&self, now: i64) -> Option<(String, Url)> {
+        let domain = self.host.trim_start_matches('.');
+        let url = Url::parse(&format!("https://{}{}", domain, self.path)).ok()?;
+        let mut cookie = format!("{}={}; Path={}", self.name, self.value, self.path);
+        if self.host.starts_with('.') {
+            cookie.push_str(&format!("; Domain={}", domain));
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        if self.http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        if let Some(expires) = self.expires {
+            cookie.push_str(&format!("; Max-Age={}", expires - now));
+        }
+        Some((cookie, url))
+    }
+}
+
+/// Load the YouTube cookies of a browser into a cookie jar.
+///
+/// `browser` is a name from [`SUPPORTED_BROWSERS`], optionally followed by
+/// `:PROFILE`, a profile directory name or path, e.g. `chrome:Profile 1`.
+/// Without a profile, Chromium browsers use `Default` and Firefox the
+/// profile whose cookies changed last.
+pub fn extract_from_browser(browser: &str) -> Result<Jar, RytError> {
+    let (name, profile) = match browser.split_once(':') {
+        Some((name, profile)) => (name, Some(profile)),
+        None => (browser, None),
+    };
+    let name = name.to_ascii_lowercase();
+    let cookies = match name.as_str() {
+        "firefox" => read_firefox_cookies(&firefox_cookie_db(profile)?)?,
+        "safari" => read_safari_cookies(&std::fs::read(safari_cookie_file()?).map_err(|e| {
+            RytError::BrowserCookies(format!("cannot read Safari cookies: {}", e))
+        })?)?,
+        _ => {
+            let browser = ChromiumBrowser::from_name(&name).ok_or_else(|| {
+                RytError::BrowserCookies(format!(
+                    "unsupported browser '{}' (supported: {})",
+                    name,
+                    SUPPORTED_BROWSERS.join(", ")
+                ))
+            })?;
+            read_chromium_cookies(&browser.cookie_db(profile)?, &browser)?
+        }
+    };
+
+    let now = unix_now();
+    let jar = Jar::default();
+    let mut loaded = 0;
+    for cookie in cookies
+        .iter()
+        .filter(|cookie| is_cookie_domain(&cookie.host) && !cookie.is_expired(now))
+    {
+        if let Some((cookie, url)) = cookie.to_set_cookie(now) {
+            jar.add_cookie_str(&cookie, &url);
+            loaded += 1;
+        }
+    }
+    info!("Loaded {} cookies from {}", loaded, name);
+    Ok(jar)
+}
+
+/// Check if a cookie host is YouTube or Google, or a subdomain of them
+fn is_cookie_domain(host: &str) -> bool {
+    let host = host.trim_start_matches('.');
+    COOKIE_DOMAINS
+        .iter()
+        .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+}
+
+/// Get the current time as Unix seconds
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// Copy an SQLite database, and its write-ahead log, to a temporary
+/// directory and open the copy
+fn open_database_copy(path: &Path) -> Result<(tempfile::TempDir, rusqlite::Connection), RytError> {
+    let io_error = |e: std::io::Error| RytError::io(e, "copy cookie database", path);
+    let dir = tempfile::tempdir().map_err(io_error)?;
+    let copy = dir.path().join("cookies.sqlite");
+    std::fs::copy(path, &copy).map_err(io_error)?;
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    if Path::new(&wal).exists() {
+        std::fs::copy(&wal, dir.path().join("cookies.sqlite-wal")).map_err(io_error)?;
+    }
+    let connection = rusqlite::Connection::open(&copy).map_err(sqlite_error)?;
+    Ok((dir, connection))
+}
+
+/// Wrap an SQLite error
+fn sqlite_error(e: rusqlite::Error) -> RytError {
+    RytError::BrowserCookies(format!("cannot read cookie database: {}", e))
+}
+
+/// Get the directory applications keep their settings in
+fn app_data_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".config")))
+    }
+}
+
+/// Chromium-based browser and where it keeps its data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChromiumBrowser {
+    /// Data directory on Linux, relative to `~/.config`
+    linux_dir: &'static str,
+    /// Data directory on macOS, relative to `~/Library/Application Support`
+    mac_dir: &'static str,
+    /// Data directory on Windows, relative to `%LOCALAPPDATA%`
+    windows_dir: &'static str,
+    /// Name of the keyring entry holding the cookie password, without the
+    /// ` Safe Storage` suffix
+    keyring_name: &'static str,
+}
+
+impl ChromiumBrowser {
+    /// Look up a Chromium-based browser by its `--cookies-from-browser` name
+    fn from_name(name: &str) -> Option<Self> {
+        let (linux_dir, mac_dir, windows_dir, keyring_name) = match name {
+            "chrome" => (
+                "google-chrome",
+                "Google/Chrome",
+                "Google/Chrome/User Data",
+                "Chrome",
+            ),
+            "chromium" => ("chromium", "Chromium", "Chromium/User Data", "Chromium"),
+            "edge" => (
+                "microsoft-edge",
+                "Microsoft Edge",
+                "Microsoft/Edge/User Data",
+                if cfg!(target_os = "macos") {
+                    "Microsoft Edge"
+                } else {
+                    "Chromium"
+                },
+            ),
+            "brave" => (
+                "BraveSoftware/Brave-Browser",
+                "BraveSoftware/Brave-Browser",
+                "BraveSoftware/Brave-Browser/User Data",
+                "Brave",
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            linux_dir,
+            mac_dir,
+            windows_dir,
+            keyring_name,
+        })
+    }
+
+    /// Get the browser's data directory on this platform
+    fn data_dir(&self) -> Option<PathBuf> {
+        let relative = if cfg!(windows) {
+            self.windows_dir
+        } else if cfg!(target_os = "macos") {
+            self.mac_dir
+        } else {
+            self.linux_dir
+        };
+        Some(app_data_dir()?.join(relative))
+    }
+
+    /// Find the cookie database of a profile, `Default` if none is given
+    fn cookie_db(&self, profile: Option<&str>) -> Result<PathBuf, RytError> {
+        let profile_dir = match profile {
+            Some(profile) if Path::new(profile).is_absolute() => PathBuf::from(profile),
+            profile => self
+                .data_dir()
+                .ok_or_else(|| {
+                    RytError::BrowserCookies("cannot find the browser's data directory".into())
+                })?
+                .join(profile.unwrap_or("Default")),
+        };
+        // Newer versions keep cookies in the Network subdirectory
+        [
+            profile_dir.join("Network").join("Cookies"),
+            profile_dir.join("Cookies"),
+        ]
+        .into_iter()
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            RytError::BrowserCookies(format!("no cookie database in {}", profile_dir.display()))
+        })
+    }
+}
+
+/// Read the cookies of a Chromium cookie database
+fn read_chromium_cookies(
+    db: &Path,
+    browser: &ChromiumBrowser,
+) -> Result<Vec<BrowserCookie>, RytError> {
+    debug!("Reading Chromium cookies from {}", db.display());
+    let (_dir, connection) = open_database_copy(db)?;
+    // From version 24 the plaintext starts with the SHA-256 of the host
+    let version: i64 = connection
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .and_then(|version| version.parse().ok())
+        .unwrap_or(0);
+    let mut decryptor = ChromiumDecryptor::new(browser, version >= 24);
+
+    let mut statement = connection
+        .prepare(
+            "SELECT host_key, name, value, encrypted_value, path, expires_utc, is_secure, \
+             is_httponly FROM cookies",
+        )
+        .map_err(sqlite_error)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, bool>(7)?,
+            ))
+        })
+        .map_err(sqlite_error)?;
+
+    let mut cookies = Vec::new();
+    for row in rows {
+        let (host, name, value, encrypted, path, expires_utc, secure, http_only) =
+            row.map_err(sqlite_error)?;
+        if !is_cookie_domain(&host) {
+            continue;
+        }
+        let value = if value.is_empty() && !encrypted.is_empty() {
+            match decryptor.decrypt(&encrypted) {
+                Some(value) => value,
+                None => continue,
+            }
+        } else {
+            value
+        };
+        cookies.push(BrowserCookie {
+            host,
+            name,
+            value,
+            path,
+            secure,
+            http_only,
+            expires: chromium_expiry(expires_utc),
+        });
+    }
+    decryptor.report();
+    Ok(cookies)
+}
+
+/// Convert a Chromium expiry, microseconds since 1601, to Unix seconds
+fn chromium_expiry(expires_utc: i64) -> Option<i64> {
+    (expires_utc > 0).then(|| expires_utc / 1_000_000 - CHROMIUM_EPOCH_OFFSET)
+}
+
+/// Decryptor of Chromium's encrypted cookie values
+struct ChromiumDecryptor {
+    browser: ChromiumBrowser,
+    /// Strip the SHA-256 host hash that starts newer plaintexts
+    has_host_hash: bool,
+    /// Keys for `v10` and `v11` values, derived on first use
+    keys: [Option<Option<[u8; 16]>>; 2],
+    /// Number of values that couldn't be decrypted
+    skipped: usize,
+}
+
+impl ChromiumDecryptor {
+    fn new(browser: &ChromiumBrowser, has_host_hash: bool) -> Self {
+        Self {
+            browser: *browser,
+            has_host_hash,
+            keys: [None, None],
+            skipped: 0,
+        }
+    }
+
+    /// Decrypt a `v10`/`v11` value, counting it as skipped if that fails
+    fn decrypt(&mut self, encrypted: &[u8]) -> Option<String> {
+        let value = self.try_decrypt(encrypted);
+        if value.is_none() {
+            self.skipped += 1;
+        }
+        value
+    }
+
+    fn try_decrypt(&mut self, encrypted: &[u8]) -> Option<String> {
+        let (slot, version) = match encrypted.get(..3)? {
+            b"v10" => (0, "v10"),
+            b"v11" => (1, "v11"),
+            _ => return None,
+        };
+        let browser = self.browser;
+        let key = (*self.keys[slot].get_or_insert_with(|| chromium_key(&browser, version)))?;
+        let mut plaintext = aes_cbc_decrypt(&key, &encrypted[3..])?;
+        if self.has_host_hash {
+            plaintext = plaintext.get(32..)?.to_vec();
+        }
+        String::from_utf8(plaintext).ok()
+    }
+
+    /// Warn about values that couldn't be decrypted
+    fn report(&self) {
+        if self.skipped == 0 {
+            return;
+        }
+        if cfg!(feature = "cookie-decryption") && !cfg!(windows) {
+            warn!(
+                "Skipped {} cookies that could not be decrypted",
+                self.skipped
+            );
+        } else {
+            warn!(
+                "Skipped {} encrypted cookies; decryption needs the cookie-decryption feature \
+                 and is not supported on Windows",
+                self.skipped
+            );
+        }
+    }
+}
+
+/// Derive the key for `v10` or `v11` cookie values
+fn chromium_key(browser: &ChromiumBrowser, version: &str) -> Option<[u8; 16]> {
+    if cfg!(windows) {
+        // The key is protected by DPAPI
+        return None;
+    }
+    if cfg!(target_os = "macos") {
+        let password = command_output(
+            "security",
+            &[
+                "find-generic-password",
+                "-w",
+                "-a",
+                browser.keyring_name,
+                "-s",
+                &format!("{} Safe Storage", browser.keyring_name),
+            ],
+        )?;
+        return derive_key(password.as_bytes(), 1003);
+    }
+    // Linux: v10 uses a fixed password, v11 the one in the keyring, or an
+    // empty one when no keyring was available
+    let password = match version {
+        "v10" => "peanuts".to_string(),
+        _ => command_output(
+            "secret-tool",
+            &[
+                "lookup",
+                "application",
+                &browser.keyring_name.to_ascii_lowercase(),
+            ],
+        )
+        .unwrap_or_default(),
+    };
+    derive_key(password.as_bytes(), 1)
+}
+
+/// Run a command and get its trimmed output, `None` if it fails
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        debug!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Derive a cookie key from a password with PBKDF2-HMAC-SHA1
+#[cfg(feature = "cookie-decryption")]
+fn derive_key(password: &[u8], iterations: usize) -> Option<[u8; 16]> {
+    let mut key = [0u8; 16];
+    openssl::pkcs5::pbkdf2_hmac(
+        password,
+        b"saltysalt",
+        iterations,
+        openssl::hash::MessageDigest::sha1(),
+        &mut key,
+    )
+    .ok()?;
+    Some(key)
+}
+
+/// Derive a cookie key; needs the `cookie-decryption` feature
+#[cfg(not(feature = "cookie-decryption"))]
+fn derive_key(_password: &[u8], _iterations: usize) -> Option<[u8; 16]> {
+    None
+}
+
+/// Decrypt AES-128-CBC with Chromium's IV of 16 spaces and PKCS#7 padding
+#[cfg(feature = "cookie-decryption")]
+fn aes_cbc_decrypt(key: &[u8; 16], ciphertext: &[u8]) -> Option<Vec<u8>> {
+    openssl::symm::decrypt(
+        openssl::symm::Cipher::aes_128_cbc(),
+        key,
+        Some(&[b' '; 16][..]),
+        ciphertext,
+    )
+    .ok()
+}
+
+/// Decrypt a cookie value; needs the `cookie-decryption` feature
+#[cfg(not(feature = "cookie-decryption"))]
+fn aes_cbc_decrypt(_key: &[u8; 16], _ciphertext: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+/// Find the Firefox cookie database of a profile, or of the profile whose
+/// cookies changed last
+fn firefox_cookie_db(profile: Option<&str>) -> Result<PathBuf, RytError> {
+    if let Some(profile) = profile.filter(|profile| Path::new(profile).is_absolute()) {
+        return Ok(Path::new(profile).join("cookies.sqlite"));
+    }
+    let roots: Vec<PathBuf> = if cfg!(windows) {
+        std::env::var_os("APPDATA")
+            .map(|dir| {
+                PathBuf::from(dir)
+                    .join("Mozilla")
+                    .join("Firefox")
+                    .join("Profiles")
+            })
+            .into_iter()
+            .collect()
+    } else if cfg!(target_os = "macos") {
+        app_data_dir()
+            .map(|dir| dir.join("Firefox").join("Profiles"))
+            .into_iter()
+            .collect()
+    } else {
+        home_dir()
+            .map(|home| {
+                vec![
+                    home.join(".mozilla").join("firefox"),
+                    home.join("snap/firefox/common/.mozilla/firefox"),
+                ]
+            })
+            .unwrap_or_default()
+    };
+
+    roots
+        .iter()
+        .filter_map(|root| std::fs::read_dir(root).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            // Profile directories are named `<random>.<profile name>`
+            profile.map_or(true, |profile| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name == profile || name.ends_with(&format!(".{}", profile))
+            })
+        })
+        .map(|entry| entry.path().join("cookies.sqlite"))
+        .filter_map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .max()
+        .map(|(_, path)| path)
+        .ok_or_else(|| RytError::BrowserCookies("no Firefox profile with cookies found".into()))
+}
+
+/// Read the cookies of a Firefox cookie database
+fn read_firefox_cookies(db: &Path) -> Result<Vec<BrowserCookie>, RytError> {
+    debug!("Reading Firefox cookies from {}", db.display());
+    let (_dir, connection) = open_database_copy(db)?;
+    let mut statement = connection
+        .prepare("SELECT host, name, value, path, expiry, isSecure, isHttpOnly FROM moz_cookies")
+        .map_err(sqlite_error)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok(BrowserCookie {
+                host: row.get(0)?,
+                name: row.get(1)?,
+                value: row.get(2)?,
+                path: row.get(3)?,
+                expires: firefox_expiry(row.get(4)?),
+                secure: row.get(5)?,
+                http_only: row.get(6)?,
+            })
+        })
+        .map_err(sqlite_error)?;
+    rows.collect::<Result<_, _>>().map_err(sqlite_error)
+}
+
+/// Convert a Firefox expiry to Unix seconds. Newer versions store
+/// milliseconds; no expiry in seconds is that far in the future.
+fn firefox_expiry(expiry: i64) -> Option<i64> {
+    if expiry <= 0 {
+        None
+    } else if expiry > 100_000_000_000 {
+        Some(expiry / 1000)
+    } else {
+        Some(expiry)
+    }
+}
+
+/// Find Safari's cookie file
+fn safari_cookie_file() -> Result<PathBuf, RytError> {
+    let home = home_dir()
+        .ok_or_else(|| RytError::BrowserCookies("cannot find the home directory".into()))?;
+    [
+        home.join("Library/Containers/com.apple.Safari/Data/Library/Cookies/Cookies.binarycookies"),
+        home.join("Library/Cookies/Cookies.binarycookies"),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+    .ok_or_else(|| RytError::BrowserCookies("no Safari cookie file found".into()))
+}
+
+/// Parse Safari's `Cookies.binarycookies` format.
+///
+/// The file starts with `cook`, a big-endian page count and page sizes.
+/// Each page holds little-endian offsets of its cookie records, which hold
+/// flags, offsets of their strings and Mac epoch dates.
+fn read_safari_cookies(data: &[u8]) -> Result<Vec<BrowserCookie>, RytError> {
+    let invalid =
+        |reason: &str| RytError::BrowserCookies(format!("invalid Safari cookies: {}", reason));
+    if data.get(..4) != Some(b"cook") {
+        return Err(invalid("missing signature"));
+    }
+    let page_count = read_u32_be(data, 4).ok_or_else(|| invalid("truncated header"))? as usize;
+    let mut page_start = 8 + page_count * 4;
+    let mut cookies = Vec::new();
+    for i in 0..page_count {
+        let page_size =
+            read_u32_be(data, 8 + i * 4).ok_or_else(|| invalid("truncated header"))? as usize;
+        let page = data
+            .get(page_start..page_start + page_size)
+            .ok_or_else(|| invalid("truncated page"))?;
+        cookies.extend(read_safari_page(page).ok_or_else(|| invalid("malformed page"))?);
+        page_start += page_size;
+    }
+    Ok(cookies)
+}
+
+/// Parse the cookie records of a Safari cookie page
+fn read_safari_page(page: &[u8]) -> Option<Vec<BrowserCookie>> {
+    if page.get(..4)? != [0, 0, 1, 0] {
+        return None;
+    }
+    let count = read_u32_le(page, 4)? as usize;
+    (0..count)
+        .map(|i| {
+            let offset = read_u32_le(page, 8 + i * 4)? as usize;
+            let size = read_u32_le(page, offset)? as usize;
+            read_safari_record(page.get(offset..offset + size)?)
+        })
+        .collect()
+}
+
+/// Parse a Safari cookie record
+fn read_safari_record(record: &[u8]) -> Option<BrowserCookie> {
+    let flags = read_u32_le(record, 8)?;
+    let string = |field: usize| -> Option<String> {
+        let start = read_u32_le(record, field)? as usize;
+        let bytes = record.get(start..)?;
+        let end = bytes.iter().position(|&b| b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    };
+    let expiry = f64::from_le_bytes(record.get(40..48)?.try_into().ok()?);
+    Some(BrowserCookie {
+        host: string(16)?,
+        name: string(20)?,
+        path: string(24)?,
+        value: string(28)?,
+        secure: flags & 1 != 0,
+        http_only: flags & 4 != 0,
+        expires: (expiry > 0.0).then(|| (expiry + MAC_EPOCH_OFFSET) as i64),
+    })
+}
+
+/// Read a big-endian `u32` at an offset
+fn read_u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Read a little-endian `u32` at an offset
+fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::cookie::CookieStore;
+
+    fn cookie(host: &str, name: &str, expires: Option<i64>) -> BrowserCookie {
+        BrowserCookie {
+            host: host.to_string(),
+            name: name.to_string(),
+            value: "value".to_string(),
+            path: "/".to_string(),
+            secure: true,
+            http_only: true,
+            expires,
+        }
+    }
+
+    #[test]
+    fn test_is_cookie_domain() {
+        assert!(is_cookie_domain(".youtube.com"));
+        assert!(is_cookie_domain("www.youtube.com"));
+        assert!(is_cookie_domain(".google.com"));
+        assert!(is_cookie_domain("accounts.google.com"));
+        assert!(!is_cookie_domain(".notyoutube.com"));
+        assert!(!is_cookie_domain("example.com"));
+    }
+
+    #[test]
+    fn test_to_set_cookie() {
+        let (header, url) = cookie(".youtube.com", "SID", Some(1_100))
+            .to_set_cookie(1_000)
+            .unwrap();
+        assert_eq!(
+            header,
+            "SID=value; Path=/; Domain=youtube.com; Secure; HttpOnly; Max-Age=100"
+        );
+        assert_eq!(url.as_str(), "https://youtube.com/");
+
+        // Host-only session cookie
+        let mut session = cookie("www.youtube.com", "PREF", None);
+        session.secure = false;
+        session.http_only = false;
+        let (header, url) = session.to_set_cookie(1_000).unwrap();
+        assert_eq!(header, "PREF=value; Path=/");
+        assert_eq!(url.as_str(), "https://www.youtube.com/");
+    }
+
+    #[test]
+    fn test_cookies_reach_jar() {
+        let jar = Jar::default();
+        let now = unix_now();
+        for cookie in [
+            cookie(".youtube.com", "SID", Some(now + 3600)),
+            cookie("www.youtube.com", "PREF", None),
+        ] {
+            let (header, url) = cookie.to_set_cookie(now).unwrap();
+            jar.add_cookie_str(&header, &url);
+        }
+        let url = Url::parse("https://www.youtube.com/youtubei/v1/player").unwrap();
+        let header = jar.cookies(&url).unwrap();
+        let header = header.to_str().unwrap();
+        assert!(header.contains("SID=value"), "{}", header);
+        assert!(header.contains("PREF=value"), "{}", header);
+
+        let other = Url::parse("https://music.youtube.com/").unwrap();
+        assert_eq!(jar.cookies(&other).unwrap().to_str().unwrap(), "SID=value");
+    }
+
+    #[test]
+    fn test_is_expired() {
+        assert!(cookie("youtube.com", "a", Some(10)).is_expired(10));
+        assert!(!cookie("youtube.com", "a", Some(11)).is_expired(10));
+        assert!(!cookie("youtube.com", "a", None).is_expired(10));
+    }
+
+    #[test]
+    fn test_expiry_conversion() {
+        assert_eq!(chromium_expiry(0), None);
+        // 2030-01-01T00:00:00Z
+        assert_eq!(
+            chromium_expiry((1_893_456_000 + CHROMIUM_EPOCH_OFFSET) * 1_000_000),
+            Some(1_893_456_000)
+        );
+        assert_eq!(firefox_expiry(0), None);
+        assert_eq!(firefox_expiry(1_893_456_000), Some(1_893_456_000));
+        assert_eq!(firefox_expiry(1_893_456_000_000), Some(1_893_456_000));
+    }
+
+    #[test]
+    fn test_chromium_browser_from_name() {
+        for name in SUPPORTED_BROWSERS {
+            let chromium = ChromiumBrowser::from_name(name);
+            assert_eq!(
+                chromium.is_none(),
+                matches!(*name, "firefox" | "safari"),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(ChromiumBrowser::from_name("netscape"), None);
+        assert_eq!(
+            ChromiumBrowser::from_name("chrome").unwrap().keyring_name,
+            "Chrome"
+        );
+    }
+
+    #[test]
+    fn test_extract_from_unsupported_browser() {
+        let err = extract_from_browser("netscape").unwrap_err();
+        assert!(matches!(err, RytError::BrowserCookies(_)));
+        assert!(err.to_string().contains("firefox"), "{}", err);
+    }
+
+    #[test]
+    fn test_read_firefox_cookies() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("cookies.sqlite");
+        let connection = rusqlite::Connection::open(&db).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE moz_cookies (id INTEGER PRIMARY KEY, host TEXT, name TEXT, \
+                 value TEXT, path TEXT, expiry INTEGER, isSecure INTEGER, isHttpOnly INTEGER);
+                 INSERT INTO moz_cookies (host, name, value, path, expiry, isSecure, isHttpOnly)
+                 VALUES ('.youtube.com', 'SID', 'abc', '/', 1893456000, 1, 1),
+                        ('.example.com', 'other', 'x', '/', 0, 0, 0);",
+            )
+            .unwrap();
+        drop(connection);
+
+        let cookies = read_firefox_cookies(&db).unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(
+            cookies[0],
+            BrowserCookie {
+                host: ".youtube.com".to_string(),
+                name: "SID".to_string(),
+                value: "abc".to_string(),
+                path: "/".to_string(),
+                secure: true,
+                http_only: true,
+                expires: Some(1_893_456_000),
+            }
+        );
+        assert_eq!(cookies[1].expires, None);
+
+        let err = read_firefox_cookies(&dir.path().join("missing.sqlite")).unwrap_err();
+        assert!(matches!(err, RytError::Io { .. }));
+    }
+
+    /// Create a Chromium cookie database with the given encrypted value
+    fn chromium_db(dir: &Path, version: &str, encrypted: &[u8]) -> PathBuf {
+        let db = dir.join("Cookies");
+        let connection = rusqlite::Connection::open(&db).unwrap();
+        connection
+            .execute_batch(&format!(
+                "CREATE TABLE meta (key TEXT, value TEXT);
+                 INSERT INTO meta VALUES ('version', '{}');
+                 CREATE TABLE cookies (host_key TEXT, name TEXT, value TEXT, \
+                 encrypted_value BLOB, path TEXT, expires_utc INTEGER, is_secure INTEGER, \
+                 is_httponly INTEGER);
+                 INSERT INTO cookies VALUES ('.youtube.com', 'PREF', 'f6=40000000', x'', '/', \
+                 0, 1, 0);
+                 INSERT INTO cookies VALUES ('.example.com', 'other', 'x', x'', '/', 0, 0, 0);",
+                version
+            ))
+            .unwrap();
+        connection
+            .execute(
+                "INSERT INTO cookies VALUES ('.youtube.com', 'SID', '', ?1, '/', 0, 1, 1)",
+                [encrypted],
+            )
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_read_chromium_plain_cookies() {
+        let dir = tempfile::tempdir().unwrap();
+        // A value no key decrypts is skipped
+        let db = chromium_db(dir.path(), "23", b"v99garbage");
+        let browser = ChromiumBrowser::from_name("chrome").unwrap();
+
+        let cookies = read_chromium_cookies(&db, &browser).unwrap();
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name, "PREF");
+        assert_eq!(cookies[0].value, "f6=40000000");
+        assert_eq!(cookies[0].expires, None);
+    }
+
+    #[cfg(all(feature = "cookie-decryption", target_os = "linux"))]
+    #[test]
+    fn test_read_chromium_encrypted_cookies() {
+        let key = derive_key(b"peanuts", 1).unwrap();
+        let mut plaintext = vec![0u8; 32];
+        plaintext.extend_from_slice(b"secret-sid");
+        let ciphertext = openssl::symm::encrypt(
+            openssl::symm::Cipher::aes_128_cbc(),
+            &key,
+            Some(&[b' '; 16][..]),
+            &plaintext,
+        )
+        .unwrap();
+        let mut encrypted = b"v10".to_vec();
+        encrypted.extend_from_slice(&ciphertext);
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = chromium_db(dir.path(), "24", &encrypted);
+        let browser = ChromiumBrowser::from_name("chromium").unwrap();
+        let cookies = read_chromium_cookies(&db, &browser).unwrap();
+        let sid = cookies.iter().find(|cookie| cookie.name == "SID").unwrap();
+        assert_eq!(sid.value, "secret-sid");
+        assert!(sid.http_only);
+    }
+
+    /// Build a Safari cookie record
+    fn safari_record(
+        flags: u32,
+        host: &str,
+        name: &str,
+        path: &str,
+        value: &str,
+        expiry: f64,
+    ) -> Vec<u8> {
+        let mut strings = Vec::new();
+        let mut offsets = Vec::new();
+        for text in [host, name, path, value] {
+            offsets.push(56 + strings.len() as u32);
+            strings.extend_from_slice(text.as_bytes());
+            strings.push(0);
+        }
+        let mut record = Vec::new();
+        record.extend_from_slice(&((56 + strings.len()) as u32).to_le_bytes());
+        record.extend_from_slice(&0u32.to_le_bytes());
+        record.extend_from_slice(&flags.to_le_bytes());
+        record.extend_from_slice(&0u32.to_le_bytes());
+        for offset in offsets {
+            record.extend_from_slice(&offset.to_le_bytes());
+        }
+        record.extend_from_slice(&[0; 8]);
+        record.extend_from_slice(&expiry.to_le_bytes());
+        record.extend_from_slice(&0f64.to_le_bytes());
+        record.extend_from_slice(&strings);
+        record
+    }
+
+    #[test]
+    fn test_read_safari_cookies() {
+        let records = [
+            safari_record(5, ".youtube.com", "SID", "/", "abc", 915_148_800.0),
+            safari_record(0, "www.youtube.com", "PREF", "/", "f6=1", 0.0),
+        ];
+        let header_len = 8 + records.len() * 4 + 4;
+        let mut page = vec![0, 0, 1, 0];
+        page.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        let mut offset = header_len;
+        for record in &records {
+            page.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += record.len();
+        }
+        page.extend_from_slice(&[0; 4]);
+        for record in &records {
+            page.extend_from_slice(record);
+        }
+
+        let mut data = b"cook".to_vec();
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&(page.len() as u32).to_be_bytes());
+        data.extend_from_slice(&page);
+        data.extend_from_slice(&[0; 8]);
+
+        let cookies = read_safari_cookies(&data).unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].host, ".youtube.com");
+        assert_eq!(cookies[0].name, "SID");
+        assert_eq!(cookies[0].value, "abc");
+        assert!(cookies[0].secure && cookies[0].http_only);
+        // 2030-01-01T00:00:00Z
+        assert_eq!(cookies[0].expires, Some(1_893_456_000));
+        assert_eq!(cookies[1].value, "f6=1");
+        assert!(!cookies[1].secure && !cookies[1].http_only);
+        assert_eq!(cookies[1].expires, None);
+
+        assert!(read_safari_cookies(b"nope").is_err());
+        assert!(read_safari_cookies(&data[..20]).is_err());
+    }
+}
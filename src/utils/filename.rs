@@ -1,7 +1,8 @@
 //! Safe filename generation utilities
 
+use crate::error::RytError;
 use regex::Regex;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Convert a title to a safe filename by removing/replacing invalid characters
 pub fn to_safe_filename(title: &str, extension: &str) -> String {
@@ -38,6 +39,96 @@ pub fn to_safe_filename(title: &str, extension: &str) -> String {
     }
 }
 
+/// Longest value a template field expands to
+pub const MAX_TEMPLATE_FIELD_CHARS: usize = 200;
+
+/// Expand an output template such as `%(title)s [%(id)s]`.
+///
+/// `%(field)s` is replaced by the value `lookup` returns for the field, or
+/// `NA` if it has none, and `%(field).50s` keeps only the first 50
+/// characters. `%%` is a literal percent sign. Values are cut to
+/// [`MAX_TEMPLATE_FIELD_CHARS`] and sanitized for filenames, so a `/` in a
+/// title doesn't create directories; the template itself may contain them.
+pub fn render_template(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    substitute_fields(template, lookup, |value, precision| {
+        let max_chars = precision
+            .unwrap_or(MAX_TEMPLATE_FIELD_CHARS)
+            .min(MAX_TEMPLATE_FIELD_CHARS);
+        sanitize_template_value(value.chars().take(max_chars))
+    })
+}
+
+/// Expand a template like [`render_template`], but keep values as they are
+/// and uncapped, e.g. for printing
+pub fn expand_template(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    substitute_fields(template, lookup, |value, precision| match precision {
+        Some(max_chars) => value.chars().take(max_chars).collect(),
+        None => value,
+    })
+}
+
+/// Get the names of the fields a template uses, in order
+pub fn template_fields(template: &str) -> Vec<&str> {
+    template_field_regex()
+        .captures_iter(template)
+        .filter_map(|caps| caps.get(1).map(|name| name.as_str()))
+        .collect()
+}
+
+/// Check that every `%(` in a template starts a well-formed field such as
+/// `%(title)s` or `%(title).50s`
+pub fn check_template(template: &str) -> Result<(), RytError> {
+    let rest = template_field_regex().replace_all(template, "");
+    match rest.find("%(") {
+        Some(start) => Err(RytError::InvalidTemplate(format!(
+            "malformed field at '{}' in '{}', expected e.g. %(title)s",
+            rest[start..].chars().take(16).collect::<String>(),
+            template
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Match `%%` or a field such as `%(title)s` or `%(title).50s`
+fn template_field_regex() -> Regex {
+    Regex::new(r"%(?:%|\((\w+)\)(?:\.(\d+))?[sd])").unwrap()
+}
+
+/// Replace template fields with the values from `lookup`, formatted with
+/// their precision
+fn substitute_fields(
+    template: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    format: impl Fn(String, Option<usize>) -> String,
+) -> String {
+    template_field_regex()
+        .replace_all(template, |caps: &regex::Captures| {
+            let name = match caps.get(1) {
+                Some(name) => name,
+                None => return "%".to_string(),
+            };
+            // A precision too large to parse doesn't limit anything
+            let precision = caps
+                .get(2)
+                .map(|precision| precision.as_str().parse().unwrap_or(usize::MAX));
+            let value = lookup(name.as_str()).unwrap_or_else(|| "NA".to_string());
+            format(value, precision)
+        })
+        .into_owned()
+}
+
+/// Make a template value safe inside a filename
+fn sanitize_template_value(value: impl Iterator<Item = char>) -> String {
+    value
+        .map(|c| match c {
+            '\r' | '\n' | '\t' => ' ',
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
 /// Check if a filename is safe for the current filesystem
 pub fn is_safe_filename(filename: &str) -> bool {
     if filename.is_empty() || filename.len() > 255 {
@@ -105,6 +196,37 @@ pub fn generate_unique_filename(base_path: &Path, filename: &str) -> std::io::Re
     Ok(final_filename)
 }
 
+/// Get `path`, or the same path with the lowest free ` (n)` suffix before
+/// the extension if a file already exists there
+pub fn unique_path(path: &Path) -> std::io::Result<PathBuf> {
+    let Some(filename) = path.file_name() else {
+        return Ok(path.to_path_buf());
+    };
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let filename = generate_unique_filename(parent, &filename.to_string_lossy())?;
+    Ok(parent.join(filename))
+}
+
+/// Create a directory and any missing parents, with mode `0o755` on Unix
+pub async fn ensure_output_directory(path: &Path) -> Result<(), RytError> {
+    let mut builder = tokio::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    builder.mode(0o755);
+    builder
+        .create(path)
+        .await
+        .map_err(|e| RytError::io(e, "create directory", path))
+}
+
+/// Check if a path names a directory by ending with a separator, e.g.
+/// `downloads/2025/`, whether or not it exists
+pub fn has_trailing_separator(path: &Path) -> bool {
+    path.as_os_str()
+        .to_string_lossy()
+        .ends_with(std::path::is_separator)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,6 +251,108 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_render_template() {
+        let lookup = |name: &str| match name {
+            "id" => Some("dQw4w9WgXcQ".to_string()),
+            "title" => Some("AC/DC: Live?".to_string()),
+            "description" => Some("First line\r\nSecond line".to_string()),
+            "ext" => Some("mp4".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            render_template("%(title)s [%(id)s].%(ext)s", lookup),
+            "AC_DC_ Live_ [dQw4w9WgXcQ].mp4"
+        );
+        assert_eq!(
+            render_template("videos/%(id)s/%(description)s", lookup),
+            "videos/dQw4w9WgXcQ/First line  Second line"
+        );
+        assert_eq!(
+            render_template("%(uploader)s - %(id)d", lookup),
+            "NA - dQw4w9WgXcQ"
+        );
+        assert_eq!(render_template("100%% %(id)s", lookup), "100% dQw4w9WgXcQ");
+        // Malformed fields are kept as is
+        assert_eq!(
+            render_template("%(id) %(title %s", lookup),
+            "%(id) %(title %s"
+        );
+    }
+
+    #[test]
+    fn test_render_template_precision() {
+        let lookup = |name: &str| match name {
+            "title" => Some("Ünïcödé title".to_string()),
+            "description" => Some("x".repeat(100 * 1024)),
+            _ => None,
+        };
+        assert_eq!(render_template("%(title).5s", lookup), "Ünïcö");
+        assert_eq!(render_template("%(title).0s|", lookup), "|");
+        assert_eq!(render_template("%(title).50s", lookup), "Ünïcödé title");
+        assert_eq!(render_template("%(missing).1s", lookup), "N");
+
+        // Long values are capped with or without a precision
+        assert_eq!(
+            render_template("%(description).50s", lookup),
+            "x".repeat(50)
+        );
+        assert_eq!(
+            render_template("%(description)s", lookup).len(),
+            MAX_TEMPLATE_FIELD_CHARS
+        );
+        assert_eq!(
+            render_template("%(description).99999999999999999999999s", lookup).len(),
+            MAX_TEMPLATE_FIELD_CHARS
+        );
+    }
+
+    #[test]
+    fn test_expand_template() {
+        let lookup = |name: &str| match name {
+            "title" => Some("AC/DC: Live?".to_string()),
+            "description" => Some("x".repeat(300)),
+            _ => None,
+        };
+        // Values are kept as they are and not capped
+        assert_eq!(expand_template("%(title)s", lookup), "AC/DC: Live?");
+        assert_eq!(expand_template("%(title).5s|%(id)s", lookup), "AC/DC|NA");
+        assert_eq!(expand_template("%(description)s", lookup).len(), 300);
+        assert_eq!(expand_template("100%%", lookup), "100%");
+    }
+
+    #[test]
+    fn test_template_fields() {
+        assert_eq!(
+            template_fields("%(id)s\t%(title).20s %% %(duration)d %(bad"),
+            vec!["id", "title", "duration"]
+        );
+        assert!(template_fields("no fields").is_empty());
+    }
+
+    #[test]
+    fn test_check_template() {
+        assert!(check_template("%(title)s [%(id)s].%(ext)s").is_ok());
+        assert!(check_template("%(uploader)s/%(title).50s - %(duration)d").is_ok());
+        assert!(check_template("100%% done, 50% off").is_ok());
+        assert!(check_template("100%%(literal)").is_ok());
+        assert!(check_template("no fields").is_ok());
+        for template in [
+            "%(title",
+            "%(title)",
+            "%(title)x",
+            "%(title).s",
+            "%()s",
+            "a %(id)s %(b",
+        ] {
+            assert!(
+                matches!(check_template(template), Err(RytError::InvalidTemplate(_))),
+                "{}",
+                template
+            );
+        }
+    }
+
     #[test]
     fn test_is_safe_filename() {
         assert!(is_safe_filename("normal_file.mp4"));
@@ -245,6 +469,54 @@ mod tests {
         assert!(is_safe_filename("file with spaces.mp4"));
     }
 
+    #[test]
+    fn test_unique_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clip.mp4");
+        assert_eq!(unique_path(&path).unwrap(), path);
+
+        std::fs::write(&path, b"").unwrap();
+        assert_eq!(unique_path(&path).unwrap(), dir.path().join("clip (1).mp4"));
+
+        // Relative paths keep their form
+        assert_eq!(
+            unique_path(Path::new("no_such_clip_ryt.mp4")).unwrap(),
+            PathBuf::from("no_such_clip_ryt.mp4")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ensure_output_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("youtube").join("2025");
+        ensure_output_directory(&nested).await.unwrap();
+        assert!(nested.is_dir());
+        // Existing directories are fine
+        ensure_output_directory(&nested).await.unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&nested).unwrap().permissions().mode();
+            // The umask may only remove bits
+            assert_eq!(mode & !0o755 & 0o777, 0);
+        }
+
+        let blocker = dir.path().join("file");
+        std::fs::write(&blocker, b"").unwrap();
+        let err = ensure_output_directory(&blocker.join("sub"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(&blocker.display().to_string()));
+    }
+
+    #[test]
+    fn test_has_trailing_separator() {
+        assert!(has_trailing_separator(Path::new("downloads/2025/")));
+        assert!(!has_trailing_separator(Path::new("downloads/2025")));
+        assert!(!has_trailing_separator(Path::new("video.mp4")));
+    }
+
     #[test]
     fn test_generate_unique_filename_edge_cases() {
         use std::fs::File;
@@ -0,0 +1,124 @@
+//! Internet shortcut files
+//!
+//! A link file points to the video page instead of holding the media, so it
+//! can be opened from a file manager like any other file.
+
+use serde::{Deserialize, Serialize};
+
+/// Kind of shortcut file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkKind {
+    /// Windows `.url` internet shortcut
+    Url,
+    /// macOS `.webloc` property list
+    Webloc,
+    /// freedesktop.org `.desktop` link entry
+    Desktop,
+}
+
+impl LinkKind {
+    /// Get the shortcut kind native to the running platform
+    pub fn platform_default() -> Self {
+        if cfg!(windows) {
+            LinkKind::Url
+        } else if cfg!(target_os = "macos") {
+            LinkKind::Webloc
+        } else {
+            LinkKind::Desktop
+        }
+    }
+
+    /// Get file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LinkKind::Url => "url",
+            LinkKind::Webloc => "webloc",
+            LinkKind::Desktop => "desktop",
+        }
+    }
+
+    /// Render a shortcut to `url`, named `title` where the format has a name
+    pub fn render(&self, url: &str, title: &str) -> String {
+        match self {
+            LinkKind::Url => format!("[InternetShortcut]\r\nURL={}\r\n", url),
+            LinkKind::Webloc => format!(
+                concat!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                    "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+                    "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+                    "<plist version=\"1.0\">\n",
+                    "<dict>\n",
+                    "\t<key>URL</key>\n",
+                    "\t<string>{}</string>\n",
+                    "</dict>\n",
+                    "</plist>\n"
+                ),
+                escape_xml(url)
+            ),
+            LinkKind::Desktop => format!(
+                "[Desktop Entry]\nEncoding=UTF-8\nName={}\nType=Link\nURL={}\nIcon=text-html\n",
+                escape_desktop_value(title),
+                escape_desktop_value(url)
+            ),
+        }
+    }
+}
+
+/// Escape text for an XML element
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Escape a `.desktop` string value, which must fit on one line
+fn escape_desktop_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42";
+
+    #[test]
+    fn test_render_url() {
+        assert_eq!(
+            LinkKind::Url.render(URL, "Title"),
+            "[InternetShortcut]\r\nURL=https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42\r\n"
+        );
+    }
+
+    #[test]
+    fn test_render_webloc() {
+        let content = LinkKind::Webloc.render(URL, "Title");
+        assert!(content.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist"));
+        assert!(content
+            .contains("<key>URL</key>\n\t<string>https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;t=42</string>"));
+        assert!(content.ends_with("</dict>\n</plist>\n"));
+    }
+
+    #[test]
+    fn test_render_desktop() {
+        assert_eq!(
+            LinkKind::Desktop.render(URL, "Line one\nC:\\path"),
+            "[Desktop Entry]\nEncoding=UTF-8\nName=Line one\\nC:\\\\path\nType=Link\n\
+             URL=https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42\nIcon=text-html\n"
+        );
+    }
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(LinkKind::Url.extension(), "url");
+        assert_eq!(LinkKind::Webloc.extension(), "webloc");
+        assert_eq!(LinkKind::Desktop.extension(), "desktop");
+    }
+}
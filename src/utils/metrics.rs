@@ -0,0 +1,246 @@
+//! Optional metrics instrumentation
+//!
+//! With the `metrics` feature enabled, key points of the download pipeline
+//! report to the [`metrics`](https://docs.rs/metrics) facade and can be
+//! exported in Prometheus text format. Without it, every function here is an
+//! empty inline no-op.
+
+use std::time::Duration;
+
+/// Downloads started counter
+pub const DOWNLOADS_STARTED: &str = "ryt_downloads_started_total";
+/// Downloads completed counter
+pub const DOWNLOADS_COMPLETED: &str = "ryt_downloads_completed_total";
+/// Downloads failed counter
+pub const DOWNLOADS_FAILED: &str = "ryt_downloads_failed_total";
+/// Downloads in progress gauge
+pub const DOWNLOADS_IN_PROGRESS: &str = "ryt_downloads_in_progress";
+/// Media bytes downloaded counter
+pub const BYTES_DOWNLOADED: &str = "ryt_bytes_downloaded_total";
+/// Retries counter, labelled by `reason`
+pub const RETRIES: &str = "ryt_retries_total";
+/// Client switches counter
+pub const CLIENT_SWITCHES: &str = "ryt_client_switches_total";
+/// Cipher cache hits counter, labelled by `kind`
+pub const CIPHER_CACHE_HITS: &str = "ryt_cipher_cache_hits_total";
+/// Cipher cache misses counter, labelled by `kind`
+pub const CIPHER_CACHE_MISSES: &str = "ryt_cipher_cache_misses_total";
+/// Per-video wall time histogram in seconds
+pub const DOWNLOAD_DURATION: &str = "ryt_download_duration_seconds";
+
+#[cfg(feature = "metrics")]
+mod imp {
+    use super::*;
+    use crate::error::RytError;
+    use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tracing::{debug, warn};
+
+    /// Record the start of a video download
+    pub fn download_started() {
+        ::metrics::counter!(DOWNLOADS_STARTED).increment(1);
+        ::metrics::gauge!(DOWNLOADS_IN_PROGRESS).increment(1.0);
+    }
+
+    /// Record the end of a video download
+    pub fn download_finished(success: bool, elapsed: Duration) {
+        let counter = if success {
+            DOWNLOADS_COMPLETED
+        } else {
+            DOWNLOADS_FAILED
+        };
+        ::metrics::counter!(counter).increment(1);
+        ::metrics::gauge!(DOWNLOADS_IN_PROGRESS).decrement(1.0);
+        ::metrics::histogram!(DOWNLOAD_DURATION).record(elapsed.as_secs_f64());
+    }
+
+    /// Record downloaded media bytes
+    pub fn bytes_downloaded(bytes: u64) {
+        ::metrics::counter!(BYTES_DOWNLOADED).increment(bytes);
+    }
+
+    /// Record a retry
+    pub fn retry(reason: &'static str) {
+        ::metrics::counter!(RETRIES, "reason" => reason).increment(1);
+    }
+
+    /// Record a client switch
+    pub fn client_switch() {
+        ::metrics::counter!(CLIENT_SWITCHES).increment(1);
+    }
+
+    /// Record a cipher cache lookup
+    pub fn cipher_cache(kind: &'static str, hit: bool) {
+        let counter = if hit {
+            CIPHER_CACHE_HITS
+        } else {
+            CIPHER_CACHE_MISSES
+        };
+        ::metrics::counter!(counter, "kind" => kind).increment(1);
+    }
+
+    /// Install a global Prometheus recorder
+    pub fn install_recorder() -> Result<PrometheusHandle, RytError> {
+        PrometheusBuilder::new()
+            .install_recorder()
+            .map_err(|e| RytError::Generic(format!("Failed to install metrics recorder: {}", e)))
+    }
+
+    /// Serve `/metrics` in Prometheus text format on `addr`
+    pub async fn serve_metrics(addr: SocketAddr, handle: PrometheusHandle) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        serve_metrics_on(listener, handle).await
+    }
+
+    /// Serve `/metrics` on an already bound listener
+    pub async fn serve_metrics_on(
+        listener: TcpListener,
+        handle: PrometheusHandle,
+    ) -> std::io::Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let handle = handle.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &handle).await {
+                    debug!("Metrics connection from {} failed: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    /// Answer a single HTTP request
+    async fn handle_connection(
+        mut stream: TcpStream,
+        handle: &PrometheusHandle,
+    ) -> std::io::Result<()> {
+        let mut buf = [0u8; 1024];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let mut parts = request.split_whitespace();
+        let (method, path) = (parts.next(), parts.next());
+
+        let response = match (method, path) {
+            (Some("GET"), Some("/metrics")) => {
+                let body = handle.render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            }
+            _ => {
+                warn!("Unexpected metrics request: {:?} {:?}", method, path);
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            }
+        };
+
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+mod imp {
+    use super::*;
+
+    /// Record the start of a video download
+    #[inline]
+    pub fn download_started() {}
+
+    /// Record the end of a video download
+    #[inline]
+    pub fn download_finished(_success: bool, _elapsed: Duration) {}
+
+    /// Record downloaded media bytes
+    #[inline]
+    pub fn bytes_downloaded(_bytes: u64) {}
+
+    /// Record a retry
+    #[inline]
+    pub fn retry(_reason: &'static str) {}
+
+    /// Record a client switch
+    #[inline]
+    pub fn client_switch() {}
+
+    /// Record a cipher cache lookup
+    #[inline]
+    pub fn cipher_cache(_kind: &'static str, _hit: bool) {}
+}
+
+pub use imp::*;
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_download_lifecycle_metrics() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        ::metrics::with_local_recorder(&recorder, || {
+            download_started();
+            download_finished(true, Duration::from_secs(2));
+            download_started();
+            download_finished(false, Duration::from_secs(1));
+            cipher_cache("signature", true);
+            cipher_cache("n", false);
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let value = |name: &str| {
+            snapshot
+                .iter()
+                .find(|(key, _, _, _)| key.key().name() == name)
+                .map(|(_, _, _, value)| value.clone())
+        };
+
+        assert_eq!(value(DOWNLOADS_STARTED), Some(DebugValue::Counter(2)));
+        assert_eq!(value(DOWNLOADS_COMPLETED), Some(DebugValue::Counter(1)));
+        assert_eq!(value(DOWNLOADS_FAILED), Some(DebugValue::Counter(1)));
+        assert_eq!(value(CIPHER_CACHE_HITS), Some(DebugValue::Counter(1)));
+        assert_eq!(value(CIPHER_CACHE_MISSES), Some(DebugValue::Counter(1)));
+        match value(DOWNLOADS_IN_PROGRESS) {
+            Some(DebugValue::Gauge(gauge)) => assert_eq!(gauge.into_inner(), 0.0),
+            other => panic!("unexpected gauge value: {:?}", other),
+        }
+        match value(DOWNLOAD_DURATION) {
+            Some(DebugValue::Histogram(samples)) => assert_eq!(samples.len(), 2),
+            other => panic!("unexpected histogram value: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serve_metrics() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        ::metrics::with_local_recorder(&recorder, || bytes_downloaded(42));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_metrics_on(listener, handle));
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("ryt_bytes_downloaded_total 42"));
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+}
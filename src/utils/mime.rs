@@ -30,6 +30,11 @@ pub fn ext_from_mime(mime_type: &str) -> &'static str {
         "audio/vorbis" => "ogg",
         "audio/opus" => "opus",
 
+        // Image formats
+        "image/jpeg" => "jpg",
+        "image/png" => "png",
+        "image/webp" => "webp",
+
         // Default fallback
         _ => "bin",
     }
@@ -61,11 +66,47 @@ pub fn mime_from_ext(extension: &str) -> &'static str {
         "aac" => "audio/aac",
         "opus" => "audio/opus",
 
+        // Image formats
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "webp" => "image/webp",
+
         // Default fallback
         _ => "application/octet-stream",
     }
 }
 
+/// Strip parameters (e.g. `codecs`) from a MIME type
+pub fn base_mime_type(mime_type: &str) -> &str {
+    mime_type.split(';').next().unwrap_or(mime_type).trim()
+}
+
+/// Get the codecs listed in a MIME type's `codecs` parameter
+pub fn mime_codecs(mime_type: &str) -> Vec<&str> {
+    mime_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.trim().strip_prefix("codecs="))
+        .flat_map(|codecs| codecs.trim_matches('"').split(','))
+        .map(str::trim)
+        .filter(|codec| !codec.is_empty())
+        .collect()
+}
+
+/// Check if a MIME type is stored in the container of a file extension.
+///
+/// Audio and video are distinguished: `mp4` matches `video/mp4` only,
+/// while `m4a` matches `audio/mp4`. WebM has no audio-only extension, so
+/// `webm` matches both `video/webm` and `audio/webm`.
+pub fn mime_matches_ext(mime_type: &str, extension: &str) -> bool {
+    let base = base_mime_type(mime_type);
+    base.eq_ignore_ascii_case(mime_from_ext(extension))
+        || (base.eq_ignore_ascii_case("audio/webm")
+            && extension
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case("webm"))
+}
+
 /// Check if MIME type is a video format
 pub fn is_video_mime(mime_type: &str) -> bool {
     mime_type.starts_with("video/")
@@ -135,6 +176,40 @@ mod tests {
         assert_eq!(mime_from_ext("unknown"), "application/octet-stream");
     }
 
+    #[test]
+    fn test_base_mime_type() {
+        assert_eq!(
+            base_mime_type("video/mp4; codecs=\"avc1.42001E, mp4a.40.2\""),
+            "video/mp4"
+        );
+        assert_eq!(base_mime_type("audio/webm;codecs=opus"), "audio/webm");
+        assert_eq!(base_mime_type("video/webm"), "video/webm");
+    }
+
+    #[test]
+    fn test_mime_codecs() {
+        assert_eq!(
+            mime_codecs("video/mp4; codecs=\"avc1.42001E, mp4a.40.2\""),
+            vec!["avc1.42001E", "mp4a.40.2"]
+        );
+        assert_eq!(mime_codecs("audio/webm;codecs=opus"), vec!["opus"]);
+        assert!(mime_codecs("video/webm").is_empty());
+        assert!(mime_codecs("video/mp4; codecs=\"\"").is_empty());
+    }
+
+    #[test]
+    fn test_mime_matches_ext() {
+        assert!(mime_matches_ext("video/mp4; codecs=\"avc1.64001F\"", "mp4"));
+        assert!(mime_matches_ext("video/mp4", ".MP4"));
+        assert!(!mime_matches_ext("audio/mp4; codecs=\"mp4a.40.2\"", "mp4"));
+        assert!(mime_matches_ext("audio/mp4; codecs=\"mp4a.40.2\"", "m4a"));
+        assert!(mime_matches_ext("video/webm; codecs=\"vp9\"", "webm"));
+        assert!(mime_matches_ext("audio/webm; codecs=\"opus\"", "webm"));
+        assert!(!mime_matches_ext("audio/webm; codecs=\"opus\"", "mp4"));
+        assert!(!mime_matches_ext("video/webm", "mp4"));
+        assert!(!mime_matches_ext("video/mp4", "unknown"));
+    }
+
     #[test]
     fn test_is_video_mime() {
         assert!(is_video_mime("video/mp4"));
@@ -207,6 +282,11 @@ mod tests {
         assert_eq!(ext_from_mime("audio/vorbis"), "ogg");
         assert_eq!(ext_from_mime("audio/opus"), "opus");
 
+        // Test image formats
+        assert_eq!(ext_from_mime("image/jpeg"), "jpg");
+        assert_eq!(ext_from_mime("image/png"), "png");
+        assert_eq!(ext_from_mime("image/webp"), "webp");
+
         // Test fallback
         assert_eq!(ext_from_mime("unknown/type"), "bin");
     }
@@ -236,6 +316,12 @@ mod tests {
         assert_eq!(mime_from_ext("aac"), "audio/aac");
         assert_eq!(mime_from_ext("opus"), "audio/opus");
 
+        // Test image formats
+        assert_eq!(mime_from_ext("jpg"), "image/jpeg");
+        assert_eq!(mime_from_ext("jpeg"), "image/jpeg");
+        assert_eq!(mime_from_ext("png"), "image/png");
+        assert_eq!(mime_from_ext("webp"), "image/webp");
+
         // Test with dots and case sensitivity
         assert_eq!(mime_from_ext(".mp4"), "video/mp4");
         assert_eq!(mime_from_ext("MP4"), "video/mp4");
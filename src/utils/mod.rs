@@ -1,11 +1,28 @@
 //! Utility functions for ryt
 
+pub mod archive;
 pub mod cache;
+pub mod cookies;
 pub mod filename;
+pub mod link;
+pub mod metrics;
 pub mod mime;
+pub mod parse;
+pub mod path;
+pub mod subtitle;
+pub mod temp;
+pub mod thumbnail;
 pub mod url;
 
+pub use archive::*;
 pub use cache::*;
+pub use cookies::*;
 pub use filename::*;
+pub use link::*;
 pub use mime::*;
+pub use parse::*;
+pub use path::*;
+pub use subtitle::*;
+pub use temp::*;
+pub use thumbnail::*;
 pub use url::*;
@@ -0,0 +1,613 @@
+//! Parsing of human-friendly sizes, rates and durations
+
+use crate::error::RytError;
+use std::time::Duration;
+
+/// Parse a size such as `500m`, `1.5G` or `700KiB` to bytes.
+///
+/// Suffixes `K`, `M`, `G` and `T` (case-insensitive, optionally followed by
+/// `B` or `iB`) are binary multiples, so `10KB` is 10240 bytes; a plain
+/// number is bytes. Whitespace around the number and unit is ignored.
+pub fn parse_size(size: &str) -> Result<u64, RytError> {
+    parse_bytes(size, false)
+}
+
+/// Parse a size like [`parse_size`], except that units spelled with a
+/// plain `B` are decimal: `10KB` is 10000 bytes while `10K` and `10KiB`
+/// are 10240. Command-line size and rate flags use this spelling.
+pub fn parse_si_size(size: &str) -> Result<u64, RytError> {
+    parse_bytes(size, true)
+}
+
+/// Parse a rate such as `5M/s` or `500K` to bytes per second.
+///
+/// Accepts everything [`parse_size`] does, with an optional `/s` suffix.
+/// Rates must be above zero.
+pub fn parse_rate(rate: &str) -> Result<u64, RytError> {
+    parse_bytes_per_second(rate, false)
+}
+
+/// Parse a rate like [`parse_rate`], with decimal units as in
+/// [`parse_si_size`], so `1MB/s` is 1000000 bytes per second
+pub fn parse_si_rate(rate: &str) -> Result<u64, RytError> {
+    parse_bytes_per_second(rate, true)
+}
+
+/// Parse a size, treating `KB`, `MB`, `GB` and `TB` as decimal if `si`
+fn parse_bytes(size: &str, si: bool) -> Result<u64, RytError> {
+    let invalid =
+        |reason: &str| RytError::InvalidInput(format!("invalid size '{}': {}", size, reason));
+
+    let normalized = size.trim().to_ascii_lowercase();
+    let (number, unit) = split_number(&normalized);
+    if number.is_empty() {
+        return Err(invalid("expected a number"));
+    }
+    let number: f64 = number
+        .parse()
+        .map_err(|_| invalid(&format!("'{}' is not a number", number)))?;
+
+    let unit = unit.trim();
+    let multiplier: u64 = match unit {
+        "" | "b" => 1,
+        "kb" if si => 1000,
+        "mb" if si => 1000_u64.pow(2),
+        "gb" if si => 1000_u64.pow(3),
+        "tb" if si => 1000_u64.pow(4),
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(invalid(&format!("unknown unit '{}'", unit))),
+    };
+
+    let bytes = number * multiplier as f64;
+    if bytes >= u64::MAX as f64 {
+        return Err(invalid("too large"));
+    }
+    Ok(bytes as u64)
+}
+
+/// Parse a size with an optional `/s` suffix, rejecting zero
+fn parse_bytes_per_second(rate: &str, si: bool) -> Result<u64, RytError> {
+    let trimmed = rate.trim();
+    let size = trimmed
+        .strip_suffix("/s")
+        .or_else(|| trimmed.strip_suffix("/S"))
+        .unwrap_or(trimmed);
+    let invalid =
+        |reason: String| RytError::InvalidInput(format!("invalid rate '{}': {}", rate, reason));
+
+    match parse_bytes(size, si) {
+        Ok(0) => Err(invalid("must be above zero".to_string())),
+        Ok(bytes) => Ok(bytes),
+        Err(RytError::InvalidInput(message)) => Err(invalid(
+            message
+                .split_once(": ")
+                .map_or(message.clone(), |(_, reason)| reason.to_string()),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+/// Parse a duration such as `30s`, `2min`, `1h30m` or `1.5h`.
+///
+/// A plain number is seconds. Otherwise the duration is a sequence of
+/// numbers, each followed by a unit: `ms`, `s`, `m`, `h`, `d` or `w`, or a
+/// longer spelling such as `sec`, `mins` or `hours`. Units are
+/// case-insensitive and may be separated by whitespace.
+pub fn parse_duration(duration: &str) -> Result<Duration, RytError> {
+    let invalid = |reason: &str| {
+        RytError::InvalidInput(format!("invalid duration '{}': {}", duration, reason))
+    };
+
+    let normalized = duration.trim().to_ascii_lowercase();
+    if normalized.is_empty() {
+        return Err(invalid("expected a number"));
+    }
+
+    let mut seconds = 0.0;
+    let mut rest = normalized.as_str();
+    while !rest.is_empty() {
+        let (number, after) = split_number(rest);
+        if number.is_empty() {
+            return Err(invalid(&format!("expected a number at '{}'", rest)));
+        }
+        let value: f64 = number
+            .parse()
+            .map_err(|_| invalid(&format!("'{}' is not a number", number)))?;
+
+        let after = after.trim_start();
+        let unit_end = after
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_end);
+        let unit_seconds = match unit {
+            // A bare number is only allowed on its own
+            "" if after.is_empty() && rest == normalized => 1.0,
+            "" => return Err(invalid(&format!("missing unit after '{}'", number))),
+            "ms" | "msec" | "msecs" | "millis" => 0.001,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86_400.0,
+            "w" | "week" | "weeks" => 604_800.0,
+            _ => return Err(invalid(&format!("unknown unit '{}'", unit))),
+        };
+        seconds += value * unit_seconds;
+        rest = after.trim_start();
+    }
+
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid("too large"))
+}
+
+/// Format a duration the way [`parse_duration`] reads it, e.g. `1h30m` or
+/// `250ms`. Precision below a millisecond is dropped.
+pub fn format_duration_spec(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis == 0 {
+        return "0s".to_string();
+    }
+    let parts = [
+        (millis / 3_600_000, "h"),
+        (millis / 60_000 % 60, "m"),
+        (millis / 1000 % 60, "s"),
+        (millis % 1000, "ms"),
+    ];
+    parts
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect()
+}
+
+/// Parse a media length as YouTube reports it, in whole or fractional
+/// seconds (`212`, `212.5`) or as a clock (`3:32`, `1:02:03`), to whole
+/// seconds. Missing or malformed lengths are 0, meaning unknown.
+pub fn parse_length_seconds(length: &str) -> u32 {
+    let length = length.trim();
+    if length.contains(':') {
+        return length
+            .split(':')
+            .try_fold(0u32, |total, part| {
+                let part: u32 = part.parse().ok()?;
+                total.checked_mul(60)?.checked_add(part)
+            })
+            .unwrap_or(0);
+    }
+    match length.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds > 0.0 => {
+            seconds.round().min(u32::MAX as f64) as u32
+        }
+        _ => 0,
+    }
+}
+
+/// Serde support for [`Duration`] fields written as human-friendly strings.
+///
+/// Durations serialize with [`format_duration_spec`] and deserialize from any
+/// string [`parse_duration`] accepts, or from a plain number of seconds. Use
+/// with `#[serde(with = "crate::utils::duration_serde")]`, or the
+/// [`option`](duration_serde::option) module for `Option<Duration>`.
+pub mod duration_serde {
+    use super::{format_duration_spec, parse_duration};
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+    use std::time::Duration;
+
+    /// Serialize a duration as a string such as `1h30m`
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_duration_spec(*duration))
+    }
+
+    /// Deserialize a duration from a string or a number of seconds
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        deserializer.deserialize_any(DurationVisitor)
+    }
+
+    struct DurationVisitor;
+
+    impl Visitor<'_> for DurationVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a duration such as \"30s\" or \"1h30m\", or a number of seconds")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Duration, E> {
+            parse_duration(value).map_err(E::custom)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Duration, E> {
+            Ok(Duration::from_secs(value))
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Duration, E> {
+            u64::try_from(value)
+                .map(Duration::from_secs)
+                .map_err(|_| E::custom(format!("negative duration {}", value)))
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<Duration, E> {
+            Duration::try_from_secs_f64(value)
+                .map_err(|_| E::custom(format!("invalid duration {}", value)))
+        }
+    }
+
+    /// Serde support for `Option<Duration>` fields
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+        use std::time::Duration;
+
+        /// Duration (de)serialized through the parent module
+        #[derive(Serialize, Deserialize)]
+        #[serde(transparent)]
+        struct HumanDuration(#[serde(with = "super")] Duration);
+
+        /// Serialize an optional duration, leaving `None` out
+        pub fn serialize<S: Serializer>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            duration.map(HumanDuration).serialize(serializer)
+        }
+
+        /// Deserialize an optional duration
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            Ok(Option::<HumanDuration>::deserialize(deserializer)?.map(|duration| duration.0))
+        }
+    }
+}
+
+/// Split leading digits and dots from the rest of `text`
+fn split_number(text: &str) -> (&str, &str) {
+    let number_end = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    text.split_at(number_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    const GIB: u64 = 1024 * MIB;
+    const TIB: u64 = 1024 * GIB;
+
+    fn size(s: &str) -> u64 {
+        parse_size(s).unwrap_or_else(|e| panic!("{}: {}", s, e))
+    }
+
+    fn rate(s: &str) -> u64 {
+        parse_rate(s).unwrap_or_else(|e| panic!("{}: {}", s, e))
+    }
+
+    fn si_size(s: &str) -> u64 {
+        parse_si_size(s).unwrap_or_else(|e| panic!("{}: {}", s, e))
+    }
+
+    fn secs(s: &str) -> f64 {
+        parse_duration(s)
+            .unwrap_or_else(|e| panic!("{}: {}", s, e))
+            .as_secs_f64()
+    }
+
+    #[test]
+    fn test_parse_size_plain_bytes() {
+        assert_eq!(size("100"), 100);
+        assert_eq!(size("0"), 0);
+        assert_eq!(size("100B"), 100);
+        assert_eq!(size("100b"), 100);
+    }
+
+    #[test]
+    fn test_parse_size_kibibytes() {
+        assert_eq!(size("10K"), 10 * KIB);
+        assert_eq!(size("10KB"), 10 * KIB);
+        assert_eq!(size("10KiB"), 10 * KIB);
+        assert_eq!(size("10k"), 10 * KIB);
+        assert_eq!(size("10kb"), 10 * KIB);
+    }
+
+    #[test]
+    fn test_parse_size_larger_units() {
+        assert_eq!(size("5M"), 5 * MIB);
+        assert_eq!(size("5MB"), 5 * MIB);
+        assert_eq!(size("500m"), 500 * MIB);
+        assert_eq!(size("1G"), GIB);
+        assert_eq!(size("1GB"), GIB);
+        assert_eq!(size("1gib"), GIB);
+        assert_eq!(size("2T"), 2 * TIB);
+        assert_eq!(size("2TB"), 2 * TIB);
+    }
+
+    #[test]
+    fn test_parse_si_size() {
+        assert_eq!(si_size("10KB"), 10_000);
+        assert_eq!(si_size("10kb"), 10_000);
+        assert_eq!(si_size("5MB"), 5_000_000);
+        assert_eq!(si_size("1GB"), 1_000_000_000);
+        assert_eq!(si_size("2TB"), 2_000_000_000_000);
+        assert_eq!(si_size("1.5MB"), 1_500_000);
+        assert_eq!(si_size("10K"), 10 * KIB);
+        assert_eq!(si_size("10KiB"), 10 * KIB);
+        assert_eq!(si_size("5MiB"), 5 * MIB);
+        assert_eq!(si_size(" 500 kb "), 500_000);
+        assert!(matches!(
+            parse_si_size("10x"),
+            Err(RytError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_size_fractions() {
+        assert_eq!(size("1.5G"), 3 * GIB / 2);
+        assert_eq!(size("0.5K"), 512);
+        assert_eq!(size("1.5"), 1);
+        assert_eq!(size(".5M"), MIB / 2);
+    }
+
+    #[test]
+    fn test_parse_size_whitespace() {
+        assert_eq!(size(" 500 K "), 500 * KIB);
+        assert_eq!(size("\t1 GB\n"), GIB);
+        assert_eq!(size("  42  "), 42);
+    }
+
+    #[test]
+    fn test_parse_size_round_trip() {
+        for (unit, multiplier, si_multiplier) in [
+            ("", 1, 1),
+            ("B", 1, 1),
+            ("K", KIB, KIB),
+            ("KiB", KIB, KIB),
+            ("kb", KIB, 1000),
+            ("M", MIB, MIB),
+            ("mib", MIB, MIB),
+            ("MB", MIB, 1000_u64.pow(2)),
+            ("G", GIB, GIB),
+            ("GB", GIB, 1000_u64.pow(3)),
+            ("T", TIB, TIB),
+            ("tb", TIB, 1000_u64.pow(4)),
+        ] {
+            for number in [0, 1, 7, 10, 100, 512, 999, 1023, 4096] {
+                let text = format!("{}{}", number, unit);
+                assert_eq!(size(&text), number * multiplier, "{}", text);
+                assert_eq!(si_size(&text), number * si_multiplier, "{}", text);
+                assert_eq!(size(&format!("{} {}", number, unit)), number * multiplier);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_size_invalid() {
+        for input in [
+            "", " ", "K", "MB", "10x", "1.2.3", "1..5G", "-5M", "+5M", "10 mbit", "5M/s", "10MB/s",
+            "1e3", "ten", "5 M B", "5KK",
+        ] {
+            assert!(
+                matches!(parse_size(input), Err(RytError::InvalidInput(_))),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_size_error_messages() {
+        let message = parse_size("10x").unwrap_err().to_string();
+        assert!(message.contains("'10x'"));
+        assert!(message.contains("unknown unit 'x'"));
+        assert!(parse_size("1..5G")
+            .unwrap_err()
+            .to_string()
+            .contains("'1..5' is not a number"));
+        assert!(parse_size("M")
+            .unwrap_err()
+            .to_string()
+            .contains("expected a number"));
+        assert!(parse_size("99999999999T")
+            .unwrap_err()
+            .to_string()
+            .contains("too large"));
+    }
+
+    #[test]
+    fn test_parse_rate_suffix() {
+        assert_eq!(rate("5M/s"), 5 * MIB);
+        assert_eq!(rate("5m/S"), 5 * MIB);
+        assert_eq!(rate("500KB/s"), 500 * KIB);
+        assert_eq!(rate("2MiB/s"), 2 * MIB);
+        assert_eq!(rate(" 1.5 M/s "), 3 * MIB / 2);
+    }
+
+    #[test]
+    fn test_parse_rate_without_suffix() {
+        assert_eq!(rate("5M"), 5 * MIB);
+        assert_eq!(rate("1024"), 1024);
+        assert_eq!(rate("100B/s"), 100);
+    }
+
+    #[test]
+    fn test_parse_rate_invalid() {
+        for input in ["", "/s", "0", "0/s", "5M/m", "5M/s/s", "fast", "-1M/s"] {
+            assert!(
+                matches!(parse_rate(input), Err(RytError::InvalidInput(_))),
+                "{:?} should be rejected",
+                input
+            );
+        }
+        let message = parse_rate("5X/s").unwrap_err().to_string();
+        assert!(message.contains("invalid rate '5X/s'"));
+        assert!(message.contains("unknown unit 'x'"));
+    }
+
+    #[test]
+    fn test_parse_si_rate() {
+        assert_eq!(parse_si_rate("1MB/s").unwrap(), 1_000_000);
+        assert_eq!(parse_si_rate("500KB/s").unwrap(), 500_000);
+        assert_eq!(parse_si_rate("1MiB/s").unwrap(), MIB);
+        assert_eq!(parse_si_rate("5M").unwrap(), 5 * MIB);
+        for input in ["", "0", "0MB/s", "-1MB/s", "fast"] {
+            assert!(
+                matches!(parse_si_rate(input), Err(RytError::InvalidInput(_))),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_plain_seconds() {
+        assert_eq!(secs("30"), 30.0);
+        assert_eq!(secs("0"), 0.0);
+        assert_eq!(secs("1.5"), 1.5);
+        assert_eq!(secs(" 5 "), 5.0);
+    }
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(secs("250ms"), 0.25);
+        assert_eq!(secs("30s"), 30.0);
+        assert_eq!(secs("2min"), 120.0);
+        assert_eq!(secs("5m"), 300.0);
+        assert_eq!(secs("1h"), 3600.0);
+        assert_eq!(secs("1.5h"), 5400.0);
+        assert_eq!(secs("2 hours"), 7200.0);
+        assert_eq!(secs("1d"), 86_400.0);
+        assert_eq!(secs("1w"), 604_800.0);
+        assert_eq!(secs("30S"), 30.0);
+    }
+
+    #[test]
+    fn test_parse_duration_compound() {
+        assert_eq!(secs("1h30m"), 5400.0);
+        assert_eq!(secs("1h 30m 15s"), 5415.0);
+        assert_eq!(secs("2m30s"), 150.0);
+        assert_eq!(secs("1d12h"), 129_600.0);
+        assert_eq!(secs("1s500ms"), 1.5);
+    }
+
+    #[test]
+    fn test_parse_duration_round_trip() {
+        for hours in [0, 1, 2, 23, 100] {
+            for minutes in [0, 1, 30, 59] {
+                for seconds in [0, 1, 45, 59] {
+                    let expected = (hours * 3600 + minutes * 60 + seconds) as f64;
+                    let text = format!("{}h{}m{}s", hours, minutes, seconds);
+                    assert_eq!(secs(&text), expected, "{}", text);
+                    let text = format!("{} hours {} min {} sec", hours, minutes, seconds);
+                    assert_eq!(secs(&text), expected, "{}", text);
+                }
+            }
+        }
+        for seconds in [0, 1, 59, 60, 3600, 86_400] {
+            assert_eq!(secs(&seconds.to_string()), seconds as f64);
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        for input in [
+            "", " ", "s", "-5s", "+5s", "5x", "1h30", "30 5m", "1..5s", "1.2.3h", "5s-", "five",
+            "1e3s", "10MB/s", "5m/s", "h5",
+        ] {
+            assert!(
+                matches!(parse_duration(input), Err(RytError::InvalidInput(_))),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_duration_spec() {
+        assert_eq!(format_duration_spec(Duration::ZERO), "0s");
+        assert_eq!(format_duration_spec(Duration::from_millis(250)), "250ms");
+        assert_eq!(format_duration_spec(Duration::from_secs(90)), "1m30s");
+        assert_eq!(format_duration_spec(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format_duration_spec(Duration::from_secs(86_400 * 2)), "48h");
+        for duration in ["1h30m", "250ms", "1m30s500ms", "48h"] {
+            assert_eq!(
+                format_duration_spec(parse_duration(duration).unwrap()),
+                duration
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_length_seconds() {
+        assert_eq!(parse_length_seconds("212"), 212);
+        assert_eq!(parse_length_seconds(" 212 "), 212);
+        assert_eq!(parse_length_seconds("212.6"), 213);
+        assert_eq!(parse_length_seconds("3:32"), 212);
+        assert_eq!(parse_length_seconds("1:02:03"), 3723);
+        assert_eq!(parse_length_seconds("0"), 0);
+        assert_eq!(parse_length_seconds(""), 0);
+        assert_eq!(parse_length_seconds("-5"), 0);
+        assert_eq!(parse_length_seconds("live"), 0);
+        assert_eq!(parse_length_seconds("3::32"), 0);
+    }
+
+    #[test]
+    fn test_duration_serde() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Timeouts {
+            #[serde(with = "duration_serde")]
+            timeout: Duration,
+            #[serde(with = "duration_serde::option", default)]
+            idle: Option<Duration>,
+        }
+
+        let timeouts = Timeouts {
+            timeout: Duration::from_secs(90),
+            idle: Some(Duration::from_millis(500)),
+        };
+        let json = serde_json::to_string(&timeouts).unwrap();
+        assert_eq!(json, r#"{"timeout":"1m30s","idle":"500ms"}"#);
+        assert_eq!(serde_json::from_str::<Timeouts>(&json).unwrap(), timeouts);
+
+        // Plain numbers are seconds, and optional durations may be left out
+        let parsed: Timeouts = serde_json::from_str(r#"{"timeout":45}"#).unwrap();
+        assert_eq!(parsed.timeout, Duration::from_secs(45));
+        assert_eq!(parsed.idle, None);
+        let parsed: Timeouts = serde_json::from_str(r#"{"timeout":1.5,"idle":null}"#).unwrap();
+        assert_eq!(parsed.timeout, Duration::from_millis(1500));
+
+        let error = serde_json::from_str::<Timeouts>(r#"{"timeout":"5 parsecs"}"#).unwrap_err();
+        assert!(
+            error.to_string().contains("unknown unit 'parsecs'"),
+            "{}",
+            error
+        );
+        assert!(serde_json::from_str::<Timeouts>(r#"{"timeout":-1}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_error_messages() {
+        let message = parse_duration("1h30x").unwrap_err().to_string();
+        assert!(message.contains("invalid duration '1h30x'"));
+        assert!(message.contains("unknown unit 'x'"));
+        assert!(parse_duration("1h30")
+            .unwrap_err()
+            .to_string()
+            .contains("missing unit after '30'"));
+        assert!(parse_duration("-5s")
+            .unwrap_err()
+            .to_string()
+            .contains("expected a number at '-5s'"));
+        assert!(parse_duration("1..5s")
+            .unwrap_err()
+            .to_string()
+            .contains("'1..5' is not a number"));
+        assert!(parse_duration("99999999999999999999w")
+            .unwrap_err()
+            .to_string()
+            .contains("too large"));
+    }
+}
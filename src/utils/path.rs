@@ -0,0 +1,171 @@
+//! Tilde and environment variable expansion for user-supplied paths
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// Expand a leading `~` or `~user` and `$VAR`/`${VAR}` references in a path.
+///
+/// Unset variables, unknown users and malformed references are left as
+/// written, so a typo shows up in the resulting path instead of silently
+/// vanishing. Paths that aren't valid UTF-8 are returned unchanged.
+pub fn expand_path(path: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(path) => PathBuf::from(expand_vars(&expand_tilde(path))),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Replace a leading `~` with the current user's home directory, or `~user`
+/// with that user's home directory
+pub fn expand_tilde(path: &str) -> Cow<'_, str> {
+    let Some(rest) = path.strip_prefix('~') else {
+        return Cow::Borrowed(path);
+    };
+    let (user, tail) = match rest.find(['/', std::path::MAIN_SEPARATOR]) {
+        Some(end) => rest.split_at(end),
+        None => (rest, ""),
+    };
+    let home = if user.is_empty() {
+        home_dir()
+    } else {
+        user_home_dir(user)
+    };
+    match home {
+        Some(home) => Cow::Owned(format!("{}{}", home.display(), tail)),
+        None => Cow::Borrowed(path),
+    }
+}
+
+/// Replace `$VAR` and `${VAR}` with the values of environment variables
+pub fn expand_vars(value: &str) -> Cow<'_, str> {
+    if !value.contains('$') {
+        return Cow::Borrowed(value);
+    }
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, reference_len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        let reference = &rest[start..start + 1 + reference_len];
+        // An empty name would make `env::var` panic
+        let value = (!name.is_empty())
+            .then(|| std::env::var(name).ok())
+            .flatten();
+        expanded.push_str(value.as_deref().unwrap_or(reference));
+        rest = &rest[start + 1 + reference_len..];
+    }
+    expanded.push_str(rest);
+    Cow::Owned(expanded)
+}
+
+/// Get the current user's home directory
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Get a user's home directory from the password database
+#[cfg(unix)]
+fn user_home_dir(user: &str) -> Option<PathBuf> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd_home(&passwd, user)
+}
+
+/// Get a user's home directory; only supported on Unix
+#[cfg(not(unix))]
+fn user_home_dir(_user: &str) -> Option<PathBuf> {
+    None
+}
+
+/// Find a user's home directory in `/etc/passwd` content
+#[cfg(unix)]
+fn passwd_home(passwd: &str, user: &str) -> Option<PathBuf> {
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() >= 6 && fields[0] == user).then(|| PathBuf::from(fields[5]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = home_dir().expect("HOME is set in tests");
+        let home = home.display().to_string();
+        assert_eq!(expand_tilde("~"), home);
+        assert_eq!(expand_tilde("~/Downloads"), format!("{}/Downloads", home));
+        assert_eq!(expand_tilde("downloads/~"), "downloads/~");
+        assert_eq!(expand_tilde("/tmp/~/x"), "/tmp/~/x");
+        assert_eq!(
+            expand_tilde("~no_such_user_ryt/vids"),
+            "~no_such_user_ryt/vids"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_passwd_home() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n\
+                      alice:x:1000:1000:Alice,,,:/home/alice:/bin/zsh\n\
+                      broken-line\n";
+        assert_eq!(
+            passwd_home(passwd, "alice"),
+            Some(PathBuf::from("/home/alice"))
+        );
+        assert_eq!(passwd_home(passwd, "root"), Some(PathBuf::from("/root")));
+        assert_eq!(passwd_home(passwd, "bob"), None);
+        assert_eq!(passwd_home(passwd, "broken-line"), None);
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        std::env::set_var("RYT_TEST_EXPAND_DIR", "/srv/media");
+        std::env::remove_var("RYT_TEST_EXPAND_UNSET");
+
+        assert_eq!(expand_vars("$RYT_TEST_EXPAND_DIR/vids"), "/srv/media/vids");
+        assert_eq!(
+            expand_vars("${RYT_TEST_EXPAND_DIR}_old/vids"),
+            "/srv/media_old/vids"
+        );
+        assert_eq!(expand_vars("no variables"), "no variables");
+
+        // Unset variables and malformed references stay as written
+        assert_eq!(
+            expand_vars("$RYT_TEST_EXPAND_UNSET/vids"),
+            "$RYT_TEST_EXPAND_UNSET/vids"
+        );
+        assert_eq!(
+            expand_vars("${RYT_TEST_EXPAND_UNSET}/vids"),
+            "${RYT_TEST_EXPAND_UNSET}/vids"
+        );
+        assert_eq!(expand_vars("cost$/${}/${open"), "cost$/${}/${open");
+        assert_eq!(expand_vars("trailing$"), "trailing$");
+    }
+
+    #[test]
+    fn test_expand_path() {
+        std::env::set_var("RYT_TEST_EXPAND_SUB", "music");
+        let home = home_dir().expect("HOME is set in tests");
+        assert_eq!(
+            expand_path(Path::new("~/$RYT_TEST_EXPAND_SUB/%(title)s.%(ext)s")),
+            home.join("music/%(title)s.%(ext)s")
+        );
+        assert_eq!(expand_path(Path::new("-")), PathBuf::from("-"));
+    }
+}
@@ -0,0 +1,224 @@
+//! WebVTT caption parsing
+
+/// Word of a caption track with the time it is shown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedWord {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Parse WebVTT captions into timed words.
+///
+/// Automatic captions time each word with inline `<00:00:01.500>` tags; such
+/// a word lasts until the next one starts or its cue ends. Words of cues
+/// without inline timing share the cue's time evenly. Lines repeated from
+/// the previous cue, as in YouTube's rolling captions, are skipped.
+pub fn parse_vtt_to_words(vtt: &str) -> Vec<TimedWord> {
+    let vtt = vtt.replace("\r\n", "\n");
+    let mut words = Vec::new();
+    let mut previous_lines: Vec<String> = Vec::new();
+
+    for block in vtt.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        // Header, NOTE and STYLE blocks have no timing line
+        let Some((cue_start, cue_end)) = lines.next().and_then(parse_cue_timing) else {
+            continue;
+        };
+
+        let mut cue_lines = Vec::new();
+        for line in lines {
+            let plain = strip_tags(line);
+            if !plain.is_empty() && !previous_lines.contains(&plain) {
+                words.extend(line_words(line, cue_start, cue_end));
+            }
+            cue_lines.push(plain);
+        }
+        previous_lines = cue_lines;
+    }
+    words
+}
+
+/// Split a cue line into words, timed by its inline timestamps
+fn line_words(line: &str, cue_start: u64, cue_end: u64) -> Vec<TimedWord> {
+    // Text runs with the time they start
+    let mut segments = vec![(cue_start, String::new())];
+    let mut rest = line;
+    while let Some(open) = rest.find('<') {
+        segments.last_mut().unwrap().1.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        if let Some(time) = parse_vtt_timestamp(tag) {
+            segments.push((time.clamp(cue_start, cue_end), String::new()));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    segments.last_mut().unwrap().1.push_str(rest);
+
+    let mut words = Vec::new();
+    for (i, (start, text)) in segments.iter().enumerate() {
+        // Inline timestamps may go backwards; such words get no duration
+        let end = segments
+            .get(i + 1)
+            .map_or(cue_end, |(next, _)| *next)
+            .max(*start);
+        let texts: Vec<String> = text.split_whitespace().map(decode_entities).collect();
+        let count = texts.len() as u64;
+        for (j, text) in texts.into_iter().enumerate() {
+            let j = j as u64;
+            words.push(TimedWord {
+                start_ms: start + (end - start) * j / count,
+                end_ms: start + (end - start) * (j + 1) / count,
+                text,
+            });
+        }
+    }
+    words
+}
+
+/// Parse a `start --> end [settings]` cue timing line
+fn parse_cue_timing(line: &str) -> Option<(u64, u64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let start = parse_vtt_timestamp(start.trim())?;
+    let end = parse_vtt_timestamp(rest.split_whitespace().next()?)?;
+    Some((start, end.max(start)))
+}
+
+/// Parse a `HH:MM:SS.mmm` or `MM:SS.mmm` timestamp into milliseconds
+fn parse_vtt_timestamp(timestamp: &str) -> Option<u64> {
+    let (clock, millis) = timestamp.split_once('.')?;
+    if millis.len() != 3 {
+        return None;
+    }
+    let millis: u64 = millis.parse().ok()?;
+    let parts: Vec<u64> = clock
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let seconds = match parts[..] {
+        [hours, minutes, seconds] => hours * 3600 + minutes * 60 + seconds,
+        [minutes, seconds] => minutes * 60 + seconds,
+        _ => return None,
+    };
+    Some(seconds * 1000 + millis)
+}
+
+/// Remove `<...>` tags and surrounding whitespace from a cue line
+fn strip_tags(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.trim().to_string()
+}
+
+/// Decode the character references WebVTT allows in cue text
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(start_ms: u64, end_ms: u64, text: &str) -> TimedWord {
+        TimedWord {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_vtt_timestamp() {
+        assert_eq!(parse_vtt_timestamp("00:00:01.500"), Some(1500));
+        assert_eq!(parse_vtt_timestamp("01:02:03.004"), Some(3_723_004));
+        assert_eq!(parse_vtt_timestamp("02:03.004"), Some(123_004));
+        assert_eq!(parse_vtt_timestamp("00:00:01"), None);
+        assert_eq!(parse_vtt_timestamp("00:00:01.5"), None);
+        assert_eq!(parse_vtt_timestamp("c"), None);
+        assert_eq!(parse_vtt_timestamp("/c"), None);
+    }
+
+    #[test]
+    fn test_parse_vtt_plain_cues() {
+        let vtt = "WEBVTT\n\
+                   Kind: captions\n\
+                   Language: en\n\
+                   \n\
+                   NOTE made by hand\n\
+                   \n\
+                   1\n\
+                   00:00:01.000 --> 00:00:03.000 align:start position:0%\n\
+                   Hello big &amp; bright\n\
+                   \n\
+                   00:00:04.000 --> 00:00:05.000\n\
+                   <i>world</i>\n";
+        assert_eq!(
+            parse_vtt_to_words(vtt),
+            vec![
+                word(1000, 1500, "Hello"),
+                word(1500, 2000, "big"),
+                word(2000, 2500, "&"),
+                word(2500, 3000, "bright"),
+                word(4000, 5000, "world"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_vtt_word_timestamps() {
+        // Rolling automatic captions repeat the previous line in each cue
+        let vtt = "WEBVTT\r\n\r\n\
+                   00:00:00.160 --> 00:00:02.000 align:start position:0%\r\n\
+                   \r\n\
+                   00:00:00.160 --> 00:00:02.000 align:start position:0%\r\n\
+                   we're<00:00:00.480><c> no</c><00:00:00.800><c> strangers</c>\r\n\
+                   \r\n\
+                   00:00:02.000 --> 00:00:03.500 align:start position:0%\r\n\
+                   we're no strangers\r\n\
+                   to<00:00:02.400><c> love</c>\r\n";
+        assert_eq!(
+            parse_vtt_to_words(vtt),
+            vec![
+                word(160, 480, "we're"),
+                word(480, 800, "no"),
+                word(800, 2000, "strangers"),
+                word(2000, 2400, "to"),
+                word(2400, 3500, "love"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_vtt_backwards_word_timestamps() {
+        let vtt = "WEBVTT\n\n\
+                   00:00:00.000 --> 00:00:03.000\n\
+                   <00:00:02.000>a <00:00:01.000>b\n";
+        let words = parse_vtt_to_words(vtt);
+        let timing: Vec<_> = words
+            .iter()
+            .map(|word| (word.text.as_str(), word.start_ms, word.end_ms))
+            .collect();
+        assert_eq!(timing, vec![("a", 2000, 2000), ("b", 1000, 3000)]);
+    }
+
+    #[test]
+    fn test_parse_vtt_without_cues() {
+        assert!(parse_vtt_to_words("").is_empty());
+        assert!(parse_vtt_to_words("WEBVTT\n\n").is_empty());
+        assert!(parse_vtt_to_words("not a caption file").is_empty());
+    }
+}
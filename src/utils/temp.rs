@@ -0,0 +1,355 @@
+//! Temporary (partial) file handling
+
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+/// Prefix for temp files created by ryt in a temp directory
+const TEMP_FILE_PREFIX: &str = "ryt-";
+
+/// Extension for partial download files in a temp directory
+const PARTIAL_EXTENSION: &str = "part";
+
+/// OS error code for cross-filesystem renames (EXDEV / ERROR_NOT_SAME_DEVICE)
+const CROSS_DEVICE_ERROR: i32 = if cfg!(windows) { 17 } else { 18 };
+
+/// Query parameters identifying a media stream across URL refreshes
+const STABLE_QUERY_PARAMS: &[&str] = &["id", "itag", "clen"];
+
+/// Get a stable key for a partial download of `url` into `output_path`.
+///
+/// Signed media URLs change on every resolve, so only the path and the
+/// stream-identifying query parameters of the URL take part in the key.
+pub fn partial_file_key(output_path: &Path, url: &str) -> String {
+    let identity = match url::Url::parse(url) {
+        Ok(parsed) => {
+            let mut identity = parsed.path().to_string();
+            for param in STABLE_QUERY_PARAMS {
+                if let Some((_, value)) = parsed.query_pairs().find(|(k, _)| k == param) {
+                    identity.push_str(&format!("&{}={}", param, value));
+                }
+            }
+            identity
+        }
+        Err(_) => url.to_string(),
+    };
+
+    let hash = fnv1a_64(
+        output_path
+            .to_string_lossy()
+            .as_bytes()
+            .iter()
+            .chain([0u8].iter())
+            .chain(identity.as_bytes()),
+    );
+    format!("{:016x}", hash)
+}
+
+/// Get the path of the partial file for a download.
+///
+/// Without a temp directory the partial file lives next to the output file.
+pub fn partial_file_path(temp_dir: Option<&Path>, output_path: &Path, url: &str) -> PathBuf {
+    match temp_dir {
+        Some(dir) => dir.join(format!(
+            "{}{}.{}",
+            TEMP_FILE_PREFIX,
+            partial_file_key(output_path, url),
+            PARTIAL_EXTENSION
+        )),
+        None => output_path.with_extension("tmp"),
+    }
+}
+
+/// Get the path a failed download's partial file is kept at for inspection.
+///
+/// Partial files that already end in `.part` keep their path.
+pub fn kept_partial_path(tmp_path: &Path) -> PathBuf {
+    if tmp_path
+        .extension()
+        .is_some_and(|ext| ext == PARTIAL_EXTENSION)
+    {
+        return tmp_path.to_path_buf();
+    }
+    let mut path = tmp_path.as_os_str().to_owned();
+    path.push(".");
+    path.push(PARTIAL_EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Write a file atomically via a temporary file next to it and a rename
+pub async fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    tokio::fs::write(&tmp_path, contents).await?;
+    if let Err(e) = tokio::fs::rename(&tmp_path, path).await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Move a file, falling back to copy when source and target are on different filesystems
+pub async fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    move_file_with(from, to, |from, to| async move {
+        tokio::fs::rename(from, to).await
+    })
+    .await
+}
+
+/// Move a file using the given rename function.
+///
+/// When `rename` fails with a cross-device error the file is copied next
+/// to the target, synced, renamed into place and the source removed.
+pub async fn move_file_with<F, Fut>(from: &Path, to: &Path, rename: F) -> io::Result<()>
+where
+    F: FnOnce(PathBuf, PathBuf) -> Fut,
+    Fut: Future<Output = io::Result<()>>,
+{
+    match rename(from.to_path_buf(), to.to_path_buf()).await {
+        Ok(()) => Ok(()),
+        Err(e) if is_cross_device_error(&e) => {
+            let staging = to.with_extension(format!("{}.moving", PARTIAL_EXTENSION));
+            copy_and_sync(from, &staging).await?;
+            if let Err(e) = tokio::fs::rename(&staging, to).await {
+                let _ = tokio::fs::remove_file(&staging).await;
+                return Err(e);
+            }
+            tokio::fs::remove_file(from).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Remove ryt temp files in `dir` older than `max_age`.
+///
+/// Other ryt processes may share the directory, so files that vanish during
+/// the scan are skipped, and a file that can't be removed is logged without
+/// stopping the scan. Returns the number of files removed.
+pub async fn cleanup_temp_dir(dir: &Path, max_age: Duration) -> io::Result<usize> {
+    let mut removed = 0;
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let now = SystemTime::now();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        if !name.to_string_lossy().starts_with(TEMP_FILE_PREFIX) {
+            continue;
+        }
+
+        let path = entry.path();
+        match remove_if_stale(&path, now, max_age).await {
+            Ok(true) => removed += 1,
+            Ok(false) => {}
+            Err(e) => warn!("Failed to clean up temp file {}: {}", path.display(), e),
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Remove the temp file at `path` if it is older than `max_age` at `now`.
+///
+/// Returns whether it was removed; a file that is already gone counts as
+/// not removed rather than as an error.
+async fn remove_if_stale(path: &Path, now: SystemTime, max_age: Duration) -> io::Result<bool> {
+    let skip_missing = |e: io::Error| match e.kind() {
+        io::ErrorKind::NotFound => Ok(false),
+        _ => Err(e),
+    };
+
+    let metadata = match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(e) => return skip_missing(e),
+    };
+    if !metadata.is_file() {
+        return Ok(false);
+    }
+
+    let age = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .unwrap_or_default();
+    if age < max_age {
+        return Ok(false);
+    }
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => Ok(true),
+        Err(e) => skip_missing(e),
+    }
+}
+
+/// Copy a file and flush it to disk
+async fn copy_and_sync(from: &Path, to: &Path) -> io::Result<()> {
+    tokio::fs::copy(from, to).await?;
+    let file = tokio::fs::OpenOptions::new().write(true).open(to).await?;
+    file.sync_all().await
+}
+
+/// Check if an error is a cross-filesystem rename failure
+fn is_cross_device_error(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(CROSS_DEVICE_ERROR)
+}
+
+/// 64-bit FNV-1a hash (stable across runs and toolchains)
+fn fnv1a_64<'a>(bytes: impl Iterator<Item = &'a u8>) -> u64 {
+    bytes.fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cross_device_error() -> io::Error {
+        io::Error::from_raw_os_error(CROSS_DEVICE_ERROR)
+    }
+
+    #[test]
+    fn test_partial_file_key_ignores_volatile_params() {
+        let output = Path::new("/videos/Title.mp4");
+        let a = partial_file_key(
+            output,
+            "https://rr1.googlevideo.com/videoplayback?id=abc&itag=18&expire=1&sig=x",
+        );
+        let b = partial_file_key(
+            output,
+            "https://rr5.googlevideo.com/videoplayback?expire=2&itag=18&id=abc&sig=y",
+        );
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 16);
+
+        let other_itag = partial_file_key(
+            output,
+            "https://rr1.googlevideo.com/videoplayback?id=abc&itag=22",
+        );
+        assert_ne!(a, other_itag);
+
+        let other_output = partial_file_key(
+            Path::new("/videos/Other.mp4"),
+            "https://rr1.googlevideo.com/videoplayback?id=abc&itag=18",
+        );
+        assert_ne!(a, other_output);
+    }
+
+    #[test]
+    fn test_partial_file_path() {
+        let output = Path::new("/videos/Title.mp4");
+        let url = "https://rr1.googlevideo.com/videoplayback?id=abc&itag=18";
+
+        assert_eq!(
+            partial_file_path(None, output, url),
+            PathBuf::from("/videos/Title.tmp")
+        );
+
+        let path = partial_file_path(Some(Path::new("/fast")), output, url);
+        assert_eq!(path.parent(), Some(Path::new("/fast")));
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with("ryt-"));
+        assert!(name.ends_with(".part"));
+        assert_eq!(
+            path,
+            partial_file_path(Some(Path::new("/fast")), output, url)
+        );
+    }
+
+    #[test]
+    fn test_kept_partial_path() {
+        assert_eq!(
+            kept_partial_path(Path::new("/videos/Title.tmp")),
+            PathBuf::from("/videos/Title.tmp.part")
+        );
+        assert_eq!(
+            kept_partial_path(Path::new("/fast/ryt-0123.part")),
+            PathBuf::from("/fast/ryt-0123.part")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_file_same_filesystem() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("ryt-a.part");
+        let to = dir.path().join("video.mp4");
+        std::fs::write(&from, b"data").unwrap();
+
+        move_file(&from, &to).await.unwrap();
+        assert!(!from.exists());
+        assert_eq!(std::fs::read(&to).unwrap(), b"data");
+    }
+
+    #[tokio::test]
+    async fn test_move_file_cross_device_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("ryt-a.part");
+        let to = dir.path().join("video.mp4");
+        std::fs::write(&from, b"data").unwrap();
+
+        move_file_with(&from, &to, |_, _| async { Err(cross_device_error()) })
+            .await
+            .unwrap();
+        assert!(!from.exists());
+        assert_eq!(std::fs::read(&to).unwrap(), b"data");
+        assert!(!to.with_extension("part.moving").exists());
+    }
+
+    #[tokio::test]
+    async fn test_move_file_other_error_not_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("ryt-a.part");
+        let to = dir.path().join("video.mp4");
+        std::fs::write(&from, b"data").unwrap();
+
+        let result = move_file_with(&from, &to, |_, _| async {
+            Err(io::Error::new(io::ErrorKind::PermissionDenied, "denied"))
+        })
+        .await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert!(from.exists());
+        assert!(!to.exists());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_temp_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ryt-a.part"), b"a").unwrap();
+        std::fs::write(dir.path().join("ryt-b.part"), b"b").unwrap();
+        std::fs::write(dir.path().join("unrelated.txt"), b"c").unwrap();
+
+        // Fresh files are kept
+        let removed = cleanup_temp_dir(dir.path(), Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(removed, 0);
+
+        let removed = cleanup_temp_dir(dir.path(), Duration::ZERO).await.unwrap();
+        assert_eq!(removed, 2);
+        assert!(dir.path().join("unrelated.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_skips_files_removed_during_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let gone = dir.path().join("ryt-gone.part");
+        let stale = dir.path().join("ryt-stale.part");
+        std::fs::write(&gone, b"a").unwrap();
+        std::fs::write(&stale, b"b").unwrap();
+
+        // Another process removes a listed file before this one gets to it
+        let now = SystemTime::now() + Duration::from_secs(60);
+        std::fs::remove_file(&gone).unwrap();
+        assert!(!remove_if_stale(&gone, now, Duration::ZERO).await.unwrap());
+        assert!(remove_if_stale(&stale, now, Duration::ZERO).await.unwrap());
+        assert!(!stale.exists());
+
+        // Directories with the prefix are left alone
+        std::fs::create_dir(dir.path().join("ryt-dir")).unwrap();
+        std::fs::write(dir.path().join("ryt-c.part"), b"c").unwrap();
+        let removed = cleanup_temp_dir(dir.path(), Duration::ZERO).await.unwrap();
+        assert_eq!(removed, 1);
+        assert!(dir.path().join("ryt-dir").exists());
+    }
+}
@@ -0,0 +1,146 @@
+//! Thumbnail image conversion
+//!
+//! YouTube serves many thumbnails as WebP, which plenty of players and
+//! taggers don't read. Thumbnails are converted in process with the `image`
+//! crate, so no ffmpeg is needed.
+
+use crate::error::RytError;
+use clap::ValueEnum;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Target format of thumbnail conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThumbnailFormat {
+    /// JPEG
+    #[value(alias = "jpeg")]
+    Jpg,
+    /// PNG
+    Png,
+}
+
+impl ThumbnailFormat {
+    /// Parse thumbnail format from string
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "jpg" | "jpeg" => Ok(ThumbnailFormat::Jpg),
+            "png" => Ok(ThumbnailFormat::Png),
+            other => Err(format!("Unsupported thumbnail format: {}", other)),
+        }
+    }
+
+    /// Get file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpg => "jpg",
+            ThumbnailFormat::Png => "png",
+        }
+    }
+
+    fn image_format(&self) -> ImageFormat {
+        match self {
+            ThumbnailFormat::Jpg => ImageFormat::Jpeg,
+            ThumbnailFormat::Png => ImageFormat::Png,
+        }
+    }
+}
+
+/// Convert a thumbnail image into `format`.
+///
+/// The converted image is written next to the original with the new
+/// extension and the original is removed. Animated WebP images are reduced
+/// to their first frame. Returns the path of the converted image.
+pub fn convert_thumbnail(path: &Path, format: ThumbnailFormat) -> Result<PathBuf, RytError> {
+    let target = path.with_extension(format.extension());
+    if target == path {
+        return Ok(target);
+    }
+
+    // Decoding an animated image yields its first frame
+    let image = image::open(path)?;
+    let image = match format {
+        // JPEG has no alpha channel
+        ThumbnailFormat::Jpg => DynamicImage::ImageRgb8(image.to_rgb8()),
+        ThumbnailFormat::Png => image,
+    };
+    image.save_with_format(&target, format.image_format())?;
+    std::fs::remove_file(path)?;
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEBP: &[u8] = include_bytes!("testdata/thumbnail.webp");
+    const ANIMATED_WEBP: &[u8] = include_bytes!("testdata/thumbnail_animated.webp");
+
+    #[test]
+    fn test_thumbnail_format_from_str() {
+        assert_eq!(
+            ThumbnailFormat::from_str("jpg").unwrap(),
+            ThumbnailFormat::Jpg
+        );
+        assert_eq!(
+            ThumbnailFormat::from_str("JPEG").unwrap(),
+            ThumbnailFormat::Jpg
+        );
+        assert_eq!(
+            ThumbnailFormat::from_str("png").unwrap(),
+            ThumbnailFormat::Png
+        );
+        assert!(ThumbnailFormat::from_str("gif").is_err());
+        assert_eq!(ThumbnailFormat::Jpg.extension(), "jpg");
+        assert_eq!(ThumbnailFormat::Png.extension(), "png");
+    }
+
+    #[test]
+    fn test_convert_webp_to_jpg() {
+        let dir = tempfile::tempdir().unwrap();
+        let webp = dir.path().join("Title.webp");
+        std::fs::write(&webp, WEBP).unwrap();
+
+        let jpg = convert_thumbnail(&webp, ThumbnailFormat::Jpg).unwrap();
+        assert_eq!(jpg, dir.path().join("Title.jpg"));
+        assert!(!webp.exists());
+
+        let data = std::fs::read(&jpg).unwrap();
+        assert_eq!(image::guess_format(&data).unwrap(), ImageFormat::Jpeg);
+        let decoded = image::load_from_memory(&data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (1, 1));
+    }
+
+    #[test]
+    fn test_convert_animated_webp_to_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let webp = dir.path().join("Title.webp");
+        std::fs::write(&webp, ANIMATED_WEBP).unwrap();
+
+        let png = convert_thumbnail(&webp, ThumbnailFormat::Png).unwrap();
+        assert_eq!(png, dir.path().join("Title.png"));
+
+        let data = std::fs::read(&png).unwrap();
+        assert_eq!(image::guess_format(&data).unwrap(), ImageFormat::Png);
+        let decoded = image::load_from_memory(&data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (1, 1));
+    }
+
+    #[test]
+    fn test_convert_thumbnail_same_format_or_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let jpg = dir.path().join("Title.jpg");
+        std::fs::write(&jpg, b"not decoded").unwrap();
+        assert_eq!(convert_thumbnail(&jpg, ThumbnailFormat::Jpg).unwrap(), jpg);
+
+        let invalid = dir.path().join("Broken.webp");
+        std::fs::write(&invalid, b"not an image").unwrap();
+        assert!(matches!(
+            convert_thumbnail(&invalid, ThumbnailFormat::Jpg),
+            Err(RytError::ImageError(_))
+        ));
+        assert!(invalid.exists());
+    }
+}
@@ -1,10 +1,52 @@
 //! URL utilities for extracting video IDs and parsing video platform URLs
 
 use crate::error::RytError;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
 use url::Url;
 
-/// Extract video ID from various video platform URL formats
+/// Media URL query parameters that sign the URL or identify the viewer
+const SENSITIVE_MEDIA_PARAMS: &[&str] = &["sig", "signature", "lsig", "n", "ip", "id", "expire"];
+
+/// Placeholder for redacted parameter values
+const REDACTED: &str = "…";
+
+/// Number of front video IPs (`fvip`) media hosts rotate through
+const FVIP_COUNT: u8 = 5;
+
+/// Whether [`loggable_url`] redacts media URLs
+static REDACT_URLS: AtomicBool = AtomicBool::new(true);
+
+/// Get the canonical watch URL of a video, so every form of a video
+/// URL maps to the same string
+pub fn canonical_video_url(video_id: &str) -> String {
+    format!("https://www.youtube.com/watch?v={}", video_id)
+}
+
+/// Check if a string is a bare video ID: 11 characters of `A-Z`, `a-z`,
+/// `0-9`, `-` and `_`
+pub fn is_video_id(value: &str) -> bool {
+    value.len() == 11
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Turn a bare video ID into its watch URL, leaving anything else as is
+pub fn normalize_video_input(input: &str) -> String {
+    if is_video_id(input) {
+        canonical_video_url(input)
+    } else {
+        input.to_string()
+    }
+}
+
+/// Extract video ID from various video platform URL formats, or take a
+/// bare video ID as is
 pub fn extract_video_id(url: &str) -> Result<String, RytError> {
+    if is_video_id(url) {
+        return Ok(url.to_string());
+    }
     let parsed = Url::parse(url)?;
 
     match parsed.host_str() {
@@ -63,6 +105,34 @@ pub fn extract_playlist_id(url: &str) -> Result<String, RytError> {
     }
 }
 
+/// Extract channel ID (`UC...`) from a channel URL
+///
+/// Handle (`/@name`) and custom URLs need a lookup and are not supported.
+pub fn extract_channel_id(url: &str) -> Result<String, RytError> {
+    // Accept raw channel IDs as-is
+    if url.starts_with("UC") && !url.contains('/') {
+        return Ok(url.to_string());
+    }
+
+    let parsed = Url::parse(url)?;
+    if !matches!(
+        parsed.host_str(),
+        Some("youtube.com") | Some("www.youtube.com") | Some("m.youtube.com")
+    ) {
+        return Err(RytError::InvalidUrl(
+            "Not a supported video platform URL".to_string(),
+        ));
+    }
+    let mut segments = parsed.path_segments().into_iter().flatten();
+    match (segments.next(), segments.next()) {
+        (Some("channel"), Some(id)) if id.starts_with("UC") => Ok(id.to_string()),
+        (Some(segment), _) if segment.starts_with('@') => Err(RytError::InvalidUrl(
+            "Channel handles are not supported, use the /channel/UC... URL".to_string(),
+        )),
+        _ => Err(RytError::InvalidUrl("Channel ID not found".to_string())),
+    }
+}
+
 /// Check if URL is a supported video platform URL
 pub fn is_video_url(url: &str) -> bool {
     if let Ok(parsed) = Url::parse(url) {
@@ -85,10 +155,233 @@ pub fn is_playlist_url(url: &str) -> bool {
     }
 }
 
+/// Replace the values of signing and identifying parameters (`sig`, `n`,
+/// `ip`, `id`, `expire`, ...) of a media URL with `…`, keeping the rest of
+/// the URL, e.g. `itag` and `mime`, as is
+pub fn redact_media_url(url: &str) -> String {
+    let (url, fragment) = match url.split_once('#') {
+        Some((url, fragment)) => (url, Some(fragment)),
+        None => (url, None),
+    };
+    let Some((base, query)) = url.split_once('?') else {
+        return match fragment {
+            Some(fragment) => format!("{}#{}", url, fragment),
+            None => url.to_string(),
+        };
+    };
+
+    let query: Vec<Cow<'_, str>> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if SENSITIVE_MEDIA_PARAMS.contains(&key) => {
+                Cow::Owned(format!("{}={}", key, REDACTED))
+            }
+            _ => Cow::Borrowed(pair),
+        })
+        .collect();
+    let mut redacted = format!("{}?{}", base, query.join("&"));
+    if let Some(fragment) = fragment {
+        redacted.push('#');
+        redacted.push_str(fragment);
+    }
+    redacted
+}
+
+/// Get alternative URLs of a googlevideo media URL on other CDN hosts, in
+/// the order to try them.
+///
+/// The `mn` parameter lists the nodes holding the stream, reachable as
+/// `rr{mvi}---{node}.googlevideo.com`; nodes other than the current host
+/// come first. Last is the URL with the next front video IP (`fvip`,
+/// cycling through 1 to 5). URLs without these parameters have none.
+pub fn media_host_candidates(url: &str) -> Vec<String> {
+    let Ok(parsed) = Url::parse(url) else {
+        return Vec::new();
+    };
+    let param = |name: &str| {
+        parsed
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let is_host_label = |label: &str| {
+        !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+
+    let mut candidates = Vec::new();
+    if let (Some(nodes), Some(mvi)) = (param("mn"), param("mvi")) {
+        if mvi.chars().all(|c| c.is_ascii_digit()) && !mvi.is_empty() {
+            let current = parsed.host_str().unwrap_or_default();
+            for node in nodes.split(',').map(str::trim).filter(|n| is_host_label(n)) {
+                let host = format!("rr{}---{}.googlevideo.com", mvi, node);
+                let mut candidate = parsed.clone();
+                if host != current && candidate.set_host(Some(&host)).is_ok() {
+                    candidates.push(candidate.into());
+                }
+            }
+        }
+    }
+
+    if let Some(fvip) = param("fvip").and_then(|fvip| fvip.parse::<u8>().ok()) {
+        let next = (fvip % FVIP_COUNT + 1).to_string();
+        // Replace the raw pair so the other values keep their encoding
+        let query: Vec<Cow<'_, str>> = parsed
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some(("fvip", _)) => Cow::Owned(format!("fvip={}", next)),
+                _ => Cow::Borrowed(pair),
+            })
+            .collect();
+        let mut candidate = parsed.clone();
+        candidate.set_query(Some(&query.join("&")));
+        candidates.push(candidate.into());
+    }
+    candidates
+}
+
+/// Set whether media URLs are redacted in logs (the default)
+pub fn set_redact_urls(redact: bool) {
+    REDACT_URLS.store(redact, Ordering::Relaxed);
+}
+
+/// Get a media URL as it may be logged: redacted unless turned off with
+/// [`set_redact_urls`]
+pub fn loggable_url(url: &str) -> Cow<'_, str> {
+    if REDACT_URLS.load(Ordering::Relaxed) {
+        Cow::Owned(redact_media_url(url))
+    } else {
+        Cow::Borrowed(url)
+    }
+}
+
+/// Get a signature or n-parameter value as it may be logged: `…` unless
+/// redaction is turned off with [`set_redact_urls`]
+pub fn loggable_secret(value: &str) -> &str {
+    if REDACT_URLS.load(Ordering::Relaxed) {
+        REDACTED
+    } else {
+        value
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_media_host_candidates() {
+        let url = "https://rr3---sn-4g5e6nze.googlevideo.com/videoplayback?expire=1700000000\
+                   &ei=xyz&ip=203.0.113.7&id=o-ABC&itag=18&source=youtube\
+                   &mh=7c&mm=31%2C29&mn=sn-4g5e6nze%2Csn-4g5ednsl%2Csn-hgn7rn7k\
+                   &ms=au%2Crdu&mv=m&mvi=3&pl=24&fvip=5&sparams=expire%2Cei%2Cip&sig=AOq0QJ8w";
+        let candidates = media_host_candidates(url);
+        assert_eq!(candidates.len(), 3);
+
+        // Other nodes of `mn` on the same mvi, with the query untouched
+        let query = url.split_once('?').unwrap().1;
+        assert_eq!(
+            candidates[0],
+            format!(
+                "https://rr3---sn-4g5ednsl.googlevideo.com/videoplayback?{}",
+                query
+            )
+        );
+        assert_eq!(
+            candidates[1],
+            format!(
+                "https://rr3---sn-hgn7rn7k.googlevideo.com/videoplayback?{}",
+                query
+            )
+        );
+
+        // Then the next front video IP, wrapping around after 5
+        assert_eq!(candidates[2], url.replace("&fvip=5&", "&fvip=1&"));
+        assert!(candidates[2].contains("sparams=expire%2Cei%2Cip"));
+    }
+
+    #[test]
+    fn test_media_host_candidates_partial_params() {
+        // Only fvip
+        let candidates = media_host_candidates(
+            "https://rr1---sn-abc.googlevideo.com/videoplayback?fvip=2&itag=18",
+        );
+        assert_eq!(
+            candidates,
+            ["https://rr1---sn-abc.googlevideo.com/videoplayback?fvip=3&itag=18"]
+        );
+
+        // mn without mvi names no host
+        assert!(media_host_candidates(
+            "https://rr1---sn-abc.googlevideo.com/videoplayback?mn=sn-abc%2Csn-def"
+        )
+        .is_empty());
+
+        // Malformed node names are skipped
+        let candidates = media_host_candidates(
+            "https://rr1---sn-abc.googlevideo.com/videoplayback?mvi=1&mn=evil.com%2F%2C%2Csn-def",
+        );
+        assert_eq!(
+            candidates,
+            ["https://rr1---sn-def.googlevideo.com/videoplayback?mvi=1&mn=evil.com%2F%2C%2Csn-def"]
+        );
+
+        assert!(media_host_candidates("https://www.youtube.com/watch?v=dQw4w9WgXcQ").is_empty());
+        assert!(media_host_candidates("not a url").is_empty());
+    }
+
+    #[test]
+    fn test_redact_media_url() {
+        let url = "https://rr3---sn-abc.googlevideo.com/videoplayback?expire=1700000000\
+                   &ei=xyz&ip=203.0.113.7&id=o-ABC&itag=18&source=youtube\
+                   &mime=video%2Fmp4&sparams=expire%2Cei%2Cip&sig=AOq0QJ8w&lsig=AG3C_x\
+                   &n=abcDEF&ratebypass=yes#t=10";
+        let redacted = redact_media_url(url);
+        assert_eq!(
+            redacted,
+            "https://rr3---sn-abc.googlevideo.com/videoplayback?expire=…\
+             &ei=xyz&ip=…&id=…&itag=18&source=youtube\
+             &mime=video%2Fmp4&sparams=expire%2Cei%2Cip&sig=…&lsig=…\
+             &n=…&ratebypass=yes#t=10"
+        );
+        for secret in [
+            "1700000000",
+            "203.0.113.7",
+            "o-ABC",
+            "AOq0QJ8w",
+            "AG3C_x",
+            "abcDEF",
+        ] {
+            assert!(!redacted.contains(secret), "{} leaked", secret);
+        }
+
+        let url = "https://example.googlevideo.com/videoplayback?itag=140&signature=abc";
+        assert_eq!(
+            redact_media_url(url),
+            "https://example.googlevideo.com/videoplayback?itag=140&signature=…"
+        );
+        // Parameter names only match whole keys, and URLs without a query pass through
+        assert_eq!(
+            redact_media_url("https://example.com/v?nsig=1&video_id=2&n"),
+            "https://example.com/v?nsig=1&video_id=2&n"
+        );
+        assert_eq!(
+            redact_media_url("https://example.com/v#frag"),
+            "https://example.com/v#frag"
+        );
+    }
+
+    #[test]
+    fn test_loggable_url() {
+        let url = "https://example.googlevideo.com/videoplayback?itag=18&sig=secret";
+        assert_eq!(
+            loggable_url(url),
+            "https://example.googlevideo.com/videoplayback?itag=18&sig=…"
+        );
+        assert_eq!(loggable_secret("AOq0QJ8w"), "…");
+    }
+
     #[test]
     fn test_extract_video_id() {
         assert_eq!(
@@ -106,9 +399,36 @@ mod tests {
             "brZCOVlyPPo"
         );
 
+        assert_eq!(extract_video_id("dQw4w9WgXcQ").unwrap(), "dQw4w9WgXcQ");
+
         // Test error cases
         assert!(extract_video_id("https://www.youtube.com/watch").is_err());
         assert!(extract_video_id("https://example.com").is_err());
+        assert!(extract_video_id("dQw4w9WgXc!").is_err());
+    }
+
+    #[test]
+    fn test_canonical_video_url() {
+        let canonical = canonical_video_url("dQw4w9WgXcQ");
+        assert_eq!(canonical, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        for input in [
+            "dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ?t=10",
+            "https://youtube.com/watch?v=dQw4w9WgXcQ&feature=share",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+        ] {
+            let id = extract_video_id(input).unwrap();
+            assert_eq!(canonical_video_url(&id), canonical, "{}", input);
+        }
+
+        assert!(is_video_id("a-b_C123456"));
+        assert!(!is_video_id("short"));
+        assert!(!is_video_id("PLxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx"));
+        assert_eq!(normalize_video_input("dQw4w9WgXcQ"), canonical);
+        assert_eq!(
+            normalize_video_input("https://youtu.be/dQw4w9WgXcQ"),
+            "https://youtu.be/dQw4w9WgXcQ"
+        );
     }
 
     #[test]
@@ -123,6 +443,26 @@ mod tests {
         assert!(extract_playlist_id("https://www.youtube.com/watch?v=xxx").is_err());
     }
 
+    #[test]
+    fn test_extract_channel_id() {
+        let id = "UCuAXFkgsw1L7xaCfnd5JJOw";
+        assert_eq!(extract_channel_id(id).unwrap(), id);
+        assert_eq!(
+            extract_channel_id(&format!("https://www.youtube.com/channel/{}", id)).unwrap(),
+            id
+        );
+        assert_eq!(
+            extract_channel_id(&format!("https://youtube.com/channel/{}/playlists", id)).unwrap(),
+            id
+        );
+
+        let err = extract_channel_id("https://www.youtube.com/@handle").unwrap_err();
+        assert!(err.to_string().contains("handles are not supported"));
+        assert!(extract_channel_id("https://www.youtube.com/watch?v=xxx").is_err());
+        assert!(extract_channel_id("https://example.com/channel/UCxxx").is_err());
+        assert!(extract_channel_id("").is_err());
+    }
+
     #[test]
     fn test_is_video_url() {
         assert!(is_video_url("https://www.youtube.com/watch?v=xxx"));